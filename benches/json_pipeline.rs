@@ -648,9 +648,8 @@ fn bench_select_patterns(c: &mut Criterion) {
     group.finish();
 }
 
-/// Benchmark array indexing: get() vs get_fast().
-/// This directly measures the optimization where get_fast() uses only BP operations
-/// for navigation and only calls text_position once at the target element.
+/// Benchmark array indexing: `get()` against walking the elements with
+/// `next_sibling` and decoding only the target.
 fn bench_array_indexing(c: &mut Criterion) {
     let Some(bytes) = load_test_file() else {
         return;
@@ -702,20 +701,54 @@ fn bench_array_indexing(c: &mut Criterion) {
             continue;
         }
 
-        // Benchmark get() - O(n) IB selects
+        // get() - BalancedParens::nth_sibling
         group.bench_function(format!("get_{idx}"), |b| {
             b.iter(|| black_box(elements.get(black_box(idx))));
         });
 
-        // Benchmark get_fast() - O(n) BP ops + O(log n) IB select
-        group.bench_function(format!("get_fast_{idx}"), |b| {
-            b.iter(|| black_box(elements.get_fast(black_box(idx))));
+        // One next_sibling per element
+        group.bench_function(format!("walk_{idx}"), |b| {
+            b.iter(|| black_box(walk_to(elements, black_box(idx))));
         });
     }
 
     group.finish();
 }
 
+/// Reach element `idx` with one `next_sibling` per element.
+fn walk_to<W: AsRef<[u64]>>(
+    elements: succinctly::json::light::JsonElements<'_, W>,
+    idx: usize,
+) -> Option<StandardJson<'_, W>> {
+    elements.cursor_iter().nth(idx).map(|cursor| cursor.value())
+}
+
+/// Benchmark `get()` on the last element of `[1, 2, ..., 10000]`, where
+/// every element before it is a scalar.
+fn bench_array_get_last(c: &mut Criterion) {
+    let json = format!(
+        "[{}]",
+        (1..=10_000)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let bytes = json.as_bytes();
+    let index = JsonIndex::build(bytes);
+    let StandardJson::Array(elements) = index.root(bytes).value() else {
+        unreachable!()
+    };
+
+    let mut group = c.benchmark_group("array_get_last_of_10000");
+    group.bench_function("get", |b| {
+        b.iter(|| black_box(elements.get(black_box(9999))));
+    });
+    group.bench_function("walk", |b| {
+        b.iter(|| black_box(walk_to(elements, black_box(9999))));
+    });
+    group.finish();
+}
+
 /// Benchmark jq evaluation with various query patterns.
 /// This tests the actual jq evaluator to measure end-to-end impact.
 fn bench_jq_queries(c: &mut Criterion) {
@@ -790,6 +823,7 @@ criterion_group!(
     bench_v1_vs_v2_full_traverse,
     bench_select_patterns,
     bench_array_indexing,
+    bench_array_get_last,
     bench_jq_queries,
 );
 criterion_main!(benches);
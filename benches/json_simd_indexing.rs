@@ -1,6 +1,6 @@
 //! Criterion benchmarks for JSON SIMD indexing operations.
 //!
-//! Measures performance of different SIMD implementations (AVX2, SSE2, NEON, PFSM)
+//! Measures performance of different SIMD implementations (AVX-512, AVX2, SSE2, NEON, PFSM)
 //! on real JSON files from data/bench/generated/.
 //!
 //! This is the heaviest json_simd benchmark - tests all SIMD variants on files up to 100MB.
@@ -8,6 +8,7 @@
 //! Run with:
 //! ```bash
 //! cargo bench --bench json_simd_indexing
+//! cargo bench --bench json_simd_indexing --features avx512  # include AVX-512BW
 //! ```
 
 mod json_simd_common;
@@ -46,7 +47,17 @@ fn bench_json_indexing(c: &mut Criterion) {
 
         group.throughput(Throughput::Bytes(*file_size));
 
-        // AVX2 (if available) - fastest on x86_64
+        // AVX-512BW (if enabled and available)
+        #[cfg(feature = "avx512")]
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+            group.bench_with_input(BenchmarkId::new("AVX512", name), &bytes, |b, bytes| {
+                b.iter(|| {
+                    succinctly::json::simd::avx512::build_semi_index_standard(black_box(bytes))
+                });
+            });
+        }
+
+        // AVX2 (if available)
         if is_x86_feature_detected!("avx2") {
             group.bench_with_input(BenchmarkId::new("AVX2", name), &bytes, |b, bytes| {
                 b.iter(|| {
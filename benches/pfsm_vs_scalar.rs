@@ -3,6 +3,8 @@
 //! Both use the standard cursor (4-state, marks value starts).
 //! - PFSM: Uses 256-entry lookup tables for state transitions
 //! - Scalar: Byte-by-byte processing with explicit state machine
+//! - PFSM-SIMD (x86_64): The same tables applied 32 bytes at a time with AVX2
+//!   `vpshufb` lookups and a parallel prefix scan over transition functions
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 use std::fs;
 use std::hint::black_box;
@@ -13,6 +15,7 @@ fn benchmark_pfsm_vs_scalar(c: &mut Criterion) {
         ("1kb", "data/bench/generated/comprehensive/1kb.json"),
         ("10kb", "data/bench/generated/comprehensive/10kb.json"),
         ("100kb", "data/bench/generated/comprehensive/100kb.json"),
+        ("100mb", "data/bench/generated/comprehensive/100mb.json"),
     ];
 
     for (name, path) in test_files {
@@ -26,12 +29,19 @@ fn benchmark_pfsm_vs_scalar(c: &mut Criterion) {
 
         let mut group = c.benchmark_group(format!("pfsm_vs_scalar/{name}"));
         group.throughput(Throughput::Bytes(json.len() as u64));
+        group.sample_size(if name == "100mb" { 10 } else { 100 });
 
         // PFSM: table-based state machine
         group.bench_function("PFSM", |b| {
             b.iter(|| standard::build_semi_index(black_box(json)));
         });
 
+        // PFSM-SIMD: shuffle-composed transitions (AVX2 when available)
+        #[cfg(target_arch = "x86_64")]
+        group.bench_function("PFSM-SIMD", |b| {
+            b.iter(|| standard::build_semi_index_pfsm_simd(black_box(json)));
+        });
+
         // Scalar: byte-by-byte fallback
         group.bench_function("Scalar", |b| {
             b.iter(|| standard::build_semi_index_scalar(black_box(json)));
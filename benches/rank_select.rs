@@ -4,6 +4,7 @@ use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use std::hint::black_box;
+use succinctly::bits::SparseBitVec;
 use succinctly::{BitVec, RankSelect};
 
 /// Generate a bitvector with specified size and density.
@@ -96,6 +97,102 @@ fn bench_select(c: &mut Criterion) {
     group.finish();
 }
 
+/// Random rank1 queries on a 64 MB bitvector, far larger than the caches:
+/// the default `RankDirectory` against the `Rank9` layout.
+///
+/// Run alone with `cargo bench --bench rank_select -- rank1_64mb`.
+fn bench_rank_directory_vs_rank9(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rank1_64mb");
+
+    let size = 64 * 1024 * 1024 * 8;
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let words: Vec<u64> = (0..size / 64).map(|_| rng.random::<u64>()).collect();
+    let bv = BitVec::from_words(words, size);
+    let rank9 = bv.build_rank9();
+    let queries = generate_queries(100_000, size, 123);
+
+    group.bench_function("RankDirectory", |b| {
+        b.iter(|| {
+            let mut sum = 0usize;
+            for &q in &queries {
+                sum += bv.rank1(black_box(q));
+            }
+            sum
+        });
+    });
+
+    group.bench_function("Rank9", |b| {
+        b.iter(|| {
+            let mut sum = 0usize;
+            for &q in &queries {
+                sum += rank9.rank1(black_box(q));
+            }
+            sum
+        });
+    });
+
+    group.finish();
+}
+
+/// Rank and select on a 10M-bit vector at 0.1% density: a plain `BitVec`
+/// against the Golomb-Rice coded `SparseBitVec`. The heap sizes of both are
+/// printed once, since the memory saving is the point of the sparse form.
+fn bench_sparse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sparse_0.1pct");
+
+    let size = 10_000_000;
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let mut words = vec![0u64; size / 64];
+    for _ in 0..size / 1000 {
+        let pos = rng.random_range(0..size);
+        words[pos / 64] |= 1 << (pos % 64);
+    }
+    let bv = BitVec::from_words(words, size);
+    let sparse = SparseBitVec::from_bitvec(&bv);
+    println!(
+        "sparse_0.1pct: BitVec {} bytes, SparseBitVec {} bytes",
+        bv.heap_size(),
+        sparse.heap_size()
+    );
+    let queries = generate_queries(10_000, size, 123);
+    let ones = bv.count_ones();
+
+    group.bench_function("BitVec/rank1", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&q| bv.rank1(black_box(q)))
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("SparseBitVec/rank1", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&q| sparse.rank1(black_box(q)))
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("BitVec/select1", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter_map(|&q| bv.select1(black_box(q % ones)))
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("SparseBitVec/select1", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter_map(|&q| sparse.select1(black_box(q % ones)))
+                .sum::<usize>()
+        });
+    });
+
+    group.finish();
+}
+
 fn bench_construction(c: &mut Criterion) {
     let mut group = c.benchmark_group("construction");
 
@@ -114,7 +211,7 @@ fn bench_construction(c: &mut Criterion) {
 }
 
 fn bench_select_in_word(c: &mut Criterion) {
-    use succinctly::select_in_word;
+    use succinctly::{select_in_word, select_in_word_broadword};
 
     let mut group = c.benchmark_group("select_in_word");
 
@@ -136,6 +233,17 @@ fn bench_select_in_word(c: &mut Criterion) {
                 sum
             });
         });
+        // The portable variant, for comparison with the dispatched one
+        // (PDEP/BDEP where fast, otherwise a CTZ loop)
+        group.bench_with_input(BenchmarkId::new(name, "broadword"), &word, |b, &word| {
+            b.iter(|| {
+                let mut sum = 0u32;
+                for k in 0..pop {
+                    sum += select_in_word_broadword(black_box(word), k);
+                }
+                sum
+            });
+        });
     }
     group.finish();
 }
@@ -222,8 +330,10 @@ fn bench_popcount(c: &mut Criterion) {
 criterion_group!(
     benches,
     bench_rank,
+    bench_rank_directory_vs_rank9,
     bench_select,
     bench_construction,
+    bench_sparse,
     bench_select_in_word,
     bench_popcount
 );
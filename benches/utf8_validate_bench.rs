@@ -15,10 +15,13 @@
 //!
 //! Benchmarks run at multiple sizes to show scaling characteristics:
 //! - 1KB, 10KB, 100KB, 1MB, 10MB
+//!
+//! `utf8_dispatch_vs_scalar_1mb` compares `validate_utf8` (AVX2 on x86_64,
+//! NEON on aarch64) with the portable `validate_utf8_scalar`.
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::hint::black_box;
-use succinctly::text::utf8::validate_utf8;
+use succinctly::text::utf8::{validate_utf8, validate_utf8_scalar};
 
 /// Generate pure ASCII content of the specified size.
 fn generate_ascii(size: usize) -> Vec<u8> {
@@ -264,6 +267,29 @@ fn bench_sequence_types(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark the dispatched validator against the scalar one.
+fn bench_dispatch_vs_scalar(c: &mut Criterion) {
+    let mut group = c.benchmark_group("utf8_dispatch_vs_scalar_1mb");
+    let size = 1024 * 1024; // 1MB
+    group.throughput(Throughput::Bytes(size as u64));
+
+    for (name, data) in [
+        ("ascii", generate_ascii(size)),
+        ("mixed", generate_mixed(size)),
+        ("cjk", generate_cjk(size)),
+        ("emoji", generate_emoji(size)),
+    ] {
+        group.bench_with_input(BenchmarkId::new("dispatch", name), &data, |b, data| {
+            b.iter(|| validate_utf8(black_box(data)));
+        });
+        group.bench_with_input(BenchmarkId::new("scalar", name), &data, |b, data| {
+            b.iter(|| validate_utf8_scalar(black_box(data)));
+        });
+    }
+
+    group.finish();
+}
+
 fn format_size(bytes: usize) -> String {
     if bytes >= 1024 * 1024 {
         format!("{}mb", bytes / (1024 * 1024))
@@ -283,6 +309,7 @@ criterion_group!(
     bench_2byte,
     bench_error_at_end,
     bench_sequence_types,
+    bench_dispatch_vs_scalar,
 );
 
 criterion_main!(benches);
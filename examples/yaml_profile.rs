@@ -64,6 +64,9 @@ fn yaml_to_owned_value<W: AsRef<[u64]>>(value: YamlValue<'_, W>) -> OwnedValue {
                 OwnedValue::Null
             }
         }
+        YamlValue::Bool(b) => OwnedValue::Bool(b),
+        YamlValue::Int(n) => OwnedValue::Int(n),
+        YamlValue::Float(f) => OwnedValue::Float(f),
         YamlValue::Error(_) | YamlValue::Null => OwnedValue::Null,
     }
 }
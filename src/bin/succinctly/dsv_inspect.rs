@@ -0,0 +1,270 @@
+//! CLI handler for the `dsv inspect` command.
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use succinctly::dsv::{build_index, unquote_field, ColumnStats, DsvConfig, DsvDelimiter, DsvRows};
+
+/// Summarize the shape and columns of a CSV/TSV file.
+#[derive(Debug, Parser)]
+pub struct InspectArgs {
+    /// Input file (reads from stdin if not provided)
+    pub file: Option<PathBuf>,
+
+    /// Field delimiter (detected from the first 4096 bytes if not given)
+    #[arg(short, long)]
+    pub delimiter: Option<char>,
+
+    /// Treat the first row as data rather than column names
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Number of distinct sample values to show per column
+    #[arg(long, default_value = "3", value_name = "N")]
+    pub samples: usize,
+
+    /// Print the summary as JSON
+    #[arg(long)]
+    pub json_output: bool,
+}
+
+/// Where the delimiter came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DelimiterSource {
+    /// Given with `-d`
+    Given,
+    /// Detected from the input
+    Detected,
+    /// Detection found nothing to go on, so `,` was used
+    Default,
+}
+
+impl DelimiterSource {
+    /// Name used in the JSON summary.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Given => "given",
+            Self::Detected => "detected",
+            Self::Default => "default",
+        }
+    }
+}
+
+/// Summary of one column.
+struct Column {
+    name: String,
+    stats: ColumnStats,
+    samples: Vec<String>,
+}
+
+/// Run the inspect command.
+pub fn run(args: InspectArgs) -> Result<()> {
+    let input = match &args.file {
+        Some(path) => {
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?
+        }
+        None => {
+            let mut input = Vec::new();
+            io::stdin()
+                .read_to_end(&mut input)
+                .context("failed to read from stdin")?;
+            input
+        }
+    };
+
+    let (delimiter, source) = match args.delimiter {
+        Some(c) if c.is_ascii() => (c as u8, DelimiterSource::Given),
+        Some(c) => bail!("delimiter must be a single ASCII character, got {c:?}"),
+        None => match DsvDelimiter::detect(&input).and_then(|d| d.single_byte()) {
+            Some(delimiter) => (delimiter, DelimiterSource::Detected),
+            None => (b',', DelimiterSource::Default),
+        },
+    };
+    let config = DsvConfig::default().with_delimiter(delimiter);
+
+    // Split off the header row so it is not counted in the column stats
+    let index = build_index(&input, &config);
+    let (names, data): (Vec<String>, &[u8]) = if args.no_header {
+        (Vec::new(), &input)
+    } else {
+        let names = DsvRows::new(&input, &index)
+            .next()
+            .map(|row| row.fields().map(decode).collect())
+            .unwrap_or_default();
+        let data_start = index.newlines_select1(0).map_or(input.len(), |nl| nl + 1);
+        (names, &input[data_start..])
+    };
+
+    let data_index = build_index(data, &config);
+    let rows = DsvRows::new(data, &data_index).count();
+    let stats = data_index.all_column_stats(data);
+    let samples = collect_samples(data, &data_index, args.samples);
+
+    let width = names.len().max(stats.len());
+    let columns: Vec<Column> = (0..width)
+        .map(|col| Column {
+            name: names
+                .get(col)
+                .cloned()
+                .unwrap_or_else(|| format!("column {}", col + 1)),
+            stats: stats.get(col).cloned().unwrap_or_else(empty_stats),
+            samples: samples.get(col).cloned().unwrap_or_default(),
+        })
+        .collect();
+
+    if args.json_output {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json_summary(delimiter, source, rows, &columns))?
+        );
+    } else {
+        print!("{}", text_summary(delimiter, source, rows, &columns));
+    }
+    Ok(())
+}
+
+/// Decode a raw cell for display.
+fn decode(raw: &[u8]) -> String {
+    String::from_utf8_lossy(&unquote_field(raw, b'"')).into_owned()
+}
+
+/// The first `limit` distinct non-empty values of each column.
+fn collect_samples(
+    data: &[u8],
+    index: &succinctly::dsv::DsvIndex,
+    limit: usize,
+) -> Vec<Vec<String>> {
+    let mut samples: Vec<Vec<String>> = Vec::new();
+    if limit == 0 {
+        return samples;
+    }
+    for row in DsvRows::new(data, index) {
+        for (col, cell) in row.fields().enumerate() {
+            if col == samples.len() {
+                samples.push(Vec::new());
+            }
+            let value = decode(cell);
+            if !value.is_empty() && samples[col].len() < limit && !samples[col].contains(&value) {
+                samples[col].push(value);
+            }
+        }
+    }
+    samples
+}
+
+/// Stats for a header column with no data cells.
+fn empty_stats() -> ColumnStats {
+    ColumnStats {
+        count: 0,
+        null_count: 0,
+        min: None,
+        max: None,
+        numeric_count: 0,
+        int_count: 0,
+        bool_count: 0,
+        numeric_min: f64::NAN,
+        numeric_max: f64::NAN,
+        numeric_mean: f64::NAN,
+    }
+}
+
+/// Non-empty cells by type: int, float, bool, string.
+fn type_counts(stats: &ColumnStats) -> [usize; 4] {
+    let non_empty = stats.count - stats.null_count;
+    [
+        stats.int_count,
+        stats.numeric_count - stats.int_count,
+        stats.bool_count,
+        non_empty - stats.numeric_count - stats.bool_count,
+    ]
+}
+
+/// Show a delimiter the way it would be written on the command line.
+fn delimiter_label(delimiter: u8) -> String {
+    match delimiter {
+        b'\t' => "\\t".to_string(),
+        b => char::from(b).to_string(),
+    }
+}
+
+/// Format the summary as `name: value` lines with one block per column.
+fn text_summary(delimiter: u8, source: DelimiterSource, rows: usize, columns: &[Column]) -> String {
+    let mut out = format!(
+        "delimiter: {}{}\nrows: {rows}\ncolumns: {}\n",
+        delimiter_label(delimiter),
+        match source {
+            DelimiterSource::Given => "",
+            DelimiterSource::Detected => " (detected)",
+            DelimiterSource::Default => " (default; could not detect)",
+        },
+        columns.len()
+    );
+    for (col, column) in columns.iter().enumerate() {
+        let stats = &column.stats;
+        let [ints, floats, bools, strings] = type_counts(stats);
+        out.push_str(&format!(
+            "\n[{}] {}\n  cells: {}, empty: {}\n  types: int {ints}, float {floats}, bool {bools}, string {strings}\n",
+            col + 1,
+            column.name,
+            stats.count,
+            stats.null_count
+        ));
+        if stats.numeric_count > 0 {
+            out.push_str(&format!(
+                "  numeric: min {}, max {}, mean {}\n",
+                stats.numeric_min, stats.numeric_max, stats.numeric_mean
+            ));
+        }
+        if let (Some(min), Some(max)) = (&stats.min, &stats.max) {
+            out.push_str(&format!("  min: {min:?}, max: {max:?}\n"));
+        }
+        if !column.samples.is_empty() {
+            let samples: Vec<String> = column.samples.iter().map(|s| format!("{s:?}")).collect();
+            out.push_str(&format!("  samples: {}\n", samples.join(", ")));
+        }
+    }
+    out
+}
+
+/// Build the summary as a JSON value.
+fn json_summary(
+    delimiter: u8,
+    source: DelimiterSource,
+    rows: usize,
+    columns: &[Column],
+) -> serde_json::Value {
+    let columns: Vec<serde_json::Value> = columns
+        .iter()
+        .map(|column| {
+            let stats = &column.stats;
+            let [ints, floats, bools, strings] = type_counts(stats);
+            let numeric = (stats.numeric_count > 0).then(|| {
+                serde_json::json!({
+                    "min": stats.numeric_min,
+                    "max": stats.numeric_max,
+                    "mean": stats.numeric_mean,
+                })
+            });
+            serde_json::json!({
+                "name": column.name,
+                "cells": stats.count,
+                "empty": stats.null_count,
+                "types": {"int": ints, "float": floats, "bool": bools, "string": strings},
+                "min": stats.min,
+                "max": stats.max,
+                "numeric": numeric,
+                "samples": column.samples,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "delimiter": char::from(delimiter).to_string(),
+        "delimiter_detected": source == DelimiterSource::Detected,
+        "delimiter_source": source.name(),
+        "rows": rows,
+        "columns": columns.len(),
+        "column_stats": columns,
+    })
+}
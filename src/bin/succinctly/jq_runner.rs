@@ -13,7 +13,7 @@ use succinctly::dsv::{build_index as build_dsv_index, DsvConfig, DsvRows};
 use succinctly::jq::eval_generic::{eval_with_cursor, to_owned as generic_to_owned, GenericResult};
 use succinctly::jq::{self, Expr, JqValue, OwnedValue, Program};
 use succinctly::json::light::{JsonCursor, StandardJson};
-use succinctly::json::validate::{self, ValidationError};
+use succinctly::json::validate::{self, ValidationError, ValidationErrorKind};
 use succinctly::json::JsonIndex;
 
 use super::JqCommand;
@@ -88,13 +88,13 @@ impl ModuleLoader {
         }
     }
 
-    /// Resolve a module path to a file path.
-    fn resolve_module(&self, module_path: &str) -> Option<PathBuf> {
-        // Add .jq extension if not present
-        let module_file = if module_path.ends_with(".jq") {
+    /// Resolve a module path to a file path, adding `extension` (`.jq` for
+    /// modules, `.json` for data) if not present.
+    fn resolve_module(&self, module_path: &str, extension: &str) -> Option<PathBuf> {
+        let module_file = if module_path.ends_with(extension) {
             module_path.to_string()
         } else {
-            format!("{module_path}.jq")
+            format!("{module_path}{extension}")
         };
 
         // Search in each path
@@ -117,7 +117,7 @@ impl ModuleLoader {
 
         // Resolve the module path
         let file_path = self
-            .resolve_module(module_path)
+            .resolve_module(module_path, ".jq")
             .ok_or_else(|| anyhow::anyhow!("module '{module_path}' not found in search path"))?;
 
         // Read and parse the module
@@ -138,6 +138,19 @@ impl ModuleLoader {
         Ok(defs)
     }
 
+    /// Load a data file for `import "path" as $name;`: every JSON value in
+    /// it, as an array (like `--slurpfile`).
+    pub fn load_data(&self, data_path: &str) -> Result<OwnedValue> {
+        let file_path = self
+            .resolve_module(data_path, ".json")
+            .ok_or_else(|| anyhow::anyhow!("data '{data_path}' not found in search path"))?;
+        let contents = std::fs::read_to_string(&file_path)
+            .with_context(|| format!("failed to read data: {}", file_path.display()))?;
+        let values = parse_json_stream(&contents)
+            .with_context(|| format!("invalid JSON in data: {}", file_path.display()))?;
+        Ok(OwnedValue::Array(values))
+    }
+
     /// Process imports and includes, returning the modified expression with all functions defined.
     pub fn process_program(&mut self, program: &Program) -> Result<Expr> {
         let mut expr = program.expr.clone();
@@ -168,7 +181,7 @@ impl ModuleLoader {
 
         // Process imports (definitions available under namespace::)
         // Load modules and add their functions with namespace prefixes
-        for import in &program.imports {
+        for import in program.imports.iter().filter(|import| !import.data) {
             let defs = self.load_module(&import.path)?;
             let namespace = &import.alias;
 
@@ -187,6 +200,16 @@ impl ModuleLoader {
         // Transform NamespacedCall expressions to regular FuncCall expressions
         expr = rewrite_namespaced_calls(expr);
 
+        // Bind data imports as both `$name` and `$name::name`
+        for import in program.imports.iter().filter(|import| import.data) {
+            let data = self.load_data(&import.path)?;
+            let namespaced = format!("{0}::{0}", import.alias);
+            expr = jq::substitute_vars(
+                &expr,
+                [(import.alias.as_str(), &data), (namespaced.as_str(), &data)],
+            );
+        }
+
         Ok(expr)
     }
 }
@@ -586,11 +609,11 @@ fn get_error_line(input: &[u8], line: usize, column: usize) -> Option<(String, u
 
     for (i, ch) in text.char_indices() {
         if current_line == line {
-            line_start = i;
             break;
         }
         if ch == '\n' {
             current_line += 1;
+            line_start = i + 1;
         }
     }
 
@@ -683,6 +706,7 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
     // Track last output for exit status
     let mut last_output: Option<OwnedValue> = None;
     let mut had_output = false;
+    let mut had_error = false;
 
     // Validate DSV delimiter if provided
     if let Some(delim) = args.input_dsv {
@@ -726,7 +750,10 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
                     let row_value = OwnedValue::Array(fields);
 
                     // Evaluate expression on this row
-                    let results = evaluate_input(&row_value, &expr, &context)?;
+                    let Some(results) = evaluate_input(&row_value, &expr, &context) else {
+                        had_error = true;
+                        continue;
+                    };
 
                     for result in results {
                         had_output = true;
@@ -742,6 +769,9 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
             out.flush()?;
 
             // Determine exit code
+            if had_error && !args.lenient {
+                return Ok(exit_codes::HALT_ERROR);
+            }
             if args.exit_status {
                 if !had_output {
                     return Ok(exit_codes::NO_OUTPUT);
@@ -801,7 +831,11 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
                 }
             }
             // Process as JSON stream (handle multiple JSON values in one input)
-            let values = find_json_values(raw);
+            let (values, invalid) = if args.lenient {
+                (find_json_values(raw), None)
+            } else {
+                split_json_stream(raw)
+            };
             for (start, end) in values {
                 let json_bytes = &raw[start..end];
 
@@ -823,7 +857,10 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
 
                 // Slow path: build index and evaluate expression
                 let index = JsonIndex::build(json_bytes);
-                let results = evaluate_bytes_lazy(json_bytes, &expr, &index);
+                let Some(results) = evaluate_bytes_lazy(json_bytes, &expr, &index) else {
+                    had_error = true;
+                    continue;
+                };
 
                 // Consume results to free memory after each value is written
                 for result in results {
@@ -836,6 +873,14 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
                     // result is dropped here, freeing its memory immediately
                 }
             }
+
+            // Like jq, stop at input that does not parse, after the values before it
+            if let Some(err) = invalid {
+                out.flush()?;
+                let filename = files.get(idx).map(|f| f.to_string_lossy().to_string());
+                print_validation_error(&err, raw, filename.as_deref());
+                return Ok(exit_codes::USAGE_ERROR);
+            }
         }
     } else {
         // Original path: parse through serde_json (loses number formatting)
@@ -846,7 +891,10 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
         };
 
         for input in inputs {
-            let results = evaluate_input(&input, &expr, &context)?;
+            let Some(results) = evaluate_input(&input, &expr, &context) else {
+                had_error = true;
+                continue;
+            };
 
             for result in results {
                 had_output = true;
@@ -859,6 +907,9 @@ pub fn run_jq(args: JqCommand) -> Result<i32> {
     out.flush()?;
 
     // Determine exit code
+    if had_error && !args.lenient {
+        return Ok(exit_codes::HALT_ERROR);
+    }
     if args.exit_status {
         if !had_output {
             return Ok(exit_codes::NO_OUTPUT);
@@ -1095,6 +1146,51 @@ fn read_file_bytes(path: &Path) -> Result<Vec<u8>> {
     std::fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))
 }
 
+/// Find the byte ranges of the JSON values in a byte slice, stopping at the
+/// first one that is not valid JSON.
+///
+/// Returns the ranges of the values before it and, if there is one, its
+/// validation error, positioned in `bytes`.
+fn split_json_stream(bytes: &[u8]) -> (Vec<(usize, usize)>, Option<ValidationError>) {
+    let mut values = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos >= bytes.len() {
+            return (values, None);
+        }
+
+        // The validator stops at the first byte after a complete value
+        let len = match validate::validate(&bytes[pos..]) {
+            Ok(()) => bytes.len() - pos,
+            Err(e) if e.kind == ValidationErrorKind::TrailingContent => e.position.offset,
+            Err(mut e) => {
+                e.position = position_at(bytes, pos + e.position.offset);
+                return (values, Some(e));
+            }
+        };
+        let value = trim_ascii_ws(&bytes[pos..pos + len]);
+        values.push((pos, pos + value.len()));
+        pos += len;
+    }
+}
+
+/// The line and column of byte `offset` in `bytes`.
+fn position_at(bytes: &[u8], offset: usize) -> validate::Position {
+    let line_start = bytes[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    validate::Position {
+        offset,
+        line: 1 + bytes[..offset].iter().filter(|&&b| b == b'\n').count(),
+        column: offset - line_start + 1,
+    }
+}
+
 /// Find the byte ranges of JSON values in a byte slice.
 ///
 /// This is a simple heuristic that finds the boundaries of top-level JSON values
@@ -1363,11 +1459,13 @@ fn serde_to_owned(value: &serde_json::Value) -> OwnedValue {
 }
 
 /// Evaluate the expression against an input value.
+///
+/// Returns `None` after printing the error if evaluation failed.
 fn evaluate_input(
     input: &OwnedValue,
     expr: &jq::Expr,
     _context: &EvalContext,
-) -> Result<Vec<OwnedValue>> {
+) -> Option<Vec<OwnedValue>> {
     // Convert OwnedValue to JSON bytes for indexing
     let json_str = input.to_json();
     let json_bytes = json_str.as_bytes();
@@ -1382,19 +1480,19 @@ fn evaluate_input(
 
     // Convert result to Vec<OwnedValue>
     match result {
-        GenericResult::One(v) => Ok(vec![generic_to_owned(&v)]),
-        GenericResult::OneCursor(c) => Ok(vec![generic_to_owned(&c.value())]),
-        GenericResult::Many(vs) => Ok(vs.iter().map(generic_to_owned).collect()),
-        GenericResult::None => Ok(vec![]),
+        GenericResult::One(v) => Some(vec![generic_to_owned(&v)]),
+        GenericResult::OneCursor(c) => Some(vec![generic_to_owned(&c.value())]),
+        GenericResult::Many(vs) => Some(vs.iter().map(generic_to_owned).collect()),
+        GenericResult::None => Some(vec![]),
         GenericResult::Error(e) => {
             eprintln!("jq: error: {e}");
-            Ok(vec![])
+            None
         }
-        GenericResult::Owned(v) => Ok(vec![v]),
-        GenericResult::ManyOwned(vs) => Ok(vs),
+        GenericResult::Owned(v) => Some(vec![v]),
+        GenericResult::ManyOwned(vs) => Some(vs),
         GenericResult::Break(label) => {
             eprintln!("jq: error: break ${label} not in label");
-            Ok(vec![])
+            None
         }
     }
 }
@@ -1402,12 +1500,13 @@ fn evaluate_input(
 /// Evaluate expression against raw JSON bytes, returning lazy JqValues.
 ///
 /// This function preserves original number formatting by working directly
-/// with the source bytes instead of parsing through serde_json.
+/// with the source bytes instead of parsing through serde_json. Returns `None`
+/// after printing the error if evaluation failed.
 fn evaluate_bytes_lazy<'a>(
     json_bytes: &'a [u8],
     expr: &jq::Expr,
     index: &'a JsonIndex,
-) -> Vec<JqValue<'a, Vec<u64>>> {
+) -> Option<Vec<JqValue<'a, Vec<u64>>>> {
     let cursor = index.root(json_bytes);
     // Use eval_with_cursor to preserve cursor context for position-based navigation
     let result = eval_with_cursor(expr, cursor);
@@ -1421,8 +1520,8 @@ fn evaluate_bytes_lazy<'a>(
 fn generic_result_to_jq_values<'a, W: Clone + AsRef<[u64]>>(
     result: GenericResult<StandardJson<'a, W>>,
     cursor: JsonCursor<'a, W>,
-) -> Vec<JqValue<'a, W>> {
-    match result {
+) -> Option<Vec<JqValue<'a, W>>> {
+    let values = match result {
         GenericResult::One(v) => vec![standard_json_to_jq_value(v, &cursor)],
         // OneCursor: directly use the cursor - most memory efficient for unchanged values
         GenericResult::OneCursor(c) => vec![JqValue::Cursor(c)],
@@ -1433,15 +1532,16 @@ fn generic_result_to_jq_values<'a, W: Clone + AsRef<[u64]>>(
         GenericResult::None => vec![],
         GenericResult::Error(e) => {
             eprintln!("jq: error: {e}");
-            vec![]
+            return None;
         }
         GenericResult::Owned(v) => vec![JqValue::from_owned(v)],
         GenericResult::ManyOwned(vs) => vs.into_iter().map(JqValue::from_owned).collect(),
         GenericResult::Break(label) => {
             eprintln!("jq: error: break ${label} not in label");
-            vec![]
+            return None;
         }
-    }
+    };
+    Some(values)
 }
 
 /// Convert StandardJson to JqValue, preserving lazy cursor references.
@@ -1599,10 +1699,11 @@ impl LiteralFormatter for JqCompatFormatter {
     }
 
     fn format_float(&self, f: f64) -> String {
-        if f.is_nan() || f.is_infinite() {
-            "null".to_string()
-        } else {
+        if f.is_finite() {
             format!("{f}")
+        } else {
+            // null for NaN, jq's largest finite number for the infinities
+            OwnedValue::Float(f).to_json()
         }
     }
 
@@ -1625,10 +1726,11 @@ impl LiteralFormatter for PreserveFormatter {
     }
 
     fn format_float(&self, f: f64) -> String {
-        if f.is_nan() || f.is_infinite() {
-            "null".to_string()
-        } else {
+        if f.is_finite() {
             format!("{f}")
+        } else {
+            // null for NaN, jq's largest finite number for the infinities
+            OwnedValue::Float(f).to_json()
         }
     }
 
@@ -2079,6 +2181,7 @@ fn format_json(value: &OwnedValue, config: &OutputConfig) -> String {
         ascii: config.ascii_output,
         float_style: FloatStyle::Shortest,
         control_escape: ControlEscape::Jq,
+        infinity_as_max: true,
     };
     let json = output::format_json(value, &opts);
 
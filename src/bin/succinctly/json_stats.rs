@@ -0,0 +1,72 @@
+//! CLI handler for the `json stats` command.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use succinctly::json::{IndexMemoryUsage, JsonIndex, JsonStats};
+
+/// Summarize the structure of a JSON document.
+#[derive(Debug, Parser)]
+pub struct StatsArgs {
+    /// Input file (reads from stdin if not provided)
+    pub file: Option<PathBuf>,
+
+    /// Also report the memory used by each part of the index
+    #[arg(long)]
+    pub memory: bool,
+}
+
+/// Run the stats command.
+pub fn run(args: StatsArgs) -> Result<()> {
+    let input = match &args.file {
+        Some(path) => {
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?
+        }
+        None => {
+            let mut input = Vec::new();
+            io::stdin()
+                .read_to_end(&mut input)
+                .context("failed to read from stdin")?;
+            input
+        }
+    };
+
+    let index = JsonIndex::build(&input);
+    print!("{}", format_stats(&index.stats(&input)));
+    if args.memory {
+        print!("{}", format_memory_usage(&index.memory_usage()));
+    }
+    Ok(())
+}
+
+/// Format stats as one `name: count` line per field.
+fn format_stats(stats: &JsonStats) -> String {
+    format!(
+        "objects: {}\narrays: {}\nstrings: {}\nnumbers: {}\nbooleans: {}\nnulls: {}\nmax_depth: {}\ntotal_nodes: {}\n",
+        stats.objects,
+        stats.arrays,
+        stats.strings,
+        stats.numbers,
+        stats.booleans,
+        stats.nulls,
+        stats.max_depth,
+        stats.total_nodes
+    )
+}
+
+/// Format memory usage as one `name: value` line per field.
+fn format_memory_usage(usage: &IndexMemoryUsage) -> String {
+    format!(
+        "bp_bits: {}\nbp_rank_bytes: {}\nbp_select_bytes: {}\nbp_excess_bytes: {}\nib_bits: {}\nib_rank_bytes: {}\nnewline_bytes: {}\ntotal_bytes: {}\n",
+        usage.bp_bits,
+        usage.bp_rank_bytes,
+        usage.bp_select_bytes,
+        usage.bp_excess_bytes,
+        usage.ib_bits,
+        usage.ib_rank_bytes,
+        usage.newline_bytes,
+        usage.total_bytes
+    )
+}
@@ -255,6 +255,12 @@ fn format_error_kind(kind: &ValidationErrorKind) -> String {
         ValidationErrorKind::NestingTooDeep { limit } => {
             format!("nesting depth exceeds limit of {limit}")
         }
+        ValidationErrorKind::DepthLimitExceeded { limit } => {
+            format!("nesting depth exceeds configured limit of {limit}")
+        }
+        ValidationErrorKind::StringTooLong { limit } => {
+            format!("string exceeds limit of {limit} bytes")
+        }
     }
 }
 
@@ -17,9 +17,9 @@ struct Cli {
 enum Command {
     /// JSON operations (generate, parse, benchmark)
     Json(JsonCommand),
-    /// DSV (CSV/TSV) operations (generate, parse)
+    /// DSV (CSV/TSV) operations (generate, inspect)
     Dsv(DsvCommand),
-    /// YAML operations (generate, parse)
+    /// YAML operations (generate, validate)
     Yaml(YamlCommand),
     /// Command-line JSON processor (jq-compatible)
     Jq(JqCommand),
@@ -174,6 +174,8 @@ enum JsonSubcommand {
     GenerateSuite(GenerateSuite),
     /// Validate JSON files strictly according to RFC 8259
     Validate(json_validate::ValidateArgs),
+    /// Summarize value counts and nesting depth of a JSON document
+    Stats(json_stats::StatsArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -188,6 +190,8 @@ enum DsvSubcommand {
     Generate(GenerateDsv),
     /// Generate a suite of DSV files with various sizes and patterns
     GenerateSuite(GenerateDsvSuite),
+    /// Summarize rows, columns, delimiter and per-column types of a CSV/TSV file
+    Inspect(dsv_inspect::InspectArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -202,6 +206,8 @@ enum YamlSubcommand {
     Generate(GenerateYaml),
     /// Generate a suite of YAML files with various sizes and patterns
     GenerateSuite(GenerateYamlSuite),
+    /// Check that a YAML document can be indexed, optionally converting it to JSON
+    Validate(yaml_validate::ValidateArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -754,6 +760,11 @@ struct JqCommand {
     #[arg(long)]
     validate: bool,
 
+    /// [Extension] Skip input that is not valid JSON and exit 0 after runtime
+    /// errors, instead of failing with exit status 2 or 5 like jq
+    #[arg(long)]
+    lenient: bool,
+
     // === Output Options ===
     /// Compact output (no pretty printing)
     #[arg(short = 'c', long)]
@@ -1153,8 +1164,12 @@ fn main() -> Result<()> {
                 }
 
                 let output = if args.pretty {
-                    let value: serde_json::Value = serde_json::from_str(&json)?;
-                    serde_json::to_string_pretty(&value)?
+                    let index = succinctly::json::JsonIndex::build(json.as_bytes());
+                    succinctly::json::format::pretty_print(
+                        index.root(json.as_bytes()),
+                        json.as_bytes(),
+                        2,
+                    )
                 } else {
                     json
                 };
@@ -1176,6 +1191,7 @@ fn main() -> Result<()> {
                 let exit_code = json_validate::run(args)?;
                 std::process::exit(exit_code);
             }
+            JsonSubcommand::Stats(args) => json_stats::run(args),
         },
         Command::Dsv(dsv_cmd) => match dsv_cmd.command {
             DsvSubcommand::Generate(args) => {
@@ -1207,6 +1223,7 @@ fn main() -> Result<()> {
                 Ok(())
             }
             DsvSubcommand::GenerateSuite(args) => generate_dsv_suite(args),
+            DsvSubcommand::Inspect(args) => dsv_inspect::run(args),
         },
         Command::Yaml(yaml_cmd) => match yaml_cmd.command {
             YamlSubcommand::Generate(args) => {
@@ -1232,6 +1249,10 @@ fn main() -> Result<()> {
                 Ok(())
             }
             YamlSubcommand::GenerateSuite(args) => generate_yaml_suite(args),
+            YamlSubcommand::Validate(args) => {
+                let exit_code = yaml_validate::run(args)?;
+                std::process::exit(exit_code);
+            }
         },
         Command::Dev(dev_cmd) => match dev_cmd.command {
             DevSubcommand::Bench(bench_cmd) => match bench_cmd.command {
@@ -2092,17 +2113,20 @@ fn format_bytes(bytes: usize) -> String {
 mod bench_runner;
 mod dsv_bench;
 mod dsv_generators;
+mod dsv_inspect;
 mod env_config;
 mod generators;
 mod jq_bench;
 mod jq_locate;
 mod jq_runner;
+mod json_stats;
 mod json_validate;
 mod output;
 mod text_generators;
 mod text_validate;
 mod utf8_bench;
 mod yaml_generators;
+mod yaml_validate;
 mod yq_bench;
 mod yq_locate;
 mod yq_runner;
@@ -11,12 +11,10 @@ pub mod exit_codes {
     // With -e: jq exits 1 when the LAST output was false/null; yq exits 1
     // when NO result was truthy (its "no matches found" failure).
     pub const FALSE_OR_NULL: i32 = 1;
-    #[allow(dead_code)] // STYLE-0005: complete jq exit-code set; not all emitted yet
-    pub const USAGE_ERROR: i32 = 2; // Usage problem or system error
+    pub const USAGE_ERROR: i32 = 2; // Usage problem, system error or invalid JSON input
     pub const COMPILE_ERROR: i32 = 3; // jq program compile error
     pub const NO_OUTPUT: i32 = 4; // With -e, no valid result produced (jq-only; yq folds into 1)
-    #[allow(dead_code)] // STYLE-0005: complete jq exit-code set; not all emitted yet
-    pub const HALT_ERROR: i32 = 5; // halt_error without explicit code
+    pub const HALT_ERROR: i32 = 5; // Runtime error, or halt_error without explicit code
 }
 
 /// Print build configuration information (similar to jq --build-configuration)
@@ -220,6 +218,9 @@ pub struct JsonFormatOpts<'a> {
     pub float_style: FloatStyle,
     /// Control-character escaping convention (jq vs yq).
     pub control_escape: ControlEscape,
+    /// Write the infinities as the largest finite float, as jq does, instead
+    /// of `null`.
+    pub infinity_as_max: bool,
 }
 
 /// Escape a JSON string body per the opts' control-escape style and ASCII mode.
@@ -259,7 +260,9 @@ fn format_json_impl(value: &OwnedValue, opts: &JsonFormatOpts, level: usize) ->
         OwnedValue::Bool(b) => b.to_string(),
         OwnedValue::Int(i) => i.to_string(),
         OwnedValue::Float(f) => {
-            if f.is_nan() || f.is_infinite() {
+            if f.is_infinite() && opts.infinity_as_max {
+                OwnedValue::Float(*f).to_json()
+            } else if f.is_nan() || f.is_infinite() {
                 "null".to_string() // JSON doesn't support NaN or Infinity
             } else {
                 match opts.float_style {
@@ -720,6 +723,7 @@ mod tests {
             ascii: false,
             float_style: FloatStyle::Shortest,
             control_escape: ControlEscape::Jq,
+            infinity_as_max: false,
         };
         assert_eq!(format_json(&value, &opts), r#"{"a":2,"z":1}"#);
     }
@@ -733,6 +737,7 @@ mod tests {
             ascii: false,
             float_style,
             control_escape: ControlEscape::Jq,
+            infinity_as_max: false,
         };
         assert_eq!(format_json(&value, &opts(FloatStyle::Shortest)), "1");
         assert_eq!(
@@ -829,6 +834,7 @@ mod tests {
             ascii: true,
             float_style: FloatStyle::Shortest,
             control_escape: ControlEscape::Yq,
+            infinity_as_max: false,
         };
         assert_eq!(
             format_json(&OwnedValue::Object(obj), &opts),
@@ -844,12 +850,24 @@ mod tests {
             ascii: false,
             float_style: FloatStyle::PreserveWholeFloat,
             control_escape: ControlEscape::Jq,
+            infinity_as_max: false,
         };
         assert_eq!(format_json(&OwnedValue::Float(f64::NAN), &opts), "null");
         assert_eq!(
             format_json(&OwnedValue::Float(f64::INFINITY), &opts),
             "null"
         );
+
+        // jq writes the infinities as the largest finite float
+        let opts = JsonFormatOpts {
+            infinity_as_max: true,
+            ..opts
+        };
+        assert_eq!(format_json(&OwnedValue::Float(f64::NAN), &opts), "null");
+        assert_eq!(
+            format_json(&OwnedValue::Float(f64::NEG_INFINITY), &opts),
+            "-1.7976931348623157e+308"
+        );
     }
 
     #[test]
@@ -860,6 +878,7 @@ mod tests {
             ascii: false,
             float_style: FloatStyle::Shortest,
             control_escape: ControlEscape::Jq,
+            infinity_as_max: false,
         };
         assert_eq!(format_json(&OwnedValue::Array(vec![]), &pretty), "[]");
         assert_eq!(
@@ -882,6 +901,7 @@ mod tests {
             ascii: true,
             float_style: FloatStyle::Shortest,
             control_escape: ControlEscape::Jq,
+            infinity_as_max: false,
         };
         assert_eq!(
             format_json(&value, &opts),
@@ -0,0 +1,128 @@
+//! CLI handler for the `yaml validate` command.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use succinctly::json::validate;
+use succinctly::yaml::{ConversionError, YamlIndex, YamlStats};
+
+/// Check that a YAML document can be indexed.
+#[derive(Debug, Parser)]
+pub struct ValidateArgs {
+    /// Input file (reads from stdin if not provided)
+    pub file: Option<PathBuf>,
+
+    /// Also decode every scalar and resolve every alias, as JSON conversion
+    /// does (catches invalid escapes and unknown aliases the index accepts)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Write the document to stdout as compact JSON (implies --strict)
+    #[arg(long, conflicts_with = "stats")]
+    pub convert_json: bool,
+
+    /// Print node, container, depth, anchor and alias counts to stdout
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Quiet mode: exit code only, no error output
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// Exit codes for the validate command.
+pub mod exit_codes {
+    /// YAML is valid.
+    pub const SUCCESS: i32 = 0;
+    /// YAML is invalid (validation error).
+    pub const INVALID: i32 = 1;
+    /// I/O error (file not found, permission denied, etc.).
+    pub const IO_ERROR: i32 = 2;
+}
+
+/// Run the validate command.
+pub fn run(args: ValidateArgs) -> Result<i32> {
+    let (input, name) = match &args.file {
+        Some(path) => match fs::read(path) {
+            Ok(input) => (input, path.to_string_lossy().into_owned()),
+            Err(e) => {
+                if !args.quiet {
+                    eprintln!("error: {}: {e}", path.display());
+                }
+                return Ok(exit_codes::IO_ERROR);
+            }
+        },
+        None => {
+            let mut input = Vec::new();
+            io::stdin()
+                .read_to_end(&mut input)
+                .context("failed to read from stdin")?;
+            (input, "<stdin>".to_string())
+        }
+    };
+
+    let index = match YamlIndex::build(&input) {
+        Ok(index) => index,
+        Err(err) => return Ok(report(&ConversionError::Yaml(err), &input, &name, &args)),
+    };
+
+    if args.strict || args.convert_json {
+        let json = match YamlIndex::to_json(&input) {
+            Ok(json) => json,
+            Err(err) => return Ok(report(&err, &input, &name, &args)),
+        };
+        if args.convert_json {
+            // The conversion promises RFC 8259 output; check before passing it on
+            validate::validate(&json)
+                .map_err(|e| anyhow::anyhow!("converted JSON is invalid: {e}"))?;
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&json)?;
+            stdout.write_all(b"\n")?;
+        }
+    }
+
+    if args.stats {
+        print!("{}", format_stats(&index.stats(&input)));
+    }
+    Ok(exit_codes::SUCCESS)
+}
+
+/// Print an error with its `name:line:column` location and return the exit code.
+fn report(err: &ConversionError, input: &[u8], name: &str, args: &ValidateArgs) -> i32 {
+    if !args.quiet {
+        eprintln!("error: {err}");
+        match location(err, input) {
+            Some((line, Some(column))) => eprintln!("  --> {name}:{line}:{column}"),
+            Some((line, None)) => eprintln!("  --> {name}:{line}"),
+            None => eprintln!("  --> {name}"),
+        }
+    }
+    exit_codes::INVALID
+}
+
+/// 1-based line and byte column of an error, when the error records one.
+fn location(err: &ConversionError, input: &[u8]) -> Option<(usize, Option<usize>)> {
+    if let Some(offset) = err.offset_in(input) {
+        let before = &input[..offset.min(input.len())];
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        return Some((line, Some(before.len() - line_start + 1)));
+    }
+    match err {
+        ConversionError::Yaml(err) => err.line().map(|line| (line, None)),
+        _ => None,
+    }
+}
+
+/// Format stats as one `name: count` line per field.
+fn format_stats(stats: &YamlStats) -> String {
+    format!(
+        "nodes: {}\ncontainers: {}\nmax_depth: {}\nanchors: {}\naliases: {}\n",
+        stats.nodes, stats.containers, stats.max_depth, stats.anchors, stats.aliases
+    )
+}
@@ -137,6 +137,9 @@ fn yaml_to_owned_value<W: AsRef<[u64]>>(value: YamlValue<'_, W>) -> Result<Owned
                 Ok(OwnedValue::Null)
             }
         }
+        YamlValue::Bool(b) => Ok(OwnedValue::Bool(b)),
+        YamlValue::Int(n) => Ok(OwnedValue::Int(n)),
+        YamlValue::Float(f) => Ok(OwnedValue::Float(f)),
         YamlValue::Error(msg) => Err(anyhow::anyhow!("YAML error: {msg}")),
         YamlValue::Null => Ok(OwnedValue::Null),
     }
@@ -472,6 +475,7 @@ fn output_value<W: Write>(writer: &mut W, value: &OwnedValue, config: &OutputCon
                 FloatStyle::PreserveWholeFloat
             },
             control_escape: ControlEscape::Yq,
+            infinity_as_max: false,
         },
     );
 
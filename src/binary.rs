@@ -9,6 +9,9 @@
 //! - **Bit vectors**: Raw `[u64]` as little-endian bytes. Length = file_size / 8 words.
 //! - **JSON semi-index**: Two separate files for IB and BP vectors.
 //!
+//! For compact integer arrays, [`varint`] encodes integers in 7-bit groups
+//! and [`delta`] stores sorted arrays as varint differences.
+//!
 //! ## Example
 //!
 //! ```
@@ -29,6 +32,9 @@ use alloc::vec::Vec;
 
 use bytemuck::cast_slice;
 
+pub mod delta;
+pub mod varint;
+
 /// Write a slice of u64 words to raw bytes.
 ///
 /// The output is the raw little-endian representation of the words.
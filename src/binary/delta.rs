@@ -0,0 +1,108 @@
+//! Delta-coded integer arrays.
+//!
+//! [`encode_sorted`] stores each value as the varint of its difference from
+//! the one before (the first from 0), so a sorted array of offsets costs
+//! about one or two bytes per value whatever the offsets' magnitude. There
+//! is no header: the count is implicit in the bytes.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::varint::{decode_varint, encode_varint};
+
+/// Delta-code `values` as varints.
+///
+/// `values` should be sorted in ascending order. Unsorted input still
+/// round-trips, as differences wrap, but a decrease costs the full
+/// [`MAX_VARINT_LEN`](super::varint::MAX_VARINT_LEN) bytes.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::binary::delta::{decode_sorted, encode_sorted};
+///
+/// let offsets = [1000, 1010, 1015, 1300];
+/// let bytes = encode_sorted(&offsets);
+/// assert_eq!(bytes, [0xE8, 0x07, 10, 5, 0x9D, 0x02]);
+/// assert_eq!(decode_sorted(&bytes), offsets);
+/// ```
+pub fn encode_sorted(values: &[u64]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(values.len());
+    let mut prev = 0u64;
+    for &value in values {
+        encode_varint(value.wrapping_sub(prev), &mut buf);
+        prev = value;
+    }
+    buf
+}
+
+/// Decode values written by [`encode_sorted`].
+///
+/// # Panics
+///
+/// Panics if `buf` is not a sequence of complete varints.
+pub fn decode_sorted(buf: &[u8]) -> Vec<u64> {
+    try_decode_sorted(buf).expect("malformed delta-coded array")
+}
+
+/// Decode values written by [`encode_sorted`].
+///
+/// Returns `None` if `buf` is not a sequence of complete varints.
+pub fn try_decode_sorted(mut buf: &[u8]) -> Option<Vec<u64>> {
+    let mut values = Vec::new();
+    let mut prev = 0u64;
+    while !buf.is_empty() {
+        let (delta, len) = decode_varint(buf)?;
+        prev = prev.wrapping_add(delta);
+        values.push(prev);
+        buf = &buf[len..];
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_small() {
+        assert!(encode_sorted(&[]).is_empty());
+        assert!(decode_sorted(&[]).is_empty());
+        assert_eq!(decode_sorted(&encode_sorted(&[42])), [42]);
+        assert_eq!(decode_sorted(&encode_sorted(&[u64::MAX])), [u64::MAX]);
+        // Repeated values encode as zero deltas
+        assert_eq!(encode_sorted(&[7, 7, 7]), [7, 0, 0]);
+    }
+
+    #[test]
+    fn test_roundtrip_million() {
+        // Line offsets of lines 0 to 199 bytes long
+        let values: Vec<u64> = (0..1_000_000u64)
+            .scan(0, |offset, i| {
+                *offset += (i * 7919) % 200;
+                Some(*offset)
+            })
+            .collect();
+        let bytes = encode_sorted(&values);
+        assert!(bytes.len() < 2 * values.len());
+        assert_eq!(decode_sorted(&bytes), values);
+    }
+
+    #[test]
+    fn test_unsorted_roundtrips() {
+        let values = [5, 3, u64::MAX, 0, 10];
+        assert_eq!(decode_sorted(&encode_sorted(&values)), values);
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(try_decode_sorted(&[1, 2, 0x80]), None);
+        assert_eq!(try_decode_sorted(&[1, 2, 3]), Some(vec![1, 3, 6]));
+    }
+
+    #[test]
+    #[should_panic(expected = "malformed delta-coded array")]
+    fn test_decode_sorted_panics_on_truncation() {
+        let _ = decode_sorted(&[0xFF]);
+    }
+}
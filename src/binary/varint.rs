@@ -0,0 +1,116 @@
+//! Variable-length integers.
+//!
+//! The protobuf encoding: 7 bits per byte, least significant group first,
+//! with the high bit set on every byte but the last. Values below 128 take
+//! one byte and `u64::MAX` takes the maximum of [`MAX_VARINT_LEN`].
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+/// The longest encoding of a `u64`, in bytes.
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Append the varint encoding of `n` to `buf`.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::binary::varint::encode_varint;
+///
+/// let mut buf = Vec::new();
+/// encode_varint(1, &mut buf);
+/// encode_varint(300, &mut buf);
+/// assert_eq!(buf, [0x01, 0xAC, 0x02]);
+/// ```
+#[inline]
+pub fn encode_varint(mut n: u64, buf: &mut Vec<u8>) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// Decode the varint at the start of `buf`.
+///
+/// Returns the value and the number of bytes it took, or `None` if `buf`
+/// ends before the last byte of the varint or the varint does not fit in a
+/// `u64`.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::binary::varint::decode_varint;
+///
+/// assert_eq!(decode_varint(&[0xAC, 0x02, 0x01]), Some((300, 2)));
+/// assert_eq!(decode_varint(&[0xAC]), None);
+/// ```
+#[inline]
+pub fn decode_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in buf.iter().take(MAX_VARINT_LEN).enumerate() {
+        let group = u64::from(byte & 0x7F);
+        // The tenth byte holds only the top bit of a u64
+        if i == MAX_VARINT_LEN - 1 && byte > 1 {
+            return None;
+        }
+        value |= group << (7 * i);
+        if byte < 0x80 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoded_lengths() {
+        let cases = [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16_383, 2),
+            (16_384, 3),
+            (u64::from(u32::MAX), 5),
+            (u64::MAX, MAX_VARINT_LEN),
+        ];
+        for (n, len) in cases {
+            let mut buf = Vec::new();
+            encode_varint(n, &mut buf);
+            assert_eq!(buf.len(), len, "{n}");
+            assert_eq!(decode_varint(&buf), Some((n, len)), "{n}");
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_sequence() {
+        let values: Vec<u64> = (0..64).map(|shift| (1u64 << shift) - 1).collect();
+        let mut buf = Vec::new();
+        for &n in &values {
+            encode_varint(n, &mut buf);
+        }
+        let mut rest = &buf[..];
+        for &n in &values {
+            let (decoded, len) = decode_varint(rest).unwrap();
+            assert_eq!(decoded, n);
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(decode_varint(&[]), None);
+        assert_eq!(decode_varint(&[0x80, 0x80]), None);
+        // Overflows 64 bits
+        let mut too_big = vec![0xFF; 9];
+        too_big.push(0x02);
+        assert_eq!(decode_varint(&too_big), None);
+        assert_eq!(decode_varint(&[0x80; 11]), None);
+        // Non-minimal encodings are accepted
+        assert_eq!(decode_varint(&[0x81, 0x00]), Some((1, 2)));
+    }
+}
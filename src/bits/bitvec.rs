@@ -11,7 +11,7 @@ use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 use crate::bits::popcount::{popcount_word, popcount_words};
-use crate::bits::rank::RankDirectory;
+use crate::bits::rank::{RankDirectory, WORDS_PER_BLOCK};
 use crate::bits::select::SelectIndex;
 use crate::util::broadword::select_in_word;
 use crate::{Config, RankSelect};
@@ -183,6 +183,12 @@ impl BitVec {
     pub fn words(&self) -> &[u64] {
         &self.words
     }
+
+    /// Returns the heap memory usage in bytes: the words plus the rank and
+    /// select indices.
+    pub fn heap_size(&self) -> usize {
+        self.words.len() * 8 + self.rank_dir.heap_size() + self.select_idx.heap_size()
+    }
 }
 
 impl Default for BitVec {
@@ -227,6 +233,51 @@ impl RankSelect for BitVec {
         i.min(self.len) - self.rank1(i)
     }
 
+    /// Count 1-bits in positions `[lo, hi)`, with `hi` clamped to `len`.
+    ///
+    /// A range within one 512-bit rank block is counted straight from its
+    /// words, skipping both directory lookups; longer ranges fall back to
+    /// `rank1(hi) - rank1(lo)`.
+    #[inline]
+    fn rank1_range(&self, lo: usize, hi: usize) -> usize {
+        let hi = hi.min(self.len);
+        if lo >= hi {
+            return 0;
+        }
+
+        let lo_word = lo / 64;
+        // Word holding the last bit of the range
+        let hi_word = (hi - 1) / 64;
+        if lo_word / WORDS_PER_BLOCK != hi_word / WORDS_PER_BLOCK {
+            return self.rank1(hi) - self.rank1(lo);
+        }
+
+        // Bits of the last word below `hi`: 1..=64
+        let hi_bits = hi - hi_word * 64;
+        let hi_mask = if hi_bits == 64 {
+            u64::MAX
+        } else {
+            (1u64 << hi_bits) - 1
+        };
+        let first = self.words[lo_word] >> (lo % 64);
+        if lo_word == hi_word {
+            return popcount_word(first & (hi_mask >> (lo % 64))) as usize;
+        }
+        popcount_word(first) as usize
+            + popcount_words(&self.words[lo_word + 1..hi_word])
+            + popcount_word(self.words[hi_word] & hi_mask) as usize
+    }
+
+    /// Count 0-bits in positions `[lo, hi)`, with `hi` clamped to `len`.
+    #[inline]
+    fn rank0_range(&self, lo: usize, hi: usize) -> usize {
+        let hi = hi.min(self.len);
+        if lo >= hi {
+            return 0;
+        }
+        hi - lo - self.rank1_range(lo, hi)
+    }
+
     /// Find position of the k-th 1-bit (0-indexed).
     ///
     /// Returns `None` if there are fewer than `k+1` ones.
@@ -387,6 +438,38 @@ mod tests {
         assert_eq!(bv.rank0(8), 4); // 8 - rank1(8) = 8 - 4 = 4
     }
 
+    #[test]
+    fn test_rank_range_matches_rank() {
+        // Ranges within a word, within a 512-bit block, and across blocks
+        let words: Vec<u64> = (0..20u64)
+            .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        for len in [0usize, 1, 63, 64, 65, 511, 512, 513, 1000, 1280] {
+            let bv = BitVec::from_words(words[..len.div_ceil(64)].to_vec(), len);
+            for lo in (0..=len + 2).step_by(7) {
+                for hi in (lo..=len + 70).step_by(5) {
+                    let ones = bv.rank1(hi) - bv.rank1(lo);
+                    assert_eq!(bv.rank1_range(lo, hi), ones, "len={len} [{lo}, {hi})");
+                    assert_eq!(
+                        bv.rank0_range(lo, hi),
+                        bv.rank0(hi) - bv.rank0(lo),
+                        "len={len} [{lo}, {hi})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_range_empty_and_reversed() {
+        let bv = BitVec::from_words(vec![u64::MAX; 2], 128);
+        assert_eq!(bv.rank1_range(10, 10), 0);
+        assert_eq!(bv.rank1_range(20, 10), 0);
+        assert_eq!(bv.rank0_range(20, 10), 0);
+        assert_eq!(bv.rank1_range(0, 64), 64);
+        assert_eq!(bv.rank1_range(1, 200), 127);
+    }
+
     #[test]
     fn test_select1_empty() {
         let bv = BitVec::from_words(vec![], 0);
@@ -658,6 +741,7 @@ mod tests {
         let words: Vec<u64> = vec![0xAAAA_AAAA_AAAA_AAAA; 16];
         let config = Config {
             select_sample_rate: 64,
+            ..Config::default()
         };
         let bv = BitVec::with_config(words, 1024, config);
 
@@ -674,6 +758,7 @@ mod tests {
         let words: Vec<u64> = vec![0xAAAA_AAAA_AAAA_AAAA; 8];
         let config = Config {
             select_sample_rate: 1,
+            ..Config::default()
         };
         let bv = BitVec::with_config(words, 512, config);
 
@@ -690,6 +775,7 @@ mod tests {
         let words: Vec<u64> = vec![1u64; 8]; // 8 ones total
         let config = Config {
             select_sample_rate: 1024,
+            ..Config::default()
         };
         let bv = BitVec::with_config(words, 512, config);
 
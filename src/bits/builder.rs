@@ -0,0 +1,290 @@
+//! Incremental construction of a [`BitVec`].
+//!
+//! [`BitVecBuilder`] appends bits one at a time or in runs, packing them
+//! into 64-bit words (LSB first) as it goes, for callers that produce bits
+//! during a traversal rather than as a ready-made word slice.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::BitVec;
+
+/// Builder that appends bits to a bitvector.
+///
+/// Bits are buffered in a `u64` accumulator and pushed as a whole word once
+/// 64 are ready; [`finish`](Self::finish) flushes the partial final word
+/// and builds the rank/select indices.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::{BitVec, RankSelect};
+///
+/// let mut builder = BitVec::streaming_builder();
+/// builder.push_bit(true);
+/// builder.push_zeros(100);
+/// builder.push_ones(3);
+/// builder.push_word(0b101, 3);
+///
+/// let bv = builder.finish();
+/// assert_eq!(bv.len(), 107);
+/// assert_eq!(bv.count_ones(), 6);
+/// assert_eq!(bv.select1(1), Some(101));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BitVecBuilder {
+    /// Completed words
+    words: Vec<u64>,
+    /// Bits of the word being filled; bits at and above `len % 64` are zero
+    current: u64,
+    /// Number of bits pushed so far
+    len: usize,
+}
+
+impl BitVecBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty builder with room for `bits` bits.
+    ///
+    /// Pushing at most `bits` bits, and finishing, never reallocates.
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(bits.div_ceil(64)),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Number of bits pushed so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no bits have been pushed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append one bit.
+    #[inline]
+    pub fn push_bit(&mut self, bit: bool) {
+        self.current |= u64::from(bit) << (self.len % 64);
+        self.len += 1;
+        if self.len % 64 == 0 {
+            self.words.push(self.current);
+            self.current = 0;
+        }
+    }
+
+    /// Append the lowest `bits` bits of `word`, least significant first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits > 64`.
+    #[inline]
+    pub fn push_word(&mut self, word: u64, bits: usize) {
+        assert!(bits <= 64, "bits {bits} exceeds word size");
+        if bits == 0 {
+            return;
+        }
+        let word = if bits == 64 {
+            word
+        } else {
+            word & ((1u64 << bits) - 1)
+        };
+
+        let pos = self.len % 64;
+        self.current |= word << pos;
+        if pos + bits >= 64 {
+            self.words.push(self.current);
+            // Bits that did not fit; `word` has none left when pos == 0
+            self.current = if pos == 0 { 0 } else { word >> (64 - pos) };
+        }
+        self.len += bits;
+    }
+
+    /// Append `n` 0-bits.
+    #[inline]
+    pub fn push_zeros(&mut self, n: usize) {
+        self.push_run(n, 0);
+    }
+
+    /// Append `n` 1-bits.
+    #[inline]
+    pub fn push_ones(&mut self, n: usize) {
+        self.push_run(n, u64::MAX);
+    }
+
+    /// Append `n` copies of the bit repeated in `fill` (all zeros or all
+    /// ones): top up the current word, fill whole words in bulk, then start
+    /// the next partial word.
+    fn push_run(&mut self, mut n: usize, fill: u64) {
+        let pos = self.len % 64;
+        if pos != 0 {
+            let head = n.min(64 - pos);
+            self.push_word(fill, head);
+            n -= head;
+        }
+        let full_words = n / 64;
+        self.words.resize(self.words.len() + full_words, fill);
+        self.len += full_words * 64;
+        self.push_word(fill, n % 64);
+    }
+
+    /// Build the bitvector with its rank/select indices.
+    pub fn finish(self) -> BitVec {
+        let (words, len) = self.into_words();
+        BitVec::from_words(words, len)
+    }
+
+    /// The packed words and bit length, without building any indices.
+    ///
+    /// For structures that take raw words, such as
+    /// [`BalancedParens::new`](crate::trees::BalancedParens::new).
+    pub fn into_words(mut self) -> (Vec<u64>, usize) {
+        if self.len % 64 != 0 {
+            self.words.push(self.current);
+        }
+        (self.words, self.len)
+    }
+}
+
+/// Copy bits `[start, start + len)` of `words` into fresh words, shifted so
+/// that bit `start` lands at bit 0. Bits past the end of `words` read as 0.
+pub(crate) fn extract_bits(words: &[u64], start: usize, len: usize) -> Vec<u64> {
+    let mut builder = BitVecBuilder::with_capacity(len);
+    let shift = start % 64;
+    let mut word_idx = start / 64;
+    let mut remaining = len;
+    while remaining > 0 {
+        let mut word = words.get(word_idx).copied().unwrap_or(0) >> shift;
+        if shift != 0 {
+            word |= words.get(word_idx + 1).copied().unwrap_or(0) << (64 - shift);
+        }
+        let bits = remaining.min(64);
+        builder.push_word(word, bits);
+        remaining -= bits;
+        word_idx += 1;
+    }
+    builder.into_words().0
+}
+
+impl BitVec {
+    /// Start building a bitvector bit by bit.
+    ///
+    /// Equivalent to [`BitVecBuilder::new`]; use
+    /// [`BitVecBuilder::with_capacity`] when the final length is known.
+    pub fn streaming_builder() -> BitVecBuilder {
+        BitVecBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RankSelect;
+
+    /// Pack bits the slow way for comparison.
+    fn pack(bits: &[bool]) -> Vec<u64> {
+        let mut words = vec![0u64; bits.len().div_ceil(64)];
+        for (i, &bit) in bits.iter().enumerate() {
+            words[i / 64] |= u64::from(bit) << (i % 64);
+        }
+        words
+    }
+
+    #[test]
+    fn test_empty() {
+        let bv = BitVecBuilder::new().finish();
+        assert!(bv.is_empty());
+        assert_eq!(BitVecBuilder::new().into_words(), (vec![], 0));
+    }
+
+    #[test]
+    fn test_push_bit_across_words() {
+        let mut builder = BitVec::streaming_builder();
+        let mut expected = Vec::new();
+        for i in 0..200 {
+            let bit = i % 3 == 0;
+            builder.push_bit(bit);
+            expected.push(bit);
+        }
+        assert_eq!(builder.len(), 200);
+        assert_eq!(builder.into_words(), (pack(&expected), 200));
+    }
+
+    #[test]
+    fn test_mixed_pushes_match_packing() {
+        let mut builder = BitVecBuilder::new();
+        let mut expected = Vec::new();
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        for _ in 0..500 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let n = (state >> 32) as usize % 150;
+            match state % 4 {
+                0 => {
+                    builder.push_bit(n % 2 == 1);
+                    expected.push(n % 2 == 1);
+                }
+                1 => {
+                    let bits = n % 65;
+                    builder.push_word(state, bits);
+                    expected.extend((0..bits).map(|i| (state >> i) & 1 == 1));
+                }
+                2 => {
+                    builder.push_zeros(n);
+                    expected.extend(core::iter::repeat(false).take(n));
+                }
+                _ => {
+                    builder.push_ones(n);
+                    expected.extend(core::iter::repeat(true).take(n));
+                }
+            }
+            assert_eq!(builder.len(), expected.len());
+        }
+
+        let bv = builder.finish();
+        assert_eq!(bv.len(), expected.len());
+        assert_eq!(bv.words(), pack(&expected).as_slice());
+        assert_eq!(bv.count_ones(), expected.iter().filter(|&&b| b).count());
+        assert_eq!(
+            bv.rank1(1000),
+            expected[..1000].iter().filter(|&&b| b).count()
+        );
+    }
+
+    #[test]
+    fn test_push_word_masks_high_bits() {
+        let mut builder = BitVecBuilder::new();
+        builder.push_word(u64::MAX, 3);
+        builder.push_word(0, 2);
+        assert_eq!(builder.into_words(), (vec![0b00111], 5));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds word size")]
+    fn test_push_word_too_wide() {
+        BitVecBuilder::new().push_word(0, 65);
+    }
+
+    #[test]
+    fn test_with_capacity_does_not_reallocate() {
+        let mut builder = BitVecBuilder::with_capacity(1000);
+        let start = builder.words.as_ptr();
+        builder.push_ones(333);
+        builder.push_bit(false);
+        builder.push_word(0xDEAD_BEEF, 40);
+        builder.push_zeros(626);
+        assert_eq!(builder.len(), 1000);
+        let (words, _) = builder.into_words();
+        assert_eq!(words.as_ptr(), start);
+        assert_eq!(words.len(), 16);
+    }
+}
@@ -6,9 +6,12 @@
 //! # Data Structures
 //!
 //! - [`BitVec`] - Main bitvector with integrated rank/select indices
+//! - [`BitVecBuilder`] - Appends bits one at a time or in runs to build a [`BitVec`]
 //! - [`RankDirectory`] - 3-level Poppy-style rank index (~25% overhead, cache-aligned)
+//! - [`Rank9`] - Vigna's Rank9 index over borrowed words (~25% overhead), an alternative layout
 //! - [`SelectIndex`] - Sampled select index (~1-3% overhead)
 //! - [`EliasFano`](crate::bits::EliasFano) - Elias-Fano encoding for monotone integer sequences
+//! - [`WaveletMatrix`] - Rank, select and range counting over a sequence of symbols
 //!
 //! # Example
 //!
@@ -22,15 +25,26 @@
 //! ```
 
 mod bitvec;
+mod builder;
 mod compact_rank;
 mod elias_fano;
 pub(crate) mod popcount;
 mod rank;
+mod rank9;
 mod select;
+mod slice;
+mod sparse;
+mod wavelet;
 
 pub use bitvec::BitVec;
+pub(crate) use builder::extract_bits;
+pub use builder::BitVecBuilder;
 pub use compact_rank::CompactRank;
 pub use elias_fano::{EliasFano, EliasFanoCursor, EliasFanoIter};
 pub use popcount::{popcount_word, popcount_word_portable, popcount_words};
 pub use rank::RankDirectory;
+pub use rank9::Rank9;
 pub use select::SelectIndex;
+pub use slice::BitVecSlice;
+pub use sparse::SparseBitVec;
+pub use wavelet::WaveletMatrix;
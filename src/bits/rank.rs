@@ -16,7 +16,7 @@ use core::alloc::Layout;
 use core::ptr::NonNull;
 
 /// Number of 64-bit words per basic block (512 bits = 8 words).
-const WORDS_PER_BLOCK: usize = 8;
+pub(crate) const WORDS_PER_BLOCK: usize = 8;
 
 /// Number of bits per basic block.
 #[allow(dead_code)] // STYLE-0005: derived layout constant; unused in some configs
@@ -428,6 +428,11 @@ impl RankDirectory {
         }
     }
 
+    /// Returns the heap memory usage in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.l0.len() * 8 + self.l1_l2.len * 16
+    }
+
     /// Get the cumulative rank at the start of the given word index.
     ///
     /// This returns the number of 1-bits in words `[0, word_idx)`.
@@ -0,0 +1,265 @@
+//! Rank9 rank/select index.
+//!
+//! Vigna's Rank9 ("Broadword Implementation of Rank/Select Queries", WEA
+//! 2008) stores two interleaved 64-bit counts per 512-bit block: the
+//! absolute number of 1-bits before the block, and seven 9-bit counts of
+//! the 1-bits before each later word of the block. A rank query reads one
+//! 16-byte pair, so it touches a single cache line of the index.
+//!
+//! The space is the same ~25% as [`RankDirectory`](super::RankDirectory);
+//! the difference is that Rank9 keeps full 64-bit absolute counts (no L0
+//! level for vectors over 2^32 bits) and extracts the in-block count
+//! without a branch on the word's position.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::rank::WORDS_PER_BLOCK;
+use super::BitVec;
+use crate::bits::popcount::popcount_word;
+use crate::util::broadword::select_in_word;
+use crate::RankSelect;
+
+/// Rank9 index over a borrowed slice of words.
+///
+/// Bits are read LSB first within each word, as in [`BitVec`]. Bits of the
+/// last word at or beyond `len` are ignored.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::{BitVec, RankSelect};
+///
+/// let bv = BitVec::from_words(vec![0b1010_1010u64; 16], 1024);
+/// let rank9 = bv.build_rank9();
+/// assert_eq!(rank9.rank1(600), bv.rank1(600));
+/// assert_eq!(rank9.select1(300), bv.select1(300));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rank9<'a> {
+    /// The indexed words, trimmed to `len.div_ceil(64)`
+    words: &'a [u64],
+    /// Number of valid bits
+    len: usize,
+    /// Total number of 1-bits below `len`
+    ones_count: usize,
+    /// Two entries per 512-bit block: the 1-bits before the block, then the
+    /// 1-bits before word `j` of the block at bits `9 * (j - 1)`, `j` in 1..8
+    counts: Vec<u64>,
+}
+
+impl<'a> Rank9<'a> {
+    /// Build the index over the first `len` bits of `words`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > words.len() * 64`.
+    pub fn new(words: &'a [u64], len: usize) -> Self {
+        assert!(
+            len <= words.len().saturating_mul(64),
+            "len {} exceeds capacity {}",
+            len,
+            words.len().saturating_mul(64)
+        );
+        let words = &words[..len.div_ceil(64)];
+
+        let mut rank9 = Self {
+            words,
+            len,
+            ones_count: 0,
+            counts: Vec::with_capacity(2 * words.len().div_ceil(WORDS_PER_BLOCK)),
+        };
+        let mut ones = 0u64;
+        for block_start in (0..words.len()).step_by(WORDS_PER_BLOCK) {
+            let block_end = (block_start + WORDS_PER_BLOCK).min(words.len());
+            let mut relative = 0u64;
+            let mut in_block = 0u64;
+            for (j, word_idx) in (block_start..block_end).enumerate() {
+                if j > 0 {
+                    relative |= in_block << (9 * (j - 1));
+                }
+                in_block += u64::from(popcount_word(rank9.word(word_idx)));
+            }
+            // Words past the end hold no bits: their counts are the block total
+            for j in (block_end - block_start).max(1)..WORDS_PER_BLOCK {
+                relative |= in_block << (9 * (j - 1));
+            }
+            rank9.counts.push(ones);
+            rank9.counts.push(relative);
+            ones += in_block;
+        }
+        rank9.ones_count = ones as usize;
+        rank9
+    }
+
+    /// Number of bits indexed.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no bits are indexed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Total number of 1-bits.
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.ones_count
+    }
+
+    /// Returns the heap memory usage of the index in bytes, not counting
+    /// the borrowed words.
+    pub fn heap_size(&self) -> usize {
+        self.counts.len() * 8
+    }
+
+    /// Word `idx` with bits at or beyond `len` cleared.
+    #[inline]
+    fn word(&self, idx: usize) -> u64 {
+        let word = self.words[idx];
+        let end = self.len - idx * 64;
+        if end >= 64 {
+            word
+        } else {
+            word & ((1u64 << end) - 1)
+        }
+    }
+
+    /// The 1-bits before word `j` (0..8) of block `block`.
+    #[inline]
+    fn relative(&self, block: usize, j: usize) -> usize {
+        // j - 1 wraps to u64::MAX for j == 0, and adding 8 wraps it to 7,
+        // selecting bit 63 of the packed counts, which is always zero
+        let t = (j as u64).wrapping_sub(1);
+        let shift = t.wrapping_add((t >> 60) & 8) * 9;
+        ((self.counts[2 * block + 1] >> shift) & 0x1FF) as usize
+    }
+}
+
+impl RankSelect for Rank9<'_> {
+    /// Count 1-bits in positions `[0, i)`.
+    ///
+    /// Returns 0 if `i == 0`, and `count_ones()` if `i >= len`.
+    #[inline]
+    fn rank1(&self, i: usize) -> usize {
+        if i >= self.len {
+            return self.ones_count;
+        }
+        let word_idx = i / 64;
+        let block = word_idx / WORDS_PER_BLOCK;
+        let partial = self.words[word_idx] & ((1u64 << (i % 64)) - 1);
+        self.counts[2 * block] as usize
+            + self.relative(block, word_idx % WORDS_PER_BLOCK)
+            + popcount_word(partial) as usize
+    }
+
+    /// Count 0-bits in positions `[0, i)`, with `i` clamped to `len`.
+    #[inline]
+    fn rank0(&self, i: usize) -> usize {
+        i.min(self.len) - self.rank1(i)
+    }
+
+    /// Find position of the k-th 1-bit (0-indexed).
+    ///
+    /// Binary searches the block counts, then scans the seven in-block
+    /// counts of the block found.
+    fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.ones_count {
+            return None;
+        }
+
+        // Last block with fewer than k + 1 ones before it
+        let (mut lo, mut hi) = (0, self.counts.len() / 2);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.counts[2 * mid] as usize <= k {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let block = lo;
+        let remaining = k - self.counts[2 * block] as usize;
+
+        let block_start = block * WORDS_PER_BLOCK;
+        let block_words = (self.words.len() - block_start).min(WORDS_PER_BLOCK);
+        let j = (1..block_words)
+            .take_while(|&j| self.relative(block, j) <= remaining)
+            .last()
+            .unwrap_or(0);
+
+        let word_idx = block_start + j;
+        let rank_in_word = (remaining - self.relative(block, j)) as u32;
+        Some(word_idx * 64 + select_in_word(self.word(word_idx), rank_in_word) as usize)
+    }
+}
+
+impl BitVec {
+    /// Build a [`Rank9`] index over this bitvector's words.
+    ///
+    /// The bitvector keeps its own [`RankDirectory`](super::RankDirectory);
+    /// this builds the alternative layout alongside it, borrowing the words.
+    pub fn build_rank9(&self) -> Rank9<'_> {
+        Rank9::new(self.words(), self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_words(count: usize, seed: u64) -> Vec<u64> {
+        let mut state = seed;
+        (0..count)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // Vary the density so some words are empty or full
+                match i % 5 {
+                    0 => 0,
+                    1 => u64::MAX,
+                    2 => state & (state >> 3),
+                    _ => state,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_bitvec() {
+        let words = random_words(40, 0x2545_F491_4F6C_DD1D);
+        for len in [0usize, 1, 63, 64, 65, 511, 512, 513, 1000, 2048, 2500] {
+            let bv = BitVec::from_words(words[..len.div_ceil(64)].to_vec(), len);
+            // Raw words with bits set past `len`
+            let rank9 = Rank9::new(&words, len);
+            assert_eq!(rank9.len(), len);
+            assert_eq!(rank9.count_ones(), bv.count_ones(), "len={len}");
+            for i in 0..=len + 3 {
+                assert_eq!(rank9.rank1(i), bv.rank1(i), "rank1({i}) len={len}");
+                assert_eq!(rank9.rank0(i), bv.rank0(i), "rank0({i}) len={len}");
+            }
+            for k in 0..=bv.count_ones() {
+                assert_eq!(rank9.select1(k), bv.select1(k), "select1({k}) len={len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_rank9_from_bitvec() {
+        let bv = BitVec::from_words(random_words(19, 7), 1200);
+        let rank9 = bv.build_rank9();
+        assert_eq!(rank9.heap_size(), 3 * 16);
+        assert_eq!(rank9.rank1(1200), bv.count_ones());
+        assert!(BitVec::new().build_rank9().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds capacity")]
+    fn test_len_exceeds_words() {
+        Rank9::new(&[0], 65);
+    }
+}
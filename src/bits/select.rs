@@ -138,6 +138,11 @@ impl SelectIndex {
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    /// Returns the heap memory usage in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.samples.len() * core::mem::size_of::<SampleEntry>()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,198 @@
+//! Zero-copy views of a bit range of a [`BitVec`].
+//!
+//! A [`BitVecSlice`] answers rank and select relative to its start by
+//! translating each query into one on the parent, so it needs no index of
+//! its own and works at any bit offset, word-aligned or not.
+
+use super::builder::extract_bits;
+use super::BitVec;
+use crate::RankSelect;
+
+/// A view of bits `[start, start + len)` of a [`BitVec`].
+///
+/// Created by [`BitVec::slice`]. Positions passed to and returned from the
+/// slice are relative to its start.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::{BitVec, RankSelect};
+///
+/// let bv = BitVec::from_words(vec![0b1011_0110], 8);
+/// let slice = bv.slice(1, 6); // bits 1..6: 1, 1, 0, 1, 1
+/// assert_eq!(slice.len(), 5);
+/// assert_eq!(slice.rank1(3), 2);
+/// assert_eq!(slice.select1(2), Some(3));
+/// assert_eq!(slice.to_owned().words(), &[0b11011]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BitVecSlice<'a> {
+    parent: &'a BitVec,
+    start: usize,
+    len: usize,
+    /// 1-bits of the parent before `start`
+    ones_before: usize,
+}
+
+impl<'a> BitVecSlice<'a> {
+    /// Number of bits in the slice.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the slice has no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Position of the slice's first bit in the parent.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The bitvector this slice views.
+    #[inline]
+    pub fn parent(&self) -> &'a BitVec {
+        self.parent
+    }
+
+    /// Access the bit at position `i` of the slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len`.
+    #[inline]
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds (len={})", i, self.len);
+        self.parent.get(self.start + i)
+    }
+
+    /// Count 1-bits in the slice.
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.rank1(self.len)
+    }
+
+    /// Count 0-bits in the slice.
+    #[inline]
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Copy the slice into a new [`BitVec`] with its own indices.
+    pub fn to_owned(&self) -> BitVec {
+        BitVec::from_words(
+            extract_bits(self.parent.words(), self.start, self.len),
+            self.len,
+        )
+    }
+}
+
+impl RankSelect for BitVecSlice<'_> {
+    /// Count 1-bits in slice positions `[0, i)`, with `i` clamped to `len`.
+    #[inline]
+    fn rank1(&self, i: usize) -> usize {
+        self.parent.rank1(self.start + i.min(self.len)) - self.ones_before
+    }
+
+    /// Count 0-bits in slice positions `[0, i)`, with `i` clamped to `len`.
+    #[inline]
+    fn rank0(&self, i: usize) -> usize {
+        i.min(self.len) - self.rank1(i)
+    }
+
+    /// Find the slice position of the k-th 1-bit of the slice (0-indexed).
+    fn select1(&self, k: usize) -> Option<usize> {
+        let pos = self.parent.select1(self.ones_before + k)?;
+        (pos < self.start + self.len).then(|| pos - self.start)
+    }
+}
+
+impl BitVec {
+    /// View bits `[start, end)` without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > end` or `end > len`.
+    pub fn slice(&self, start: usize, end: usize) -> BitVecSlice<'_> {
+        assert!(
+            start <= end && end <= self.len(),
+            "slice {}..{} out of bounds (len={})",
+            start,
+            end,
+            self.len()
+        );
+        BitVecSlice {
+            parent: self,
+            start,
+            len: end - start,
+            ones_before: self.rank1(start),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_bitvec(words: usize, len: usize) -> BitVec {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let words = (0..words)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            })
+            .collect();
+        BitVec::from_words(words, len)
+    }
+
+    #[test]
+    fn test_slice_matches_copy() {
+        let bv = random_bitvec(20, 1250);
+        for start in [0usize, 1, 63, 64, 65, 511, 512, 700] {
+            for end in [start, start + 1, start + 64, start + 130, 1250] {
+                let end = end.min(1250);
+                let slice = bv.slice(start, end);
+                let owned = slice.to_owned();
+                assert_eq!(owned.len(), end - start);
+                assert_eq!(slice.count_ones(), owned.count_ones(), "{start}..{end}");
+                for i in 0..slice.len() {
+                    assert_eq!(slice.get(i), bv.get(start + i), "{start}..{end} get({i})");
+                }
+                for i in 0..=slice.len() + 2 {
+                    assert_eq!(slice.rank1(i), owned.rank1(i), "{start}..{end} rank1({i})");
+                    assert_eq!(slice.rank0(i), owned.rank0(i), "{start}..{end} rank0({i})");
+                }
+                for k in 0..=slice.count_ones() {
+                    assert_eq!(
+                        slice.select1(k),
+                        owned.select1(k),
+                        "{start}..{end} select1({k})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_slice_select_stops_at_end() {
+        // All ones: the parent has 1-bits past the slice's end
+        let bv = BitVec::from_words(vec![u64::MAX; 2], 128);
+        let slice = bv.slice(65, 70);
+        assert_eq!(slice.select1(4), Some(4));
+        assert_eq!(slice.select1(5), None);
+        assert_eq!(slice.rank1_range(1, 3), 2);
+        assert!(bv.slice(10, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_slice_past_end() {
+        BitVec::from_words(vec![0], 64).slice(10, 65);
+    }
+}
@@ -0,0 +1,367 @@
+//! Golomb-Rice coded sparse bitvectors.
+//!
+//! A bitvector with few 1-bits is stored as the gaps between consecutive
+//! 1-bits. Each gap is split by a parameter `b` into a quotient, written in
+//! unary, and a `b`-bit remainder. With `b = ceil(log2(n / m))` for `n` bits
+//! and `m` ones, each 1-bit costs about `b + 2` bits instead of the
+//! `n / m` bits it occupies in a plain [`BitVec`].
+//!
+//! Every `SAMPLE_RATE`th 1-bit is sampled with its position and the
+//! stream offset after its code, so `select1` decodes at most
+//! `SAMPLE_RATE - 1` gaps and `rank1` binary searches the samples first.
+//! [`EliasFano`](super::EliasFano) gives O(1) access to a sequence of
+//! positions instead; this type trades that for a smaller encoding of
+//! very sparse sets and a bitvector-shaped API.
+//!
+//! # Example
+//!
+//! ```
+//! use succinctly::bits::SparseBitVec;
+//! use succinctly::RankSelect;
+//!
+//! let sparse = SparseBitVec::build(&[3, 1_000, 1_001, 70_000], 100_000);
+//! assert_eq!(sparse.count_ones(), 4);
+//! assert_eq!(sparse.rank1(1_001), 2);
+//! assert_eq!(sparse.select1(3), Some(70_000));
+//! assert!(sparse.get(1_000));
+//! ```
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::{BitVec, BitVecBuilder};
+use crate::RankSelect;
+
+/// One sample per this many 1-bits.
+const SAMPLE_RATE: usize = 64;
+
+/// A bitvector stored as Golomb-Rice coded gaps between its 1-bits.
+#[derive(Clone, Debug)]
+pub struct SparseBitVec {
+    /// Concatenated codes: `q` 0-bits, a 1-bit, then `rice_bits` remainder
+    /// bits (LSB first) for each gap
+    codes: Vec<u64>,
+    /// Width of each remainder
+    rice_bits: u32,
+    /// Number of bits represented
+    len: usize,
+    /// Number of 1-bits
+    ones: usize,
+    /// Position of 1-bit `s * SAMPLE_RATE`
+    sample_positions: Vec<usize>,
+    /// Code offset just after the code of 1-bit `s * SAMPLE_RATE`
+    sample_offsets: Vec<usize>,
+}
+
+/// Smallest `b` with `ones << b >= len`, i.e. `ceil(log2(len / ones))`.
+fn rice_parameter(len: usize, ones: usize) -> u32 {
+    if ones == 0 {
+        return 0;
+    }
+    let mut b = 0;
+    while b < 63 && (ones << b) < len {
+        b += 1;
+    }
+    b
+}
+
+impl SparseBitVec {
+    /// Build from the positions of the 1-bits in a bitvector of `len` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `positions` is not strictly increasing or contains a
+    /// position `>= len`.
+    pub fn build(positions: &[usize], len: usize) -> Self {
+        let rice_bits = rice_parameter(len, positions.len());
+        let mut codes = BitVecBuilder::new();
+        let mut sample_positions = Vec::with_capacity(positions.len().div_ceil(SAMPLE_RATE));
+        let mut sample_offsets = Vec::with_capacity(sample_positions.capacity());
+
+        let mut next = 0;
+        for (k, &pos) in positions.iter().enumerate() {
+            assert!(
+                pos >= next && pos < len,
+                "position {pos} out of order or out of bounds (len={len})"
+            );
+            let gap = pos - next;
+            codes.push_zeros(gap >> rice_bits);
+            codes.push_bit(true);
+            codes.push_word(gap as u64, rice_bits as usize);
+            if k % SAMPLE_RATE == 0 {
+                sample_positions.push(pos);
+                sample_offsets.push(codes.len());
+            }
+            next = pos + 1;
+        }
+
+        Self {
+            codes: codes.into_words().0,
+            rice_bits,
+            len,
+            ones: positions.len(),
+            sample_positions,
+            sample_offsets,
+        }
+    }
+
+    /// Build from the 1-bits of `bv`.
+    pub fn from_bitvec(bv: &BitVec) -> Self {
+        let mut positions = Vec::with_capacity(bv.count_ones());
+        for (word_idx, &word) in bv.words().iter().enumerate() {
+            let mut bits = word;
+            while bits != 0 {
+                let pos = word_idx * 64 + bits.trailing_zeros() as usize;
+                if pos >= bv.len() {
+                    break;
+                }
+                positions.push(pos);
+                bits &= bits - 1;
+            }
+        }
+        Self::build(&positions, bv.len())
+    }
+
+    /// Number of bits represented.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the bitvector has no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of 1-bits.
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Width of the fixed-size part of each code.
+    #[inline]
+    pub fn rice_bits(&self) -> u32 {
+        self.rice_bits
+    }
+
+    /// Access the bit at position `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len`.
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds (len={})", i, self.len);
+        self.rank1(i + 1) > self.rank1(i)
+    }
+
+    /// Returns the heap memory usage in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.codes.len() * 8
+            + (self.sample_positions.len() + self.sample_offsets.len())
+                * core::mem::size_of::<usize>()
+    }
+
+    /// Decode the gap whose code starts at `offset`, returning it and the
+    /// offset of the next code.
+    #[inline]
+    fn decode_gap(&self, mut offset: usize) -> (usize, usize) {
+        let mut quotient = 0;
+        loop {
+            let bits = self.codes[offset / 64] >> (offset % 64);
+            if bits != 0 {
+                let zeros = bits.trailing_zeros() as usize;
+                quotient += zeros;
+                offset += zeros + 1;
+                break;
+            }
+            let skipped = 64 - offset % 64;
+            quotient += skipped;
+            offset += skipped;
+        }
+        let remainder = self.read_bits(offset, self.rice_bits);
+        (
+            (quotient << self.rice_bits) | remainder,
+            offset + self.rice_bits as usize,
+        )
+    }
+
+    /// Read `width` bits starting at bit `offset` of the codes.
+    #[inline]
+    fn read_bits(&self, offset: usize, width: u32) -> usize {
+        if width == 0 {
+            return 0;
+        }
+        let shift = offset % 64;
+        let mut bits = self.codes[offset / 64] >> shift;
+        if shift + width as usize > 64 {
+            bits |= self.codes[offset / 64 + 1] << (64 - shift);
+        }
+        (bits & (u64::MAX >> (64 - width))) as usize
+    }
+}
+
+impl RankSelect for SparseBitVec {
+    /// Count 1-bits in positions `[0, i)`, with `i` clamped to `len`.
+    fn rank1(&self, i: usize) -> usize {
+        if i >= self.len {
+            return self.ones;
+        }
+        // Last sample at or before position i - 1
+        let sample = self.sample_positions.partition_point(|&pos| pos < i);
+        if sample == 0 {
+            return 0;
+        }
+        let sample = sample - 1;
+        let mut count = sample * SAMPLE_RATE + 1;
+        let mut pos = self.sample_positions[sample];
+        let mut offset = self.sample_offsets[sample];
+        while count < self.ones {
+            let (gap, next_offset) = self.decode_gap(offset);
+            pos += gap + 1;
+            if pos >= i {
+                break;
+            }
+            count += 1;
+            offset = next_offset;
+        }
+        count
+    }
+
+    /// Count 0-bits in positions `[0, i)`, with `i` clamped to `len`.
+    #[inline]
+    fn rank0(&self, i: usize) -> usize {
+        i.min(self.len) - self.rank1(i)
+    }
+
+    /// Find position of the k-th 1-bit (0-indexed).
+    fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.ones {
+            return None;
+        }
+        let sample = k / SAMPLE_RATE;
+        let mut pos = self.sample_positions[sample];
+        let mut offset = self.sample_offsets[sample];
+        for _ in 0..k % SAMPLE_RATE {
+            let (gap, next_offset) = self.decode_gap(offset);
+            pos += gap + 1;
+            offset = next_offset;
+        }
+        Some(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Positions with gaps drawn around `mean_gap`, including some zeros.
+    fn sparse_positions(count: usize, mean_gap: usize, seed: u64) -> Vec<usize> {
+        let mut state = seed;
+        let mut pos = 0;
+        (0..count)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let p = pos;
+                pos += 1 + (state as usize % (2 * mean_gap)) * usize::from(state % 7 != 0);
+                p
+            })
+            .collect()
+    }
+
+    fn check_against_bitvec(sparse: &SparseBitVec, bv: &BitVec) {
+        assert_eq!(sparse.len(), bv.len());
+        assert_eq!(sparse.count_ones(), bv.count_ones());
+        for i in (0..=bv.len() + 1).step_by(3).chain([0, bv.len()]) {
+            assert_eq!(sparse.rank1(i), bv.rank1(i), "rank1({i})");
+            assert_eq!(sparse.rank0(i), bv.rank0(i), "rank0({i})");
+        }
+        for k in 0..=bv.count_ones() {
+            assert_eq!(sparse.select1(k), bv.select1(k), "select1({k})");
+        }
+    }
+
+    #[test]
+    fn test_matches_bitvec() {
+        for (count, mean_gap) in [
+            (0usize, 1usize),
+            (1, 5),
+            (63, 1),
+            (64, 3),
+            (65, 100),
+            (500, 40),
+        ] {
+            let positions = sparse_positions(count, mean_gap, 0x9E37_79B9_7F4A_7C15);
+            let len = positions.last().map_or(10, |&p| p + 1 + mean_gap);
+            let sparse = SparseBitVec::build(&positions, len);
+            let bv = BitVec::from_words(
+                {
+                    let mut words = vec![0u64; len.div_ceil(64)];
+                    for &p in &positions {
+                        words[p / 64] |= 1 << (p % 64);
+                    }
+                    words
+                },
+                len,
+            );
+            check_against_bitvec(&sparse, &bv);
+            let round_trip = SparseBitVec::from_bitvec(&bv);
+            assert_eq!(round_trip.codes, sparse.codes);
+        }
+    }
+
+    #[test]
+    fn test_get_and_edges() {
+        let sparse = SparseBitVec::build(&[0, 1, 2, 199], 200);
+        assert!(sparse.get(0) && sparse.get(2) && sparse.get(199));
+        assert!(!sparse.get(3) && !sparse.get(198));
+        assert_eq!(sparse.rank1(0), 0);
+        assert_eq!(sparse.rank1(500), 4);
+        assert_eq!(sparse.select1(4), None);
+
+        let empty = SparseBitVec::build(&[], 0);
+        assert!(empty.is_empty());
+        assert_eq!(empty.rank1(0), 0);
+        assert_eq!(empty.select1(0), None);
+    }
+
+    #[test]
+    fn test_rice_parameter() {
+        assert_eq!(rice_parameter(1000, 0), 0);
+        assert_eq!(rice_parameter(10, 10), 0);
+        assert_eq!(rice_parameter(1000, 1), 10);
+        assert_eq!(rice_parameter(1024, 1), 10);
+        assert_eq!(rice_parameter(1025, 1), 11);
+        assert_eq!(rice_parameter(1_000_000, 1_000), 10);
+    }
+
+    #[test]
+    fn test_memory_at_one_per_thousand() {
+        // 0.1% density: 1 000 ones in 1 000 000 bits
+        let positions: Vec<usize> = (0..1_000).map(|i| i * 1_000 + (i * 37) % 1_000).collect();
+        let sparse = SparseBitVec::build(&positions, 1_000_000);
+        let bv = BitVec::from_words(
+            {
+                let mut words = vec![0u64; 1_000_000usize.div_ceil(64)];
+                for &p in &positions {
+                    words[p / 64] |= 1 << (p % 64);
+                }
+                words
+            },
+            1_000_000,
+        );
+        // About 12 bits per 1-bit plus samples, against 125 KB of words
+        assert!(sparse.heap_size() < 2_000, "{}", sparse.heap_size());
+        assert!(bv.heap_size() > 60 * sparse.heap_size());
+        check_against_bitvec(&sparse, &bv);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn test_unsorted_positions() {
+        SparseBitVec::build(&[5, 5], 10);
+    }
+}
@@ -0,0 +1,343 @@
+//! Wavelet matrix over a sequence of symbols.
+//!
+//! A wavelet matrix generalizes bitvector rank/select to sequences over an
+//! alphabet `0..sigma`. It stores `ceil(log2(sigma))` bitvectors of the
+//! sequence length, one per bit of the symbols from most to least
+//! significant. Level `l` holds bit `l` of every symbol, after the sequence has
+//! been stably partitioned by the bits above it (0-bits first). Each query
+//! walks down the levels with one or two `rank` calls per level, so `get`,
+//! `rank` and [`range_frequency`](WaveletMatrix::range_frequency) cost
+//! O(log sigma), and `select` costs O(log sigma * log n).
+//!
+//! # Example
+//!
+//! ```
+//! use succinctly::bits::WaveletMatrix;
+//!
+//! let wm = WaveletMatrix::build(&[3, 1, 4, 1, 5, 2, 6, 5], 8);
+//! assert_eq!(wm.get(2), 4);
+//! assert_eq!(wm.rank(1, 4), 2); // two 1s in seq[0..4]
+//! assert_eq!(wm.select(5, 1), Some(7)); // second 5 is at position 7
+//! assert_eq!(wm.range_frequency(1, 6, 1, 4), 3); // 1, 1, 2
+//! ```
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::{BitVec, BitVecBuilder};
+use crate::RankSelect;
+
+/// A wavelet matrix over symbols `0..sigma`.
+#[derive(Clone, Debug)]
+pub struct WaveletMatrix {
+    /// One bitvector per symbol bit, most significant first
+    levels: Vec<BitVec>,
+    /// Number of 0-bits in each level; the 1-bits follow them at the next level
+    zeros: Vec<usize>,
+    /// Alphabet size
+    sigma: u32,
+    /// Sequence length
+    len: usize,
+}
+
+impl WaveletMatrix {
+    /// Build from a sequence of symbols in `0..sigma`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a symbol is `>= sigma`.
+    pub fn build(seq: &[u32], sigma: u32) -> Self {
+        if let Some(&symbol) = seq.iter().find(|&&s| s >= sigma) {
+            panic!("symbol {symbol} out of range (sigma={sigma})");
+        }
+        let depth = bit_width(sigma);
+        let mut levels = Vec::with_capacity(depth);
+        let mut zeros = Vec::with_capacity(depth);
+
+        let mut current = seq.to_vec();
+        let mut ones = Vec::with_capacity(seq.len());
+        for level in 0..depth {
+            let shift = depth - 1 - level;
+            let mut bits = BitVecBuilder::with_capacity(seq.len());
+            // Stable partition by this bit: 0-bits stay in `current`
+            let mut kept = 0;
+            for i in 0..current.len() {
+                let symbol = current[i];
+                let bit = (symbol >> shift) & 1 == 1;
+                bits.push_bit(bit);
+                if bit {
+                    ones.push(symbol);
+                } else {
+                    current[kept] = symbol;
+                    kept += 1;
+                }
+            }
+            current.truncate(kept);
+            current.append(&mut ones);
+            levels.push(bits.finish());
+            zeros.push(kept);
+        }
+
+        Self {
+            levels,
+            zeros,
+            sigma,
+            len: seq.len(),
+        }
+    }
+
+    /// Length of the sequence.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the sequence is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Alphabet size the matrix was built with.
+    #[inline]
+    pub fn sigma(&self) -> u32 {
+        self.sigma
+    }
+
+    /// The symbol at position `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len`.
+    pub fn get(&self, i: usize) -> u32 {
+        assert!(i < self.len, "index {} out of bounds (len={})", i, self.len);
+        let mut pos = i;
+        let mut symbol = 0;
+        for (bits, &zeros) in self.levels.iter().zip(&self.zeros) {
+            symbol <<= 1;
+            if bits.get(pos) {
+                symbol |= 1;
+                pos = zeros + bits.rank1(pos);
+            } else {
+                pos = bits.rank0(pos);
+            }
+        }
+        symbol
+    }
+
+    /// Count occurrences of `symbol` in positions `[0, pos)`.
+    ///
+    /// `pos` is clamped to `len`. Returns 0 if `symbol >= sigma`.
+    pub fn rank(&self, symbol: u32, pos: usize) -> usize {
+        if symbol >= self.sigma {
+            return 0;
+        }
+        let (start, end) = self.descend(symbol, 0, pos.min(self.len));
+        end - start
+    }
+
+    /// Find the position of the `k`-th occurrence (0-indexed) of `symbol`.
+    ///
+    /// Returns `None` if `symbol` occurs `k` times or fewer.
+    pub fn select(&self, symbol: u32, k: usize) -> Option<usize> {
+        if symbol >= self.sigma {
+            return None;
+        }
+        let (start, end) = self.descend(symbol, 0, self.len);
+        if k >= end - start {
+            return None;
+        }
+        // Walk back up from the symbol's block in the final arrangement
+        let mut pos = start + k;
+        for (level, (bits, &zeros)) in self.levels.iter().zip(&self.zeros).enumerate().rev() {
+            pos = if self.bit(symbol, level) {
+                bits.select1(pos - zeros)?
+            } else {
+                select0(bits, pos)?
+            };
+        }
+        Some(pos)
+    }
+
+    /// Count positions `i` in `[lo_pos, hi_pos)` whose symbol lies in
+    /// `[lo_sym, hi_sym)`.
+    ///
+    /// Positions are clamped to `len`. Returns 0 for an empty range.
+    pub fn range_frequency(&self, lo_pos: usize, hi_pos: usize, lo_sym: u32, hi_sym: u32) -> usize {
+        let hi_pos = hi_pos.min(self.len);
+        if lo_pos >= hi_pos || lo_sym >= hi_sym {
+            return 0;
+        }
+        self.count_less(lo_pos, hi_pos, hi_sym) - self.count_less(lo_pos, hi_pos, lo_sym)
+    }
+
+    /// Returns the heap memory usage in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.levels.iter().map(BitVec::heap_size).sum::<usize>()
+            + self.levels.capacity() * core::mem::size_of::<BitVec>()
+            + self.zeros.capacity() * core::mem::size_of::<usize>()
+    }
+
+    /// Bit of `symbol` examined at `level`.
+    #[inline]
+    fn bit(&self, symbol: u32, level: usize) -> bool {
+        (symbol >> (self.levels.len() - 1 - level)) & 1 == 1
+    }
+
+    /// Follow `symbol`'s bits down the levels, mapping the range
+    /// `[start, end)` to the matching range in the final arrangement.
+    fn descend(&self, symbol: u32, mut start: usize, mut end: usize) -> (usize, usize) {
+        for (level, (bits, &zeros)) in self.levels.iter().zip(&self.zeros).enumerate() {
+            if self.bit(symbol, level) {
+                start = zeros + bits.rank1(start);
+                end = zeros + bits.rank1(end);
+            } else {
+                start = bits.rank0(start);
+                end = bits.rank0(end);
+            }
+        }
+        (start, end)
+    }
+
+    /// Count positions in `[start, end)` whose symbol is `< bound`.
+    fn count_less(&self, mut start: usize, mut end: usize, bound: u32) -> usize {
+        if bound.checked_shr(self.levels.len() as u32).unwrap_or(0) != 0 {
+            return end - start;
+        }
+        let mut count = 0;
+        for (level, (bits, &zeros)) in self.levels.iter().zip(&self.zeros).enumerate() {
+            if self.bit(bound, level) {
+                // Every symbol with a 0 here (and the same bits above) is smaller
+                count += bits.rank0_range(start, end);
+                start = zeros + bits.rank1(start);
+                end = zeros + bits.rank1(end);
+            } else {
+                start = bits.rank0(start);
+                end = bits.rank0(end);
+            }
+        }
+        count
+    }
+}
+
+/// Number of bits needed to write every symbol in `0..sigma`.
+fn bit_width(sigma: u32) -> usize {
+    (u32::BITS - sigma.saturating_sub(1).leading_zeros()) as usize
+}
+
+/// Position of the `k`-th 0-bit (0-indexed), by binary search on `rank0`.
+fn select0(bits: &BitVec, k: usize) -> Option<usize> {
+    if k >= bits.count_zeros() {
+        return None;
+    }
+    // Smallest `i` with more than `k` zeros in `[0, i]`
+    let (mut lo, mut hi) = (0, bits.len() - 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if bits.rank0(mid + 1) > k {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo-random symbols in `0..sigma`.
+    fn symbols(len: usize, sigma: u32, seed: u64) -> Vec<u32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % u64::from(sigma)) as u32
+            })
+            .collect()
+    }
+
+    fn check_against_naive(seq: &[u32], sigma: u32) {
+        let wm = WaveletMatrix::build(seq, sigma);
+        assert_eq!(wm.len(), seq.len());
+        for (i, &symbol) in seq.iter().enumerate() {
+            assert_eq!(wm.get(i), symbol, "get({i})");
+        }
+        for symbol in 0..=sigma {
+            let positions: Vec<usize> = (0..seq.len()).filter(|&i| seq[i] == symbol).collect();
+            for pos in (0..=seq.len() + 1).step_by(7).chain([seq.len()]) {
+                let expected = positions.iter().filter(|&&p| p < pos).count();
+                assert_eq!(wm.rank(symbol, pos), expected, "rank({symbol}, {pos})");
+            }
+            for k in 0..=positions.len() {
+                assert_eq!(
+                    wm.select(symbol, k),
+                    positions.get(k).copied(),
+                    "select({symbol}, {k})"
+                );
+            }
+        }
+        for (lo_pos, hi_pos) in [(0, seq.len()), (3, 17), (seq.len() / 2, seq.len() + 5)] {
+            for lo_sym in 0..=sigma {
+                for hi_sym in lo_sym..=sigma + 1 {
+                    let expected = seq
+                        .iter()
+                        .take(hi_pos)
+                        .skip(lo_pos)
+                        .filter(|&&s| (lo_sym..hi_sym).contains(&s))
+                        .count();
+                    assert_eq!(
+                        wm.range_frequency(lo_pos, hi_pos, lo_sym, hi_sym),
+                        expected,
+                        "range_frequency({lo_pos}, {hi_pos}, {lo_sym}, {hi_sym})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_naive() {
+        for (len, sigma) in [
+            (0, 4),
+            (1, 1),
+            (50, 1),
+            (64, 2),
+            (130, 5),
+            (300, 16),
+            (500, 37),
+        ] {
+            check_against_naive(&symbols(len, sigma, 0x9E37_79B9_7F4A_7C15), sigma);
+        }
+    }
+
+    #[test]
+    fn test_large_symbols() {
+        let seq = [u32::MAX - 1, 0, 1 << 31, u32::MAX - 1, 7];
+        let wm = WaveletMatrix::build(&seq, u32::MAX);
+        assert_eq!(wm.get(2), 1 << 31);
+        assert_eq!(wm.rank(u32::MAX - 1, 5), 2);
+        assert_eq!(wm.select(u32::MAX - 1, 1), Some(3));
+        assert_eq!(wm.range_frequency(0, 5, 1, u32::MAX), 4);
+    }
+
+    #[test]
+    fn test_out_of_range_queries() {
+        let wm = WaveletMatrix::build(&[0, 1, 2, 1], 3);
+        assert_eq!(wm.sigma(), 3);
+        assert_eq!(wm.rank(3, 4), 0);
+        assert_eq!(wm.select(3, 0), None);
+        assert_eq!(wm.select(1, 2), None);
+        assert_eq!(wm.range_frequency(2, 1, 0, 3), 0);
+        assert_eq!(wm.range_frequency(0, 4, 2, 1), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_symbol_out_of_range() {
+        WaveletMatrix::build(&[0, 4], 4);
+    }
+}
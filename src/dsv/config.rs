@@ -1,26 +1,172 @@
 //! Configuration for DSV parsing.
 
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// A field delimiter: one byte, or a sequence such as `||`, as given to
+/// [`DsvConfig::with_delimiter`].
+///
+/// Single-byte delimiters use the SIMD index builders. Longer ones use a
+/// builder that skips to the next candidate byte with a vector scan, then
+/// matches the sequence left to right outside quotes; it still stops at
+/// every occurrence of the delimiter's first byte, so text dense in that
+/// byte indexes more slowly than with a single-byte delimiter. An empty
+/// `String` never matches, so every row is a single field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DsvDelimiter {
+    /// A single byte, such as `,` or `\t`
+    Byte(u8),
+    /// A two-byte sequence, such as `||`
+    TwoBytes([u8; 2]),
+    /// Any byte sequence
+    String(String),
+}
+
+impl DsvDelimiter {
+    /// The delimiter's bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Byte(b) => core::slice::from_ref(b),
+            Self::TwoBytes(bytes) => bytes,
+            Self::String(s) => s.as_bytes(),
+        }
+    }
+
+    /// The delimiter's byte, if it is exactly one byte long.
+    pub fn single_byte(&self) -> Option<u8> {
+        match *self.as_bytes() {
+            [b] => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Guess the delimiter of `input` from its first 4096 bytes.
+    ///
+    /// Each of `,`, `\t`, `;` and `|` is counted per row, outside `"`
+    /// quotes. The winner is the candidate whose most common non-zero count
+    /// is shared by the most rows, with ties going to the larger count and
+    /// then to the order above. A row cut off by the 4096-byte limit is
+    /// ignored. Returns `None` if no candidate appears.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::DsvDelimiter;
+    ///
+    /// let tsv = b"name\tnote\nAlice\t\"a, b, c\"\nBob\tx, y\n";
+    /// assert_eq!(DsvDelimiter::detect(tsv), Some(DsvDelimiter::Byte(b'\t')));
+    /// assert_eq!(DsvDelimiter::detect(b"one\ntwo\n"), None);
+    /// ```
+    pub fn detect(input: &[u8]) -> Option<Self> {
+        const SAMPLE_LEN: usize = 4096;
+        const CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+        let mut sample = &input[..input.len().min(SAMPLE_LEN)];
+        if input.len() > SAMPLE_LEN {
+            if let Some(end) = sample.iter().rposition(|&b| b == b'\n') {
+                sample = &sample[..end];
+            }
+        }
+
+        // Per-row candidate counts, for rows with any content
+        let mut rows: Vec<[usize; CANDIDATES.len()]> = Vec::new();
+        let mut row = [0; CANDIDATES.len()];
+        let mut row_has_content = false;
+        let mut in_quotes = false;
+        for &b in sample {
+            if b == b'"' {
+                in_quotes = !in_quotes;
+            } else if in_quotes {
+                // Delimiters and newlines inside quotes are data
+            } else if b == b'\n' {
+                if row_has_content {
+                    rows.push(row);
+                }
+                row = [0; CANDIDATES.len()];
+                row_has_content = false;
+                continue;
+            } else if let Some(i) = CANDIDATES.iter().position(|&c| c == b) {
+                row[i] += 1;
+            } else if b == b'\r' {
+                continue;
+            }
+            row_has_content = true;
+        }
+        if row_has_content {
+            rows.push(row);
+        }
+
+        let mut best: Option<(usize, usize, u8)> = None;
+        for (i, &candidate) in CANDIDATES.iter().enumerate() {
+            // Most common non-zero count and how many rows share it
+            let mut counts: Vec<usize> = rows.iter().map(|row| row[i]).filter(|&n| n > 0).collect();
+            counts.sort_unstable();
+            let (mut rows_matching, mut per_row) = (0, 0);
+            let mut start = 0;
+            while start < counts.len() {
+                let run = counts[start..]
+                    .iter()
+                    .take_while(|&&n| n == counts[start])
+                    .count();
+                if (run, counts[start]) > (rows_matching, per_row) {
+                    (rows_matching, per_row) = (run, counts[start]);
+                }
+                start += run;
+            }
+            if rows_matching > 0 && best.map_or(true, |(r, n, _)| (rows_matching, per_row) > (r, n))
+            {
+                best = Some((rows_matching, per_row, candidate));
+            }
+        }
+        best.map(|(_, _, candidate)| Self::Byte(candidate))
+    }
+}
+
+impl From<u8> for DsvDelimiter {
+    fn from(b: u8) -> Self {
+        Self::Byte(b)
+    }
+}
+
+impl From<[u8; 2]> for DsvDelimiter {
+    fn from(bytes: [u8; 2]) -> Self {
+        Self::TwoBytes(bytes)
+    }
+}
+
+impl From<&str> for DsvDelimiter {
+    fn from(s: &str) -> Self {
+        Self::String(s.into())
+    }
+}
+
 /// Configuration for DSV parsing.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvConfig {
-    /// Field delimiter (default: b',')
-    pub delimiter: u8,
+    /// Field delimiter (default: `,`)
+    pub delimiter: DsvDelimiter,
     /// Quote character (default: b'"')
     pub quote_char: u8,
     /// Record delimiter (default: b'\n')
     pub newline: u8,
+    /// Emit unquoted numbers and booleans as JSON numbers and booleans when
+    /// converting to JSON (default: false)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub infer_types: bool,
 }
 
 impl Default for DsvConfig {
     fn default() -> Self {
         Self {
-            delimiter: b',',
+            delimiter: DsvDelimiter::Byte(b','),
             quote_char: b'"',
             newline: b'\n',
+            infer_types: false,
         }
     }
 }
@@ -34,7 +180,7 @@ impl DsvConfig {
     /// Create a TSV configuration (tab-separated).
     pub fn tsv() -> Self {
         Self {
-            delimiter: b'\t',
+            delimiter: DsvDelimiter::Byte(b'\t'),
             ..Self::default()
         }
     }
@@ -42,20 +188,67 @@ impl DsvConfig {
     /// Create a PSV configuration (pipe-separated).
     pub fn psv() -> Self {
         Self {
-            delimiter: b'|',
+            delimiter: DsvDelimiter::Byte(b'|'),
             ..Self::default()
         }
     }
 
-    /// Set the field delimiter.
-    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
-        self.delimiter = delimiter;
+    /// Set the field delimiter: a byte, a byte pair or a string.
+    ///
+    /// A one-byte pair or string is stored as [`DsvDelimiter::Byte`].
+    pub fn with_delimiter(mut self, delimiter: impl Into<DsvDelimiter>) -> Self {
+        let delimiter = delimiter.into();
+        self.delimiter = match delimiter.single_byte() {
+            Some(byte) => DsvDelimiter::Byte(byte),
+            None => delimiter,
+        };
         self
     }
 
+    /// The field delimiter's bytes.
+    pub fn delimiter_bytes(&self) -> &[u8] {
+        self.delimiter.as_bytes()
+    }
+
+    /// The field delimiter, if it is exactly one byte long.
+    pub(crate) fn single_byte_delimiter(&self) -> Option<u8> {
+        self.delimiter.single_byte()
+    }
+
     /// Set the quote character.
     pub fn with_quote_char(mut self, quote_char: u8) -> Self {
         self.quote_char = quote_char;
         self
     }
+
+    /// Set whether JSON conversion infers numbers and booleans.
+    pub fn with_infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_delimiter() {
+        let detect = |input: &[u8]| DsvDelimiter::detect(input).and_then(|d| d.single_byte());
+
+        assert_eq!(detect(b"a,b,c\n1,2,3\n"), Some(b','));
+        assert_eq!(detect(b"a;b\r\n1,5;2,5\r\n3,0;4,0\r\n"), Some(b';'));
+        assert_eq!(detect(b"a|b|c\n1|2|3"), Some(b'|'));
+        // Quoted delimiters and newlines do not count
+        assert_eq!(detect(b"a\tb\n\"x,y,\nz\"\t1\n"), Some(b'\t'));
+        // Consistency beats raw frequency
+        assert_eq!(detect(b"a,b|c\nd,e,,,,|f\ng,h|i\n"), Some(b'|'));
+        assert_eq!(detect(b"single column\n\nrows\n"), None);
+        assert_eq!(detect(b""), None);
+
+        // A row cut off at the sample limit is ignored
+        let mut input = b"a;b\n".repeat(1023);
+        input.extend_from_slice(b",,,,,,,,");
+        assert_eq!(detect(&input), Some(b';'));
+    }
 }
@@ -1,5 +1,9 @@
 //! Cursor and navigation for DSV data.
 
+use alloc::borrow::Cow;
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+
 use super::index::DsvIndex;
 
 /// Lightweight cursor for navigating DSV data.
@@ -50,7 +54,15 @@ impl<'a> DsvCursor<'a> {
         // Find position of next marker
         if let Some(next_pos) = self.index.markers_select1(current_rank) {
             if next_pos < self.text.len() {
-                self.position = next_pos + 1; // Move past the delimiter
+                // Move past the newline or the (possibly multi-byte) delimiter
+                let is_newline =
+                    self.index.newlines_rank1(next_pos + 1) > self.index.newlines_rank1(next_pos);
+                let width = if is_newline {
+                    1
+                } else {
+                    self.index.delimiter_len()
+                };
+                self.position = (next_pos + width).min(self.text.len());
                 return !self.at_end();
             }
         }
@@ -129,6 +141,103 @@ impl<'a> DsvCursor<'a> {
         core::str::from_utf8(self.current_field())
     }
 
+    /// Raw field `col` (0-indexed) of the row containing the cursor, or
+    /// `None` if the row has fewer fields.
+    pub fn get_field(&self, col: usize) -> Option<&'a [u8]> {
+        let rank = self.index.newlines_rank1(self.position);
+        let row_start = match rank {
+            0 => 0,
+            _ => self.index.newlines_select1(rank - 1)? + 1,
+        };
+        if row_start >= self.text.len() {
+            return None;
+        }
+        DsvRow::from_cursor(DsvCursor {
+            position: row_start,
+            ..*self
+        })
+        .get(col)
+    }
+
+    /// Field `col` of the current row, with quotes decoded, as UTF-8.
+    fn field_text(&self, col: usize) -> Option<Cow<'a, str>> {
+        match unquote_field(self.get_field(col)?, b'"') {
+            Cow::Borrowed(field) => core::str::from_utf8(field).ok().map(Cow::Borrowed),
+            Cow::Owned(field) => String::from_utf8(field).ok().map(Cow::Owned),
+        }
+    }
+
+    /// Field `col` of the current row parsed as an `i64`.
+    ///
+    /// Quoted fields are decoded first. Returns `None` if the field is
+    /// missing or is not an integer.
+    pub fn get_as_i64(&self, col: usize) -> Option<i64> {
+        self.field_text(col)?.parse().ok()
+    }
+
+    /// Field `col` of the current row parsed as an `f64`.
+    ///
+    /// Besides decimal and exponent forms, `inf`, `infinity` and `nan` are
+    /// accepted in any case, with an optional sign.
+    pub fn get_as_f64(&self, col: usize) -> Option<f64> {
+        self.field_text(col)?.parse().ok()
+    }
+
+    /// Field `col` of the current row as a boolean: `true`/`1`/`yes` or
+    /// `false`/`0`/`no`, in any case.
+    pub fn get_as_bool(&self, col: usize) -> Option<bool> {
+        parse_bool(&self.field_text(col)?)
+    }
+
+    /// Guess the type of column `col_idx` from up to `sample_rows` rows,
+    /// starting at the cursor's row.
+    ///
+    /// The result is the first of `Int`, `Float` and `Bool` that every
+    /// non-empty sampled cell parses as (see [`get_as_i64`](Self::get_as_i64)
+    /// and friends), or `String` if none fits or no cell is non-empty. Move
+    /// the cursor past a header row before sampling.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{Dsv, InferredType};
+    ///
+    /// let dsv = Dsv::parse(b"id,price,ok\n1,2.5,yes\n2,,no\n3,4,no\n");
+    /// let mut cursor = dsv.cursor();
+    /// assert_eq!(cursor.infer_column_type(0, 10), InferredType::String);
+    /// cursor.goto_row(1);
+    /// assert_eq!(cursor.infer_column_type(0, 10), InferredType::Int);
+    /// assert_eq!(cursor.infer_column_type(1, 10), InferredType::Float);
+    /// assert_eq!(cursor.infer_column_type(2, 10), InferredType::Bool);
+    /// ```
+    pub fn infer_column_type(&self, col_idx: usize, sample_rows: usize) -> InferredType {
+        let (mut cells, mut ints, mut floats, mut bools) = (0, 0, 0, 0);
+        let mut row = *self;
+        for i in 0..sample_rows {
+            if i > 0 && !row.next_row() {
+                break;
+            }
+            let Some(text) = row.field_text(col_idx) else {
+                continue;
+            };
+            if text.is_empty() {
+                continue;
+            }
+            cells += 1;
+            ints += usize::from(text.parse::<i64>().is_ok());
+            floats += usize::from(text.parse::<f64>().is_ok());
+            bools += usize::from(parse_bool(&text).is_some());
+        }
+
+        match cells {
+            0 => InferredType::String,
+            _ if ints == cells => InferredType::Int,
+            _ if floats == cells => InferredType::Float,
+            _ if bools == cells => InferredType::Bool,
+            _ => InferredType::String,
+        }
+    }
+
     /// Check if the current byte is a newline marker.
     fn at_newline(&self) -> bool {
         if self.position == 0 || self.position > self.text.len() {
@@ -142,6 +251,32 @@ impl<'a> DsvCursor<'a> {
     }
 }
 
+/// Column type guessed by [`DsvCursor::infer_column_type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InferredType {
+    /// Every sampled cell is an `i64`
+    Int,
+    /// Every sampled cell is an `f64`
+    Float,
+    /// Every sampled cell is a boolean
+    Bool,
+    /// Anything else
+    String,
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    ["true", "1", "yes"]
+        .iter()
+        .any(|t| text.eq_ignore_ascii_case(t))
+        .then_some(true)
+        .or_else(|| {
+            ["false", "0", "no"]
+                .iter()
+                .any(|f| text.eq_ignore_ascii_case(f))
+                .then_some(false)
+        })
+}
+
 /// A single row in DSV data.
 #[derive(Clone, Copy, Debug)]
 pub struct DsvRow<'a> {
@@ -308,6 +443,42 @@ impl<'a> Iterator for DsvFields<'a> {
     }
 }
 
+/// Decode a raw field: strip the surrounding quotes and undouble embedded
+/// quotes (`"a ""b"""` → `a "b"`).
+///
+/// Fields from [`DsvRow::fields`] and [`DsvCursor::current_field`] are the
+/// raw bytes between delimiters. Unquoted fields are returned borrowed.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::dsv::unquote_field;
+///
+/// assert_eq!(&*unquote_field(b"\"say \"\"hi\"\"\"", b'"'), b"say \"hi\"");
+/// assert_eq!(&*unquote_field(b"plain", b'"'), b"plain");
+/// ```
+pub fn unquote_field(field: &[u8], quote_char: u8) -> Cow<'_, [u8]> {
+    let [first, inner @ .., last] = field else {
+        return Cow::Borrowed(field);
+    };
+    if *first != quote_char || *last != quote_char {
+        return Cow::Borrowed(field);
+    }
+    if !inner.contains(&quote_char) {
+        return Cow::Borrowed(inner);
+    }
+    let mut out = Vec::with_capacity(inner.len());
+    let mut bytes = inner.iter();
+    while let Some(&b) = bytes.next() {
+        out.push(b);
+        if b == quote_char {
+            // Skip the second quote of a doubled pair
+            bytes.next();
+        }
+    }
+    Cow::Owned(out)
+}
+
 /// Strip surrounding quotes from a field if present.
 #[allow(dead_code)] // STYLE-0005: helper kept for future use
 pub fn strip_quotes(field: &[u8]) -> &[u8] {
@@ -444,4 +615,53 @@ mod tests {
         assert_eq!(strip_quotes(b"\"\""), b"");
         assert_eq!(strip_quotes(b"\""), b"\"");
     }
+
+    #[test]
+    fn test_typed_accessors() {
+        let csv = b"42,-1.5e3,YES,\"7\",x\n-0,INF,no,,nan\n";
+        let index = build_index(csv, &DsvConfig::default());
+        let mut cursor = DsvCursor::new(csv, &index);
+
+        // Any position in the row addresses the same fields
+        assert!(cursor.next_field());
+        assert_eq!(cursor.get_field(0), Some(&b"42"[..]));
+        assert_eq!(cursor.get_as_i64(0), Some(42));
+        assert_eq!(cursor.get_as_i64(1), None);
+        assert_eq!(cursor.get_as_f64(1), Some(-1500.0));
+        assert_eq!(cursor.get_as_bool(2), Some(true));
+        assert_eq!(cursor.get_as_i64(3), Some(7));
+        assert_eq!(cursor.get_as_f64(4), None);
+        assert_eq!(cursor.get_field(5), None);
+
+        assert!(cursor.goto_row(1));
+        assert_eq!(cursor.get_as_i64(0), Some(0));
+        assert_eq!(cursor.get_as_f64(1), Some(f64::INFINITY));
+        assert_eq!(cursor.get_as_bool(2), Some(false));
+        assert_eq!(cursor.get_as_i64(3), None);
+        assert!(cursor.get_as_f64(4).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_infer_column_type() {
+        let csv = b"n,f,b,s,e\n1,1,true,1,\n2,2.5,0,b,\n,3,no,,\n";
+        let index = build_index(csv, &DsvConfig::default());
+        let mut cursor = DsvCursor::new(csv, &index);
+        assert_eq!(cursor.infer_column_type(0, 4), InferredType::String);
+
+        assert!(cursor.goto_row(1));
+        let types: Vec<_> = (0..5).map(|col| cursor.infer_column_type(col, 4)).collect();
+        assert_eq!(
+            types,
+            [
+                InferredType::Int,
+                InferredType::Float,
+                InferredType::Bool,
+                InferredType::String,
+                InferredType::String,
+            ]
+        );
+        // Only the sampled rows count
+        assert_eq!(cursor.infer_column_type(1, 1), InferredType::Int);
+        assert_eq!(cursor.infer_column_type(6, 4), InferredType::String);
+    }
 }
@@ -0,0 +1,162 @@
+//! Dropping the data rows of a DSV input whose key was already seen.
+
+#[cfg(not(test))]
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(test)]
+use std::borrow::Cow;
+
+use indexmap::IndexSet;
+
+use super::build_index;
+use super::config::DsvConfig;
+use super::cursor::{unquote_field, DsvCursor};
+use super::filter::copy_row;
+use super::index::DsvIndex;
+
+impl DsvIndex {
+    /// The header row of `input` followed by the first data row for each
+    /// distinct value of column `key_col`, in input order.
+    ///
+    /// Keys compare after quotes are decoded, and a missing cell counts as
+    /// empty. Every distinct key is held in a set until the end; for input
+    /// already sorted by the key, [`deduplicate_sorted`](Self::deduplicate_sorted)
+    /// needs only the previous one. Rows are copied from the input verbatim,
+    /// as [`filter`](Self::filter) does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let csv = b"id,v\nb,1\na,2\n\"b\",3\na,4\nc,5\n";
+    /// assert_eq!(
+    ///     DsvIndex::deduplicate(csv, 0, DsvConfig::csv()),
+    ///     b"id,v\nb,1\na,2\nc,5\n"
+    /// );
+    /// ```
+    pub fn deduplicate(input: &[u8], key_col: usize, config: DsvConfig) -> Vec<u8> {
+        let index = build_index(input, &config);
+        let mut seen = IndexSet::new();
+        dedup_rows(input, &index, key_col, &config, |key| seen.insert(key))
+    }
+
+    /// [`deduplicate`](Self::deduplicate) for input whose data rows are
+    /// sorted, or at least grouped, by column `key_col`.
+    ///
+    /// A row is kept when its key differs from the previous row's, so only
+    /// that one key is held. On ungrouped input a key that returns after
+    /// another is kept again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let tsv = b"k\tv\na\t1\na\t2\nb\t3\nb\t4\nb\t5\n";
+    /// assert_eq!(
+    ///     DsvIndex::deduplicate_sorted(tsv, 0, DsvConfig::tsv()),
+    ///     b"k\tv\na\t1\nb\t3\n"
+    /// );
+    /// ```
+    pub fn deduplicate_sorted(input: &[u8], key_col: usize, config: DsvConfig) -> Vec<u8> {
+        let index = build_index(input, &config);
+        let mut previous: Option<Cow<'_, [u8]>> = None;
+        dedup_rows(input, &index, key_col, &config, |key| {
+            let new = previous.as_ref() != Some(&key);
+            previous = Some(key);
+            new
+        })
+    }
+}
+
+/// The header row of `input` and the data rows for which `keep` returns
+/// true when passed the row's decoded key.
+fn dedup_rows<'a>(
+    input: &'a [u8],
+    index: &'a DsvIndex,
+    key_col: usize,
+    config: &DsvConfig,
+    mut keep: impl FnMut(Cow<'a, [u8]>) -> bool,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut cursor = DsvCursor::new(input, index);
+    if cursor.at_end() {
+        return out;
+    }
+    copy_row(&mut out, input, index, cursor.position(), config.newline);
+    while cursor.next_row() {
+        let field = cursor.get_field(key_col).unwrap_or_default();
+        if keep(unquote_field(field, config.quote_char)) {
+            copy_row(&mut out, input, index, cursor.position(), config.newline);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each key `k0..k{keys}` three times, interleaved or grouped.
+    fn triplicated(keys: usize, grouped: bool) -> Vec<u8> {
+        let mut csv = b"key,copy,note\n".to_vec();
+        let rows: Vec<(usize, usize)> = if grouped {
+            (0..keys)
+                .flat_map(|k| (0..3).map(move |c| (k, c)))
+                .collect()
+        } else {
+            (0..3)
+                .flat_map(|c| (0..keys).map(move |k| (k, c)))
+                .collect()
+        };
+        for (k, copy) in rows {
+            csv.extend_from_slice(format!("k{k},{copy},\"n, {k}\"\n").as_bytes());
+        }
+        csv
+    }
+
+    #[test]
+    fn test_deduplicate_keeps_first_row_per_key() {
+        let csv = triplicated(50, false);
+        let out = DsvIndex::deduplicate(&csv, 0, DsvConfig::csv());
+        let lines: Vec<&str> = core::str::from_utf8(&out).unwrap().lines().collect();
+        assert_eq!(lines.len(), 51);
+        assert_eq!(lines[0], "key,copy,note");
+        for (k, line) in lines[1..].iter().enumerate() {
+            // First copy of each key, fields in their original order
+            assert_eq!(*line, format!("k{k},0,\"n, {k}\""));
+        }
+    }
+
+    #[test]
+    fn test_deduplicate_sorted_matches_on_grouped_input() {
+        let csv = triplicated(50, true);
+        let sorted = DsvIndex::deduplicate_sorted(&csv, 0, DsvConfig::csv());
+        assert_eq!(sorted, DsvIndex::deduplicate(&csv, 0, DsvConfig::csv()));
+        assert_eq!(core::str::from_utf8(&sorted).unwrap().lines().count(), 51);
+
+        // On ungrouped input every change of key starts a new run
+        let csv = triplicated(50, false);
+        let out = DsvIndex::deduplicate_sorted(&csv, 0, DsvConfig::csv());
+        assert_eq!(out, csv);
+    }
+
+    #[test]
+    fn test_deduplicate_quoted_and_missing_keys() {
+        let csv = b"a,b\n\"x\",1\nx,2\n\n3\n,4\n\"y\"\"\",5\n\"y\"\"\",6";
+        assert_eq!(
+            DsvIndex::deduplicate(csv, 0, DsvConfig::csv()),
+            b"a,b\n\"x\",1\n\n3\n\"y\"\"\",5\n".as_slice()
+        );
+        // A missing second column is an empty key, like an empty cell
+        assert_eq!(
+            DsvIndex::deduplicate(csv, 1, DsvConfig::csv()),
+            b"a,b\n\"x\",1\nx,2\n\n,4\n\"y\"\"\",5\n\"y\"\"\",6\n".as_slice()
+        );
+        assert_eq!(DsvIndex::deduplicate(b"", 0, DsvConfig::csv()), b"");
+        assert_eq!(
+            DsvIndex::deduplicate_sorted(b"h", 0, DsvConfig::csv()),
+            b"h\n"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Selecting the rows of a DSV input that match a predicate.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use super::build_index;
+use super::config::DsvConfig;
+use super::cursor::{unquote_field, DsvCursor};
+use super::index::DsvIndex;
+
+impl DsvIndex {
+    /// The header row of `input` followed by every data row for which
+    /// `predicate` returns `true`.
+    ///
+    /// The predicate sees a cursor at the start of the row, so
+    /// [`DsvCursor::get_field`] and [`get_as_i64`](DsvCursor::get_as_i64)
+    /// read that row's columns. Rows are copied from the input as they are,
+    /// quoting included, in one pass; a last row without a record delimiter
+    /// gets one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let csv = b"name,age\nAda,36\nAlan,41\n\"Hopper, Grace\",85\n";
+    /// let over_40 = DsvIndex::filter(csv, DsvConfig::csv(), |row| {
+    ///     row.get_as_i64(1).is_some_and(|age| age > 40)
+    /// });
+    /// assert_eq!(over_40, b"name,age\nAlan,41\n\"Hopper, Grace\",85\n");
+    /// ```
+    pub fn filter<P>(input: &[u8], config: DsvConfig, predicate: P) -> Vec<u8>
+    where
+        P: Fn(&DsvCursor<'_>) -> bool,
+    {
+        let index = build_index(input, &config);
+        let mut out = Vec::new();
+        let mut cursor = DsvCursor::new(input, &index);
+        if cursor.at_end() {
+            return out;
+        }
+
+        copy_row(&mut out, input, &index, cursor.position(), config.newline);
+        while cursor.next_row() {
+            if predicate(&cursor) {
+                copy_row(&mut out, input, &index, cursor.position(), config.newline);
+            }
+        }
+        out
+    }
+
+    /// The header row of `input` followed by every data row whose column
+    /// `col` equals `value`, like [`filter`](Self::filter).
+    ///
+    /// Fields compare after quotes are decoded; rows with fewer columns
+    /// never match.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let tsv = b"city\tcountry\nLyon\tFR\nOsaka\tJP\nNice\t\"FR\"\n";
+    /// let french = DsvIndex::filter_by_column_value(tsv, 1, "FR", DsvConfig::tsv());
+    /// assert_eq!(french, b"city\tcountry\nLyon\tFR\nNice\t\"FR\"\n");
+    /// ```
+    pub fn filter_by_column_value(
+        input: &[u8],
+        col: usize,
+        value: &str,
+        config: DsvConfig,
+    ) -> Vec<u8> {
+        let quote_char = config.quote_char;
+        Self::filter(input, config, |row| {
+            row.get_field(col)
+                .is_some_and(|field| *unquote_field(field, quote_char) == *value.as_bytes())
+        })
+    }
+}
+
+/// Append the row starting at `start`, with its record delimiter, to `out`.
+pub(super) fn copy_row(
+    out: &mut Vec<u8>,
+    input: &[u8],
+    index: &DsvIndex,
+    start: usize,
+    newline: u8,
+) {
+    let end = index
+        .newlines_select1(index.newlines_rank1(start))
+        .map_or(input.len(), |newline| newline + 1);
+    out.extend_from_slice(&input[start..end]);
+    if end == input.len() && input.last() != Some(&newline) {
+        out.push(newline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_by_threshold() {
+        let mut csv = b"id,score,label\n".to_vec();
+        for id in 0..100_000 {
+            csv.extend_from_slice(format!("{id},{},\"row {id}, quoted\"\n", id % 1000).as_bytes());
+        }
+
+        let filtered = DsvIndex::filter(&csv, DsvConfig::csv(), |row| {
+            row.get_as_i64(1).is_some_and(|score| score >= 900)
+        });
+        let lines: Vec<&str> = core::str::from_utf8(&filtered).unwrap().lines().collect();
+        assert_eq!(lines[0], "id,score,label");
+        assert_eq!(lines.len(), 1 + 10_000);
+        assert_eq!(lines[1], "900,900,\"row 900, quoted\"");
+        assert_eq!(lines[10_000], "99999,999,\"row 99999, quoted\"");
+    }
+
+    #[test]
+    fn test_filter_copies_rows_verbatim() {
+        // A quoted record delimiter stays inside its row, and the last row
+        // gets a delimiter
+        let csv = b"a,b\n1,\"x\ny\"\n2,z\n3,\"w\"";
+        let all = DsvIndex::filter(csv, DsvConfig::csv(), |_| true);
+        assert_eq!(all, b"a,b\n1,\"x\ny\"\n2,z\n3,\"w\"\n");
+        let odd = DsvIndex::filter(csv, DsvConfig::csv(), |row| {
+            row.get_as_i64(0).is_some_and(|n| n % 2 == 1)
+        });
+        assert_eq!(odd, b"a,b\n1,\"x\ny\"\n3,\"w\"\n");
+    }
+
+    #[test]
+    fn test_filter_by_column_value() {
+        let csv = b"k,v\na,1\nb,2\n\"a\",3\nc\n";
+        assert_eq!(
+            DsvIndex::filter_by_column_value(csv, 0, "a", DsvConfig::csv()),
+            b"k,v\na,1\n\"a\",3\n"
+        );
+        assert_eq!(
+            DsvIndex::filter_by_column_value(csv, 1, "", DsvConfig::csv()),
+            b"k,v\n"
+        );
+    }
+
+    #[test]
+    fn test_filter_header_only_and_empty() {
+        assert_eq!(DsvIndex::filter(b"", DsvConfig::csv(), |_| true), b"");
+        assert_eq!(
+            DsvIndex::filter(b"a,b", DsvConfig::csv(), |_| true),
+            b"a,b\n"
+        );
+        assert_eq!(
+            DsvIndex::filter(b"a,b\n1,2\n", DsvConfig::csv(), |_| false),
+            b"a,b\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Semi-index for DSV data.
 
+use super::config::DsvConfig;
 use super::index_lightweight::DsvIndexLightweight;
 
 #[cfg(feature = "serde")]
@@ -20,12 +21,49 @@ use serde::{Deserialize, Serialize};
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DsvIndex {
     inner: DsvIndexLightweight,
+    /// Bytes in the field delimiter; a field starts this far past its marker
+    #[cfg_attr(feature = "serde", serde(default = "default_delimiter_len"))]
+    delimiter_len: usize,
+    /// Whether JSON conversion infers numbers and booleans
+    #[cfg_attr(feature = "serde", serde(default))]
+    infer_types: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_delimiter_len() -> usize {
+    1
 }
 
 impl DsvIndex {
     /// Create a new DsvIndex from lightweight index.
     pub fn new_lightweight(inner: DsvIndexLightweight) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            delimiter_len: 1,
+            infer_types: false,
+        }
+    }
+
+    /// Record the settings from `config` that are needed after the build:
+    /// the delimiter length (markers sit on a delimiter's first byte) and
+    /// [`DsvConfig::infer_types`].
+    pub(crate) fn with_config(mut self, config: &DsvConfig) -> Self {
+        self.delimiter_len = config.delimiter_bytes().len();
+        self.infer_types = config.infer_types;
+        self
+    }
+
+    /// Length in bytes of the field delimiter this index was built with.
+    #[inline]
+    pub fn delimiter_len(&self) -> usize {
+        self.delimiter_len
+    }
+
+    /// Whether [`to_json_array`](Self::to_json_array) infers numbers and
+    /// booleans, as set by [`DsvConfig::infer_types`] at build time.
+    #[inline]
+    pub fn infer_types(&self) -> bool {
+        self.infer_types
     }
 
     /// Get reference to inner lightweight index.
@@ -0,0 +1,257 @@
+//! SQL-style joins between two DSV inputs on a key column.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use indexmap::IndexMap;
+
+use super::build_index;
+use super::config::DsvConfig;
+use super::cursor::{unquote_field, DsvRows};
+use super::index::DsvIndex;
+use super::writer::write_record;
+
+/// One input's rows, as decoded fields.
+struct Table {
+    rows: Vec<Vec<Vec<u8>>>,
+    key_col: usize,
+}
+
+impl Table {
+    fn parse(input: &[u8], key_col: usize, config: &DsvConfig) -> Self {
+        let index = build_index(input, config);
+        let rows = DsvRows::new(input, &index)
+            .map(|row| {
+                row.fields()
+                    .map(|field| unquote_field(field, config.quote_char).into_owned())
+                    .collect()
+            })
+            .collect();
+        Self { rows, key_col }
+    }
+
+    fn key(&self, row: usize) -> Option<&[u8]> {
+        self.rows[row].get(self.key_col).map(Vec::as_slice)
+    }
+
+    /// Data row indices (the header is row 0) grouped by key.
+    fn key_groups(&self) -> IndexMap<&[u8], Vec<usize>> {
+        let mut groups: IndexMap<&[u8], Vec<usize>> = IndexMap::new();
+        for row in 1..self.rows.len() {
+            if let Some(key) = self.key(row) {
+                groups.entry(key).or_default().push(row);
+            }
+        }
+        groups
+    }
+}
+
+impl DsvIndex {
+    /// Inner join of two DSV inputs whose first rows are headers.
+    ///
+    /// Each output row is a left row followed by the columns of a right row
+    /// whose `right_key_col` cell equals the left row's `left_key_col` cell,
+    /// minus the right key column. The output starts with the combined header
+    /// and is written with `left_config`. Rows come out in left order, and a
+    /// left row matching several right rows repeats once per match, in right
+    /// order. Keys compare after quotes are decoded. The smaller input is
+    /// hashed and the larger probed against it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let users = b"id,name\n1,Ada\n2,Alan\n";
+    /// let orders = b"user_id\tamount\n2\t10\n1\t5\n2\t7\n";
+    /// let joined = DsvIndex::inner_join(users, 0, orders, 0, DsvConfig::csv(), DsvConfig::tsv());
+    /// assert_eq!(joined, b"id,name,amount\n1,Ada,5\n2,Alan,10\n2,Alan,7\n");
+    /// ```
+    pub fn inner_join(
+        left_input: &[u8],
+        left_key_col: usize,
+        right_input: &[u8],
+        right_key_col: usize,
+        left_config: DsvConfig,
+        right_config: DsvConfig,
+    ) -> Vec<u8> {
+        join(
+            left_input,
+            left_key_col,
+            right_input,
+            right_key_col,
+            &left_config,
+            &right_config,
+            false,
+        )
+    }
+
+    /// Left join of two DSV inputs whose first rows are headers.
+    ///
+    /// Like [`inner_join`](Self::inner_join), but a left row with no
+    /// matching right row is kept, with empty cells for the right columns.
+    pub fn left_join(
+        left_input: &[u8],
+        left_key_col: usize,
+        right_input: &[u8],
+        right_key_col: usize,
+        left_config: DsvConfig,
+        right_config: DsvConfig,
+    ) -> Vec<u8> {
+        join(
+            left_input,
+            left_key_col,
+            right_input,
+            right_key_col,
+            &left_config,
+            &right_config,
+            true,
+        )
+    }
+}
+
+fn join(
+    left_input: &[u8],
+    left_key_col: usize,
+    right_input: &[u8],
+    right_key_col: usize,
+    left_config: &DsvConfig,
+    right_config: &DsvConfig,
+    keep_unmatched: bool,
+) -> Vec<u8> {
+    let left = Table::parse(left_input, left_key_col, left_config);
+    let right = Table::parse(right_input, right_key_col, right_config);
+
+    // (left row, right row) pairs, from hashing whichever side is smaller
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    if left.rows.len() <= right.rows.len() {
+        let groups = left.key_groups();
+        for r in 1..right.rows.len() {
+            if let Some(matches) = right.key(r).and_then(|key| groups.get(key)) {
+                pairs.extend(matches.iter().map(|&l| (l, r)));
+            }
+        }
+    } else {
+        let groups = right.key_groups();
+        for l in 1..left.rows.len() {
+            if let Some(matches) = left.key(l).and_then(|key| groups.get(key)) {
+                pairs.extend(matches.iter().map(|&r| (l, r)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+
+    let right_width = right.rows.first().map_or(0, |header| {
+        header.len() - usize::from(right_key_col < header.len())
+    });
+    let right_cells = |row: Option<usize>| -> Vec<&[u8]> {
+        let cells: Vec<&[u8]> = row.map_or_else(Vec::new, |row| {
+            right.rows[row]
+                .iter()
+                .enumerate()
+                .filter(|&(col, _)| col != right_key_col)
+                .map(|(_, cell)| cell.as_slice())
+                .collect()
+        });
+        let padding = right_width.saturating_sub(cells.len());
+        cells
+            .into_iter()
+            .chain((0..padding).map(|_| &b""[..]))
+            .collect()
+    };
+
+    let mut out = Vec::new();
+    let mut emit = |l: usize, r: Option<usize>| {
+        let mut record: Vec<&[u8]> = left.rows[l].iter().map(Vec::as_slice).collect();
+        record.extend(right_cells(r));
+        write_record(&mut out, &record, left_config);
+    };
+
+    if left.rows.is_empty() {
+        return Vec::new();
+    }
+    emit(0, (!right.rows.is_empty()).then_some(0));
+
+    let mut pairs = pairs.into_iter().peekable();
+    for l in 1..left.rows.len() {
+        let mut matched = false;
+        while let Some(&(_, r)) = pairs.peek().filter(|&&(pl, _)| pl == l) {
+            emit(l, Some(r));
+            pairs.next();
+            matched = true;
+        }
+        if !matched && keep_unmatched {
+            emit(l, None);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USERS: &[u8] = b"id,name\n1,Ada\n2,Alan\n3,\"Grace, RADM\"\n";
+    const ORDERS: &[u8] = b"order,user_id,amount\n100,2,10\n101,1,5\n102,2,7\n103,9,1\n";
+
+    fn join_rows(joined: &[u8]) -> Vec<&str> {
+        core::str::from_utf8(joined).unwrap().lines().collect()
+    }
+
+    #[test]
+    fn test_inner_join_users_orders() {
+        let joined = DsvIndex::inner_join(USERS, 0, ORDERS, 1, DsvConfig::csv(), DsvConfig::csv());
+        assert_eq!(
+            join_rows(&joined),
+            [
+                "id,name,order,amount",
+                "1,Ada,101,5",
+                "2,Alan,100,10",
+                "2,Alan,102,7",
+            ]
+        );
+        // With the larger input on the left, the right side is hashed instead
+        let joined = DsvIndex::inner_join(ORDERS, 1, USERS, 0, DsvConfig::csv(), DsvConfig::csv());
+        assert_eq!(
+            join_rows(&joined),
+            [
+                "order,user_id,amount,name",
+                "100,2,10,Alan",
+                "101,1,5,Ada",
+                "102,2,7,Alan",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_left_join_keeps_unmatched_rows() {
+        let joined = DsvIndex::left_join(USERS, 0, ORDERS, 1, DsvConfig::csv(), DsvConfig::csv());
+        assert_eq!(
+            join_rows(&joined),
+            [
+                "id,name,order,amount",
+                "1,Ada,101,5",
+                "2,Alan,100,10",
+                "2,Alan,102,7",
+                "3,\"Grace, RADM\",,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_decodes_keys_and_reencodes_fields() {
+        let left = b"k\tv\n\"a\"\tx,y\n";
+        let right = b"k,w\na,\"p\tq\"\n";
+        let joined = DsvIndex::inner_join(left, 0, right, 0, DsvConfig::tsv(), DsvConfig::csv());
+        assert_eq!(joined, b"k\tv\tw\na\tx,y\t\"p\tq\"\n");
+    }
+
+    #[test]
+    fn test_join_empty_inputs() {
+        assert!(
+            DsvIndex::inner_join(b"", 0, ORDERS, 1, DsvConfig::csv(), DsvConfig::csv()).is_empty()
+        );
+        let joined = DsvIndex::left_join(USERS, 0, b"", 0, DsvConfig::csv(), DsvConfig::csv());
+        assert_eq!(join_rows(&joined).len(), 4);
+    }
+}
@@ -0,0 +1,273 @@
+//! Converting DSV with a header row to JSON records.
+
+#[cfg(not(test))]
+use alloc::{format, string::String, vec::Vec};
+
+use super::cursor::{unquote_field, DsvRows};
+use super::index::DsvIndex;
+use crate::jq::OwnedValue;
+
+impl DsvIndex {
+    /// Convert DSV whose first row is a header to a JSON array of objects.
+    ///
+    /// `input` must be the text this index was built from. Each row after
+    /// the header becomes one object whose keys are the header's field
+    /// names, in header order. Cells are decoded with [`unquote_field`]
+    /// using `"` as the quote character and become strings; a row too short
+    /// for the header has `null` for the missing cells, and cells past the
+    /// header's width are dropped. A name that repeats in the header gets
+    /// a suffix on each later use, `_2`, `_3` and so on, skipping names the
+    /// header already has, so no column is lost: `a,a,a` gives the keys
+    /// `a`, `a_2` and `a_3`.
+    ///
+    /// If the index was built with [`DsvConfig::infer_types`](super::DsvConfig::infer_types),
+    /// unquoted cells written as JSON numbers become numbers, and `true`
+    /// and `false` become booleans. Other spellings stay strings, so
+    /// identifiers such as `007` or `+1` keep their text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{build_index, DsvConfig};
+    ///
+    /// let csv = b"id,name,ok\n1,Ada,true\n\"2\",Alan\n";
+    /// let index = build_index(csv, &DsvConfig::csv().with_infer_types(true));
+    /// assert_eq!(
+    ///     index.to_json_array(csv),
+    ///     br#"[{"id":1,"name":"Ada","ok":true},{"id":"2","name":"Alan","ok":null}]"#
+    /// );
+    /// ```
+    pub fn to_json_array(&self, input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::from(*b"[");
+        for (i, record) in self.json_records(input).enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            out.extend_from_slice(&record.to_json_bytes());
+        }
+        out.push(b']');
+        out
+    }
+
+    /// Write the records of [`to_json_array`](Self::to_json_array) to
+    /// `writer` as NDJSON, one compact object per line.
+    ///
+    /// Each record is written as soon as its row is converted, so the whole
+    /// output is never held in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    #[cfg(feature = "std")]
+    pub fn to_json_streaming(
+        &self,
+        input: &[u8],
+        writer: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        for record in self.json_records(input) {
+            let mut line = record.to_json_bytes();
+            line.push(b'\n');
+            writer.write_all(&line)?;
+        }
+        Ok(())
+    }
+
+    /// The rows after the header, as objects keyed by the header's names.
+    fn json_records<'a>(&'a self, input: &'a [u8]) -> impl Iterator<Item = OwnedValue> + 'a {
+        let mut rows = DsvRows::new(input, self);
+        let header = rows
+            .next()
+            .map(|row| unique_names(row.fields().map(cell_text)))
+            .unwrap_or_default();
+        let infer_types = self.infer_types();
+
+        rows.map(move |row| {
+            let mut cells = row.fields();
+            OwnedValue::Object(
+                header
+                    .iter()
+                    .map(|name| {
+                        let value = cells
+                            .next()
+                            .map_or(OwnedValue::Null, |cell| cell_value(cell, infer_types));
+                        (name.clone(), value)
+                    })
+                    .collect(),
+            )
+        })
+    }
+}
+
+/// Header names with repeats renamed `name_2`, `name_3`, ..., skipping
+/// names already taken.
+fn unique_names(names: impl Iterator<Item = String>) -> Vec<String> {
+    let names: Vec<String> = names.collect();
+    let mut unique: Vec<String> = Vec::with_capacity(names.len());
+    for (i, name) in names.iter().enumerate() {
+        if !unique.contains(name) {
+            unique.push(name.clone());
+            continue;
+        }
+        let mut suffix = 2;
+        let mut renamed = format!("{name}_{suffix}");
+        while unique.contains(&renamed) || names[i..].contains(&renamed) {
+            suffix += 1;
+            renamed = format!("{name}_{suffix}");
+        }
+        unique.push(renamed);
+    }
+    unique
+}
+
+/// A raw cell decoded to text; invalid UTF-8 is replaced.
+fn cell_text(raw: &[u8]) -> String {
+    String::from_utf8_lossy(&unquote_field(raw, b'"')).into_owned()
+}
+
+fn cell_value(raw: &[u8], infer_types: bool) -> OwnedValue {
+    let text = cell_text(raw);
+    // Quoting a cell marks it as text, so only bare cells are inferred
+    if infer_types && raw.first() != Some(&b'"') {
+        if let Some(value) = infer_value(&text) {
+            return value;
+        }
+    }
+    OwnedValue::String(text)
+}
+
+fn infer_value(text: &str) -> Option<OwnedValue> {
+    match text {
+        "true" => Some(OwnedValue::Bool(true)),
+        "false" => Some(OwnedValue::Bool(false)),
+        _ if !is_json_number(text.as_bytes()) => None,
+        _ => text.parse().map(OwnedValue::Int).ok().or_else(|| {
+            text.parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(OwnedValue::Float)
+        }),
+    }
+}
+
+/// Whether `text` is a number in JSON's grammar: no leading zeros, `+`,
+/// bare `.` or `inf`, which Rust's parsers would accept.
+fn is_json_number(text: &[u8]) -> bool {
+    let digits = |s: &[u8]| s.iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut rest = text.strip_prefix(b"-").unwrap_or(text);
+    match digits(rest) {
+        0 => return false,
+        n if n > 1 && rest[0] == b'0' => return false,
+        n => rest = &rest[n..],
+    }
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        match digits(fraction) {
+            0 => return false,
+            n => rest = &fraction[n..],
+        }
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or_else(|| rest.strip_prefix(b"E")) {
+        let exponent = exponent
+            .strip_prefix(b"+")
+            .or_else(|| exponent.strip_prefix(b"-"))
+            .unwrap_or(exponent);
+        match digits(exponent) {
+            0 => return false,
+            n => rest = &exponent[n..],
+        }
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsv::{build_index, DsvConfig};
+    use crate::json::validate::validate;
+
+    #[test]
+    fn test_to_json_array() {
+        let csv = b"name,note\nAda,\"says \"\"hi\"\", \"\"bye\"\"\"\n\"Smith, J\",\"two\nlines\"\n";
+        let index = build_index(csv, &DsvConfig::csv());
+        let json = index.to_json_array(csv);
+        validate(&json).unwrap();
+        assert_eq!(
+            json,
+            br#"[{"name":"Ada","note":"says \"hi\", \"bye\""},{"name":"Smith, J","note":"two\nlines"}]"#
+        );
+
+        // Without inference everything is a string
+        let index = build_index(b"a,b\n1,true\n", &DsvConfig::csv());
+        assert_eq!(
+            index.to_json_array(b"a,b\n1,true\n"),
+            br#"[{"a":"1","b":"true"}]"#
+        );
+
+        // Short rows, long rows, a header only, and no input at all
+        let csv = b"a,b\n1\n2,3,4\n";
+        let index = build_index(csv, &DsvConfig::csv());
+        assert_eq!(
+            index.to_json_array(csv),
+            br#"[{"a":"1","b":null},{"a":"2","b":"3"}]"#
+        );
+        let index = build_index(b"a,b\n", &DsvConfig::csv());
+        assert_eq!(index.to_json_array(b"a,b\n"), b"[]");
+        let index = build_index(b"", &DsvConfig::csv());
+        assert_eq!(index.to_json_array(b""), b"[]");
+    }
+
+    #[test]
+    fn test_to_json_array_infer_types() {
+        let tsv = b"i\tf\tb\ts\n-7\t2.5e3\tfalse\tTrue\n\"7\"\t1e999\ttrue\tnan\n";
+        let config = DsvConfig::tsv().with_infer_types(true);
+        let index = build_index(tsv, &config);
+        let json = index.to_json_array(tsv);
+        validate(&json).unwrap();
+        assert_eq!(
+            json,
+            br#"[{"i":-7,"f":2500,"b":false,"s":"True"},{"i":"7","f":"1e999","b":true,"s":"nan"}]"#
+        );
+
+        // Only JSON number spellings are numbers; leading zeros are kept
+        let csv = b"a,b,c,d,e,f,g,h
+007,0,-0.5,+1,.5,5.,00.1,1E-2
+";
+        let index = build_index(csv, &DsvConfig::csv().with_infer_types(true));
+        assert_eq!(
+            index.to_json_array(csv),
+            br#"[{"a":"007","b":0,"c":-0.5,"d":"+1","e":".5","f":"5.","g":"00.1","h":0.01}]"#
+        );
+    }
+
+    #[test]
+    fn test_to_json_array_duplicate_headers() {
+        let csv = b"a,b,a,a_2,a
+1,2,3,4,5
+";
+        let index = build_index(csv, &DsvConfig::csv());
+        assert_eq!(
+            index.to_json_array(csv),
+            br#"[{"a":"1","b":"2","a_3":"3","a_2":"4","a_4":"5"}]"#
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_to_json_streaming() {
+        let csv = b"x,y\n1,a\n2,\"b,c\"\n";
+        let config = DsvConfig::csv().with_infer_types(true);
+        let index = build_index(csv, &config);
+        let mut out = Vec::new();
+        index.to_json_streaming(csv, &mut out).unwrap();
+        assert_eq!(out, b"{\"x\":1,\"y\":\"a\"}\n{\"x\":2,\"y\":\"b,c\"}\n");
+        for line in out.split(|&b| b == b'\n').filter(|line| !line.is_empty()) {
+            validate(line).unwrap();
+        }
+
+        // Each line is one element of the array form
+        let array = index.to_json_array(csv);
+        let lines: Vec<&[u8]> = out
+            .split(|&b| b == b'\n')
+            .filter(|l| !l.is_empty())
+            .collect();
+        assert_eq!(array, [&b"["[..], &lines.join(&b','), b"]"].concat());
+    }
+}
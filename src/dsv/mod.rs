@@ -47,14 +47,24 @@
 
 mod config;
 mod cursor;
+mod dedup;
+mod filter;
 mod index;
 mod index_lightweight;
+mod join;
+mod json;
 mod parser;
 pub mod simd;
+mod sort;
+mod stats;
+mod writer;
 
-pub use config::DsvConfig;
-pub use cursor::{DsvCursor, DsvFields, DsvRow, DsvRows};
+pub use config::{DsvConfig, DsvDelimiter};
+pub use cursor::{unquote_field, DsvCursor, DsvFields, DsvRow, DsvRows, InferredType};
 pub use index::DsvIndex;
+pub use stats::ColumnStats;
+#[cfg(feature = "std")]
+pub use writer::DsvWriter;
 
 // Use SIMD parser by default on supported platforms
 #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
@@ -266,4 +276,87 @@ mod tests {
         assert_eq!(row.get(2), Some(b"NYC".as_slice()));
         assert_eq!(row.get(3), None);
     }
+
+    fn rows_of(text: &[u8], config: &DsvConfig) -> Vec<Vec<Vec<u8>>> {
+        let index = build_index(text, config);
+        // The SIMD dispatcher falls back to the scalar builder
+        let scalar = build_index_scalar(text, config);
+        assert_eq!(scalar.marker_count(), index.marker_count());
+        DsvRef::new(text, &index)
+            .rows()
+            .map(|row| row.fields().map(<[u8]>::to_vec).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_byte_delimiters() {
+        let config = DsvConfig::default().with_delimiter([b'|', b'|']);
+        assert_eq!(
+            rows_of(b"a||b||c\n1|2||\"x||y\"||\n", &config),
+            [
+                vec![&b"a"[..], b"b", b"c"],
+                vec![&b"1|2"[..], b"\"x||y\"", b""],
+            ]
+        );
+        // Matches are taken left to right without overlap
+        assert_eq!(rows_of(b"a|||b\n", &config), [vec![&b"a"[..], b"|b"]]);
+
+        let config = DsvConfig::default().with_delimiter("<sep>");
+        assert_eq!(
+            rows_of(b"x<sep>y<sep\n<sep>z\n", &config),
+            [vec![&b"x"[..], b"y<sep"], vec![&b""[..], b"z"]]
+        );
+
+        // A one-byte string is the same as a byte
+        let config = DsvConfig::default().with_delimiter(";");
+        assert_eq!(config.delimiter, DsvDelimiter::Byte(b';'));
+        assert_eq!(rows_of(b"a;b\n", &config), [vec![&b"a"[..], b"b"]]);
+
+        // Long runs between delimiters, quotes and newlines are skipped a
+        // vector at a time
+        let config = DsvConfig::default().with_delimiter("::");
+        let long = "x".repeat(100);
+        let quoted = format!("\"{long}::{long}\n\"");
+        let text = format!("{long}::{quoted}::\n:{long}:\n");
+        assert_eq!(
+            rows_of(text.as_bytes(), &config),
+            [
+                vec![long.as_bytes(), quoted.as_bytes(), b""],
+                vec![format!(":{long}:").as_bytes()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_byte_delimiter_round_trip() {
+        let config = DsvConfig::default().with_delimiter("\t\t");
+        let records = [["a\tb", "c"], ["", "d\t"], ["e\"f", "g\nh"]];
+        let text = DsvIndex::to_dsv_writer(
+            &records
+                .iter()
+                .map(|[x, y]| {
+                    crate::jq::OwnedValue::object_from([
+                        ("x".to_string(), (*x).into()),
+                        ("y".to_string(), (*y).into()),
+                    ])
+                })
+                .collect::<Vec<_>>(),
+            config.clone(),
+        );
+        let dsv = Dsv::parse_with_config(&text, &config);
+        let parsed: Vec<Vec<Vec<u8>>> = dsv
+            .rows()
+            .skip(1)
+            .map(|row| {
+                row.fields()
+                    .map(|f| unquote_field(f, b'"').into_owned())
+                    .collect()
+            })
+            .collect();
+        let expected: Vec<Vec<Vec<u8>>> = records
+            .iter()
+            .map(|r| r.iter().map(|f| f.as_bytes().to_vec()).collect())
+            .collect();
+        assert_eq!(parsed, expected);
+    }
 }
@@ -9,6 +9,7 @@ use super::config::DsvConfig;
 use super::index::DsvIndex;
 use super::index_lightweight::DsvIndexLightweight;
 use crate::json::BitWriter;
+use crate::util::simd::scan::{find_first_of_2, find_first_of_4};
 
 /// Build a DsvIndex from input text.
 ///
@@ -22,6 +23,9 @@ pub fn build_index(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
         return DsvIndex::new_lightweight(DsvIndexLightweight::new(vec![], vec![], 0));
     }
+    let Some(delimiter) = config.single_byte_delimiter() else {
+        return build_index_multi_byte(text, config);
+    };
 
     let num_words = text.len().div_ceil(64);
 
@@ -34,7 +38,7 @@ pub fn build_index(text: &[u8], config: &DsvConfig) -> DsvIndex {
     // Process byte by byte (simpler than chunked approach, still fast)
     for &byte in text {
         let is_quote = byte == config.quote_char;
-        let is_delimiter = byte == config.delimiter;
+        let is_delimiter = byte == delimiter;
         let is_newline = byte == config.newline;
 
         // Toggle quote state on quote character
@@ -65,7 +69,73 @@ pub fn build_index(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
+}
+
+/// Build a DsvIndex for a delimiter longer (or shorter) than one byte.
+///
+/// Outside quotes, each occurrence of the delimiter is matched left to right
+/// and marked at its first byte; the index records the delimiter's length so
+/// cursors step over all of it. Runs of bytes that cannot start a match,
+/// toggle quoting or end a row are skipped a vector at a time.
+fn build_index_multi_byte(text: &[u8], config: &DsvConfig) -> DsvIndex {
+    let delimiter = config.delimiter_bytes();
+    let quote = config.quote_char;
+    // An empty delimiter never matches, so only quotes and newlines stop
+    // the scan
+    let first = delimiter.first().copied().unwrap_or(quote);
+    let num_words = text.len().div_ceil(64);
+
+    let mut markers_writer = BitWriter::with_capacity(num_words);
+    let mut newlines_writer = BitWriter::with_capacity(num_words);
+    let mut in_quote = false;
+
+    let mut i = 0;
+    while i < text.len() {
+        // Inside quotes only the closing quote matters
+        let rest = &text[i..];
+        let skip = if in_quote {
+            find_first_of_2(rest, quote, quote)
+        } else {
+            find_first_of_4(rest, first, quote, config.newline, quote)
+        }
+        .unwrap_or(rest.len());
+        markers_writer.write_zeros(skip);
+        newlines_writer.write_zeros(skip);
+        i += skip;
+        if i == text.len() {
+            break;
+        }
+
+        let byte = text[i];
+        if byte == quote {
+            in_quote = !in_quote;
+        }
+
+        if !in_quote && !delimiter.is_empty() && text[i..].starts_with(delimiter) {
+            markers_writer.write_1();
+            markers_writer.write_zeros(delimiter.len() - 1);
+            newlines_writer.write_zeros(delimiter.len());
+            i += delimiter.len();
+            continue;
+        }
+
+        let is_newline = !in_quote && byte == config.newline;
+        if is_newline {
+            markers_writer.write_1();
+            newlines_writer.write_1();
+        } else {
+            markers_writer.write_0();
+            newlines_writer.write_0();
+        }
+        i += 1;
+    }
+
+    let markers_words = markers_writer.finish();
+    let newlines_words = newlines_writer.finish();
+
+    let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 
 /// Build a DsvIndex using word-at-a-time processing (faster for large files).
@@ -77,6 +147,9 @@ pub fn build_index_fast(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
         return DsvIndex::new_lightweight(DsvIndexLightweight::new(vec![], vec![], 0));
     }
+    let Some(delimiter) = config.single_byte_delimiter() else {
+        return build_index_multi_byte(text, config);
+    };
 
     let num_words = text.len().div_ceil(64);
 
@@ -93,7 +166,7 @@ pub fn build_index_fast(text: &[u8], config: &DsvConfig) -> DsvIndex {
 
         for (i, &byte) in chunk.iter().enumerate() {
             let is_quote = byte == config.quote_char;
-            let is_delimiter = byte == config.delimiter;
+            let is_delimiter = byte == delimiter;
             let is_newline = byte == config.newline;
 
             if is_quote {
@@ -118,7 +191,7 @@ pub fn build_index_fast(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 
 #[cfg(test)]
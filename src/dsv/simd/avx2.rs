@@ -15,6 +15,9 @@ use super::super::index_lightweight::DsvIndexLightweight;
 use crate::json::BitWriter;
 
 /// Build a DsvIndex using AVX2 SIMD acceleration.
+///
+/// Panics if the delimiter is longer than one byte; [`super::build_index_simd`]
+/// sends those to the scalar builder.
 #[cfg(target_arch = "x86_64")]
 pub fn build_index_simd(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
@@ -22,20 +25,24 @@ pub fn build_index_simd(text: &[u8], config: &DsvConfig) -> DsvIndex {
         return DsvIndex::new_lightweight(empty);
     }
 
+    let delimiter = config
+        .single_byte_delimiter()
+        .expect("multi-byte delimiters are dispatched to the scalar builder");
+
     // SAFETY: Caller verified AVX2 is available via runtime detection
-    unsafe { build_index_avx2(text, config) }
+    unsafe { build_index_avx2(text, delimiter, config) }
 }
 
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
-unsafe fn build_index_avx2(text: &[u8], config: &DsvConfig) -> DsvIndex {
+unsafe fn build_index_avx2(text: &[u8], delimiter: u8, config: &DsvConfig) -> DsvIndex {
     let num_words = text.len().div_ceil(64);
     let mut markers_writer = BitWriter::with_capacity(num_words);
     let mut newlines_writer = BitWriter::with_capacity(num_words);
 
     let mut in_quote = false;
 
-    let delimiter = config.delimiter as i8;
+    let delimiter = delimiter as i8;
     let quote_char = config.quote_char as i8;
     let newline = config.newline as i8;
 
@@ -81,7 +88,7 @@ unsafe fn build_index_avx2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 
 /// Process a 64-byte chunk and return (markers, newlines, new_in_quote).
@@ -22,19 +22,26 @@ use crate::json::BitWriter;
 ///
 /// This is the fastest path on x86_64 processors that support both AVX2 and BMI2.
 /// Uses PDEP for quote state masking instead of prefix_xor.
+///
+/// Panics if the delimiter is longer than one byte; [`super::build_index_simd`]
+/// sends those to the scalar builder.
 #[cfg(target_arch = "x86_64")]
 pub fn build_index_simd(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
         return DsvIndex::new_lightweight(DsvIndexLightweight::new(vec![], vec![], 0));
     }
 
+    let delimiter = config
+        .single_byte_delimiter()
+        .expect("multi-byte delimiters are dispatched to the scalar builder");
+
     // SAFETY: Caller verified AVX2 and BMI2 are available via runtime detection
-    unsafe { build_index_bmi2(text, config) }
+    unsafe { build_index_bmi2(text, delimiter, config) }
 }
 
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2", enable = "bmi2")]
-unsafe fn build_index_bmi2(text: &[u8], config: &DsvConfig) -> DsvIndex {
+unsafe fn build_index_bmi2(text: &[u8], delimiter: u8, config: &DsvConfig) -> DsvIndex {
     let num_words = text.len().div_ceil(64);
     let mut markers_writer = BitWriter::with_capacity(num_words);
     let mut newlines_writer = BitWriter::with_capacity(num_words);
@@ -42,7 +49,7 @@ unsafe fn build_index_bmi2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     // Track quote state across chunks using carry
     let mut qq_carry: u64 = 0;
 
-    let delimiter = config.delimiter as i8;
+    let delimiter = delimiter as i8;
     let quote_char = config.quote_char as i8;
     let newline = config.newline as i8;
 
@@ -89,7 +96,7 @@ unsafe fn build_index_bmi2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 /// Alternating bit pattern used by toggle64: 0101...
 const ODDS_MASK: u64 = 0x5555_5555_5555_5555;
@@ -45,17 +45,26 @@ pub mod bmi2;
 #[cfg(target_arch = "x86_64")]
 pub mod sse2;
 
+/// Build a DSV index using the fastest available SIMD implementation.
+///
+/// The SIMD builders match single-byte delimiters; multi-byte delimiters
+/// (see [`DsvDelimiter`](super::DsvDelimiter)) use the scalar builder.
+pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
+    if config.single_byte_delimiter().is_none() {
+        return super::parser::build_index(text, config);
+    }
+    dispatch(text, config)
+}
+
 // ============================================================================
-// ARM exports with runtime dispatch (SVE2 > NEON)
+// ARM dispatch (SVE2 > NEON)
 // ============================================================================
 
-/// Build a DSV index using the fastest available SIMD implementation.
-///
 /// Runtime dispatch order (fastest to slowest):
 /// 1. SVE2-BITPERM + NEON: Uses BDEP for quote masking (~10x faster)
 /// 2. NEON: Uses prefix_xor for quote masking (fallback)
 #[cfg(all(target_arch = "aarch64", feature = "std"))]
-pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
+fn dispatch(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
     // Check for SVE2-BITPERM (fastest path on ARM)
     if detect_sve2() {
         return sve2::build_index_simd(text, config);
@@ -67,20 +76,18 @@ pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvInd
 
 // Without std feature, default to NEON (can't do runtime detection)
 #[cfg(all(target_arch = "aarch64", not(feature = "std")))]
-pub use neon::build_index_simd;
+use neon::build_index_simd as dispatch;
 
 // ============================================================================
-// x86_64 exports with runtime dispatch
+// x86_64 dispatch
 // ============================================================================
 
-/// Build a DSV index using the fastest available SIMD implementation.
-///
 /// Runtime dispatch order (fastest to slowest):
 /// 1. BMI2 + AVX2: Uses PDEP for quote masking (~10x faster)
 /// 2. AVX2: Uses prefix_xor for quote masking
 /// 3. SSE2: Fallback for older CPUs
 #[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
-pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
+fn dispatch(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
     // Check for BMI2 + AVX2 (fastest path)
     if detect_bmi2() && detect_avx2() {
         return bmi2::build_index_simd(text, config);
@@ -97,16 +104,14 @@ pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvInd
 
 // Without std feature, default to SSE2 (can't do runtime detection)
 #[cfg(all(target_arch = "x86_64", not(any(test, feature = "std"))))]
-pub use sse2::build_index_simd;
+use sse2::build_index_simd as dispatch;
 
 // ============================================================================
 // Fallback for other platforms
 // ============================================================================
 
 #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
-pub fn build_index_simd(text: &[u8], config: &super::DsvConfig) -> super::DsvIndex {
-    super::parser::build_index(text, config)
-}
+use super::parser::build_index as dispatch;
 
 // ============================================================================
 // Feature detection (indirection point for the dispatcher fallback-arm tests)
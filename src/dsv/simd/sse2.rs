@@ -15,26 +15,33 @@ use super::super::index_lightweight::DsvIndexLightweight;
 use crate::json::BitWriter;
 
 /// Build a DsvIndex using SSE2 SIMD acceleration.
+///
+/// Panics if the delimiter is longer than one byte; [`super::build_index_simd`]
+/// sends those to the scalar builder.
 #[cfg(target_arch = "x86_64")]
 pub fn build_index_simd(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
         return DsvIndex::new_lightweight(DsvIndexLightweight::new(vec![], vec![], 0));
     }
 
+    let delimiter = config
+        .single_byte_delimiter()
+        .expect("multi-byte delimiters are dispatched to the scalar builder");
+
     // SAFETY: SSE2 is mandatory on x86_64
-    unsafe { build_index_sse2(text, config) }
+    unsafe { build_index_sse2(text, delimiter, config) }
 }
 
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "sse2")]
-unsafe fn build_index_sse2(text: &[u8], config: &DsvConfig) -> DsvIndex {
+unsafe fn build_index_sse2(text: &[u8], delimiter: u8, config: &DsvConfig) -> DsvIndex {
     let num_words = text.len().div_ceil(64);
     let mut markers_writer = BitWriter::with_capacity(num_words);
     let mut newlines_writer = BitWriter::with_capacity(num_words);
 
     let mut in_quote = false;
 
-    let delimiter = config.delimiter as i8;
+    let delimiter = delimiter as i8;
     let quote_char = config.quote_char as i8;
     let newline = config.newline as i8;
 
@@ -80,7 +87,7 @@ unsafe fn build_index_sse2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 
 /// Process a 64-byte chunk and return (markers, newlines, new_in_quote).
@@ -37,17 +37,24 @@ use crate::util::simd::sve2::toggle64_sve2;
 /// # Safety
 ///
 /// Caller must verify SVE2-BITPERM is available via runtime detection.
+///
+/// Panics if the delimiter is longer than one byte; [`super::build_index_simd`]
+/// sends those to the scalar builder.
 pub fn build_index_simd(text: &[u8], config: &DsvConfig) -> DsvIndex {
     if text.is_empty() {
         return DsvIndex::new_lightweight(DsvIndexLightweight::new(vec![], vec![], 0));
     }
 
+    let delimiter = config
+        .single_byte_delimiter()
+        .expect("multi-byte delimiters are dispatched to the scalar builder");
+
     // SAFETY: Caller verified SVE2-BITPERM is available via runtime detection
-    unsafe { build_index_sve2(text, config) }
+    unsafe { build_index_sve2(text, delimiter, config) }
 }
 
 #[target_feature(enable = "neon", enable = "sve2-bitperm")]
-unsafe fn build_index_sve2(text: &[u8], config: &DsvConfig) -> DsvIndex {
+unsafe fn build_index_sve2(text: &[u8], delimiter: u8, config: &DsvConfig) -> DsvIndex {
     let num_words = text.len().div_ceil(64);
     let mut markers_writer = BitWriter::with_capacity(num_words);
     let mut newlines_writer = BitWriter::with_capacity(num_words);
@@ -55,7 +62,6 @@ unsafe fn build_index_sve2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     // Track quote state across chunks using carry (same as BMI2 implementation)
     let mut qq_carry: u64 = 0;
 
-    let delimiter = config.delimiter;
     let quote_char = config.quote_char;
     let newline = config.newline;
 
@@ -102,7 +108,7 @@ unsafe fn build_index_sve2(text: &[u8], config: &DsvConfig) -> DsvIndex {
     let newlines_words = newlines_writer.finish();
 
     let lightweight = DsvIndexLightweight::new(markers_words, newlines_words, text.len());
-    DsvIndex::new_lightweight(lightweight)
+    DsvIndex::new_lightweight(lightweight).with_config(config)
 }
 
 /// Process a 64-byte chunk using NEON for character matching and SVE2 BDEP for quote masking.
@@ -0,0 +1,197 @@
+//! Reordering the data rows of a DSV input by key columns.
+
+#[cfg(not(test))]
+use alloc::{borrow::Cow, vec::Vec};
+use core::cmp::Ordering;
+#[cfg(test)]
+use std::borrow::Cow;
+
+use super::build_index;
+use super::config::DsvConfig;
+use super::cursor::{unquote_field, DsvCursor};
+use super::filter::copy_row;
+use super::index::DsvIndex;
+
+/// One key cell, with its numeric value if the whole cell is a number.
+struct SortKey<'a> {
+    text: Cow<'a, [u8]>,
+    number: Option<f64>,
+}
+
+impl<'a> SortKey<'a> {
+    fn new(text: Cow<'a, [u8]>) -> Self {
+        let number = core::str::from_utf8(&text)
+            .ok()
+            .and_then(|text| text.trim().parse::<f64>().ok())
+            .filter(|number| number.is_finite());
+        Self { text, number }
+    }
+
+    /// Natural order: numbers by value before any other text, which
+    /// compares byte by byte.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.number, other.number) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.text.cmp(&other.text),
+        }
+    }
+}
+
+impl DsvIndex {
+    /// The header row of `input` followed by its data rows sorted by column
+    /// `key_col`.
+    ///
+    /// See [`sort_by_columns`](Self::sort_by_columns) for how cells compare.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    ///
+    /// let csv = b"name,size\nb,10\na,9\nc,100\n";
+    /// assert_eq!(
+    ///     DsvIndex::sort(csv, 1, true, DsvConfig::csv()),
+    ///     b"name,size\na,9\nb,10\nc,100\n"
+    /// );
+    /// assert_eq!(
+    ///     DsvIndex::sort(csv, 0, false, DsvConfig::csv()),
+    ///     b"name,size\nc,100\nb,10\na,9\n"
+    /// );
+    /// ```
+    pub fn sort(input: &[u8], key_col: usize, ascending: bool, config: DsvConfig) -> Vec<u8> {
+        Self::sort_by_columns(input, &[(key_col, ascending)], config)
+    }
+
+    /// The header row of `input` followed by its data rows sorted by several
+    /// `(column, ascending)` keys, the first deciding and each later one
+    /// breaking ties in the ones before.
+    ///
+    /// Cells compare after quotes are decoded, in natural order: cells that
+    /// parse as finite numbers compare by value and sort before all other
+    /// cells, which compare byte by byte. A missing cell counts as empty.
+    /// The sort is stable, so rows with equal keys keep their input order.
+    ///
+    /// Only the keys and the row ranges are sorted; each row is then copied
+    /// from the input once, verbatim, as [`filter`](Self::filter) does.
+    pub fn sort_by_columns(input: &[u8], keys: &[(usize, bool)], config: DsvConfig) -> Vec<u8> {
+        let index = build_index(input, &config);
+        let mut out = Vec::new();
+        let mut cursor = DsvCursor::new(input, &index);
+        if cursor.at_end() {
+            return out;
+        }
+        copy_row(&mut out, input, &index, cursor.position(), config.newline);
+
+        let mut rows: Vec<(Vec<SortKey<'_>>, usize)> = Vec::new();
+        while cursor.next_row() {
+            let row_keys = keys
+                .iter()
+                .map(|&(col, _)| {
+                    let field = cursor.get_field(col).unwrap_or_default();
+                    SortKey::new(unquote_field(field, config.quote_char))
+                })
+                .collect();
+            rows.push((row_keys, cursor.position()));
+        }
+
+        rows.sort_by(|(a, _), (b, _)| {
+            keys.iter()
+                .zip(a.iter().zip(b))
+                .map(|(&(_, ascending), (a, b))| {
+                    let order = a.cmp(b);
+                    if ascending {
+                        order
+                    } else {
+                        order.reverse()
+                    }
+                })
+                .find(|order| order.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        for (_, start) in rows {
+            copy_row(&mut out, input, &index, start, config.newline);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(output: &[u8], col: usize) -> Vec<String> {
+        core::str::from_utf8(output)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(col).unwrap_or("").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_numeric_column() {
+        let csv = b"id,score\na,10\nb,-2.5\nc,9\nd,1e2\ne,0\n";
+        let sorted = DsvIndex::sort(csv, 1, true, DsvConfig::csv());
+        assert_eq!(column(&sorted, 1), ["-2.5", "0", "9", "10", "1e2"]);
+        let sorted = DsvIndex::sort(csv, 1, false, DsvConfig::csv());
+        assert_eq!(column(&sorted, 1), ["1e2", "10", "9", "0", "-2.5"]);
+    }
+
+    #[test]
+    fn test_sort_string_column() {
+        let csv = b"name,n\npear,1\n\"apple\",2\nBanana,3\nfig,4\n";
+        let sorted = DsvIndex::sort(csv, 0, true, DsvConfig::csv());
+        assert_eq!(
+            sorted,
+            b"name,n\nBanana,3\n\"apple\",2\nfig,4\npear,1\n".as_slice()
+        );
+    }
+
+    #[test]
+    fn test_sort_mixed_and_missing() {
+        // Numbers first, then text; a missing cell is empty text
+        let csv = b"k\nx\n10\n\n2\nn/a\n";
+        let sorted = DsvIndex::sort(csv, 0, true, DsvConfig::csv());
+        assert_eq!(sorted, b"k\n2\n10\n\nn/a\nx\n".as_slice());
+    }
+
+    #[test]
+    fn test_sort_by_columns_is_stable() {
+        let csv = b"team,points,name\nred,3,a\nblue,5,b\nred,5,c\nblue,5,d\nred,3,e\n";
+        let sorted = DsvIndex::sort_by_columns(csv, &[(1, false), (0, true)], DsvConfig::csv());
+        assert_eq!(column(&sorted, 2), ["b", "d", "c", "a", "e"]);
+    }
+
+    #[test]
+    fn test_sort_copies_rows_verbatim() {
+        let tsv = b"k\tv\n2\t\"multi\nline\"\n1\tlast";
+        let sorted = DsvIndex::sort(tsv, 0, true, DsvConfig::tsv());
+        assert_eq!(sorted, b"k\tv\n1\tlast\n2\t\"multi\nline\"\n".as_slice());
+        assert_eq!(DsvIndex::sort(b"", 0, true, DsvConfig::csv()), b"");
+        assert_eq!(DsvIndex::sort(b"h", 3, true, DsvConfig::csv()), b"h\n");
+    }
+
+    #[test]
+    fn test_sort_large() {
+        let mut csv = b"id,bucket\n".to_vec();
+        for id in 0..50_000u64 {
+            let bucket = (id * 7919) % 1000;
+            csv.extend_from_slice(format!("{id},{bucket}\n").as_bytes());
+        }
+        let sorted = DsvIndex::sort(&csv, 1, true, DsvConfig::csv());
+        let buckets: Vec<u64> = column(&sorted, 1)
+            .iter()
+            .map(|b| b.parse().unwrap())
+            .collect();
+        assert_eq!(buckets.len(), 50_000);
+        assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]));
+        // Stable: ids within a bucket stay ascending
+        let ids: Vec<u64> = column(&sorted, 0)
+            .iter()
+            .map(|b| b.parse().unwrap())
+            .collect();
+        assert!((1..ids.len()).all(|i| buckets[i - 1] != buckets[i] || ids[i - 1] < ids[i]));
+    }
+}
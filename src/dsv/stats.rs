@@ -0,0 +1,214 @@
+//! Per-column statistics for data profiling.
+
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+
+use super::cursor::{unquote_field, DsvRows};
+use super::index::DsvIndex;
+
+/// Summary of the values in one DSV column.
+///
+/// Cells are decoded with [`unquote_field`] using `"` as the quote
+/// character. Every row counts, including a header row if the data has one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStats {
+    /// Rows that have a cell in this column
+    pub count: usize,
+    /// Cells that are empty
+    pub null_count: usize,
+    /// Smallest non-empty cell, by byte order
+    pub min: Option<String>,
+    /// Largest non-empty cell, by byte order
+    pub max: Option<String>,
+    /// Cells that parse as `f64`
+    pub numeric_count: usize,
+    /// Cells that parse as `i64` (a subset of `numeric_count`)
+    pub int_count: usize,
+    /// Non-numeric cells that read as booleans: `true`, `false`, `yes` or
+    /// `no` in any case
+    pub bool_count: usize,
+    /// Smallest numeric cell (NaN if `numeric_count` is 0)
+    pub numeric_min: f64,
+    /// Largest numeric cell (NaN if `numeric_count` is 0)
+    pub numeric_max: f64,
+    /// Mean of the numeric cells (NaN if `numeric_count` is 0)
+    pub numeric_mean: f64,
+}
+
+/// Running totals behind a [`ColumnStats`].
+#[derive(Default)]
+struct Accumulator {
+    count: usize,
+    null_count: usize,
+    min: Option<String>,
+    max: Option<String>,
+    numeric_count: usize,
+    int_count: usize,
+    bool_count: usize,
+    numeric_min: f64,
+    numeric_max: f64,
+    numeric_sum: f64,
+}
+
+impl Accumulator {
+    fn add(&mut self, raw: &[u8]) {
+        self.count += 1;
+        let cell = unquote_field(raw, b'"');
+        if cell.is_empty() {
+            self.null_count += 1;
+            return;
+        }
+
+        let text = String::from_utf8_lossy(&cell);
+        if self.min.as_deref().map_or(true, |min| *text < *min) {
+            self.min = Some(text.clone().into_owned());
+        }
+        if self.max.as_deref().map_or(true, |max| *text > *max) {
+            self.max = Some(text.clone().into_owned());
+        }
+
+        if let Ok(n) = text.parse::<f64>() {
+            if self.numeric_count == 0 {
+                self.numeric_min = n;
+                self.numeric_max = n;
+            } else {
+                self.numeric_min = self.numeric_min.min(n);
+                self.numeric_max = self.numeric_max.max(n);
+            }
+            self.numeric_count += 1;
+            self.numeric_sum += n;
+            self.int_count += usize::from(text.parse::<i64>().is_ok());
+        } else if ["true", "false", "yes", "no"]
+            .iter()
+            .any(|word| text.eq_ignore_ascii_case(word))
+        {
+            self.bool_count += 1;
+        }
+    }
+
+    fn finish(self) -> ColumnStats {
+        let numeric = |value: f64| {
+            if self.numeric_count == 0 {
+                f64::NAN
+            } else {
+                value
+            }
+        };
+        ColumnStats {
+            count: self.count,
+            null_count: self.null_count,
+            min: self.min,
+            max: self.max,
+            numeric_count: self.numeric_count,
+            int_count: self.int_count,
+            bool_count: self.bool_count,
+            numeric_min: numeric(self.numeric_min),
+            numeric_max: numeric(self.numeric_max),
+            numeric_mean: numeric(self.numeric_sum / self.numeric_count as f64),
+        }
+    }
+}
+
+impl DsvIndex {
+    /// Statistics for column `col` (0-indexed) in one pass over the rows.
+    ///
+    /// `input` must be the text this index was built from. Rows too short to
+    /// have the column are not counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{build_index, DsvConfig};
+    ///
+    /// let csv = b"a,1\nb,\nc,2.5\n";
+    /// let index = build_index(csv, &DsvConfig::csv());
+    /// let stats = index.column_stats(1, csv);
+    /// assert_eq!((stats.count, stats.null_count, stats.numeric_count), (3, 1, 2));
+    /// assert_eq!(stats.numeric_mean, 1.75);
+    /// ```
+    pub fn column_stats(&self, col: usize, input: &[u8]) -> ColumnStats {
+        let mut stats = Accumulator::default();
+        for row in DsvRows::new(input, self) {
+            if let Some(cell) = row.fields().nth(col) {
+                stats.add(cell);
+            }
+        }
+        stats.finish()
+    }
+
+    /// Statistics for every column in one pass over the rows.
+    ///
+    /// The result has one entry per column of the widest row.
+    pub fn all_column_stats(&self, input: &[u8]) -> Vec<ColumnStats> {
+        let mut columns: Vec<Accumulator> = Vec::new();
+        for row in DsvRows::new(input, self) {
+            for (col, cell) in row.fields().enumerate() {
+                if col == columns.len() {
+                    columns.push(Accumulator::default());
+                }
+                columns[col].add(cell);
+            }
+        }
+        columns.into_iter().map(Accumulator::finish).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsv::{build_index, DsvConfig};
+
+    #[test]
+    fn test_column_stats() {
+        let csv = b"name,score\n\"Smith, J\",10\nadams,-2\n,x\nZed,7\n";
+        let index = build_index(csv, &DsvConfig::csv());
+
+        let names = index.column_stats(0, csv);
+        assert_eq!(names.count, 5);
+        assert_eq!(names.null_count, 1);
+        assert_eq!(names.min.as_deref(), Some("Smith, J"));
+        assert_eq!(names.max.as_deref(), Some("name"));
+        assert_eq!(names.numeric_count, 0);
+        assert!(names.numeric_mean.is_nan());
+
+        let scores = index.column_stats(1, csv);
+        assert_eq!(scores.count, 5);
+        assert_eq!(scores.null_count, 0);
+        assert_eq!(scores.numeric_count, 3);
+        assert_eq!(scores.numeric_min, -2.0);
+        assert_eq!(scores.numeric_max, 10.0);
+        assert_eq!(scores.numeric_mean, 5.0);
+        // Lexicographic, not numeric
+        assert_eq!(scores.min.as_deref(), Some("-2"));
+        assert_eq!(scores.max.as_deref(), Some("x"));
+
+        assert_eq!(index.column_stats(2, csv).count, 0);
+    }
+
+    #[test]
+    fn test_column_stats_type_counts() {
+        let csv = b"1\n2.5\n-3\nTRUE\nno\n0\nyesterday\n\n";
+        let index = build_index(csv, &DsvConfig::csv());
+        let stats = index.column_stats(0, csv);
+        assert_eq!(stats.numeric_count, 4);
+        assert_eq!(stats.int_count, 3);
+        // `0` is counted as a number, not a boolean
+        assert_eq!(stats.bool_count, 2);
+    }
+
+    #[test]
+    fn test_all_column_stats_matches_single_column() {
+        let csv = b"a,b\n1,2,3\n4\n\"5\",6\n";
+        let index = build_index(csv, &DsvConfig::csv());
+        let all = index.all_column_stats(csv);
+        assert_eq!(all.len(), 3);
+        for (col, stats) in all.iter().enumerate() {
+            let single = index.column_stats(col, csv);
+            assert_eq!(stats.count, single.count);
+            assert_eq!(stats.min, single.min);
+            assert_eq!(stats.numeric_count, single.numeric_count);
+        }
+        assert_eq!(all[0].numeric_count, 3);
+        assert_eq!(all[0].numeric_max, 5.0);
+        assert_eq!(all[2].count, 1);
+    }
+}
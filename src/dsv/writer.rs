@@ -0,0 +1,295 @@
+//! Writing records as DSV text.
+//!
+//! Fields are quoted only when they must be: a field containing the
+//! delimiter, the quote character, the record delimiter or `\r` is wrapped in
+//! quotes, with embedded quotes doubled (RFC 4180). Everything else is written
+//! as is, so the output parses back with [`DsvIndex`] to the same fields.
+
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+
+use indexmap::IndexSet;
+
+use super::config::DsvConfig;
+use super::index::DsvIndex;
+use crate::jq::OwnedValue;
+use crate::util::simd::scan::find_first_of_4;
+
+/// Buffered bytes at which [`DsvWriter`] hands its output to the sink.
+#[cfg(feature = "std")]
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// Writes records as DSV to a [`std::io::Write`] sink.
+///
+/// Records are encoded into an internal buffer that is passed on to the sink
+/// in large chunks; call [`finish`](Self::finish) to write out the rest.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::dsv::{DsvConfig, DsvWriter};
+///
+/// let mut writer = DsvWriter::new(Vec::new(), DsvConfig::csv());
+/// writer.write_record(&["name", "note"]).unwrap();
+/// writer.write_record(&["Ada", "says \"hi\", twice"]).unwrap();
+/// let csv = writer.finish().unwrap();
+/// assert_eq!(csv, b"name,note\nAda,\"says \"\"hi\"\", twice\"\n");
+/// ```
+#[cfg(feature = "std")]
+pub struct DsvWriter<W: std::io::Write> {
+    sink: W,
+    config: DsvConfig,
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> DsvWriter<W> {
+    /// Create a writer that encodes records with `config`'s delimiter, quote
+    /// character and record delimiter.
+    pub fn new(sink: W, config: DsvConfig) -> Self {
+        Self {
+            sink,
+            config,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Encode one record, followed by the record delimiter.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from the sink when the buffer is passed on.
+    pub fn write_record(&mut self, fields: &[&str]) -> std::io::Result<()> {
+        write_record(&mut self.buffer, fields, &self.config);
+        if self.buffer.len() >= FLUSH_THRESHOLD {
+            self.sink.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Write out any buffered records, flush the sink and return it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to or flushing the sink.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.sink.write_all(&self.buffer)?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+impl DsvIndex {
+    /// Convert JSON records (objects) to DSV text with a header row.
+    ///
+    /// The header lists every key in the order it is first seen across the
+    /// records. Each record then has one field per header column: strings
+    /// are written as is, numbers and booleans as their JSON text, arrays
+    /// and objects as compact JSON, and `null` or a missing key as an empty
+    /// field. A record that is not an object fills the first column with its
+    /// value and leaves the rest empty. With no keys at all there is no
+    /// header row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::dsv::{DsvConfig, DsvIndex};
+    /// use succinctly::jq::OwnedValue;
+    ///
+    /// let records = [
+    ///     OwnedValue::object_from([("id".to_string(), OwnedValue::Int(1))]),
+    ///     OwnedValue::object_from([("name".to_string(), OwnedValue::string("a\tb"))]),
+    /// ];
+    /// let tsv = DsvIndex::to_dsv_writer(&records, DsvConfig::tsv());
+    /// assert_eq!(tsv, b"id\tname\n1\t\n\t\"a\tb\"\n");
+    /// ```
+    pub fn to_dsv_writer(json_records: &[OwnedValue], config: DsvConfig) -> Vec<u8> {
+        let mut header: IndexSet<&str> = IndexSet::new();
+        for record in json_records {
+            if let OwnedValue::Object(fields) = record {
+                header.extend(fields.keys().map(String::as_str));
+            }
+        }
+
+        let mut out = Vec::new();
+        let columns: Vec<&str> = header.iter().copied().collect();
+        if !columns.is_empty() {
+            write_record(&mut out, &columns, &config);
+        }
+
+        let mut row: Vec<String> = Vec::with_capacity(columns.len().max(1));
+        for record in json_records {
+            row.clear();
+            match record {
+                OwnedValue::Object(fields) => row.extend(
+                    columns
+                        .iter()
+                        .map(|column| fields.get(*column).map(field_text).unwrap_or_default()),
+                ),
+                other => {
+                    row.push(field_text(other));
+                    row.extend(columns.iter().skip(1).map(|_| String::new()));
+                }
+            }
+            let fields: Vec<&str> = row.iter().map(String::as_str).collect();
+            write_record(&mut out, &fields, &config);
+        }
+        out
+    }
+}
+
+/// The text of one JSON value as a DSV field.
+fn field_text(value: &OwnedValue) -> String {
+    match value {
+        OwnedValue::Null => String::new(),
+        OwnedValue::String(s) => s.clone(),
+        other => other.to_json(),
+    }
+}
+
+/// Append `fields` to `out` as one record, quoting fields as needed.
+pub(super) fn write_record<F: AsRef<[u8]>>(out: &mut Vec<u8>, fields: &[F], config: &DsvConfig) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(config.delimiter_bytes());
+        }
+        write_field(out, field.as_ref(), config);
+    }
+    out.push(config.newline);
+}
+
+fn write_field(out: &mut Vec<u8>, field: &[u8], config: &DsvConfig) {
+    // Any byte of a multi-byte delimiter could combine with its neighbours
+    // into a match, so all of them force quotes
+    let needs_quotes = match config.single_byte_delimiter() {
+        Some(delimiter) => {
+            find_first_of_4(field, delimiter, config.quote_char, config.newline, b'\r').is_some()
+        }
+        None => {
+            let delimiter = config.delimiter_bytes();
+            field.iter().any(|&b| {
+                delimiter.contains(&b)
+                    || b == config.quote_char
+                    || b == config.newline
+                    || b == b'\r'
+            })
+        }
+    };
+    if !needs_quotes {
+        out.extend_from_slice(field);
+        return;
+    }
+
+    out.push(config.quote_char);
+    for &b in field {
+        if b == config.quote_char {
+            out.push(b);
+        }
+        out.push(b);
+    }
+    out.push(config.quote_char);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsv::{unquote_field, Dsv};
+
+    /// Parse `text` back into unquoted string fields.
+    fn reparse(text: &[u8], config: &DsvConfig) -> Vec<Vec<String>> {
+        let dsv = Dsv::parse_with_config(text, config);
+        dsv.rows()
+            .map(|row| {
+                row.fields()
+                    .map(|field| {
+                        let field = unquote_field(field, config.quote_char);
+                        String::from_utf8(field.into_owned()).unwrap()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn encode(records: &[&[&str]], config: &DsvConfig) -> Vec<u8> {
+        let mut writer = DsvWriter::new(Vec::new(), config.clone());
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_quotes_only_when_needed() {
+        let csv = encode(
+            &[&["plain", "a,b", "say \"x\"", "", "x\ny", "cr\r"]],
+            &DsvConfig::csv(),
+        );
+        assert_eq!(csv, b"plain,\"a,b\",\"say \"\"x\"\"\",,\"x\ny\",\"cr\r\"\n");
+        // A comma needs no quotes in TSV
+        let tsv = encode(&[&["a,b", "a\tb"]], &DsvConfig::tsv());
+        assert_eq!(tsv, b"a,b\t\"a\tb\"\n");
+    }
+
+    #[test]
+    fn test_round_trip_edge_cases() {
+        let records: &[&[&str]] = &[
+            &["id", "text", "unicode"],
+            &["1", "line one\nline two", "naïve café"],
+            &["2", "\"quoted\"", "日本語, with comma"],
+            &["3", "", "emoji 🎉\ttab"],
+            &["4", "\"", "trailing space "],
+        ];
+        for config in [DsvConfig::csv(), DsvConfig::tsv(), DsvConfig::psv()] {
+            let text = encode(records, &config);
+            let parsed = reparse(&text, &config);
+            assert_eq!(parsed, records, "{:?}", String::from_utf8_lossy(&text));
+        }
+    }
+
+    #[test]
+    fn test_custom_quote_char() {
+        let config = DsvConfig::csv().with_quote_char(b'\'');
+        let text = encode(&[&["it's", "\"as is\""]], &config);
+        assert_eq!(text, b"'it''s',\"as is\"\n");
+        assert_eq!(reparse(&text, &config), [["it's", "\"as is\""]]);
+    }
+
+    #[test]
+    fn test_large_output_flushes_in_chunks() {
+        let field = "x".repeat(1000);
+        let records: Vec<[&str; 2]> = (0..200).map(|_| [field.as_str(), "y"]).collect();
+        let mut writer = DsvWriter::new(Vec::new(), DsvConfig::csv());
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        assert!(!writer.sink.is_empty());
+        let text = writer.finish().unwrap();
+        assert_eq!(reparse(&text, &DsvConfig::csv()).len(), 200);
+    }
+
+    #[test]
+    fn test_json_records() {
+        let records = [
+            OwnedValue::object_from([
+                ("name".to_string(), OwnedValue::string("Ada, Countess")),
+                ("age".to_string(), OwnedValue::Int(36)),
+            ]),
+            OwnedValue::object_from([
+                ("age".to_string(), OwnedValue::Float(1.5)),
+                ("tags".to_string(), vec!["a", "b"].into()),
+                ("name".to_string(), OwnedValue::Null),
+            ]),
+        ];
+        let csv = DsvIndex::to_dsv_writer(&records, DsvConfig::csv());
+        assert_eq!(
+            reparse(&csv, &DsvConfig::csv()),
+            [
+                ["name", "age", "tags"],
+                ["Ada, Countess", "36", ""],
+                ["", "1.5", "[\"a\",\"b\"]"],
+            ]
+        );
+        assert_eq!(DsvIndex::to_dsv_writer(&[], DsvConfig::csv()), b"");
+    }
+}
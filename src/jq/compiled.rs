@@ -0,0 +1,146 @@
+//! Parsed jq queries that can be stored and run against many documents.
+//!
+//! [`query_compiled`] parses a filter once; [`CompiledQuery::run`] then
+//! evaluates it against any number of JSON cursors, such as one per line of
+//! an NDJSON stream, without re-parsing the source.
+
+use super::eval::{eval, EvalSemantics, JqSemantics, QueryResult};
+use super::expr::Expr;
+use super::parser::{parse, ParseError};
+use crate::json::light::JsonCursor;
+
+/// A parsed jq filter, ready to run against JSON documents.
+///
+/// This currently holds the parsed [`Expr`] unchanged; later versions may
+/// rewrite it (for example by folding constant subexpressions) when it is
+/// compiled, without changing how it is run.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::jq::{query_compiled, QueryResult};
+/// use succinctly::json::{JsonIndex, StandardJson};
+///
+/// let query = query_compiled(".id").unwrap();
+/// for (line, expected) in [(&br#"{"id": 1}"#[..], 1), (br#"{"id": 2}"#, 2)] {
+///     let index = JsonIndex::build(line);
+///     match query.run(index.root(line)) {
+///         QueryResult::One(StandardJson::Number(n)) => {
+///             assert_eq!(n.as_i64().unwrap(), expected);
+///         }
+///         other => panic!("unexpected result: {other:?}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledQuery {
+    expr: Expr,
+}
+
+impl CompiledQuery {
+    /// Wrap an already parsed expression.
+    pub fn new(expr: Expr) -> Self {
+        Self { expr }
+    }
+
+    /// The expression this query evaluates.
+    #[inline]
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Unwrap the expression this query evaluates.
+    pub fn into_expr(self) -> Expr {
+        self.expr
+    }
+
+    /// Run the query against a JSON cursor with jq semantics.
+    ///
+    /// Equivalent to [`eval`] with [`JqSemantics`].
+    #[inline]
+    pub fn run<'a, W: Clone + AsRef<[u64]>>(
+        &self,
+        cursor: JsonCursor<'a, W>,
+    ) -> QueryResult<'a, W> {
+        self.run_with::<W, JqSemantics>(cursor)
+    }
+
+    /// Run the query against a JSON cursor with the given semantics.
+    #[inline]
+    pub fn run_with<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+        &self,
+        cursor: JsonCursor<'a, W>,
+    ) -> QueryResult<'a, W> {
+        eval::<W, S>(&self.expr, cursor)
+    }
+}
+
+impl From<Expr> for CompiledQuery {
+    fn from(expr: Expr) -> Self {
+        Self::new(expr)
+    }
+}
+
+/// Parse a jq filter into a [`CompiledQuery`] that can be run repeatedly.
+///
+/// Accepts the same syntax as [`parse`](super::parse).
+pub fn query_compiled(source: &str) -> Result<CompiledQuery, ParseError> {
+    parse(source).map(CompiledQuery::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jq::{OwnedValue, YqSemantics};
+    use crate::json::light::StandardJson;
+    use crate::json::JsonIndex;
+
+    #[test]
+    fn test_run_reuses_query_across_documents() {
+        let query = query_compiled("[.[] | select(. > 1)] | length").unwrap();
+        let lines: [&[u8]; 3] = [b"[1, 2, 3]", b"[]", b"[5, 0, 7, 9]"];
+        let counts: Vec<_> = lines
+            .iter()
+            .map(|line| {
+                let index = JsonIndex::build(line);
+                match query.run(index.root(line)) {
+                    QueryResult::Owned(OwnedValue::Int(n)) => n,
+                    other => panic!("unexpected result: {other:?}"),
+                }
+            })
+            .collect();
+        assert_eq!(counts, [2, 0, 3]);
+    }
+
+    #[test]
+    fn test_run_matches_eval() {
+        let json = br#"{"a": [1, 2], "b": null}"#;
+        let index = JsonIndex::build(json);
+        for source in [".", ".a[]", ".b // \"x\"", ".missing.field"] {
+            let query = query_compiled(source).unwrap();
+            let expected = eval::<_, JqSemantics>(&parse(source).unwrap(), index.root(json));
+            assert_eq!(
+                format!("{:?}", query.run(index.root(json))),
+                format!("{expected:?}"),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_with_semantics() {
+        let json = br#"{"a": 1}"#;
+        let index = JsonIndex::build(json);
+        let query = CompiledQuery::from(parse(".a").unwrap());
+        assert!(matches!(
+            query.run_with::<_, YqSemantics>(index.root(json)),
+            QueryResult::One(StandardJson::Number(_))
+        ));
+        assert_eq!(query.into_expr(), parse(".a").unwrap());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(query_compiled(".[").is_err());
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Evaluates expressions against JSON using the cursor-based navigation API.
 
+use alloc::borrow::Cow;
 #[cfg(not(test))]
 use alloc::boxed::Box;
 #[cfg(not(test))]
@@ -29,6 +30,14 @@ pub trait EvalSemantics: Copy + Default {
     const NEGATIVE_INDEX_IN_HAS: bool;
     /// If true, `%` truncates float operands to integers (jq). If false, float modulo (yq).
     const MOD_TRUNCATES_FLOATS: bool;
+    /// How deeply user-defined function bodies may nest, e.g. the recursion
+    /// depth of `def f: if . < 5000 then . + 1 | f else . end`. Deeper calls
+    /// fail with an error instead of overflowing the stack.
+    ///
+    /// A level of a simple recursion like that one takes about 2 KiB of stack
+    /// in release builds and 7 KiB in debug builds, so the default fits the
+    /// 8 MiB stack of a main thread. Lower it to evaluate on smaller stacks.
+    const MAX_FUNCTION_DEPTH: usize = 1000;
 }
 
 /// jq-compatible evaluation semantics (default).
@@ -61,13 +70,17 @@ impl EvalSemantics for YqSemantics {
     const MOD_TRUNCATES_FLOATS: bool = false;
 }
 
-use crate::json::light::{JsonCursor, JsonElements, JsonFields, StandardJson};
+use crate::json::light::{
+    number_to_owned, to_owned_value_with, JsonCursor, JsonElements, JsonFields, JsonNumber,
+    StandardJson,
+};
 
 use super::expr::{
     ArithOp, AssignOp, Builtin, CompareOp, Expr, FormatType, Literal, ObjectEntry, ObjectKey,
     Pattern, StringPart,
 };
-use super::value::OwnedValue;
+use super::schema::JsonSchema;
+use super::value::{OwnedValue, PathComponent};
 
 /// Result of evaluating a jq expression.
 #[derive(Debug)]
@@ -113,6 +126,40 @@ impl<W: Clone + AsRef<[u64]>> QueryResult<'_, W> {
         }
     }
 
+    /// Number of values this result outputs.
+    fn output_count(&self) -> usize {
+        match self {
+            QueryResult::One(_) | QueryResult::OneCursor(_) | QueryResult::Owned(_) => 1,
+            QueryResult::Many(vs) => vs.len(),
+            QueryResult::ManyOwned(vs) => vs.len(),
+            QueryResult::None | QueryResult::Error(_) | QueryResult::Break(_) => 0,
+        }
+    }
+
+    /// Keep at most the first `n` outputs.
+    fn take(self, n: usize) -> Self {
+        match self.materialize_cursor() {
+            QueryResult::One(_) | QueryResult::Owned(_) if n == 0 => QueryResult::None,
+            QueryResult::Many(mut vs) => {
+                vs.truncate(n);
+                match vs.len() {
+                    0 => QueryResult::None,
+                    1 => QueryResult::One(vs.pop().expect("one value")),
+                    _ => QueryResult::Many(vs),
+                }
+            }
+            QueryResult::ManyOwned(mut vs) => {
+                vs.truncate(n);
+                match vs.len() {
+                    0 => QueryResult::None,
+                    1 => QueryResult::Owned(vs.pop().expect("one value")),
+                    _ => QueryResult::ManyOwned(vs),
+                }
+            }
+            other => other,
+        }
+    }
+
     /// Returns true if this result is an evaluation error.
     ///
     /// Mirrors [`crate::jq::eval_generic::GenericResult::is_error`] so the two
@@ -145,6 +192,8 @@ impl<W: Clone + AsRef<[u64]>> QueryResult<'_, W> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct EvalError {
     pub message: String,
+    /// Value raised by `error(v)`; see [`value`](Self::value).
+    value: Option<OwnedValue>,
 }
 
 impl EvalError {
@@ -152,9 +201,36 @@ impl EvalError {
     pub fn new(message: impl Into<String>) -> Self {
         Self {
             message: message.into(),
+            value: None,
+        }
+    }
+
+    /// Create an error raised by `error(value)`.
+    pub fn raised(value: OwnedValue) -> Self {
+        let message = match &value {
+            OwnedValue::String(s) => s.clone(),
+            v => v.to_json(),
+        };
+        Self {
+            message,
+            value: Some(value),
         }
     }
 
+    /// Value raised by `error(v)`; `try ... catch` hands it to the handler.
+    /// `None` for errors raised by the evaluator itself, whose handler input
+    /// is the message.
+    pub fn value(&self) -> Option<&OwnedValue> {
+        self.value.as_ref()
+    }
+
+    /// The value a `catch` handler receives for this error.
+    pub fn catch_value(&self) -> OwnedValue {
+        self.value
+            .clone()
+            .unwrap_or_else(|| OwnedValue::String(self.message.clone()))
+    }
+
     /// Create a type error.
     pub fn type_error(expected: &str, got: &str) -> Self {
         Self::new(format!("expected {expected}, got {got}"))
@@ -192,43 +268,31 @@ fn type_name<W>(value: &StandardJson<'_, W>) -> &'static str {
 
 /// Convert a StandardJson value to an OwnedValue.
 fn to_owned<W: Clone + AsRef<[u64]>>(value: &StandardJson<'_, W>) -> OwnedValue {
-    match value {
-        StandardJson::Null => OwnedValue::Null,
-        StandardJson::Bool(b) => OwnedValue::Bool(*b),
-        StandardJson::Number(n) => {
-            if let Ok(i) = n.as_i64() {
-                OwnedValue::Int(i)
-            } else if let Ok(f) = n.as_f64() {
-                OwnedValue::Float(f)
-            } else {
-                // Fallback - shouldn't happen for valid JSON
-                OwnedValue::Float(0.0)
-            }
-        }
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                OwnedValue::String(cow.into_owned())
-            } else {
-                OwnedValue::String(String::new())
-            }
-        }
-        StandardJson::Array(elements) => {
-            let items: Vec<OwnedValue> = (*elements).map(|e| to_owned(&e)).collect();
-            OwnedValue::Array(items)
-        }
-        StandardJson::Object(fields) => {
-            let mut map = IndexMap::new();
-            for field in *fields {
-                // Get the key as a string
-                if let StandardJson::String(key_str_val) = field.key() {
-                    if let Ok(cow) = key_str_val.as_str() {
-                        map.insert(cow.into_owned(), to_owned(&field.value()));
-                    }
-                }
-            }
-            OwnedValue::Object(map)
-        }
-        StandardJson::Error(_) => OwnedValue::Null,
+    to_owned_value_with(value, usize::MAX, evaluator_number_to_owned)
+}
+
+/// How NaN is written in the JSON text the evaluator re-indexes between pipe
+/// stages (see [`owned_value_to_json_string`]).
+///
+/// JSON has no NaN, so this is not valid JSON. Only the evaluator's own number
+/// reads, [`number_as_f64`] and [`to_owned`], recognize it; the public
+/// [`JsonNumber`] parsers reject it.
+const NAN_TEXT: &str = "-NaN";
+
+/// A number as the evaluator reads it: [`JsonNumber::as_f64`], or NaN for
+/// [`NAN_TEXT`].
+pub(crate) fn number_as_f64(n: &JsonNumber<'_>) -> Result<f64, crate::json::light::JsonError> {
+    if n.text_from_start().starts_with(NAN_TEXT.as_bytes()) {
+        return Ok(f64::NAN);
+    }
+    n.as_f64()
+}
+
+/// [`number_to_owned`], reading [`NAN_TEXT`] as NaN.
+fn evaluator_number_to_owned(n: &JsonNumber<'_>) -> OwnedValue {
+    match number_as_f64(n) {
+        Ok(f) if f.is_nan() => OwnedValue::Float(f),
+        _ => number_to_owned(n),
     }
 }
 
@@ -259,185 +323,135 @@ fn needs_path_context(expr: &Expr) -> bool {
     }
 }
 
+/// Evaluation state passed down from an expression to its subexpressions.
+#[derive(Debug, Clone, Copy, Default)]
+struct Scope {
+    /// Errors yield no output instead, as inside `expr?`.
+    optional: bool,
+    /// Number of user-defined function bodies being evaluated around this
+    /// expression, bounded by [`EvalSemantics::MAX_FUNCTION_DEPTH`].
+    depth: usize,
+}
+
+impl Scope {
+    /// The scope of `expr` in `expr?`.
+    fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    /// The scope of a function body, or `None` if that would nest function
+    /// bodies deeper than `S` allows.
+    fn enter_function<S: EvalSemantics>(self) -> Option<Self> {
+        (self.depth < S::MAX_FUNCTION_DEPTH).then_some(Self {
+            depth: self.depth + 1,
+            ..self
+        })
+    }
+}
+
 /// Evaluate a single expression against a JSON value.
+///
+/// Every call level of a recursive function passes through here several
+/// times, so this handles only the expressions that commonly sit between two
+/// levels and leaves the rest to [`eval_single_other`]. Each arm adds to the
+/// stack frame of every level, which bounds the function depth that fits in a
+/// thread's stack.
 fn eval_single<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match expr {
         Expr::Identity => QueryResult::One(value),
 
-        Expr::Field(name) => match value {
-            StandardJson::Object(fields) => match find_field::<W>(fields, name) {
-                Some(v) => QueryResult::One(v),
-                // jq returns null for missing fields on objects (not an error)
-                None => QueryResult::One(StandardJson::Null),
-            },
-            // jq returns null for field access on null
-            StandardJson::Null => QueryResult::One(StandardJson::Null),
-            _ if optional => QueryResult::None,
-            _ => QueryResult::Error(EvalError::type_error("object", type_name(&value))),
-        },
+        Expr::Optional(inner) => eval_single::<W, S>(inner, value, scope.optional()),
 
-        Expr::Index(idx) => match value {
-            StandardJson::Array(elements) => match get_element_at_index::<W>(elements, *idx) {
-                Some(v) => QueryResult::One(v),
-                // jq returns null for out-of-bounds array access (not an error)
-                None => QueryResult::One(StandardJson::Null),
-            },
-            // jq returns null for index on null
-            StandardJson::Null => QueryResult::One(StandardJson::Null),
-            _ if optional => QueryResult::None,
-            _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
-        },
+        Expr::Pipe(exprs) => eval_pipe::<W, S>(exprs, value, scope),
 
-        Expr::Slice { start, end } => match value {
-            StandardJson::Array(elements) => {
-                // Fast path: full slice [:] / [0:] returns the original array unchanged
-                if matches!(start, None | Some(0)) && end.is_none() {
-                    return QueryResult::One(value);
-                }
-                // jq array slicing yields a single sub-array, not a stream of elements
-                let items: Vec<OwnedValue> = slice_elements::<W>(elements, *start, *end)
-                    .iter()
-                    .map(to_owned)
-                    .collect();
-                QueryResult::Owned(OwnedValue::Array(items))
-            }
-            // jq returns null for slice on null
-            StandardJson::Null => QueryResult::One(StandardJson::Null),
-            // jq supports string slicing
-            StandardJson::String(s) => {
-                let s_str = match s.as_str() {
-                    Ok(s) => s,
-                    Err(_) => return QueryResult::Error(EvalError::new("invalid UTF-8 in string")),
-                };
+        Expr::Comma(exprs) => eval_comma::<W, S>(exprs, value, scope),
 
-                // Fast path: identity slice [:] returns original string without character counting
-                if start.is_none() && end.is_none() {
-                    return QueryResult::One(value);
-                }
+        Expr::Arithmetic { op, left, right } => {
+            eval_arithmetic::<W, S>(*op, left, right, value, scope)
+        }
 
-                // Fast path: [0:] on non-empty string returns original
-                if let Some(0) = start {
-                    if end.is_none() && !s_str.is_empty() {
-                        return QueryResult::One(value);
-                    }
-                }
+        Expr::Paren(inner) => eval_single::<W, S>(inner, value, scope),
 
-                // Only count characters when actually slicing
-                let len = s_str.chars().count();
+        Expr::Alternative(left, right) => eval_alternative::<W, S>(left, right, value, scope),
 
-                // Resolve indices like jq does
-                let resolve_idx = |idx: i64| -> usize {
-                    if idx >= 0 {
-                        (idx as usize).min(len)
-                    } else {
-                        let pos = len as i64 + idx;
-                        if pos < 0 {
-                            0
-                        } else {
-                            pos as usize
-                        }
-                    }
-                };
+        Expr::If {
+            cond,
+            then_branch,
+            else_branch,
+        } => eval_if::<W, S>(cond, then_branch, else_branch, value, scope),
 
-                let start_idx = start.map_or(0, resolve_idx);
-                let end_idx = end.map_or(len, resolve_idx);
+        Expr::Try { expr, catch } => eval_try::<W, S>(expr, catch.as_deref(), value, scope),
 
-                // Check for empty slice
-                if start_idx >= end_idx || start_idx >= len {
-                    return QueryResult::Owned(OwnedValue::String(String::new()));
-                }
+        Expr::FuncDef {
+            name,
+            params,
+            body,
+            then,
+        } => eval_func_def::<W, S>(name, params, body, then, value, scope),
 
-                // Fast path: if resolved slice is full string, return original
-                if start_idx == 0 && end_idx == len {
-                    return QueryResult::One(value);
-                }
+        // Label-break for non-local control flow
+        Expr::Label { name, body } => eval_label::<W, S>(name, body, value, scope),
 
-                // Actually slice the string (requires character iteration)
-                let sliced: String = s_str
-                    .chars()
-                    .skip(start_idx)
-                    .take(end_idx - start_idx)
-                    .collect();
-                QueryResult::Owned(OwnedValue::String(sliced))
-            }
-            _ if optional => QueryResult::None,
-            _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
-        },
+        _ => eval_single_other::<W, S>(expr, value, scope),
+    }
+}
 
-        Expr::Iterate => match value {
-            StandardJson::Array(elements) => {
-                let results: Vec<_> = elements.collect();
-                QueryResult::Many(results)
-            }
-            StandardJson::Object(fields) => {
-                let results: Vec<_> = fields.map(|f| f.value()).collect();
-                QueryResult::Many(results)
-            }
-            _ if optional => QueryResult::None,
-            _ => QueryResult::Error(EvalError::type_error("array or object", type_name(&value))),
-        },
+/// The expressions [`eval_single`] does not handle itself.
+#[inline(never)]
+fn eval_single_other<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    match expr {
+        Expr::Field(name) => eval_field::<W>(name, value, scope),
 
-        Expr::Optional(inner) => eval_single::<W, S>(inner, value, true),
+        Expr::Index(idx) => eval_index::<W>(*idx, value, scope),
 
-        Expr::Pipe(exprs) => eval_pipe::<W, S>(exprs, value, optional),
+        Expr::Iterate => eval_iterate::<W>(value, scope),
 
-        Expr::Comma(exprs) => eval_comma::<W, S>(exprs, value, optional),
+        Expr::Literal(lit) => QueryResult::Owned(literal_to_owned(lit)),
 
-        Expr::Array(inner) => eval_array_construction::<W, S>(inner, value, optional),
+        Expr::Slice { start, end } => eval_slice::<W>(*start, *end, value, scope),
 
-        Expr::Object(entries) => eval_object_construction::<W, S>(entries, value, optional),
+        Expr::Array(inner) => eval_array_construction::<W, S>(inner, value, scope),
 
-        Expr::Literal(lit) => QueryResult::Owned(literal_to_owned(lit)),
+        Expr::Object(entries) => eval_object_construction::<W, S>(entries, value, scope),
 
         Expr::RecursiveDescent => eval_recursive_descent::<W, S>(value),
 
-        Expr::Paren(inner) => eval_single::<W, S>(inner, value, optional),
-
-        Expr::Arithmetic { op, left, right } => {
-            eval_arithmetic::<W, S>(*op, left, right, value, optional)
-        }
-
-        Expr::Compare { op, left, right } => {
-            eval_compare::<W, S>(*op, left, right, value, optional)
-        }
+        Expr::Compare { op, left, right } => eval_compare::<W, S>(*op, left, right, value, scope),
 
-        Expr::And(left, right) => eval_and::<W, S>(left, right, value, optional),
+        Expr::And(left, right) => eval_and::<W, S>(left, right, value, scope),
 
-        Expr::Or(left, right) => eval_or::<W, S>(left, right, value, optional),
+        Expr::Or(left, right) => eval_or::<W, S>(left, right, value, scope),
 
         Expr::Not => eval_not::<W>(value),
 
-        Expr::Alternative(left, right) => eval_alternative::<W, S>(left, right, value, optional),
-
-        Expr::If {
-            cond,
-            then_branch,
-            else_branch,
-        } => eval_if::<W, S>(cond, then_branch, else_branch, value, optional),
-
-        Expr::Try { expr, catch } => eval_try::<W, S>(expr, catch.as_deref(), value, optional),
+        Expr::Error(msg) => eval_error::<W, S>(msg.as_deref(), value, scope),
 
-        Expr::Error(msg) => eval_error::<W, S>(msg.as_deref(), value, optional),
+        Expr::Builtin(builtin) => eval_builtin::<W, S>(builtin, value, scope),
 
-        Expr::Builtin(builtin) => eval_builtin::<W, S>(builtin, value, optional),
+        Expr::StringInterpolation(parts) => eval_string_interpolation::<W, S>(parts, value, scope),
 
-        Expr::StringInterpolation(parts) => {
-            eval_string_interpolation::<W, S>(parts, value, optional)
-        }
-
-        Expr::Format(format_type) => eval_format::<W>(format_type.clone(), value, optional),
+        Expr::Format(format_type) => eval_format::<W>(format_type.clone(), value, scope),
 
         // Phase 8: Variables and Advanced Control Flow
-        Expr::As { expr, var, body } => eval_as::<W, S>(expr, var, body, value, optional),
+        Expr::As { expr, var, body } => eval_as::<W, S>(expr, var, body, value, scope),
+
         Expr::Var(name) => {
             // Variable references without context should error
             // In practice, variables are resolved by eval_as which substitutes them
             QueryResult::Error(EvalError::new(format!("undefined variable: ${name}")))
         }
+
         Expr::Loc { line } => {
             // $__loc__ returns {"file": "<stdin>", "line": N}
             // where N is the 1-based line number in the jq filter source
@@ -446,55 +460,59 @@ fn eval_single<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             obj.insert("line".into(), OwnedValue::Int(*line as i64));
             QueryResult::Owned(OwnedValue::Object(obj))
         }
+
         Expr::Env => {
             // $ENV returns an object containing all environment variables
-            eval_env::<W>(optional)
+            eval_env::<W>(scope)
         }
+
         Expr::Reduce {
             input,
             var,
             init,
             update,
-        } => eval_reduce::<W, S>(input, var, init, update, value, optional),
+        } => eval_reduce::<W, S>(input, var, init, update, value, scope),
+
         Expr::Foreach {
             input,
             var,
             init,
             update,
             extract,
-        } => eval_foreach::<W, S>(
-            input,
-            var,
-            init,
-            update,
-            extract.as_deref(),
+        } => eval_foreach::<W, S>(input, var, init, update, extract.as_deref(), value, scope),
+
+        Expr::Limit { n, expr } => eval_limit::<W, S>(n, expr, value, scope),
+
+        Expr::FirstExpr(expr) => eval_first_expr::<W, S>(expr, value, scope),
+
+        Expr::LastExpr(expr) => eval_last_expr::<W, S>(expr, value, scope),
+
+        Expr::NthExpr { n, expr } => eval_nth_expr::<W, S>(n, expr, value, scope),
+
+        Expr::Until { cond, update } => eval_until::<W, S>(cond, update, value, scope),
+
+        Expr::While { cond, update } => eval_while::<W, S>(cond, update, value, scope),
+
+        Expr::Repeat(expr) => eval_repeat::<W, S>(expr, MAX_REPEAT, value, scope),
+
+        Expr::Range { from, to, step } => eval_range::<W, S>(
+            from,
+            to.as_deref(),
+            step.as_deref(),
+            MAX_RANGE,
             value,
-            optional,
+            scope,
         ),
-        Expr::Limit { n, expr } => eval_limit::<W, S>(n, expr, value, optional),
-        Expr::FirstExpr(expr) => eval_first_expr::<W, S>(expr, value, optional),
-        Expr::LastExpr(expr) => eval_last_expr::<W, S>(expr, value, optional),
-        Expr::NthExpr { n, expr } => eval_nth_expr::<W, S>(n, expr, value, optional),
-        Expr::Until { cond, update } => eval_until::<W, S>(cond, update, value, optional),
-        Expr::While { cond, update } => eval_while::<W, S>(cond, update, value, optional),
-        Expr::Repeat(expr) => eval_repeat::<W, S>(expr, value, optional),
-        Expr::Range { from, to, step } => {
-            eval_range::<W, S>(from, to.as_deref(), step.as_deref(), value, optional)
-        }
 
         // Phase 9: Variables & Definitions
         Expr::AsPattern {
             expr,
             pattern,
             body,
-        } => eval_as_pattern::<W, S>(expr, pattern, body, value, optional),
-        Expr::FuncDef {
-            name,
-            params,
-            body,
-            then,
-        } => eval_func_def::<W, S>(name, params, body, then, value, optional),
-        Expr::FuncCall { name, args } => eval_func_call::<W>(name, args, value, optional),
+        } => eval_as_pattern::<W, S>(expr, pattern, body, value, scope),
+
+        Expr::FuncCall { name, args } => eval_func_call::<W>(name, args, value, scope),
+
         Expr::NamespacedCall {
             namespace,
             name,
@@ -508,20 +526,171 @@ fn eval_single<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
 
         // Assignment operators
-        Expr::Assign { path, value: val } => eval_assign::<W, S>(path, val, value, optional),
-        Expr::Update { path, filter } => eval_update::<W, S>(path, filter, value, optional),
+        Expr::Assign { path, value: val } => eval_assign::<W, S>(path, val, value, scope),
+
+        Expr::Update { path, filter } => eval_update::<W, S>(path, filter, value, scope),
+
         Expr::CompoundAssign {
             op,
             path,
             value: val,
-        } => eval_compound_assign::<W, S>(*op, path, val, value, optional),
+        } => eval_compound_assign::<W, S>(*op, path, val, value, scope),
+
         Expr::AlternativeAssign { path, value: val } => {
-            eval_alternative_assign::<W, S>(path, val, value, optional)
+            eval_alternative_assign::<W, S>(path, val, value, scope)
         }
 
-        // Label-break for non-local control flow
-        Expr::Label { name, body } => eval_label::<W, S>(name, body, value, optional),
         Expr::Break(name) => QueryResult::Break(name.clone()),
+
+        Expr::Identity
+        | Expr::Optional(_)
+        | Expr::Pipe(_)
+        | Expr::Comma(_)
+        | Expr::Paren(_)
+        | Expr::Arithmetic { .. }
+        | Expr::If { .. }
+        | Expr::Try { .. }
+        | Expr::Alternative(..)
+        | Expr::FuncDef { .. }
+        | Expr::Label { .. } => eval_single::<W, S>(expr, value, scope),
+    }
+}
+
+/// Evaluate field access `.name`.
+fn eval_field<'a, W: Clone + AsRef<[u64]>>(
+    name: &str,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    match value {
+        StandardJson::Object(fields) => match find_field::<W>(fields, name) {
+            Some(v) => QueryResult::One(v),
+            // jq returns null for missing fields on objects (not an error)
+            None => QueryResult::One(StandardJson::Null),
+        },
+        // jq returns null for field access on null
+        StandardJson::Null => QueryResult::One(StandardJson::Null),
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::type_error("object", type_name(&value))),
+    }
+}
+
+/// Evaluate array index `.[n]`.
+fn eval_index<W: Clone + AsRef<[u64]>>(
+    idx: i64,
+    value: StandardJson<W>,
+    scope: Scope,
+) -> QueryResult<W> {
+    match value {
+        StandardJson::Array(elements) => match get_element_at_index::<W>(elements, idx) {
+            Some(v) => QueryResult::One(v),
+            // jq returns null for out-of-bounds array access (not an error)
+            None => QueryResult::One(StandardJson::Null),
+        },
+        // jq returns null for index on null
+        StandardJson::Null => QueryResult::One(StandardJson::Null),
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
+    }
+}
+
+/// Evaluate slice `.[start:end]` of an array or string.
+fn eval_slice<W: Clone + AsRef<[u64]>>(
+    start: Option<i64>,
+    end: Option<i64>,
+    value: StandardJson<W>,
+    scope: Scope,
+) -> QueryResult<W> {
+    match value {
+        StandardJson::Array(elements) => {
+            // Fast path: full slice [:] / [0:] returns the original array unchanged
+            if matches!(start, None | Some(0)) && end.is_none() {
+                return QueryResult::One(value);
+            }
+            // jq array slicing yields a single sub-array, not a stream of elements
+            let items: Vec<OwnedValue> = slice_elements::<W>(elements, start, end)
+                .iter()
+                .map(to_owned)
+                .collect();
+            QueryResult::Owned(OwnedValue::Array(items))
+        }
+        // jq returns null for slice on null
+        StandardJson::Null => QueryResult::One(StandardJson::Null),
+        // jq supports string slicing
+        StandardJson::String(s) => {
+            let s_str = match s.as_str() {
+                Ok(s) => s,
+                Err(_) => return QueryResult::Error(EvalError::new("invalid UTF-8 in string")),
+            };
+
+            // Fast path: identity slice [:] returns original string without character counting
+            if start.is_none() && end.is_none() {
+                return QueryResult::One(value);
+            }
+
+            // Fast path: [0:] on non-empty string returns original
+            if let Some(0) = start {
+                if end.is_none() && !s_str.is_empty() {
+                    return QueryResult::One(value);
+                }
+            }
+
+            // Only count characters when actually slicing
+            let len = s_str.chars().count();
+
+            // Resolve indices like jq does
+            let resolve_idx = |idx: i64| -> usize {
+                if idx >= 0 {
+                    (idx as usize).min(len)
+                } else {
+                    let pos = len as i64 + idx;
+                    if pos < 0 {
+                        0
+                    } else {
+                        pos as usize
+                    }
+                }
+            };
+
+            let start_idx = start.map_or(0, resolve_idx);
+            let end_idx = end.map_or(len, resolve_idx);
+
+            // Check for empty slice
+            if start_idx >= end_idx || start_idx >= len {
+                return QueryResult::Owned(OwnedValue::String(String::new()));
+            }
+
+            // Fast path: if resolved slice is full string, return original
+            if start_idx == 0 && end_idx == len {
+                return QueryResult::One(value);
+            }
+
+            // Actually slice the string (requires character iteration)
+            let sliced: String = s_str
+                .chars()
+                .skip(start_idx)
+                .take(end_idx - start_idx)
+                .collect();
+            QueryResult::Owned(OwnedValue::String(sliced))
+        }
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
+    }
+}
+
+/// Evaluate iteration `.[]` over an array or object.
+fn eval_iterate<W: Clone + AsRef<[u64]>>(value: StandardJson<W>, scope: Scope) -> QueryResult<W> {
+    match value {
+        StandardJson::Array(elements) => {
+            let results: Vec<_> = elements.collect();
+            QueryResult::Many(results)
+        }
+        StandardJson::Object(fields) => {
+            let results: Vec<_> = fields.map(|f| f.value()).collect();
+            QueryResult::Many(results)
+        }
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::type_error("array or object", type_name(&value))),
     }
 }
 
@@ -537,53 +706,76 @@ fn literal_to_owned(lit: &Literal) -> OwnedValue {
 }
 
 /// Evaluate a comma expression (multiple outputs).
+#[inline(never)]
 fn eval_comma<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     exprs: &[Expr],
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     if exprs.is_empty() {
         return QueryResult::None;
     }
 
-    let mut all_results = Vec::new();
-    let mut all_owned = Vec::new();
-    let mut has_owned = false;
-
+    let mut results = Vec::with_capacity(exprs.len());
     for expr in exprs {
-        match eval_single::<W, S>(expr, value.clone(), optional).materialize_cursor() {
-            QueryResult::One(v) => all_results.push(v),
+        match eval_single::<W, S>(expr, value.clone(), scope) {
+            result @ (QueryResult::Error(_) | QueryResult::Break(_)) => return result,
+            result => results.push(result),
+        }
+    }
+    concat_results(results)
+}
+
+/// Concatenate the outputs of several results, in order.
+///
+/// Stays borrowed until the first owned output, then promotes everything
+/// collected so far (as in `eval_pipe`). The first error or break wins.
+fn concat_results<'a, W: Clone + AsRef<[u64]>>(
+    results: Vec<QueryResult<'a, W>>,
+) -> QueryResult<'a, W> {
+    let mut borrowed: Vec<StandardJson<'a, W>> = Vec::new();
+    let mut owned: Option<Vec<OwnedValue>> = None;
+
+    for result in results {
+        match result.materialize_cursor() {
+            QueryResult::One(v) => match owned.as_mut() {
+                Some(acc) => acc.push(to_owned(&v)),
+                None => borrowed.push(v),
+            },
             QueryResult::OneCursor(_) => {
                 unreachable!("materialize_cursor should have converted this")
             }
-            QueryResult::Many(vs) => all_results.extend(vs),
-            QueryResult::Owned(v) => {
-                has_owned = true;
-                all_owned.push(v);
-            }
-            QueryResult::ManyOwned(vs) => {
-                has_owned = true;
-                all_owned.extend(vs);
-            }
+            QueryResult::Many(vs) => match owned.as_mut() {
+                Some(acc) => acc.extend(vs.iter().map(to_owned)),
+                None => borrowed.extend(vs),
+            },
+            QueryResult::Owned(v) => owned
+                .get_or_insert_with(|| {
+                    core::mem::take(&mut borrowed)
+                        .iter()
+                        .map(to_owned)
+                        .collect()
+                })
+                .push(v),
+            QueryResult::ManyOwned(vs) => owned
+                .get_or_insert_with(|| {
+                    core::mem::take(&mut borrowed)
+                        .iter()
+                        .map(to_owned)
+                        .collect()
+                })
+                .extend(vs),
             QueryResult::None => {}
             QueryResult::Error(e) => return QueryResult::Error(e),
             QueryResult::Break(label) => return QueryResult::Break(label),
         }
     }
 
-    // If we have any owned values, we need to convert all results to owned
-    if has_owned {
-        let mut converted: Vec<OwnedValue> = all_results.iter().map(to_owned).collect();
-        converted.extend(all_owned);
-        if converted.len() == 1 {
-            QueryResult::Owned(converted.pop().unwrap())
-        } else {
-            QueryResult::ManyOwned(converted)
-        }
-    } else if all_results.len() == 1 {
-        QueryResult::One(all_results.pop().unwrap())
-    } else {
-        QueryResult::Many(all_results)
+    match owned {
+        Some(mut converted) if converted.len() == 1 => QueryResult::Owned(converted.pop().unwrap()),
+        Some(converted) => QueryResult::ManyOwned(converted),
+        None if borrowed.len() == 1 => QueryResult::One(borrowed.pop().unwrap()),
+        None => QueryResult::Many(borrowed),
     }
 }
 
@@ -591,10 +783,10 @@ fn eval_comma<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn eval_array_construction<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     inner: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Collect all outputs from the inner expression into an array
-    let result = eval_single::<W, S>(inner, value, optional);
+    let result = eval_single::<W, S>(inner, value, scope);
 
     let items: Vec<OwnedValue> = match result.materialize_cursor() {
         QueryResult::One(v) => vec![to_owned(&v)],
@@ -614,7 +806,7 @@ fn eval_array_construction<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn eval_object_construction<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     entries: &[super::expr::ObjectEntry],
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let mut map = IndexMap::new();
 
@@ -623,7 +815,7 @@ fn eval_object_construction<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         let key_str = match &entry.key {
             ObjectKey::Literal(s) => s.clone(),
             ObjectKey::Expr(key_expr) => {
-                let key_result = eval_single::<W, S>(key_expr, value.clone(), optional);
+                let key_result = eval_single::<W, S>(key_expr, value.clone(), scope);
                 match key_result {
                     QueryResult::One(StandardJson::String(s)) => {
                         if let Ok(cow) = s.as_str() {
@@ -643,7 +835,7 @@ fn eval_object_construction<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         };
 
         // Evaluate the value
-        let val_result = eval_single::<W, S>(&entry.value, value.clone(), optional);
+        let val_result = eval_single::<W, S>(&entry.value, value.clone(), scope);
         let owned_val = match val_result.materialize_cursor() {
             QueryResult::One(v) => to_owned(&v),
             QueryResult::OneCursor(_) => unreachable!(),
@@ -733,37 +925,61 @@ fn result_to_owned<W: Clone + AsRef<[u64]>>(
     }
 }
 
-/// Evaluate arithmetic operations.
-fn eval_arithmetic<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    op: ArithOp,
+/// Convert every output of a QueryResult to an OwnedValue.
+fn result_to_owned_all<W: Clone + AsRef<[u64]>>(
+    result: QueryResult<'_, W>,
+) -> Result<Vec<OwnedValue>, EvalError> {
+    match result.materialize_cursor() {
+        QueryResult::One(v) => Ok(vec![to_owned(&v)]),
+        QueryResult::OneCursor(_) => unreachable!(),
+        QueryResult::Owned(v) => Ok(vec![v]),
+        QueryResult::Many(vs) => Ok(vs.iter().map(to_owned).collect()),
+        QueryResult::ManyOwned(vs) => Ok(vs),
+        QueryResult::None => Ok(Vec::new()),
+        QueryResult::Error(e) => Err(e),
+        QueryResult::Break(label) => Err(EvalError::new(format!("break ${label} not in label"))),
+    }
+}
+
+/// Evaluate arithmetic operations.
+#[inline(never)]
+fn eval_arithmetic<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    op: ArithOp,
     left: &Expr,
     right: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), optional)) {
+    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
-    let right_val = match result_to_owned(eval_single::<W, S>(right, value, optional)) {
+    let right_val = match result_to_owned(eval_single::<W, S>(right, value, scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
 
-    let result = match op {
-        ArithOp::Add => arith_add::<S>(left_val, right_val),
-        ArithOp::Sub => arith_sub::<S>(left_val, right_val),
-        ArithOp::Mul => arith_mul::<S>(left_val, right_val),
-        ArithOp::Div => arith_div::<S>(left_val, right_val),
-        ArithOp::Mod => arith_mod::<S>(left_val, right_val),
-    };
-
-    match result {
+    match arith::<S>(op, left_val, right_val) {
         Ok(v) => QueryResult::Owned(v),
         Err(e) => QueryResult::Error(e),
     }
 }
 
+/// Apply an arithmetic operator to two values.
+fn arith<S: EvalSemantics>(
+    op: ArithOp,
+    left: OwnedValue,
+    right: OwnedValue,
+) -> Result<OwnedValue, EvalError> {
+    match op {
+        ArithOp::Add => arith_add::<S>(left, right),
+        ArithOp::Sub => arith_sub::<S>(left, right),
+        ArithOp::Mul => arith_mul::<S>(left, right),
+        ArithOp::Div => arith_div::<S>(left, right),
+        ArithOp::Mod => arith_mod::<S>(left, right),
+    }
+}
+
 /// Add two values (numbers, strings, arrays, objects).
 fn arith_add<S: EvalSemantics>(
     left: OwnedValue,
@@ -879,9 +1095,7 @@ fn arith_mul<S: EvalSemantics>(
             }
         }
         // Object recursive merge
-        (OwnedValue::Object(a), OwnedValue::Object(b)) => {
-            Ok(OwnedValue::Object(merge_objects(a, b)))
-        }
+        (left @ OwnedValue::Object(_), right @ OwnedValue::Object(_)) => Ok(left.deep_merge(right)),
         // null * x = null
         (OwnedValue::Null, _) | (_, OwnedValue::Null) => Ok(OwnedValue::Null),
         (a, b) => Err(EvalError::new(format!(
@@ -892,24 +1106,6 @@ fn arith_mul<S: EvalSemantics>(
     }
 }
 
-/// Recursively merge two objects.
-fn merge_objects(
-    mut left: IndexMap<String, OwnedValue>,
-    right: IndexMap<String, OwnedValue>,
-) -> IndexMap<String, OwnedValue> {
-    for (k, v) in right {
-        match (left.get(&k).cloned(), v) {
-            (Some(OwnedValue::Object(a)), OwnedValue::Object(b)) => {
-                left.insert(k, OwnedValue::Object(merge_objects(a, b)));
-            }
-            (_, v) => {
-                left.insert(k, v);
-            }
-        }
-    }
-    left
-}
-
 /// Divide two values.
 fn arith_div<S: EvalSemantics>(
     left: OwnedValue,
@@ -1027,27 +1223,57 @@ fn eval_compare<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     left: &Expr,
     right: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), optional)) {
+    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
-    let right_val = match result_to_owned(eval_single::<W, S>(right, value, optional)) {
+    let right_val = match result_to_owned(eval_single::<W, S>(right, value, scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
 
-    let result = match op {
-        CompareOp::Eq => left_val == right_val,
-        CompareOp::Ne => left_val != right_val,
-        CompareOp::Lt => compare_values(&left_val, &right_val) == core::cmp::Ordering::Less,
-        CompareOp::Le => compare_values(&left_val, &right_val) != core::cmp::Ordering::Greater,
-        CompareOp::Gt => compare_values(&left_val, &right_val) == core::cmp::Ordering::Greater,
-        CompareOp::Ge => compare_values(&left_val, &right_val) != core::cmp::Ordering::Less,
+    QueryResult::Owned(OwnedValue::Bool(compare_with(op, &left_val, &right_val)))
+}
+
+/// Apply a comparison operator with jq semantics.
+///
+/// jq orders a NaN operand below every number, itself included, so
+/// `nan < nan` holds while `nan == nan` does not.
+pub(super) fn compare_with(op: CompareOp, left: &OwnedValue, right: &OwnedValue) -> bool {
+    use core::cmp::Ordering;
+
+    let ordering = if is_nan_value(left) && is_nan_value(right) {
+        Ordering::Less
+    } else {
+        compare_values(left, right)
     };
+    match op {
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+    }
+}
+
+fn is_nan_value(value: &OwnedValue) -> bool {
+    matches!(value, OwnedValue::Float(f) if f.is_nan())
+}
 
-    QueryResult::Owned(OwnedValue::Bool(result))
+/// Order two numbers as jq sorts them: NaN below every other number.
+///
+/// Unlike `partial_cmp`, this is a total order (NaNs are equal to each
+/// other), so it is safe to sort with.
+fn compare_numbers(a: f64, b: f64) -> core::cmp::Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => core::cmp::Ordering::Equal,
+        (true, false) => core::cmp::Ordering::Less,
+        (false, true) => core::cmp::Ordering::Greater,
+        (false, false) => a.partial_cmp(&b).expect("neither is NaN"),
+    }
 }
 
 /// Compare two values using jq ordering: null < bool < number < string < array < object.
@@ -1076,13 +1302,9 @@ fn compare_values(left: &OwnedValue, right: &OwnedValue) -> core::cmp::Ordering
         (OwnedValue::Null, OwnedValue::Null) => Ordering::Equal,
         (OwnedValue::Bool(a), OwnedValue::Bool(b)) => a.cmp(b),
         (OwnedValue::Int(a), OwnedValue::Int(b)) => a.cmp(b),
-        (OwnedValue::Float(a), OwnedValue::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
-        (OwnedValue::Int(a), OwnedValue::Float(b)) => {
-            (*a as f64).partial_cmp(b).unwrap_or(Ordering::Equal)
-        }
-        (OwnedValue::Float(a), OwnedValue::Int(b)) => {
-            a.partial_cmp(&(*b as f64)).unwrap_or(Ordering::Equal)
-        }
+        (OwnedValue::Float(a), OwnedValue::Float(b)) => compare_numbers(*a, *b),
+        (OwnedValue::Int(a), OwnedValue::Float(b)) => compare_numbers(*a as f64, *b),
+        (OwnedValue::Float(a), OwnedValue::Int(b)) => compare_numbers(*a, *b as f64),
         (OwnedValue::String(a), OwnedValue::String(b)) => a.cmp(b),
         (OwnedValue::Array(a), OwnedValue::Array(b)) => {
             for (av, bv) in a.iter().zip(b.iter()) {
@@ -1121,10 +1343,10 @@ fn eval_and<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     left: &Expr,
     right: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate left first
-    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), optional)) {
+    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
@@ -1135,7 +1357,7 @@ fn eval_and<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 
     // Evaluate right
-    let right_val = match result_to_owned(eval_single::<W, S>(right, value, optional)) {
+    let right_val = match result_to_owned(eval_single::<W, S>(right, value, scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
@@ -1148,10 +1370,10 @@ fn eval_or<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     left: &Expr,
     right: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate left first
-    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), optional)) {
+    let left_val = match result_to_owned(eval_single::<W, S>(left, value.clone(), scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
@@ -1162,7 +1384,7 @@ fn eval_or<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 
     // Evaluate right
-    let right_val = match result_to_owned(eval_single::<W, S>(right, value, optional)) {
+    let right_val = match result_to_owned(eval_single::<W, S>(right, value, scope)) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
     };
@@ -1177,14 +1399,15 @@ fn eval_not<W: Clone + AsRef<[u64]>>(value: StandardJson<'_, W>) -> QueryResult<
 }
 
 /// Evaluate alternative operator (//): returns left if truthy, otherwise right.
+#[inline(never)]
 fn eval_alternative<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     left: &Expr,
     right: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate left
-    let left_result = eval_single::<W, S>(left, value.clone(), optional);
+    let left_result = eval_single::<W, S>(left, value.clone(), scope);
 
     // Check if left produced a truthy result
     let is_truthy = match &left_result {
@@ -1201,20 +1424,21 @@ fn eval_alternative<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     if is_truthy {
         left_result
     } else {
-        eval_single::<W, S>(right, value, optional)
+        eval_single::<W, S>(right, value, scope)
     }
 }
 
 /// Evaluate if-then-else expression.
+#[inline(never)]
 fn eval_if<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     cond: &Expr,
     then_branch: &Expr,
     else_branch: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate condition
-    let cond_result = eval_single::<W, S>(cond, value.clone(), optional);
+    let cond_result = eval_single::<W, S>(cond, value.clone(), scope);
 
     // Check if condition is truthy
     let is_truthy = match &cond_result {
@@ -1229,42 +1453,156 @@ fn eval_if<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     };
 
     if is_truthy {
-        eval_single::<W, S>(then_branch, value, optional)
+        eval_single::<W, S>(then_branch, value, scope)
     } else {
-        eval_single::<W, S>(else_branch, value, optional)
+        eval_single::<W, S>(else_branch, value, scope)
     }
 }
 
 /// Evaluate try-catch expression.
+#[inline(never)]
 fn eval_try<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     catch: Option<&Expr>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Evaluate the expression
-    let result = eval_single::<W, S>(expr, value.clone(), optional);
+    // Outputs before the error are kept: `try (1, error("x"), 3) catch .`
+    // is `1, "x"`
+    let mut outputs = Vec::new();
+    match eval_until_error::<W, S>(expr, value, scope, &mut outputs) {
+        // The handler's input is the error value, e.g. the message of `error("msg")`
+        Some(QueryResult::Error(e)) => {
+            if let Some(catch_expr) = catch {
+                outputs.push(eval_owned_pipe::<W, S>(
+                    core::slice::from_ref(catch_expr),
+                    e.catch_value(),
+                    scope,
+                ));
+            }
+        }
+        Some(stop) => return stop,
+        None => {}
+    }
+    match outputs.len() {
+        0 => QueryResult::None,
+        // Non-error results pass through
+        1 => outputs.pop().expect("one result"),
+        _ => concat_results(outputs),
+    }
+}
+
+/// Evaluate `expr`, appending its results to `outputs` until an error or
+/// `break` ends it, and return that error or break.
+///
+/// [`eval_single`] reports only the error of a failing expression. This
+/// keeps the outputs that commas and pipes produced before it, so that `try`
+/// and [`eval_many`] can pass them on; other expressions fail as a whole.
+fn eval_until_error<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+    outputs: &mut Vec<QueryResult<'a, W>>,
+) -> Option<QueryResult<'a, W>> {
+    match expr {
+        Expr::Paren(inner) => eval_until_error::<W, S>(inner, value, scope, outputs),
+        Expr::Comma(exprs) => exprs
+            .iter()
+            .find_map(|expr| eval_until_error::<W, S>(expr, value.clone(), scope, outputs)),
+        Expr::Pipe(exprs) => pipe_until_error::<W, S>(exprs, value, scope, outputs),
+        _ => push_until_error(eval_single::<W, S>(expr, value, scope), outputs),
+    }
+}
 
+/// Append `result` to `outputs`, or return it if it is an error or `break`.
+fn push_until_error<'a, W>(
+    result: QueryResult<'a, W>,
+    outputs: &mut Vec<QueryResult<'a, W>>,
+) -> Option<QueryResult<'a, W>> {
     match result {
-        // If error, use catch handler or return nothing
-        QueryResult::Error(_) => match catch {
-            Some(catch_expr) => eval_single::<W, S>(catch_expr, value, optional),
-            None => QueryResult::None,
-        },
-        // Non-error results pass through
-        other => other,
+        stop @ (QueryResult::Error(_) | QueryResult::Break(_)) => Some(stop),
+        result => {
+            outputs.push(result);
+            None
+        }
+    }
+}
+
+/// [`eval_until_error`] for a pipe: the later stages run on every output the
+/// first stage produced before it failed, then its error is returned.
+fn pipe_until_error<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    exprs: &[Expr],
+    value: StandardJson<'a, W>,
+    scope: Scope,
+    outputs: &mut Vec<QueryResult<'a, W>>,
+) -> Option<QueryResult<'a, W>> {
+    let (first, rest) = match exprs {
+        [only] => return eval_until_error::<W, S>(only, value, scope, outputs),
+        [first, rest @ ..] if !exprs.iter().any(needs_path_context) => (first, rest),
+        _ => return push_until_error(eval_pipe::<W, S>(exprs, value, scope), outputs),
+    };
+
+    let mut inputs = Vec::new();
+    let first_stop = eval_until_error::<W, S>(first, value, scope, &mut inputs);
+    for input in inputs {
+        let stop = match input.materialize_cursor() {
+            QueryResult::One(v) => pipe_until_error::<W, S>(rest, v, scope, outputs),
+            QueryResult::Many(vs) => vs
+                .into_iter()
+                .find_map(|v| pipe_until_error::<W, S>(rest, v, scope, outputs)),
+            QueryResult::Owned(v) => pipe_owned_until_error::<W, S>(rest, &v, scope, outputs),
+            QueryResult::ManyOwned(vs) => vs
+                .iter()
+                .find_map(|v| pipe_owned_until_error::<W, S>(rest, v, scope, outputs)),
+            _ => None,
+        };
+        if stop.is_some() {
+            return stop;
+        }
+    }
+    first_stop
+}
+
+/// [`pipe_until_error`] on an owned input, which is re-indexed for the
+/// remaining stages.
+fn pipe_owned_until_error<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    rest: &[Expr],
+    value: &OwnedValue,
+    scope: Scope,
+    outputs: &mut Vec<QueryResult<'a, W>>,
+) -> Option<QueryResult<'a, W>> {
+    let json = owned_value_to_json_string(value);
+    let index = crate::json::JsonIndex::build(json.as_bytes());
+    let mut results = Vec::new();
+    let stop = pipe_until_error::<Vec<u64>, S>(
+        rest,
+        index.root(json.as_bytes()).value(),
+        scope,
+        &mut results,
+    );
+    outputs.push(QueryResult::ManyOwned(
+        results
+            .into_iter()
+            .flat_map(QueryResult::collect_owned)
+            .collect(),
+    ));
+    match stop? {
+        QueryResult::Error(e) => Some(QueryResult::Error(e)),
+        QueryResult::Break(label) => Some(QueryResult::Break(label)),
+        _ => unreachable!("pipe_until_error stops only on errors and breaks"),
     }
 }
 
 /// Evaluate label expression.
 /// `label $name | expr` establishes a scope that can be exited with `break $name`.
+#[inline(never)]
 fn eval_label<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     name: &str,
     body: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(body, value, optional);
+    let result = eval_single::<W, S>(body, value, scope);
     match result {
         // If we get a Break with matching label, convert to empty output
         QueryResult::Break(label) if label == name => QueryResult::None,
@@ -1277,28 +1615,25 @@ fn eval_label<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn eval_error<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     msg: Option<&Expr>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let message = match msg {
-        Some(msg_expr) => {
-            let msg_result = eval_single::<W, S>(msg_expr, value, optional);
-            match result_to_owned(msg_result) {
-                Ok(OwnedValue::String(s)) => s,
-                Ok(v) => v.to_json(),
-                Err(e) => return QueryResult::Error(e),
-            }
-        }
-        None => "null".into(),
+    // `error` alone raises its input, like `error(.)`
+    let raised = match msg {
+        Some(msg_expr) => match result_to_owned(eval_single::<W, S>(msg_expr, value, scope)) {
+            Ok(v) => v,
+            Err(e) => return QueryResult::Error(e),
+        },
+        None => to_owned(&value),
     };
 
-    QueryResult::Error(EvalError::new(message))
+    QueryResult::Error(EvalError::raised(raised))
 }
 
 /// Evaluate a builtin function.
 fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     builtin: &Builtin,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match builtin {
         // Type functions
@@ -1415,115 +1750,126 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
 
         // Length & Keys
-        Builtin::Length => builtin_length::<W>(value, optional),
-        Builtin::Utf8ByteLength => builtin_utf8bytelength(value, optional),
-        Builtin::Keys => builtin_keys::<W>(value, optional, true),
-        Builtin::KeysUnsorted => builtin_keys::<W>(value, optional, false),
-        Builtin::Has(key_expr) => builtin_has::<W, S>(key_expr, value, optional),
-        Builtin::In(obj_expr) => builtin_in::<W, S>(obj_expr, value, optional),
+        Builtin::Length => builtin_length::<W>(value, scope),
+        Builtin::Utf8ByteLength => builtin_utf8bytelength(value, scope),
+        Builtin::Keys => builtin_keys::<W>(value, scope, true),
+        Builtin::KeysUnsorted => builtin_keys::<W>(value, scope, false),
+        Builtin::Has(key_expr) => builtin_has::<W, S>(key_expr, value, scope),
+        Builtin::In(obj_expr) => builtin_in::<W, S>(obj_expr, value, scope),
 
         // Selection & Filtering
-        Builtin::Select(cond) => builtin_select::<W, S>(cond, value, optional),
+        Builtin::Select(cond) => builtin_select::<W, S>(cond, value, scope),
         Builtin::Empty => QueryResult::None,
 
         // Map & Iteration
-        Builtin::Map(f) => builtin_map::<W, S>(f, value, optional),
-        Builtin::MapValues(f) => builtin_map_values::<W, S>(f, value, optional),
+        Builtin::Map(f) => builtin_map::<W, S>(f, value, scope),
+        Builtin::MapValues(f) => builtin_map_values::<W, S>(f, value, scope),
 
         // Reduction
-        Builtin::Add => builtin_add::<W, S>(value, optional),
-        Builtin::Any => builtin_any::<W>(value, optional),
-        Builtin::All => builtin_all::<W>(value, optional),
-        Builtin::Min => builtin_min::<W>(value, optional),
-        Builtin::Max => builtin_max::<W>(value, optional),
-        Builtin::MinBy(f) => builtin_min_by::<W, S>(f, value, optional),
-        Builtin::MaxBy(f) => builtin_max_by::<W, S>(f, value, optional),
+        Builtin::Add => builtin_add::<W, S>(value, scope),
+        Builtin::Any => eval_any_all::<W, S>(&Expr::Iterate, &Expr::Identity, false, value, scope),
+        Builtin::All => eval_any_all::<W, S>(&Expr::Iterate, &Expr::Identity, true, value, scope),
+        Builtin::AnyOf(generator, cond) => {
+            eval_any_all::<W, S>(generator, cond, false, value, scope)
+        }
+        Builtin::AllOf(generator, cond) => {
+            eval_any_all::<W, S>(generator, cond, true, value, scope)
+        }
+        Builtin::Min => builtin_min::<W>(value, scope),
+        Builtin::Max => builtin_max::<W>(value, scope),
+        Builtin::MinBy(f) => builtin_min_by::<W, S>(f, value, scope),
+        Builtin::MaxBy(f) => builtin_max_by::<W, S>(f, value, scope),
 
         // Phase 5: String Functions
-        Builtin::AsciiDowncase => builtin_ascii_downcase::<W>(value, optional),
-        Builtin::AsciiUpcase => builtin_ascii_upcase::<W>(value, optional),
-        Builtin::Ltrimstr(s) => builtin_ltrimstr::<W, S>(s, value, optional),
-        Builtin::Rtrimstr(s) => builtin_rtrimstr::<W, S>(s, value, optional),
-        Builtin::Startswith(s) => builtin_startswith::<W, S>(s, value, optional),
-        Builtin::Endswith(s) => builtin_endswith::<W, S>(s, value, optional),
-        Builtin::Split(sep) => builtin_split::<W, S>(sep, value, optional),
-        Builtin::Join(sep) => builtin_join::<W, S>(sep, value, optional),
-        Builtin::Contains(b) => builtin_contains::<W, S>(b, value, optional),
-        Builtin::Inside(b) => builtin_inside::<W, S>(b, value, optional),
+        Builtin::AsciiDowncase => builtin_ascii_case::<W>(false, value, scope),
+        Builtin::AsciiUpcase => builtin_ascii_case::<W>(true, value, scope),
+        Builtin::Ltrimstr(s) => builtin_trimstr::<W, S>(s, false, value, scope),
+        Builtin::Rtrimstr(s) => builtin_trimstr::<W, S>(s, true, value, scope),
+        Builtin::Startswith(s) => builtin_affix_test::<W, S>(s, false, value, scope),
+        Builtin::Endswith(s) => builtin_affix_test::<W, S>(s, true, value, scope),
+        Builtin::Split(sep) => builtin_split::<W, S>(sep, value, scope),
+        Builtin::Join(sep) => builtin_join::<W, S>(sep, value, scope),
+        Builtin::Contains(b) => builtin_containment::<W, S>(b, false, value, scope),
+        Builtin::Inside(b) => builtin_containment::<W, S>(b, true, value, scope),
 
         // Phase 5: Array Functions
-        Builtin::First => builtin_first::<W>(value, optional),
-        Builtin::Last => builtin_last::<W>(value, optional),
-        Builtin::Nth(n) => builtin_nth::<W, S>(n, value, optional),
-        Builtin::Reverse => builtin_reverse::<W>(value, optional),
-        Builtin::Flatten => builtin_flatten::<W>(value, optional, 1),
-        Builtin::FlattenDepth(depth) => builtin_flatten_depth::<W, S>(depth, value, optional),
-        Builtin::GroupBy(f) => builtin_group_by::<W, S>(f, value, optional),
-        Builtin::Unique => builtin_unique::<W>(value, optional),
-        Builtin::UniqueBy(f) => builtin_unique_by::<W, S>(f, value, optional),
-        Builtin::Sort => builtin_sort::<W>(value, optional),
-        Builtin::SortBy(f) => builtin_sort_by::<W, S>(f, value, optional),
+        Builtin::First => builtin_first::<W>(value, scope),
+        Builtin::Last => builtin_last::<W>(value, scope),
+        Builtin::Nth(n) => builtin_nth::<W, S>(n, value, scope),
+        Builtin::Reverse => builtin_reverse::<W>(value, scope),
+        Builtin::Flatten => builtin_flatten::<W>(value, scope, 1),
+        Builtin::FlattenDepth(depth) => builtin_flatten_depth::<W, S>(depth, value, scope),
+        Builtin::GroupBy(f) => builtin_group_by::<W, S>(f, value, scope),
+        Builtin::Unique => builtin_unique::<W>(value, scope),
+        Builtin::UniqueBy(f) => builtin_unique_by::<W, S>(f, value, scope),
+        Builtin::Sort => builtin_sort::<W>(value, scope),
+        Builtin::SortBy(f) => builtin_sort_by::<W, S>(f, value, scope),
 
         // Phase 5: Object Functions
-        Builtin::ToEntries => builtin_to_entries::<W>(value, optional),
-        Builtin::FromEntries => builtin_from_entries::<W>(value, optional),
-        Builtin::WithEntries(f) => builtin_with_entries::<W, S>(f, value, optional),
+        Builtin::ToEntries => builtin_to_entries::<W>(value, scope),
+        Builtin::FromEntries => builtin_from_entries::<W>(value, scope),
+        Builtin::WithEntries(f) => builtin_with_entries::<W, S>(f, value, scope),
 
         // Phase 6: Type Conversions
-        Builtin::ToString => builtin_tostring::<W>(value, optional),
-        Builtin::ToNumber => builtin_tonumber::<W>(value, optional),
-        Builtin::ToJson => builtin_tojson::<W>(value, optional),
-        Builtin::FromJson => builtin_fromjson::<W>(value, optional),
+        Builtin::ToString => builtin_tostring::<W>(value, scope),
+        Builtin::ToNumber => builtin_tonumber::<W>(value, scope),
+        Builtin::ToJson => builtin_tojson::<W>(value, scope),
+        Builtin::FromJson => builtin_fromjson::<W>(value, scope),
 
         // Phase 6: Additional String Functions
-        Builtin::Explode => builtin_explode::<W>(value, optional),
-        Builtin::Implode => builtin_implode::<W>(value, optional),
+        Builtin::Explode => builtin_explode::<W>(value, scope),
+        Builtin::Implode => builtin_implode::<W>(value, scope),
+        Builtin::Ascii(n) => builtin_ascii::<W, S>(n, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::Test(re) => builtin_test_regex::<W, S>(re, value, optional),
+        Builtin::Test(re) => builtin_regex::<W, S>(RegexOutput::Test, re, None, value, scope),
         #[cfg(not(feature = "regex"))]
-        Builtin::Test(re) => builtin_test::<W, S>(re, value, optional),
-        Builtin::Indices(s) => builtin_indices::<W, S>(s, value, optional),
-        Builtin::Index(s) => builtin_index::<W, S>(s, value, optional),
-        Builtin::Rindex(s) => builtin_rindex::<W, S>(s, value, optional),
-        Builtin::ToJsonStream => builtin_tojsonstream::<W>(value, optional),
-        Builtin::FromJsonStream => builtin_fromjsonstream::<W>(value, optional),
-        Builtin::GetPath(path) => builtin_getpath::<W, S>(path, value, optional),
+        Builtin::Test(re) => builtin_test::<W, S>(re, value, scope),
+        Builtin::Indices(s) => builtin_indices::<W, S>(IndexOutput::All, s, value, scope),
+        Builtin::Index(s) => builtin_indices::<W, S>(IndexOutput::First, s, value, scope),
+        Builtin::Rindex(s) => builtin_indices::<W, S>(IndexOutput::Last, s, value, scope),
+        Builtin::ToJsonStream => builtin_tojsonstream::<W>(value, scope),
+        Builtin::FromJsonStream => builtin_fromjsonstream::<W>(value, scope),
+        Builtin::GetPath(path) => builtin_getpath::<W, S>(path, value, scope),
 
         // Phase 16: Regex Functions
         #[cfg(feature = "regex")]
-        Builtin::TestFlags(re, flags) => builtin_test_flags::<W, S>(re, flags, value, optional),
+        Builtin::TestFlags(re, flags) => {
+            builtin_regex::<W, S>(RegexOutput::Test, re, Some(flags), value, scope)
+        }
         #[cfg(feature = "regex")]
-        Builtin::Match(re) => builtin_match::<W, S>(re, None, value, optional),
+        Builtin::Match(re) => builtin_regex::<W, S>(RegexOutput::Match, re, None, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::MatchFlags(re, flags) => builtin_match_flags::<W, S>(re, flags, value, optional),
+        Builtin::MatchFlags(re, flags) => {
+            builtin_regex::<W, S>(RegexOutput::Match, re, Some(flags), value, scope)
+        }
         #[cfg(feature = "regex")]
-        Builtin::Capture(re) => builtin_capture::<W, S>(re, value, optional),
+        Builtin::Capture(re) => builtin_regex::<W, S>(RegexOutput::Capture, re, None, value, scope),
         #[cfg(feature = "regex")]
         Builtin::CaptureFlags(re, flags) => {
-            builtin_capture_flags::<W, S>(re, flags, value, optional)
+            builtin_regex::<W, S>(RegexOutput::Capture, re, Some(flags), value, scope)
         }
         #[cfg(feature = "regex")]
-        Builtin::Sub(re, replacement) => builtin_sub::<W, S>(re, replacement, value, optional),
+        Builtin::Sub(re, replacement) => builtin_sub::<W, S>(re, replacement, value, scope),
         #[cfg(feature = "regex")]
         Builtin::SubFlags(re, replacement, flags) => {
-            builtin_sub_flags::<W, S>(re, replacement, flags, value, optional)
+            builtin_sub_flags::<W, S>(re, replacement, flags, value, scope)
         }
         #[cfg(feature = "regex")]
-        Builtin::Gsub(re, replacement) => builtin_gsub::<W, S>(re, replacement, value, optional),
+        Builtin::Gsub(re, replacement) => builtin_gsub::<W, S>(re, replacement, value, scope),
         #[cfg(feature = "regex")]
         Builtin::GsubFlags(re, replacement, flags) => {
-            builtin_gsub_flags::<W, S>(re, replacement, flags, value, optional)
+            builtin_gsub_flags::<W, S>(re, replacement, flags, value, scope)
         }
         #[cfg(feature = "regex")]
-        Builtin::Scan(re) => builtin_scan::<W, S>(re, value, optional),
+        Builtin::Scan(re) => builtin_scan::<W, S>(re, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::ScanFlags(re, flags) => builtin_scan_flags::<W, S>(re, flags, value, optional),
+        Builtin::ScanFlags(re, flags) => builtin_scan_flags::<W, S>(re, flags, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::SplitRegex(re, flags) => builtin_split_regex::<W, S>(re, flags, value, optional),
+        Builtin::SplitRegex(re, flags) => builtin_split_regex::<W, S>(re, flags, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::Splits(re) => builtin_splits::<W, S>(re, value, optional),
+        Builtin::Splits(re) => builtin_splits::<W, S>(re, value, scope),
         #[cfg(feature = "regex")]
-        Builtin::SplitsFlags(re, flags) => builtin_splits_flags::<W, S>(re, flags, value, optional),
+        Builtin::SplitsFlags(re, flags) => builtin_splits_flags::<W, S>(re, flags, value, scope),
         // Non-regex fallbacks for when regex feature is not enabled
         #[cfg(not(feature = "regex"))]
         Builtin::TestFlags(_, _)
@@ -1544,14 +1890,14 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
 
         // Phase 8: Advanced Control Flow Builtins
-        Builtin::Recurse => builtin_recurse::<W, S>(value, optional),
-        Builtin::RecurseF(f) => builtin_recurse_f::<W, S>(f, value, optional),
-        Builtin::RecurseCond(f, cond) => builtin_recurse_cond::<W, S>(f, cond, value, optional),
-        Builtin::Walk(f) => builtin_walk::<W, S>(f, value, optional),
-        Builtin::IsValid(expr) => builtin_isvalid::<W, S>(expr, value, optional),
+        Builtin::Recurse | Builtin::RecurseF(_) | Builtin::RecurseCond(..) => {
+            builtin_recurse::<W, S>(builtin, value, scope, usize::MAX)
+        }
+        Builtin::Walk(f) => builtin_walk::<W, S>(f, value, scope),
+        Builtin::IsValid(expr) => builtin_isvalid::<W, S>(expr, value, scope),
 
         // Phase 10: Path Expressions
-        Builtin::Path(expr) => builtin_path::<W>(expr, value, optional),
+        Builtin::Path(expr) => builtin_path::<W, S>(expr, value, scope),
         Builtin::PathNoArg => {
             // PathNoArg requires path context which is handled in eval_pipe_with_context
             // When called without context, return empty path (root position)
@@ -1568,73 +1914,73 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             let _ = n_expr; // Unused here, but evaluated in context version
             QueryResult::Owned(OwnedValue::Object(IndexMap::new()))
         }
-        Builtin::Paths => builtin_paths::<W>(value, optional),
-        Builtin::PathsFilter(filter) => builtin_paths_filter::<W, S>(filter, value, optional),
-        Builtin::LeafPaths => builtin_leaf_paths::<W>(value, optional),
-        Builtin::SetPath(path, val) => builtin_setpath::<W, S>(path, val, value, optional),
-        Builtin::DelPaths(paths) => builtin_delpaths::<W, S>(paths, value, optional),
+        Builtin::Paths => builtin_paths::<W>(value, scope),
+        Builtin::PathsFilter(filter) => builtin_paths_filter::<W, S>(filter, value, scope),
+        Builtin::LeafPaths => builtin_leaf_paths::<W>(value, scope),
+        Builtin::SetPath(path, val) => builtin_setpath::<W, S>(path, val, value, scope),
+        Builtin::DelPaths(paths) => builtin_delpaths::<W, S>(paths, value, scope),
 
         // Phase 10: Math Functions
-        Builtin::Floor => builtin_floor::<W>(value, optional),
-        Builtin::Ceil => builtin_ceil::<W>(value, optional),
-        Builtin::Round => builtin_round::<W>(value, optional),
-        Builtin::Sqrt => builtin_sqrt::<W>(value, optional),
-        Builtin::Fabs => builtin_fabs::<W>(value, optional),
-        Builtin::Log => builtin_log::<W>(value, optional),
-        Builtin::Log10 => builtin_log10::<W>(value, optional),
-        Builtin::Log2 => builtin_log2::<W>(value, optional),
-        Builtin::Exp => builtin_exp::<W>(value, optional),
-        Builtin::Exp10 => builtin_exp10::<W>(value, optional),
-        Builtin::Exp2 => builtin_exp2::<W>(value, optional),
-        Builtin::Pow(base, exp) => builtin_pow::<W, S>(base, exp, value, optional),
-        Builtin::Sin => builtin_sin::<W>(value, optional),
-        Builtin::Cos => builtin_cos::<W>(value, optional),
-        Builtin::Tan => builtin_tan::<W>(value, optional),
-        Builtin::Asin => builtin_asin::<W>(value, optional),
-        Builtin::Acos => builtin_acos::<W>(value, optional),
-        Builtin::Atan => builtin_atan::<W>(value, optional),
-        Builtin::Atan2(y, x) => builtin_atan2::<W, S>(y, x, value, optional),
-        Builtin::Sinh => builtin_sinh::<W>(value, optional),
-        Builtin::Cosh => builtin_cosh::<W>(value, optional),
-        Builtin::Tanh => builtin_tanh::<W>(value, optional),
-        Builtin::Asinh => builtin_asinh::<W>(value, optional),
-        Builtin::Acosh => builtin_acosh::<W>(value, optional),
-        Builtin::Atanh => builtin_atanh::<W>(value, optional),
+        Builtin::Floor => builtin_floor::<W>(value, scope),
+        Builtin::Ceil => builtin_ceil::<W>(value, scope),
+        Builtin::Round => builtin_round::<W>(value, scope),
+        Builtin::Sqrt => builtin_sqrt::<W>(value, scope),
+        Builtin::Fabs => builtin_fabs::<W>(value, scope),
+        Builtin::Log => builtin_log::<W>(value, scope),
+        Builtin::Log10 => builtin_log10::<W>(value, scope),
+        Builtin::Log2 => builtin_log2::<W>(value, scope),
+        Builtin::Exp => builtin_exp::<W>(value, scope),
+        Builtin::Exp10 => builtin_exp10::<W>(value, scope),
+        Builtin::Exp2 => builtin_exp2::<W>(value, scope),
+        Builtin::Pow(base, exp) => builtin_pow::<W, S>(base, exp, value, scope),
+        Builtin::Sin => builtin_sin::<W>(value, scope),
+        Builtin::Cos => builtin_cos::<W>(value, scope),
+        Builtin::Tan => builtin_tan::<W>(value, scope),
+        Builtin::Asin => builtin_asin::<W>(value, scope),
+        Builtin::Acos => builtin_acos::<W>(value, scope),
+        Builtin::Atan => builtin_atan::<W>(value, scope),
+        Builtin::Atan2(y, x) => builtin_atan2::<W, S>(y, x, value, scope),
+        Builtin::Sinh => builtin_sinh::<W>(value, scope),
+        Builtin::Cosh => builtin_cosh::<W>(value, scope),
+        Builtin::Tanh => builtin_tanh::<W>(value, scope),
+        Builtin::Asinh => builtin_asinh::<W>(value, scope),
+        Builtin::Acosh => builtin_acosh::<W>(value, scope),
+        Builtin::Atanh => builtin_atanh::<W>(value, scope),
 
         // Phase 10: Number Classification & Constants
         Builtin::Infinite => QueryResult::Owned(OwnedValue::Float(f64::INFINITY)),
         Builtin::Nan => QueryResult::Owned(OwnedValue::Float(f64::NAN)),
-        Builtin::IsInfinite => builtin_isinfinite::<W>(value, optional),
-        Builtin::IsNan => builtin_isnan::<W>(value, optional),
-        Builtin::IsNormal => builtin_isnormal::<W>(value, optional),
-        Builtin::IsFinite => builtin_isfinite::<W>(value, optional),
+        Builtin::IsInfinite => builtin_isinfinite::<W>(value, scope),
+        Builtin::IsNan => builtin_isnan::<W>(value, scope),
+        Builtin::IsNormal => builtin_isnormal::<W>(value, scope),
+        Builtin::IsFinite => builtin_isfinite::<W>(value, scope),
 
         // Phase 10: Debug
-        Builtin::Debug => builtin_debug::<W>(value, optional),
-        Builtin::DebugMsg(msg) => builtin_debug_msg::<W>(msg, value, optional),
+        Builtin::Debug => builtin_debug::<W>(value, scope),
+        Builtin::DebugMsg(msg) => builtin_debug_msg::<W>(msg, value, scope),
 
         // Phase 10: Environment
-        Builtin::Env => builtin_env::<W>(value, optional),
-        Builtin::EnvVar(var) => builtin_envvar::<W, S>(var, value, optional),
-        Builtin::EnvObject(name) => builtin_env_object::<W>(name, optional),
-        Builtin::StrEnv(name) => builtin_strenv::<W>(name, optional),
+        Builtin::Env => builtin_env::<W>(value, scope),
+        Builtin::EnvVar(var) => builtin_envvar::<W, S>(var, value, scope),
+        Builtin::EnvObject(name) => builtin_env_object::<W>(name, scope),
+        Builtin::StrEnv(name) => builtin_strenv::<W>(name, scope),
 
         // Phase 10: Null handling
         Builtin::NullLit => QueryResult::Owned(OwnedValue::Null),
 
         // Phase 10: String functions
-        Builtin::Trim => builtin_trim::<W>(value, optional),
-        Builtin::Ltrim => builtin_ltrim::<W>(value, optional),
-        Builtin::Rtrim => builtin_rtrim::<W>(value, optional),
+        Builtin::Trim => builtin_trim::<W>(value, scope),
+        Builtin::Ltrim => builtin_ltrim::<W>(value, scope),
+        Builtin::Rtrim => builtin_rtrim::<W>(value, scope),
 
         // Phase 10: Array functions
-        Builtin::Transpose => builtin_transpose::<W>(value, optional),
-        Builtin::BSearch(x) => builtin_bsearch::<W, S>(x, value, optional),
+        Builtin::Transpose => builtin_transpose::<W>(value, scope),
+        Builtin::BSearch(x) => builtin_bsearch::<W, S>(x, value, scope),
 
         // Phase 10: Object functions
-        Builtin::ModuleMeta(name) => builtin_modulemeta::<W>(name, value, optional),
-        Builtin::Pick(keys) => builtin_pick::<W, S>(keys, value, optional),
-        Builtin::Omit(keys) => builtin_omit::<W, S>(keys, value, optional),
+        Builtin::ModuleMeta(name) => builtin_modulemeta::<W>(name, value, scope),
+        Builtin::Pick(keys) => builtin_pick::<W, S>(keys, value, scope),
+        Builtin::Omit(keys) => builtin_omit::<W, S>(keys, value, scope),
 
         // YAML metadata functions (yq)
         Builtin::Tag => builtin_tag::<W>(value),
@@ -1644,8 +1990,8 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         Builtin::Line => builtin_line::<W>(),
         Builtin::Column => builtin_column::<W>(),
         Builtin::DocumentIndex => builtin_document_index::<W>(),
-        Builtin::Shuffle => builtin_shuffle::<W>(value, optional),
-        Builtin::Pivot => builtin_pivot::<W>(value, optional),
+        Builtin::Shuffle => builtin_shuffle::<W>(value, scope),
+        Builtin::Pivot => builtin_pivot::<W>(value, scope),
         Builtin::SplitDoc => {
             // split_doc is identity - the output formatting (--- separators)
             // is handled by the yq runner, not here
@@ -1658,58 +2004,56 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
 
         // Phase 11: Path manipulation
-        Builtin::Del(path) => builtin_del::<W>(path, value, optional),
+        Builtin::Del(path) => builtin_del::<W>(path, value, scope),
 
         // Phase 12: Additional builtins
         Builtin::Now => builtin_now::<W>(),
-        Builtin::Abs => builtin_fabs::<W>(value, optional), // abs is an alias for fabs
+        Builtin::Abs => builtin_fabs::<W>(value, scope), // abs is an alias for fabs
         Builtin::Builtins => builtin_builtins::<W>(),
         Builtin::Normals => builtin_normals::<W>(value),
         Builtin::Finites => builtin_finites::<W>(value),
 
         // Phase 13: Iteration control
-        Builtin::Limit(n_expr, expr) => builtin_limit::<W, S>(n_expr, expr, value, optional),
-        Builtin::FirstStream(expr) => builtin_first_stream::<W, S>(expr, value, optional),
-        Builtin::LastStream(expr) => builtin_last_stream::<W, S>(expr, value, optional),
-        Builtin::NthStream(n_expr, expr) => {
-            builtin_nth_stream::<W, S>(n_expr, expr, value, optional)
-        }
-        Builtin::IsEmpty(expr) => builtin_isempty::<W, S>(expr, value, optional),
+        Builtin::Limit(n_expr, expr) => eval_limit::<W, S>(n_expr, expr, value, scope),
+        Builtin::FirstStream(expr) => eval_first_expr::<W, S>(expr, value, scope),
+        Builtin::LastStream(expr) => eval_last_expr::<W, S>(expr, value, scope),
+        Builtin::NthStream(n_expr, expr) => eval_nth_expr::<W, S>(n_expr, expr, value, scope),
+        Builtin::IsEmpty(expr) => builtin_isempty::<W, S>(expr, value, scope),
 
         // Phase 14: Recursive traversal (extends Phase 8)
-        Builtin::RecurseDown => builtin_recurse::<W, S>(value, optional), // alias for recurse
+        Builtin::RecurseDown => builtin_recurse::<W, S>(builtin, value, scope, usize::MAX),
 
         // Phase 15: Date/Time functions
-        Builtin::Gmtime => builtin_gmtime::<W>(value, optional),
-        Builtin::Localtime => builtin_localtime::<W>(value, optional),
-        Builtin::Mktime => builtin_mktime::<W>(value, optional),
-        Builtin::Strftime(fmt) => builtin_strftime::<W, S>(fmt, value, optional),
-        Builtin::Strptime(fmt) => builtin_strptime::<W, S>(fmt, value, optional),
-        Builtin::Todate => builtin_todate::<W>(value, optional),
-        Builtin::Fromdate => builtin_fromdate::<W>(value, optional),
-        Builtin::Todateiso8601 => builtin_todate::<W>(value, optional), // alias for todate
-        Builtin::Fromdateiso8601 => builtin_fromdate::<W>(value, optional), // alias for fromdate
+        Builtin::Gmtime => builtin_gmtime::<W>(value, scope),
+        Builtin::Localtime => builtin_localtime::<W>(value, scope),
+        Builtin::Mktime => builtin_mktime::<W>(value, scope),
+        Builtin::Strftime(fmt) => builtin_strftime::<W, S>(fmt, value, scope),
+        Builtin::Strptime(fmt) => builtin_strptime::<W, S>(fmt, value, scope),
+        Builtin::Todate => builtin_todate::<W>(value, scope),
+        Builtin::Fromdate => builtin_fromdate::<W>(value, scope),
+        Builtin::Todateiso8601 => builtin_todate::<W>(value, scope), // alias for todate
+        Builtin::Fromdateiso8601 => builtin_fromdate::<W>(value, scope), // alias for fromdate
 
         // Phase 17: Combinations
-        Builtin::Combinations => builtin_combinations::<W>(value, optional),
-        Builtin::CombinationsN(n) => builtin_combinations_n::<W, S>(n, value, optional),
+        Builtin::Combinations => builtin_combinations::<W>(value, scope),
+        Builtin::CombinationsN(n) => builtin_combinations_n::<W, S>(n, value, scope),
 
         // Phase 18: Additional math functions
-        Builtin::Trunc => builtin_trunc::<W>(value, optional),
+        Builtin::Trunc => builtin_trunc::<W>(value, scope),
 
         // Phase 19: Type conversion
-        Builtin::ToBoolean => builtin_toboolean::<W>(value, optional),
+        Builtin::ToBoolean => builtin_toboolean::<W>(value, scope),
 
         // Phase 20: Iteration control extension
-        Builtin::Skip(n_expr, expr) => builtin_skip::<W, S>(n_expr, expr, value, optional),
+        Builtin::Skip(n_expr, expr) => builtin_skip::<W, S>(n_expr, expr, value, scope),
 
         // Phase 21: Extended Date/Time functions (yq)
-        Builtin::FromUnix => builtin_from_unix::<W>(value, optional),
-        Builtin::ToUnix => builtin_to_unix::<W>(value, optional),
-        Builtin::Tz(zone) => builtin_tz::<W, S>(zone, value, optional),
+        Builtin::FromUnix => builtin_from_unix::<W>(value, scope),
+        Builtin::ToUnix => builtin_to_unix::<W>(value, scope),
+        Builtin::Tz(zone) => builtin_tz::<W, S>(zone, value, scope),
 
         // Phase 22: File operations (yq)
-        Builtin::Load(file_expr) => builtin_load::<W, S>(file_expr, value, optional),
+        Builtin::Load(file_expr) => builtin_load::<W, S>(file_expr, value, scope),
 
         // Phase 23: Position-based navigation (succinctly extension)
         // These require cursor context - handled in eval_generic.rs
@@ -1719,13 +2063,18 @@ fn eval_builtin<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         Builtin::AtPosition(_, _) => QueryResult::Error(EvalError::new(
             "at_position requires document cursor context".to_string(),
         )),
+
+        // Phase 24: Schema validation (succinctly extension)
+        Builtin::SchemaValidates(schema_expr) => {
+            builtin_schema_validates::<W, S>(schema_expr, value, scope)
+        }
     }
 }
 
 /// Builtin: length
 fn builtin_length<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::Null => QueryResult::Owned(OwnedValue::Int(0)),
@@ -1750,16 +2099,17 @@ fn builtin_length<W: Clone + AsRef<[u64]>>(
                     Some(a) => OwnedValue::Int(a),
                     None => OwnedValue::Float(-(i as f64)),
                 })
-            } else if let Ok(f) = n.as_f64() {
+            } else if let Ok(f) = number_as_f64(n) {
                 QueryResult::Owned(OwnedValue::Float(f.abs()))
             } else {
                 QueryResult::Owned(OwnedValue::Int(0))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new(format!(
-            "{} has no length",
-            type_name(&value)
+            "{} ({}) has no length",
+            type_name(&value),
+            to_owned(&value).to_json()
         ))),
     }
 }
@@ -1767,7 +2117,7 @@ fn builtin_length<W: Clone + AsRef<[u64]>>(
 /// Builtin: utf8bytelength
 fn builtin_utf8bytelength<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::String(s) => {
@@ -1777,7 +2127,7 @@ fn builtin_utf8bytelength<W: Clone + AsRef<[u64]>>(
                 QueryResult::Owned(OwnedValue::Int(0))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     }
 }
@@ -1785,7 +2135,7 @@ fn builtin_utf8bytelength<W: Clone + AsRef<[u64]>>(
 /// Builtin: keys / keys_unsorted
 fn builtin_keys<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
     sorted: bool,
 ) -> QueryResult<'_, W> {
     match value {
@@ -1810,7 +2160,7 @@ fn builtin_keys<W: Clone + AsRef<[u64]>>(
             let arr: Vec<OwnedValue> = (0..len).map(|i| OwnedValue::Int(i as i64)).collect();
             QueryResult::Owned(OwnedValue::Array(arr))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("object or array", type_name(&value))),
     }
 }
@@ -1819,10 +2169,10 @@ fn builtin_keys<W: Clone + AsRef<[u64]>>(
 fn builtin_has<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     key_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the key expression
-    let key_result = eval_single::<W, S>(key_expr, value.clone(), optional);
+    let key_result = eval_single::<W, S>(key_expr, value.clone(), scope);
     let key_owned = match result_to_owned(key_result) {
         Ok(v) => v,
         Err(e) => return QueryResult::Error(e),
@@ -1859,7 +2209,7 @@ fn builtin_has<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             };
             QueryResult::Owned(OwnedValue::Bool(in_bounds))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new(
             "has() requires object+string or array+number",
         )),
@@ -1870,11 +2220,11 @@ fn builtin_has<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_in<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     obj_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // The input should be a key (string or number), and we check if it exists in obj
     let key_owned = to_owned(&value);
-    let obj_result = eval_single::<W, S>(obj_expr, value.clone(), optional);
+    let obj_result = eval_single::<W, S>(obj_expr, value.clone(), scope);
 
     // Get the object/array to check against (need to handle Owned case for object literals)
     let obj_owned = match obj_result {
@@ -1882,7 +2232,7 @@ fn builtin_in<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         QueryResult::Many(os) => {
             if let Some(o) = os.into_iter().next() {
                 to_owned(&o)
-            } else if optional {
+            } else if scope.optional {
                 return QueryResult::None;
             } else {
                 return QueryResult::Error(EvalError::new(
@@ -1892,7 +2242,7 @@ fn builtin_in<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
         QueryResult::Owned(o) => o,
         QueryResult::Error(e) => return QueryResult::Error(e),
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => {
             return QueryResult::Error(EvalError::new("in() requires an object or array argument"));
         }
@@ -1919,7 +2269,7 @@ fn builtin_in<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             };
             QueryResult::Owned(OwnedValue::Bool(in_bounds))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new(
             "in() requires string/number key and object/array",
         )),
@@ -1930,27 +2280,27 @@ fn builtin_in<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_select<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     cond: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate condition
-    let cond_result = eval_single::<W, S>(cond, value.clone(), optional);
+    let cond_result = eval_single::<W, S>(cond, value.clone(), scope);
 
-    // Check if condition is truthy
-    let is_truthy = match &cond_result {
-        QueryResult::One(v) => to_owned(v).is_truthy(),
+    // Like jq, emit the input once per truthy output of the condition
+    let truthy = match &cond_result {
+        QueryResult::One(v) => usize::from(to_owned(v).is_truthy()),
         QueryResult::OneCursor(_) => unreachable!("eval_single never produces OneCursor"),
-        QueryResult::Owned(v) => v.is_truthy(),
-        QueryResult::Many(vs) => vs.first().is_some_and(|v| to_owned(v).is_truthy()),
-        QueryResult::ManyOwned(vs) => vs.first().is_some_and(super::value::OwnedValue::is_truthy),
-        QueryResult::None => false,
+        QueryResult::Owned(v) => usize::from(v.is_truthy()),
+        QueryResult::Many(vs) => vs.iter().filter(|v| to_owned(v).is_truthy()).count(),
+        QueryResult::ManyOwned(vs) => vs.iter().filter(|v| v.is_truthy()).count(),
+        QueryResult::None => 0,
         QueryResult::Error(e) => return QueryResult::Error(e.clone()),
         QueryResult::Break(label) => return QueryResult::Break(label.clone()),
     };
 
-    if is_truthy {
-        QueryResult::One(value)
-    } else {
-        QueryResult::None
+    match truthy {
+        0 => QueryResult::None,
+        1 => QueryResult::One(value),
+        n => QueryResult::Many(vec![value; n]),
     }
 }
 
@@ -1958,14 +2308,14 @@ fn builtin_select<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_map<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // map(f) is equivalent to [.[] | f]
     match value {
         StandardJson::Array(elements) => {
             let mut results = Vec::new();
             for elem in elements {
-                match eval_single::<W, S>(f, elem, optional).materialize_cursor() {
+                match eval_single::<W, S>(f, elem, scope).materialize_cursor() {
                     QueryResult::One(v) => results.push(to_owned(&v)),
                     QueryResult::OneCursor(_) => unreachable!(),
                     QueryResult::Owned(v) => results.push(v),
@@ -1978,7 +2328,7 @@ fn builtin_map<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             }
             QueryResult::Owned(OwnedValue::Array(results))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -1987,7 +2337,7 @@ fn builtin_map<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_map_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Object(fields) => {
@@ -2006,7 +2356,7 @@ fn builtin_map_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
                 // Apply f to the value
                 let field_val = field.value();
-                match eval_single::<W, S>(f, field_val, optional).materialize_cursor() {
+                match eval_single::<W, S>(f, field_val, scope).materialize_cursor() {
                     QueryResult::One(v) => {
                         result_map.insert(key, to_owned(&v));
                     }
@@ -2035,7 +2385,7 @@ fn builtin_map_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             // map_values on array applies to each element
             let mut results = Vec::new();
             for elem in elements {
-                match eval_single::<W, S>(f, elem, optional).materialize_cursor() {
+                match eval_single::<W, S>(f, elem, scope).materialize_cursor() {
                     QueryResult::One(v) => results.push(to_owned(&v)),
                     QueryResult::OneCursor(_) => unreachable!(),
                     QueryResult::Owned(v) => results.push(v),
@@ -2056,7 +2406,7 @@ fn builtin_map_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             }
             QueryResult::Owned(OwnedValue::Array(results))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("object or array", type_name(&value))),
     }
 }
@@ -2064,7 +2414,7 @@ fn builtin_map_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: add
 fn builtin_add<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2083,55 +2433,152 @@ fn builtin_add<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
                 Err(e) => QueryResult::Error(e),
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
 
-/// Builtin: any
-fn builtin_any<W: Clone + AsRef<[u64]>>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    match value {
-        StandardJson::Array(elements) => {
-            for elem in elements {
-                let owned = to_owned(&elem);
-                if owned.is_truthy() {
-                    return QueryResult::Owned(OwnedValue::Bool(true));
-                }
-            }
-            QueryResult::Owned(OwnedValue::Bool(false))
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
+/// Evaluate `any(gen; cond)`, or `all(gen; cond)` when `all` is set.
+///
+/// jq defines these as `isempty(first(gen | cond or empty)) | not` and
+/// `isempty(first(gen | cond and empty))`, so evaluation stops at the first
+/// deciding output: nothing after it runs, and a long generator such as
+/// `range(1000000)` ends as soon as the answer is known.
+fn eval_any_all<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    generator: &Expr,
+    cond: &Expr,
+    all: bool,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    match find_truthiness::<W, S>(&[generator, cond], value, scope, !all) {
+        Ok(found) => QueryResult::Owned(OwnedValue::Bool(found != all)),
+        Err(result) => result,
     }
 }
 
-/// Builtin: all
-fn builtin_all<W: Clone + AsRef<[u64]>>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    match value {
-        StandardJson::Array(elements) => {
-            for elem in elements {
-                let owned = to_owned(&elem);
-                if !owned.is_truthy() {
-                    return QueryResult::Owned(OwnedValue::Bool(false));
+/// Whether some output of the pipe `stages` has truthiness `wanted`.
+///
+/// Outputs of a leading `range`, `.[]`, `recurse`, comma or nested pipe are
+/// walked one at a time, so the search stops at the first match; any other stage runs in
+/// full for each input. `Err` carries an error or `break` reached first.
+fn find_truthiness<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    stages: &[&Expr],
+    value: StandardJson<'a, W>,
+    scope: Scope,
+    wanted: bool,
+) -> Result<bool, QueryResult<'a, W>> {
+    let Some((&first, rest)) = stages.split_first() else {
+        let truthy = !matches!(value, StandardJson::Null | StandardJson::Bool(false));
+        return Ok(truthy == wanted);
+    };
+    let then_rest =
+        |head| -> Vec<&Expr> { core::iter::once(head).chain(rest.iter().copied()).collect() };
+
+    match first {
+        Expr::Paren(inner) => find_truthiness::<W, S>(&then_rest(inner), value, scope, wanted),
+        Expr::Pipe(exprs) => {
+            let stages: Vec<&Expr> = exprs.iter().chain(rest.iter().copied()).collect();
+            find_truthiness::<W, S>(&stages, value, scope, wanted)
+        }
+        Expr::Comma(exprs) => {
+            for expr in exprs {
+                if find_truthiness::<W, S>(&then_rest(expr), value.clone(), scope, wanted)? {
+                    return Ok(true);
                 }
             }
-            QueryResult::Owned(OwnedValue::Bool(true))
+            Ok(false)
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
+        Expr::Range { from, to, step } => {
+            let values = range_iter::<W, S>(from, to.as_deref(), step.as_deref(), value, scope)
+                .map_err(QueryResult::Error)?;
+            for v in values {
+                if find_owned_truthiness::<W, S>(rest, &v, scope, wanted)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Expr::Builtin(
+            builtin @ (Builtin::Recurse
+            | Builtin::RecurseDown
+            | Builtin::RecurseF(_)
+            | Builtin::RecurseCond(..)),
+        ) => {
+            for v in recurse_iter::<W, S>(builtin, &value, scope).expect("recurse builtin") {
+                if find_owned_truthiness::<W, S>(rest, &v, scope, wanted)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Expr::Iterate if matches!(value, StandardJson::Array(_) | StandardJson::Object(_)) => {
+            let items: Box<dyn Iterator<Item = StandardJson<'a, W>>> = match value {
+                StandardJson::Array(elements) => Box::new(elements),
+                StandardJson::Object(fields) => Box::new(fields.map(|f| f.value())),
+                _ => unreachable!("checked above"),
+            };
+            for item in items {
+                if find_truthiness::<W, S>(rest, item, scope, wanted)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        _ => match eval_single::<W, S>(first, value, scope).materialize_cursor() {
+            QueryResult::One(v) => find_truthiness::<W, S>(rest, v, scope, wanted),
+            QueryResult::OneCursor(_) => unreachable!(),
+            QueryResult::Many(vs) => {
+                for v in vs {
+                    if find_truthiness::<W, S>(rest, v, scope, wanted)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            QueryResult::Owned(v) => find_owned_truthiness::<W, S>(rest, &v, scope, wanted),
+            QueryResult::ManyOwned(vs) => {
+                for v in &vs {
+                    if find_owned_truthiness::<W, S>(rest, v, scope, wanted)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            QueryResult::None => Ok(false),
+            result @ (QueryResult::Error(_) | QueryResult::Break(_)) => Err(result),
+        },
     }
 }
 
+/// [`find_truthiness`] for an owned input, which is re-indexed so the
+/// remaining stages can stop early too.
+fn find_owned_truthiness<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    stages: &[&Expr],
+    value: &OwnedValue,
+    scope: Scope,
+    wanted: bool,
+) -> Result<bool, QueryResult<'a, W>> {
+    if stages.is_empty() {
+        return Ok(value.is_truthy() == wanted);
+    }
+    let json = owned_value_to_json_string(value);
+    let index = crate::json::JsonIndex::build(json.as_bytes());
+    let cursor = index.root(json.as_bytes());
+    find_truthiness::<Vec<u64>, S>(stages, cursor.value(), scope, wanted).map_err(|result| {
+        match result {
+            QueryResult::Break(label) => QueryResult::Break(label),
+            result => QueryResult::Error(
+                result_to_owned_all(result).expect_err("only errors and breaks end a search"),
+            ),
+        }
+    })
+}
+
 /// Builtin: min
 fn builtin_min<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2143,7 +2590,7 @@ fn builtin_min<W: Clone + AsRef<[u64]>>(
             let min = items.into_iter().min_by(compare_values).unwrap();
             QueryResult::Owned(min)
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2151,7 +2598,7 @@ fn builtin_min<W: Clone + AsRef<[u64]>>(
 /// Builtin: max
 fn builtin_max<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2163,43 +2610,51 @@ fn builtin_max<W: Clone + AsRef<[u64]>>(
             let max = items.into_iter().max_by(compare_values).unwrap();
             QueryResult::Owned(max)
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
 
+/// Pair each array element with its sort key for the `*_by(f)` builtins.
+///
+/// Like jq's `map([f])`, the key is the array of *all* outputs of `f`, so
+/// `sort_by(.a, .b)` sorts by `.a` then `.b`, and an element for which `f`
+/// produces nothing sorts first (its key is `[]`).
+fn keyed_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    f: &Expr,
+    elements: JsonElements<'a, W>,
+    scope: Scope,
+) -> Result<Vec<(OwnedValue, StandardJson<'a, W>)>, EvalError> {
+    elements
+        .map(|item| {
+            let key = result_to_owned_all(eval_single::<W, S>(f, item.clone(), scope))?;
+            Ok((OwnedValue::Array(key), item))
+        })
+        .collect()
+}
+
 /// Builtin: min_by(f)
 fn builtin_min_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Array(elements) => {
-            let items: Vec<StandardJson<'a, W>> = elements.collect();
-            if items.is_empty() {
-                return QueryResult::Owned(OwnedValue::Null);
-            }
-
-            // Compute keys for each item
-            let mut keyed: Vec<(OwnedValue, StandardJson<'a, W>)> = Vec::new();
-            for item in items {
-                match eval_single::<W, S>(f, item.clone(), optional) {
-                    QueryResult::One(v) => keyed.push((to_owned(&v), item)),
-                    QueryResult::Owned(v) => keyed.push((v, item)),
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    _ => keyed.push((OwnedValue::Null, item)),
-                }
-            }
+            let keyed = match keyed_by::<W, S>(f, elements, scope) {
+                Ok(keyed) => keyed,
+                Err(e) => return QueryResult::Error(e),
+            };
 
-            let min = keyed
+            // Ties keep the first element, as in jq
+            keyed
                 .into_iter()
                 .min_by(|(a, _), (b, _)| compare_values(a, b))
-                .map(|(_, v)| to_owned(&v))
-                .unwrap();
-            QueryResult::Owned(min)
+                .map_or(QueryResult::Owned(OwnedValue::Null), |(_, v)| {
+                    QueryResult::Owned(to_owned(&v))
+                })
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2208,34 +2663,24 @@ fn builtin_min_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_max_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Array(elements) => {
-            let items: Vec<StandardJson<'a, W>> = elements.collect();
-            if items.is_empty() {
-                return QueryResult::Owned(OwnedValue::Null);
-            }
-
-            // Compute keys for each item
-            let mut keyed: Vec<(OwnedValue, StandardJson<'a, W>)> = Vec::new();
-            for item in items {
-                match eval_single::<W, S>(f, item.clone(), optional) {
-                    QueryResult::One(v) => keyed.push((to_owned(&v), item)),
-                    QueryResult::Owned(v) => keyed.push((v, item)),
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    _ => keyed.push((OwnedValue::Null, item)),
-                }
-            }
+            let keyed = match keyed_by::<W, S>(f, elements, scope) {
+                Ok(keyed) => keyed,
+                Err(e) => return QueryResult::Error(e),
+            };
 
-            let max = keyed
+            // Ties keep the last element, as in jq
+            keyed
                 .into_iter()
                 .max_by(|(a, _), (b, _)| compare_values(a, b))
-                .map(|(_, v)| to_owned(&v))
-                .unwrap();
-            QueryResult::Owned(max)
+                .map_or(QueryResult::Owned(OwnedValue::Null), |(_, v)| {
+                    QueryResult::Owned(to_owned(&v))
+                })
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2244,293 +2689,322 @@ fn builtin_max_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 // Phase 5: String Functions
 // =============================================================================
 
-/// Builtin: ascii_downcase - lowercase ASCII characters
-fn builtin_ascii_downcase<W: Clone + AsRef<[u64]>>(
+/// Builtins: ascii_downcase / ascii_upcase - change the case of ASCII letters
+///
+/// Other characters, including non-ASCII letters, are left alone. A string
+/// with nothing to convert is passed through without copying.
+fn builtin_ascii_case<W: Clone + AsRef<[u64]>>(
+    upper: bool,
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let lowered: String = cow.chars().map(|c| c.to_ascii_lowercase()).collect();
-                QueryResult::Owned(OwnedValue::String(lowered))
-            } else {
-                QueryResult::Owned(OwnedValue::String(String::new()))
-            }
-        }
-        _ if optional => QueryResult::None,
+        StandardJson::String(s) => match s.as_str() {
+            Ok(cow) => match ascii_case(&cow, upper) {
+                Cow::Owned(converted) => QueryResult::Owned(OwnedValue::String(converted)),
+                Cow::Borrowed(_) => QueryResult::One(value.clone()),
+            },
+            Err(_) => QueryResult::Owned(OwnedValue::String(String::new())),
+        },
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     }
 }
 
-/// Builtin: ascii_upcase - uppercase ASCII characters
-fn builtin_ascii_upcase<W: Clone + AsRef<[u64]>>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let uppered: String = cow.chars().map(|c| c.to_ascii_uppercase()).collect();
-                QueryResult::Owned(OwnedValue::String(uppered))
-            } else {
-                QueryResult::Owned(OwnedValue::String(String::new()))
-            }
+/// `s` with its ASCII letters in upper (or lower) case; borrowed if it has
+/// none to convert.
+fn ascii_case(s: &str, upper: bool) -> Cow<'_, str> {
+    let convertible = |b: u8| {
+        if upper {
+            b.is_ascii_lowercase()
+        } else {
+            b.is_ascii_uppercase()
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+    };
+    if !s.bytes().any(convertible) {
+        Cow::Borrowed(s)
+    } else if upper {
+        Cow::Owned(s.to_ascii_uppercase())
+    } else {
+        Cow::Owned(s.to_ascii_lowercase())
     }
 }
 
-/// Builtin: ltrimstr(s) - remove prefix s
-fn builtin_ltrimstr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    prefix_expr: &Expr,
+/// Builtins: ltrimstr(s) / rtrimstr(s) - remove a prefix / suffix
+///
+/// As in jq, the input passes through unchanged if it does not start (end)
+/// with `s`, or if either is not a string.
+fn builtin_trimstr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    affix_expr: &Expr,
+    suffix: bool,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Get the prefix string
-    let prefix_result = eval_single::<W, S>(prefix_expr, value.clone(), optional);
-    let prefix = match result_to_owned(prefix_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
+    let affixes = match result_to_owned_all(eval_single::<W, S>(affix_expr, value.clone(), scope)) {
+        Ok(affixes) => affixes,
         Err(e) => return QueryResult::Error(e),
     };
 
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let result = if cow.starts_with(&prefix) {
-                    cow[prefix.len()..].to_string()
-                } else {
-                    cow.into_owned()
-                };
-                QueryResult::Owned(OwnedValue::String(result))
-            } else {
-                QueryResult::Owned(OwnedValue::String(String::new()))
+    let results = affixes
+        .iter()
+        .map(|affix| {
+            let trimmed = match (&value, affix) {
+                (StandardJson::String(s), OwnedValue::String(affix)) => {
+                    s.as_str().ok().and_then(|cow| {
+                        let rest = if suffix {
+                            cow.strip_suffix(affix.as_str())
+                        } else {
+                            cow.strip_prefix(affix.as_str())
+                        };
+                        rest.map(ToString::to_string)
+                    })
+                }
+                _ => None,
+            };
+            match trimmed {
+                Some(t) => QueryResult::Owned(OwnedValue::String(t)),
+                None => QueryResult::One(value.clone()),
             }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    }
+        })
+        .collect();
+    concat_results(results)
 }
 
-/// Builtin: rtrimstr(s) - remove suffix s
-fn builtin_rtrimstr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    suffix_expr: &Expr,
+/// Builtins: startswith(s) / endswith(s) - test for a prefix / suffix
+fn builtin_affix_test<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    affix_expr: &Expr,
+    suffix: bool,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Get the suffix string
-    let suffix_result = eval_single::<W, S>(suffix_expr, value.clone(), optional);
-    let suffix = match result_to_owned(suffix_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
+    let affixes = match result_to_owned_all(eval_single::<W, S>(affix_expr, value.clone(), scope)) {
+        Ok(affixes) => affixes,
         Err(e) => return QueryResult::Error(e),
     };
 
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let result = if cow.ends_with(&suffix) {
-                    cow[..cow.len() - suffix.len()].to_string()
-                } else {
-                    cow.into_owned()
-                };
-                QueryResult::Owned(OwnedValue::String(result))
-            } else {
-                QueryResult::Owned(OwnedValue::String(String::new()))
+    let mut results = Vec::with_capacity(affixes.len());
+    for affix in &affixes {
+        match (&value, affix) {
+            (StandardJson::String(s), OwnedValue::String(affix)) => {
+                let found = s.as_str().is_ok_and(|cow| {
+                    if suffix {
+                        cow.ends_with(affix.as_str())
+                    } else {
+                        cow.starts_with(affix.as_str())
+                    }
+                });
+                results.push(OwnedValue::Bool(found));
             }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    }
-}
-
-/// Builtin: startswith(s) - check if string starts with s
-fn builtin_startswith<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    prefix_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the prefix string
-    let prefix_result = eval_single::<W, S>(prefix_expr, value.clone(), optional);
-    let prefix = match result_to_owned(prefix_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                QueryResult::Owned(OwnedValue::Bool(cow.starts_with(&prefix)))
-            } else {
-                QueryResult::Owned(OwnedValue::Bool(false))
+            _ if scope.optional => {}
+            _ => {
+                let name = if suffix { "endswith" } else { "startswith" };
+                return QueryResult::Error(EvalError::new(format!(
+                    "{name}() requires string inputs"
+                )));
             }
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     }
-}
-
-/// Builtin: endswith(s) - check if string ends with s
-fn builtin_endswith<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    suffix_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the suffix string
-    let suffix_result = eval_single::<W, S>(suffix_expr, value.clone(), optional);
-    let suffix = match result_to_owned(suffix_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
-        Err(e) => return QueryResult::Error(e),
-    };
 
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                QueryResult::Owned(OwnedValue::Bool(cow.ends_with(&suffix)))
-            } else {
-                QueryResult::Owned(OwnedValue::Bool(false))
-            }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
     }
 }
 
-/// Builtin: split(s) - split string by separator
+/// Builtin: split(s) - split string on a literal separator
+///
+/// As in jq, an empty input splits into `[]` and an empty separator splits
+/// into characters; `split(s) | join(s)` gives back the input.
 fn builtin_split<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     sep_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Get the separator string
-    let sep_result = eval_single::<W, S>(sep_expr, value.clone(), optional);
-    let sep = match result_to_owned(sep_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
+    let seps = match result_to_owned_all(eval_single::<W, S>(sep_expr, value.clone(), scope)) {
+        Ok(seps) => seps,
         Err(e) => return QueryResult::Error(e),
     };
 
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                // jq: split("") returns each character as a separate element
-                // Rust's split("") includes empty strings at boundaries, so special-case it
-                let parts: Vec<OwnedValue> = if sep.is_empty() {
-                    cow.chars()
-                        .map(|c| OwnedValue::String(c.to_string()))
-                        .collect()
-                } else {
-                    cow.split(&sep)
-                        .map(|p| OwnedValue::String(p.to_string()))
-                        .collect()
-                };
-                QueryResult::Owned(OwnedValue::Array(parts))
-            } else {
-                QueryResult::Owned(OwnedValue::Array(vec![]))
+    let mut results = Vec::with_capacity(seps.len());
+    for sep in &seps {
+        let input = match &value {
+            StandardJson::String(s) => s.as_str().ok(),
+            _ => None,
+        };
+        match (input, sep) {
+            (Some(input), OwnedValue::String(sep)) => {
+                results.push(OwnedValue::Array(split_literal(&input, sep)));
+            }
+            _ if scope.optional => {}
+            _ => {
+                return QueryResult::Error(EvalError::new(
+                    "split input and separator must be strings",
+                ))
             }
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+    }
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
+}
+
+/// Split `input` on every occurrence of `sep`, the way jq's `split/1` does.
+fn split_literal(input: &str, sep: &str) -> Vec<OwnedValue> {
+    if input.is_empty() {
+        Vec::new()
+    } else if sep.is_empty() {
+        input
+            .chars()
+            .map(|c| OwnedValue::String(c.to_string()))
+            .collect()
+    } else {
+        input
+            .split(sep)
+            .map(|p| OwnedValue::String(p.to_string()))
+            .collect()
     }
 }
 
 /// Builtin: join(s) - join array elements with separator
+///
+/// Strings are joined as-is, numbers and booleans as their JSON text and
+/// nulls as empty strings; arrays and objects cannot be joined.
 fn builtin_join<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     sep_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Get the separator string
-    let sep_result = eval_single::<W, S>(sep_expr, value.clone(), optional);
-    let sep = match result_to_owned(sep_result) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "non-string")),
+    let seps = match result_to_owned_all(eval_single::<W, S>(sep_expr, value.clone(), scope)) {
+        Ok(seps) => seps,
         Err(e) => return QueryResult::Error(e),
     };
 
-    match value {
-        StandardJson::Array(elements) => {
-            let mut parts: Vec<String> = Vec::new();
-            for elem in elements {
-                match &elem {
-                    StandardJson::String(s) => {
-                        if let Ok(cow) = s.as_str() {
-                            parts.push(cow.into_owned());
-                        }
-                    }
-                    StandardJson::Null => {
-                        // Skip nulls in join
-                    }
-                    _ => {
-                        // For non-strings, convert to string representation
-                        parts.push(to_owned(&elem).to_json());
-                    }
-                }
+    let StandardJson::Array(elements) = &value else {
+        if scope.optional {
+            return QueryResult::None;
+        }
+        return QueryResult::Error(EvalError::new(format!(
+            "Cannot iterate over {}",
+            type_name(&value)
+        )));
+    };
+
+    let mut parts = Vec::new();
+    for elem in *elements {
+        match &elem {
+            StandardJson::String(s) => {
+                parts.push(s.as_str().map(Cow::into_owned).unwrap_or_default());
+            }
+            StandardJson::Null => parts.push(String::new()),
+            StandardJson::Number(_) | StandardJson::Bool(_) => {
+                parts.push(to_owned(&elem).to_json());
+            }
+            _ if scope.optional => return QueryResult::None,
+            _ => {
+                return QueryResult::Error(EvalError::new(format!(
+                    "Cannot join with {}",
+                    type_name(&elem)
+                )))
             }
-            QueryResult::Owned(OwnedValue::String(parts.join(&sep)))
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
+    }
+
+    let mut results = Vec::with_capacity(seps.len());
+    for sep in &seps {
+        match sep {
+            OwnedValue::String(sep) => results.push(OwnedValue::String(parts.join(sep))),
+            // A lone element needs no separator, whatever its type
+            _ if parts.len() < 2 => results.push(OwnedValue::String(parts.concat())),
+            _ if scope.optional => {}
+            _ => {
+                return QueryResult::Error(EvalError::new(format!(
+                    "string ({}) and {} ({}) cannot be added",
+                    OwnedValue::String(parts[0].clone()).to_json(),
+                    sep.type_name(),
+                    sep.to_json()
+                )))
+            }
+        }
+    }
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
     }
 }
 
-/// Builtin: contains(b) - check if input contains b
-fn builtin_contains<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+/// Builtin: contains(b) - check if input contains b, or with `inside` set,
+/// inside(b) - check if b contains input (`b | contains(.)`)
+///
+/// `b` may generate several values; each produces its own answer.
+fn builtin_containment<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     b_expr: &Expr,
+    inside: bool,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Get the value to check
-    let b_result = eval_single::<W, S>(b_expr, value.clone(), optional);
-    let b = match result_to_owned(b_result) {
-        Ok(v) => v,
+    let bs = match result_to_owned_all(eval_single::<W, S>(b_expr, value.clone(), scope)) {
+        Ok(bs) => bs,
         Err(e) => return QueryResult::Error(e),
     };
 
     let input = to_owned(&value);
-    QueryResult::Owned(OwnedValue::Bool(owned_contains(&input, &b)))
+    let mut results = Vec::with_capacity(bs.len());
+    for b in &bs {
+        let (haystack, needle) = if inside { (b, &input) } else { (&input, b) };
+        if !same_containment_kind(haystack, needle) {
+            if scope.optional {
+                continue;
+            }
+            return QueryResult::Error(EvalError::new(format!(
+                "{} ({}) and {} ({}) cannot have their containment checked",
+                haystack.type_name(),
+                haystack.to_json(),
+                needle.type_name(),
+                needle.to_json()
+            )));
+        }
+        results.push(OwnedValue::Bool(contains_value(haystack, needle)));
+    }
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
 }
 
-/// Check if `a` contains `b` (recursive containment check)
-fn owned_contains(a: &OwnedValue, b: &OwnedValue) -> bool {
+/// Whether jq checks containment between `a` and `b` rather than raising an
+/// error: only values of one type compare, and jq gives `true` and `false`
+/// different kinds.
+fn same_containment_kind(a: &OwnedValue, b: &OwnedValue) -> bool {
     match (a, b) {
-        // String contains string
-        (OwnedValue::String(a_str), OwnedValue::String(b_str)) => a_str.contains(b_str.as_str()),
-        // Array contains: all elements of b must be contained in a
-        (OwnedValue::Array(a_arr), OwnedValue::Array(b_arr)) => b_arr
-            .iter()
-            .all(|b_elem| a_arr.iter().any(|a_elem| owned_contains(a_elem, b_elem))),
-        // Object contains: all keys in b must exist in a with matching values
-        (OwnedValue::Object(a_obj), OwnedValue::Object(b_obj)) => b_obj.iter().all(|(k, b_val)| {
-            a_obj
-                .get(k)
-                .is_some_and(|a_val| owned_contains(a_val, b_val))
-        }),
-        // Scalars: equality
-        _ => a == b,
+        (OwnedValue::Bool(a), OwnedValue::Bool(b)) => a == b,
+        _ => a.type_name() == b.type_name(),
     }
 }
 
-/// Builtin: inside(b) - check if input is inside b
-fn builtin_inside<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    b_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the container value
-    let b_result = eval_single::<W, S>(b_expr, value.clone(), optional);
-    let b = match result_to_owned(b_result) {
-        Ok(v) => v,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    let input = to_owned(&value);
-    // inside is the inverse of contains: b contains input
-    QueryResult::Owned(OwnedValue::Bool(owned_contains(&b, &input)))
+/// Whether `haystack` contains `needle` under jq's containment rules.
+///
+/// Strings contain their substrings; arrays contain `needle` when every
+/// element of `needle` is contained in some element of `haystack`; objects
+/// when every key of `needle` is present with a contained value. Anything
+/// else, including a mismatch of types below the top level, falls back to
+/// equality. So `{"a":[1,2],"b":0} | contains({"a":[1]})` holds though the
+/// two are not `==`.
+fn contains_value(haystack: &OwnedValue, needle: &OwnedValue) -> bool {
+    match (haystack, needle) {
+        (OwnedValue::String(h), OwnedValue::String(n)) => h.contains(n.as_str()),
+        (OwnedValue::Array(h), OwnedValue::Array(n)) => n
+            .iter()
+            .all(|n_elem| h.iter().any(|h_elem| contains_value(h_elem, n_elem))),
+        (OwnedValue::Object(h), OwnedValue::Object(n)) => n
+            .iter()
+            .all(|(k, n_val)| h.get(k).is_some_and(|h_val| contains_value(h_val, n_val))),
+        _ => haystack == needle,
+    }
 }
 
 // =============================================================================
@@ -2540,7 +3014,7 @@ fn builtin_inside<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: first - first element (.[0])
 fn builtin_first<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => match elements.get(0) {
@@ -2557,7 +3031,7 @@ fn builtin_first<W: Clone + AsRef<[u64]>>(
 /// Builtin: last - last element (.[-1])
 fn builtin_last<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2579,7 +3053,7 @@ fn builtin_last<W: Clone + AsRef<[u64]>>(
 fn builtin_nth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     n_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // jq: null | nth(0) => null
     if matches!(value, StandardJson::Null) {
@@ -2587,7 +3061,7 @@ fn builtin_nth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 
     // Get the index
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
+    let n_result = eval_single::<W, S>(n_expr, value.clone(), scope);
     let n = match result_to_owned(n_result) {
         Ok(OwnedValue::Int(i)) => i,
         Ok(_) => return QueryResult::Error(EvalError::type_error("number", "non-number")),
@@ -2600,7 +3074,7 @@ fn builtin_nth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             // jq: [1,2] | nth(10) => null
             None => QueryResult::Owned(OwnedValue::Null),
         },
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2608,7 +3082,7 @@ fn builtin_nth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: reverse - reverse array
 fn builtin_reverse<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2634,7 +3108,7 @@ fn builtin_reverse<W: Clone + AsRef<[u64]>>(
 /// Builtin: flatten - flatten nested arrays (1 level)
 fn builtin_flatten<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
     depth: usize,
 ) -> QueryResult<'_, W> {
     match value {
@@ -2643,7 +3117,7 @@ fn builtin_flatten<W: Clone + AsRef<[u64]>>(
             let flattened = flatten_owned(items, depth);
             QueryResult::Owned(OwnedValue::Array(flattened))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2670,10 +3144,10 @@ fn flatten_owned(items: Vec<OwnedValue>, depth: usize) -> Vec<OwnedValue> {
 fn builtin_flatten_depth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     depth_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the depth
-    let depth_result = eval_single::<W, S>(depth_expr, value.clone(), optional);
+    let depth_result = eval_single::<W, S>(depth_expr, value.clone(), scope);
     let depth = match result_to_owned(depth_result) {
         Ok(OwnedValue::Int(d)) if d >= 0 => d as usize,
         Ok(OwnedValue::Int(_)) => {
@@ -2683,32 +3157,23 @@ fn builtin_flatten_depth<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         Err(e) => return QueryResult::Error(e),
     };
 
-    builtin_flatten::<W>(value, optional, depth)
+    builtin_flatten::<W>(value, scope, depth)
 }
 
 /// Builtin: group_by(f) - group by key function
 fn builtin_group_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Array(elements) => {
-            let items: Vec<StandardJson<'a, W>> = elements.collect();
-
-            // Compute keys for each item
-            let mut keyed: Vec<(OwnedValue, OwnedValue)> = Vec::new();
-            for item in items {
-                let key = match eval_single::<W, S>(f, item.clone(), optional) {
-                    QueryResult::One(v) => to_owned(&v),
-                    QueryResult::Owned(v) => v,
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    _ => OwnedValue::Null,
-                };
-                keyed.push((key, to_owned(&item)));
-            }
+            let mut keyed = match keyed_by::<W, S>(f, elements, scope) {
+                Ok(keyed) => keyed,
+                Err(e) => return QueryResult::Error(e),
+            };
 
-            // Sort by key
+            // Stable sort: equal keys keep their input order
             keyed.sort_by(|(a, _), (b, _)| compare_values(a, b));
 
             // Group consecutive items with same key
@@ -2719,13 +3184,13 @@ fn builtin_group_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             for (key, item) in keyed {
                 match &current_key {
                     Some(k) if compare_values(k, &key) == core::cmp::Ordering::Equal => {
-                        current_group.push(item);
+                        current_group.push(to_owned(&item));
                     }
                     _ => {
                         if !current_group.is_empty() {
                             groups.push(OwnedValue::Array(current_group));
                         }
-                        current_group = vec![item];
+                        current_group = vec![to_owned(&item)];
                         current_key = Some(key);
                     }
                 }
@@ -2736,7 +3201,7 @@ fn builtin_group_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
             QueryResult::Owned(OwnedValue::Array(groups))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2744,7 +3209,7 @@ fn builtin_group_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: unique - remove duplicates
 fn builtin_unique<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2758,7 +3223,7 @@ fn builtin_unique<W: Clone + AsRef<[u64]>>(
 
             QueryResult::Owned(OwnedValue::Array(items))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2767,34 +3232,25 @@ fn builtin_unique<W: Clone + AsRef<[u64]>>(
 fn builtin_unique_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Array(elements) => {
-            let items: Vec<StandardJson<'a, W>> = elements.collect();
-
-            // Compute keys for each item
-            let mut keyed: Vec<(OwnedValue, OwnedValue)> = Vec::new();
-            for item in items {
-                let key = match eval_single::<W, S>(f, item.clone(), optional) {
-                    QueryResult::One(v) => to_owned(&v),
-                    QueryResult::Owned(v) => v,
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    _ => OwnedValue::Null,
-                };
-                keyed.push((key, to_owned(&item)));
-            }
+            let mut keyed = match keyed_by::<W, S>(f, elements, scope) {
+                Ok(keyed) => keyed,
+                Err(e) => return QueryResult::Error(e),
+            };
 
-            // Sort by key
+            // Stable sort: equal keys keep their input order
             keyed.sort_by(|(a, _), (b, _)| compare_values(a, b));
 
             // Remove consecutive duplicates by key
             keyed.dedup_by(|(a, _), (b, _)| compare_values(a, b) == core::cmp::Ordering::Equal);
 
-            let result: Vec<OwnedValue> = keyed.into_iter().map(|(_, v)| v).collect();
+            let result: Vec<OwnedValue> = keyed.into_iter().map(|(_, v)| to_owned(&v)).collect();
             QueryResult::Owned(OwnedValue::Array(result))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2802,7 +3258,7 @@ fn builtin_unique_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: sort - sort array
 fn builtin_sort<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -2810,7 +3266,7 @@ fn builtin_sort<W: Clone + AsRef<[u64]>>(
             items.sort_by(compare_values);
             QueryResult::Owned(OwnedValue::Array(items))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2819,31 +3275,22 @@ fn builtin_sort<W: Clone + AsRef<[u64]>>(
 fn builtin_sort_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     match value {
         StandardJson::Array(elements) => {
-            let items: Vec<StandardJson<'a, W>> = elements.collect();
-
-            // Compute keys for each item
-            let mut keyed: Vec<(OwnedValue, OwnedValue)> = Vec::new();
-            for item in items {
-                let key = match eval_single::<W, S>(f, item.clone(), optional) {
-                    QueryResult::One(v) => to_owned(&v),
-                    QueryResult::Owned(v) => v,
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    _ => OwnedValue::Null,
-                };
-                keyed.push((key, to_owned(&item)));
-            }
+            let mut keyed = match keyed_by::<W, S>(f, elements, scope) {
+                Ok(keyed) => keyed,
+                Err(e) => return QueryResult::Error(e),
+            };
 
-            // Sort by key
+            // Stable sort: equal keys keep their input order
             keyed.sort_by(|(a, _), (b, _)| compare_values(a, b));
 
-            let result: Vec<OwnedValue> = keyed.into_iter().map(|(_, v)| v).collect();
+            let result: Vec<OwnedValue> = keyed.into_iter().map(|(_, v)| to_owned(&v)).collect();
             QueryResult::Owned(OwnedValue::Array(result))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2853,74 +3300,31 @@ fn builtin_sort_by<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 // =============================================================================
 
 /// Builtin: to_entries - {k:v} → [{key:k, value:v}]
+///
+/// Arrays produce their indices as keys, as in jq: `[a] → [{key:0, value:a}]`.
 fn builtin_to_entries<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match value {
-        StandardJson::Object(fields) => {
-            let mut entries: Vec<OwnedValue> = Vec::new();
-            for field in fields {
-                let key = if let StandardJson::String(k) = field.key() {
-                    if let Ok(cow) = k.as_str() {
-                        cow.into_owned()
-                    } else {
-                        continue;
-                    }
-                } else {
-                    continue;
-                };
-                let val = to_owned(&field.value());
-
-                let mut entry = IndexMap::new();
-                entry.insert("key".to_string(), OwnedValue::String(key));
-                entry.insert("value".to_string(), val);
-                entries.push(OwnedValue::Object(entry));
-            }
-            QueryResult::Owned(OwnedValue::Array(entries))
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("object", type_name(&value))),
+    match to_entries(value) {
+        Ok(entries) => QueryResult::Owned(OwnedValue::Array(entries)),
+        Err(_) if scope.optional => QueryResult::None,
+        Err(e) => QueryResult::Error(e),
     }
 }
 
 /// Builtin: from_entries - [{key:k, value:v}] → {k:v}
 fn builtin_from_entries<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
-        StandardJson::Array(elements) => {
-            let mut result = IndexMap::new();
-
-            for elem in elements {
-                // Each element should be an object with "key"/"name" and "value" fields
-                if let StandardJson::Object(fields) = elem {
-                    let owned = to_owned(&StandardJson::Object(fields));
-                    if let OwnedValue::Object(obj) = owned {
-                        // Try "key" first, then "name", then "k"
-                        let key = obj
-                            .get("key")
-                            .or_else(|| obj.get("name"))
-                            .or_else(|| obj.get("k"));
-
-                        // Try "value" first, then "v"
-                        let val = obj
-                            .get("value")
-                            .or_else(|| obj.get("v"))
-                            .cloned()
-                            .unwrap_or(OwnedValue::Null);
-
-                        if let Some(OwnedValue::String(k)) = key {
-                            result.insert(k.clone(), val);
-                        }
-                    }
-                }
-            }
-
-            QueryResult::Owned(OwnedValue::Object(result))
-        }
-        _ if optional => QueryResult::None,
+        StandardJson::Array(elements) => match from_entries(elements.map(|elem| to_owned(&elem))) {
+            Ok(obj) => QueryResult::Owned(OwnedValue::Object(obj)),
+            Err(_) if scope.optional => QueryResult::None,
+            Err(e) => QueryResult::Error(e),
+        },
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -2929,79 +3333,117 @@ fn builtin_from_entries<W: Clone + AsRef<[u64]>>(
 fn builtin_with_entries<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
+    let entries = match to_entries(value) {
+        Ok(entries) => entries,
+        Err(_) if scope.optional => return QueryResult::None,
+        Err(e) => return QueryResult::Error(e),
+    };
+
+    // Apply f to each entry
+    let mut transformed: Vec<OwnedValue> = Vec::new();
+    for entry in entries {
+        let entry_json = owned_to_json_bytes(&entry);
+        let index = crate::json::JsonIndex::build(&entry_json);
+        let cursor = index.root(&entry_json);
+
+        match eval_single::<Vec<u64>, S>(f, cursor.value(), scope).materialize_cursor() {
+            QueryResult::One(v) => transformed.push(to_owned(&v)),
+            QueryResult::OneCursor(_) => unreachable!(),
+            QueryResult::Owned(v) => transformed.push(v),
+            QueryResult::Many(vs) => {
+                for v in vs {
+                    transformed.push(to_owned(&v));
+                }
+            }
+            QueryResult::ManyOwned(vs) => transformed.extend(vs),
+            QueryResult::None => {}
+            QueryResult::Error(e) => return QueryResult::Error(e),
+            QueryResult::Break(label) => return QueryResult::Break(label),
+        }
+    }
+
+    match from_entries(transformed) {
+        Ok(obj) => QueryResult::Owned(OwnedValue::Object(obj)),
+        Err(_) if scope.optional => QueryResult::None,
+        Err(e) => QueryResult::Error(e),
+    }
+}
+
+/// Entries of an object (string keys) or array (index keys), in document order.
+fn to_entries<W: Clone + AsRef<[u64]>>(
+    value: StandardJson<'_, W>,
+) -> Result<Vec<OwnedValue>, EvalError> {
+    let entry = |key: OwnedValue, value: OwnedValue| {
+        let mut entry = IndexMap::new();
+        entry.insert("key".to_string(), key);
+        entry.insert("value".to_string(), value);
+        OwnedValue::Object(entry)
+    };
+
     match value {
-        StandardJson::Object(fields) => {
-            // Convert to entries
-            let mut entries: Vec<OwnedValue> = Vec::new();
-            for field in fields {
-                let key = if let StandardJson::String(k) = field.key() {
-                    if let Ok(cow) = k.as_str() {
-                        cow.into_owned()
-                    } else {
-                        continue;
-                    }
-                } else {
-                    continue;
+        StandardJson::Object(fields) => Ok(fields
+            .filter_map(|field| {
+                let StandardJson::String(k) = field.key() else {
+                    return None;
                 };
-                let val = to_owned(&field.value());
+                let key = k.as_str().ok()?.into_owned();
+                Some(entry(OwnedValue::String(key), to_owned(&field.value())))
+            })
+            .collect()),
+        StandardJson::Array(elements) => Ok(elements
+            .enumerate()
+            .map(|(i, elem)| entry(OwnedValue::Int(i as i64), to_owned(&elem)))
+            .collect()),
+        _ => Err(EvalError::type_error("object", type_name(&value))),
+    }
+}
 
-                let mut entry = IndexMap::new();
-                entry.insert("key".to_string(), OwnedValue::String(key));
-                entry.insert("value".to_string(), val);
-                entries.push(OwnedValue::Object(entry));
-            }
+/// Build an object from entries, following jq 1.7's `from_entries`.
+///
+/// The key is `.key`, or when that is missing or null the first truthy of
+/// `.k`, `.name`, `.Name`, `.K`, falling back to `.Key`. Non-string keys are
+/// converted with `tojson` (so `null` becomes `"null"` and `1` becomes `"1"`).
+/// The value is `.value` if present, else `.v`, else `.Value` (or null).
+fn from_entries(
+    entries: impl IntoIterator<Item = OwnedValue>,
+) -> Result<IndexMap<String, OwnedValue>, EvalError> {
+    let mut result = IndexMap::new();
 
-            // Apply f to each entry
-            let mut transformed: Vec<OwnedValue> = Vec::new();
-            for entry in entries {
-                let entry_json = owned_to_json_bytes(&entry);
-                let index = crate::json::JsonIndex::build(&entry_json);
-                let cursor = index.root(&entry_json);
+    for entry in entries {
+        let OwnedValue::Object(obj) = entry else {
+            return Err(EvalError::new(format!(
+                "Cannot index {} with \"key\"",
+                entry.type_name()
+            )));
+        };
 
-                match eval_single::<Vec<u64>, S>(f, cursor.value(), optional).materialize_cursor() {
-                    QueryResult::One(v) => transformed.push(to_owned(&v)),
-                    QueryResult::OneCursor(_) => unreachable!(),
-                    QueryResult::Owned(v) => transformed.push(v),
-                    QueryResult::Many(vs) => {
-                        for v in vs {
-                            transformed.push(to_owned(&v));
-                        }
-                    }
-                    QueryResult::ManyOwned(vs) => transformed.extend(vs),
-                    QueryResult::None => {}
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    QueryResult::Break(label) => return QueryResult::Break(label),
-                }
-            }
+        let key = match obj.get("key") {
+            Some(key) if !matches!(key, OwnedValue::Null) => key,
+            _ => ["k", "name", "Name", "K"]
+                .iter()
+                .filter_map(|name| obj.get(*name))
+                .find(|v| v.is_truthy())
+                .or_else(|| obj.get("Key"))
+                .unwrap_or(&OwnedValue::Null),
+        };
+        let key = match key {
+            OwnedValue::String(s) => s.clone(),
+            other => other.to_json(),
+        };
 
-            // Convert back from entries
-            let mut result = IndexMap::new();
-            for entry in transformed {
-                if let OwnedValue::Object(obj) = entry {
-                    let key = obj
-                        .get("key")
-                        .or_else(|| obj.get("name"))
-                        .or_else(|| obj.get("k"));
-
-                    let val = obj
-                        .get("value")
-                        .or_else(|| obj.get("v"))
-                        .cloned()
-                        .unwrap_or(OwnedValue::Null);
-
-                    if let Some(OwnedValue::String(k)) = key {
-                        result.insert(k.clone(), val);
-                    }
-                }
-            }
+        let value = obj
+            .get("value")
+            .or_else(|| obj.get("v"))
+            .or_else(|| obj.get("Value"))
+            .cloned()
+            .unwrap_or(OwnedValue::Null);
 
-            QueryResult::Owned(OwnedValue::Object(result))
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("object", type_name(&value))),
+        result.insert(key, value);
     }
+
+    Ok(result)
 }
 
 /// Convert an OwnedValue to JSON bytes for re-parsing
@@ -3017,7 +3459,7 @@ fn owned_to_json_bytes(value: &OwnedValue) -> Vec<u8> {
 fn eval_string_interpolation<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     parts: &[StringPart],
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let mut result = String::new();
 
@@ -3025,7 +3467,7 @@ fn eval_string_interpolation<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         match part {
             StringPart::Literal(s) => result.push_str(s),
             StringPart::Expr(expr) => {
-                let val = eval_single::<W, S>(expr, value.clone(), optional).materialize_cursor();
+                let val = eval_single::<W, S>(expr, value.clone(), scope).materialize_cursor();
                 let s = match val {
                     QueryResult::One(v) => owned_to_string(&to_owned(&v)),
                     QueryResult::OneCursor(_) => unreachable!(),
@@ -3073,22 +3515,22 @@ fn owned_to_string(value: &OwnedValue) -> String {
 fn eval_format<W: Clone + AsRef<[u64]>>(
     format_type: FormatType,
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     let owned = to_owned(&value);
 
     let result = match format_type {
         FormatType::Text => format_text(&owned),
         FormatType::Json => format_json(&owned),
-        FormatType::Uri => format_uri(&owned, optional),
-        FormatType::Csv => format_csv(&owned, optional),
-        FormatType::Tsv => format_tsv(&owned, optional),
-        FormatType::Dsv(delimiter) => format_dsv(&owned, &delimiter, optional),
-        FormatType::Base64 => format_base64(&owned, optional),
-        FormatType::Base64d => format_base64d(&owned, optional),
-        FormatType::Html => format_html(&owned, optional),
-        FormatType::Sh => format_sh(&owned, optional),
-        FormatType::Urid => format_urid(&owned, optional),
+        FormatType::Uri => format_uri(&owned, scope),
+        FormatType::Csv => format_csv(&owned, scope),
+        FormatType::Tsv => format_tsv(&owned, scope),
+        FormatType::Dsv(delimiter) => format_dsv(&owned, &delimiter, scope),
+        FormatType::Base64 => format_base64(&owned, scope),
+        FormatType::Base64d => format_base64d(&owned, scope),
+        FormatType::Html => format_html(&owned, scope),
+        FormatType::Sh => format_sh(&owned, scope),
+        FormatType::Urid => format_urid(&owned, scope),
         FormatType::Yaml => format_yaml(&owned),
         FormatType::Props => format_props(&owned),
     };
@@ -3110,7 +3552,7 @@ fn format_json(value: &OwnedValue) -> Result<String, EvalError> {
 }
 
 /// @uri - URI/percent encode
-fn format_uri(value: &OwnedValue, _optional: bool) -> Result<String, EvalError> {
+fn format_uri(value: &OwnedValue, _scope: Scope) -> Result<String, EvalError> {
     // jq converts non-strings to strings first (e.g., 42 | @uri => "42")
     let s = match value {
         OwnedValue::String(s) => s.clone(),
@@ -3141,7 +3583,7 @@ fn format_uri(value: &OwnedValue, _optional: bool) -> Result<String, EvalError>
 }
 
 /// @urid - URI/percent decode
-fn format_urid(value: &OwnedValue, optional: bool) -> Result<String, EvalError> {
+fn format_urid(value: &OwnedValue, scope: Scope) -> Result<String, EvalError> {
     match value {
         OwnedValue::String(s) => {
             let mut result = String::new();
@@ -3166,170 +3608,161 @@ fn format_urid(value: &OwnedValue, optional: bool) -> Result<String, EvalError>
             }
             Ok(result)
         }
-        _ if optional => Ok(String::new()),
+        _ if scope.optional => Ok(String::new()),
         _ => Err(EvalError::type_error("string", value.type_name())),
     }
 }
 
-/// @csv - CSV format (for arrays)
-fn format_csv(value: &OwnedValue, optional: bool) -> Result<String, EvalError> {
-    match value {
-        OwnedValue::Array(arr) => {
-            let parts: Vec<String> = arr
-                .iter()
-                .map(|v| match v {
-                    // jq unconditionally double-quotes every string field
-                    // (inner `"` doubled), regardless of whether it contains a
-                    // delimiter — see #306.
-                    OwnedValue::String(s) => format!("\"{}\"", s.replace('"', "\"\"")),
-                    OwnedValue::Null => String::new(),
-                    other => owned_to_string(other),
-                })
-                .collect();
-            Ok(parts.join(","))
+/// Format an array as one delimited row, as `@csv`, `@tsv` and `@dsv` do.
+///
+/// Strings go through `quote`; numbers and booleans are written bare and
+/// null as an empty field. Nested arrays and objects are an error, as in jq.
+fn format_row(
+    value: &OwnedValue,
+    format: &str,
+    delimiter: &str,
+    scope: Scope,
+    quote: impl Fn(&str) -> String,
+) -> Result<String, EvalError> {
+    let arr = match value {
+        OwnedValue::Array(arr) => arr,
+        _ if scope.optional => return Ok(String::new()),
+        other => {
+            return Err(EvalError::new(format!(
+                "{} ({}) cannot be {format}-formatted, only an array can be",
+                other.type_name(),
+                other.to_json()
+            )))
         }
-        _ if optional => Ok(String::new()),
-        _ => Err(EvalError::type_error("array", value.type_name())),
-    }
-}
+    };
 
-/// @tsv - TSV format (for arrays)
-fn format_tsv(value: &OwnedValue, optional: bool) -> Result<String, EvalError> {
-    match value {
-        OwnedValue::Array(arr) => {
-            let parts: Vec<String> = arr
-                .iter()
-                .map(|v| match v {
-                    OwnedValue::String(s) => s
-                        .replace('\\', "\\\\")
-                        .replace('\t', "\\t")
-                        .replace('\n', "\\n")
-                        .replace('\r', "\\r"),
-                    OwnedValue::Null => String::new(),
-                    other => owned_to_string(other),
-                })
-                .collect();
-            Ok(parts.join("\t"))
+    let mut row = String::new();
+    for (i, field) in arr.iter().enumerate() {
+        if i > 0 {
+            row.push_str(delimiter);
+        }
+        match field {
+            OwnedValue::String(s) => row.push_str(&quote(s)),
+            OwnedValue::Null => {}
+            OwnedValue::Array(_) | OwnedValue::Object(_) => {
+                return Err(EvalError::new(format!(
+                    "{} ({}) is not valid in a {format} row",
+                    field.type_name(),
+                    field.to_json()
+                )))
+            }
+            other => row.push_str(&owned_to_string(other)),
         }
-        _ if optional => Ok(String::new()),
-        _ => Err(EvalError::type_error("array", value.type_name())),
     }
+    Ok(row)
 }
 
-/// @dsv(delimiter) - Generic DSV format with custom delimiter (for arrays)
-fn format_dsv(value: &OwnedValue, delimiter: &str, optional: bool) -> Result<String, EvalError> {
-    match value {
-        OwnedValue::Array(arr) => {
-            let parts: Vec<String> = arr
-                .iter()
-                .map(|v| match v {
-                    // Match @csv: always double-quote string fields (inner `"`
-                    // doubled) so @dsv(",") stays byte-identical to @csv — #306.
-                    OwnedValue::String(s) => format!("\"{}\"", s.replace('"', "\"\"")),
-                    OwnedValue::Null => String::new(),
-                    other => owned_to_string(other),
-                })
-                .collect();
-            Ok(parts.join(delimiter))
-        }
-        _ if optional => Ok(String::new()),
-        _ => Err(EvalError::type_error("array", value.type_name())),
-    }
+/// @csv - CSV format (for arrays)
+fn format_csv(value: &OwnedValue, scope: Scope) -> Result<String, EvalError> {
+    // jq unconditionally double-quotes every string field (inner `"`
+    // doubled), regardless of whether it contains a delimiter — see #306.
+    format_row(value, "csv", ",", scope, quote_csv_field)
 }
 
-/// @base64 - Base64 encode (simple implementation without external crate)
-fn format_base64(value: &OwnedValue, optional: bool) -> Result<String, EvalError> {
-    match value {
-        OwnedValue::String(s) => {
-            // Simple base64 encoding
-            const ALPHABET: &[u8] =
-                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-            let bytes = s.as_bytes();
-            let mut result = String::new();
+/// @tsv - TSV format (for arrays)
+fn format_tsv(value: &OwnedValue, scope: Scope) -> Result<String, EvalError> {
+    format_row(value, "tsv", "\t", scope, |s| {
+        s.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    })
+}
 
-            for chunk in bytes.chunks(3) {
-                let b0 = chunk[0] as u32;
-                let b1 = chunk.get(1).map_or(0, |&b| b as u32);
-                let b2 = chunk.get(2).map_or(0, |&b| b as u32);
+/// @dsv(delimiter) - Generic DSV format with custom delimiter (for arrays)
+fn format_dsv(value: &OwnedValue, delimiter: &str, scope: Scope) -> Result<String, EvalError> {
+    // Match @csv: always double-quote string fields (inner `"` doubled) so
+    // @dsv(",") stays byte-identical to @csv — #306.
+    format_row(value, "dsv", delimiter, scope, quote_csv_field)
+}
 
-                let triple = (b0 << 16) | (b1 << 8) | b2;
+fn quote_csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
 
-                result.push(ALPHABET[((triple >> 18) & 0x3F) as usize] as char);
-                result.push(ALPHABET[((triple >> 12) & 0x3F) as usize] as char);
+/// @base64 - Base64 encode (RFC 4648, padded); non-strings are encoded as JSON
+fn format_base64(value: &OwnedValue, _scope: Scope) -> Result<String, EvalError> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
-                if chunk.len() > 1 {
-                    result.push(ALPHABET[((triple >> 6) & 0x3F) as usize] as char);
-                } else {
-                    result.push('=');
-                }
+    let text = owned_to_string(value);
+    let mut result = String::with_capacity(text.len().div_ceil(3) * 4);
+    for chunk in text.as_bytes().chunks(3) {
+        let b0 = u32::from(chunk[0]);
+        let b1 = chunk.get(1).map_or(0, |&b| u32::from(b));
+        let b2 = chunk.get(2).map_or(0, |&b| u32::from(b));
+        let triple = (b0 << 16) | (b1 << 8) | b2;
 
-                if chunk.len() > 2 {
-                    result.push(ALPHABET[(triple & 0x3F) as usize] as char);
-                } else {
-                    result.push('=');
-                }
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                result.push(ALPHABET[((triple >> shift) & 0x3F) as usize] as char);
+            } else {
+                result.push('=');
             }
-
-            Ok(result)
         }
-        _ if optional => Ok(String::new()),
-        _ => Err(EvalError::type_error("string", value.type_name())),
     }
+    Ok(result)
 }
 
 /// @base64d - Base64 decode
-fn format_base64d(value: &OwnedValue, optional: bool) -> Result<String, EvalError> {
-    match value {
-        OwnedValue::String(s) => {
-            // Simple base64 decoding
-            fn decode_char(c: u8) -> Option<u8> {
-                match c {
-                    b'A'..=b'Z' => Some(c - b'A'),
-                    b'a'..=b'z' => Some(c - b'a' + 26),
-                    b'0'..=b'9' => Some(c - b'0' + 52),
-                    b'+' => Some(62),
-                    b'/' => Some(63),
-                    b'=' => Some(0), // Padding
-                    _ => None,
-                }
-            }
-
-            let s = s.replace(|c: char| c.is_whitespace(), "");
-            let bytes: Vec<u8> = s.bytes().collect();
-            let mut result = Vec::new();
-
-            for chunk in bytes.chunks(4) {
-                if chunk.len() < 4 {
-                    break;
-                }
-
-                let a = decode_char(chunk[0]).ok_or_else(|| EvalError::new("invalid base64"))?;
-                let b = decode_char(chunk[1]).ok_or_else(|| EvalError::new("invalid base64"))?;
-                let c_val =
-                    decode_char(chunk[2]).ok_or_else(|| EvalError::new("invalid base64"))?;
-                let d = decode_char(chunk[3]).ok_or_else(|| EvalError::new("invalid base64"))?;
+///
+/// Padding is optional and whitespace is ignored. Decoded bytes that are not
+/// valid UTF-8 become U+FFFD, as in jq.
+fn format_base64d(value: &OwnedValue, _scope: Scope) -> Result<String, EvalError> {
+    fn decode_char(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some(u32::from(c - b'A')),
+            b'a'..=b'z' => Some(u32::from(c - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(c - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
 
-                let triple =
-                    ((a as u32) << 18) | ((b as u32) << 12) | ((c_val as u32) << 6) | (d as u32);
+    let text = owned_to_string(value);
+    let invalid = || {
+        EvalError::new(format!(
+            "{} ({}) is not valid base64 data",
+            value.type_name(),
+            value.to_json()
+        ))
+    };
 
-                result.push(((triple >> 16) & 0xFF) as u8);
-                if chunk[2] != b'=' {
-                    result.push(((triple >> 8) & 0xFF) as u8);
-                }
-                if chunk[3] != b'=' {
-                    result.push((triple & 0xFF) as u8);
-                }
-            }
+    let digits = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<Vec<u8>>();
+    // Padding may only end the input
+    let end = digits
+        .iter()
+        .position(|&c| c == b'=')
+        .unwrap_or(digits.len());
+    if digits[end..].iter().any(|&c| c != b'=') {
+        return Err(invalid());
+    }
 
-            String::from_utf8(result).map_err(|_| EvalError::new("invalid UTF-8 in decoded base64"))
+    let mut result = Vec::with_capacity(end / 4 * 3 + 2);
+    for chunk in digits[..end].chunks(4) {
+        let mut group = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            group |= decode_char(c).ok_or_else(invalid)? << (18 - 6 * i);
         }
-        _ if optional => Ok(String::new()),
-        _ => Err(EvalError::type_error("string", value.type_name())),
+        // n digits carry n * 6 bits: one byte fewer than digits (a lone
+        // trailing digit has no complete byte)
+        let bytes = group.to_be_bytes();
+        result.extend_from_slice(&bytes[1..chunk.len()]);
     }
+
+    Ok(String::from_utf8_lossy(&result).into_owned())
 }
 
 /// @html - HTML entity escape
-fn format_html(value: &OwnedValue, _optional: bool) -> Result<String, EvalError> {
+fn format_html(value: &OwnedValue, _scope: Scope) -> Result<String, EvalError> {
     // jq converts non-strings to strings first (e.g., 42 | @html => "42")
     let s = match value {
         OwnedValue::String(s) => s.clone(),
@@ -3388,7 +3821,7 @@ fn shell_quote_value(value: &OwnedValue) -> String {
 }
 
 /// @sh - Shell quote
-fn format_sh(value: &OwnedValue, _optional: bool) -> Result<String, EvalError> {
+fn format_sh(value: &OwnedValue, _scope: Scope) -> Result<String, EvalError> {
     match value {
         OwnedValue::String(s) => {
             // Use single quotes and escape single quotes
@@ -3606,60 +4039,57 @@ fn yaml_quote_string(s: &str) -> String {
 // Phase 6: Type Conversion Builtins
 // =============================================================================
 
-/// Builtin: tostring - convert any value to string
+/// Builtin: tostring - strings pass through, anything else becomes its JSON text
 fn builtin_tostring<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
-    let owned = to_owned(&value);
-    let s = match owned {
-        OwnedValue::String(s) => s,
-        OwnedValue::Null => "null".to_string(),
-        OwnedValue::Bool(true) => "true".to_string(),
-        OwnedValue::Bool(false) => "false".to_string(),
-        OwnedValue::Int(n) => format!("{n}"),
-        OwnedValue::Float(f) => format!("{f}"),
-        OwnedValue::Array(_) | OwnedValue::Object(_) => owned.to_json(),
-    };
-    QueryResult::Owned(OwnedValue::String(s))
+    match &value {
+        StandardJson::String(_) => QueryResult::One(value),
+        _ => QueryResult::Owned(OwnedValue::String(to_owned(&value).to_json())),
+    }
 }
 
-/// Builtin: tonumber - convert string to number
+/// Builtin: tonumber - parse a string as a JSON number
+///
+/// The string must be a JSON number as the validator accepts it (surrounding
+/// whitespace allowed): `"0x10"`, `".5"`, `"+1"` and `"nan"` are errors.
 fn builtin_tonumber<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
-        StandardJson::Number(n) => {
-            // Already a number, return as-is
-            if let Ok(i) = n.as_i64() {
-                QueryResult::Owned(OwnedValue::Int(i))
-            } else if let Ok(f) = n.as_f64() {
-                QueryResult::Owned(OwnedValue::Float(f))
-            } else {
-                QueryResult::Owned(OwnedValue::Int(0))
-            }
-        }
+        StandardJson::Number(_) => QueryResult::One(value),
         StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let s = cow.as_ref().trim();
-                if let Ok(i) = s.parse::<i64>() {
-                    QueryResult::Owned(OwnedValue::Int(i))
-                } else if let Ok(f) = s.parse::<f64>() {
-                    QueryResult::Owned(OwnedValue::Float(f))
-                } else if optional {
-                    QueryResult::None
-                } else {
-                    QueryResult::Error(EvalError::new(format!("cannot parse '{s}' as number")))
+            let text = s.as_str().unwrap_or_default();
+            match number_from_text(&text) {
+                Some(n) => QueryResult::Owned(n),
+                None if scope.optional => QueryResult::None,
+                None => {
+                    QueryResult::Error(EvalError::new(format!("cannot parse '{text}' as number")))
                 }
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
             }
         }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string or number", type_name(&value))),
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::new(format!(
+            "{} ({}) cannot be parsed as a number",
+            type_name(&value),
+            to_owned(&value).to_json()
+        ))),
+    }
+}
+
+/// Parse `text` as a JSON number, using the rules of [`crate::json::validate`].
+pub(super) fn number_from_text(text: &str) -> Option<OwnedValue> {
+    let trimmed = text.trim_matches(|c| matches!(c, ' ' | '\t' | '\n' | '\r'));
+    if !trimmed.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+        || crate::json::validate::validate(trimmed.as_bytes()).is_err()
+    {
+        return None;
+    }
+    match trimmed.parse::<i64>() {
+        Ok(i) => Some(OwnedValue::Int(i)),
+        Err(_) => trimmed.parse::<f64>().ok().map(OwnedValue::Float),
     }
 }
 
@@ -3667,7 +4097,7 @@ fn builtin_tonumber<W: Clone + AsRef<[u64]>>(
 /// Accepts: true, false, "true", "false"
 fn builtin_toboolean<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::Bool(b) => QueryResult::Owned(OwnedValue::Bool(*b)),
@@ -3676,18 +4106,18 @@ fn builtin_toboolean<W: Clone + AsRef<[u64]>>(
                 match cow.as_ref() {
                     "true" => QueryResult::Owned(OwnedValue::Bool(true)),
                     "false" => QueryResult::Owned(OwnedValue::Bool(false)),
-                    _ if optional => QueryResult::None,
+                    _ if scope.optional => QueryResult::None,
                     other => QueryResult::Error(EvalError::new(format!(
                         "string ({other:?}) cannot be parsed as a boolean"
                     ))),
                 }
-            } else if optional {
+            } else if scope.optional {
                 QueryResult::None
             } else {
                 QueryResult::Error(EvalError::new("invalid string"))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new(format!(
             "{} cannot be parsed as a boolean",
             type_name(&value)
@@ -3700,10 +4130,10 @@ fn builtin_skip<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     n_expr: &Expr,
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate n
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
+    let n_result = eval_single::<W, S>(n_expr, value.clone(), scope);
     let n = match n_result {
         QueryResult::One(v) => {
             if let StandardJson::Number(num) = v {
@@ -3719,7 +4149,7 @@ fn builtin_skip<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     };
 
     // Evaluate expr and skip first n results
-    let result = eval_single::<W, S>(expr, value, optional);
+    let result = eval_single::<W, S>(expr, value, scope);
     match result {
         QueryResult::One(v) => {
             if n == 0 {
@@ -3772,640 +4202,275 @@ fn builtin_skip<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: tojson - convert any value to JSON string
 fn builtin_tojson<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
-    let owned = to_owned(&value);
-    let json_string = owned.to_json();
+    let json_string = crate::json::format::compact_serialize(&to_owned(&value));
     QueryResult::Owned(OwnedValue::String(json_string))
 }
 
 /// Builtin: fromjson - parse JSON string to value
 fn builtin_fromjson<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let json_str = cow.as_ref();
-                // Parse the JSON string
-                match parse_json_string(json_str) {
-                    Ok(owned) => QueryResult::Owned(owned),
-                    Err(_) if optional => QueryResult::None,
-                    Err(e) => QueryResult::Error(EvalError::new(format!("fromjson: {e}"))),
-                }
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+    let parsed = match &value {
+        StandardJson::String(s) => match s.as_str() {
+            Ok(text) => parse_json_text(&text),
+            Err(_) => Err(EvalError::new("invalid string")),
+        },
+        _ => Err(EvalError::type_error("string", type_name(&value))),
+    };
+    match parsed {
+        Ok(owned) => QueryResult::Owned(owned),
+        Err(_) if scope.optional => QueryResult::None,
+        Err(e) => QueryResult::Error(e),
     }
 }
 
-/// Parse a JSON string into an OwnedValue
-fn parse_json_string(s: &str) -> Result<OwnedValue, String> {
-    let bytes = s.as_bytes();
-    let mut pos = 0;
-
-    // Skip whitespace
-    while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'\n' | b'\r') {
-        pos += 1;
-    }
+/// Parse JSON text into an owned value.
+///
+/// The text is validated first, so malformed input (including trailing values
+/// such as `1 2`) is reported with the validator's message and position, then
+/// read through the same index and cursor the evaluator uses for its input.
+fn parse_json_text(text: &str) -> Result<OwnedValue, EvalError> {
+    use crate::json::JsonIndex;
 
-    if pos >= bytes.len() {
-        return Err("empty input".to_string());
+    if let Err(e) = crate::json::validate::validate(text.as_bytes()) {
+        return Err(EvalError::new(format!("{e} (while parsing '{text}')")));
     }
-
-    parse_json_value(bytes, &mut pos)
+    let index = JsonIndex::build(text.as_bytes());
+    Ok(to_owned(&index.root(text.as_bytes()).value()))
 }
 
-/// Parse a JSON value starting at the given position
-fn parse_json_value(bytes: &[u8], pos: &mut usize) -> Result<OwnedValue, String> {
-    // Skip whitespace
-    while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-        *pos += 1;
-    }
-
-    if *pos >= bytes.len() {
-        return Err("unexpected end of input".to_string());
-    }
-
-    match bytes[*pos] {
-        b'n' => {
-            // null
-            if bytes[*pos..].starts_with(b"null") {
-                *pos += 4;
-                Ok(OwnedValue::Null)
-            } else {
-                Err("expected 'null'".to_string())
-            }
-        }
-        b't' => {
-            // true
-            if bytes[*pos..].starts_with(b"true") {
-                *pos += 4;
-                Ok(OwnedValue::Bool(true))
-            } else {
-                Err("expected 'true'".to_string())
-            }
-        }
-        b'f' => {
-            // false
-            if bytes[*pos..].starts_with(b"false") {
-                *pos += 5;
-                Ok(OwnedValue::Bool(false))
-            } else {
-                Err("expected 'false'".to_string())
-            }
-        }
-        b'"' => {
-            // string
-            parse_json_string_value(bytes, pos)
-        }
-        b'[' => {
-            // array
-            parse_json_array(bytes, pos)
-        }
-        b'{' => {
-            // object
-            parse_json_object(bytes, pos)
-        }
-        b'-' | b'0'..=b'9' => {
-            // number
-            parse_json_number(bytes, pos)
+/// Builtin: explode - string to array of codepoints
+fn builtin_explode<W: Clone + AsRef<[u64]>>(
+    value: StandardJson<'_, W>,
+    scope: Scope,
+) -> QueryResult<'_, W> {
+    match &value {
+        StandardJson::String(s) => {
+            let codepoints = s
+                .as_str()
+                .unwrap_or_default()
+                .chars()
+                .map(|c| OwnedValue::Int(i64::from(u32::from(c))))
+                .collect();
+            QueryResult::Owned(OwnedValue::Array(codepoints))
         }
-        c => Err(format!("unexpected character: '{}'", c as char)),
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::new("explode input must be a string")),
     }
 }
 
-/// Parse a JSON string value
-fn parse_json_string_value(bytes: &[u8], pos: &mut usize) -> Result<OwnedValue, String> {
-    if bytes[*pos] != b'"' {
-        return Err("expected '\"'".to_string());
-    }
-    *pos += 1;
-
-    let mut result = String::new();
-    while *pos < bytes.len() {
-        match bytes[*pos] {
-            b'"' => {
-                *pos += 1;
-                return Ok(OwnedValue::String(result));
-            }
-            b'\\' => {
-                *pos += 1;
-                if *pos >= bytes.len() {
-                    return Err("unexpected end of string".to_string());
-                }
-                match bytes[*pos] {
-                    b'"' => result.push('"'),
-                    b'\\' => result.push('\\'),
-                    b'/' => result.push('/'),
-                    b'b' => result.push('\x08'),
-                    b'f' => result.push('\x0C'),
-                    b'n' => result.push('\n'),
-                    b'r' => result.push('\r'),
-                    b't' => result.push('\t'),
-                    b'u' => {
-                        // Unicode escape
-                        *pos += 1;
-                        if *pos + 4 > bytes.len() {
-                            return Err("invalid unicode escape".to_string());
-                        }
-                        let hex = core::str::from_utf8(&bytes[*pos..*pos + 4])
-                            .map_err(|_| "invalid unicode escape")?;
-                        let codepoint =
-                            u32::from_str_radix(hex, 16).map_err(|_| "invalid unicode escape")?;
-
-                        // Handle surrogate pairs
-                        if (0xD800..=0xDBFF).contains(&codepoint) {
-                            // High surrogate - look for low surrogate
-                            *pos += 4;
-                            if *pos + 6 <= bytes.len()
-                                && bytes[*pos] == b'\\'
-                                && bytes[*pos + 1] == b'u'
-                            {
-                                let hex2 = core::str::from_utf8(&bytes[*pos + 2..*pos + 6])
-                                    .map_err(|_| "invalid unicode escape")?;
-                                let low = u32::from_str_radix(hex2, 16)
-                                    .map_err(|_| "invalid unicode escape")?;
-                                if (0xDC00..=0xDFFF).contains(&low) {
-                                    // Valid surrogate pair
-                                    let combined =
-                                        0x10000 + ((codepoint - 0xD800) << 10) + (low - 0xDC00);
-                                    if let Some(c) = char::from_u32(combined) {
-                                        result.push(c);
-                                    }
-                                    *pos += 5; // Move past the low surrogate (will be incremented again below)
-                                } else {
-                                    // Lone high surrogate - use replacement character
-                                    result.push('\u{FFFD}');
-                                    *pos -= 1; // Back up so we don't skip the next escape
-                                }
-                            } else {
-                                // Lone high surrogate
-                                result.push('\u{FFFD}');
-                                *pos -= 1;
-                            }
-                        } else if let Some(c) = char::from_u32(codepoint) {
-                            result.push(c);
-                            *pos += 3; // Move past the hex digits (will be incremented again below)
-                        } else {
-                            return Err("invalid unicode codepoint".to_string());
-                        }
-                    }
-                    c => return Err(format!("invalid escape sequence: \\{}", c as char)),
-                }
-                *pos += 1;
-            }
-            c => {
-                // Regular character - handle UTF-8
-                let remaining = &bytes[*pos..];
-                if let Ok(s) = core::str::from_utf8(remaining) {
-                    if let Some(c) = s.chars().next() {
-                        result.push(c);
-                        *pos += c.len_utf8();
-                    } else {
-                        return Err("unexpected end of string".to_string());
-                    }
-                } else {
-                    // Try to get just the next character
-                    result.push(c as char);
-                    *pos += 1;
-                }
-            }
+/// Builtin: implode - array of codepoints to string
+///
+/// Every element must be a Unicode scalar value: surrogates and numbers
+/// outside `0..=0x10FFFF` are errors rather than being replaced.
+fn builtin_implode<W: Clone + AsRef<[u64]>>(
+    value: StandardJson<'_, W>,
+    scope: Scope,
+) -> QueryResult<'_, W> {
+    let StandardJson::Array(elements) = &value else {
+        if scope.optional {
+            return QueryResult::None;
         }
+        return QueryResult::Error(EvalError::new("implode input must be an array"));
+    };
+    let chars: Result<String, EvalError> =
+        elements.map(|e| codepoint_char(&to_owned(&e))).collect();
+    match chars {
+        Ok(s) => QueryResult::Owned(OwnedValue::String(s)),
+        Err(_) if scope.optional => QueryResult::None,
+        Err(e) => QueryResult::Error(e),
     }
-    Err("unterminated string".to_string())
 }
 
-/// Parse a JSON array
-fn parse_json_array(bytes: &[u8], pos: &mut usize) -> Result<OwnedValue, String> {
-    if bytes[*pos] != b'[' {
-        return Err("expected '['".to_string());
-    }
-    *pos += 1;
-
-    let mut elements = Vec::new();
-
-    // Skip whitespace
-    while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-        *pos += 1;
+/// Builtin: ascii / ascii(n) - the one-character string for a codepoint
+fn builtin_ascii<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    n_expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let codepoints = match result_to_owned_all(eval_single::<W, S>(n_expr, value, scope)) {
+        Ok(codepoints) => codepoints,
+        Err(e) => return QueryResult::Error(e),
+    };
+    let mut results = Vec::with_capacity(codepoints.len());
+    for codepoint in &codepoints {
+        match codepoint_char(codepoint) {
+            Ok(c) => results.push(OwnedValue::String(c.to_string())),
+            Err(_) if scope.optional => {}
+            Err(e) => return QueryResult::Error(e),
+        }
     }
-
-    // Check for empty array
-    if *pos < bytes.len() && bytes[*pos] == b']' {
-        *pos += 1;
-        return Ok(OwnedValue::Array(elements));
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
     }
+}
 
-    loop {
-        let value = parse_json_value(bytes, pos)?;
-        elements.push(value);
-
-        // Skip whitespace
-        while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-            *pos += 1;
-        }
+/// The character for a codepoint number, as `implode` and `ascii` accept it.
+fn codepoint_char(value: &OwnedValue) -> Result<char, EvalError> {
+    let codepoint = match value {
+        OwnedValue::Int(i) => *i,
+        #[allow(clippy::cast_possible_truncation)]
+        OwnedValue::Float(f) if f.is_finite() => *f as i64,
+        _ => return Err(EvalError::new("Unicode codepoint must be numeric")),
+    };
+    u32::try_from(codepoint)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| EvalError::new(format!("invalid codepoint: {codepoint}")))
+}
 
-        if *pos >= bytes.len() {
-            return Err("unterminated array".to_string());
-        }
+/// Builtin: test(re) - test if string matches (substring fallback without regex feature)
+#[cfg(not(feature = "regex"))]
+fn builtin_test<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    re_expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    // Evaluate the pattern
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
+        Ok(OwnedValue::String(s)) => s,
+        Ok(_) if scope.optional => return QueryResult::None,
+        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
+        Err(e) => return QueryResult::Error(e),
+    };
 
-        match bytes[*pos] {
-            b']' => {
-                *pos += 1;
-                return Ok(OwnedValue::Array(elements));
-            }
-            b',' => {
-                *pos += 1;
+    // Check if the input string contains the pattern (simple substring match)
+    match &value {
+        StandardJson::String(s) => {
+            if let Ok(cow) = s.as_str() {
+                QueryResult::Owned(OwnedValue::Bool(cow.contains(&pattern)))
+            } else if scope.optional {
+                QueryResult::None
+            } else {
+                QueryResult::Error(EvalError::new("invalid string"))
             }
-            c => return Err(format!("expected ',' or ']', got '{}'", c as char)),
         }
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     }
 }
 
-/// Parse a JSON object
-fn parse_json_object(bytes: &[u8], pos: &mut usize) -> Result<OwnedValue, String> {
-    if bytes[*pos] != b'{' {
-        return Err("expected '{{'".to_string());
-    }
-    *pos += 1;
-
-    let mut entries = IndexMap::new();
-
-    // Skip whitespace
-    while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-        *pos += 1;
-    }
-
-    // Check for empty object
-    if *pos < bytes.len() && bytes[*pos] == b'}' {
-        *pos += 1;
-        return Ok(OwnedValue::Object(entries));
-    }
+/// Which of the matching positions `indices`, `index` and `rindex` produce.
+#[derive(Clone, Copy)]
+enum IndexOutput {
+    /// `indices`: all of them, as an array
+    All,
+    /// `index`: the first, or null
+    First,
+    /// `rindex`: the last, or null
+    Last,
+}
 
-    loop {
-        // Skip whitespace
-        while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-            *pos += 1;
-        }
+/// Builtins: indices(s), index(s), rindex(s)
+///
+/// On strings the positions are byte offsets of (possibly overlapping)
+/// occurrences of `s`, as in jq 1.7. On arrays they are the positions of an
+/// element equal to `s`, or, if `s` is an array, where it occurs as a
+/// contiguous subarray. A null input gives null.
+fn builtin_indices<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    output: IndexOutput,
+    s_expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let patterns = match result_to_owned_all(eval_single::<W, S>(s_expr, value.clone(), scope)) {
+        Ok(patterns) => patterns,
+        Err(e) => return QueryResult::Error(e),
+    };
 
-        // Parse key (must be a string)
-        let key = match parse_json_string_value(bytes, pos)? {
-            OwnedValue::String(s) => s,
-            _ => return Err("object key must be a string".to_string()),
+    let mut results = Vec::with_capacity(patterns.len());
+    for pattern in &patterns {
+        let positions = match find_indices(&value, pattern) {
+            Ok(Some(positions)) => positions,
+            Ok(None) => {
+                results.push(OwnedValue::Null);
+                continue;
+            }
+            Err(_) if scope.optional => continue,
+            Err(e) => return QueryResult::Error(e),
         };
-
-        // Skip whitespace
-        while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-            *pos += 1;
-        }
-
-        // Expect colon
-        if *pos >= bytes.len() || bytes[*pos] != b':' {
-            return Err("expected ':'".to_string());
-        }
-        *pos += 1;
-
-        // Parse value
-        let value = parse_json_value(bytes, pos)?;
-        entries.insert(key, value);
-
-        // Skip whitespace
-        while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b'\n' | b'\r') {
-            *pos += 1;
-        }
-
-        if *pos >= bytes.len() {
-            return Err("unterminated object".to_string());
-        }
-
-        match bytes[*pos] {
-            b'}' => {
-                *pos += 1;
-                return Ok(OwnedValue::Object(entries));
+        let position = |p: usize| OwnedValue::Int(p as i64);
+        results.push(match output {
+            IndexOutput::All => OwnedValue::Array(positions.into_iter().map(position).collect()),
+            IndexOutput::First => positions
+                .first()
+                .copied()
+                .map_or(OwnedValue::Null, position),
+            IndexOutput::Last => positions.last().copied().map_or(OwnedValue::Null, position),
+        });
+    }
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
+}
+
+/// Positions of `pattern` in `value` for the `indices` family, or `None` for
+/// a null input.
+fn find_indices<W: Clone + AsRef<[u64]>>(
+    value: &StandardJson<'_, W>,
+    pattern: &OwnedValue,
+) -> Result<Option<Vec<usize>>, EvalError> {
+    match (value, pattern) {
+        (StandardJson::Null, _) => Ok(None),
+        (StandardJson::String(s), OwnedValue::String(needle)) => {
+            let haystack = s.as_str().map_err(|_| EvalError::new("invalid string"))?;
+            let mut positions = Vec::new();
+            if !needle.is_empty() {
+                let mut start = 0;
+                while let Some(pos) = haystack[start..].find(needle.as_str()) {
+                    let at = start + pos;
+                    positions.push(at);
+                    // Step one character past the match start so that
+                    // overlapping occurrences are found too
+                    start = at + haystack[at..].chars().next().map_or(1, char::len_utf8);
+                }
             }
-            b',' => {
-                *pos += 1;
+            Ok(Some(positions))
+        }
+        (StandardJson::Array(elements), OwnedValue::Array(needle)) => {
+            let items: Vec<OwnedValue> = elements.map(|e| to_owned(&e)).collect();
+            if needle.is_empty() || needle.len() > items.len() {
+                return Ok(Some(Vec::new()));
             }
-            c => return Err(format!("expected ',' or '}}', got '{}'", c as char)),
+            Ok(Some(
+                items
+                    .windows(needle.len())
+                    .enumerate()
+                    .filter(|(_, window)| *window == needle.as_slice())
+                    .map(|(i, _)| i)
+                    .collect(),
+            ))
         }
-    }
-}
-
-/// Parse a JSON number
-fn parse_json_number(bytes: &[u8], pos: &mut usize) -> Result<OwnedValue, String> {
-    let start = *pos;
-
-    // Optional minus sign
-    if *pos < bytes.len() && bytes[*pos] == b'-' {
-        *pos += 1;
-    }
-
-    // Integer part
-    if *pos >= bytes.len() {
-        return Err("expected number".to_string());
-    }
-
-    if bytes[*pos] == b'0' {
-        *pos += 1;
-    } else if bytes[*pos].is_ascii_digit() {
-        while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
-            *pos += 1;
-        }
-    } else {
-        return Err("expected digit".to_string());
-    }
-
-    let mut is_float = false;
-
-    // Fractional part
-    if *pos < bytes.len() && bytes[*pos] == b'.' {
-        is_float = true;
-        *pos += 1;
-        if *pos >= bytes.len() || !bytes[*pos].is_ascii_digit() {
-            return Err("expected digit after decimal point".to_string());
-        }
-        while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
-            *pos += 1;
-        }
-    }
-
-    // Exponent part
-    if *pos < bytes.len() && matches!(bytes[*pos], b'e' | b'E') {
-        is_float = true;
-        *pos += 1;
-        if *pos < bytes.len() && matches!(bytes[*pos], b'+' | b'-') {
-            *pos += 1;
-        }
-        if *pos >= bytes.len() || !bytes[*pos].is_ascii_digit() {
-            return Err("expected digit in exponent".to_string());
-        }
-        while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
-            *pos += 1;
-        }
-    }
-
-    let num_str =
-        core::str::from_utf8(&bytes[start..*pos]).map_err(|_| "invalid number encoding")?;
-
-    if is_float {
-        let f: f64 = num_str.parse().map_err(|_| "invalid float")?;
-        Ok(OwnedValue::Float(f))
-    } else {
-        // Try integer first
-        if let Ok(i) = num_str.parse::<i64>() {
-            Ok(OwnedValue::Int(i))
-        } else {
-            // Fall back to float for large numbers
-            let f: f64 = num_str.parse().map_err(|_| "invalid number")?;
-            Ok(OwnedValue::Float(f))
-        }
-    }
-}
-
-// =============================================================================
-// Phase 6: Additional String Builtins
-// =============================================================================
-
-/// Builtin: explode - string to array of Unicode codepoints
-fn builtin_explode<W: Clone + AsRef<[u64]>>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                let codepoints: Vec<OwnedValue> = cow
-                    .chars()
-                    .map(|c| OwnedValue::Int(c as u32 as i64))
-                    .collect();
-                QueryResult::Owned(OwnedValue::Array(codepoints))
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    }
-}
-
-/// Builtin: implode - array of codepoints to string
-fn builtin_implode<W: Clone + AsRef<[u64]>>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::Array(elements) => {
-            let mut result = String::new();
-            for elem in *elements {
-                if let StandardJson::Number(n) = elem {
-                    if let Ok(codepoint) = n.as_i64() {
-                        if let Some(c) = char::from_u32(codepoint as u32) {
-                            result.push(c);
-                        } else if optional {
-                            continue;
-                        } else {
-                            return QueryResult::Error(EvalError::new(format!(
-                                "invalid codepoint: {codepoint}"
-                            )));
-                        }
-                    }
-                }
-            }
-            QueryResult::Owned(OwnedValue::String(result))
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
-    }
-}
-
-/// Builtin: test(re) - test if string matches (substring fallback without regex feature)
-#[cfg(not(feature = "regex"))]
-fn builtin_test<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Check if the input string contains the pattern (simple substring match)
-    match &value {
-        StandardJson::String(s) => {
-            if let Ok(cow) = s.as_str() {
-                QueryResult::Owned(OwnedValue::Bool(cow.contains(&pattern)))
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    }
-}
-
-/// Builtin: indices(s) - find all indices of substring/element s
-fn builtin_indices<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    s_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the pattern (can be any type for arrays, must be string for strings)
-    let pattern = match result_to_owned(eval_single::<W, S>(s_expr, value.clone(), optional)) {
-        Ok(v) => v,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    match &value {
-        StandardJson::String(s) => {
-            // For strings, pattern must be a string
-            let pattern_str = match &pattern {
-                OwnedValue::String(p) => p,
-                _ if optional => return QueryResult::None,
-                _ => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-            };
-            if let Ok(cow) = s.as_str() {
-                let mut indices = Vec::new();
-                let mut start = 0;
-                while let Some(pos) = cow[start..].find(pattern_str.as_str()) {
-                    indices.push(OwnedValue::Int((start + pos) as i64));
-                    start += pos + 1;
-                    if start >= cow.len() {
-                        break;
-                    }
-                }
-                QueryResult::Owned(OwnedValue::Array(indices))
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        StandardJson::Array(elements) => {
-            // For arrays, find indices where element equals the pattern (any type)
-            let mut indices = Vec::new();
-            for (i, elem) in (*elements).enumerate() {
-                if to_owned(&elem) == pattern {
-                    indices.push(OwnedValue::Int(i as i64));
-                }
-            }
-            QueryResult::Owned(OwnedValue::Array(indices))
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string or array", type_name(&value))),
-    }
-}
-
-/// Builtin: index(s) - first index of substring/element s, or null
-fn builtin_index<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    s_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the pattern (can be any type for arrays, must be string for strings)
-    let pattern = match result_to_owned(eval_single::<W, S>(s_expr, value.clone(), optional)) {
-        Ok(v) => v,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    match &value {
-        StandardJson::String(s) => {
-            // For strings, pattern must be a string
-            let pattern_str = match &pattern {
-                OwnedValue::String(p) => p,
-                _ if optional => return QueryResult::None,
-                _ => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-            };
-            if let Ok(cow) = s.as_str() {
-                if let Some(pos) = cow.find(pattern_str.as_str()) {
-                    QueryResult::Owned(OwnedValue::Int(pos as i64))
-                } else {
-                    QueryResult::Owned(OwnedValue::Null)
-                }
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        StandardJson::Array(elements) => {
-            // For arrays, pattern can be any type
-            for (i, elem) in (*elements).enumerate() {
-                if to_owned(&elem) == pattern {
-                    return QueryResult::Owned(OwnedValue::Int(i as i64));
-                }
-            }
-            QueryResult::Owned(OwnedValue::Null)
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string or array", type_name(&value))),
-    }
-}
-
-/// Builtin: rindex(s) - last index of substring/element s, or null
-fn builtin_rindex<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    s_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the pattern (can be any type for arrays, must be string for strings)
-    let pattern = match result_to_owned(eval_single::<W, S>(s_expr, value.clone(), optional)) {
-        Ok(v) => v,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    match &value {
-        StandardJson::String(s) => {
-            // For strings, pattern must be a string
-            let pattern_str = match &pattern {
-                OwnedValue::String(p) => p,
-                _ if optional => return QueryResult::None,
-                _ => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-            };
-            if let Ok(cow) = s.as_str() {
-                if let Some(pos) = cow.rfind(pattern_str.as_str()) {
-                    QueryResult::Owned(OwnedValue::Int(pos as i64))
-                } else {
-                    QueryResult::Owned(OwnedValue::Null)
-                }
-            } else if optional {
-                QueryResult::None
-            } else {
-                QueryResult::Error(EvalError::new("invalid string"))
-            }
-        }
-        StandardJson::Array(elements) => {
-            // For arrays, pattern can be any type
-            let items: Vec<_> = (*elements).collect();
-            for (i, elem) in items.iter().enumerate().rev() {
-                if to_owned(elem) == pattern {
-                    return QueryResult::Owned(OwnedValue::Int(i as i64));
-                }
-            }
-            QueryResult::Owned(OwnedValue::Null)
-        }
-        _ if optional => QueryResult::None,
-        _ => QueryResult::Error(EvalError::type_error("string or array", type_name(&value))),
+        (StandardJson::Array(elements), _) => Ok(Some(
+            elements
+                .enumerate()
+                .filter(|(_, elem)| to_owned(elem) == *pattern)
+                .map(|(i, _)| i)
+                .collect(),
+        )),
+        (StandardJson::String(_), _) => Err(EvalError::new(format!(
+            "Cannot index string with {}",
+            pattern.type_name()
+        ))),
+        _ => Err(EvalError::new(format!(
+            "Cannot index {} with {}",
+            type_name(value),
+            pattern.type_name()
+        ))),
     }
 }
 
 /// Builtin: tojsonstream - convert to JSON text stream format (simplified)
 fn builtin_tojsonstream<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     // Simplified: just return the value as JSON lines format
     let owned = to_owned(&value);
@@ -4441,7 +4506,7 @@ fn builtin_tojsonstream<W: Clone + AsRef<[u64]>>(
 /// Builtin: fromjsonstream - convert from JSON text stream format (simplified)
 fn builtin_fromjsonstream<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     // This is a complex operation - provide a simplified version
     match &value {
@@ -4449,7 +4514,7 @@ fn builtin_fromjsonstream<W: Clone + AsRef<[u64]>>(
             // For now, return the input - full implementation would reconstruct
             QueryResult::Owned(to_owned(&value))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -4458,47 +4523,29 @@ fn builtin_fromjsonstream<W: Clone + AsRef<[u64]>>(
 fn builtin_getpath<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     path_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the path expression
-    let path = match result_to_owned(eval_single::<W, S>(path_expr, value.clone(), optional)) {
+    let path = match result_to_owned(eval_single::<W, S>(path_expr, value.clone(), scope)) {
         Ok(OwnedValue::Array(arr)) => arr,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("array", "path")),
+        Ok(_) if scope.optional => return QueryResult::None,
+        Ok(_) => return QueryResult::Error(EvalError::new("Path must be specified as an array")),
         Err(e) => return QueryResult::Error(e),
     };
 
-    let mut current = to_owned(&value);
-
-    for segment in path {
-        match (&current, &segment) {
-            // jq: null | getpath(["a"]) => null
-            (OwnedValue::Null, _) => {
-                return QueryResult::Owned(OwnedValue::Null);
-            }
-            (OwnedValue::Object(obj), OwnedValue::String(key)) => {
-                current = obj.get(key).cloned().unwrap_or(OwnedValue::Null);
-            }
-            (OwnedValue::Array(arr), OwnedValue::Int(idx)) => {
-                let index = if *idx < 0 {
-                    (arr.len() as i64 + *idx) as usize
-                } else {
-                    *idx as usize
-                };
-                current = arr.get(index).cloned().unwrap_or(OwnedValue::Null);
-            }
-            _ if optional => return QueryResult::None,
-            _ => {
-                return QueryResult::Error(EvalError::new(format!(
-                    "cannot index {} with {}",
-                    current.type_name(),
-                    segment.type_name()
-                )));
-            }
-        }
+    let input = to_owned(&value);
+    match path_components(&input, &path) {
+        // Paths that run off the tree, even through a negative index, are null
+        Ok(Some(components)) => QueryResult::Owned(
+            input
+                .get_path(&components)
+                .cloned()
+                .unwrap_or(OwnedValue::Null),
+        ),
+        Ok(None) => QueryResult::Owned(OwnedValue::Null),
+        Err(_) if scope.optional => QueryResult::None,
+        Err(e) => QueryResult::Error(e),
     }
-
-    QueryResult::Owned(current)
 }
 
 // =============================================================================
@@ -4506,215 +4553,265 @@ fn builtin_getpath<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 // =============================================================================
 
 /// Build regex flags from jq flag string
+///
+/// jq's flags are Oniguruma options, which differ from Rust's inline flags
+/// of the same letter: Oniguruma's `m` makes `.` match newlines (Rust `s`),
+/// and its `s` anchors `^` and `$` to the whole input, which Rust already
+/// does. `^` and `$` never match at line breaks, as in jq.
 #[cfg(feature = "regex")]
 fn build_regex(pattern: &str, flags: Option<&str>) -> Result<regex::Regex, EvalError> {
     let mut pattern = pattern.to_string();
 
     // Apply flags
     if let Some(flags) = flags {
-        let mut prefix = String::from("(?");
+        let mut inline = String::new();
         for c in flags.chars() {
-            match c {
-                'i' => prefix.push('i'), // case insensitive
-                'x' => prefix.push('x'), // extended mode (ignore whitespace)
-                's' => prefix.push('s'), // single-line mode (. matches newline)
-                'm' => prefix.push('m'), // multi-line mode
-                'g' => {}                // global - handled at call site
-                'p' => {}                // PCRE mode - not fully supported
-                _ => {}
+            let flag = match c {
+                'i' => Some('i'), // case insensitive
+                'x' => Some('x'), // extended mode (ignore whitespace)
+                's' => None,      // single-line mode: ^ and $ anchor the input
+                'm' => Some('s'), // dot matches newline
+                'p' => Some('s'), // both s and m modes
+                'g' => None,      // global - handled at call site
+                'n' => None,      // ignore empty matches - handled at call site
+                'l' => None,      // longest match - leftmost-first is used
+                _ => {
+                    return Err(EvalError::new(format!(
+                        "{flags} is not a valid modifier string"
+                    )))
+                }
+            };
+            if let Some(flag) = flag.filter(|f| !inline.contains(*f)) {
+                inline.push(flag);
             }
         }
-        if prefix.len() > 2 {
-            prefix.push(')');
-            pattern = format!("{prefix}{pattern}");
+        if !inline.is_empty() {
+            pattern = format!("(?{inline}){pattern}");
         }
     }
 
     regex::Regex::new(&pattern).map_err(|e| EvalError::new(format!("invalid regex: {e}")))
 }
 
-/// Builtin: test(re) - test if string matches the regex
+/// What a regex builtin produces for each match.
 #[cfg(feature = "regex")]
-fn builtin_test_regex<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+#[derive(Clone, Copy)]
+enum RegexOutput {
+    /// `test`: whether the regex matches at all
+    Test,
+    /// `match`: a match object per match
+    Match,
+    /// `capture`: an object of the named captures per match
+    Capture,
+}
+
+/// Builtins: test(re), match(re), capture(re) and their `(re; flags)` forms
+///
+/// With the `g` flag `match` and `capture` emit one output per match; with
+/// no match they emit nothing. The single-argument forms also accept
+/// `[re]` or `[re, flags]`. Offsets and lengths count codepoints, as in jq.
+#[cfg(feature = "regex")]
+fn builtin_regex<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    output: RegexOutput,
     re_expr: &Expr,
+    flags_expr: Option<&Expr>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Evaluate the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the input string
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
-        _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+        _ if scope.optional => return QueryResult::None,
+        _ => {
+            return QueryResult::Error(EvalError::new(format!(
+                "{} ({}) cannot be matched, as it is not a string",
+                type_name(&value),
+                to_owned(&value).to_json()
+            )))
+        }
     };
 
-    // Build regex and test for a match
-    let re = match build_regex(&pattern, None) {
-        Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+    let args = match regex_args::<W, S>(re_expr, flags_expr, &value, scope) {
+        Ok(args) => args,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
-    QueryResult::Owned(OwnedValue::Bool(re.is_match(&input)))
+    let mut results = Vec::new();
+    for (pattern, flags) in args {
+        let flags = flags.as_deref().unwrap_or("");
+        let re = match build_regex(&pattern, Some(flags)) {
+            Ok(r) => r,
+            Err(_) if scope.optional => return QueryResult::None,
+            Err(e) => return QueryResult::Error(e),
+        };
+        let skip_empty = flags.contains('n');
+        match output {
+            RegexOutput::Test => {
+                let found = re.find_iter(&input).any(|m| !(skip_empty && m.is_empty()));
+                results.push(OwnedValue::Bool(found));
+            }
+            RegexOutput::Match => {
+                results.extend(regex_matches(&re, &input, flags.contains('g'), skip_empty));
+            }
+            RegexOutput::Capture => results.extend(
+                regex_matches(&re, &input, flags.contains('g'), skip_empty)
+                    .iter()
+                    .map(named_captures),
+            ),
+        }
+    }
+
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
 }
 
-/// Builtin: match(re) or match(re; flags) - return match object
+/// Evaluate the regex and flags arguments into `(pattern, flags)` pairs,
+/// one per combination of their outputs.
 #[cfg(feature = "regex")]
-fn builtin_match<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+fn regex_args<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
-    flags: Option<&str>,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the input string
-    let input = match &value {
-        StandardJson::String(s) => match s.as_str() {
-            Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
-            Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
-        },
-        _ if optional => return QueryResult::None,
-        _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
+    flags_expr: Option<&Expr>,
+    value: &StandardJson<'_, W>,
+    scope: Scope,
+) -> Result<Vec<(String, Option<String>)>, EvalError> {
+    let regexes = result_to_owned_all(eval_single::<W, S>(re_expr, value.clone(), scope))?;
+    let all_flags = match flags_expr {
+        Some(f) => result_to_owned_all(eval_single::<W, S>(f, value.clone(), scope))?,
+        None => vec![OwnedValue::Null],
     };
 
-    // Build regex
-    let re = match build_regex(&pattern, flags) {
-        Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Check if global flag is set
-    let global = flags.is_some_and(|f| f.contains('g'));
-
-    if global {
-        // Return all matches
-        let matches: Vec<OwnedValue> = re
-            .find_iter(&input)
-            .map(|m| build_match_object(&re, m.as_str(), m.start(), &input))
-            .collect();
-        QueryResult::Owned(OwnedValue::Array(matches))
-    } else {
-        // Return first match or null
-        match re.find(&input) {
-            Some(m) => QueryResult::Owned(build_match_object(&re, m.as_str(), m.start(), &input)),
-            None => QueryResult::Owned(OwnedValue::Null),
+    let mut args = Vec::new();
+    for re in &regexes {
+        for flags in &all_flags {
+            let (pattern, flags) = match (re, flags_expr) {
+                (OwnedValue::Array(parts), None) if !parts.is_empty() => {
+                    (&parts[0], parts.get(1).unwrap_or(&OwnedValue::Null))
+                }
+                (OwnedValue::String(_), _) | (_, Some(_)) => (re, flags),
+                (other, None) => {
+                    return Err(EvalError::new(format!(
+                        "{} not a string or array",
+                        other.type_name()
+                    )))
+                }
+            };
+            let OwnedValue::String(pattern) = pattern else {
+                return Err(EvalError::new(format!(
+                    "{} ({}) cannot be matched, as it is not a string",
+                    pattern.type_name(),
+                    pattern.to_json()
+                )));
+            };
+            let flags = match flags {
+                OwnedValue::Null => None,
+                OwnedValue::String(f) => Some(f.clone()),
+                other => {
+                    return Err(EvalError::new(format!(
+                        "{} is not a string",
+                        other.to_json()
+                    )))
+                }
+            };
+            args.push((pattern.clone(), flags));
         }
     }
+    Ok(args)
 }
 
-/// Build a jq match object
+/// jq match objects for the first match of `re` in `input`, or every match
+/// if `global`. Empty matches are dropped if `skip_empty` (the `n` flag).
 #[cfg(feature = "regex")]
-fn build_match_object(re: &regex::Regex, matched: &str, offset: usize, input: &str) -> OwnedValue {
-    let mut obj = IndexMap::new();
+fn regex_matches(
+    re: &regex::Regex,
+    input: &str,
+    global: bool,
+    skip_empty: bool,
+) -> Vec<OwnedValue> {
+    let codepoints = |from: usize, to: usize| input[from..to].chars().count() as i64;
 
-    obj.insert("offset".to_string(), OwnedValue::Int(offset as i64));
-    obj.insert("length".to_string(), OwnedValue::Int(matched.len() as i64));
-    obj.insert(
-        "string".to_string(),
-        OwnedValue::String(matched.to_string()),
-    );
+    let mut results = Vec::new();
+    // Codepoint offset of byte `pos`, advanced as matches move right
+    let (mut pos, mut offset) = (0, 0);
+    for caps in re.captures_iter(input) {
+        let whole = caps.get(0).expect("group 0 always participates");
+        if skip_empty && whole.is_empty() {
+            continue;
+        }
+        offset += codepoints(pos, whole.start());
+        pos = whole.start();
 
-    // Build captures array
-    let mut captures = Vec::new();
-    if let Some(caps) = re.captures(input) {
-        for (i, name) in re.capture_names().enumerate() {
-            if i == 0 {
-                continue; // Skip the full match
-            }
-            let cap = caps.get(i);
-            let mut cap_obj = IndexMap::new();
-            cap_obj.insert(
-                "offset".to_string(),
-                cap.map_or(OwnedValue::Null, |m| OwnedValue::Int(m.start() as i64)),
-            );
-            cap_obj.insert(
-                "length".to_string(),
-                cap.map_or(OwnedValue::Int(0), |m| OwnedValue::Int(m.len() as i64)),
-            );
-            cap_obj.insert(
-                "string".to_string(),
-                cap.map_or(OwnedValue::Null, |m| {
-                    OwnedValue::String(m.as_str().to_string())
-                }),
-            );
-            cap_obj.insert(
-                "name".to_string(),
-                name.map_or(OwnedValue::Null, |n| OwnedValue::String(n.to_string())),
-            );
-            captures.push(OwnedValue::Object(cap_obj));
+        let captures = re
+            .capture_names()
+            .enumerate()
+            .skip(1)
+            .map(|(i, name)| {
+                let mut cap = match caps.get(i) {
+                    Some(m) => match_object(
+                        offset + codepoints(pos, m.start()),
+                        codepoints(m.start(), m.end()),
+                        OwnedValue::String(m.as_str().to_string()),
+                    ),
+                    // A group that did not participate in the match
+                    None => match_object(-1, 0, OwnedValue::Null),
+                };
+                cap.insert(
+                    "name".to_string(),
+                    name.map_or(OwnedValue::Null, |n| OwnedValue::String(n.to_string())),
+                );
+                OwnedValue::Object(cap)
+            })
+            .collect();
+
+        let mut obj = match_object(
+            offset,
+            codepoints(whole.start(), whole.end()),
+            OwnedValue::String(whole.as_str().to_string()),
+        );
+        obj.insert("captures".to_string(), OwnedValue::Array(captures));
+        results.push(OwnedValue::Object(obj));
+
+        if !global {
+            break;
         }
     }
-    obj.insert("captures".to_string(), OwnedValue::Array(captures));
-
-    OwnedValue::Object(obj)
+    results
 }
 
-/// Builtin: capture(re) - return named captures as object
+/// The `offset`, `length` and `string` fields shared by matches and captures.
 #[cfg(feature = "regex")]
-fn builtin_capture<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the input string
-    let input = match &value {
-        StandardJson::String(s) => match s.as_str() {
-            Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
-            Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
-        },
-        _ if optional => return QueryResult::None,
-        _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    };
-
-    // Build regex
-    let re = match build_regex(&pattern, None) {
-        Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
-        Err(e) => return QueryResult::Error(e),
-    };
+fn match_object(offset: i64, length: i64, string: OwnedValue) -> IndexMap<String, OwnedValue> {
+    let mut obj = IndexMap::new();
+    obj.insert("offset".to_string(), OwnedValue::Int(offset));
+    obj.insert("length".to_string(), OwnedValue::Int(length));
+    obj.insert("string".to_string(), string);
+    obj
+}
 
-    // Extract named captures
-    match re.captures(&input) {
-        Some(caps) => {
-            let mut result = IndexMap::new();
-            for name in re.capture_names().flatten() {
-                if let Some(m) = caps.name(name) {
-                    result.insert(name.to_string(), OwnedValue::String(m.as_str().to_string()));
+/// `capture`'s output for a match object: each named group's string (null if
+/// the group did not participate).
+#[cfg(feature = "regex")]
+fn named_captures(match_obj: &OwnedValue) -> OwnedValue {
+    let mut result = IndexMap::new();
+    if let OwnedValue::Object(obj) = match_obj {
+        if let Some(OwnedValue::Array(captures)) = obj.get("captures") {
+            for cap in captures {
+                if let OwnedValue::Object(cap) = cap {
+                    if let Some(OwnedValue::String(name)) = cap.get("name") {
+                        let string = cap.get("string").cloned().unwrap_or(OwnedValue::Null);
+                        result.insert(name.clone(), string);
+                    }
                 }
             }
-            QueryResult::Owned(OwnedValue::Object(result))
         }
-        None => QueryResult::Owned(OwnedValue::Null),
     }
+    OwnedValue::Object(result)
 }
 
 /// Builtin: scan(re) - find all matches
@@ -4722,12 +4819,12 @@ fn builtin_capture<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_scan<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -4736,17 +4833,17 @@ fn builtin_scan<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, None) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -4764,12 +4861,12 @@ fn builtin_scan<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_splits<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -4778,17 +4875,17 @@ fn builtin_splits<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, None) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -4807,43 +4904,40 @@ fn builtin_sub<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     replacement_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
 
     // Get the replacement
-    let replacement = match result_to_owned(eval_single::<W, S>(
-        replacement_expr,
-        value.clone(),
-        optional,
-    )) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
-        Err(e) => return QueryResult::Error(e),
-    };
+    let replacement =
+        match result_to_owned(eval_single::<W, S>(replacement_expr, value.clone(), scope)) {
+            Ok(OwnedValue::String(s)) => s,
+            Ok(_) if scope.optional => return QueryResult::None,
+            Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
+            Err(e) => return QueryResult::Error(e),
+        };
 
     // Get the input string
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, None) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -4858,43 +4952,40 @@ fn builtin_gsub<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     replacement_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
 
     // Get the replacement
-    let replacement = match result_to_owned(eval_single::<W, S>(
-        replacement_expr,
-        value.clone(),
-        optional,
-    )) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
-        Err(e) => return QueryResult::Error(e),
-    };
+    let replacement =
+        match result_to_owned(eval_single::<W, S>(replacement_expr, value.clone(), scope)) {
+            Ok(OwnedValue::String(s)) => s,
+            Ok(_) if scope.optional => return QueryResult::None,
+            Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
+            Err(e) => return QueryResult::Error(e),
+        };
 
     // Get the input string
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, None) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -4903,141 +4994,6 @@ fn builtin_gsub<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     QueryResult::Owned(OwnedValue::String(result.into_owned()))
 }
 
-/// Builtin: test(re; flags) - test with flags expression
-#[cfg(feature = "regex")]
-fn builtin_test_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    flags_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the input string
-    let input = match &value {
-        StandardJson::String(s) => match s.as_str() {
-            Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
-            Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
-        },
-        _ if optional => return QueryResult::None,
-        _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    };
-
-    // Build regex with flags
-    let re = match build_regex(&pattern, Some(&flags)) {
-        Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Test if regex matches
-    QueryResult::Owned(OwnedValue::Bool(re.is_match(&input)))
-}
-
-/// Builtin: match(re; flags) - match with flags expression
-#[cfg(feature = "regex")]
-fn builtin_match_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    flags_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    builtin_match::<W, S>(re_expr, Some(&flags), value, optional)
-}
-
-/// Builtin: capture(re; flags) - capture with flags expression
-#[cfg(feature = "regex")]
-fn builtin_capture_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    flags_expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    builtin_capture_with_flags::<W, S>(re_expr, Some(&flags), value, optional)
-}
-
-/// Builtin: capture(re) or capture(re; flags) - capture named groups
-#[cfg(feature = "regex")]
-fn builtin_capture_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    re_expr: &Expr,
-    flags: Option<&str>,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Get the input string
-    let input = match &value {
-        StandardJson::String(s) => match s.as_str() {
-            Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
-            Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
-        },
-        _ if optional => return QueryResult::None,
-        _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
-    };
-
-    // Build regex
-    let re = match build_regex(&pattern, flags) {
-        Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    // Find first match and extract named captures
-    if let Some(caps) = re.captures(&input) {
-        let mut entries = IndexMap::new();
-        for name in re.capture_names().flatten() {
-            if let Some(m) = caps.name(name) {
-                entries.insert(name.to_string(), OwnedValue::String(m.as_str().to_string()));
-            }
-        }
-        QueryResult::Owned(OwnedValue::Object(entries))
-    } else if optional {
-        QueryResult::None
-    } else {
-        // jq returns empty object when no match
-        QueryResult::Owned(OwnedValue::Object(IndexMap::new()))
-    }
-}
-
 /// Builtin: sub(re; replacement; flags) - replace first match with flags
 #[cfg(feature = "regex")]
 fn builtin_sub_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
@@ -5045,17 +5001,17 @@ fn builtin_sub_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     replacement_expr: &Expr,
     flags_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
+    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
         Err(e) => return QueryResult::Error(e),
     };
 
-    builtin_sub_with_flags::<W, S>(re_expr, replacement_expr, Some(&flags), value, optional)
+    builtin_sub_with_flags::<W, S>(re_expr, replacement_expr, Some(&flags), value, scope)
 }
 
 /// Builtin: sub(re; replacement) or sub(re; replacement; flags) - replace first match
@@ -5065,43 +5021,40 @@ fn builtin_sub_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     replacement_expr: &Expr,
     flags: Option<&str>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
 
     // Get the replacement
-    let replacement = match result_to_owned(eval_single::<W, S>(
-        replacement_expr,
-        value.clone(),
-        optional,
-    )) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
-        Err(e) => return QueryResult::Error(e),
-    };
+    let replacement =
+        match result_to_owned(eval_single::<W, S>(replacement_expr, value.clone(), scope)) {
+            Ok(OwnedValue::String(s)) => s,
+            Ok(_) if scope.optional => return QueryResult::None,
+            Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
+            Err(e) => return QueryResult::Error(e),
+        };
 
     // Get the input string
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, flags) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -5120,17 +5073,17 @@ fn builtin_gsub_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     replacement_expr: &Expr,
     flags_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
+    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
         Err(e) => return QueryResult::Error(e),
     };
 
-    builtin_gsub_with_flags::<W, S>(re_expr, replacement_expr, Some(&flags), value, optional)
+    builtin_gsub_with_flags::<W, S>(re_expr, replacement_expr, Some(&flags), value, scope)
 }
 
 /// Builtin: gsub(re; replacement) or gsub(re; replacement; flags) - replace all matches
@@ -5140,43 +5093,40 @@ fn builtin_gsub_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     replacement_expr: &Expr,
     flags: Option<&str>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
 
     // Get the replacement
-    let replacement = match result_to_owned(eval_single::<W, S>(
-        replacement_expr,
-        value.clone(),
-        optional,
-    )) {
-        Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
-        Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
-        Err(e) => return QueryResult::Error(e),
-    };
+    let replacement =
+        match result_to_owned(eval_single::<W, S>(replacement_expr, value.clone(), scope)) {
+            Ok(OwnedValue::String(s)) => s,
+            Ok(_) if scope.optional => return QueryResult::None,
+            Ok(_) => return QueryResult::Error(EvalError::type_error("string", "replacement")),
+            Err(e) => return QueryResult::Error(e),
+        };
 
     // Get the input string
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, flags) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -5194,17 +5144,17 @@ fn builtin_scan_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     flags_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
+    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
         Err(e) => return QueryResult::Error(e),
     };
 
-    builtin_scan_with_flags::<W, S>(re_expr, Some(&flags), value, optional)
+    builtin_scan_with_flags::<W, S>(re_expr, Some(&flags), value, scope)
 }
 
 /// Builtin: scan(re) or scan(re; flags) - find all matches
@@ -5213,12 +5163,12 @@ fn builtin_scan_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     flags: Option<&str>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -5227,17 +5177,17 @@ fn builtin_scan_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, flags) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -5276,20 +5226,20 @@ fn builtin_split_regex<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     flags_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
+    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
         Err(e) => return QueryResult::Error(e),
     };
 
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -5298,17 +5248,17 @@ fn builtin_split_regex<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, Some(&flags)) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -5327,17 +5277,17 @@ fn builtin_splits_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     flags_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the flags expression
-    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), optional)) {
+    let flags = match result_to_owned(eval_single::<W, S>(flags_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "flags")),
         Err(e) => return QueryResult::Error(e),
     };
 
-    builtin_splits_with_flags::<W, S>(re_expr, Some(&flags), value, optional)
+    builtin_splits_with_flags::<W, S>(re_expr, Some(&flags), value, scope)
 }
 
 /// Builtin: splits(re) or splits(re; flags) - split by regex as stream
@@ -5346,12 +5296,12 @@ fn builtin_splits_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     re_expr: &Expr,
     flags: Option<&str>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the pattern
-    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), optional)) {
+    let pattern = match result_to_owned(eval_single::<W, S>(re_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "pattern")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -5360,17 +5310,17 @@ fn builtin_splits_with_flags<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", type_name(&value))),
     };
 
     // Build regex
     let re = match build_regex(&pattern, flags) {
         Ok(r) => r,
-        Err(_e) if optional => return QueryResult::None,
+        Err(_e) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -5435,12 +5385,12 @@ fn convert_jq_replacement(replacement: &str) -> String {
 fn eval_pipe<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     exprs: &[Expr],
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Check if any expression in the pipe needs path context (PathNoArg, Parent)
     if exprs.iter().any(needs_path_context) {
         let owned = to_owned(&value);
-        return eval_pipe_with_path_context::<W, S>(exprs, &owned, &[], optional);
+        return eval_pipe_with_path_context::<W, S>(exprs, &owned, &[], scope);
     }
 
     if exprs.is_empty() {
@@ -5450,107 +5400,123 @@ fn eval_pipe<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let (first, rest) = exprs.split_first().unwrap();
 
     // Evaluate first expression
-    let result = eval_single::<W, S>(first, value, optional);
+    let result = eval_single::<W, S>(first, value, scope);
 
     if rest.is_empty() {
         return result;
     }
 
-    // Apply remaining expressions to the result
+    eval_pipe_rest::<W, S>(result, rest, scope)
+}
+
+/// Apply the remaining stages of a pipe to each output of its first stage.
+fn eval_pipe_rest<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    result: QueryResult<'a, W>,
+    rest: &[Expr],
+    scope: Scope,
+) -> QueryResult<'a, W> {
     match result.materialize_cursor() {
-        QueryResult::One(v) => eval_pipe::<W, S>(rest, v, optional),
+        QueryResult::One(v) => eval_pipe::<W, S>(rest, v, scope),
         QueryResult::OneCursor(_) => unreachable!(),
-        QueryResult::Many(values) => {
-            // Rest-of-pipe applied per element may yield borrowed (One/Many) OR
-            // computed owned (Owned/ManyOwned) results — e.g. `.+1`, `[.]`,
-            // `tostring`. Keep the borrowed fast-path (return `Many`), but the
-            // moment any owned result appears, promote the whole batch to owned
-            // so nothing is dropped (#295). Order is preserved across promotion.
-            let mut borrowed: Vec<StandardJson<'a, W>> = Vec::new();
-            let mut owned: Option<Vec<OwnedValue>> = None;
-            for v in values {
-                match eval_pipe::<W, S>(rest, v, optional).materialize_cursor() {
-                    QueryResult::One(r) => match owned.as_mut() {
-                        Some(acc) => acc.push(to_owned(&r)),
-                        None => borrowed.push(r),
-                    },
-                    QueryResult::OneCursor(_) => unreachable!(),
-                    QueryResult::Many(rs) => match owned.as_mut() {
-                        Some(acc) => acc.extend(rs.iter().map(to_owned)),
-                        None => borrowed.extend(rs),
-                    },
-                    QueryResult::Owned(r) => owned
-                        .get_or_insert_with(|| {
-                            core::mem::take(&mut borrowed)
-                                .iter()
-                                .map(to_owned)
-                                .collect()
-                        })
-                        .push(r),
-                    QueryResult::ManyOwned(rs) => owned
-                        .get_or_insert_with(|| {
-                            core::mem::take(&mut borrowed)
-                                .iter()
-                                .map(to_owned)
-                                .collect()
-                        })
-                        .extend(rs),
-                    QueryResult::None => {}
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    QueryResult::Break(label) => return QueryResult::Break(label),
-                }
-            }
-            match owned {
-                Some(acc) => QueryResult::ManyOwned(acc),
-                None => QueryResult::Many(borrowed),
-            }
-        }
+        QueryResult::Many(values) => pipe_borrowed_values::<W, S>(values, rest, scope),
         QueryResult::None => QueryResult::None,
         QueryResult::Error(e) => QueryResult::Error(e),
         QueryResult::Break(label) => QueryResult::Break(label),
-        QueryResult::Owned(v) => {
-            // Continue piping with owned value using eval_owned_pipe
-            // Convert the result type since eval_owned_pipe uses Vec<u64> internally
-            match eval_owned_pipe::<Vec<u64>, S>(rest, v, optional) {
-                QueryResult::Owned(o) => QueryResult::Owned(o),
-                QueryResult::Error(e) => QueryResult::Error(e),
-                QueryResult::None => QueryResult::None,
-                QueryResult::ManyOwned(vs) => QueryResult::ManyOwned(vs),
-                QueryResult::Break(label) => QueryResult::Break(label),
-                _ => unreachable!("eval_owned_pipe only returns Owned variants"),
-            }
+        // Continue piping with owned value using eval_owned_pipe
+        QueryResult::Owned(v) => eval_owned_pipe::<W, S>(rest, v, scope),
+        QueryResult::ManyOwned(vs) => pipe_owned_values::<W, S>(vs, rest, scope),
+    }
+}
+
+/// The `Many` case of [`eval_pipe_rest`], kept out of its stack frame: every
+/// call level of a recursive function like `def f: . + 1 | f` passes
+/// through [`eval_pipe_rest`].
+#[inline(never)]
+fn pipe_borrowed_values<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    values: Vec<StandardJson<'a, W>>,
+    rest: &[Expr],
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    // Rest-of-pipe applied per element may yield borrowed (One/Many) OR
+    // computed owned (Owned/ManyOwned) results — e.g. `.+1`, `[.]`,
+    // `tostring`. Keep the borrowed fast-path (return `Many`), but the
+    // moment any owned result appears, promote the whole batch to owned
+    // so nothing is dropped (#295). Order is preserved across promotion.
+    let mut borrowed: Vec<StandardJson<'a, W>> = Vec::new();
+    let mut owned: Option<Vec<OwnedValue>> = None;
+    for v in values {
+        match eval_pipe::<W, S>(rest, v, scope).materialize_cursor() {
+            QueryResult::One(r) => match owned.as_mut() {
+                Some(acc) => acc.push(to_owned(&r)),
+                None => borrowed.push(r),
+            },
+            QueryResult::OneCursor(_) => unreachable!(),
+            QueryResult::Many(rs) => match owned.as_mut() {
+                Some(acc) => acc.extend(rs.iter().map(to_owned)),
+                None => borrowed.extend(rs),
+            },
+            QueryResult::Owned(r) => owned
+                .get_or_insert_with(|| {
+                    core::mem::take(&mut borrowed)
+                        .iter()
+                        .map(to_owned)
+                        .collect()
+                })
+                .push(r),
+            QueryResult::ManyOwned(rs) => owned
+                .get_or_insert_with(|| {
+                    core::mem::take(&mut borrowed)
+                        .iter()
+                        .map(to_owned)
+                        .collect()
+                })
+                .extend(rs),
+            QueryResult::None => {}
+            QueryResult::Error(e) => return QueryResult::Error(e),
+            QueryResult::Break(label) => return QueryResult::Break(label),
         }
-        QueryResult::ManyOwned(vs) => {
-            // Pipe each owned value through the rest
-            let mut all_results: Vec<OwnedValue> = Vec::new();
-            for v in vs {
-                match eval_owned_pipe::<Vec<u64>, S>(rest, v, optional).materialize_cursor() {
-                    QueryResult::Owned(r) => all_results.push(r),
-                    QueryResult::OneCursor(_) => unreachable!(),
-                    QueryResult::ManyOwned(rs) => all_results.extend(rs),
-                    QueryResult::One(r) => all_results.push(to_owned(&r)),
-                    QueryResult::Many(rs) => all_results.extend(rs.iter().map(to_owned)),
-                    QueryResult::None => {}
-                    QueryResult::Error(e) => return QueryResult::Error(e),
-                    QueryResult::Break(label) => return QueryResult::Break(label),
-                }
-            }
-            if all_results.is_empty() {
-                QueryResult::None
-            } else if all_results.len() == 1 {
-                QueryResult::Owned(all_results.pop().unwrap())
-            } else {
-                QueryResult::ManyOwned(all_results)
-            }
+    }
+    match owned {
+        Some(acc) => QueryResult::ManyOwned(acc),
+        None => QueryResult::Many(borrowed),
+    }
+}
+
+/// The `ManyOwned` case of [`eval_pipe_rest`]; see [`pipe_borrowed_values`].
+#[inline(never)]
+fn pipe_owned_values<'a, W, S: EvalSemantics>(
+    vs: Vec<OwnedValue>,
+    rest: &[Expr],
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    // Pipe each owned value through the rest
+    let mut all_results: Vec<OwnedValue> = Vec::new();
+    for v in vs {
+        match eval_owned_pipe::<Vec<u64>, S>(rest, v, scope).materialize_cursor() {
+            QueryResult::Owned(r) => all_results.push(r),
+            QueryResult::OneCursor(_) => unreachable!(),
+            QueryResult::ManyOwned(rs) => all_results.extend(rs),
+            QueryResult::One(r) => all_results.push(to_owned(&r)),
+            QueryResult::Many(rs) => all_results.extend(rs.iter().map(to_owned)),
+            QueryResult::None => {}
+            QueryResult::Error(e) => return QueryResult::Error(e),
+            QueryResult::Break(label) => return QueryResult::Break(label),
         }
     }
+    if all_results.is_empty() {
+        QueryResult::None
+    } else if all_results.len() == 1 {
+        QueryResult::Owned(all_results.pop().unwrap())
+    } else {
+        QueryResult::ManyOwned(all_results)
+    }
 }
 
 /// Evaluate a pipe with an OwnedValue as input.
 fn eval_owned_pipe<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     exprs: &[Expr],
     value: OwnedValue,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     if exprs.is_empty() {
         return QueryResult::Owned(value);
@@ -5564,8 +5530,9 @@ fn eval_owned_pipe<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         Expr::Pipe(exprs.to_vec())
     };
 
-    match eval_owned_expr::<S>(&rest_expr, &value, optional) {
-        Ok(v) => QueryResult::Owned(v),
+    match eval_owned_outputs::<S>(&rest_expr, &value, scope) {
+        Ok(mut vs) if vs.len() <= 1 => vs.pop().map_or(QueryResult::None, QueryResult::Owned),
+        Ok(vs) => QueryResult::ManyOwned(vs),
         Err(e) => QueryResult::Error(e),
     }
 }
@@ -5579,21 +5546,18 @@ fn find_field<'a, W: Clone + AsRef<[u64]>>(
 }
 
 /// Get element at index (supports negative indexing).
-///
-/// Uses `get_fast` for O(n) BP operations + O(log n) IB select,
-/// instead of `get` which does O(n) IB selects.
 fn get_element_at_index<W: Clone + AsRef<[u64]>>(
     elements: JsonElements<'_, W>,
     idx: i64,
 ) -> Option<StandardJson<'_, W>> {
     if idx >= 0 {
-        elements.get_fast(idx as usize)
+        elements.get(idx as usize)
     } else {
         // Negative index: count from end
         let len = count_elements(elements);
         let positive_idx = len as i64 + idx;
         if positive_idx >= 0 {
-            elements.get_fast(positive_idx as usize)
+            elements.get(positive_idx as usize)
         } else {
             None
         }
@@ -5665,7 +5629,7 @@ pub fn eval<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     if matches!(expr, Expr::Identity) {
         return QueryResult::OneCursor(cursor);
     }
-    eval_single::<W, S>(expr, cursor.value(), false)
+    eval_single::<W, S>(expr, cursor.value(), Scope::default())
 }
 
 /// Evaluate a jq expression, returning only successfully matched values.
@@ -5686,84 +5650,171 @@ pub fn eval_lenient<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 }
 
+/// Evaluate several jq expressions against one JSON cursor, yielding their
+/// outputs one at a time: all outputs of `exprs[0]`, then of `exprs[1]`, and
+/// so on, as if the expressions were joined with `,`.
+///
+/// Evaluation is lazy per expression: an expression runs only once the
+/// consumer has taken every output of the ones before it, so a consumer that
+/// stops early never evaluates the rest. Each expression is buffered as a
+/// whole, but `limit(n; ...)` and `first(...)` inside it stop after the
+/// outputs they keep, so `limit(10; .[] | f)` runs `f` on ten elements of
+/// even a huge array.
+///
+/// An evaluation error ends the iteration after the outputs produced before
+/// it; [`EvalIterator::error`] reports it.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::jq::{eval_many, parse, JqSemantics, OwnedValue};
+/// use succinctly::json::JsonIndex;
+///
+/// let json = br#"{"items": [1, 2, 3, 4]}"#;
+/// let index = JsonIndex::build(json);
+/// let exprs = [parse("limit(2; .items[])").unwrap(), parse(".items | length").unwrap()];
+///
+/// let values: Vec<_> = eval_many::<_, JqSemantics>(&exprs, index.root(json)).collect();
+/// assert_eq!(values, [OwnedValue::Int(1), OwnedValue::Int(2), OwnedValue::Int(4)]);
+/// ```
+pub fn eval_many<'e, 'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    exprs: &'e [Expr],
+    cursor: JsonCursor<'a, W>,
+) -> EvalIterator<'e, 'a, W, S> {
+    EvalIterator {
+        exprs,
+        cursor,
+        buffer: Vec::new().into_iter(),
+        pending: None,
+        error: None,
+        semantics: core::marker::PhantomData,
+    }
+}
+
+/// Iterator over the outputs of [`eval_many`].
+#[derive(Debug)]
+pub struct EvalIterator<'e, 'a, W = Vec<u64>, S = JqSemantics> {
+    /// Expressions not yet evaluated.
+    exprs: &'e [Expr],
+    cursor: JsonCursor<'a, W>,
+    /// Outputs of the current expression not yet yielded.
+    buffer: alloc::vec::IntoIter<OwnedValue>,
+    /// Error to report once the outputs before it are yielded.
+    pending: Option<EvalError>,
+    error: Option<EvalError>,
+    semantics: core::marker::PhantomData<S>,
+}
+
+impl<W, S> EvalIterator<'_, '_, W, S> {
+    /// The error that ended the iteration, if any.
+    pub fn error(&self) -> Option<&EvalError> {
+        self.error.as_ref()
+    }
+}
+
+impl<W: Clone + AsRef<[u64]>, S: EvalSemantics> Iterator for EvalIterator<'_, '_, W, S> {
+    type Item = OwnedValue;
+
+    fn next(&mut self) -> Option<OwnedValue> {
+        loop {
+            if let Some(value) = self.buffer.next() {
+                return Some(value);
+            }
+            if self.pending.is_some() {
+                self.error = self.pending.take();
+                return None;
+            }
+            let (expr, rest) = self.exprs.split_first()?;
+            self.exprs = rest;
+            // Outputs before an error are yielded before the error ends the iteration
+            let mut outputs = Vec::new();
+            let stop =
+                eval_until_error::<W, S>(expr, self.cursor.value(), Scope::default(), &mut outputs);
+            if let Some(QueryResult::Error(e)) = stop {
+                self.pending = Some(e);
+                self.exprs = &[];
+            }
+            self.buffer = outputs
+                .into_iter()
+                .flat_map(QueryResult::collect_owned)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+impl<W: Clone + AsRef<[u64]>, S: EvalSemantics> core::iter::FusedIterator
+    for EvalIterator<'_, '_, W, S>
+{
+}
+
 // =============================================================================
 // Assignment Operators Implementation
 // =============================================================================
 
 /// Evaluate simple assignment: `.path = value`
-/// Sets the value at path and returns the modified input.
+///
+/// As in jq, `value` is evaluated against the input, and each of its outputs
+/// is stored at every path `path` selects, giving one result per output.
 fn eval_assign<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     path_expr: &Expr,
     value_expr: &Expr,
     input: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // First evaluate the value expression
-    let new_value = match eval_single::<W, S>(value_expr, input.clone(), optional)
-        .materialize_cursor()
-    {
-        QueryResult::One(v) => to_owned(&v),
-        QueryResult::Owned(v) => v,
-        QueryResult::None => OwnedValue::Null,
-        QueryResult::Error(e) => return QueryResult::Error(e),
-        QueryResult::Many(vs) => {
-            // If value produces multiple results, use the first one
-            if let Some(v) = vs.first() {
-                to_owned(v)
-            } else {
-                OwnedValue::Null
-            }
-        }
-        QueryResult::ManyOwned(vs) => {
-            if let Some(v) = vs.into_iter().next() {
-                v
-            } else {
-                OwnedValue::Null
-            }
-        }
-        QueryResult::OneCursor(_) => unreachable!("materialize_cursor should have converted this"),
-        QueryResult::Break(label) => return QueryResult::Break(label),
+    let values = match result_to_owned_all(eval_single::<W, S>(value_expr, input.clone(), scope)) {
+        Ok(values) => values,
+        Err(e) => return QueryResult::Error(e),
+    };
+    let input = to_owned(&input);
+    let paths = match assignment_paths::<S>(path_expr, &input, scope) {
+        Ok(paths) => paths,
+        Err(e) => return QueryResult::Error(e),
     };
 
-    // Convert input to owned for modification
-    let mut result = to_owned(&input);
-
-    // Apply the assignment using path
-    if let Err(e) = set_path(&mut result, path_expr, new_value) {
-        return QueryResult::Error(e);
+    let mut results = Vec::with_capacity(values.len());
+    for new_value in values {
+        let mut result = input.clone();
+        for path in &paths {
+            result = match set_owned_path(result, path, new_value.clone()) {
+                Ok(result) => result,
+                Err(e) => return QueryResult::Error(e),
+            };
+        }
+        results.push(result);
     }
-
-    QueryResult::Owned(result)
+    owned_results(results)
 }
 
 /// Evaluate update assignment: `.path |= filter`
-/// Applies filter to the value at path and updates it.
+///
+/// Replaces the value at each path with the first output of `filter` applied
+/// to it, or deletes the path if `filter` produces nothing (jq 1.7). Paths are
+/// updated one after another, so a deletion shifts the array indices of the
+/// paths after it, as in jq: `[1, 2, 3] | .[] |= empty` is `[2]`.
 fn eval_update<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     path_expr: &Expr,
     filter_expr: &Expr,
     input: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Convert input to owned for modification
-    let mut result = to_owned(&input);
-
-    // Get current value at path, apply filter, and set back
-    if let Err(e) = update_path::<S>(&mut result, path_expr, filter_expr, optional) {
-        return QueryResult::Error(e);
+    match update_paths::<S>(to_owned(&input), path_expr, filter_expr, scope) {
+        Ok(result) => QueryResult::Owned(result),
+        Err(e) => QueryResult::Error(e),
     }
-
-    QueryResult::Owned(result)
 }
 
 /// Evaluate compound assignment: `.path += value`, `.path -= value`, etc.
+///
+/// `value` is evaluated against the input, not the value at the path, so
+/// `.a += .b` adds the input's `.b`. Each output of `value` gives a result.
 fn eval_compound_assign<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     op: AssignOp,
     path_expr: &Expr,
     value_expr: &Expr,
     input: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Convert to update: .path op= value  becomes  .path |= . op value
     let arith_op = match op {
         AssignOp::Add => ArithOp::Add,
         AssignOp::Sub => ArithOp::Sub,
@@ -5771,14 +5822,13 @@ fn eval_compound_assign<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         AssignOp::Div => ArithOp::Div,
         AssignOp::Mod => ArithOp::Mod,
     };
-
-    let filter = Expr::Arithmetic {
-        op: arith_op,
-        left: Box::new(Expr::Identity),
-        right: value_expr.clone().into(),
-    };
-
-    eval_update::<W, S>(path_expr, &filter, input, optional)
+    eval_update_with_each::<W, S>(path_expr, value_expr, input, scope, |value| {
+        Expr::Arithmetic {
+            op: arith_op,
+            left: Box::new(Expr::Identity),
+            right: Box::new(value),
+        }
+    })
 }
 
 /// Evaluate alternative assignment: `.path //= value`
@@ -5786,251 +5836,109 @@ fn eval_compound_assign<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn eval_alternative_assign<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     path_expr: &Expr,
     value_expr: &Expr,
-    input: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Convert to update: .path //= value  becomes  .path |= . // value
-    let filter = Expr::Alternative(Box::new(Expr::Identity), Box::new(value_expr.clone()));
-
-    eval_update::<W, S>(path_expr, &filter, input, optional)
-}
-
-/// Set a value at a path in an owned value.
-fn set_path(
-    root: &mut OwnedValue,
-    path_expr: &Expr,
-    new_value: OwnedValue,
-) -> Result<(), EvalError> {
-    match path_expr {
-        Expr::Identity => {
-            *root = new_value;
-            Ok(())
-        }
-        Expr::Field(name) => {
-            if let OwnedValue::Object(map) = root {
-                map.insert(name.clone(), new_value);
-                Ok(())
-            } else {
-                Err(EvalError::type_error("object", owned_type_name(root)))
-            }
-        }
-        Expr::Index(idx) => {
-            if let OwnedValue::Array(arr) = root {
-                let len = arr.len() as i64;
-                let actual_idx = if *idx < 0 { len + idx } else { *idx };
-                if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                    arr[actual_idx as usize] = new_value;
-                    Ok(())
-                } else {
-                    Err(EvalError::index_out_of_bounds(*idx, arr.len()))
-                }
-            } else {
-                Err(EvalError::type_error("array", owned_type_name(root)))
-            }
-        }
-        Expr::Pipe(exprs) if !exprs.is_empty() => {
-            // For chained paths like .a.b.c, navigate to parent and set at last element
-            if exprs.len() == 1 {
-                set_path(root, &exprs[0], new_value)
-            } else {
-                // Navigate to parent
-                let parent_path = &exprs[..exprs.len() - 1];
-                let last_path = &exprs[exprs.len() - 1];
-
-                let parent = get_path_mut(root, parent_path)?;
-                set_path(parent, last_path, new_value)
-            }
-        }
-        Expr::Optional(inner) => {
-            // For optional assignment, try to set but don't error if path doesn't exist
-            match set_path(root, inner, new_value) {
-                Ok(()) => Ok(()),
-                Err(_) => Ok(()), // Silently succeed for optional
-            }
-        }
-        _ => Err(EvalError::new(
-            "cannot use expression as assignment target".to_string(),
-        )),
-    }
-}
-
-/// Get a mutable reference to a value at a path.
-fn get_path_mut<'a>(
-    root: &'a mut OwnedValue,
-    path_parts: &[Expr],
-) -> Result<&'a mut OwnedValue, EvalError> {
-    let mut current = root;
-
-    for part in path_parts {
-        current = match part {
-            Expr::Identity => current,
-            Expr::Field(name) => {
-                if let OwnedValue::Object(map) = current {
-                    map.entry(name.clone()).or_insert(OwnedValue::Null)
-                } else {
-                    return Err(EvalError::type_error("object", owned_type_name(current)));
-                }
-            }
-            Expr::Index(idx) => {
-                if let OwnedValue::Array(arr) = current {
-                    let len = arr.len() as i64;
-                    let actual_idx = if *idx < 0 { len + idx } else { *idx };
-                    if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                        &mut arr[actual_idx as usize]
-                    } else {
-                        return Err(EvalError::index_out_of_bounds(*idx, arr.len()));
-                    }
-                } else {
-                    return Err(EvalError::type_error("array", owned_type_name(current)));
-                }
-            }
-            _ => return Err(EvalError::new("invalid path component")),
-        };
-    }
-
-    Ok(current)
-}
-
-/// Update a value at a path by applying a filter.
-fn update_path<S: EvalSemantics>(
-    root: &mut OwnedValue,
-    path_expr: &Expr,
-    filter_expr: &Expr,
-    optional: bool,
-) -> Result<(), EvalError> {
-    match path_expr {
-        Expr::Identity => {
-            // Apply filter to root itself using eval_owned_expr
-            match eval_owned_expr::<S>(filter_expr, root, optional) {
-                Ok(v) => {
-                    *root = v;
-                    Ok(())
-                }
-                Err(_e) if optional => Ok(()),
-                Err(e) => Err(e),
-            }
-        }
-        Expr::Field(name) => {
-            if let OwnedValue::Object(map) = root {
-                let current = map.entry(name.clone()).or_insert(OwnedValue::Null);
-                update_path::<S>(current, &Expr::Identity, filter_expr, optional)
-            } else if optional {
-                Ok(())
-            } else {
-                Err(EvalError::type_error("object", owned_type_name(root)))
-            }
-        }
-        Expr::Index(idx) => {
-            if let OwnedValue::Array(arr) = root {
-                let len = arr.len() as i64;
-                let actual_idx = if *idx < 0 { len + idx } else { *idx };
-                if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                    update_path::<S>(
-                        &mut arr[actual_idx as usize],
-                        &Expr::Identity,
-                        filter_expr,
-                        optional,
-                    )
-                } else if optional {
-                    Ok(())
-                } else {
-                    Err(EvalError::index_out_of_bounds(*idx, arr.len()))
-                }
-            } else if optional {
-                Ok(())
-            } else {
-                Err(EvalError::type_error("array", owned_type_name(root)))
-            }
-        }
-        Expr::Iterate => {
-            // Update all elements
-            match root {
-                OwnedValue::Array(arr) => {
-                    for elem in arr.iter_mut() {
-                        update_path::<S>(elem, &Expr::Identity, filter_expr, optional)?;
-                    }
-                    Ok(())
-                }
-                OwnedValue::Object(map) => {
-                    for value in map.values_mut() {
-                        update_path::<S>(value, &Expr::Identity, filter_expr, optional)?;
-                    }
-                    Ok(())
-                }
-                _ if optional => Ok(()),
-                _ => Err(EvalError::type_error(
-                    "array or object",
-                    owned_type_name(root),
-                )),
-            }
-        }
-        Expr::Pipe(exprs) if !exprs.is_empty() => {
-            // Chain: navigate and update
-            if exprs.len() == 1 {
-                update_path::<S>(root, &exprs[0], filter_expr, optional)
-            } else {
-                // Navigate to the penultimate path, then update the last
-                let first = &exprs[0];
-                let rest = Expr::Pipe(exprs[1..].to_vec());
+    input: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    eval_update_with_each::<W, S>(path_expr, value_expr, input, scope, |value| {
+        Expr::Alternative(Box::new(Expr::Identity), Box::new(value))
+    })
+}
 
-                match first {
-                    Expr::Field(name) => {
-                        if let OwnedValue::Object(map) = root {
-                            let current = map.entry(name.clone()).or_insert(OwnedValue::Null);
-                            update_path::<S>(current, &rest, filter_expr, optional)
-                        } else if optional {
-                            Ok(())
-                        } else {
-                            Err(EvalError::type_error("object", owned_type_name(root)))
-                        }
-                    }
-                    Expr::Index(idx) => {
-                        if let OwnedValue::Array(arr) = root {
-                            let len = arr.len() as i64;
-                            let actual_idx = if *idx < 0 { len + idx } else { *idx };
-                            if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                                update_path::<S>(
-                                    &mut arr[actual_idx as usize],
-                                    &rest,
-                                    filter_expr,
-                                    optional,
-                                )
-                            } else if optional {
-                                Ok(())
-                            } else {
-                                Err(EvalError::index_out_of_bounds(*idx, arr.len()))
-                            }
-                        } else if optional {
-                            Ok(())
-                        } else {
-                            Err(EvalError::type_error("array", owned_type_name(root)))
-                        }
-                    }
-                    Expr::Iterate => match root {
-                        OwnedValue::Array(arr) => {
-                            for elem in arr.iter_mut() {
-                                update_path::<S>(elem, &rest, filter_expr, optional)?;
-                            }
-                            Ok(())
-                        }
-                        OwnedValue::Object(map) => {
-                            for value in map.values_mut() {
-                                update_path::<S>(value, &rest, filter_expr, optional)?;
-                            }
-                            Ok(())
-                        }
-                        _ if optional => Ok(()),
-                        _ => Err(EvalError::type_error(
-                            "array or object",
-                            owned_type_name(root),
-                        )),
-                    },
-                    _ => update_path::<S>(root, first, filter_expr, optional),
-                }
-            }
+/// Update `path` with `filter(v)` for each output `v` of `value_expr` on the
+/// input, giving one result per output: `lhs op= rhs` is `rhs as $v | lhs |= . op $v`.
+fn eval_update_with_each<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    path_expr: &Expr,
+    value_expr: &Expr,
+    input: StandardJson<'a, W>,
+    scope: Scope,
+    filter: impl Fn(Expr) -> Expr,
+) -> QueryResult<'a, W> {
+    let values = match result_to_owned_all(eval_single::<W, S>(value_expr, input.clone(), scope)) {
+        Ok(values) => values,
+        Err(e) => return QueryResult::Error(e),
+    };
+    let input = to_owned(&input);
+
+    let mut results = Vec::with_capacity(values.len());
+    for value in values {
+        let filter = filter(owned_to_expr(&value));
+        match update_paths::<S>(input.clone(), path_expr, &filter, scope) {
+            Ok(result) => results.push(result),
+            Err(e) => return QueryResult::Error(e),
         }
-        Expr::Optional(inner) => update_path::<S>(root, inner, filter_expr, true),
-        _ => Err(EvalError::new("cannot use expression as update target")),
+    }
+    owned_results(results)
+}
+
+/// Wrap the results of an assignment, one per right-hand output.
+fn owned_results<'a, W>(mut results: Vec<OwnedValue>) -> QueryResult<'a, W> {
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
+}
+
+/// The paths an assignment's left-hand side selects in `input`, as for
+/// `path(lhs)`.
+fn assignment_paths<S: EvalSemantics>(
+    path_expr: &Expr,
+    input: &OwnedValue,
+    scope: Scope,
+) -> Result<Vec<Vec<OwnedValue>>, EvalError> {
+    // path() reports a slice as the paths of its elements, so assigning
+    // through one would replace each element rather than the slice
+    if has_slice_step(path_expr) {
+        return Err(EvalError::new("cannot use a slice as an assignment target"));
+    }
+    let selected = eval_paths::<S>(path_expr, input, &[], scope)?;
+    Ok(selected.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Whether a path expression steps through a slice.
+fn has_slice_step(expr: &Expr) -> bool {
+    match expr {
+        Expr::Slice { .. } => true,
+        Expr::Pipe(exprs) | Expr::Comma(exprs) => exprs.iter().any(has_slice_step),
+        Expr::Paren(inner) | Expr::Optional(inner) => has_slice_step(inner),
+        _ => false,
+    }
+}
+
+/// Apply `filter` to each path `path_expr` selects in `input`, in order.
+fn update_paths<S: EvalSemantics>(
+    mut input: OwnedValue,
+    path_expr: &Expr,
+    filter_expr: &Expr,
+    scope: Scope,
+) -> Result<OwnedValue, EvalError> {
+    let paths = assignment_paths::<S>(path_expr, &input, scope)?;
+    let first = Expr::FirstExpr(Box::new(filter_expr.clone()));
+    for path in paths {
+        let Some(components) = path_components(&input, &path)? else {
+            return Err(EvalError::new("Out of bounds negative array index"));
+        };
+        let current = input
+            .get_path(&components)
+            .cloned()
+            .unwrap_or(OwnedValue::Null);
+        input = match eval_owned_outputs::<S>(&first, &current, scope)?.pop() {
+            Some(new_value) => input.set_path(&components, new_value),
+            None => input.del_paths(&[components]),
+        };
+    }
+    Ok(input)
+}
+
+/// Store `new_value` at `path` in `root`, creating missing containers.
+fn set_owned_path(
+    root: OwnedValue,
+    path: &[OwnedValue],
+    new_value: OwnedValue,
+) -> Result<OwnedValue, EvalError> {
+    match path_components(&root, path)? {
+        Some(components) => Ok(root.set_path(&components, new_value)),
+        None => Err(EvalError::new("Out of bounds negative array index")),
     }
 }
 
@@ -6169,7 +6077,10 @@ fn substitute_var(expr: &Expr, var_name: &str, replacement: &OwnedValue) -> Expr
                 .as_ref()
                 .map(|e| Box::new(substitute_var(e, var_name, replacement))),
         },
-        Expr::Error(msg) => Expr::Error(msg.clone()),
+        Expr::Error(msg) => Expr::Error(
+            msg.as_ref()
+                .map(|m| Box::new(substitute_var(m, var_name, replacement))),
+        ),
         Expr::Builtin(b) => Expr::Builtin(substitute_var_in_builtin(b, var_name, replacement)),
         Expr::StringInterpolation(parts) => Expr::StringInterpolation(
             parts
@@ -6417,6 +6328,14 @@ fn substitute_var_in_builtin(
         Builtin::Add => Builtin::Add,
         Builtin::Any => Builtin::Any,
         Builtin::All => Builtin::All,
+        Builtin::AnyOf(g, c) => Builtin::AnyOf(
+            Box::new(substitute_var(g, var_name, replacement)),
+            Box::new(substitute_var(c, var_name, replacement)),
+        ),
+        Builtin::AllOf(g, c) => Builtin::AllOf(
+            Box::new(substitute_var(g, var_name, replacement)),
+            Box::new(substitute_var(c, var_name, replacement)),
+        ),
         Builtin::Min => Builtin::Min,
         Builtin::Max => Builtin::Max,
         Builtin::MinBy(e) => Builtin::MinBy(Box::new(substitute_var(e, var_name, replacement))),
@@ -6467,6 +6386,7 @@ fn substitute_var_in_builtin(
         Builtin::FromJson => Builtin::FromJson,
         Builtin::Explode => Builtin::Explode,
         Builtin::Implode => Builtin::Implode,
+        Builtin::Ascii(e) => Builtin::Ascii(Box::new(substitute_var(e, var_name, replacement))),
         Builtin::Test(e) => Builtin::Test(Box::new(substitute_var(e, var_name, replacement))),
         Builtin::Indices(e) => Builtin::Indices(Box::new(substitute_var(e, var_name, replacement))),
         Builtin::Index(e) => Builtin::Index(Box::new(substitute_var(e, var_name, replacement))),
@@ -6692,6 +6612,11 @@ fn substitute_var_in_builtin(
             Box::new(substitute_var(line, var_name, replacement)),
             Box::new(substitute_var(col, var_name, replacement)),
         ),
+
+        // Phase 24: Schema validation (succinctly extension)
+        Builtin::SchemaValidates(e) => {
+            Builtin::SchemaValidates(Box::new(substitute_var(e, var_name, replacement)))
+        }
     }
 }
 
@@ -6732,10 +6657,10 @@ fn eval_as<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     var: &str,
     body: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the expression to get the value to bind
-    let bound_result = eval_single::<W, S>(expr, value.clone(), optional);
+    let bound_result = eval_single::<W, S>(expr, value.clone(), scope);
 
     // Get all values from the expression
     let bound_values: Vec<OwnedValue> = match bound_result.materialize_cursor() {
@@ -6754,7 +6679,7 @@ fn eval_as<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
     for bound_val in bound_values {
         let substituted_body = substitute_var(body, var, &bound_val);
-        match eval_single::<W, S>(&substituted_body, value.clone(), optional).materialize_cursor() {
+        match eval_single::<W, S>(&substituted_body, value.clone(), scope).materialize_cursor() {
             QueryResult::One(v) => all_results.push(to_owned(&v)),
             QueryResult::OneCursor(_) => unreachable!(),
             QueryResult::Many(vs) => all_results.extend(vs.iter().map(to_owned)),
@@ -6782,10 +6707,10 @@ fn eval_reduce<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     init: &Expr,
     update: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate input to get the stream of values
-    let input_result = eval_single::<W, S>(input, value.clone(), optional);
+    let input_result = eval_single::<W, S>(input, value.clone(), scope);
     let input_values: Vec<OwnedValue> = match input_result.materialize_cursor() {
         QueryResult::One(v) => vec![to_owned(&v)],
         QueryResult::OneCursor(_) => unreachable!(),
@@ -6797,34 +6722,56 @@ fn eval_reduce<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         QueryResult::Break(label) => return QueryResult::Break(label),
     };
 
-    // Evaluate initial accumulator
-    let init_result = eval_single::<W, S>(init, value.clone(), optional);
-    let mut acc = match result_to_owned(init_result) {
-        Ok(v) => v,
+    // Each output of init starts its own reduction, as in jq
+    let inits = match result_to_owned_all(eval_single::<W, S>(init, value.clone(), scope)) {
+        Ok(inits) => inits,
         Err(e) => return QueryResult::Error(e),
     };
 
-    // For each input value, update the accumulator
-    for input_val in input_values {
-        // Substitute $var in update, then evaluate with acc as input
-        let substituted = substitute_var(update, var, &input_val);
-        // We need to evaluate with acc as the input
-        let acc_result = eval_owned_expr::<S>(&substituted, &acc, optional);
-        match acc_result {
-            Ok(new_acc) => acc = new_acc,
-            Err(e) => return QueryResult::Error(e),
+    let mut results = Vec::with_capacity(inits.len());
+    for mut acc in inits {
+        for input_val in &input_values {
+            // Substitute $var in update, then evaluate with acc as input.
+            // jq 1.7 keeps the last output of update, or null if it has none.
+            let substituted = substitute_var(update, var, input_val);
+            match eval_owned_outputs::<S>(&substituted, &acc, scope) {
+                Ok(outputs) => acc = outputs.into_iter().last().unwrap_or(OwnedValue::Null),
+                Err(e) => return QueryResult::Error(e),
+            }
         }
+        results.push(acc);
     }
 
-    QueryResult::Owned(acc)
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
 }
 
 /// Evaluate an expression with an OwnedValue as input.
+///
+/// Multiple outputs are collected into an array and no output becomes null;
+/// use [`eval_owned_outputs`] to see the outputs individually.
 fn eval_owned_expr<S: EvalSemantics>(
     expr: &Expr,
     input: &OwnedValue,
-    optional: bool,
+    scope: Scope,
 ) -> Result<OwnedValue, EvalError> {
+    let mut outputs = eval_owned_outputs::<S>(expr, input, scope)?;
+    Ok(match outputs.len() {
+        0 => OwnedValue::Null,
+        1 => outputs.pop().expect("one output"),
+        _ => OwnedValue::Array(outputs),
+    })
+}
+
+/// Evaluate an expression with an OwnedValue as input, returning every output.
+fn eval_owned_outputs<S: EvalSemantics>(
+    expr: &Expr,
+    input: &OwnedValue,
+    scope: Scope,
+) -> Result<Vec<OwnedValue>, EvalError> {
     // Create a synthetic JSON from the owned value
     // For simplicity, we'll serialize and reparse
     // This is inefficient but correct
@@ -6832,44 +6779,41 @@ fn eval_owned_expr<S: EvalSemantics>(
     let json_bytes = json_str.as_bytes();
 
     // We need to create a temporary index and cursor
-    use crate::json::JsonIndex;
-    let index = JsonIndex::build(json_bytes);
+    let index = build_index_boxed(json_bytes);
     let cursor = index.root(json_bytes);
 
-    match eval_single::<Vec<u64>, S>(expr, cursor.value(), optional).materialize_cursor() {
-        QueryResult::One(v) => Ok(to_owned(&v)),
-        QueryResult::OneCursor(_) => unreachable!(),
-        QueryResult::Owned(v) => Ok(v),
-        QueryResult::Many(vs) => {
-            if vs.len() == 1 {
-                Ok(to_owned(&vs[0]))
-            } else {
-                Ok(OwnedValue::Array(vs.iter().map(to_owned).collect()))
-            }
-        }
-        QueryResult::ManyOwned(vs) => {
-            if vs.len() == 1 {
-                Ok(vs.into_iter().next().unwrap())
-            } else {
-                Ok(OwnedValue::Array(vs))
-            }
-        }
-        QueryResult::None => Ok(OwnedValue::Null),
-        QueryResult::Error(e) => Err(e),
-        QueryResult::Break(label) => Err(EvalError::new(format!("break ${label} not in label"))),
-    }
+    result_to_owned_all(eval_single::<Vec<u64>, S>(expr, cursor.value(), scope))
+}
+
+/// Index `json` on the heap. Every call level of a recursive function
+/// re-indexes its input in [`eval_owned_outputs`], so keeping the index out of
+/// that frame leaves more of the stack for deeper recursion.
+#[inline(never)]
+fn build_index_boxed(json: &[u8]) -> Box<crate::json::JsonIndex> {
+    Box::new(crate::json::JsonIndex::build(json))
 }
 
-/// Convert an OwnedValue to a JSON string.
-fn owned_value_to_json_string(value: &OwnedValue) -> String {
+/// Convert an OwnedValue to the JSON text [`eval_owned_outputs`] re-indexes.
+///
+/// Infinities are written as `1e999` / `-1e999`, which parse back to the
+/// same value, and NaN as [`NAN_TEXT`], which [`number_as_f64`] reads back as
+/// NaN.
+pub(super) fn owned_value_to_json_string(value: &OwnedValue) -> String {
     match value {
         OwnedValue::Null => "null".into(),
         OwnedValue::Bool(true) => "true".into(),
         OwnedValue::Bool(false) => "false".into(),
         OwnedValue::Int(i) => format!("{i}"),
         OwnedValue::Float(f) => {
-            if f.is_nan() || f.is_infinite() {
-                "null".into() // JSON doesn't have NaN or Infinity
+            if f.is_nan() {
+                NAN_TEXT.into()
+            } else if f.is_infinite() {
+                if f.is_sign_positive() {
+                    "1e999"
+                } else {
+                    "-1e999"
+                }
+                .into()
             } else {
                 format!("{f}")
             }
@@ -6929,10 +6873,10 @@ fn eval_foreach<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     update: &Expr,
     extract: Option<&Expr>,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate input to get the stream
-    let input_result = eval_single::<W, S>(input, value.clone(), optional);
+    let input_result = eval_single::<W, S>(input, value.clone(), scope);
     let input_values: Vec<OwnedValue> = match input_result.materialize_cursor() {
         QueryResult::One(v) => vec![to_owned(&v)],
         QueryResult::OneCursor(_) => unreachable!(),
@@ -6944,34 +6888,37 @@ fn eval_foreach<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         QueryResult::Break(label) => return QueryResult::Break(label),
     };
 
-    // Evaluate initial state
-    let init_result = eval_single::<W, S>(init, value.clone(), optional);
-    let mut state = match result_to_owned(init_result) {
-        Ok(v) => v,
+    // Each output of init starts its own loop, as in jq
+    let inits = match result_to_owned_all(eval_single::<W, S>(init, value.clone(), scope)) {
+        Ok(inits) => inits,
         Err(e) => return QueryResult::Error(e),
     };
 
     let mut outputs: Vec<OwnedValue> = Vec::new();
 
-    for input_val in input_values {
-        // Substitute $var and evaluate update with state as input
-        let substituted_update = substitute_var(update, var, &input_val);
-        match eval_owned_expr::<S>(&substituted_update, &state, optional) {
-            Ok(new_state) => {
+    for mut state in inits {
+        for input_val in &input_values {
+            // Every output of update becomes the state in turn and is extracted;
+            // the last one carries over, and no output leaves the state as is.
+            let substituted_update = substitute_var(update, var, input_val);
+            let new_states = match eval_owned_outputs::<S>(&substituted_update, &state, scope) {
+                Ok(states) => states,
+                Err(e) => return QueryResult::Error(e),
+            };
+            for new_state in new_states {
                 state = new_state;
-                // If there's an extract expression, evaluate it
-                if let Some(ext) = extract {
-                    let substituted_extract = substitute_var(ext, var, &input_val);
-                    match eval_owned_expr::<S>(&substituted_extract, &state, optional) {
-                        Ok(output) => outputs.push(output),
-                        Err(e) => return QueryResult::Error(e),
+                match extract {
+                    Some(ext) => {
+                        let substituted_extract = substitute_var(ext, var, input_val);
+                        match eval_owned_outputs::<S>(&substituted_extract, &state, scope) {
+                            Ok(extracted) => outputs.extend(extracted),
+                            Err(e) => return QueryResult::Error(e),
+                        }
                     }
-                } else {
                     // Without extract, output the current state
-                    outputs.push(state.clone());
+                    None => outputs.push(state.clone()),
                 }
             }
-            Err(e) => return QueryResult::Error(e),
         }
     }
 
@@ -6984,96 +6931,229 @@ fn eval_foreach<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 }
 
-/// Evaluate `limit(n; expr)` - take first n outputs.
-fn eval_limit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    n_expr: &Expr,
+/// Evaluate `expr`, producing at most its first `max` outputs.
+///
+/// The evaluator is strict, so `expr` normally runs to completion before it is
+/// truncated. Generators that can stop early do: `range`, `repeat` and the
+/// `recurse` family produce only `max` values, and comma branches past the
+/// first `max` outputs, even failing ones, are never evaluated
+/// (`first(1, error("x"))` is `1`). Likewise `.[]` visits only the first
+/// `max` elements, and a pipe stops feeding its later stages once they have
+/// produced `max` outputs, so `limit(10; .[] | f)` runs `f` on as few elements
+/// as it needs. A leading `recurse` is walked the same way, so
+/// `first(recurse(. + 1) | select(. > 5))` ends.
+fn eval_at_most<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
+    max: usize,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Evaluate n
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
-    let n = match result_to_owned(n_result) {
-        Ok(OwnedValue::Int(i)) if i >= 0 => i as usize,
-        Ok(_) => {
-            return QueryResult::Error(EvalError::new("limit requires non-negative integer"));
-        }
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    if n == 0 {
+    if max == 0 {
         return QueryResult::None;
     }
 
-    // Evaluate expr and take first n
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result {
-        QueryResult::One(v) if n >= 1 => QueryResult::One(v),
-        QueryResult::Many(vs) => {
-            let taken: Vec<_> = vs.into_iter().take(n).collect();
-            if taken.is_empty() {
-                QueryResult::None
-            } else if taken.len() == 1 {
-                QueryResult::One(taken.into_iter().next().unwrap())
-            } else {
-                QueryResult::Many(taken)
+    match expr {
+        Expr::Paren(inner) => eval_at_most::<W, S>(inner, max, value, scope),
+        Expr::Range { from, to, step } => {
+            eval_range::<W, S>(from, to.as_deref(), step.as_deref(), max, value, scope)
+        }
+        Expr::Repeat(inner) => eval_repeat::<W, S>(inner, max, value, scope),
+        Expr::Builtin(
+            builtin @ (Builtin::Recurse
+            | Builtin::RecurseDown
+            | Builtin::RecurseF(_)
+            | Builtin::RecurseCond(..)),
+        ) => builtin_recurse::<W, S>(builtin, value, scope, max),
+        Expr::Optional(inner) => eval_at_most::<W, S>(inner, max, value, scope.optional()),
+        Expr::Label { name, body } => match eval_at_most::<W, S>(body, max, value, scope) {
+            QueryResult::Break(label) if label == *name => QueryResult::None,
+            other => other,
+        },
+        Expr::Iterate => match value {
+            StandardJson::Array(elements) => QueryResult::Many(elements.take(max).collect()),
+            StandardJson::Object(fields) => {
+                QueryResult::Many(fields.take(max).map(|f| f.value()).collect())
             }
+            value => eval_single::<W, S>(expr, value, scope),
         }
-        QueryResult::Owned(v) if n >= 1 => QueryResult::Owned(v),
-        QueryResult::ManyOwned(vs) => {
-            let taken: Vec<_> = vs.into_iter().take(n).collect();
-            if taken.is_empty() {
-                QueryResult::None
-            } else if taken.len() == 1 {
-                QueryResult::Owned(taken.into_iter().next().unwrap())
-            } else {
-                QueryResult::ManyOwned(taken)
+        .take(max),
+        Expr::Pipe(exprs) => eval_pipe_at_most::<W, S>(exprs, max, value, scope),
+        Expr::Comma(exprs) => {
+            let mut results = Vec::new();
+            let mut remaining = max;
+            for expr in exprs {
+                let result = eval_at_most::<W, S>(expr, remaining, value.clone(), scope);
+                if matches!(result, QueryResult::Error(_) | QueryResult::Break(_)) {
+                    return result;
+                }
+                remaining -= result.output_count();
+                results.push(result);
+                if remaining == 0 {
+                    break;
+                }
             }
+            concat_results(results)
         }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        _ => QueryResult::None,
+        _ => eval_single::<W, S>(expr, value, scope).take(max),
     }
 }
 
-/// Evaluate `first(expr)` - take first output.
-fn eval_first_expr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    expr: &Expr,
+/// [`eval_at_most`] for a pipe: the later stages run on one output of the
+/// first stage at a time until they have produced `max` outputs.
+fn eval_pipe_at_most<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    exprs: &[Expr],
+    max: usize,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result.materialize_cursor() {
-        QueryResult::One(v) => QueryResult::One(v),
-        QueryResult::OneCursor(_) => unreachable!(),
-        QueryResult::Many(vs) => {
-            if let Some(first) = vs.into_iter().next() {
-                QueryResult::One(first)
-            } else {
-                QueryResult::None
-            }
+    let (first, rest) = match exprs {
+        [] => return eval_pipe::<W, S>(exprs, value, scope).take(max),
+        [only] => return eval_at_most::<W, S>(only, max, value, scope),
+        [first, rest @ ..] => (first, rest),
+    };
+    if exprs.iter().any(needs_path_context) {
+        return eval_pipe::<W, S>(exprs, value, scope).take(max);
+    }
+
+    let value = match (first, value) {
+        (Expr::Iterate, StandardJson::Array(elements)) => {
+            return pipe_each_at_most::<W, S>(rest, max, elements, scope);
         }
-        QueryResult::Owned(v) => QueryResult::Owned(v),
-        QueryResult::ManyOwned(vs) => {
-            if let Some(first) = vs.into_iter().next() {
-                QueryResult::Owned(first)
-            } else {
-                QueryResult::None
+        (Expr::Iterate, StandardJson::Object(fields)) => {
+            return pipe_each_at_most::<W, S>(rest, max, fields.map(|f| f.value()), scope);
+        }
+        (Expr::Builtin(builtin), value) => {
+            if let Some(values) = recurse_iter::<W, S>(builtin, &value, scope) {
+                return pipe_owned_at_most::<W, S>(rest, max, values, scope);
             }
+            value
         }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        QueryResult::Break(label) => QueryResult::Break(label),
+        (_, value) => value,
+    };
+    match eval_single::<W, S>(first, value, scope).materialize_cursor() {
+        QueryResult::One(v) => eval_pipe_at_most::<W, S>(rest, max, v, scope),
+        QueryResult::Many(vs) => pipe_each_at_most::<W, S>(rest, max, vs.into_iter(), scope),
+        QueryResult::Owned(v) => pipe_owned_at_most::<W, S>(rest, max, core::iter::once(v), scope),
+        QueryResult::ManyOwned(vs) => pipe_owned_at_most::<W, S>(rest, max, vs.into_iter(), scope),
+        other => eval_pipe_rest::<W, S>(other, rest, scope).take(max),
+    }
+}
+
+/// Run the pipe stages `rest` on each of `inputs` in turn, stopping once they
+/// have produced `max` outputs.
+fn pipe_each_at_most<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    rest: &[Expr],
+    max: usize,
+    inputs: impl Iterator<Item = StandardJson<'a, W>>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let mut results = Vec::new();
+    let mut remaining = max;
+    for input in inputs {
+        if remaining == 0 {
+            break;
+        }
+        let result = eval_pipe_at_most::<W, S>(rest, remaining, input, scope);
+        if matches!(result, QueryResult::Error(_) | QueryResult::Break(_)) {
+            return result;
+        }
+        remaining -= result.output_count();
+        results.push(result);
+    }
+    concat_results(results).take(max)
+}
+
+/// [`pipe_each_at_most`] for owned inputs, which are pulled from `inputs` only
+/// while fewer than `max` outputs have been produced.
+fn pipe_owned_at_most<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    rest: &[Expr],
+    max: usize,
+    inputs: impl Iterator<Item = OwnedValue>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let mut results = Vec::new();
+    for input in inputs {
+        if results.len() >= max {
+            break;
+        }
+        if rest.is_empty() {
+            results.push(input);
+            continue;
+        }
+        // Re-index the owned value so the stages can stop early too
+        let json = owned_value_to_json_string(&input);
+        let index = crate::json::JsonIndex::build(json.as_bytes());
+        let cursor = index.root(json.as_bytes());
+        let remaining = max - results.len();
+        match eval_pipe_at_most::<Vec<u64>, S>(rest, remaining, cursor.value(), scope)
+            .materialize_cursor()
+        {
+            QueryResult::Owned(r) => results.push(r),
+            QueryResult::ManyOwned(rs) => results.extend(rs),
+            QueryResult::One(r) => results.push(to_owned(&r)),
+            QueryResult::Many(rs) => results.extend(rs.iter().map(to_owned)),
+            QueryResult::OneCursor(_) => unreachable!(),
+            QueryResult::None => {}
+            QueryResult::Error(e) => return QueryResult::Error(e),
+            QueryResult::Break(label) => return QueryResult::Break(label),
+        }
+    }
+    results.truncate(max);
+    owned_outputs(results)
+}
+
+/// Evaluate the count argument of `limit`/`nth`.
+fn eval_count<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    name: &str,
+    n_expr: &Expr,
+    value: StandardJson<'_, W>,
+    scope: Scope,
+) -> Result<i64, EvalError> {
+    match result_to_owned(eval_single::<W, S>(n_expr, value, scope))? {
+        OwnedValue::Int(i) => Ok(i),
+        OwnedValue::Float(f) => Ok(f.ceil() as i64),
+        other => Err(EvalError::new(format!(
+            "{name} requires a number, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Evaluate `limit(n; expr)` - take first n outputs.
+fn eval_limit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    n_expr: &Expr,
+    expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let n = match eval_count::<W, S>("limit", n_expr, value.clone(), scope) {
+        Ok(n) => n,
+        Err(e) => return QueryResult::Error(e),
+    };
+
+    match usize::try_from(n) {
+        Ok(n) => eval_at_most::<W, S>(expr, n, value, scope),
+        // jq 1.7 passes every output through for a negative limit
+        Err(_) => eval_single::<W, S>(expr, value, scope),
     }
 }
 
+/// Evaluate `first(expr)` - take first output.
+fn eval_first_expr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    eval_at_most::<W, S>(expr, 1, value, scope)
+}
+
 /// Evaluate `last(expr)` - take last output.
 fn eval_last_expr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(expr, value, optional);
+    let result = eval_single::<W, S>(expr, value, scope);
     match result.materialize_cursor() {
         QueryResult::One(v) => QueryResult::One(v),
         QueryResult::OneCursor(_) => unreachable!(),
@@ -7103,42 +7183,28 @@ fn eval_nth_expr<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     n_expr: &Expr,
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    // Evaluate n
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
-    let n = match result_to_owned(n_result) {
-        Ok(OwnedValue::Int(i)) if i >= 0 => i as usize,
-        Ok(_) => {
-            return QueryResult::Error(EvalError::new("nth requires non-negative integer"));
-        }
+    let n = match eval_count::<W, S>("nth", n_expr, value.clone(), scope) {
+        Ok(n) => n,
         Err(e) => return QueryResult::Error(e),
     };
-
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result.materialize_cursor() {
-        QueryResult::One(v) if n == 0 => QueryResult::One(v),
-        QueryResult::OneCursor(_) => unreachable!(),
-        QueryResult::One(_) => QueryResult::None,
-        QueryResult::Many(vs) => {
-            if let Some(item) = vs.into_iter().nth(n) {
-                QueryResult::One(item)
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::Owned(v) if n == 0 => QueryResult::Owned(v),
-        QueryResult::Owned(_) => QueryResult::None,
-        QueryResult::ManyOwned(vs) => {
-            if let Some(item) = vs.into_iter().nth(n) {
-                QueryResult::Owned(item)
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        QueryResult::Break(label) => QueryResult::Break(label),
+    let Ok(n) = usize::try_from(n) else {
+        return QueryResult::Error(EvalError::new("Out of bounds negative array index"));
+    };
+
+    // `last(limit(n + 1; expr))`, but only if there are n + 1 outputs
+    let outputs = eval_at_most::<W, S>(expr, n + 1, value, scope);
+    if outputs.output_count() <= n {
+        return match outputs {
+            result @ (QueryResult::Error(_) | QueryResult::Break(_)) => result,
+            _ => QueryResult::None,
+        };
+    }
+    match outputs {
+        QueryResult::Many(mut vs) => QueryResult::One(vs.pop().expect("n + 1 values")),
+        QueryResult::ManyOwned(mut vs) => QueryResult::Owned(vs.pop().expect("n + 1 values")),
+        single => single,
     }
 }
 
@@ -7147,14 +7213,14 @@ fn eval_until<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     cond: &Expr,
     update: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let mut current = to_owned(&value);
     const MAX_ITERATIONS: usize = 10000;
 
     for _ in 0..MAX_ITERATIONS {
         // Check condition
-        match eval_owned_expr::<S>(cond, &current, optional) {
+        match eval_owned_expr::<S>(cond, &current, scope) {
             Ok(cond_val) => {
                 if cond_val.is_truthy() {
                     return QueryResult::Owned(current);
@@ -7164,7 +7230,7 @@ fn eval_until<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
 
         // Apply update
-        match eval_owned_expr::<S>(update, &current, optional) {
+        match eval_owned_expr::<S>(update, &current, scope) {
             Ok(new_val) => current = new_val,
             Err(e) => return QueryResult::Error(e),
         }
@@ -7178,7 +7244,7 @@ fn eval_while<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     cond: &Expr,
     update: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let mut current = to_owned(&value);
     let mut outputs: Vec<OwnedValue> = Vec::new();
@@ -7186,7 +7252,7 @@ fn eval_while<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
     for _ in 0..MAX_ITERATIONS {
         // Check condition
-        match eval_owned_expr::<S>(cond, &current, optional) {
+        match eval_owned_expr::<S>(cond, &current, scope) {
             Ok(cond_val) => {
                 if !cond_val.is_truthy() {
                     break;
@@ -7199,7 +7265,7 @@ fn eval_while<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         outputs.push(current.clone());
 
         // Apply update
-        match eval_owned_expr::<S>(update, &current, optional) {
+        match eval_owned_expr::<S>(update, &current, scope) {
             Ok(new_val) => current = new_val,
             Err(e) => return QueryResult::Error(e),
         }
@@ -7221,16 +7287,16 @@ fn eval_while<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Note: This produces an infinite stream, so it should be used with `limit`.
 fn eval_repeat<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
+    max: usize,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let owned = to_owned(&value);
     let mut outputs: Vec<OwnedValue> = Vec::new();
-    const MAX_ITERATIONS: usize = 1000; // Limit to prevent infinite loops
 
-    for _ in 0..MAX_ITERATIONS {
+    for _ in 0..max {
         // Evaluate expr with the original input each time
-        match eval_owned_expr::<S>(expr, &owned, optional) {
+        match eval_owned_expr::<S>(expr, &owned, scope) {
             Ok(new_val) => outputs.push(new_val),
             Err(_) => break, // Stop on error
         }
@@ -7245,6 +7311,12 @@ fn eval_repeat<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 }
 
+/// Most values a `range()` produces outside of `limit`/`first`.
+const MAX_RANGE: usize = 100_000;
+
+/// Most values a `repeat()` produces outside of `limit`/`first`.
+const MAX_REPEAT: usize = 1000;
+
 /// A numeric argument to `range()`: kept as `i64` when exact so all-integer
 /// ranges emit `Int` values, promoted to `f64` when any argument is a float.
 #[derive(Clone, Copy)]
@@ -7282,201 +7354,217 @@ fn eval_range<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     from: &Expr,
     to: Option<&Expr>,
     step: Option<&Expr>,
+    max: usize,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let from_val = match range_arg(eval_single::<W, S>(from, value.clone(), optional)) {
-        Ok(n) => n,
-        Err(e) => return QueryResult::Error(e),
-    };
-
-    let to_val = if let Some(to_expr) = to {
-        match range_arg(eval_single::<W, S>(to_expr, value.clone(), optional)) {
-            Ok(n) => n,
-            Err(e) => return QueryResult::Error(e),
-        }
-    } else {
-        // range(n) means range(0; n)
-        return match from_val {
-            RangeNum::Int(to) => eval_range_values::<W>(0, to, 1),
-            RangeNum::Float(to) => eval_range_values_f64::<W>(0.0, to, 1.0),
-        };
-    };
-
-    let step_val = if let Some(step_expr) = step {
-        match range_arg(eval_single::<W, S>(step_expr, value, optional)) {
-            Ok(n) => n,
-            Err(e) => return QueryResult::Error(e),
-        }
-    } else {
-        RangeNum::Int(1)
-    };
-
-    match (from_val, to_val, step_val) {
-        (RangeNum::Int(from), RangeNum::Int(to), RangeNum::Int(step)) => {
-            eval_range_values::<W>(from, to, step)
+    match range_iter::<W, S>(from, to, step, value, scope) {
+        Ok(values) => {
+            let mut values: Vec<OwnedValue> = values.take(max).collect();
+            match values.len() {
+                0 => QueryResult::None,
+                1 => QueryResult::Owned(values.pop().expect("one value")),
+                _ => QueryResult::ManyOwned(values),
+            }
         }
-        (from, to, step) => eval_range_values_f64::<W>(from.as_f64(), to.as_f64(), step.as_f64()),
+        Err(e) => QueryResult::Error(e),
     }
 }
 
-/// Helper to generate range values.
-fn eval_range_values<'a, W: Clone + AsRef<[u64]>>(
-    from: i64,
-    to: i64,
-    step: i64,
-) -> QueryResult<'a, W> {
-    let mut values: Vec<OwnedValue> = Vec::new();
-    const MAX_RANGE: usize = 100000;
+/// Evaluate the arguments of `range()` into an iterator over its values.
+fn range_iter<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    from: &Expr,
+    to: Option<&Expr>,
+    step: Option<&Expr>,
+    value: StandardJson<'_, W>,
+    scope: Scope,
+) -> Result<RangeIter, EvalError> {
+    let from_val = range_arg(eval_single::<W, S>(from, value.clone(), scope))?;
 
-    if step > 0 {
-        let mut i = from;
-        while i < to && values.len() < MAX_RANGE {
-            values.push(OwnedValue::Int(i));
-            i += step;
-        }
-    } else if step < 0 {
-        let mut i = from;
-        while i > to && values.len() < MAX_RANGE {
-            values.push(OwnedValue::Int(i));
-            i += step;
-        }
-    }
+    let Some(to_expr) = to else {
+        // range(n) means range(0; n)
+        return Ok(RangeIter::new(RangeNum::Int(0), from_val, RangeNum::Int(1)));
+    };
+    let to_val = range_arg(eval_single::<W, S>(to_expr, value.clone(), scope))?;
 
-    if values.is_empty() {
-        QueryResult::None
-    } else if values.len() == 1 {
-        QueryResult::Owned(values.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(values)
-    }
+    let step_val = match step {
+        Some(step_expr) => range_arg(eval_single::<W, S>(step_expr, value, scope))?,
+        None => RangeNum::Int(1),
+    };
+
+    Ok(RangeIter::new(from_val, to_val, step_val))
 }
 
-/// Helper to generate range values over floats.
+/// The values of a `range()`, produced one at a time.
 ///
-/// Accumulates by repeated addition of `step` (jq semantics), so results carry
-/// the same floating-point drift as jq (e.g. `range(0;1;0.3)` ends at
+/// Accumulates by repeated addition of `step` (jq semantics), so float ranges
+/// carry the same drift as jq (e.g. `range(0;1;0.3)` ends at
 /// 0.8999999999999999). A zero or NaN step yields no values, matching jq.
-fn eval_range_values_f64<'a, W: Clone + AsRef<[u64]>>(
-    from: f64,
-    to: f64,
-    step: f64,
-) -> QueryResult<'a, W> {
-    let mut values: Vec<OwnedValue> = Vec::new();
-    const MAX_RANGE: usize = 100000;
+enum RangeIter {
+    Int { next: i64, to: i64, step: i64 },
+    Float { next: f64, to: f64, step: f64 },
+}
 
-    if step > 0.0 {
-        let mut i = from;
-        while i < to && values.len() < MAX_RANGE {
-            values.push(OwnedValue::Float(i));
-            i += step;
-        }
-    } else if step < 0.0 {
-        let mut i = from;
-        while i > to && values.len() < MAX_RANGE {
-            values.push(OwnedValue::Float(i));
-            i += step;
+impl RangeIter {
+    fn new(from: RangeNum, to: RangeNum, step: RangeNum) -> Self {
+        match (from, to, step) {
+            (RangeNum::Int(next), RangeNum::Int(to), RangeNum::Int(step)) => {
+                Self::Int { next, to, step }
+            }
+            (from, to, step) => Self::Float {
+                next: from.as_f64(),
+                to: to.as_f64(),
+                step: step.as_f64(),
+            },
         }
     }
-
-    if values.is_empty() {
-        QueryResult::None
-    } else if values.len() == 1 {
-        QueryResult::Owned(values.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(values)
-    }
-}
-
-/// Builtin: recurse (recurse(.[]))
-fn builtin_recurse<W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    value: StandardJson<'_, W>,
-    optional: bool,
-) -> QueryResult<'_, W> {
-    // Default recurse is equivalent to recurse(.[]?)
-    let f = Expr::Optional(Box::new(Expr::Iterate));
-    builtin_recurse_f::<W, S>(&f, value, optional)
 }
 
-/// Builtin: recurse(f)
-fn builtin_recurse_f<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    f: &Expr,
-    value: StandardJson<'a, W>,
-    _optional: bool,
-) -> QueryResult<'a, W> {
-    let mut outputs: Vec<OwnedValue> = Vec::new();
-    let mut queue: Vec<OwnedValue> = vec![to_owned(&value)];
-    const MAX_ITEMS: usize = 10000;
-
-    while !queue.is_empty() && outputs.len() < MAX_ITEMS {
-        let current = queue.remove(0);
-        outputs.push(current.clone());
+impl Iterator for RangeIter {
+    type Item = OwnedValue;
 
-        // Apply f to get children
-        match eval_owned_expr::<S>(f, &current, true) {
-            Ok(OwnedValue::Array(arr)) => {
-                queue.extend(arr);
+    fn next(&mut self) -> Option<OwnedValue> {
+        match self {
+            Self::Int { next, to, step } => {
+                let current = *next;
+                let in_range = (*step > 0 && current < *to) || (*step < 0 && current > *to);
+                if !in_range {
+                    return None;
+                }
+                // Stepping past i64 ends the range rather than overflowing
+                match current.checked_add(*step) {
+                    Some(n) => *next = n,
+                    None => *step = 0,
+                }
+                Some(OwnedValue::Int(current))
             }
-            Ok(v) if !matches!(v, OwnedValue::Null) => {
-                queue.push(v);
+            Self::Float { next, to, step } => {
+                let current = *next;
+                let in_range = (*step > 0.0 && current < *to) || (*step < 0.0 && current > *to);
+                if !in_range {
+                    return None;
+                }
+                *next += *step;
+                Some(OwnedValue::Float(current))
             }
-            _ => {}
         }
     }
-
-    if outputs.is_empty() {
-        QueryResult::None
-    } else if outputs.len() == 1 {
-        QueryResult::Owned(outputs.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(outputs)
-    }
 }
 
-/// Builtin: recurse(f; cond)
-fn builtin_recurse_cond<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    f: &Expr,
-    cond: &Expr,
+/// Builtins `recurse`, `recurse(f)` and `recurse(f; cond)`, producing at most
+/// `max` values.
+fn builtin_recurse<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    builtin: &Builtin,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
+    max: usize,
 ) -> QueryResult<'a, W> {
-    let mut outputs: Vec<OwnedValue> = Vec::new();
-    let mut queue: Vec<OwnedValue> = vec![to_owned(&value)];
-    const MAX_ITEMS: usize = 10000;
-
-    while !queue.is_empty() && outputs.len() < MAX_ITEMS {
-        let current = queue.remove(0);
-
-        // Check condition
-        let should_continue = match eval_owned_expr::<S>(cond, &current, optional) {
-            Ok(v) => v.is_truthy(),
-            Err(_) => false,
-        };
+    match recurse_iter::<W, S>(builtin, &value, scope) {
+        Some(values) => owned_outputs(values.take(max).collect()),
+        None => unreachable!("builtin_recurse called on {builtin:?}"),
+    }
+}
+
+/// The outputs of a `recurse` builtin as a lazy iterator, or `None` if
+/// `builtin` is not one. Callers that can stop early pull values from it one
+/// at a time, so an infinite recursion such as `recurse(. + 1)` only runs as
+/// far as they need.
+fn recurse_iter<'e, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    builtin: &'e Builtin,
+    value: &StandardJson<'_, W>,
+    scope: Scope,
+) -> Option<RecurseIter<'e>> {
+    if !matches!(
+        builtin,
+        Builtin::Recurse | Builtin::RecurseDown | Builtin::RecurseF(_) | Builtin::RecurseCond(..)
+    ) {
+        return None;
+    }
+    let input = to_owned(value);
+    Some(match builtin {
+        // recurse(.[]?)
+        Builtin::Recurse | Builtin::RecurseDown => RecurseIter::new(input, false, |v| match v {
+            OwnedValue::Array(items) => items.clone(),
+            OwnedValue::Object(entries) => entries.values().cloned().collect(),
+            _ => Vec::new(),
+        }),
+        Builtin::RecurseF(f) => RecurseIter::new(input, true, move |v| {
+            eval_owned_outputs::<S>(f, v, scope.optional()).unwrap_or_default()
+        }),
+        // def r: ., (f | select(cond) | r); r
+        Builtin::RecurseCond(f, cond) => RecurseIter::new(input, true, move |v| {
+            let mut children = eval_owned_outputs::<S>(f, v, scope.optional()).unwrap_or_default();
+            children.retain(|child| {
+                eval_owned_expr::<S>(cond, child, scope).is_ok_and(|c| c.is_truthy())
+            });
+            children
+        }),
+        _ => unreachable!("checked above"),
+    })
+}
 
-        if !should_continue {
-            continue;
+/// The input followed by everything reachable from it through `children`,
+/// depth first in the order jq produces them.
+///
+/// With `guarded`, for a user-supplied step that may not terminate, a child
+/// equal to a value on its own path from the input is dropped rather than
+/// followed: the step would only reproduce the same values from it again, as
+/// with `recurse(.next)` reaching `null`, whose `.next` is `null`. A step that
+/// fails or produces nothing ends its branch.
+struct RecurseIter<'e> {
+    /// Values still to emit with their depth, next one last
+    pending: Vec<(OwnedValue, usize)>,
+    /// Values from the input down to the parent of the next pending value
+    path: Vec<OwnedValue>,
+    guarded: bool,
+    children: RecurseStep<'e>,
+}
+
+/// The values a [`RecurseIter`] visits after a given value.
+type RecurseStep<'e> = Box<dyn FnMut(&OwnedValue) -> Vec<OwnedValue> + 'e>;
+
+impl<'e> RecurseIter<'e> {
+    fn new(
+        input: OwnedValue,
+        guarded: bool,
+        children: impl FnMut(&OwnedValue) -> Vec<OwnedValue> + 'e,
+    ) -> Self {
+        Self {
+            pending: vec![(input, 0)],
+            path: Vec::new(),
+            guarded,
+            children: Box::new(children),
         }
+    }
+}
 
-        outputs.push(current.clone());
+impl Iterator for RecurseIter<'_> {
+    type Item = OwnedValue;
 
-        // Apply f to get children
-        match eval_owned_expr::<S>(f, &current, true) {
-            Ok(OwnedValue::Array(arr)) => {
-                queue.extend(arr);
-            }
-            Ok(v) if !matches!(v, OwnedValue::Null) => {
-                queue.push(v);
-            }
-            _ => {}
+    fn next(&mut self) -> Option<OwnedValue> {
+        let (current, depth) = self.pending.pop()?;
+        let next = (self.children)(&current);
+        if self.guarded {
+            self.path.truncate(depth);
+            self.path.push(current.clone());
         }
+        let path = &self.path;
+        self.pending.extend(
+            next.into_iter()
+                .rev()
+                .filter(|child| !path.contains(child))
+                .map(|child| (child, depth + 1)),
+        );
+        Some(current)
     }
+}
 
-    if outputs.is_empty() {
-        QueryResult::None
-    } else if outputs.len() == 1 {
-        QueryResult::Owned(outputs.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(outputs)
+/// Zero, one or many owned outputs as a [`QueryResult`].
+fn owned_outputs<'a, W: Clone + AsRef<[u64]>>(mut outputs: Vec<OwnedValue>) -> QueryResult<'a, W> {
+    match outputs.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(outputs.pop().expect("one output")),
+        _ => QueryResult::ManyOwned(outputs),
     }
 }
 
@@ -7484,51 +7572,61 @@ fn builtin_recurse_cond<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_walk<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     f: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let owned = to_owned(&value);
-    match walk_impl::<S>(f, owned, optional) {
-        Ok(result) => QueryResult::Owned(result),
+    match walk_impl::<S>(f, owned, scope) {
+        Ok(mut results) => match results.len() {
+            0 => QueryResult::None,
+            1 => QueryResult::Owned(results.pop().expect("one result")),
+            _ => QueryResult::ManyOwned(results),
+        },
         Err(e) => QueryResult::Error(e),
     }
 }
 
 /// Implementation of walk - processes children first, then applies f.
+///
+/// Follows jq's definition: array elements are `map(walk(f))` (every output
+/// kept), object values are `map_values(walk(f))` (first output kept, the key
+/// dropped if there is none), and then every output of `f` is returned.
 fn walk_impl<S: EvalSemantics>(
     f: &Expr,
     value: OwnedValue,
-    optional: bool,
-) -> Result<OwnedValue, EvalError> {
+    scope: Scope,
+) -> Result<Vec<OwnedValue>, EvalError> {
     // First, recursively process children
     let processed = match value {
         OwnedValue::Array(arr) => {
-            let new_arr: Result<Vec<_>, _> = arr
-                .into_iter()
-                .map(|v| walk_impl::<S>(f, v, optional))
-                .collect();
-            OwnedValue::Array(new_arr?)
+            let mut new_arr = Vec::with_capacity(arr.len());
+            for v in arr {
+                new_arr.extend(walk_impl::<S>(f, v, scope)?);
+            }
+            OwnedValue::Array(new_arr)
         }
         OwnedValue::Object(obj) => {
-            let new_obj: Result<IndexMap<_, _>, _> = obj
-                .into_iter()
-                .map(|(k, v)| walk_impl::<S>(f, v, optional).map(|nv| (k, nv)))
-                .collect();
-            OwnedValue::Object(new_obj?)
+            let mut new_obj = IndexMap::with_capacity(obj.len());
+            for (k, v) in obj {
+                if let Some(nv) = walk_impl::<S>(f, v, scope)?.into_iter().next() {
+                    new_obj.insert(k, nv);
+                }
+            }
+            OwnedValue::Object(new_obj)
         }
         other => other,
     };
 
     // Then apply f to the processed value
-    eval_owned_expr::<S>(f, &processed, optional)
+    eval_owned_outputs::<S>(f, &processed, scope)
 }
 
 /// Builtin: isvalid(expr) - check if expr succeeds without errors.
 fn builtin_isvalid<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     value: StandardJson<'a, W>,
-    _optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    match eval_single::<W, S>(expr, value, true) {
+    match eval_single::<W, S>(expr, value, scope.optional()) {
         QueryResult::Error(_) => QueryResult::Owned(OwnedValue::Bool(false)),
         QueryResult::None => QueryResult::Owned(OwnedValue::Bool(false)),
         _ => QueryResult::Owned(OwnedValue::Bool(true)),
@@ -7545,10 +7643,10 @@ fn eval_pipe_with_path_context<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     exprs: &[Expr],
     value: &OwnedValue,
     current_path: &[OwnedValue],
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Call the internal version with root value
-    eval_pipe_with_path_context_internal::<W, S>(exprs, value, value, current_path, optional)
+    eval_pipe_with_path_context_internal::<W, S>(exprs, value, value, current_path, scope)
 }
 
 /// Internal helper that also tracks the root value for parent navigation.
@@ -7557,7 +7655,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
     value: &OwnedValue,
     root: &OwnedValue,
     current_path: &[OwnedValue],
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     if exprs.is_empty() {
         return QueryResult::Owned(value.clone());
@@ -7578,7 +7676,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 &v,
                 root,
                 current_path,
-                optional,
+                scope,
             );
         }
     }
@@ -7602,7 +7700,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 &v,
                 root,
                 current_path,
-                optional,
+                scope,
             );
         }
     }
@@ -7636,7 +7734,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 &v,
                 root,
                 &parent_path,
-                optional,
+                scope,
             );
         }
     }
@@ -7644,12 +7742,12 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
     // Handle ParentN - return the nth parent value
     if let Expr::Builtin(Builtin::ParentN(n_expr)) = first {
         // Evaluate n
-        let n = match eval_owned_expr::<S>(n_expr, value, optional) {
+        let n = match eval_owned_expr::<S>(n_expr, value, scope) {
             Ok(OwnedValue::Int(i)) => i as usize,
             Ok(OwnedValue::Float(f)) => f as usize,
-            Ok(_) if optional => return QueryResult::None,
+            Ok(_) if scope.optional => return QueryResult::None,
             Ok(_) => return QueryResult::Error(EvalError::type_error("number", "other")),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(e) => return QueryResult::Error(e),
         };
 
@@ -7679,7 +7777,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 &v,
                 root,
                 &parent_path,
-                optional,
+                scope,
             );
         }
     }
@@ -7688,7 +7786,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
     match first {
         Expr::Identity => {
             // Identity doesn't change the path
-            eval_pipe_with_path_context_internal::<W, S>(rest, value, root, current_path, optional)
+            eval_pipe_with_path_context_internal::<W, S>(rest, value, root, current_path, scope)
         }
         Expr::Field(name) => {
             // Extend path with field name
@@ -7702,7 +7800,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                         return QueryResult::Owned(v.clone());
                     }
                     return eval_pipe_with_path_context_internal::<W, S>(
-                        rest, v, root, &new_path, optional,
+                        rest, v, root, &new_path, scope,
                     );
                 }
                 // jq returns null for missing fields on objects (not an error)
@@ -7713,7 +7811,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 return QueryResult::Owned(OwnedValue::Null);
             }
             // Non-object/null: error (or None if optional)
-            if optional {
+            if scope.optional {
                 QueryResult::None
             } else {
                 QueryResult::Error(EvalError::type_error("object", owned_type_name(value)))
@@ -7734,11 +7832,11 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                         return QueryResult::Owned(v.clone());
                     }
                     return eval_pipe_with_path_context_internal::<W, S>(
-                        rest, v, root, &new_path, optional,
+                        rest, v, root, &new_path, scope,
                     );
                 }
             }
-            if optional {
+            if scope.optional {
                 QueryResult::None
             } else {
                 QueryResult::Error(EvalError::index_out_of_bounds(
@@ -7763,7 +7861,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                             results.push(v.clone());
                         } else {
                             match eval_pipe_with_path_context_internal::<W, S>(
-                                rest, v, root, &new_path, optional,
+                                rest, v, root, &new_path, scope,
                             ) {
                                 QueryResult::Owned(r) => results.push(r),
                                 QueryResult::ManyOwned(rs) => results.extend(rs),
@@ -7782,7 +7880,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                             results.push(v.clone());
                         } else {
                             match eval_pipe_with_path_context_internal::<W, S>(
-                                rest, v, root, &new_path, optional,
+                                rest, v, root, &new_path, scope,
                             ) {
                                 QueryResult::Owned(r) => results.push(r),
                                 QueryResult::ManyOwned(rs) => results.extend(rs),
@@ -7793,7 +7891,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                         }
                     }
                 }
-                _ if optional => return QueryResult::None,
+                _ if scope.optional => return QueryResult::None,
                 _ => {
                     return QueryResult::Error(EvalError::type_error(
                         "array or object",
@@ -7817,7 +7915,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                     value,
                     root,
                     current_path,
-                    optional,
+                    scope,
                 )
             } else {
                 let mut combined = vec![(**inner).clone()];
@@ -7827,7 +7925,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                     value,
                     root,
                     current_path,
-                    optional,
+                    scope,
                 )
             }
         }
@@ -7839,7 +7937,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                     value,
                     root,
                     current_path,
-                    true,
+                    scope.optional(),
                 )
             } else {
                 let mut combined = vec![(**inner).clone()];
@@ -7849,7 +7947,7 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                     value,
                     root,
                     current_path,
-                    true,
+                    scope.optional(),
                 )
             }
         }
@@ -7862,12 +7960,12 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                 value,
                 root,
                 current_path,
-                optional,
+                scope,
             )
         }
         Expr::Builtin(builtin) => {
             // Handle other builtins that don't need special path handling
-            match eval_builtin_owned::<S>(builtin, value, optional) {
+            match eval_builtin_owned::<S>(builtin, value, scope) {
                 Ok(result) => {
                     if rest.is_empty() {
                         QueryResult::Owned(result)
@@ -7877,18 +7975,18 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                             &result,
                             root,
                             current_path,
-                            optional,
+                            scope,
                         )
                     }
                 }
-                Err(_) if optional => QueryResult::None,
+                Err(_) if scope.optional => QueryResult::None,
                 Err(e) => QueryResult::Error(e),
             }
         }
         Expr::Object(_) | Expr::Array(_) | Expr::Literal(_) => {
             // Value-constructing expressions reset the path context
             // because we're now at the "root" of a newly constructed value
-            match eval_owned_expr::<S>(first, value, optional) {
+            match eval_owned_expr::<S>(first, value, scope) {
                 Ok(result) => {
                     if rest.is_empty() {
                         QueryResult::Owned(result)
@@ -7899,18 +7997,18 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                             &result,
                             &result,
                             &[],
-                            optional,
+                            scope,
                         )
                     }
                 }
-                Err(_) if optional => QueryResult::None,
+                Err(_) if scope.optional => QueryResult::None,
                 Err(e) => QueryResult::Error(e),
             }
         }
         _ => {
             // For other expressions, evaluate normally and continue
             // Note: This loses path context for complex expressions
-            match eval_owned_expr::<S>(first, value, optional) {
+            match eval_owned_expr::<S>(first, value, scope) {
                 Ok(result) => {
                     if rest.is_empty() {
                         QueryResult::Owned(result)
@@ -7920,11 +8018,11 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
                             &result,
                             root,
                             current_path,
-                            optional,
+                            scope,
                         )
                     }
                 }
-                Err(_) if optional => QueryResult::None,
+                Err(_) if scope.optional => QueryResult::None,
                 Err(e) => QueryResult::Error(e),
             }
         }
@@ -7935,339 +8033,389 @@ fn eval_pipe_with_path_context_internal<'a, W: Clone + AsRef<[u64]>, S: EvalSema
 fn eval_builtin_owned<S: EvalSemantics>(
     builtin: &Builtin,
     value: &OwnedValue,
-    optional: bool,
+    scope: Scope,
 ) -> Result<OwnedValue, EvalError> {
     // For most builtins, we can just delegate to eval_owned_expr
-    eval_owned_expr::<S>(&Expr::Builtin(builtin.clone()), value, optional)
+    eval_owned_expr::<S>(&Expr::Builtin(builtin.clone()), value, scope)
 }
 
-/// Builtin: path(expr) - return the path to values selected by expr
-/// This evaluates the expression while tracking the path taken to reach each value.
-fn builtin_path<'a, W: Clone + AsRef<[u64]>>(
+/// Builtin: path(expr) - return the path to each value selected by expr
+fn builtin_path<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let owned = to_owned(&value);
-    let mut paths = Vec::new();
-    eval_with_path_tracking(expr, &owned, &[], &mut paths, optional);
-
-    if paths.is_empty() {
-        if optional {
-            QueryResult::None
-        } else {
-            QueryResult::Owned(OwnedValue::Array(vec![]))
+    match eval_paths::<S>(expr, &owned, &[], scope) {
+        Ok(outputs) => {
+            let mut paths: Vec<OwnedValue> = outputs
+                .into_iter()
+                .map(|(path, _)| OwnedValue::Array(path))
+                .collect();
+            match paths.len() {
+                0 => QueryResult::None,
+                1 => QueryResult::Owned(paths.pop().unwrap()),
+                _ => QueryResult::ManyOwned(paths),
+            }
         }
-    } else if paths.len() == 1 {
-        QueryResult::Owned(paths.into_iter().next().unwrap())
-    } else {
-        // Multiple paths - return as Many
-        let owned_paths: Vec<OwnedValue> = paths;
-        // Convert to QueryResult::Many by wrapping each path
-        QueryResult::ManyOwned(owned_paths)
+        Err(e) => QueryResult::Error(e),
     }
 }
 
-/// Evaluate an expression while tracking the path taken to reach each value.
-/// Each path is collected as an OwnedValue::Array of path components.
-fn eval_with_path_tracking(
+/// A value selected by a path expression, with the path that reaches it.
+type PathValue = (Vec<OwnedValue>, OwnedValue);
+
+/// Evaluate a path expression, returning each output together with its path
+/// from the root.
+///
+/// Path expressions are the ones that select parts of their input: `.foo`,
+/// `.[n]`, `.[]`, `..`, pipes and commas of those, and filters that pass such
+/// values through (`select`, `if`, `//`, `first(f)`, `getpath`, ...).
+/// Anything else is an error if it produces a value, as in jq.
+fn eval_paths<S: EvalSemantics>(
     expr: &Expr,
     value: &OwnedValue,
-    current_path: &[OwnedValue],
-    paths: &mut Vec<OwnedValue>,
-    optional: bool,
-) {
+    path: &[OwnedValue],
+    scope: Scope,
+) -> Result<Vec<PathValue>, EvalError> {
+    let child = |component: OwnedValue, child: OwnedValue| {
+        let mut child_path = path.to_vec();
+        child_path.push(component);
+        (child_path, child)
+    };
+
     match expr {
-        Expr::Identity => {
-            // Identity returns the current path
-            paths.push(OwnedValue::Array(current_path.to_vec()));
-        }
-        Expr::Field(name) => {
+        Expr::Identity => Ok(vec![(path.to_vec(), value.clone())]),
+        Expr::Field(name) => match value {
             // jq's path() returns the path regardless of whether the field exists
-            // This matches jq behavior: path(.missing) returns ["missing"]
-            let mut new_path = current_path.to_vec();
-            new_path.push(OwnedValue::String(name.clone()));
-            paths.push(OwnedValue::Array(new_path));
-        }
-        Expr::Index(idx) => {
+            OwnedValue::Object(entries) => Ok(vec![child(
+                OwnedValue::String(name.clone()),
+                entries.get(name).cloned().unwrap_or(OwnedValue::Null),
+            )]),
+            OwnedValue::Null => Ok(vec![child(
+                OwnedValue::String(name.clone()),
+                OwnedValue::Null,
+            )]),
+            _ if scope.optional => Ok(Vec::new()),
+            other => Err(EvalError::new(format!(
+                "Cannot index {} with \"{name}\"",
+                other.type_name()
+            ))),
+        },
+        Expr::Index(idx) => match value {
             // jq's path() preserves the original index (including negative)
-            // This matches jq behavior: path(.[-1]) returns [-1]
-            let mut new_path = current_path.to_vec();
-            new_path.push(OwnedValue::Int(*idx));
-            paths.push(OwnedValue::Array(new_path));
-        }
-        Expr::Iterate => match value {
             OwnedValue::Array(arr) => {
-                for (i, _) in arr.iter().enumerate() {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::Int(i as i64));
-                    paths.push(OwnedValue::Array(new_path));
-                }
-            }
-            OwnedValue::Object(entries) => {
-                for (key, _) in entries {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::String(key.clone()));
-                    paths.push(OwnedValue::Array(new_path));
-                }
-            }
-            _ => {}
+                let actual = if *idx < 0 {
+                    arr.len() as i64 + *idx
+                } else {
+                    *idx
+                };
+                let element = usize::try_from(actual)
+                    .ok()
+                    .and_then(|i| arr.get(i))
+                    .cloned()
+                    .unwrap_or(OwnedValue::Null);
+                Ok(vec![child(OwnedValue::Int(*idx), element)])
+            }
+            OwnedValue::Null => Ok(vec![child(OwnedValue::Int(*idx), OwnedValue::Null)]),
+            _ if scope.optional => Ok(Vec::new()),
+            other => Err(EvalError::new(format!(
+                "Cannot index {} with number",
+                other.type_name()
+            ))),
         },
-        Expr::Pipe(exprs) => {
-            if exprs.is_empty() {
-                paths.push(OwnedValue::Array(current_path.to_vec()));
-                return;
-            }
-            // For pipe, we need to evaluate step by step, tracking paths
-            eval_pipe_with_path_tracking(exprs, value, current_path, paths, optional);
-        }
-        Expr::Optional(inner) => {
-            eval_with_path_tracking(inner, value, current_path, paths, true);
-        }
-        Expr::Paren(inner) => {
-            eval_with_path_tracking(inner, value, current_path, paths, optional);
-        }
-        Expr::Slice { start, end } => {
-            if let OwnedValue::Array(arr) = value {
+        Expr::Slice { start, end } => match value {
+            // A slice yields the path to each element it covers
+            OwnedValue::Array(arr) => {
                 let len = arr.len() as i64;
                 let s = start.unwrap_or(0);
                 let e = end.unwrap_or(len);
                 let actual_start = if s < 0 { (len + s).max(0) } else { s.min(len) } as usize;
                 let actual_end = if e < 0 { (len + e).max(0) } else { e.min(len) } as usize;
-                // Slicing doesn't produce a single path - it produces a new value
-                // In jq, path on a slice gives the path to each element in the slice
-                for i in actual_start..actual_end {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::Int(i as i64));
-                    paths.push(OwnedValue::Array(new_path));
-                }
-            }
-        }
-        // For complex expressions that don't represent simple paths, we can't track
-        _ => {
-            // For non-path expressions, we don't produce path output
-            // This includes arithmetic, comparisons, etc.
-        }
-    }
-}
-
-/// Evaluate a pipe expression while tracking paths
-fn eval_pipe_with_path_tracking(
-    exprs: &[Expr],
-    value: &OwnedValue,
-    current_path: &[OwnedValue],
-    paths: &mut Vec<OwnedValue>,
-    optional: bool,
-) {
-    if exprs.is_empty() {
-        paths.push(OwnedValue::Array(current_path.to_vec()));
-        return;
-    }
-
-    // For the first expression, get intermediate paths and values
-    let first = &exprs[0];
-    let rest = &exprs[1..];
-
-    if rest.is_empty() {
-        // Last expression - collect final paths
-        eval_with_path_tracking(first, value, current_path, paths, optional);
-        return;
-    }
-
-    // Get intermediate results with their paths
-    let mut intermediate: Vec<(Vec<OwnedValue>, OwnedValue)> = Vec::new();
-    collect_intermediate_with_paths(first, value, current_path, &mut intermediate, optional);
-
-    // Continue with the rest of the pipe for each intermediate result
-    for (path, val) in intermediate {
-        eval_pipe_with_path_tracking(rest, &val, &path, paths, optional);
-    }
-}
-
-/// Collect intermediate values along with their paths for continuing pipe evaluation
-#[allow(clippy::only_used_in_recursion)] // STYLE-0004: parameter used only in the recursive call; part of the recursion contract
-fn collect_intermediate_with_paths(
-    expr: &Expr,
-    value: &OwnedValue,
-    current_path: &[OwnedValue],
-    results: &mut Vec<(Vec<OwnedValue>, OwnedValue)>,
-    optional: bool,
-) {
-    match expr {
-        Expr::Identity => {
-            results.push((current_path.to_vec(), value.clone()));
+                Ok(arr
+                    .iter()
+                    .enumerate()
+                    .take(actual_end)
+                    .skip(actual_start)
+                    .map(|(i, item)| child(OwnedValue::Int(i as i64), item.clone()))
+                    .collect())
+            }
+            OwnedValue::Null => Ok(Vec::new()),
+            _ if scope.optional => Ok(Vec::new()),
+            other => Err(EvalError::new(format!(
+                "Cannot index {} with object",
+                other.type_name()
+            ))),
+        },
+        Expr::Iterate => match value {
+            OwnedValue::Array(arr) => Ok(arr
+                .iter()
+                .enumerate()
+                .map(|(i, item)| child(OwnedValue::Int(i as i64), item.clone()))
+                .collect()),
+            OwnedValue::Object(entries) => Ok(entries
+                .iter()
+                .map(|(key, item)| child(OwnedValue::String(key.clone()), item.clone()))
+                .collect()),
+            _ if scope.optional => Ok(Vec::new()),
+            other => Err(EvalError::new(format!(
+                "Cannot iterate over {}",
+                other.type_name()
+            ))),
+        },
+        Expr::RecursiveDescent | Expr::Builtin(Builtin::Recurse | Builtin::RecurseDown) => {
+            let mut out = Vec::new();
+            collect_descendant_paths(path, value, &mut out);
+            Ok(out)
         }
-        Expr::Field(name) => {
-            if let OwnedValue::Object(entries) = value {
-                for (key, val) in entries {
-                    if key == name {
-                        let mut new_path = current_path.to_vec();
-                        new_path.push(OwnedValue::String(name.clone()));
-                        results.push((new_path, val.clone()));
-                        return;
-                    }
-                }
+        Expr::Builtin(Builtin::RecurseF(f)) => {
+            let mut out = Vec::new();
+            let mut pending = vec![(path.to_vec(), value.clone())];
+            while let Some((p, v)) = pending.pop() {
+                let children = eval_paths::<S>(f, &v, &p, scope)?;
+                out.push((p, v));
+                pending.extend(children.into_iter().rev());
             }
+            Ok(out)
         }
-        Expr::Index(idx) => {
-            if let OwnedValue::Array(arr) = value {
-                let len = arr.len() as i64;
-                let actual_idx = if *idx < 0 { len + *idx } else { *idx };
-                if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                    let mut new_path = current_path.to_vec();
-                    // Preserve original index (including negative) to match jq behavior
-                    new_path.push(OwnedValue::Int(*idx));
-                    results.push((new_path, arr[actual_idx as usize].clone()));
-                }
-            }
+        Expr::Optional(inner) => {
+            Ok(eval_paths::<S>(inner, value, path, scope.optional()).unwrap_or_default())
         }
-        Expr::Iterate => match value {
-            OwnedValue::Array(arr) => {
-                for (i, val) in arr.iter().enumerate() {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::Int(i as i64));
-                    results.push((new_path, val.clone()));
-                }
-            }
-            OwnedValue::Object(entries) => {
-                for (key, val) in entries {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::String(key.clone()));
-                    results.push((new_path, val.clone()));
+        Expr::Paren(inner) => eval_paths::<S>(inner, value, path, scope),
+        Expr::Pipe(exprs) => {
+            let mut current = vec![(path.to_vec(), value.clone())];
+            for e in exprs {
+                let mut next = Vec::new();
+                for (p, v) in &current {
+                    next.extend(eval_paths::<S>(e, v, p, scope)?);
                 }
+                current = next;
             }
-            _ => {}
-        },
-        Expr::Optional(inner) => {
-            collect_intermediate_with_paths(inner, value, current_path, results, true);
+            Ok(current)
         }
-        Expr::Paren(inner) => {
-            collect_intermediate_with_paths(inner, value, current_path, results, optional);
+        Expr::Comma(exprs) => {
+            let mut out = Vec::new();
+            for e in exprs {
+                out.extend(eval_paths::<S>(e, value, path, scope)?);
+            }
+            Ok(out)
         }
-        Expr::Pipe(inner_exprs) => {
-            // Nested pipe - flatten it
-            if inner_exprs.is_empty() {
-                results.push((current_path.to_vec(), value.clone()));
-                return;
-            }
-            let mut intermediate: Vec<(Vec<OwnedValue>, OwnedValue)> = Vec::new();
-            collect_intermediate_with_paths(
-                &inner_exprs[0],
-                value,
-                current_path,
-                &mut intermediate,
-                optional,
-            );
-
-            for (path, val) in intermediate {
-                if inner_exprs.len() == 1 {
-                    results.push((path, val));
+        Expr::If {
+            cond,
+            then_branch,
+            else_branch,
+        } => {
+            let mut out = Vec::new();
+            for c in eval_owned_outputs::<S>(cond, value, scope)? {
+                let branch = if c.is_truthy() {
+                    then_branch
                 } else {
-                    // Continue with rest
-                    let rest_pipe = Expr::Pipe(inner_exprs[1..].to_vec());
-                    collect_intermediate_with_paths(&rest_pipe, &val, &path, results, optional);
+                    else_branch
+                };
+                out.extend(eval_paths::<S>(branch, value, path, scope)?);
+            }
+            Ok(out)
+        }
+        Expr::Alternative(left, right) => {
+            let truthy: Vec<PathValue> = eval_paths::<S>(left, value, path, scope)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, v)| v.is_truthy())
+                .collect();
+            if truthy.is_empty() {
+                eval_paths::<S>(right, value, path, scope)
+            } else {
+                Ok(truthy)
+            }
+        }
+        Expr::Try { expr: body, catch } => match eval_paths::<S>(body, value, path, scope) {
+            Ok(out) => Ok(out),
+            Err(e) => match catch {
+                None => Ok(Vec::new()),
+                Some(handler) => {
+                    let outputs = eval_owned_outputs::<S>(handler, &e.catch_value(), scope)?;
+                    non_path_outputs(&outputs)
+                }
+            },
+        },
+        Expr::AsPattern {
+            expr: source,
+            pattern,
+            body,
+        } => {
+            let mut out = Vec::new();
+            for bound in eval_owned_outputs::<S>(source, value, scope)? {
+                let mut substituted = (**body).clone();
+                for (name, val) in &extract_pattern_bindings(pattern, &bound)? {
+                    substituted = substitute_var(&substituted, name, val);
                 }
+                out.extend(eval_paths::<S>(&substituted, value, path, scope)?);
             }
+            Ok(out)
         }
-        Expr::Slice { start, end } => {
-            if let OwnedValue::Array(arr) = value {
-                let len = arr.len() as i64;
-                let s = start.unwrap_or(0);
-                let e = end.unwrap_or(len);
-                let actual_start = if s < 0 { (len + s).max(0) } else { s.min(len) } as usize;
-                let actual_end = if e < 0 { (len + e).max(0) } else { e.min(len) } as usize;
-                for (i, item) in arr
-                    .iter()
-                    .enumerate()
-                    .skip(actual_start)
-                    .take(actual_end - actual_start)
-                {
-                    let mut new_path = current_path.to_vec();
-                    new_path.push(OwnedValue::Int(i as i64));
-                    results.push((new_path, item.clone()));
+        Expr::FuncDef {
+            name,
+            params,
+            body,
+            then,
+        } => eval_paths::<S>(
+            &expand_func_calls(then, name, params, body),
+            value,
+            path,
+            scope,
+        ),
+        Expr::FirstExpr(inner) | Expr::Builtin(Builtin::FirstStream(inner)) => {
+            let mut out = eval_paths::<S>(inner, value, path, scope)?;
+            out.truncate(1);
+            Ok(out)
+        }
+        Expr::LastExpr(inner) | Expr::Builtin(Builtin::LastStream(inner)) => {
+            let out = eval_paths::<S>(inner, value, path, scope)?;
+            Ok(out.into_iter().last().into_iter().collect())
+        }
+        Expr::Builtin(Builtin::First) => eval_paths::<S>(&Expr::Index(0), value, path, scope),
+        Expr::Builtin(Builtin::Last) => eval_paths::<S>(&Expr::Index(-1), value, path, scope),
+        Expr::Builtin(Builtin::Empty) => Ok(Vec::new()),
+        Expr::Builtin(Builtin::Select(cond)) => Ok(eval_owned_outputs::<S>(cond, value, scope)?
+            .into_iter()
+            .filter(OwnedValue::is_truthy)
+            .map(|_| (path.to_vec(), value.clone()))
+            .collect()),
+        Expr::Builtin(Builtin::GetPath(path_expr)) => {
+            let mut out = Vec::new();
+            for p in eval_owned_outputs::<S>(path_expr, value, scope)? {
+                let OwnedValue::Array(components) = p else {
+                    return Err(EvalError::new("Path must be specified as an array"));
+                };
+                let mut current = (path.to_vec(), value.clone());
+                for component in components {
+                    let step = match component {
+                        OwnedValue::String(key) => Expr::Field(key),
+                        OwnedValue::Int(idx) => Expr::Index(idx),
+                        other => {
+                            return Err(EvalError::new(format!(
+                                "Cannot index {} with {}",
+                                current.1.type_name(),
+                                other.type_name()
+                            )))
+                        }
+                    };
+                    // getpath treats null as an empty container at any depth
+                    current = eval_paths::<S>(&step, &current.1, &current.0, scope)?
+                        .pop()
+                        .unwrap_or((current.0, OwnedValue::Null));
                 }
+                out.push(current);
             }
+            Ok(out)
         }
         _ => {
-            // For non-path expressions, we can't track paths
+            let outputs = eval_owned_outputs::<S>(expr, value, scope)?;
+            non_path_outputs(&outputs)
+        }
+    }
+}
+
+/// Outputs of an expression that is not a path expression: none is fine
+/// (e.g. `path(error)` inside `try`), any value is an error.
+fn non_path_outputs(outputs: &[OwnedValue]) -> Result<Vec<PathValue>, EvalError> {
+    match outputs.first() {
+        None => Ok(Vec::new()),
+        Some(v) => Err(EvalError::new(format!(
+            "Invalid path expression with result {}",
+            v.to_json()
+        ))),
+    }
+}
+
+/// Paths of `value` and everything below it, in pre-order (the paths `..` visits).
+fn collect_descendant_paths(path: &[OwnedValue], value: &OwnedValue, out: &mut Vec<PathValue>) {
+    out.push((path.to_vec(), value.clone()));
+    let mut child_path = path.to_vec();
+    match value {
+        OwnedValue::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                child_path.push(OwnedValue::Int(i as i64));
+                collect_descendant_paths(&child_path, item, out);
+                child_path.pop();
+            }
+        }
+        OwnedValue::Object(entries) => {
+            for (key, item) in entries {
+                child_path.push(OwnedValue::String(key.clone()));
+                collect_descendant_paths(&child_path, item, out);
+                child_path.pop();
+            }
         }
+        _ => {}
     }
 }
 
-/// Helper to collect all paths recursively
-fn collect_paths(value: &OwnedValue, current_path: &[OwnedValue], paths: &mut Vec<OwnedValue>) {
+/// Visit every path below `value` in pre-order, excluding the empty path to
+/// `value` itself, with the value found there.
+fn visit_paths<E>(
+    value: &OwnedValue,
+    path: &mut Vec<OwnedValue>,
+    visit: &mut impl FnMut(&[OwnedValue], &OwnedValue) -> Result<(), E>,
+) -> Result<(), E> {
     match value {
         OwnedValue::Object(entries) => {
             for (key, val) in entries {
-                let mut new_path = current_path.to_vec();
-                new_path.push(OwnedValue::String(key.clone()));
-                paths.push(OwnedValue::Array(new_path.clone()));
-                collect_paths(val, &new_path, paths);
+                path.push(OwnedValue::String(key.clone()));
+                visit(path, val)?;
+                visit_paths(val, path, visit)?;
+                path.pop();
             }
         }
         OwnedValue::Array(arr) => {
             for (i, val) in arr.iter().enumerate() {
-                let mut new_path = current_path.to_vec();
-                new_path.push(OwnedValue::Int(i as i64));
-                paths.push(OwnedValue::Array(new_path.clone()));
-                collect_paths(val, &new_path, paths);
+                path.push(OwnedValue::Int(i as i64));
+                visit(path, val)?;
+                visit_paths(val, path, visit)?;
+                path.pop();
             }
         }
         _ => {}
     }
+    Ok(())
 }
 
 /// Builtin: paths - all paths to values (excluding empty paths)
 /// Returns each path as a separate output (streaming), matching jq behavior
 fn builtin_paths<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     let owned = to_owned(&value);
     let mut paths = Vec::new();
-    collect_paths(&owned, &[], &mut paths);
-    // Stream individual paths instead of wrapping in array
-    if paths.is_empty() {
-        QueryResult::None
-    } else if paths.len() == 1 {
-        QueryResult::Owned(paths.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(paths)
-    }
+    let _ = visit_paths::<()>(&owned, &mut Vec::new(), &mut |path, _| {
+        paths.push(OwnedValue::Array(path.to_vec()));
+        Ok(())
+    });
+    owned_results(paths)
 }
 
 /// Builtin: paths(filter) - paths to values matching filter
-/// Returns each path as a separate output (streaming), matching jq behavior
+/// Like jq's `paths | select(...)`, a path is emitted once for each truthy
+/// output of `filter` on the value there, and errors from `filter` propagate.
 fn builtin_paths_filter<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     filter: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let owned = to_owned(&value);
-    let mut all_paths = Vec::new();
-    collect_paths(&owned, &[], &mut all_paths);
-
-    let mut filtered_paths = Vec::new();
-    for path in all_paths {
-        if let OwnedValue::Array(path_arr) = &path {
-            // Get the value at this path
-            if let Some(val_at_path) = get_value_at_path(&owned, path_arr) {
-                // Check if filter matches
-                if let Ok(OwnedValue::Bool(true)) =
-                    eval_owned_expr::<S>(filter, &val_at_path, optional)
-                {
-                    filtered_paths.push(path);
-                }
+    let mut paths = Vec::new();
+    let visited = visit_paths(&owned, &mut Vec::new(), &mut |path, val| {
+        for output in eval_owned_outputs::<S>(filter, val, scope)? {
+            if output.is_truthy() {
+                paths.push(OwnedValue::Array(path.to_vec()));
             }
         }
-    }
-    // Stream individual paths instead of wrapping in array
-    if filtered_paths.is_empty() {
-        QueryResult::None
-    } else if filtered_paths.len() == 1 {
-        QueryResult::Owned(filtered_paths.pop().unwrap())
-    } else {
-        QueryResult::ManyOwned(filtered_paths)
+        Ok(())
+    });
+    match visited {
+        Ok(()) => owned_results(paths),
+        Err(e) => QueryResult::Error(e),
     }
 }
 
@@ -8346,7 +8494,7 @@ fn collect_leaf_paths(
 /// Returns each path as a separate output (streaming), matching jq's paths(scalars) behavior
 fn builtin_leaf_paths<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     let owned = to_owned(&value);
     let mut paths = Vec::new();
@@ -8361,56 +8509,60 @@ fn builtin_leaf_paths<W: Clone + AsRef<[u64]>>(
     }
 }
 
-/// Helper to set a value at a path
-fn set_value_at_path(value: OwnedValue, path: &[OwnedValue], new_val: OwnedValue) -> OwnedValue {
-    if path.is_empty() {
-        return new_val;
-    }
-    match &path[0] {
-        OwnedValue::String(key) => match value {
-            OwnedValue::Object(mut entries) => {
-                if entries.contains_key(key) {
-                    let old = entries.shift_remove(key).unwrap_or(OwnedValue::Null);
-                    let new = set_value_at_path(old, &path[1..], new_val);
-                    entries.insert(key.clone(), new);
-                } else {
-                    let val = set_value_at_path(OwnedValue::Null, &path[1..], new_val);
-                    entries.insert(key.clone(), val);
-                }
-                OwnedValue::Object(entries)
-            }
-            _ => {
-                // Create new object
-                let val = set_value_at_path(OwnedValue::Null, &path[1..], new_val);
-                let mut entries = IndexMap::new();
-                entries.insert(key.clone(), val);
-                OwnedValue::Object(entries)
-            }
-        },
-        OwnedValue::Int(idx) => match value {
-            OwnedValue::Array(mut arr) => {
-                let index = if *idx < 0 {
-                    (arr.len() as i64 + *idx) as usize
-                } else {
-                    *idx as usize
+/// Convert a jq path array into [`PathComponent`]s for `root`.
+///
+/// Negative indices count from the end of the array they index in `root`;
+/// `Ok(None)` means one has no such element (or indexes past the tree), which
+/// `getpath` answers with null and `setpath` rejects. Floats index by their
+/// floor. As in jq, keys must meet objects and indices arrays, while `null`
+/// (or a missing value) accepts either.
+fn path_components(
+    root: &OwnedValue,
+    path: &[OwnedValue],
+) -> Result<Option<Vec<PathComponent>>, EvalError> {
+    let mut components = Vec::with_capacity(path.len());
+    let mut current = Some(root);
+    for component in path {
+        let at = current.unwrap_or(&OwnedValue::Null);
+        match (at, component) {
+            (OwnedValue::Object(_) | OwnedValue::Null, OwnedValue::String(key)) => {
+                current = current.and_then(|v| v.as_object()).and_then(|o| o.get(key));
+                components.push(PathComponent::Key(key.clone()));
+            }
+            (
+                OwnedValue::Array(_) | OwnedValue::Null,
+                OwnedValue::Int(_) | OwnedValue::Float(_),
+            ) => {
+                let index = match component {
+                    OwnedValue::Int(i) => *i,
+                    OwnedValue::Float(f) => f.floor() as i64,
+                    _ => unreachable!("matched a number"),
                 };
-                // Extend array if needed
-                while arr.len() <= index {
-                    arr.push(OwnedValue::Null);
-                }
-                let old = arr[index].clone();
-                arr[index] = set_value_at_path(old, &path[1..], new_val);
-                OwnedValue::Array(arr)
-            }
-            _ => {
-                // Create new array
-                let index = if *idx < 0 { 0 } else { *idx as usize };
-                let mut arr = vec![OwnedValue::Null; index + 1];
-                arr[index] = set_value_at_path(OwnedValue::Null, &path[1..], new_val);
-                OwnedValue::Array(arr)
+                let len = at.as_array().map_or(0, Vec::len);
+                let resolved = if index < 0 { len as i64 + index } else { index };
+                let Ok(resolved) = usize::try_from(resolved) else {
+                    return Ok(None);
+                };
+                current = at.as_array().and_then(|a| a.get(resolved));
+                components.push(PathComponent::Index(resolved));
             }
-        },
-        _ => value,
+            _ => return Err(index_error(at, component)),
+        }
+    }
+    Ok(Some(components))
+}
+
+/// jq's error for indexing `value` with a path component of the wrong type.
+fn index_error(value: &OwnedValue, component: &OwnedValue) -> EvalError {
+    match component {
+        OwnedValue::String(key) => {
+            EvalError::new(format!("Cannot index {} with \"{key}\"", value.type_name()))
+        }
+        other => EvalError::new(format!(
+            "Cannot index {} with {}",
+            value.type_name(),
+            other.type_name()
+        )),
     }
 }
 
@@ -8419,10 +8571,10 @@ fn builtin_setpath<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     path_expr: &Expr,
     val_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate path expression
-    let path_result = eval_single::<W, S>(path_expr, value.clone(), optional);
+    let path_result = eval_single::<W, S>(path_expr, value.clone(), scope);
     let path_owned = match path_result {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::Owned(v) => v,
@@ -8436,76 +8588,18 @@ fn builtin_setpath<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     };
 
     // Evaluate value expression
-    let new_val = match eval_single::<W, S>(val_expr, value.clone(), optional) {
+    let new_val = match eval_single::<W, S>(val_expr, value.clone(), scope) {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::Owned(v) => v,
         QueryResult::Error(e) => return QueryResult::Error(e),
         _ => OwnedValue::Null,
     };
 
-    let owned = to_owned(&value);
-    let result = set_value_at_path(owned, &path, new_val);
-    QueryResult::Owned(result)
-}
-
-/// Helper to delete a path from a value
-fn delete_path(value: OwnedValue, path: &[OwnedValue]) -> OwnedValue {
-    if path.is_empty() {
-        return OwnedValue::Null;
-    }
-    if path.len() == 1 {
-        match &path[0] {
-            OwnedValue::String(key) => match value {
-                OwnedValue::Object(mut entries) => {
-                    entries.shift_remove(key);
-                    return OwnedValue::Object(entries);
-                }
-                other => return other,
-            },
-            OwnedValue::Int(idx) => match value {
-                OwnedValue::Array(mut arr) => {
-                    let index = if *idx < 0 {
-                        (arr.len() as i64 + *idx) as usize
-                    } else {
-                        *idx as usize
-                    };
-                    if index < arr.len() {
-                        arr.remove(index);
-                    }
-                    return OwnedValue::Array(arr);
-                }
-                other => return other,
-            },
-            _ => return value,
-        }
-    }
-    match &path[0] {
-        OwnedValue::String(key) => match value {
-            OwnedValue::Object(mut entries) => {
-                if let Some(v) = entries.shift_remove(key) {
-                    let updated = delete_path(v, &path[1..]);
-                    entries.insert(key.clone(), updated);
-                }
-                OwnedValue::Object(entries)
-            }
-            other => other,
-        },
-        OwnedValue::Int(idx) => match value {
-            OwnedValue::Array(mut arr) => {
-                let index = if *idx < 0 {
-                    (arr.len() as i64 + *idx) as usize
-                } else {
-                    *idx as usize
-                };
-                if index < arr.len() {
-                    let old = arr[index].clone();
-                    arr[index] = delete_path(old, &path[1..]);
-                }
-                OwnedValue::Array(arr)
-            }
-            other => other,
-        },
-        _ => value,
+    let input = to_owned(&value);
+    match path_components(&input, &path) {
+        Ok(Some(components)) => QueryResult::Owned(input.set_path(&components, new_val)),
+        Ok(None) => QueryResult::Error(EvalError::new("Out of bounds negative array index")),
+        Err(e) => QueryResult::Error(e),
     }
 }
 
@@ -8513,12 +8607,12 @@ fn delete_path(value: OwnedValue, path: &[OwnedValue]) -> OwnedValue {
 fn builtin_del<'a, W: Clone + AsRef<[u64]>>(
     path_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Convert to owned and delete the path
     let mut result = to_owned(&value);
 
-    if let Err(e) = delete_at_path(&mut result, path_expr, optional) {
+    if let Err(e) = delete_at_path(&mut result, path_expr, scope) {
         return QueryResult::Error(e);
     }
 
@@ -8526,11 +8620,7 @@ fn builtin_del<'a, W: Clone + AsRef<[u64]>>(
 }
 
 /// Delete a value at a path expression.
-fn delete_at_path(
-    root: &mut OwnedValue,
-    path_expr: &Expr,
-    optional: bool,
-) -> Result<(), EvalError> {
+fn delete_at_path(root: &mut OwnedValue, path_expr: &Expr, scope: Scope) -> Result<(), EvalError> {
     match path_expr {
         Expr::Identity => {
             // del(.) replaces with null
@@ -8541,7 +8631,7 @@ fn delete_at_path(
             if let OwnedValue::Object(map) = root {
                 map.shift_remove(name);
                 Ok(())
-            } else if optional {
+            } else if scope.optional {
                 Ok(())
             } else {
                 Err(EvalError::type_error("object", owned_type_name(root)))
@@ -8554,12 +8644,12 @@ fn delete_at_path(
                 if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
                     arr.remove(actual_idx as usize);
                     Ok(())
-                } else if optional {
+                } else if scope.optional {
                     Ok(())
                 } else {
                     Err(EvalError::index_out_of_bounds(*idx, arr.len()))
                 }
-            } else if optional {
+            } else if scope.optional {
                 Ok(())
             } else {
                 Err(EvalError::type_error("array", owned_type_name(root)))
@@ -8576,7 +8666,7 @@ fn delete_at_path(
                     map.clear();
                     Ok(())
                 }
-                _ if optional => Ok(()),
+                _ if scope.optional => Ok(()),
                 _ => Err(EvalError::type_error(
                     "array or object",
                     owned_type_name(root),
@@ -8586,7 +8676,7 @@ fn delete_at_path(
         Expr::Pipe(exprs) if !exprs.is_empty() => {
             // Chain: navigate and delete at the last path
             if exprs.len() == 1 {
-                delete_at_path(root, &exprs[0], optional)
+                delete_at_path(root, &exprs[0], scope)
             } else {
                 let first = &exprs[0];
                 let rest = Expr::Pipe(exprs[1..].to_vec());
@@ -8595,13 +8685,13 @@ fn delete_at_path(
                     Expr::Field(name) => {
                         if let OwnedValue::Object(map) = root {
                             if let Some(current) = map.get_mut(name) {
-                                delete_at_path(current, &rest, optional)
-                            } else if optional {
+                                delete_at_path(current, &rest, scope)
+                            } else if scope.optional {
                                 Ok(())
                             } else {
                                 Err(EvalError::field_not_found(name))
                             }
-                        } else if optional {
+                        } else if scope.optional {
                             Ok(())
                         } else {
                             Err(EvalError::type_error("object", owned_type_name(root)))
@@ -8612,13 +8702,13 @@ fn delete_at_path(
                             let len = arr.len() as i64;
                             let actual_idx = if *idx < 0 { len + idx } else { *idx };
                             if actual_idx >= 0 && (actual_idx as usize) < arr.len() {
-                                delete_at_path(&mut arr[actual_idx as usize], &rest, optional)
-                            } else if optional {
+                                delete_at_path(&mut arr[actual_idx as usize], &rest, scope)
+                            } else if scope.optional {
                                 Ok(())
                             } else {
                                 Err(EvalError::index_out_of_bounds(*idx, arr.len()))
                             }
-                        } else if optional {
+                        } else if scope.optional {
                             Ok(())
                         } else {
                             Err(EvalError::type_error("array", owned_type_name(root)))
@@ -8627,27 +8717,27 @@ fn delete_at_path(
                     Expr::Iterate => match root {
                         OwnedValue::Array(arr) => {
                             for elem in arr.iter_mut() {
-                                delete_at_path(elem, &rest, optional)?;
+                                delete_at_path(elem, &rest, scope)?;
                             }
                             Ok(())
                         }
                         OwnedValue::Object(map) => {
                             for value in map.values_mut() {
-                                delete_at_path(value, &rest, optional)?;
+                                delete_at_path(value, &rest, scope)?;
                             }
                             Ok(())
                         }
-                        _ if optional => Ok(()),
+                        _ if scope.optional => Ok(()),
                         _ => Err(EvalError::type_error(
                             "array or object",
                             owned_type_name(root),
                         )),
                     },
-                    _ => delete_at_path(root, first, optional),
+                    _ => delete_at_path(root, first, scope),
                 }
             }
         }
-        Expr::Optional(inner) => delete_at_path(root, inner, true),
+        Expr::Optional(inner) => delete_at_path(root, inner, scope.optional()),
         _ => Err(EvalError::new("cannot use expression as delete target")),
     }
 }
@@ -8675,9 +8765,9 @@ fn builtin_now<'a, W: Clone + AsRef<[u64]>>() -> QueryResult<'a, W> {
 /// Returns [year, month(0-11), day(1-31), hour, minute, second, weekday(0-6, Sunday=0), yearday(0-365)]
 fn builtin_gmtime<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    let timestamp = match get_float_value::<W>(&value, optional) {
+    let timestamp = match get_float_value::<W>(&value, scope) {
         Ok(f) => f,
         Err(r) => return r,
     };
@@ -8740,11 +8830,11 @@ fn builtin_gmtime<W: Clone + AsRef<[u64]>>(
 /// Returns [year, month(0-11), day(1-31), hour, minute, second, weekday(0-6, Sunday=0), yearday(0-365)]
 fn builtin_localtime<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     #[cfg(feature = "std")]
     {
-        let timestamp = match get_float_value::<W>(&value, optional) {
+        let timestamp = match get_float_value::<W>(&value, scope) {
             Ok(f) => f,
             Err(r) => return r,
         };
@@ -8834,7 +8924,7 @@ fn builtin_localtime<W: Clone + AsRef<[u64]>>(
     #[cfg(not(feature = "std"))]
     {
         // In no_std, fall back to gmtime (UTC)
-        builtin_gmtime::<W>(value, optional)
+        builtin_gmtime::<W>(value, scope)
     }
 }
 
@@ -8890,17 +8980,17 @@ fn parse_simple_tz_offset(tz: &str) -> Option<i64> {
 /// Builtin: mktime - convert broken-down time to Unix timestamp
 fn builtin_mktime<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     let arr = match to_owned(&value) {
         OwnedValue::Array(a) => a,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("array", "mktime")),
     };
 
     // Need at least 6 elements: [year, month, day, hour, minute, second]
     if arr.len() < 6 {
-        if optional {
+        if scope.optional {
             return QueryResult::None;
         }
         return QueryResult::Error(EvalError::new(
@@ -8920,32 +9010,32 @@ fn builtin_mktime<W: Clone + AsRef<[u64]>>(
 
     let year = match get_int(0) {
         Ok(y) => y,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
     let month = match get_int(1) {
         Ok(m) => m + 1, // jq uses 0-indexed months
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
     let day = match get_int(2) {
         Ok(d) => d,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
     let hour = match get_int(3) {
         Ok(h) => h,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
     let minute = match get_int(4) {
         Ok(m) => m,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
     let second = match get_int(5) {
         Ok(s) => s,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(e),
     };
 
@@ -8969,12 +9059,12 @@ fn builtin_mktime<W: Clone + AsRef<[u64]>>(
 fn builtin_strftime<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     fmt_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // First get the format string
-    let fmt = match result_to_owned(eval_single::<W, S>(fmt_expr, value.clone(), optional)) {
+    let fmt = match result_to_owned(eval_single::<W, S>(fmt_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "strftime format")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -8982,12 +9072,12 @@ fn builtin_strftime<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Value should be a broken-down time array
     let arr = match to_owned(&value) {
         OwnedValue::Array(a) => a,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("array", "strftime")),
     };
 
     if arr.len() < 6 {
-        if optional {
+        if scope.optional {
             return QueryResult::None;
         }
         return QueryResult::Error(EvalError::new(
@@ -9130,12 +9220,12 @@ fn format_strftime(
 fn builtin_strptime<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     fmt_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // First get the format string
-    let fmt = match result_to_owned(eval_single::<W, S>(fmt_expr, value.clone(), optional)) {
+    let fmt = match result_to_owned(eval_single::<W, S>(fmt_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "strptime format")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -9144,10 +9234,10 @@ fn builtin_strptime<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", "strptime")),
     };
 
@@ -9165,7 +9255,7 @@ fn builtin_strptime<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             ];
             QueryResult::Owned(OwnedValue::Array(result))
         }
-        Err(_) if optional => QueryResult::None,
+        Err(_) if scope.optional => QueryResult::None,
         Err(e) => QueryResult::Error(EvalError::new(e)),
     }
 }
@@ -9490,9 +9580,9 @@ fn parse_digits(
 /// Builtin: todate - convert Unix timestamp to ISO 8601 date string
 fn builtin_todate<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    let timestamp = match get_float_value::<W>(&value, optional) {
+    let timestamp = match get_float_value::<W>(&value, scope) {
         Ok(f) => f,
         Err(r) => return r,
     };
@@ -9528,22 +9618,22 @@ fn builtin_todate<W: Clone + AsRef<[u64]>>(
 /// Builtin: fromdate - parse ISO 8601 date string to Unix timestamp
 fn builtin_fromdate<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     let input = match &value {
         StandardJson::String(s) => match s.as_str() {
             Ok(cow) => cow.into_owned(),
-            Err(_) if optional => return QueryResult::None,
+            Err(_) if scope.optional => return QueryResult::None,
             Err(_) => return QueryResult::Error(EvalError::new("invalid string")),
         },
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("string", "fromdate")),
     };
 
     // Parse ISO 8601 format: YYYY-MM-DDTHH:MM:SSZ or YYYY-MM-DDTHH:MM:SS+HH:MM
     match parse_iso8601(&input) {
         Ok(timestamp) => QueryResult::Owned(OwnedValue::Float(timestamp)),
-        Err(_) if optional => QueryResult::None,
+        Err(_) if scope.optional => QueryResult::None,
         Err(e) => QueryResult::Error(EvalError::new(e)),
     }
 }
@@ -9667,20 +9757,20 @@ fn parse_iso8601(input: &str) -> Result<f64, String> {
 /// This is semantically identical to todate/todateiso8601 but with yq naming convention
 fn builtin_from_unix<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     // from_unix is the same as todate - converts Unix timestamp to ISO 8601 string
-    builtin_todate::<W>(value, optional)
+    builtin_todate::<W>(value, scope)
 }
 
 /// Builtin: to_unix - convert ISO 8601 date string to Unix epoch
 /// This is semantically identical to fromdate/fromdateiso8601 but with yq naming convention
 fn builtin_to_unix<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     // to_unix is the same as fromdate - parses ISO 8601 string to Unix timestamp
-    builtin_fromdate::<W>(value, optional)
+    builtin_fromdate::<W>(value, scope)
 }
 
 /// Format Unix timestamp to ISO 8601 string with timezone offset
@@ -9981,18 +10071,18 @@ fn is_dst_australia(timestamp: f64) -> bool {
 fn builtin_tz<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     zone_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get the timestamp from input
-    let timestamp = match get_float_value::<W>(&value, optional) {
+    let timestamp = match get_float_value::<W>(&value, scope) {
         Ok(f) => f,
         Err(r) => return r,
     };
 
     // Evaluate the timezone expression to get the zone name
-    let zone_str = match result_to_owned(eval_single::<W, S>(zone_expr, value.clone(), optional)) {
+    let zone_str = match result_to_owned(eval_single::<W, S>(zone_expr, value.clone(), scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "tz zone argument")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -10000,7 +10090,7 @@ fn builtin_tz<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Get the timezone offset
     let offset = match get_timezone_offset(&zone_str, timestamp) {
         Ok(o) => o,
-        Err(_) if optional => return QueryResult::None,
+        Err(_) if scope.optional => return QueryResult::None,
         Err(e) => return QueryResult::Error(EvalError::new(e)),
     };
 
@@ -10017,14 +10107,14 @@ fn builtin_tz<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_load<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     file_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     use std::path::Path;
 
     // Evaluate the file expression to get the filename
-    let filename = match result_to_owned(eval_single::<W, S>(file_expr, value, optional)) {
+    let filename = match result_to_owned(eval_single::<W, S>(file_expr, value, scope)) {
         Ok(OwnedValue::String(s)) => s,
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("string", "load filename")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -10032,7 +10122,7 @@ fn builtin_load<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Read the file contents
     let file_bytes = match std::fs::read(&filename) {
         Ok(bytes) => bytes,
-        Err(_) if optional => {
+        Err(_) if scope.optional => {
             // In optional mode, return null for file errors
             return QueryResult::None;
         }
@@ -10078,7 +10168,7 @@ fn builtin_load<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
                     }
                 }
             }
-            Err(_) if optional => QueryResult::None,
+            Err(_) if scope.optional => QueryResult::None,
             Err(e) => QueryResult::Error(EvalError::new(format!(
                 "load: failed to parse YAML file '{filename}': {e}"
             ))),
@@ -10095,6 +10185,9 @@ fn yaml_value_to_owned<W: Clone + AsRef<[u64]>>(
 
     match value {
         YamlValue::Null => OwnedValue::Null,
+        YamlValue::Bool(b) => OwnedValue::Bool(b),
+        YamlValue::Int(n) => OwnedValue::Int(n),
+        YamlValue::Float(f) => OwnedValue::Float(f),
         YamlValue::String(s) => {
             // Get the string value
             let str_value = match s.as_str() {
@@ -10156,9 +10249,9 @@ fn yaml_value_to_owned<W: Clone + AsRef<[u64]>>(
 fn builtin_load<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     _file_expr: &Expr,
     _value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    if optional {
+    if scope.optional {
         QueryResult::None
     } else {
         QueryResult::Error(EvalError::new(
@@ -10167,13 +10260,44 @@ fn builtin_load<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     }
 }
 
+// Phase 24: Schema validation
+
+/// Builtin: schema_validates(schema) - whether the input matches `schema`,
+/// a JSON Schema document read by [`JsonSchema::from_value`]. Outputs one
+/// boolean per schema `schema` produces.
+fn builtin_schema_validates<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
+    schema_expr: &Expr,
+    value: StandardJson<'a, W>,
+    scope: Scope,
+) -> QueryResult<'a, W> {
+    let schemas = match result_to_owned_all(eval_single::<W, S>(schema_expr, value.clone(), scope))
+    {
+        Ok(schemas) => schemas,
+        Err(e) => return QueryResult::Error(e),
+    };
+    let input = to_owned(&value);
+    let mut results = Vec::with_capacity(schemas.len());
+    for schema in &schemas {
+        match JsonSchema::from_value(schema) {
+            Ok(schema) => results.push(OwnedValue::Bool(input.schema_validate(&schema).is_ok())),
+            Err(_) if scope.optional => {}
+            Err(e) => return QueryResult::Error(EvalError::new(format!("schema_validates: {e}"))),
+        }
+    }
+    match results.len() {
+        0 => QueryResult::None,
+        1 => QueryResult::Owned(results.pop().expect("one result")),
+        _ => QueryResult::ManyOwned(results),
+    }
+}
+
 // Phase 17: Combinations
 
 /// Builtin: combinations - generate all combinations from array of arrays
 /// Input: [[1,2], [3,4]] -> outputs [1,3], [1,4], [2,3], [2,4]
 fn builtin_combinations<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     // Input must be an array of arrays
     let arrays = match &value {
@@ -10185,7 +10309,7 @@ fn builtin_combinations<W: Clone + AsRef<[u64]>>(
                         let inner_values: Vec<OwnedValue> = inner.map(|v| to_owned(&v)).collect();
                         arrays.push(inner_values);
                     }
-                    _ if optional => return QueryResult::None,
+                    _ if scope.optional => return QueryResult::None,
                     _ => {
                         return QueryResult::Error(EvalError::type_error("array", type_name(&elem)))
                     }
@@ -10193,7 +10317,7 @@ fn builtin_combinations<W: Clone + AsRef<[u64]>>(
             }
             arrays
         }
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     };
 
@@ -10217,16 +10341,16 @@ fn builtin_combinations<W: Clone + AsRef<[u64]>>(
 fn builtin_combinations_n<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     n_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Get n
-    let n = match result_to_owned(eval_single::<W, S>(n_expr, value.clone(), optional)) {
+    let n = match result_to_owned(eval_single::<W, S>(n_expr, value.clone(), scope)) {
         Ok(OwnedValue::Int(i)) if i >= 0 => i as usize,
-        Ok(OwnedValue::Int(_)) if optional => return QueryResult::None,
+        Ok(OwnedValue::Int(_)) if scope.optional => return QueryResult::None,
         Ok(OwnedValue::Int(_)) => {
             return QueryResult::Error(EvalError::new("combinations(n): n must be non-negative"))
         }
-        Ok(_) if optional => return QueryResult::None,
+        Ok(_) if scope.optional => return QueryResult::None,
         Ok(_) => return QueryResult::Error(EvalError::type_error("number", "n")),
         Err(e) => return QueryResult::Error(e),
     };
@@ -10234,7 +10358,7 @@ fn builtin_combinations_n<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Input must be an array
     let base_array = match &value {
         StandardJson::Array(elements) => (*elements).map(|v| to_owned(&v)).collect::<Vec<_>>(),
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     };
 
@@ -10335,7 +10459,11 @@ fn builtin_builtins<'a, W: Clone + AsRef<[u64]>>() -> QueryResult<'a, W> {
         // Reduction (arity 0-1)
         "add/0",
         "any/0",
+        "any/1",
+        "any/2",
         "all/0",
+        "all/1",
+        "all/2",
         "min/0",
         "max/0",
         "min_by/1",
@@ -10381,6 +10509,7 @@ fn builtin_builtins<'a, W: Clone + AsRef<[u64]>>() -> QueryResult<'a, W> {
         // String functions (arity 0-1)
         "explode/0",
         "implode/0",
+        "ascii/0",
         "test/1",
         "indices/1",
         "index/1",
@@ -10496,213 +10625,45 @@ fn builtin_builtins<'a, W: Clone + AsRef<[u64]>>() -> QueryResult<'a, W> {
     let arr: Vec<OwnedValue> = builtins
         .iter()
         .map(|s| OwnedValue::String((*s).to_string()))
-        .collect();
-
-    QueryResult::Owned(OwnedValue::Array(arr))
-}
-
-/// Builtin: normals - select only normal numbers (not zero, infinite, NaN, or subnormal)
-fn builtin_normals<W: Clone + AsRef<[u64]>>(value: StandardJson<'_, W>) -> QueryResult<'_, W> {
-    if let StandardJson::Number(n) = &value {
-        if let Ok(f) = n.as_f64() {
-            if f.is_normal() {
-                return QueryResult::One(value);
-            }
-        }
-    }
-    QueryResult::None
-}
-
-/// Builtin: finites - select only finite numbers (not infinite or NaN)
-fn builtin_finites<W: Clone + AsRef<[u64]>>(value: StandardJson<'_, W>) -> QueryResult<'_, W> {
-    if let StandardJson::Number(n) = &value {
-        if let Ok(f) = n.as_f64() {
-            if f.is_finite() {
-                return QueryResult::One(value);
-            }
-        }
-    }
-    QueryResult::None
-}
-
-// Phase 13: Iteration control
-
-/// Builtin: limit(n; expr) - output at most n values from expr
-fn builtin_limit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    n_expr: &Expr,
-    expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate n
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
-    let n = match n_result {
-        QueryResult::One(v) => {
-            if let StandardJson::Number(num) = v {
-                num.as_i64().unwrap_or(0) as usize
-            } else {
-                return QueryResult::Error(EvalError::type_error("number", type_name(&v)));
-            }
-        }
-        QueryResult::Owned(OwnedValue::Int(i)) => i as usize,
-        QueryResult::Owned(OwnedValue::Float(f)) => f as usize,
-        QueryResult::Error(e) => return QueryResult::Error(e),
-        _ => return QueryResult::Error(EvalError::type_error("number", "null")),
-    };
-
-    if n == 0 {
-        return QueryResult::None;
-    }
-
-    // Evaluate expr and take at most n results
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result {
-        QueryResult::One(v) => QueryResult::Owned(to_owned(&v)),
-        QueryResult::OneCursor(c) => QueryResult::Owned(to_owned(&c.value())),
-        QueryResult::Owned(v) => QueryResult::Owned(v),
-        QueryResult::Many(results) => {
-            let limited: Vec<OwnedValue> =
-                results.into_iter().take(n).map(|v| to_owned(&v)).collect();
-            if limited.is_empty() {
-                QueryResult::None
-            } else if limited.len() == 1 {
-                QueryResult::Owned(limited.into_iter().next().unwrap())
-            } else {
-                QueryResult::ManyOwned(limited)
-            }
-        }
-        QueryResult::ManyOwned(results) => {
-            let limited: Vec<OwnedValue> = results.into_iter().take(n).collect();
-            if limited.is_empty() {
-                QueryResult::None
-            } else if limited.len() == 1 {
-                QueryResult::Owned(limited.into_iter().next().unwrap())
-            } else {
-                QueryResult::ManyOwned(limited)
-            }
-        }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        QueryResult::Break(label) => QueryResult::Break(label),
-    }
-}
-
-/// Builtin: first(expr) - output only the first value from expr (stream version)
-fn builtin_first_stream<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result {
-        QueryResult::One(v) => QueryResult::Owned(to_owned(&v)),
-        QueryResult::OneCursor(c) => QueryResult::Owned(to_owned(&c.value())),
-        QueryResult::Owned(v) => QueryResult::Owned(v),
-        QueryResult::Many(results) => {
-            if let Some(first) = results.into_iter().next() {
-                QueryResult::Owned(to_owned(&first))
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::ManyOwned(results) => {
-            if let Some(first) = results.into_iter().next() {
-                QueryResult::Owned(first)
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        QueryResult::Break(label) => QueryResult::Break(label),
-    }
+        .collect();
+
+    QueryResult::Owned(OwnedValue::Array(arr))
 }
 
-/// Builtin: last(expr) - output only the last value from expr (stream version)
-fn builtin_last_stream<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result {
-        QueryResult::One(v) => QueryResult::Owned(to_owned(&v)),
-        QueryResult::OneCursor(c) => QueryResult::Owned(to_owned(&c.value())),
-        QueryResult::Owned(v) => QueryResult::Owned(v),
-        QueryResult::Many(results) => {
-            if let Some(last) = results.into_iter().last() {
-                QueryResult::Owned(to_owned(&last))
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::ManyOwned(results) => {
-            if let Some(last) = results.into_iter().last() {
-                QueryResult::Owned(last)
-            } else {
-                QueryResult::None
+/// Builtin: normals - select only normal numbers (not zero, infinite, NaN, or subnormal)
+fn builtin_normals<W: Clone + AsRef<[u64]>>(value: StandardJson<'_, W>) -> QueryResult<'_, W> {
+    if let StandardJson::Number(n) = &value {
+        if let Ok(f) = number_as_f64(n) {
+            if f.is_normal() {
+                return QueryResult::One(value);
             }
         }
-        QueryResult::None => QueryResult::None,
-        QueryResult::Error(e) => QueryResult::Error(e),
-        QueryResult::Break(label) => QueryResult::Break(label),
     }
+    QueryResult::None
 }
 
-/// Builtin: nth(n; expr) - output only the nth value from expr (0-indexed, stream version)
-fn builtin_nth_stream<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
-    n_expr: &Expr,
-    expr: &Expr,
-    value: StandardJson<'a, W>,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    // Evaluate n
-    let n_result = eval_single::<W, S>(n_expr, value.clone(), optional);
-    let n = match n_result {
-        QueryResult::One(v) => {
-            if let StandardJson::Number(num) = v {
-                num.as_i64().unwrap_or(0) as usize
-            } else {
-                return QueryResult::Error(EvalError::type_error("number", type_name(&v)));
-            }
-        }
-        QueryResult::Owned(OwnedValue::Int(i)) => i as usize,
-        QueryResult::Owned(OwnedValue::Float(f)) => f as usize,
-        QueryResult::Error(e) => return QueryResult::Error(e),
-        _ => return QueryResult::Error(EvalError::type_error("number", "null")),
-    };
-
-    // Evaluate expr and get the nth result
-    let result = eval_single::<W, S>(expr, value, optional);
-    match result {
-        QueryResult::One(v) if n == 0 => QueryResult::Owned(to_owned(&v)),
-        QueryResult::OneCursor(c) if n == 0 => QueryResult::Owned(to_owned(&c.value())),
-        QueryResult::Owned(v) if n == 0 => QueryResult::Owned(v),
-        QueryResult::Many(results) => {
-            if let Some(nth) = results.into_iter().nth(n) {
-                QueryResult::Owned(to_owned(&nth))
-            } else {
-                QueryResult::None
-            }
-        }
-        QueryResult::ManyOwned(results) => {
-            if let Some(nth) = results.into_iter().nth(n) {
-                QueryResult::Owned(nth)
-            } else {
-                QueryResult::None
+/// Builtin: finites - select only finite numbers (not infinite or NaN)
+fn builtin_finites<W: Clone + AsRef<[u64]>>(value: StandardJson<'_, W>) -> QueryResult<'_, W> {
+    if let StandardJson::Number(n) = &value {
+        if let Ok(f) = number_as_f64(n) {
+            if f.is_finite() {
+                return QueryResult::One(value);
             }
         }
-        _ => QueryResult::None,
     }
+    QueryResult::None
 }
 
+// Phase 13: Iteration control
+
 /// Builtin: isempty(expr) - returns true if expr produces no outputs
 fn builtin_isempty<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let result = eval_single::<W, S>(expr, value, optional);
+    // One output is enough to decide, so infinite generators stop early
+    let result = eval_at_most::<W, S>(expr, 1, value, scope);
     let is_empty = match result {
         QueryResult::None => true,
         QueryResult::Many(ref v) if v.is_empty() => true,
@@ -10717,10 +10678,10 @@ fn builtin_isempty<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_delpaths<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     paths_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate paths expression
-    let paths_result = eval_single::<W, S>(paths_expr, value.clone(), optional);
+    let paths_result = eval_single::<W, S>(paths_expr, value.clone(), scope);
     let paths_owned = match paths_result {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::Owned(v) => v,
@@ -10733,21 +10694,20 @@ fn builtin_delpaths<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         _ => return QueryResult::Error(EvalError::new("delpaths requires array of paths")),
     };
 
-    let mut result = to_owned(&value);
-    // Sort paths by length descending to delete deeper paths first
-    let mut sorted_paths: Vec<Vec<OwnedValue>> = paths
-        .into_iter()
-        .filter_map(|p| match p {
-            OwnedValue::Array(arr) => Some(arr),
-            _ => None,
-        })
-        .collect();
-    sorted_paths.sort_by_key(|b| core::cmp::Reverse(b.len()));
-
-    for path in sorted_paths {
-        result = delete_path(result, &path);
+    let input = to_owned(&value);
+    let mut resolved = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let OwnedValue::Array(path) = path else {
+            return QueryResult::Error(EvalError::new("Path must be specified as an array"));
+        };
+        match path_components(&input, path) {
+            Ok(Some(components)) => resolved.push(components),
+            // A negative index with no element has nothing to delete
+            Ok(None) => {}
+            Err(e) => return QueryResult::Error(e),
+        }
     }
-    QueryResult::Owned(result)
+    QueryResult::Owned(input.del_paths(&resolved))
 }
 
 // Phase 10: Math Functions
@@ -10755,19 +10715,19 @@ fn builtin_delpaths<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Helper to get float value from input
 fn get_float_value<'a, W: Clone + AsRef<[u64]>>(
     value: &StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> Result<f64, QueryResult<'a, W>> {
     match value {
         StandardJson::Number(n) => {
-            if let Ok(f) = n.as_f64() {
+            if let Ok(f) = number_as_f64(n) {
                 Ok(f)
-            } else if optional {
+            } else if scope.optional {
                 Err(QueryResult::None)
             } else {
                 Err(QueryResult::Error(EvalError::new("invalid number")))
             }
         }
-        _ if optional => Err(QueryResult::None),
+        _ if scope.optional => Err(QueryResult::None),
         _ => Err(QueryResult::Error(EvalError::new(
             "math function requires number",
         ))),
@@ -10825,9 +10785,9 @@ fn sqrt_f64(x: f64) -> f64 {
 /// Builtin: floor
 fn builtin_floor<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Int(floor_f64(n) as i64)),
         Err(r) => r,
     }
@@ -10836,9 +10796,9 @@ fn builtin_floor<W: Clone + AsRef<[u64]>>(
 /// Builtin: ceil
 fn builtin_ceil<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Int(ceil_f64(n) as i64)),
         Err(r) => r,
     }
@@ -10847,9 +10807,9 @@ fn builtin_ceil<W: Clone + AsRef<[u64]>>(
 /// Builtin: round
 fn builtin_round<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Int(round_f64(n) as i64)),
         Err(r) => r,
     }
@@ -10858,9 +10818,9 @@ fn builtin_round<W: Clone + AsRef<[u64]>>(
 /// Builtin: trunc - truncate toward zero
 fn builtin_trunc<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Int(libm::trunc(n) as i64)),
         Err(r) => r,
     }
@@ -10869,9 +10829,9 @@ fn builtin_trunc<W: Clone + AsRef<[u64]>>(
 /// Builtin: sqrt
 fn builtin_sqrt<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(sqrt_f64(n))),
         Err(r) => r,
     }
@@ -10880,9 +10840,9 @@ fn builtin_sqrt<W: Clone + AsRef<[u64]>>(
 /// Builtin: fabs (absolute value)
 fn builtin_fabs<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::fabs(n))),
         Err(r) => r,
     }
@@ -10891,9 +10851,9 @@ fn builtin_fabs<W: Clone + AsRef<[u64]>>(
 /// Builtin: log (natural logarithm)
 fn builtin_log<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::log(n))),
         Err(r) => r,
     }
@@ -10902,9 +10862,9 @@ fn builtin_log<W: Clone + AsRef<[u64]>>(
 /// Builtin: log10
 fn builtin_log10<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::log10(n))),
         Err(r) => r,
     }
@@ -10913,9 +10873,9 @@ fn builtin_log10<W: Clone + AsRef<[u64]>>(
 /// Builtin: log2
 fn builtin_log2<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::log2(n))),
         Err(r) => r,
     }
@@ -10924,9 +10884,9 @@ fn builtin_log2<W: Clone + AsRef<[u64]>>(
 /// Builtin: exp (e^x)
 fn builtin_exp<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::exp(n))),
         Err(r) => r,
     }
@@ -10935,9 +10895,9 @@ fn builtin_exp<W: Clone + AsRef<[u64]>>(
 /// Builtin: exp10 (10^x)
 fn builtin_exp10<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::pow(10.0, n))),
         Err(r) => r,
     }
@@ -10946,9 +10906,9 @@ fn builtin_exp10<W: Clone + AsRef<[u64]>>(
 /// Builtin: exp2 (2^x)
 fn builtin_exp2<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::exp2(n))),
         Err(r) => r,
     }
@@ -10963,16 +10923,16 @@ enum NumberError {
 /// Helper to get number from eval result
 fn get_number_from_result<W: Clone + AsRef<[u64]>>(
     result: QueryResult<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> Result<f64, NumberError> {
     match result {
         QueryResult::Owned(OwnedValue::Int(n)) => Ok(n as f64),
         QueryResult::Owned(OwnedValue::Float(n)) => Ok(n),
-        QueryResult::One(StandardJson::Number(n)) => n
-            .as_f64()
-            .map_err(|_| NumberError::Error(EvalError::new("invalid number"))),
+        QueryResult::One(StandardJson::Number(n)) => {
+            number_as_f64(&n).map_err(|_| NumberError::Error(EvalError::new("invalid number")))
+        }
         QueryResult::Error(e) => Err(NumberError::Error(e)),
-        _ if optional => Err(NumberError::None),
+        _ if scope.optional => Err(NumberError::None),
         _ => Err(NumberError::Error(EvalError::new("expected number"))),
     }
 }
@@ -10982,19 +10942,16 @@ fn builtin_pow<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     base_expr: &Expr,
     exp_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let base = match get_number_from_result(
-        eval_single::<W, S>(base_expr, value.clone(), optional),
-        optional,
-    ) {
-        Ok(n) => n,
-        Err(NumberError::None) => return QueryResult::None,
-        Err(NumberError::Error(e)) => return QueryResult::Error(e),
-    };
+    let base =
+        match get_number_from_result(eval_single::<W, S>(base_expr, value.clone(), scope), scope) {
+            Ok(n) => n,
+            Err(NumberError::None) => return QueryResult::None,
+            Err(NumberError::Error(e)) => return QueryResult::Error(e),
+        };
 
-    let exp = match get_number_from_result(eval_single::<W, S>(exp_expr, value, optional), optional)
-    {
+    let exp = match get_number_from_result(eval_single::<W, S>(exp_expr, value, scope), scope) {
         Ok(n) => n,
         Err(NumberError::None) => return QueryResult::None,
         Err(NumberError::Error(e)) => return QueryResult::Error(e),
@@ -11008,9 +10965,9 @@ fn builtin_pow<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: sin
 fn builtin_sin<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::sin(n))),
         Err(r) => r,
     }
@@ -11019,9 +10976,9 @@ fn builtin_sin<W: Clone + AsRef<[u64]>>(
 /// Builtin: cos
 fn builtin_cos<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::cos(n))),
         Err(r) => r,
     }
@@ -11030,9 +10987,9 @@ fn builtin_cos<W: Clone + AsRef<[u64]>>(
 /// Builtin: tan
 fn builtin_tan<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::tan(n))),
         Err(r) => r,
     }
@@ -11041,9 +10998,9 @@ fn builtin_tan<W: Clone + AsRef<[u64]>>(
 /// Builtin: asin
 fn builtin_asin<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::asin(n))),
         Err(r) => r,
     }
@@ -11052,9 +11009,9 @@ fn builtin_asin<W: Clone + AsRef<[u64]>>(
 /// Builtin: acos
 fn builtin_acos<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::acos(n))),
         Err(r) => r,
     }
@@ -11063,9 +11020,9 @@ fn builtin_acos<W: Clone + AsRef<[u64]>>(
 /// Builtin: atan
 fn builtin_atan<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::atan(n))),
         Err(r) => r,
     }
@@ -11076,18 +11033,15 @@ fn builtin_atan2<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     y_expr: &Expr,
     x_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
-    let y = match get_number_from_result(
-        eval_single::<W, S>(y_expr, value.clone(), optional),
-        optional,
-    ) {
+    let y = match get_number_from_result(eval_single::<W, S>(y_expr, value.clone(), scope), scope) {
         Ok(n) => n,
         Err(NumberError::None) => return QueryResult::None,
         Err(NumberError::Error(e)) => return QueryResult::Error(e),
     };
 
-    let x = match get_number_from_result(eval_single::<W, S>(x_expr, value, optional), optional) {
+    let x = match get_number_from_result(eval_single::<W, S>(x_expr, value, scope), scope) {
         Ok(n) => n,
         Err(NumberError::None) => return QueryResult::None,
         Err(NumberError::Error(e)) => return QueryResult::Error(e),
@@ -11101,9 +11055,9 @@ fn builtin_atan2<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 /// Builtin: sinh
 fn builtin_sinh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::sinh(n))),
         Err(r) => r,
     }
@@ -11112,9 +11066,9 @@ fn builtin_sinh<W: Clone + AsRef<[u64]>>(
 /// Builtin: cosh
 fn builtin_cosh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::cosh(n))),
         Err(r) => r,
     }
@@ -11123,9 +11077,9 @@ fn builtin_cosh<W: Clone + AsRef<[u64]>>(
 /// Builtin: tanh
 fn builtin_tanh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::tanh(n))),
         Err(r) => r,
     }
@@ -11134,9 +11088,9 @@ fn builtin_tanh<W: Clone + AsRef<[u64]>>(
 /// Builtin: asinh
 fn builtin_asinh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::asinh(n))),
         Err(r) => r,
     }
@@ -11145,9 +11099,9 @@ fn builtin_asinh<W: Clone + AsRef<[u64]>>(
 /// Builtin: acosh
 fn builtin_acosh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::acosh(n))),
         Err(r) => r,
     }
@@ -11156,9 +11110,9 @@ fn builtin_acosh<W: Clone + AsRef<[u64]>>(
 /// Builtin: atanh
 fn builtin_atanh<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
+    match get_float_value::<W>(&value, scope) {
         Ok(n) => QueryResult::Owned(OwnedValue::Float(libm::atanh(n))),
         Err(r) => r,
     }
@@ -11170,62 +11124,56 @@ fn builtin_atanh<W: Clone + AsRef<[u64]>>(
 /// Builtin: isinfinite
 fn builtin_isinfinite<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::Number(n) => {
-            if let Ok(f) = n.as_f64() {
-                QueryResult::Owned(OwnedValue::Bool(f.is_infinite()))
-            } else {
-                QueryResult::Owned(OwnedValue::Bool(false))
-            }
-        }
-        _ => QueryResult::Owned(OwnedValue::Bool(false)),
-    }
+    number_predicate(&value, scope, f64::is_infinite)
 }
 
 /// Builtin: isnan
 fn builtin_isnan<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::Number(n) => {
-            if let Ok(f) = n.as_f64() {
-                QueryResult::Owned(OwnedValue::Bool(f.is_nan()))
-            } else {
-                QueryResult::Owned(OwnedValue::Bool(false))
-            }
-        }
-        _ => QueryResult::Owned(OwnedValue::Bool(false)),
-    }
+    number_predicate(&value, scope, f64::is_nan)
 }
 
 /// Builtin: isnormal
 fn builtin_isnormal<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
-    match &value {
-        StandardJson::Number(n) => {
-            if let Ok(f) = n.as_f64() {
-                QueryResult::Owned(OwnedValue::Bool(f.is_normal()))
-            } else {
-                QueryResult::Owned(OwnedValue::Bool(false))
-            }
-        }
-        _ => QueryResult::Owned(OwnedValue::Bool(false)),
-    }
+    number_predicate(&value, scope, f64::is_normal)
 }
 
-/// Builtin: isfinite
+/// Builtin: isfinite - jq defines it as `type == "number" and (isinfinite | not)`,
+/// so NaN counts as finite and non-numbers are simply `false`.
 fn builtin_isfinite<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
-    match get_float_value::<W>(&value, optional) {
-        Ok(n) => QueryResult::Owned(OwnedValue::Bool(n.is_finite())),
-        Err(_) => QueryResult::Owned(OwnedValue::Bool(false)),
+    let finite = match &value {
+        StandardJson::Number(n) => number_as_f64(n).map_or(true, |f| !f.is_infinite()),
+        _ => false,
+    };
+    QueryResult::Owned(OwnedValue::Bool(finite))
+}
+
+/// Apply a float classifier to a number input; other inputs are an error, as in jq.
+fn number_predicate<'a, W: Clone + AsRef<[u64]>>(
+    value: &StandardJson<'a, W>,
+    scope: Scope,
+    predicate: fn(f64) -> bool,
+) -> QueryResult<'a, W> {
+    match value {
+        StandardJson::Number(n) => {
+            QueryResult::Owned(OwnedValue::Bool(number_as_f64(n).is_ok_and(predicate)))
+        }
+        _ if scope.optional => QueryResult::None,
+        _ => QueryResult::Error(EvalError::new(format!(
+            "{} ({}) number required",
+            type_name(value),
+            to_owned(value).to_json()
+        ))),
     }
 }
 
@@ -11234,7 +11182,7 @@ fn builtin_isfinite<W: Clone + AsRef<[u64]>>(
 /// Builtin: debug - output value to stderr, pass through unchanged
 fn builtin_debug<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     // In a library context we don't actually print to stderr
     // We just pass through the value unchanged
@@ -11245,7 +11193,7 @@ fn builtin_debug<W: Clone + AsRef<[u64]>>(
 fn builtin_debug_msg<'a, W: Clone + AsRef<[u64]>>(
     _msg: &Expr,
     value: StandardJson<'a, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'a, W> {
     // In a library context we don't actually print to stderr
     // We just pass through the value unchanged
@@ -11255,41 +11203,38 @@ fn builtin_debug_msg<'a, W: Clone + AsRef<[u64]>>(
 // Environment functions
 
 /// $ENV expression - returns object of all environment variables
-#[cfg(feature = "std")]
-fn eval_env<'a, W: Clone + AsRef<[u64]>>(_optional: bool) -> QueryResult<'a, W> {
-    let mut env_obj = IndexMap::new();
-    for (key, value) in std::env::vars() {
-        env_obj.insert(key, OwnedValue::String(value));
-    }
-    QueryResult::Owned(OwnedValue::Object(env_obj))
-}
-
-#[cfg(not(feature = "std"))]
-fn eval_env<'a, W: Clone + AsRef<[u64]>>(_optional: bool) -> QueryResult<'a, W> {
-    // Return empty object in no_std context
-    QueryResult::Owned(OwnedValue::Object(IndexMap::new()))
+fn eval_env<'a, W: Clone + AsRef<[u64]>>(_scope: Scope) -> QueryResult<'a, W> {
+    QueryResult::Owned(OwnedValue::Object(env_object()))
 }
 
-/// Builtin: env - object of all environment variables
-#[cfg(feature = "std")]
+/// Builtin: env - object of all environment variables (same as `$ENV`)
 fn builtin_env<W: Clone + AsRef<[u64]>>(
     _value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
-    let mut env_obj = IndexMap::new();
-    for (key, value) in std::env::vars() {
-        env_obj.insert(key, OwnedValue::String(value));
-    }
-    QueryResult::Owned(OwnedValue::Object(env_obj))
+    QueryResult::Owned(OwnedValue::Object(env_object()))
+}
+
+/// The process environment, read at query time.
+///
+/// Names and values that are not valid UTF-8 are converted lossily rather
+/// than aborting the query, as `std::env::vars` would.
+#[cfg(feature = "std")]
+fn env_object() -> IndexMap<String, OwnedValue> {
+    std::env::vars_os()
+        .map(|(key, value)| {
+            (
+                key.to_string_lossy().into_owned(),
+                OwnedValue::String(value.to_string_lossy().into_owned()),
+            )
+        })
+        .collect()
 }
 
+/// Without `std` there is no environment to read: `env` and `$ENV` are `{}`.
 #[cfg(not(feature = "std"))]
-fn builtin_env<'a, W: Clone + AsRef<[u64]>>(
-    _value: StandardJson<'a, W>,
-    _optional: bool,
-) -> QueryResult<'a, W> {
-    // Return empty object in no_std context
-    QueryResult::Owned(OwnedValue::Object(IndexMap::new()))
+fn env_object() -> IndexMap<String, OwnedValue> {
+    IndexMap::new()
 }
 
 /// Builtin: env.VAR or $ENV.VAR - get environment variable (expression-based)
@@ -11297,20 +11242,20 @@ fn builtin_env<'a, W: Clone + AsRef<[u64]>>(
 fn builtin_envvar<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     var: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the expression to get the variable name
     let owned_value = to_owned(&value);
-    let var_result = eval_owned_expr::<S>(var, &owned_value, optional);
+    let var_result = eval_owned_expr::<S>(var, &owned_value, scope);
     let var_name = match var_result {
         Ok(OwnedValue::String(s)) => s,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::new("env variable name must be a string")),
     };
 
     match std::env::var(&var_name) {
         Ok(val) => QueryResult::Owned(OwnedValue::String(val)),
-        Err(_) if optional => QueryResult::None,
+        Err(_) if scope.optional => QueryResult::None,
         Err(_) => QueryResult::Owned(OwnedValue::Null),
     }
 }
@@ -11319,7 +11264,7 @@ fn builtin_envvar<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_envvar<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     _var: &Expr,
     _value: StandardJson<'a, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'a, W> {
     // Return null in no_std context
     QueryResult::Owned(OwnedValue::Null)
@@ -11327,13 +11272,10 @@ fn builtin_envvar<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
 /// Builtin: env(VAR_NAME) - get environment variable by literal name (yq syntax)
 #[cfg(feature = "std")]
-fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(
-    name: &str,
-    optional: bool,
-) -> QueryResult<'a, W> {
+fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(name: &str, scope: Scope) -> QueryResult<'a, W> {
     match std::env::var(name) {
         Ok(val) => QueryResult::Owned(OwnedValue::String(val)),
-        Err(_) if optional => QueryResult::None,
+        Err(_) if scope.optional => QueryResult::None,
         Err(_) => QueryResult::Error(EvalError::new(format!(
             "value for env variable '{name}' not provided in env()"
         ))),
@@ -11341,11 +11283,8 @@ fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(
 }
 
 #[cfg(not(feature = "std"))]
-fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(
-    name: &str,
-    optional: bool,
-) -> QueryResult<'a, W> {
-    if optional {
+fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(name: &str, scope: Scope) -> QueryResult<'a, W> {
+    if scope.optional {
         QueryResult::None
     } else {
         QueryResult::Error(EvalError::new(format!(
@@ -11358,10 +11297,10 @@ fn builtin_env_object<'a, W: Clone + AsRef<[u64]>>(
 /// Builtin: strenv(VAR_NAME) - get environment variable as string (yq syntax)
 /// This is the same as env() but explicitly for strings
 #[cfg(feature = "std")]
-fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, optional: bool) -> QueryResult<'a, W> {
+fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, scope: Scope) -> QueryResult<'a, W> {
     match std::env::var(name) {
         Ok(val) => QueryResult::Owned(OwnedValue::String(val)),
-        Err(_) if optional => QueryResult::None,
+        Err(_) if scope.optional => QueryResult::None,
         Err(_) => QueryResult::Error(EvalError::new(format!(
             "value for env variable '{name}' not provided in strenv()"
         ))),
@@ -11369,8 +11308,8 @@ fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, optional: bool) -> Qu
 }
 
 #[cfg(not(feature = "std"))]
-fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, optional: bool) -> QueryResult<'a, W> {
-    if optional {
+fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, scope: Scope) -> QueryResult<'a, W> {
+    if scope.optional {
         QueryResult::None
     } else {
         QueryResult::Error(EvalError::new(format!(
@@ -11385,7 +11324,7 @@ fn builtin_strenv<'a, W: Clone + AsRef<[u64]>>(name: &str, optional: bool) -> Qu
 /// Builtin: trim - remove leading/trailing whitespace
 fn builtin_trim<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::String(s) => {
@@ -11395,7 +11334,7 @@ fn builtin_trim<W: Clone + AsRef<[u64]>>(
                 QueryResult::Owned(OwnedValue::String(String::new()))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new("trim requires string")),
     }
 }
@@ -11403,7 +11342,7 @@ fn builtin_trim<W: Clone + AsRef<[u64]>>(
 /// Builtin: ltrim - remove leading whitespace
 fn builtin_ltrim<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::String(s) => {
@@ -11413,7 +11352,7 @@ fn builtin_ltrim<W: Clone + AsRef<[u64]>>(
                 QueryResult::Owned(OwnedValue::String(String::new()))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new("ltrim requires string")),
     }
 }
@@ -11421,7 +11360,7 @@ fn builtin_ltrim<W: Clone + AsRef<[u64]>>(
 /// Builtin: rtrim - remove trailing whitespace
 fn builtin_rtrim<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match &value {
         StandardJson::String(s) => {
@@ -11431,7 +11370,7 @@ fn builtin_rtrim<W: Clone + AsRef<[u64]>>(
                 QueryResult::Owned(OwnedValue::String(String::new()))
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new("rtrim requires string")),
     }
 }
@@ -11441,11 +11380,11 @@ fn builtin_rtrim<W: Clone + AsRef<[u64]>>(
 /// Builtin: transpose - transpose array of arrays
 fn builtin_transpose<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     let elements = match value {
         StandardJson::Array(a) => a,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::new("transpose requires array")),
     };
 
@@ -11489,50 +11428,20 @@ fn builtin_transpose<W: Clone + AsRef<[u64]>>(
     QueryResult::Owned(OwnedValue::Array(result))
 }
 
-/// Helper to compare OwnedValue for binary search
-fn compare_owned_values(a: &OwnedValue, b: &OwnedValue) -> core::cmp::Ordering {
-    use core::cmp::Ordering;
-    match (a, b) {
-        (OwnedValue::Null, OwnedValue::Null) => Ordering::Equal,
-        (OwnedValue::Bool(x), OwnedValue::Bool(y)) => x.cmp(y),
-        (OwnedValue::Int(x), OwnedValue::Int(y)) => x.cmp(y),
-        (OwnedValue::Float(x), OwnedValue::Float(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
-        (OwnedValue::Int(x), OwnedValue::Float(y)) => {
-            (*x as f64).partial_cmp(y).unwrap_or(Ordering::Equal)
-        }
-        (OwnedValue::Float(x), OwnedValue::Int(y)) => {
-            x.partial_cmp(&(*y as f64)).unwrap_or(Ordering::Equal)
-        }
-        (OwnedValue::String(x), OwnedValue::String(y)) => x.cmp(y),
-        // Different types: use a consistent ordering
-        _ => {
-            let type_order = |v: &OwnedValue| match v {
-                OwnedValue::Null => 0,
-                OwnedValue::Bool(_) => 1,
-                OwnedValue::Int(_) | OwnedValue::Float(_) => 2,
-                OwnedValue::String(_) => 3,
-                OwnedValue::Array(_) => 4,
-                OwnedValue::Object(_) => 5,
-            };
-            type_order(a).cmp(&type_order(b))
-        }
-    }
-}
-
 /// Builtin: bsearch(x) - binary search for x in sorted array
 fn builtin_bsearch<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     x_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     let elements_iter = match value.clone() {
         StandardJson::Array(a) => a,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::new("bsearch requires array")),
     };
 
     // Evaluate search value
-    let x = match eval_single::<W, S>(x_expr, value, optional) {
+    let x = match eval_single::<W, S>(x_expr, value, scope) {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::Owned(v) => v,
         QueryResult::Error(e) => return QueryResult::Error(e),
@@ -11543,7 +11452,7 @@ fn builtin_bsearch<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     let elements: Vec<OwnedValue> = elements_iter.map(|v| to_owned(&v)).collect();
 
     // Binary search
-    match elements.binary_search_by(|probe| compare_owned_values(probe, &x)) {
+    match elements.binary_search_by(|probe| compare_values(probe, &x)) {
         Ok(idx) => QueryResult::Owned(OwnedValue::Int(idx as i64)),
         Err(idx) => {
             // jq returns an object with "index" field when not found
@@ -11560,7 +11469,7 @@ fn builtin_bsearch<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_modulemeta<'a, W: Clone + AsRef<[u64]>>(
     _name: &Expr,
     _value: StandardJson<'a, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'a, W> {
     // Return null as we don't support modules
     QueryResult::Owned(OwnedValue::Null)
@@ -11570,10 +11479,10 @@ fn builtin_modulemeta<'a, W: Clone + AsRef<[u64]>>(
 fn builtin_pick<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     keys_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the keys expression to get the array of keys
-    let keys_owned = match eval_single::<W, S>(keys_expr, value.clone(), optional) {
+    let keys_owned = match eval_single::<W, S>(keys_expr, value.clone(), scope) {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::OneCursor(c) => to_owned(&c.value()),
         QueryResult::Owned(v) => v,
@@ -11583,7 +11492,7 @@ fn builtin_pick<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
         QueryResult::Error(e) => return QueryResult::Error(e),
         QueryResult::Break(label) => return QueryResult::Break(label),
-        QueryResult::None if optional => return QueryResult::None,
+        QueryResult::None if scope.optional => return QueryResult::None,
         QueryResult::None => {
             return QueryResult::Error(EvalError::new("pick: keys expression produced no output"))
         }
@@ -11596,7 +11505,7 @@ fn builtin_pick<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Keys must be an array
     let keys = match &keys_owned {
         OwnedValue::Array(arr) => arr,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::new("pick: argument must be an array of keys")),
     };
 
@@ -11645,7 +11554,7 @@ fn builtin_pick<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             }
             QueryResult::Owned(OwnedValue::Array(result))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new("pick: input must be an object or array")),
     }
 }
@@ -11655,10 +11564,10 @@ fn builtin_pick<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 fn builtin_omit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     keys_expr: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the keys expression to get the array of keys to omit
-    let keys_owned = match eval_single::<W, S>(keys_expr, value.clone(), optional) {
+    let keys_owned = match eval_single::<W, S>(keys_expr, value.clone(), scope) {
         QueryResult::One(v) => to_owned(&v),
         QueryResult::OneCursor(c) => to_owned(&c.value()),
         QueryResult::Owned(v) => v,
@@ -11668,7 +11577,7 @@ fn builtin_omit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
         }
         QueryResult::Error(e) => return QueryResult::Error(e),
         QueryResult::Break(label) => return QueryResult::Break(label),
-        QueryResult::None if optional => return QueryResult::None,
+        QueryResult::None if scope.optional => return QueryResult::None,
         QueryResult::None => {
             return QueryResult::Error(EvalError::new("omit: keys expression produced no output"))
         }
@@ -11681,7 +11590,7 @@ fn builtin_omit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     // Keys must be an array
     let keys = match &keys_owned {
         OwnedValue::Array(arr) => arr,
-        _ if optional => return QueryResult::None,
+        _ if scope.optional => return QueryResult::None,
         _ => return QueryResult::Error(EvalError::new("omit: argument must be an array of keys")),
     };
 
@@ -11751,7 +11660,7 @@ fn builtin_omit<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
 
             QueryResult::Owned(OwnedValue::Array(result))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::new("omit: input must be an object or array")),
     }
 }
@@ -11847,7 +11756,7 @@ fn builtin_document_index<'a, W: Clone + AsRef<[u64]>>() -> QueryResult<'a, W> {
 #[cfg(feature = "cli")]
 fn builtin_shuffle<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     use rand::seq::SliceRandom;
     use rand::SeedableRng;
@@ -11862,7 +11771,7 @@ fn builtin_shuffle<W: Clone + AsRef<[u64]>>(
             items.shuffle(&mut rng);
             QueryResult::Owned(OwnedValue::Array(items))
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -11871,7 +11780,7 @@ fn builtin_shuffle<W: Clone + AsRef<[u64]>>(
 #[cfg(not(feature = "cli"))]
 fn builtin_shuffle<W: Clone + AsRef<[u64]>>(
     _value: StandardJson<'_, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'_, W> {
     QueryResult::Error(EvalError::new(
         "shuffle requires the 'cli' feature to be enabled",
@@ -11890,7 +11799,7 @@ fn builtin_shuffle<W: Clone + AsRef<[u64]>>(
 /// Handles missing keys with null padding.
 fn builtin_pivot<W: Clone + AsRef<[u64]>>(
     value: StandardJson<'_, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'_, W> {
     match value {
         StandardJson::Array(elements) => {
@@ -11914,7 +11823,7 @@ fn builtin_pivot<W: Clone + AsRef<[u64]>>(
                 pivot_objects::<W>(&items)
             } else {
                 // Mixed or unsupported types
-                if optional {
+                if scope.optional {
                     QueryResult::None
                 } else {
                     QueryResult::Error(EvalError::new(
@@ -11923,7 +11832,7 @@ fn builtin_pivot<W: Clone + AsRef<[u64]>>(
                 }
             }
         }
-        _ if optional => QueryResult::None,
+        _ if scope.optional => QueryResult::None,
         _ => QueryResult::Error(EvalError::type_error("array", type_name(&value))),
     }
 }
@@ -12006,10 +11915,10 @@ fn eval_as_pattern<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     pattern: &Pattern,
     body: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
     // Evaluate the expression to get the value to destructure
-    let bound_result = eval_single::<W, S>(expr, value.clone(), optional);
+    let bound_result = eval_single::<W, S>(expr, value.clone(), scope);
 
     let bound_values: Vec<OwnedValue> = match bound_result.materialize_cursor() {
         QueryResult::One(v) => vec![to_owned(&v)],
@@ -12037,7 +11946,7 @@ fn eval_as_pattern<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
             substituted_body = substitute_var(&substituted_body, var_name, var_value);
         }
 
-        match eval_single::<W, S>(&substituted_body, value.clone(), optional).materialize_cursor() {
+        match eval_single::<W, S>(&substituted_body, value.clone(), scope).materialize_cursor() {
             QueryResult::One(v) => all_results.push(to_owned(&v)),
             QueryResult::OneCursor(_) => unreachable!(),
             QueryResult::Many(vs) => all_results.extend(vs.iter().map(to_owned)),
@@ -12098,17 +12007,32 @@ fn extract_pattern_bindings(
 ///
 /// In jq, function definitions are scoped - the function is available in `then`.
 /// We implement this by substituting function calls with the body (with args substituted).
+#[inline(never)]
 fn eval_func_def<'a, W: Clone + AsRef<[u64]>, S: EvalSemantics>(
     name: &str,
     params: &[String],
     body: &Expr,
     then: &Expr,
     value: StandardJson<'a, W>,
-    optional: bool,
+    scope: Scope,
 ) -> QueryResult<'a, W> {
+    // Recursive functions unfold one definition per call level
+    let Some(scope) = scope.enter_function::<S>() else {
+        return QueryResult::Error(EvalError::new(format!(
+            "{name}: maximum function depth ({}) exceeded",
+            S::MAX_FUNCTION_DEPTH
+        )));
+    };
+
     // Substitute all calls to this function in `then` with the body
     let expanded_then = expand_func_calls(then, name, params, body);
-    eval_single::<W, S>(&expanded_then, value, optional)
+    eval_single::<W, S>(&expanded_then, value, scope)
+}
+
+/// Whether `body` calls the function `func_name` it is the body of.
+fn calls_function(body: &Expr, func_name: &str, params: &[String]) -> bool {
+    // Inlining `.` for every call leaves a non-recursive body unchanged
+    expand_func_calls(body, func_name, params, &Expr::Identity) != *body
 }
 
 /// Expand function calls to a defined function by inlining the body.
@@ -12137,8 +12061,19 @@ fn expand_func_calls(expr: &Expr, func_name: &str, params: &[String], body: &Exp
             for (param, arg) in params.iter().zip(expanded_args.iter()) {
                 result = substitute_func_param(&result, param, arg);
             }
-            // Also expand any recursive calls in the result
-            expand_func_calls(&result, func_name, params, body)
+            // A recursive body still calls the function. Expanding those calls
+            // eagerly would never terminate, so keep the definition in scope and
+            // let evaluation unfold one level at a time.
+            if calls_function(body, func_name, params) {
+                Expr::FuncDef {
+                    name: func_name.to_string(),
+                    params: params.to_vec(),
+                    body: Box::new(body.clone()),
+                    then: Box::new(result),
+                }
+            } else {
+                result
+            }
         }
         // Recursively expand in all subexpressions
         Expr::Identity => Expr::Identity,
@@ -12223,7 +12158,10 @@ fn expand_func_calls(expr: &Expr, func_name: &str, params: &[String], body: &Exp
                 .as_ref()
                 .map(|c| Box::new(expand_func_calls(c, func_name, params, body))),
         },
-        Expr::Error(msg) => Expr::Error(msg.clone()),
+        Expr::Error(msg) => Expr::Error(
+            msg.as_ref()
+                .map(|m| Box::new(expand_func_calls(m, func_name, params, body))),
+        ),
         Expr::Builtin(b) => Expr::Builtin(expand_func_calls_in_builtin(b, func_name, params, body)),
         Expr::StringInterpolation(parts) => Expr::StringInterpolation(
             parts
@@ -12472,7 +12410,10 @@ fn substitute_func_param(expr: &Expr, param: &str, arg: &Expr) -> Expr {
                 .as_ref()
                 .map(|c| Box::new(substitute_func_param(c, param, arg))),
         },
-        Expr::Error(msg) => Expr::Error(msg.clone()),
+        Expr::Error(msg) => Expr::Error(
+            msg.as_ref()
+                .map(|m| Box::new(substitute_func_param(m, param, arg))),
+        ),
         Expr::Builtin(b) => Expr::Builtin(substitute_func_param_in_builtin(b, param, arg)),
         Expr::StringInterpolation(parts) => Expr::StringInterpolation(
             parts
@@ -12708,6 +12649,14 @@ fn expand_func_calls_in_builtin(
         Builtin::Add => Builtin::Add,
         Builtin::Any => Builtin::Any,
         Builtin::All => Builtin::All,
+        Builtin::AnyOf(g, c) => Builtin::AnyOf(
+            Box::new(expand_func_calls(g, func_name, params, body)),
+            Box::new(expand_func_calls(c, func_name, params, body)),
+        ),
+        Builtin::AllOf(g, c) => Builtin::AllOf(
+            Box::new(expand_func_calls(g, func_name, params, body)),
+            Box::new(expand_func_calls(c, func_name, params, body)),
+        ),
         Builtin::Min => Builtin::Min,
         Builtin::Max => Builtin::Max,
         Builtin::MinBy(e) => {
@@ -12770,6 +12719,9 @@ fn expand_func_calls_in_builtin(
         Builtin::FromJson => Builtin::FromJson,
         Builtin::Explode => Builtin::Explode,
         Builtin::Implode => Builtin::Implode,
+        Builtin::Ascii(e) => {
+            Builtin::Ascii(Box::new(expand_func_calls(e, func_name, params, body)))
+        }
         Builtin::Test(e) => Builtin::Test(Box::new(expand_func_calls(e, func_name, params, body))),
         Builtin::Indices(e) => {
             Builtin::Indices(Box::new(expand_func_calls(e, func_name, params, body)))
@@ -13018,6 +12970,11 @@ fn expand_func_calls_in_builtin(
             Box::new(expand_func_calls(line, func_name, params, body)),
             Box::new(expand_func_calls(col, func_name, params, body)),
         ),
+
+        // Phase 24: Schema validation (succinctly extension)
+        Builtin::SchemaValidates(e) => {
+            Builtin::SchemaValidates(Box::new(expand_func_calls(e, func_name, params, body)))
+        }
     }
 }
 
@@ -13053,6 +13010,14 @@ fn substitute_func_param_in_builtin(builtin: &Builtin, param: &str, arg: &Expr)
         Builtin::Add => Builtin::Add,
         Builtin::Any => Builtin::Any,
         Builtin::All => Builtin::All,
+        Builtin::AnyOf(g, c) => Builtin::AnyOf(
+            Box::new(substitute_func_param(g, param, arg)),
+            Box::new(substitute_func_param(c, param, arg)),
+        ),
+        Builtin::AllOf(g, c) => Builtin::AllOf(
+            Box::new(substitute_func_param(g, param, arg)),
+            Box::new(substitute_func_param(c, param, arg)),
+        ),
         Builtin::Min => Builtin::Min,
         Builtin::Max => Builtin::Max,
         Builtin::MinBy(e) => Builtin::MinBy(Box::new(substitute_func_param(e, param, arg))),
@@ -13093,6 +13058,7 @@ fn substitute_func_param_in_builtin(builtin: &Builtin, param: &str, arg: &Expr)
         Builtin::FromJson => Builtin::FromJson,
         Builtin::Explode => Builtin::Explode,
         Builtin::Implode => Builtin::Implode,
+        Builtin::Ascii(e) => Builtin::Ascii(Box::new(substitute_func_param(e, param, arg))),
         Builtin::Test(e) => Builtin::Test(Box::new(substitute_func_param(e, param, arg))),
         Builtin::Indices(e) => Builtin::Indices(Box::new(substitute_func_param(e, param, arg))),
         Builtin::Index(e) => Builtin::Index(Box::new(substitute_func_param(e, param, arg))),
@@ -13303,6 +13269,11 @@ fn substitute_func_param_in_builtin(builtin: &Builtin, param: &str, arg: &Expr)
             Box::new(substitute_func_param(line, param, arg)),
             Box::new(substitute_func_param(col, param, arg)),
         ),
+
+        // Phase 24: Schema validation (succinctly extension)
+        Builtin::SchemaValidates(e) => {
+            Builtin::SchemaValidates(Box::new(substitute_func_param(e, param, arg)))
+        }
     }
 }
 
@@ -13312,7 +13283,7 @@ fn eval_func_call<'a, W: Clone + AsRef<[u64]>>(
     name: &str,
     _args: &[Expr],
     _value: StandardJson<'a, W>,
-    _optional: bool,
+    _scope: Scope,
 ) -> QueryResult<'a, W> {
     QueryResult::Error(EvalError::new(format!("undefined function: {name}")))
 }
@@ -13522,6 +13493,11 @@ mod tests {
                 assert_eq!(values.len(), 2);
             }
         );
+
+        // Borrowed and computed outputs keep their order
+        query!(br"5", "1, ., . + 1, .",
+            QueryResult::ManyOwned(values) => assert_eq!(values, ints(&[1, 5, 6, 5]))
+        );
     }
 
     #[test]
@@ -14032,10 +14008,10 @@ mod tests {
 
     #[test]
     fn test_error_basic() {
-        // error without message
+        // error without message raises its input
         query!(br"{}", "error",
             QueryResult::Error(e) => {
-                assert_eq!(e.message, "null");
+                assert_eq!(e.message, "{}");
             }
         );
 
@@ -14050,8 +14026,14 @@ mod tests {
         query!(br"{}", "error(42)",
             QueryResult::Error(e) => {
                 assert_eq!(e.message, "42");
+                assert_eq!(e.value(), Some(&OwnedValue::Int(42)));
             }
         );
+
+        // Evaluator errors carry no raised value
+        query!(br"{}", ".[0]",
+            QueryResult::Error(e) => assert_eq!(e.value(), None)
+        );
     }
 
     #[test]
@@ -14065,15 +14047,96 @@ mod tests {
     }
 
     #[test]
-    fn test_try_catch_raised_error() {
-        // try-catch with error - error is caught
-        query!(br"{}", "try error(\"oops\") catch \"caught\"",
-            QueryResult::Owned(OwnedValue::String(s)) if s == "caught" => {}
+    fn test_try_catch_raised_error() {
+        // try-catch with error - error is caught
+        query!(br"{}", "try error(\"oops\") catch \"caught\"",
+            QueryResult::Owned(OwnedValue::String(s)) if s == "caught" => {}
+        );
+
+        // try without catch - error is suppressed
+        query!(br"{}", "try error(\"oops\")",
+            QueryResult::None => {}
+        );
+    }
+
+    #[test]
+    fn test_try_keeps_outputs_before_error() {
+        // As in jq, outputs before the error are kept and the handler runs once
+        query!(br"null", r#"[try (1, error("x"), 3) catch .]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, [OwnedValue::Int(1), OwnedValue::String("x".into())]);
+            }
+        );
+        query!(br"null", r#"[(1, error("x"), 3)?]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1]))
+        );
+        query!(br"[1, 2, 3]", r#"[try (.[] | if . == 2 then error("two") else . end) catch .]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, [OwnedValue::Int(1), OwnedValue::String("two".into())]);
+            }
+        );
+        query!(br"null", r#"[try ((1, 2) | . * 10 | (., error("e"))) catch .]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, [OwnedValue::Int(10), OwnedValue::String("e".into())]);
+            }
+        );
+        query!(br"[1, 2]", r#"[.[] | (., error("x"))?]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 2]))
+        );
+    }
+
+    #[test]
+    fn test_try_catch_handler_input() {
+        // The handler receives the error message
+        query!(br"null", r#"try error("oops") catch ."#,
+            QueryResult::Owned(OwnedValue::String(s)) if s == "oops" => {}
+        );
+        query!(br"123", r#"try .foo catch ("caught: " + .)"#,
+            QueryResult::Owned(OwnedValue::String(s)) => {
+                assert_eq!(s, "caught: expected object, got number");
+            }
+        );
+
+        // Non-string error values reach the handler unchanged
+        query!(br"null", r"try error({a: 1}) catch .a",
+            QueryResult::Owned(OwnedValue::Int(1)) => {}
+        );
+        query!(br"null", r"try error(null) catch .",
+            QueryResult::Owned(OwnedValue::Null) => {}
+        );
+        query!(br"[1, 2]", r"try error catch length",
+            QueryResult::Owned(OwnedValue::Int(2)) => {}
+        );
+        query!(br"null", r"1 as $x | try error($x) catch . + 1",
+            QueryResult::Owned(OwnedValue::Int(2)) => {}
+        );
+
+        // Errors raised by the handler propagate
+        query!(br"null", r#"try error("x") catch error("y")"#,
+            QueryResult::Error(e) => assert_eq!(e.message, "y")
+        );
+        query!(br"null", r#"try (try error("x") catch error(. + "y")) catch ."#,
+            QueryResult::Owned(OwnedValue::String(s)) if s == "xy" => {}
+        );
+    }
+
+    #[test]
+    fn test_try_suppresses_errors() {
+        query!(br"null", "[try (1 / 0)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert!(arr.is_empty())
+        );
+        query!(br"[1, 0, 4]", "[.[] | try (if . == 0 then error(\"zero\") else . end)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 4]))
         );
 
-        // try without catch - error is suppressed
-        query!(br"{}", "try error(\"oops\")",
-            QueryResult::None => {}
+        // `term?` is shorthand for `try term`
+        query!(br#"[1, "a", 3]"#, "[.[] | (1 / .)?]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Float(1.0), OwnedValue::Float(1.0 / 3.0)]);
+            }
+        );
+        query!(br"null", r#"[error("x")?, 1]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1]))
         );
     }
 
@@ -14127,6 +14190,12 @@ mod tests {
         query!(br#"{"a": 1}"#, "type",
             QueryResult::Owned(OwnedValue::String(s)) if s == "object" => {}
         );
+        query!(br#"[false, -1.5, 1e3, "", {}, []]"#, "map(type)",
+            QueryResult::Owned(v) => assert_eq!(
+                v.to_json(),
+                r#"["boolean","number","number","string","object","array"]"#
+            )
+        );
     }
 
     #[test]
@@ -14211,6 +14280,22 @@ mod tests {
         query!(br"-5", "length",
             QueryResult::Owned(OwnedValue::Int(5)) => {}
         );
+        query!(br"-1.5", "length",
+            QueryResult::Owned(OwnedValue::Float(f)) => assert_eq!(f, 1.5)
+        );
+
+        // Nested containers count only their direct children
+        query!(br#"[[1, [2, 3]], {"a": {"b": 1}}, []]"#, "[.[] | length]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[2, 1, 0]))
+        );
+
+        // booleans have no length, as in jq
+        query!(br"true", "length",
+            QueryResult::Error(e) => assert_eq!(e.message, "boolean (true) has no length")
+        );
+        query!(br"false", "length?",
+            QueryResult::None => {}
+        );
     }
 
     #[test]
@@ -14253,8 +14338,27 @@ mod tests {
         // keys_unsorted preserves original order
         query!(br#"{"b": 2, "a": 1}"#, "keys_unsorted",
             QueryResult::Owned(OwnedValue::Array(arr)) => {
-                assert_eq!(arr.len(), 2);
-                // Note: Order depends on how JSON was parsed
+                assert_eq!(arr, vec![OwnedValue::String("b".into()), OwnedValue::String("a".into())]);
+            }
+        );
+
+        // Scalars have no keys
+        query!(br"1", "keys",
+            QueryResult::Error(_) => {}
+        );
+    }
+
+    #[test]
+    fn test_builtin_values() {
+        // values is select(. != null): it filters, it does not collect
+        query!(br#"{"a": 1, "b": null, "c": false}"#, "[.[] | values]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(1), OwnedValue::Bool(false)]);
+            }
+        );
+        query!(br"null", "[values]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert!(arr.is_empty());
             }
         );
     }
@@ -14278,14 +14382,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_has_edge_cases() {
+        // null has nothing
+        query!(br"null", "has(\"a\")",
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+        query!(br"null", "has(0)",
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+
+        // has(3) on a 3-element array is past the end; negative indices are
+        // never present in jq
+        query!(br"[1, 2, 3]", "has(2)",
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(br"[1, 2, 3]", "has(3)",
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+        query!(br"[1, 2, 3]", "has(-1)",
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+
+        // The key type must match the container: a string is never an array index
+        query!(br#"["a", "b"]"#, "has(\"a\")",
+            QueryResult::Error(e) => {
+                assert!(e.message.contains("has()"));
+            }
+        );
+        query!(br#"{"0": 1}"#, "has(0)",
+            QueryResult::Error(_) => {}
+        );
+        query!(br#""abc""#, "has(\"a\")",
+            QueryResult::Error(_) => {}
+        );
+    }
+
     #[test]
     fn test_builtin_in() {
-        // in() checks if a key/index exists
-        // Note: in() with piped owned values requires fixing eval_pipe
-        // For now, test has() which works similarly
-        query!(br#"{"a": 1, "b": 2}"#, "has(\"a\")",
+        // in(obj) is has() with the key as input
+        query!(br#""a""#, r#"in({"a": 1, "b": 2})"#,
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(br#""c""#, r#"in({"a": 1, "b": 2})"#,
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+        query!(br"1", "in([10, 20])",
             QueryResult::Owned(OwnedValue::Bool(true)) => {}
         );
+        query!(br"2", "in([10, 20])",
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+
+        // Piped keys against the input document
+        query!(br#"{"keys": ["a", "z"], "obj": {"a": 1}}"#, "[.obj as $o | .keys[] | in($o)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Bool(true), OwnedValue::Bool(false)]);
+            }
+        );
+
+        // Mismatched key and container types are errors, as with has()
+        query!(br"0", r#"in({"0": 1})"#,
+            QueryResult::Error(_) => {}
+        );
     }
 
     #[test]
@@ -14301,6 +14460,46 @@ mod tests {
         query!(br"2", "select(. > 3)",
             QueryResult::None => {}
         );
+        query!(br"2", "select(false)",
+            QueryResult::None => {}
+        );
+        query!(br"2", "select(null)",
+            QueryResult::None => {}
+        );
+    }
+
+    #[test]
+    fn test_builtin_select_generator() {
+        // The input is emitted once per truthy output of the condition
+        query!(br"2", "[select(true, false, 1)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(2), OwnedValue::Int(2)]);
+            }
+        );
+        query!(br"2", "[select(empty)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert!(arr.is_empty());
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_map_select() {
+        query!(br"[1, 2, 3, 4]", "map(select(. > 2))",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(3), OwnedValue::Int(4)]);
+            }
+        );
+        query!(br"[1, 2, 3, 4]", "map_select(. > 2)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(3), OwnedValue::Int(4)]);
+            }
+        );
+        query!(br"[1, 2, 3, 4]", "map_select(. > 10)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert!(arr.is_empty());
+            }
+        );
     }
 
     #[test]
@@ -14328,6 +14527,16 @@ mod tests {
                 assert_eq!(arr[0], OwnedValue::Int(2));
             }
         );
+
+        // map(f) is [.[] | f], so f may produce several outputs per element
+        query!(br"[1, 2]", "map(., . * 10)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![OwnedValue::Int(1), OwnedValue::Int(10), OwnedValue::Int(2), OwnedValue::Int(20)]
+                );
+            }
+        );
     }
 
     #[test]
@@ -14573,10 +14782,10 @@ mod tests {
             }
         );
 
-        // No match - returns original
+        // No match - returns the input itself
         query!(br#""hello world""#, r#"ltrimstr("goodbye")"#,
-            QueryResult::Owned(OwnedValue::String(s)) => {
-                assert_eq!(s, "hello world");
+            QueryResult::One(StandardJson::String(s)) => {
+                assert_eq!(s.as_str().unwrap().as_ref(), "hello world");
             }
         );
     }
@@ -14589,10 +14798,10 @@ mod tests {
             }
         );
 
-        // No match - returns original
+        // No match - returns the input itself
         query!(br#""hello world""#, r#"rtrimstr("goodbye")"#,
-            QueryResult::Owned(OwnedValue::String(s)) => {
-                assert_eq!(s, "hello world");
+            QueryResult::One(StandardJson::String(s)) => {
+                assert_eq!(s.as_str().unwrap().as_ref(), "hello world");
             }
         );
     }
@@ -14627,6 +14836,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_ascii_case_unchanged() {
+        // Nothing to convert: the input string is returned without copying
+        query!(br#""hello 123""#, "ascii_downcase",
+            QueryResult::One(StandardJson::String(s)) => {
+                assert_eq!(s.as_str().unwrap().as_ref(), "hello 123");
+            }
+        );
+        query!("\"caf\u{e9}\"".as_bytes(), "ascii_upcase",
+            QueryResult::Owned(OwnedValue::String(s)) => {
+                assert_eq!(s, "CAF\u{e9}");
+            }
+        );
+        query!(b"1", "ascii_upcase", QueryResult::Error(_) => {});
+    }
+
+    #[test]
+    fn test_builtin_trimstr_non_strings() {
+        // jq: 1 | ltrimstr("x") => 1
+        query!(b"1", r#"ltrimstr("x")"#,
+            QueryResult::One(StandardJson::Number(_)) => {}
+        );
+        // jq: "abc" | rtrimstr(1) => "abc"
+        query!(br#""abc""#, "rtrimstr(1)",
+            QueryResult::One(StandardJson::String(s)) => {
+                assert_eq!(s.as_str().unwrap().as_ref(), "abc");
+            }
+        );
+        // Trimming the whole string leaves an empty string
+        query!(br#""abc""#, r#"ltrimstr("abc")"#,
+            QueryResult::Owned(OwnedValue::String(s)) => {
+                assert!(s.is_empty());
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_trimstr_generator_args() {
+        query!(br#""foobar""#, r#"[ltrimstr("f", "foo", "x")]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![
+                        OwnedValue::String("oobar".into()),
+                        OwnedValue::String("bar".into()),
+                        OwnedValue::String("foobar".into()),
+                    ]
+                );
+            }
+        );
+        query!(br#""foobar""#, r#"[rtrimstr("r", "bar")]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![OwnedValue::String("fooba".into()), OwnedValue::String("foo".into())]
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_affix_test_errors_and_generators() {
+        query!(br#""foobar""#, r#"[startswith("foo", "bar"), endswith("foo", "bar")]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![
+                        OwnedValue::Bool(true),
+                        OwnedValue::Bool(false),
+                        OwnedValue::Bool(false),
+                        OwnedValue::Bool(true),
+                    ]
+                );
+            }
+        );
+        // Empty affix always matches
+        query!(br#""abc""#, r#"startswith("")"#,
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(b"1", r#"startswith("x")"#, QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), "startswith() requires string inputs");
+        });
+        query!(br#""abc""#, "endswith(1)", QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), "endswith() requires string inputs");
+        });
+        query!(b"1", r#"[startswith("x")?]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert!(arr.is_empty());
+            }
+        );
+    }
+
     #[test]
     fn test_builtin_split() {
         query!(br#""a,b,c""#, r#"split(",")"#,
@@ -14647,10 +14948,10 @@ mod tests {
             }
         );
 
-        // Join with null values (should be skipped)
+        // Nulls join as empty strings, as in jq
         query!(br#"["a", null, "c"]"#, r#"join("-")"#,
             QueryResult::Owned(OwnedValue::String(s)) => {
-                assert_eq!(s, "a-c");
+                assert_eq!(s, "a--c");
             }
         );
     }
@@ -14813,6 +15114,73 @@ mod tests {
         );
     }
 
+    /// Records with duplicate keys `k`, tagged with their input position `i`.
+    const BY_INPUT: &[u8] =
+        br#"[{"k": 2, "i": 0}, {"k": 1, "i": 1}, {"k": 2, "i": 2}, {"k": 1, "i": 3}, {"k": null, "i": 4}]"#;
+
+    fn ints(values: &[i64]) -> Vec<OwnedValue> {
+        values.iter().map(|&i| OwnedValue::Int(i)).collect()
+    }
+
+    #[test]
+    fn test_builtin_by_functions_are_stable() {
+        // Equal keys keep their input order
+        query!(BY_INPUT, "sort_by(.k) | map(.i)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[4, 1, 3, 0, 2]));
+            }
+        );
+        query!(BY_INPUT, "group_by(.k) | map(map(.i))",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![
+                        OwnedValue::Array(ints(&[4])),
+                        OwnedValue::Array(ints(&[1, 3])),
+                        OwnedValue::Array(ints(&[0, 2])),
+                    ]
+                );
+            }
+        );
+        // unique_by keeps the first element of each group
+        query!(BY_INPUT, "unique_by(.k) | map(.i)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[4, 1, 0]));
+            }
+        );
+        // min_by keeps the first of equal minima, max_by the last of equal maxima
+        query!(BY_INPUT, "[.[] | select(.k != null)] | min_by(.k).i",
+            QueryResult::Owned(OwnedValue::Int(1)) => {}
+        );
+        query!(BY_INPUT, "max_by(.k).i",
+            QueryResult::Owned(OwnedValue::Int(2)) => {}
+        );
+    }
+
+    #[test]
+    fn test_builtin_by_functions_multiple_keys() {
+        // The key is every output of f, like jq's map([f])
+        query!(BY_INPUT, "sort_by(.k, -.i) | map(.i)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[4, 3, 1, 2, 0]));
+            }
+        );
+        // No output sorts before null
+        query!(br#"[{"a": null}, {}, {"a": 0}]"#, "sort_by(.a // empty) | map(.a)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Null, OwnedValue::Null, OwnedValue::Int(0)]);
+            }
+        );
+        query!(br#"[{"a": null}, {}, {"a": 0}]"#, "sort_by(.a // empty) | map(has(\"a\"))",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Bool(true), OwnedValue::Bool(false), OwnedValue::Bool(true)]);
+            }
+        );
+        query!(br"[]", "min_by(.x)",
+            QueryResult::Owned(OwnedValue::Null) => {}
+        );
+    }
+
     // ==========================================================================
     // Phase 5: Object Functions Tests
     // ==========================================================================
@@ -14848,6 +15216,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_to_entries_array() {
+        // Arrays use their indices as keys
+        query!(br#"["x", "y"]"#, "to_entries",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr.len(), 2);
+                let OwnedValue::Object(obj) = &arr[1] else { panic!("expected object") };
+                assert_eq!(obj.get("key"), Some(&OwnedValue::Int(1)));
+                assert_eq!(obj.get("value"), Some(&OwnedValue::String("y".into())));
+            }
+        );
+        query!(br"1", "to_entries",
+            QueryResult::Error(_) => {}
+        );
+    }
+
+    #[test]
+    fn test_builtin_from_entries_spellings() {
+        // jq accepts key/k/name/Name/K/Key and value/v/Value
+        query!(
+            br#"[{"k": "a", "v": 1}, {"Name": "b", "Value": 2}, {"K": "c", "value": 3}, {"Key": "d", "v": 4}]"#,
+            "from_entries",
+            QueryResult::Owned(OwnedValue::Object(obj)) => {
+                assert_eq!(obj.get("a"), Some(&OwnedValue::Int(1)));
+                assert_eq!(obj.get("b"), Some(&OwnedValue::Int(2)));
+                assert_eq!(obj.get("c"), Some(&OwnedValue::Int(3)));
+                assert_eq!(obj.get("d"), Some(&OwnedValue::Int(4)));
+            }
+        );
+
+        // Non-string keys go through tojson; a missing value is null
+        query!(
+            br#"[{"key": 1, "value": "one"}, {"key": false}, {"key": null, "value": 0}]"#,
+            "from_entries",
+            QueryResult::Owned(OwnedValue::Object(obj)) => {
+                assert_eq!(obj.get("1"), Some(&OwnedValue::String("one".into())));
+                assert_eq!(obj.get("false"), Some(&OwnedValue::Null));
+                assert_eq!(obj.get("null"), Some(&OwnedValue::Int(0)));
+            }
+        );
+
+        // An explicit null value wins over "v"
+        query!(br#"[{"key": "a", "value": null, "v": 1}]"#, "from_entries",
+            QueryResult::Owned(OwnedValue::Object(obj)) => {
+                assert_eq!(obj.get("a"), Some(&OwnedValue::Null));
+            }
+        );
+
+        query!(br"[1]", "from_entries",
+            QueryResult::Error(_) => {}
+        );
+    }
+
+    #[test]
+    fn test_builtin_entries_round_trip() {
+        // from_entries(to_entries(.)) == . for any object
+        for json in [
+            &b"{}"[..],
+            br#"{"a": 1}"#,
+            br#"{"b": [1, {"c": null}], "a": "x", "": false}"#,
+            br#"{"null": null, "1": 1.5}"#,
+        ] {
+            query!(json, "(to_entries | from_entries) == .",
+                QueryResult::Owned(OwnedValue::Bool(true)) => {}
+            );
+            query!(json, "with_entries(.) == .",
+                QueryResult::Owned(OwnedValue::Bool(true)) => {}
+            );
+        }
+    }
+
     #[test]
     fn test_builtin_with_entries() {
         // Simple transformation - just pass through
@@ -15213,12 +15652,53 @@ mod tests {
             }
         );
 
-        // Already a number
+        // Already a number: passed through as-is
         query!(br"42", "tonumber",
-            QueryResult::Owned(OwnedValue::Int(n)) => {
-                assert_eq!(n, 42);
+            QueryResult::One(StandardJson::Number(n)) => {
+                assert_eq!(n.as_i64().unwrap(), 42);
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_tonumber_uses_json_number_syntax() {
+        query!(br#"" -1.5e2 ""#, "tonumber",
+            QueryResult::Owned(OwnedValue::Float(f)) => assert!((f + 150.0).abs() < f64::EPSILON)
+        );
+        for text in [
+            "0x10", ".5", "1.", "+1", "01", "nan", "inf", "1e", "", "[1]", "1 2",
+        ] {
+            let json = format!("\"{text}\"");
+            query!(json.as_bytes(), "tonumber", QueryResult::Error(e) => {
+                assert_eq!(e.to_string(), format!("cannot parse '{text}' as number"));
+            });
+        }
+        query!(b"true", "tonumber", QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), "boolean (true) cannot be parsed as a number");
+        });
+        query!(br#"["1", "x", 2]"#, "[.[] | tonumber?]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr.len(), 2);
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_tostring_is_json_text() {
+        query!(br#"[1.5, {"a": [1, "x"]}, "s"]"#, "map(tostring)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![
+                        OwnedValue::String("1.5".into()),
+                        OwnedValue::String(r#"{"a":[1,"x"]}"#.into()),
+                        OwnedValue::String("s".into()),
+                    ]
+                );
             }
         );
+        // Strings pass through untouched
+        query!(br#""s""#, "tostring", QueryResult::One(StandardJson::String(_)) => {});
     }
 
     // =========================================================================
@@ -15246,6 +15726,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_explode_implode_round_trip() {
+        query!("\"Hello \u{65e5}\u{672c}\u{8a9e} \u{1f600}\"".as_bytes(), "explode | implode",
+            QueryResult::Owned(OwnedValue::String(s)) => {
+                assert_eq!(s, "Hello \u{65e5}\u{672c}\u{8a9e} \u{1f600}");
+            }
+        );
+        // Astral codepoints are single elements, not surrogate pairs
+        query!("\"\u{1f600}\"".as_bytes(), "explode",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(0x1F600)]);
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_schema_validates() {
+        let schema = r#"{"type": "object", "required": ["id"], "properties": {"id": {"type": "integer", "minimum": 1}}}"#;
+        let query = format!("[.[] | select(schema_validates({schema})) | .id]");
+        query!(br#"[{"id": 2}, {"id": 0}, {"name": "x"}, 3, {"id": 5, "x": null}]"#, &query,
+            QueryResult::Owned(OwnedValue::Array(ids)) => {
+                assert_eq!(ids, vec![OwnedValue::Int(2), OwnedValue::Int(5)]);
+            }
+        );
+        // One output per schema
+        query!(br#""abc""#, r#"schema_validates({"maxLength": 2}, {"type": "string"})"#,
+            QueryResult::ManyOwned(results) => {
+                assert_eq!(results, vec![OwnedValue::Bool(false), OwnedValue::Bool(true)]);
+            }
+        );
+        query!(b"1", r#"schema_validates({"type": "int"})"#,
+            QueryResult::Error(e) => {
+                assert_eq!(
+                    e.to_string(),
+                    r#"schema_validates: .type: invalid schema: unknown type "int""#
+                );
+            }
+        );
+        query!(b"1", r#"schema_validates({"type": "int"})?"#, QueryResult::None => {});
+        query!(b"[1.5, 2]", r#"map(schema_validates({"type": "integer"}))"#,
+            QueryResult::Owned(OwnedValue::Array(results)) => {
+                assert_eq!(results, vec![OwnedValue::Bool(false), OwnedValue::Bool(true)]);
+            }
+        );
+        // Unsupported keywords are errors, not ignored
+        query!(b"3", r#"schema_validates({"enum": [1, 2]})"#,
+            QueryResult::Error(e) => {
+                assert_eq!(
+                    e.to_string(),
+                    "schema_validates: .enum: invalid schema: enum is not supported"
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn test_builtin_implode_rejects_invalid_codepoints() {
+        for (json, msg) in [
+            ("[55296]", "invalid codepoint: 55296"),
+            ("[57343]", "invalid codepoint: 57343"),
+            ("[1114112]", "invalid codepoint: 1114112"),
+            ("[-1]", "invalid codepoint: -1"),
+            (r#"["a"]"#, "Unicode codepoint must be numeric"),
+            ("[null]", "Unicode codepoint must be numeric"),
+            (r#""abc""#, "implode input must be an array"),
+        ] {
+            query!(json.as_bytes(), "implode", QueryResult::Error(e) => {
+                assert_eq!(e.to_string(), msg, "implode of {json}");
+            });
+        }
+        query!(b"[1114111, 55295]", "implode | length",
+            QueryResult::Owned(OwnedValue::Int(2)) => {}
+        );
+        query!(b"1", "explode", QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), "explode input must be a string");
+        });
+    }
+
+    #[test]
+    fn test_builtin_ascii() {
+        query!(b"65", "ascii",
+            QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "A")
+        );
+        query!(b"null", "[ascii(72, 105)] | add",
+            QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "Hi")
+        );
+        query!(b"55296", "ascii", QueryResult::Error(_) => {});
+    }
+
     #[test]
     fn test_builtin_test() {
         query!(br#""hello world""#, r#"test("world")"#,
@@ -15272,6 +15841,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_indices_generator_args() {
+        query!(br#""a,b;c""#, r#"[indices(",", ";")]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![
+                        OwnedValue::Array(vec![OwnedValue::Int(1)]),
+                        OwnedValue::Array(vec![OwnedValue::Int(3)]),
+                    ]
+                );
+            }
+        );
+        query!(br#""a,b;c""#, r#"[split(",", ";")] | map(length)"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(2), OwnedValue::Int(2)]);
+            }
+        );
+    }
+
     #[test]
     fn test_builtin_index() {
         query!(br#""hello world""#, r#"index("world")"#,
@@ -15421,9 +16010,69 @@ mod tests {
             }
         );
 
-        // No match returns null
+        // No match produces no output
         query!(br#""hello""#, r#"match("[0-9]+")"#,
-            QueryResult::Owned(OwnedValue::Null) => {}
+            QueryResult::None => {}
+        );
+
+        // Offsets and lengths count codepoints, not bytes
+        query!("\"aéb\"".as_bytes(), r#"match("b") | [.offset, .length]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[2, 1]))
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_match_global() {
+        // Each match is a separate output, with its own captures
+        query!(br#""a1 b2""#, r#"[match("([a-z])([0-9])"; "g") | [.offset, (.captures[] | .string)]]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"[[0,"a","1"],[3,"b","2"]]"#)
+        );
+
+        // Empty matches are found at every position unless `n` is given
+        query!(br#""abc""#, r#"[match(""; "g") | .offset]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[0, 1, 2, 3]))
+        );
+        query!(br#""abc""#, r#"[match(""; "gn")]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert!(arr.is_empty())
+        );
+
+        // Array form: [re, flags]
+        query!(br#""aAbB""#, r#"[match(["a", "gi"]) | .string]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"["a","A"]"#)
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_unmatched_group() {
+        query!(br#""foo""#, r#"match("(?<x>z)?(o+)") | .captures"#,
+            QueryResult::Owned(v) => assert_eq!(
+                v.to_json(),
+                r#"[{"offset":-1,"length":0,"string":null,"name":"x"},{"offset":1,"length":2,"string":"oo","name":null}]"#
+            )
+        );
+        query!(br#""foo""#, r#"capture("(?<a>x)?(?<b>f)")"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":null,"b":"f"}"#)
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_errors() {
+        query!(b"123", r#"test("1")"#,
+            QueryResult::Error(e) => {
+                assert_eq!(e.message, "number (123) cannot be matched, as it is not a string");
+            }
+        );
+        query!(br#""abc""#, r#"test("a"; "q")"#,
+            QueryResult::Error(e) => assert_eq!(e.message, "q is not a valid modifier string")
+        );
+        query!(br#""abc""#, r#"test("a"; 1)"#,
+            QueryResult::Error(e) => assert_eq!(e.message, "1 is not a string")
+        );
+        query!(br#""abc""#, "test(1)",
+            QueryResult::Error(e) => assert_eq!(e.message, "number not a string or array")
         );
     }
 
@@ -15436,6 +16085,44 @@ mod tests {
                 assert_eq!(obj.get("second"), Some(&OwnedValue::String("bar".to_string())));
             }
         );
+
+        // One object per match with `g`; nothing without a match
+        query!(br#""k1=a, k2=b""#, r#"[capture("(?<key>\\w+)=(?<value>\\w+)"; "g")]"#,
+            QueryResult::Owned(v) => assert_eq!(
+                v.to_json(),
+                r#"[{"key":"k1","value":"a"},{"key":"k2","value":"b"}]"#
+            )
+        );
+        query!(br#""none""#, r#"capture("(?<d>[0-9])")"#,
+            QueryResult::None => {}
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_test_flags() {
+        query!(br#""ABC""#, r#"test("b"; "i")"#,
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(br#""ABC""#, r#"test(["b", "i"])"#,
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(br#""ABC""#, r#"test("b"; null)"#,
+            QueryResult::Owned(OwnedValue::Bool(false)) => {}
+        );
+        // Oniguruma flags: `s` anchors ^ and $ to the input, `m` makes `.`
+        // match newlines, `p` is both
+        query!(br#""a\nb""#,
+            r#"[test("a.b"), test("a.b"; "s"), test("a.b"; "m"), test("a.b"; "p")]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[false,false,true,true]")
+        );
+        query!(br#""a\nb""#,
+            r#"[test("^b"), test("^b"; "s"), test("^b"; "m"), test("a$"; "p"), test("b$"; "s")]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[false,false,false,false,true]")
+        );
+        query!(br#""abc""#, r#"[test("a", "z")]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+        );
     }
 
     // =========================================================================
@@ -15469,6 +16156,55 @@ mod tests {
         query!(br#"["a", "b", "c"]"#, r"reduce .[] as $x (0; . + 1)",
             QueryResult::Owned(OwnedValue::Int(3)) => {}
         );
+
+        // Empty input leaves the initial value
+        query!(br"[]", r"reduce .[] as $x (42; . + $x)",
+            QueryResult::Owned(OwnedValue::Int(42)) => {}
+        );
+
+        // `.` is the accumulator, `$x` the element; the input is still reachable
+        // through variables bound outside
+        query!(br#"{"xs": [1, 2], "k": 10}"#, r".k as $k | reduce .xs[] as $x ([]; . + [$x * $k])",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(10), OwnedValue::Int(20)]);
+            }
+        );
+    }
+
+    #[test]
+    fn test_reduce_generators() {
+        // Each init output starts its own reduction
+        query!(br"[1, 2, 3]", r"[reduce .[] as $x (0, 10; . + $x)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![OwnedValue::Int(6), OwnedValue::Int(16)]);
+            }
+        );
+        query!(br"[1, 2, 3]", r"[reduce .[] as $x (empty; . + $x)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert!(arr.is_empty());
+            }
+        );
+
+        // The last output of update wins; no output resets to null (jq 1.7)
+        query!(br"[1, 2, 3]", r"reduce .[] as $x (0; . + $x, . * 100)",
+            QueryResult::Owned(OwnedValue::Int(0)) => {}
+        );
+        query!(br"[1, 2, 3]", r"reduce .[] as $x (0; empty)",
+            QueryResult::Owned(OwnedValue::Null) => {}
+        );
+    }
+
+    #[test]
+    fn test_reduce_destructuring() {
+        query!(br"[[1, 2], [3, 4]]", r"reduce .[] as [$a, $b] (0; . + $a * $b)",
+            QueryResult::Owned(OwnedValue::Int(14)) => {}
+        );
+        query!(br#"[{"k": "a", "v": 1}, {"k": "b", "v": 2}]"#, r"reduce .[] as {k: $k, v: $v} ({}; . + {($k): $v})",
+            QueryResult::Owned(OwnedValue::Object(obj)) => {
+                assert_eq!(obj.get("a"), Some(&OwnedValue::Int(1)));
+                assert_eq!(obj.get("b"), Some(&OwnedValue::Int(2)));
+            }
+        );
     }
 
     #[test]
@@ -15482,6 +16218,70 @@ mod tests {
                 assert_eq!(arr[2], OwnedValue::Int(6));
             }
         );
+
+        // Extract sees the element and the state
+        query!(br"[1, 2, 3]", r"[foreach .[] as $x (0; . + $x; [$x, .])]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![
+                    OwnedValue::Array(ints(&[1, 1])),
+                    OwnedValue::Array(ints(&[2, 3])),
+                    OwnedValue::Array(ints(&[3, 6])),
+                ]);
+            }
+        );
+
+        // Sliding window of the last two elements
+        query!(br"[1, 2, 3, 4]", r"[foreach .[] as $x ([]; (. + [$x])[-2:]; select(length == 2) | add)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[3, 5, 7]))
+        );
+
+        // Early termination
+        query!(br"[1, 2, 3, 4]", r"[limit(2; foreach .[] as $x (0; . + $x))]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 3]))
+        );
+        query!(br"[1, 2, 3, 4]", r"first(foreach .[] as $x (0; . + $x; select(. > 4)))",
+            QueryResult::Owned(OwnedValue::Int(6)) => {}
+        );
+    }
+
+    #[test]
+    fn test_foreach_generators() {
+        // Each init output starts its own loop
+        query!(br"null", r"[foreach (1, 2) as $x (0, 10; . + $x)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 3, 11, 13]))
+        );
+
+        // Every update output is emitted; the last one carries over
+        query!(br"[1, 2]", r"[foreach .[] as $x (0; . + $x, . * 10)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 0, 2, 0]))
+        );
+
+        // No update output emits nothing and keeps the state
+        query!(br"[1, 2, 3]", r"[foreach .[] as $x (0; if $x == 2 then empty else . + $x end)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 4]))
+        );
+
+        // Extract may emit any number of values
+        query!(br"[1, 2, 3]", r"[foreach .[] as $x (0; . + $x; select(. > 1), -1)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[-1, 3, -1, 6, -1]));
+            }
+        );
+    }
+
+    #[test]
+    fn test_foreach_destructuring() {
+        query!(br"[[1, 2], [3, 4]]", r"[foreach .[] as [$a, $b] (0; . + $a * $b; [$a, .])]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![
+                    OwnedValue::Array(ints(&[1, 2])),
+                    OwnedValue::Array(ints(&[3, 14])),
+                ]);
+            }
+        );
+        query!(br#"[{"n": 1}, {"n": 2}]"#, r"[foreach .[] as {n: $n} (0; . + $n)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 3]))
+        );
     }
 
     #[test]
@@ -15536,6 +16336,45 @@ mod tests {
                 ]);
             }
         );
+
+        query!(br"null", r"[limit(3; range(100))]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[0, 1, 2]))
+        );
+        query!(br"null", r"first(range(10))",
+            QueryResult::Owned(OwnedValue::Int(0)) => {}
+        );
+
+        // Comma arguments; a negative limit passes everything through
+        query!(br"null", r"[limit(2; 1, 2, 3)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 2]))
+        );
+        query!(br"[1, 2, 3]", r"[limit(-1; .[])]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 2, 3]))
+        );
+    }
+
+    #[test]
+    fn test_limit_short_circuits() {
+        // Outputs past the limit are never evaluated
+        query!(br"null", r#"first(1, error("unreachable"))"#,
+            QueryResult::Owned(OwnedValue::Int(1)) => {}
+        );
+        query!(br"null", r#"[limit(2; 1, (2, error("unreachable")))]"#,
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 2]))
+        );
+
+        // ...but earlier errors still surface
+        query!(br"null", r#"[limit(2; 1, error("x"))]"#,
+            QueryResult::Error(e) => assert_eq!(e.message, "x")
+        );
+
+        // Generators produce only what is needed, past their usual caps
+        query!(br"null", r"[limit(200000; range(1000000))] | length",
+            QueryResult::Owned(OwnedValue::Int(200_000)) => {}
+        );
+        query!(br"null", r"[limit(5000; repeat(1))] | length",
+            QueryResult::Owned(OwnedValue::Int(5000)) => {}
+        );
     }
 
     #[test]
@@ -15703,35 +16542,218 @@ mod tests {
             QueryResult::Owned(OwnedValue::Int(10)) => {}
         );
 
-        // This should work: use both in a pipe, but inc is defined first
-        // (so inc isn't nested inside double's scope)
-        query!(br"5", r"def inc: . + 1; def double: . * 2; double | inc",
-            QueryResult::Owned(OwnedValue::Int(11)) => {}
+        // This should work: use both in a pipe, but inc is defined first
+        // (so inc isn't nested inside double's scope)
+        query!(br"5", r"def inc: . + 1; def double: . * 2; double | inc",
+            QueryResult::Owned(OwnedValue::Int(11)) => {}
+        );
+    }
+
+    #[test]
+    fn test_function_uses_input() {
+        // Function that uses the input value
+        query!(br#"{"x": 10}"#, r"def getx: .x; getx",
+            QueryResult::One(StandardJson::Number(n)) => {
+                assert_eq!(n.as_i64().unwrap(), 10);
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_with_filter_param() {
+        // Function with filter parameter (jq-style)
+        query!(br"[1, 2, 3]", r"def apply(f): map(f); apply(. * 2)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, vec![
+                    OwnedValue::Int(2),
+                    OwnedValue::Int(4),
+                    OwnedValue::Int(6),
+                ]);
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_recursive() {
+        query!(
+            br"5",
+            r"def factorial: if . <= 1 then 1 else . * ((. - 1) | factorial) end; factorial",
+            QueryResult::Owned(OwnedValue::Int(120)) => {}
+        );
+
+        query!(
+            br"null",
+            r"def fib: if . < 2 then . else (. - 1 | fib) + (. - 2 | fib) end; [range(10) | fib]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[0, 1, 1, 2, 3, 5, 8, 13, 21, 34]));
+            }
+        );
+
+        // Recursion through a filter parameter
+        query!(
+            br"3",
+            r"def f(g): if . > 0 then . - 1 | f(g) else g end; f(. + 100)",
+            QueryResult::Owned(OwnedValue::Int(100)) => {}
+        );
+    }
+
+    #[test]
+    fn test_eval_many_limit_stops_early() {
+        // 1 MB array: ten numbers, then strings that `. + 1` would reject
+        let mut json = String::from("[0, 1, 2, 3, 4, 5, 6, 7, 8, 9");
+        while json.len() < 1 << 20 {
+            json.push_str(r#", "x""#);
+        }
+        json.push(']');
+        let index = JsonIndex::build(json.as_bytes());
+        let cursor = index.root(json.as_bytes());
+
+        for (filter, count) in [("limit(10; .[] | . + 1)", 10), ("first(.[] | . + 1)", 1)] {
+            let exprs = [parse(filter).unwrap()];
+            let mut outputs = eval_many::<Vec<u64>, JqSemantics>(&exprs, cursor);
+            let values: Vec<_> = outputs.by_ref().collect();
+            assert_eq!(outputs.error(), None, "{filter} evaluated past its limit");
+            assert_eq!(values, ints(&(1..=count).collect::<Vec<_>>()));
+        }
+
+        // Without the limit the eleventh element fails after the first ten
+        let exprs = [parse(".[] | . + 1").unwrap()];
+        let mut outputs = eval_many::<Vec<u64>, JqSemantics>(&exprs, cursor);
+        let values: Vec<_> = outputs.by_ref().collect();
+        assert_eq!(values, ints(&(1..=10).collect::<Vec<_>>()));
+        assert!(outputs.error().is_some());
+    }
+
+    #[test]
+    fn test_eval_many_sequences_expressions() {
+        let json = br#"{"a": [1, 2], "b": "x"}"#;
+        let index = JsonIndex::build(json);
+        let cursor = index.root(json);
+        let exprs = [
+            parse(".a[]").unwrap(),
+            parse("empty").unwrap(),
+            parse(".b").unwrap(),
+            parse(r#"error("stop")"#).unwrap(),
+            parse(".a").unwrap(),
+        ];
+        let mut outputs = eval_many::<Vec<u64>, JqSemantics>(&exprs, cursor);
+        assert_eq!(outputs.next(), Some(OwnedValue::Int(1)));
+        assert_eq!(outputs.next(), Some(OwnedValue::Int(2)));
+        assert_eq!(outputs.next(), Some(OwnedValue::String("x".into())));
+        assert_eq!(outputs.next(), None);
+        assert_eq!(outputs.next(), None);
+        assert!(outputs.error().is_some());
+
+        assert_eq!(eval_many::<Vec<u64>, JqSemantics>(&[], cursor).next(), None);
+    }
+
+    #[test]
+    fn test_eval_many_yields_outputs_before_error() {
+        let json = br"null";
+        let index = JsonIndex::build(json);
+        let exprs = [parse(r#"1, error("x"), 3"#).unwrap(), parse("4").unwrap()];
+        let mut outputs = eval_many::<Vec<u64>, JqSemantics>(&exprs, index.root(json));
+        assert_eq!(outputs.next(), Some(OwnedValue::Int(1)));
+        assert_eq!(outputs.error(), None);
+        assert_eq!(outputs.next(), None);
+        assert_eq!(outputs.error().map(|e| e.message.as_str()), Some("x"));
+        assert_eq!(outputs.next(), None);
+    }
+
+    #[test]
+    fn test_program_helper_defs() {
+        // A multi-line program file: helpers calling helpers, then the filter
+        let program = crate::jq::parse_program(
+            "# Vector helpers\n\
+             def sq: . * .;\n\
+             def sumsq: map(sq) | add;\n\
+             def scale($k): map(. * $k);\n\
+             \n\
+             {sumsq: sumsq, doubled: scale(2) | sumsq}\n",
+        )
+        .unwrap();
+        let json = br"[1, 2, 3]";
+        let index = JsonIndex::build(json);
+        match eval::<Vec<u64>, JqSemantics>(&program.expr, index.root(json)) {
+            QueryResult::Owned(OwnedValue::Object(obj)) => {
+                assert_eq!(obj.get("sumsq"), Some(&OwnedValue::Int(14)));
+                assert_eq!(obj.get("doubled"), Some(&OwnedValue::Int(56)));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_function_generator_body() {
+        query!(br"null", r"def g: 1, 2; [g, 3]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[1, 2, 3]));
+            }
+        );
+
+        query!(
+            br"0",
+            r"def upto: if . < 3 then ., (. + 1 | upto) else . end; [upto]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(arr, ints(&[0, 1, 2, 3]));
+            }
         );
     }
 
     #[test]
-    fn test_function_uses_input() {
-        // Function that uses the input value
-        query!(br#"{"x": 10}"#, r"def getx: .x; getx",
-            QueryResult::One(StandardJson::Number(n)) => {
-                assert_eq!(n.as_i64().unwrap(), 10);
-            }
-        );
+    fn test_function_depth_limit() {
+        // A full-depth recursion takes more than the 2 MiB of a test thread
+        // in debug builds; give it the usual 8 MiB of a main thread
+        let run = || {
+            query!(br"0", r"def f: f; f", QueryResult::Error(e) => {
+                assert!(e.message.contains("maximum function depth (1000)"), "{}", e.message);
+            });
+            query!(br"0", r"def f: . + 1 | f; f", QueryResult::Error(e) => {
+                assert!(e.message.contains("maximum function depth"), "{}", e.message);
+            });
+
+            let max = JqSemantics::MAX_FUNCTION_DEPTH;
+            let filter = format!("def r: if . < {} then . + 1 | r else . end; r", max - 2);
+            query!(br"0", &filter,
+                QueryResult::Owned(OwnedValue::Int(n)) => assert_eq!(n as usize, max - 2)
+            );
+        };
+        std::thread::Builder::new()
+            .stack_size(8 << 20)
+            .spawn(run)
+            .unwrap()
+            .join()
+            .unwrap();
     }
 
     #[test]
-    fn test_function_with_filter_param() {
-        // Function with filter parameter (jq-style)
-        query!(br"[1, 2, 3]", r"def apply(f): map(f); apply(. * 2)",
-            QueryResult::Owned(OwnedValue::Array(arr)) => {
-                assert_eq!(arr, vec![
-                    OwnedValue::Int(2),
-                    OwnedValue::Int(4),
-                    OwnedValue::Int(6),
-                ]);
+    fn test_function_depth_limit_from_semantics() {
+        #[derive(Debug, Clone, Copy, Default)]
+        struct Shallow;
+
+        impl EvalSemantics for Shallow {
+            const OVERFLOW_WRAPS: bool = false;
+            const DIV_BY_ZERO_IS_INFINITY: bool = false;
+            const NEGATIVE_INDEX_IN_HAS: bool = false;
+            const MOD_TRUNCATES_FLOATS: bool = true;
+            const MAX_FUNCTION_DEPTH: usize = 10;
+        }
+
+        let json = br"0";
+        let index = JsonIndex::build(json);
+        let depth = |n: usize| {
+            let filter = format!("def r: if . < {n} then . + 1 | r else . end; r");
+            eval::<Vec<u64>, Shallow>(&parse(&filter).unwrap(), index.root(json))
+        };
+        assert!(matches!(depth(8), QueryResult::Owned(OwnedValue::Int(8))));
+        match depth(9) {
+            QueryResult::Error(e) => {
+                assert_eq!(e.message, "r: maximum function depth (10) exceeded");
             }
-        );
+            other => panic!("unexpected result: {other:?}"),
+        }
+        // Each evaluation starts from depth 0
+        assert!(matches!(depth(8), QueryResult::Owned(OwnedValue::Int(8))));
     }
 
     // Phase 10 tests
@@ -15846,6 +16868,109 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_number_predicates_on_nan_and_infinite() {
+        let cases = [
+            ("nan | isnan", true),
+            ("nan | isinfinite", false),
+            ("nan | isnormal", false),
+            // jq: isfinite is `isinfinite | not`, so NaN counts as finite
+            ("nan | isfinite", true),
+            ("infinite | isinfinite", true),
+            ("-infinite | isinfinite", true),
+            ("infinite | isfinite", false),
+            ("infinite | isnan", false),
+            ("[infinite] | .[0] | isinfinite", true),
+            ("0 | isnormal", false),
+            (r#""a" | isfinite"#, false),
+        ];
+        for (filter, expected) in cases {
+            query!(b"null", filter, QueryResult::Owned(OwnedValue::Bool(b)) => {
+                assert_eq!(b, expected, "{filter}");
+            });
+        }
+        query!(b"null", "nan | type", QueryResult::Owned(OwnedValue::String(t)) => {
+            assert_eq!(t, "number");
+        });
+        query!(br#""a""#, "isnan", QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), r#"string ("a") number required"#);
+        });
+        query!(br#""a""#, "[isinfinite?]", QueryResult::Owned(OwnedValue::Array(arr)) => {
+            assert!(arr.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_nan_survives_composition() {
+        // NaN reaches later stages, nested values and conditions as NaN
+        let cases = [
+            ("nan | [isnan]", "[true]"),
+            ("nan | if isnan then 1 else 0 end", "1"),
+            ("[nan, 1] | map(isnan)", "[true,false]"),
+            ("nan | isnan and true", "true"),
+            ("{a: nan} | .a | isnan", "true"),
+            ("[nan] | .[0] < 0", "true"),
+            // but is still written as null
+            ("[nan] | tojson", r#""[null]""#),
+        ];
+        for (filter, expected) in cases {
+            query!(b"null", filter, QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), expected, "{filter}");
+            });
+        }
+    }
+
+    #[test]
+    fn test_nan_comparisons() {
+        let cases = [
+            ("nan == nan", false),
+            ("nan != nan", true),
+            // jq sorts NaN below every number, including another NaN
+            ("nan < nan", true),
+            ("nan > nan", false),
+            ("nan < -infinite", true),
+            ("1 > nan", true),
+            ("nan > null", true),
+            ("infinite > 1e308", true),
+        ];
+        for (filter, expected) in cases {
+            query!(b"null", filter, QueryResult::Owned(OwnedValue::Bool(b)) => {
+                assert_eq!(b, expected, "{filter}");
+            });
+        }
+        query!(b"null", "[infinite, -infinite, 1] | sort | map(isinfinite)",
+            QueryResult::Owned(OwnedValue::Array(arr)) => {
+                assert_eq!(
+                    arr,
+                    vec![OwnedValue::Bool(true), OwnedValue::Bool(false), OwnedValue::Bool(true)]
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn test_compare_values_orders_nan_first() {
+        let mut values = [
+            OwnedValue::Float(1.5),
+            OwnedValue::Float(f64::NAN),
+            OwnedValue::Int(-3),
+            OwnedValue::Null,
+            OwnedValue::Float(f64::NEG_INFINITY),
+            OwnedValue::Float(f64::NAN),
+        ];
+        values.sort_by(compare_values);
+        assert_eq!(values[0], OwnedValue::Null);
+        assert!(is_nan_value(&values[1]) && is_nan_value(&values[2]));
+        assert_eq!(
+            values[3..],
+            [
+                OwnedValue::Float(f64::NEG_INFINITY),
+                OwnedValue::Int(-3),
+                OwnedValue::Float(1.5)
+            ]
+        );
+    }
+
     #[test]
     fn test_trim() {
         query!(br#""  hello world  ""#, "trim", QueryResult::Owned(OwnedValue::String(s)) => {
@@ -15938,6 +17063,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_paths_nested_count() {
+        query!(br#"{"a": {"b": 1}, "c": [2, 3]}"#, "[paths] | length",
+            QueryResult::Owned(OwnedValue::Int(n)) => {
+                assert_eq!(n, 5);
+            }
+        );
+    }
+
+    #[test]
+    fn test_paths_filter_nested() {
+        query!(br#"{"a": {"b": 1}, "c": [2, "x"]}"#, r#"[paths(type == "number")]"#,
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"[["a","b"],["c",0]]"#);
+            }
+        );
+        // One path per truthy output of the filter, as with `select`
+        query!(br"[[1]]", "[paths(true, ., false)]",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), "[[0],[0],[0,0],[0,0]]");
+            }
+        );
+    }
+
+    #[test]
+    fn test_paths_filter_error() {
+        query!(br#"[1, "a"]"#, "[paths(. + 1)]",
+            QueryResult::Error(_) => {}
+        );
+    }
+
     #[test]
     fn test_leaf_paths() {
         // leaf_paths streams individual paths (like paths(scalars) in jq)
@@ -16005,6 +17161,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_setpath_preserves_order_and_checks_types() {
+        query!(br#"{"a": {"b": 0}, "c": 1}"#, r#"setpath(["a","b"]; 2)"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":{"b":2},"c":1}"#)
+        );
+        query!(b"null", r#"setpath(["a",1]; true)"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":[null,true]}"#)
+        );
+        query!(b"[1, 2]", "setpath([-1]; 3)",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,3]")
+        );
+        query!(b"[1, 2]", "setpath([-3]; 3)",
+            QueryResult::Error(e) => assert_eq!(e.message, "Out of bounds negative array index")
+        );
+        query!(b"[1, 2]", r#"setpath(["x"]; 3)"#,
+            QueryResult::Error(e) => assert_eq!(e.message, r#"Cannot index array with "x""#)
+        );
+    }
+
     #[test]
     fn test_delpaths() {
         query!(br#"{"a": 1, "b": 2}"#, r#"delpaths([["b"]])"#,
@@ -16013,6 +17188,14 @@ mod tests {
                 assert_eq!(obj.get("b"), None);
             }
         );
+
+        // Array elements are removed from the highest index down
+        query!(br#"{"a": 0, "c": [1, 2, 3], "d": 4}"#, r#"delpaths([["c",0],["c",2]])"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":0,"c":[2],"d":4}"#)
+        );
+        query!(br#"{"a": {"b": 1, "c": 2}, "d": 3}"#, r#"delpaths([["a","b"]])"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":{"c":2},"d":3}"#)
+        );
     }
 
     #[test]
@@ -16102,6 +17285,24 @@ mod tests {
         });
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_env_matches_process_environment() {
+        query!(b"null", "env | keys | length > 0",
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(b"null", "env == $ENV", QueryResult::Owned(OwnedValue::Bool(true)) => {});
+        query!(b"null", "[env[] | type] | unique",
+            QueryResult::Owned(OwnedValue::Array(types)) => {
+                assert_eq!(types, vec![OwnedValue::String("string".into())]);
+            }
+        );
+        let path = std::env::var("PATH").unwrap_or_default();
+        query!(b"null", "env.PATH // \"\"", QueryResult::Owned(OwnedValue::String(s)) => {
+            assert_eq!(s, path);
+        });
+    }
+
     #[test]
     fn test_dollar_env() {
         // $ENV returns object of environment variables (same as env builtin)
@@ -16245,6 +17446,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_path_generators() {
+        let json = br#"{"a": {"b": [1], "foo": 2}, "c": [1, 2, 3], "foo": 1}"#;
+
+        // Recursive descent visits every path in pre-order, including the root
+        query!(json, "[path(..)]",
+            QueryResult::Owned(paths) => {
+                assert_eq!(
+                    paths.to_json(),
+                    r#"[[],["a"],["a","b"],["a","b",0],["a","foo"],["c"],["c",0],["c",1],["c",2],["foo"]]"#
+                );
+            }
+        );
+
+        // Every `foo` at any depth; `.foo?` skips arrays and scalars
+        query!(json, "[path(..|.foo?)]",
+            QueryResult::Owned(paths) => {
+                assert_eq!(paths.to_json(), r#"[["foo"],["a","foo"]]"#);
+            }
+        );
+
+        query!(json, "[path(.c[] | select(. > 1))]",
+            QueryResult::Owned(paths) => {
+                assert_eq!(paths.to_json(), r#"[["c",1],["c",2]]"#);
+            }
+        );
+        query!(json, "[path(.a, .c[0])]",
+            QueryResult::Owned(paths) => {
+                assert_eq!(paths.to_json(), r#"[["a"],["c",0]]"#);
+            }
+        );
+        query!(json, "path(first(.c[]))",
+            QueryResult::Owned(path) => assert_eq!(path.to_json(), r#"["c",0]"#)
+        );
+        query!(json, "path(.z // .a.b)",
+            QueryResult::Owned(path) => assert_eq!(path.to_json(), r#"["a","b"]"#)
+        );
+        query!(json, "path(if .a then .a.b else .c end)",
+            QueryResult::Owned(path) => assert_eq!(path.to_json(), r#"["a","b"]"#)
+        );
+        query!(json, r#"path(getpath(["a","b"]))"#,
+            QueryResult::Owned(path) => assert_eq!(path.to_json(), r#"["a","b"]"#)
+        );
+        query!(json, "[path(empty)]",
+            QueryResult::Owned(OwnedValue::Array(paths)) => assert!(paths.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_path_invalid_expression() {
+        query!(b"{}", "path(1)",
+            QueryResult::Error(e) => {
+                assert_eq!(e.message, "Invalid path expression with result 1");
+            }
+        );
+        query!(br#"{"a": 1}"#, "path(.a | tostring)",
+            QueryResult::Error(e) => {
+                assert_eq!(e.message, r#"Invalid path expression with result "1""#);
+            }
+        );
+        query!(b"[1]", "path(.a)",
+            QueryResult::Error(e) => {
+                assert_eq!(e.message, r#"Cannot index array with "a""#);
+            }
+        );
+    }
+
     #[test]
     fn test_paths_filter() {
         // paths(filter) streams paths where values match filter
@@ -16305,6 +17573,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_tojson_fromjson() {
+        query!(br#"{"a": [1, "x\ny", null]}"#, "tojson",
+            QueryResult::Owned(OwnedValue::String(s)) => {
+                assert_eq!(s, r#"{"a":[1,"x\ny",null]}"#);
+            }
+        );
+        query!(br#"{"a": 1}"#, r#"tojson | fromjson == {"a": 1}"#,
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+        query!(br#"" [1, \"\u00e9\"] ""#, "fromjson",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,\"\u{e9}\"]")
+        );
+    }
+
+    #[test]
+    fn test_builtin_fromjson_errors() {
+        query!(b"1", "fromjson", QueryResult::Error(e) => {
+            assert_eq!(e.to_string(), "expected string, got number");
+        });
+        query!(br#""[1,2""#, "fromjson", QueryResult::Error(e) => {
+            assert!(e.to_string().starts_with("unexpected end of input"), "{e}");
+            assert!(e.to_string().ends_with("(while parsing '[1,2')"), "{e}");
+        });
+        query!(br#""1 2""#, "fromjson", QueryResult::Error(e) => {
+            assert!(e.to_string().starts_with("trailing content"), "{e}");
+        });
+        query!(br#""""#, "fromjson?", QueryResult::None => {});
+    }
+
     #[test]
     fn test_tojsonstream() {
         // tojsonstream converts to path/value pairs
@@ -16889,6 +18187,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_assign_creates_missing_paths() {
+        query!(br#"{"a": 1}"#, r".b.c = 1",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"a":1,"b":{"c":1}}"#);
+            }
+        );
+        query!(br#"{"l": [1]}"#, r".l[3] = 1",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"l":[1,null,null,1]}"#);
+            }
+        );
+    }
+
+    #[test]
+    fn test_assign_each_rhs_output() {
+        // One result per output of the right-hand side, evaluated against `.`
+        query!(br#"{"a": 1, "x": 5}"#, r".a = (.x, 2)",
+            QueryResult::ManyOwned(results) => {
+                let results: Vec<_> = results.iter().map(OwnedValue::to_json).collect();
+                assert_eq!(results, [r#"{"a":5,"x":5}"#, r#"{"a":2,"x":5}"#]);
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_multiple_paths() {
+        query!(br#"{"a": 1, "l": [2, 3]}"#, r"(.a, .l[0]) |= . + 10",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"a":11,"l":[12,3]}"#);
+            }
+        );
+        query!(br"[1, 5, 2, 7]", r"(.[] | select(. > 4)) |= 0",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), "[1,0,2,0]");
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_empty_deletes() {
+        query!(br#"{"a": 1, "x": 2}"#, r".x |= empty",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"a":1}"#);
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_uses_first_output() {
+        query!(br#"{"a": 1}"#, r".a |= (. + 1, . + 2)",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"a":2}"#);
+            }
+        );
+    }
+
+    #[test]
+    fn test_compound_assign_rhs_uses_input() {
+        // The right-hand side of `op=` sees the whole input, not the target
+        query!(br#"{"a": 1, "b": 10}"#, r".a += .b",
+            QueryResult::Owned(v) => {
+                assert_eq!(v.to_json(), r#"{"a":11,"b":10}"#);
+            }
+        );
+    }
+
+    #[test]
+    fn test_assign_slice_is_error() {
+        query!(br"[1, 2, 3]", r".[1:] = [9]",
+            QueryResult::Error(e) => {
+                assert!(e.to_string().contains("slice"), "{e}");
+            }
+        );
+    }
+
     // ========================================================================
     // YAML Metadata Functions (yq)
     // ========================================================================
@@ -17439,12 +18813,23 @@ mod tests {
 
     #[test]
     fn test_nth_stream() {
-        // nth(1; .[]) - get second element (0-indexed)
+        // nth(1; .[]) - get second element (0-indexed), as a reference
         query!(b"[10, 20, 30]", "nth(1; .[])",
-            QueryResult::Owned(OwnedValue::Int(n)) => {
-                assert_eq!(n, 20);
+            QueryResult::One(StandardJson::Number(n)) => {
+                assert_eq!(n.as_i64().unwrap(), 20);
             }
         );
+
+        // Too few outputs, and computed outputs
+        query!(b"[10, 20, 30]", "[nth(3; .[])]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert!(arr.is_empty())
+        );
+        query!(b"null", "nth(2; range(10; 0; -1))",
+            QueryResult::Owned(OwnedValue::Int(8)) => {}
+        );
+        query!(b"null", "nth(-1; 1, 2)",
+            QueryResult::Error(e) => assert_eq!(e.message, "Out of bounds negative array index")
+        );
     }
 
     #[test]
@@ -17658,6 +19043,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_recurse_depth_first_order() {
+        query!(b"null", "[recurse] == [null]", QueryResult::Owned(OwnedValue::Bool(true)) => {});
+        query!(br#"{"a":{"b":1}}"#, "[recurse] | length", QueryResult::Owned(OwnedValue::Int(3)) => {});
+        // Pre-order, each subtree before the next sibling, as jq does
+        query!(br#"{"a":{"b":1},"c":[2]}"#, "[recurse]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"[{"a":{"b":1},"c":[2]},{"b":1},1,[2],2]"#)
+        );
+        query!(b"1", "[recurse(if . < 3 then .+1, .+2 else empty end)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,2,3,4,3]")
+        );
+        // The input is emitted whether or not it satisfies cond
+        query!(b"0", "[recurse(.+1; . < 0)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[0]")
+        );
+    }
+
+    #[test]
+    fn test_recurse_keeps_each_output_of_f() {
+        // An array produced by f is one value, not a list of children
+        query!(br#"{"children":[[1],[2]]}"#, "[recurse(.children?)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"[{"children":[[1],[2]]},[[1],[2]]]"#)
+        );
+        // So is null, until f would only reproduce it
+        query!(br#"{"c":{"c":null}}"#, "[recurse(.c)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"[{"c":{"c":null}},{"c":null},null]"#)
+        );
+        query!(b"1", "[recurse(if . == 1 then 2 else 1 end)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,2]")
+        );
+        // An error from f ends that branch
+        query!(b"2", r#"[recurse(if . > 0 then . - 1 else error("x") end)]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[2,1,0]")
+        );
+    }
+
+    #[test]
+    fn test_recurse_unbounded() {
+        query!(b"0", "[recurse(if . < 12000 then . + 1 else empty end)] | length",
+            QueryResult::Owned(OwnedValue::Int(12001)) => {}
+        );
+        // limit and first stop an infinite recursion
+        query!(b"0", "[limit(3; recurse(. + 1))]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[0,1,2]")
+        );
+        query!(b"0", "first(recurse(. + 1; . > 5))", QueryResult::Owned(OwnedValue::Int(0)) => {});
+        // Also when recurse is not the first stage of the pipe
+        query!(b"0", "first(1 | recurse(. + 1))", QueryResult::Owned(OwnedValue::Int(1)) => {});
+        query!(b"0", "[limit(3; 1 | recurse(. + 1))]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,2,3]")
+        );
+        query!(br#"{"a":5}"#, "[limit(3; .a | recurse(. + 1) | . * 2)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[10,12,14]")
+        );
+        query!(b"[1,2]", "[limit(4; .[] | recurse(. + 1) | select(. % 2 == 0))]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[2,4,6,8]")
+        );
+        query!(b"null", "first(label $out | 1 | recurse(. + 1))",
+            QueryResult::Owned(OwnedValue::Int(1)) => {}
+        );
+        query!(b"null", "any(1 | recurse(. + 1); . > 5)",
+            QueryResult::Owned(OwnedValue::Bool(true)) => {}
+        );
+    }
+
+    #[test]
+    fn test_recurse_stops_early_when_composed() {
+        // Infinite recursions feeding later stages, isempty and any/all are
+        // only walked as far as the result needs
+        query!(b"0", "first(recurse(.+1) | select(. > 5))",
+            QueryResult::Owned(OwnedValue::Int(6)) => {}
+        );
+        query!(b"0", "[limit(2; recurse(.+1) | .)]",
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[0,1]")
+        );
+        query!(b"0", "isempty(recurse(.+1))", QueryResult::Owned(OwnedValue::Bool(false)) => {});
+        query!(b"0", "any(recurse(.+1); . > 5)", QueryResult::Owned(OwnedValue::Bool(true)) => {});
+        query!(b"0", "all(recurse(.+1); . < 5)", QueryResult::Owned(OwnedValue::Bool(false)) => {});
+    }
+
     #[test]
     fn test_walk_strings() {
         // walk to uppercase all strings
@@ -17691,6 +19156,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_walk_deeply_nested() {
+        // Every number at every depth is doubled, inside arrays and objects
+        query!(br#"{"a": 1, "b": [2, {"c": [3, [4, {"d": 5}]]}], "e": {"f": {"g": {"h": 6}}}, "s": "x"}"#,
+            r#"walk(if type == "number" then . * 2 else . end)"#,
+            QueryResult::Owned(v) => assert_eq!(
+                v.to_json(),
+                r#"{"a":2,"b":[4,{"c":[6,[8,{"d":10}]]}],"e":{"f":{"g":{"h":12}}},"s":"x"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_walk_generators() {
+        // Array elements keep every output, object values the first; f's
+        // outputs at the top are all emitted (jq 1.7)
+        query!(br#"{"a": 1, "b": [2, "x"]}"#, r#"[walk(if type == "number" then empty else . end)]"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"[{"b":["x"]}]"#)
+        );
+        query!(br"[1, [2]]", r#"walk(if type == "number" then ., . * 10 else . end)"#,
+            QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,10,[2,20]]")
+        );
+        query!(br#"{"a": 1}"#, "[walk(empty)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert!(arr.is_empty())
+        );
+        query!(br"1", "[walk(., .)]",
+            QueryResult::Owned(OwnedValue::Array(arr)) => assert_eq!(arr, ints(&[1, 1]))
+        );
+    }
+
     // Tests for indices/index/rindex
 
     #[test]
@@ -16,8 +16,11 @@ use alloc::vec::Vec;
 use indexmap::IndexMap;
 
 use super::document::{DocumentCursor, DocumentElements, DocumentFields, DocumentValue};
-use super::eval::{eval as full_eval, EvalError, EvalSemantics, JqSemantics, QueryResult};
-use super::expr::{Builtin, CompareOp, Expr, Literal};
+use super::eval::{
+    compare_with, eval as full_eval, number_as_f64, number_from_text, owned_value_to_json_string,
+    EvalError, EvalSemantics, JqSemantics, QueryResult,
+};
+use super::expr::{Builtin, Expr, Literal};
 use super::value::OwnedValue;
 use crate::json::JsonIndex;
 
@@ -74,7 +77,7 @@ fn standard_json_to_owned<W: Clone + AsRef<[u64]>>(
         StandardJson::Number(n) => {
             if let Ok(i) = n.as_i64() {
                 OwnedValue::Int(i)
-            } else if let Ok(f) = n.as_f64() {
+            } else if let Ok(f) = number_as_f64(n) {
                 OwnedValue::Float(f)
             } else {
                 OwnedValue::Null
@@ -102,72 +105,6 @@ fn standard_json_to_owned<W: Clone + AsRef<[u64]>>(
     }
 }
 
-/// Compare two OwnedValues for ordering.
-///
-/// Uses jq ordering: null < bool < number < string < array < object.
-/// Returns Some(Ordering) if values are comparable, None if not (for mixed incompatible types).
-fn compare_values(left: &OwnedValue, right: &OwnedValue) -> Option<core::cmp::Ordering> {
-    use core::cmp::Ordering;
-
-    fn type_order(v: &OwnedValue) -> u8 {
-        match v {
-            OwnedValue::Null => 0,
-            OwnedValue::Bool(_) => 1,
-            OwnedValue::Int(_) | OwnedValue::Float(_) => 2,
-            OwnedValue::String(_) => 3,
-            OwnedValue::Array(_) => 4,
-            OwnedValue::Object(_) => 5,
-        }
-    }
-
-    let left_type = type_order(left);
-    let right_type = type_order(right);
-
-    if left_type != right_type {
-        return Some(left_type.cmp(&right_type));
-    }
-
-    match (left, right) {
-        (OwnedValue::Null, OwnedValue::Null) => Some(Ordering::Equal),
-        (OwnedValue::Bool(a), OwnedValue::Bool(b)) => Some(a.cmp(b)),
-        (OwnedValue::Int(a), OwnedValue::Int(b)) => Some(a.cmp(b)),
-        (OwnedValue::Float(a), OwnedValue::Float(b)) => a.partial_cmp(b),
-        (OwnedValue::Int(a), OwnedValue::Float(b)) => (*a as f64).partial_cmp(b),
-        (OwnedValue::Float(a), OwnedValue::Int(b)) => a.partial_cmp(&(*b as f64)),
-        (OwnedValue::String(a), OwnedValue::String(b)) => Some(a.cmp(b)),
-        // Arrays: compare element-wise, then by length
-        (OwnedValue::Array(a), OwnedValue::Array(b)) => {
-            for (ai, bi) in a.iter().zip(b.iter()) {
-                match compare_values(ai, bi) {
-                    Some(Ordering::Equal) => continue,
-                    other => return other,
-                }
-            }
-            Some(a.len().cmp(&b.len()))
-        }
-        // Objects: jq compares the sorted key arrays first, then values in
-        // sorted-key order.
-        (OwnedValue::Object(a), OwnedValue::Object(b)) => {
-            let mut a_keys: Vec<&String> = a.keys().collect();
-            let mut b_keys: Vec<&String> = b.keys().collect();
-            a_keys.sort();
-            b_keys.sort();
-            match a_keys.cmp(&b_keys) {
-                Ordering::Equal => {}
-                other => return Some(other),
-            }
-            for k in a_keys {
-                match compare_values(&a[k], &b[k]) {
-                    Some(Ordering::Equal) => continue,
-                    other => return other,
-                }
-            }
-            Some(Ordering::Equal)
-        }
-        _ => None,
-    }
-}
-
 /// Evaluate an expression on an OwnedValue using the full evaluator.
 ///
 /// This converts the OwnedValue to JSON, evaluates using the full evaluator,
@@ -176,7 +113,7 @@ fn eval_on_owned<S: EvalSemantics, V: DocumentValue>(
     expr: &Expr,
     owned: OwnedValue,
 ) -> GenericResult<V> {
-    let json_str = owned.to_json();
+    let json_str = owned_value_to_json_string(&owned);
     let json_bytes = json_str.as_bytes();
     let index = JsonIndex::build(json_bytes);
     let cursor = index.root(json_bytes);
@@ -700,56 +637,15 @@ fn eval_single<S: EvalSemantics, V: DocumentValue>(
                 GenericResult::Break(label) => return GenericResult::Break(label),
             };
 
-            // Perform the comparison
-            let result = match op {
-                CompareOp::Eq => left_owned == right_owned,
-                CompareOp::Ne => left_owned != right_owned,
-                CompareOp::Lt => {
-                    compare_values(&left_owned, &right_owned) == Some(core::cmp::Ordering::Less)
-                }
-                CompareOp::Le => matches!(
-                    compare_values(&left_owned, &right_owned),
-                    Some(core::cmp::Ordering::Less | core::cmp::Ordering::Equal)
-                ),
-                CompareOp::Gt => {
-                    compare_values(&left_owned, &right_owned) == Some(core::cmp::Ordering::Greater)
-                }
-                CompareOp::Ge => matches!(
-                    compare_values(&left_owned, &right_owned),
-                    Some(core::cmp::Ordering::Greater | core::cmp::Ordering::Equal)
-                ),
-            };
+            let result = compare_with(*op, &left_owned, &right_owned);
 
             GenericResult::Owned(OwnedValue::Bool(result))
         }
 
         // Fall back to the full evaluator for complex expressions
         _ => {
-            // Convert to OwnedValue, then to JSON, then evaluate with full evaluator
-            let owned = to_owned(&value);
-            let json_str = owned.to_json();
-            let json_bytes = json_str.as_bytes();
-            let index = JsonIndex::build(json_bytes);
-            let cursor = index.root(json_bytes);
-
-            // Evaluate using the full evaluator
-            match full_eval::<Vec<u64>, S>(expr, cursor) {
-                QueryResult::One(v) => {
-                    // Convert StandardJson back to OwnedValue
-                    GenericResult::Owned(standard_json_to_owned(&v))
-                }
-                QueryResult::OneCursor(c) => {
-                    GenericResult::Owned(standard_json_to_owned(&c.value()))
-                }
-                QueryResult::Many(vs) => {
-                    GenericResult::ManyOwned(vs.iter().map(standard_json_to_owned).collect())
-                }
-                QueryResult::None => GenericResult::None,
-                QueryResult::Error(e) => GenericResult::Error(e),
-                QueryResult::Owned(v) => GenericResult::Owned(v),
-                QueryResult::ManyOwned(vs) => GenericResult::ManyOwned(vs),
-                QueryResult::Break(label) => GenericResult::Break(label),
-            }
+            // Convert to OwnedValue, then evaluate with the full evaluator
+            eval_on_owned::<S, V>(expr, to_owned(&value))
         }
     }
 }
@@ -821,9 +717,19 @@ fn eval_builtin<S: EvalSemantics, V: DocumentValue>(
                 }
                 GenericResult::None => GenericResult::None,
                 GenericResult::Many(_) | GenericResult::ManyOwned(_) => {
-                    // Multiple results from condition - this is unusual but treat first as condition
-                    // jq behavior: select with multiple outputs uses first truthy value
-                    GenericResult::One(value)
+                    // Like jq, emit the input once per truthy output of the condition
+                    let truthy = match &cond_result {
+                        GenericResult::Many(vs) => {
+                            vs.iter().filter(|v| is_truthy(&to_owned(*v))).count()
+                        }
+                        GenericResult::ManyOwned(vs) => vs.iter().filter(|v| is_truthy(v)).count(),
+                        _ => unreachable!("matched above"),
+                    };
+                    match truthy {
+                        0 => GenericResult::None,
+                        1 => GenericResult::One(value),
+                        n => GenericResult::Many(vec![value; n]),
+                    }
                 }
                 GenericResult::Break(label) => GenericResult::Break(label),
             }
@@ -969,8 +875,9 @@ fn eval_builtin<S: EvalSemantics, V: DocumentValue>(
                 GenericResult::Owned(OwnedValue::Float(f.abs()))
             } else {
                 GenericResult::Error(EvalError::new(format!(
-                    "{} has no length",
-                    value.type_name()
+                    "{} ({}) has no length",
+                    value.type_name(),
+                    to_owned(&value).to_json()
                 )))
             }
         }
@@ -1101,18 +1008,10 @@ fn eval_builtin<S: EvalSemantics, V: DocumentValue>(
 
         Builtin::Empty => GenericResult::None,
 
-        Builtin::ToString => {
-            let owned = to_owned(&value);
-            let s = match &owned {
-                OwnedValue::Null => "null".to_string(),
-                OwnedValue::Bool(b) => b.to_string(),
-                OwnedValue::Int(i) => i.to_string(),
-                OwnedValue::Float(f) => f.to_string(),
-                OwnedValue::String(s) => s.clone(),
-                OwnedValue::Array(_) | OwnedValue::Object(_) => owned.to_json(),
-            };
-            GenericResult::Owned(OwnedValue::String(s))
-        }
+        Builtin::ToString => match value.as_str() {
+            Some(s) => GenericResult::Owned(OwnedValue::String(s.to_string())),
+            None => GenericResult::Owned(OwnedValue::String(to_owned(&value).to_json())),
+        },
 
         Builtin::ToNumber => {
             if let Some(i) = value.as_i64() {
@@ -1120,12 +1019,11 @@ fn eval_builtin<S: EvalSemantics, V: DocumentValue>(
             } else if let Some(f) = value.as_f64() {
                 GenericResult::Owned(OwnedValue::Float(f))
             } else if let Some(s) = value.as_str() {
-                if let Ok(i) = s.parse::<i64>() {
-                    GenericResult::Owned(OwnedValue::Int(i))
-                } else if let Ok(f) = s.parse::<f64>() {
-                    GenericResult::Owned(OwnedValue::Float(f))
-                } else {
-                    GenericResult::Error(EvalError::new(format!("cannot convert '{s}' to number")))
+                match number_from_text(&s) {
+                    Some(n) => GenericResult::Owned(n),
+                    None => GenericResult::Error(EvalError::new(format!(
+                        "cannot convert '{s}' to number"
+                    ))),
                 }
             } else {
                 GenericResult::Error(EvalError::new(format!(
@@ -1973,6 +1871,23 @@ mod tests {
         assert!(result.is_error());
     }
 
+    #[test]
+    fn test_nan_survives_generic_pipes() {
+        let json = b"null";
+        let index = JsonIndex::build(json);
+        for (filter, expected) in [
+            ("nan | isnan", true),
+            ("nan < nan", true),
+            ("nan == nan", false),
+            ("1 > nan", true),
+            ("-infinite | isinfinite", true),
+        ] {
+            let expr = crate::jq::parse(filter).unwrap();
+            let result = eval_with_cursor(&expr, index.root(json)).into_owned();
+            assert_eq!(result, Some(OwnedValue::Bool(expected)), "{filter}");
+        }
+    }
+
     #[test]
     fn test_arithmetic_semantics_are_threaded() {
         // The generic evaluator delegates arithmetic to the full evaluator; the
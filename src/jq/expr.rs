@@ -147,7 +147,9 @@ pub enum Expr {
     Reduce {
         /// Input expression (what to iterate over)
         input: Box<Self>,
-        /// Variable name for each element
+        /// Variable name for each element. For a destructuring binding
+        /// (`as [$a, $b]`) this is a hidden variable and `update` starts by
+        /// destructuring it.
         var: String,
         /// Initial accumulator value
         init: Box<Self>,
@@ -357,12 +359,17 @@ pub enum MetaValue {
 }
 
 /// Import directive: `import "path" as name;` or `import "path" as name { meta };`
+///
+/// With a `$` before the alias (`import "path" as $name;`) the path names a
+/// JSON data file instead of a module, bound to `$name` and `$name::name`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Import {
-    /// The module path (relative, without .jq extension)
+    /// The module path (relative, without .jq or .json extension)
     pub path: String,
-    /// The namespace alias
+    /// The namespace alias, or the variable name of a data import
     pub alias: String,
+    /// Whether this imports JSON data (`as $name`) rather than definitions
+    pub data: bool,
     /// Optional metadata overrides
     pub metadata: Option<BTreeMap<String, MetaValue>>,
 }
@@ -508,6 +515,12 @@ pub enum Builtin {
     Any,
     /// `all` - true if all elements are truthy
     All,
+    /// `any(gen; cond)` - true if cond is truthy for some output of gen;
+    /// `any(cond)` is `any(.[]; cond)`
+    AnyOf(Box<Expr>, Box<Expr>),
+    /// `all(gen; cond)` - true if cond is truthy for every output of gen;
+    /// `all(cond)` is `all(.[]; cond)`
+    AllOf(Box<Expr>, Box<Expr>),
     /// `min` - minimum element
     Min,
     /// `max` - maximum element
@@ -586,6 +599,8 @@ pub enum Builtin {
     Explode,
     /// `implode` - array of codepoints to string
     Implode,
+    /// `ascii` / `ascii(n)` - codepoint (the input, or `n`) to a one-character string
+    Ascii(Box<Expr>),
     /// `test(re)` - test if regex matches (basic string contains for now)
     Test(Box<Expr>),
     /// `indices(s)` - array of indices where s occurs
@@ -831,7 +846,7 @@ pub enum Builtin {
 
     // Phase 16: Regex functions
     /// `test(re; flags)` - test if regex matches with flags
-    /// Flags: "i" (case insensitive), "x" (extended), "s" (single-line), "m" (multi-line), "g" (global)
+    /// Flags: "i" (case insensitive), "x" (extended), "s" (single-line), "m" (dot matches newline), "p" (both), "g" (global)
     TestFlags(Box<Expr>, Box<Expr>),
     /// `match(re)` - find first regex match, returning {offset, length, string, captures}
     Match(Box<Expr>),
@@ -913,6 +928,12 @@ pub enum Builtin {
     /// Returns the value at the specified line and column in the document.
     /// This is a succinctly-specific extension not available in standard jq.
     AtPosition(Box<Expr>, Box<Expr>),
+
+    // Phase 24: Schema validation (succinctly extension)
+    /// `schema_validates(schema)` - whether the input matches a JSON Schema
+    /// document, as checked by [`OwnedValue::schema_validate`](crate::jq::OwnedValue::schema_validate).
+    /// This is a succinctly-specific extension not available in standard jq.
+    SchemaValidates(Box<Expr>),
 }
 
 /// Arithmetic operators.
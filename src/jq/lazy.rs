@@ -270,7 +270,7 @@ impl<'a, W: Clone + AsRef<[u64]>> JqValue<'a, W> {
                 .ok()
                 .and_then(|s| s.parse().ok()),
             JqValue::Cursor(c) => match c.value() {
-                StandardJson::Number(n) => n.as_f64().ok(),
+                StandardJson::Number(n) => crate::jq::number_as_f64(&n).ok(),
                 _ => None,
             },
             _ => None,
@@ -450,10 +450,10 @@ impl<'a, W: Clone + AsRef<[u64]>> JqValue<'a, W> {
             JqValue::Bool(false) => out.write_str("false"),
             JqValue::Int(n) => write!(out, "{n}"),
             JqValue::Float(f) => {
-                if f.is_nan() || f.is_infinite() {
-                    out.write_str("null")
-                } else {
+                if f.is_finite() {
                     write!(out, "{f}")
+                } else {
+                    out.write_str(&OwnedValue::Float(*f).to_json())
                 }
             }
             JqValue::RawNumber(bytes) => {
@@ -529,7 +529,7 @@ fn cursor_to_owned<W: Clone + AsRef<[u64]>>(cursor: &JsonCursor<'_, W>) -> Owned
         StandardJson::Number(n) => {
             if let Ok(i) = n.as_i64() {
                 OwnedValue::Int(i)
-            } else if let Ok(f) = n.as_f64() {
+            } else if let Ok(f) = crate::jq::number_as_f64(&n) {
                 OwnedValue::Float(f)
             } else {
                 OwnedValue::Float(0.0)
@@ -68,26 +68,35 @@
 //! }
 //! ```
 
+mod compiled;
 pub mod document;
 mod eval;
 pub mod eval_generic;
 mod expr;
 mod lazy;
 mod parser;
+mod schema;
 pub mod stream;
 mod value;
 
+pub use compiled::{query_compiled, CompiledQuery};
+pub(crate) use eval::number_as_f64;
 pub use eval::{
-    eval, eval_lenient, substitute_vars, EvalError, EvalSemantics, JqSemantics, QueryResult,
-    YqSemantics,
+    eval, eval_lenient, eval_many, substitute_vars, EvalError, EvalIterator, EvalSemantics,
+    JqSemantics, QueryResult, YqSemantics,
 };
 pub use expr::{
     ArithOp, AssignOp, Builtin, CompareOp, Expr, FormatType, Import, Include, Literal, MetaValue,
     ModuleMeta, ObjectEntry, ObjectKey, Pattern, PatternEntry, Program, StringPart,
 };
 pub use lazy::JqValue;
+#[cfg(feature = "std")]
+pub use parser::parse_file;
 pub use parser::{
     parse, parse_program, parse_program_with_mode, parse_with_mode, ParseError, ParserMode,
 };
+pub use schema::{JsonSchema, SchemaError, SchemaErrorKind};
 pub use stream::{StreamStats, StreamableValue};
-pub use value::OwnedValue;
+#[cfg(feature = "serde")]
+pub use value::UnrepresentableNumber;
+pub use value::{ArrayMergeStrategy, OwnedValue, PathComponent};
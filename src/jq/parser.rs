@@ -74,6 +74,24 @@ impl core::fmt::Display for ParseError {
     }
 }
 
+/// Hidden variable that carries each element of a `reduce`/`foreach` whose
+/// binding is a destructuring pattern (dunder-named like `$__loc__` to stay
+/// clear of user variables).
+const LOOP_PATTERN_VAR: &str = "__loop_pattern__";
+
+/// Wrap a `reduce`/`foreach` clause so it destructures the element first:
+/// `$__loop_pattern__ as PATTERN | clause`.
+fn bind_loop_pattern(var: &str, pattern: Option<Pattern>, clause: Expr) -> Expr {
+    match pattern {
+        Some(pattern) => Expr::AsPattern {
+            expr: Box::new(Expr::Var(var.to_string())),
+            pattern,
+            body: Box::new(clause),
+        },
+        None => clause,
+    }
+}
+
 /// Parser state.
 struct Parser<'a> {
     input: &'a str,
@@ -896,7 +914,12 @@ impl<'a> Parser<'a> {
             Some('$') => {
                 let line = self.current_line();
                 self.next();
-                let name = self.parse_ident()?;
+                let mut name = self.parse_ident()?;
+                // Data import binding: `$name::name`
+                if self.peek_str(2) == "::" {
+                    self.pos += 2;
+                    name = format!("{name}::{}", self.parse_ident()?);
+                }
                 let expr = if name == "__loc__" {
                     Expr::Loc { line }
                 } else if name == "ENV" {
@@ -926,7 +949,8 @@ impl<'a> Parser<'a> {
                 } else if self.matches_keyword("try") {
                     self.parse_try_expr()
                 } else if self.matches_keyword("error") {
-                    self.parse_error_expr()
+                    let error = self.parse_error_expr()?;
+                    self.parse_postfix(error)
                 } else if self.matches_keyword("reduce") {
                     self.parse_reduce_expr()
                 } else if self.matches_keyword("foreach") {
@@ -957,7 +981,8 @@ impl<'a> Parser<'a> {
                     self.parse_postfix(Expr::Builtin(builtin))
                 } else {
                     // Phase 9: Try to parse as function call
-                    self.parse_func_call_or_error()
+                    let call = self.parse_func_call_or_error()?;
+                    self.parse_postfix(call)
                 }
             }
 
@@ -987,7 +1012,7 @@ impl<'a> Parser<'a> {
         self.skip_ws();
 
         // Parse then branch
-        let then_branch = self.parse_pipe_expr()?;
+        let then_branch = self.parse_expr()?;
         self.skip_ws();
 
         // Parse elif/else/end
@@ -1016,7 +1041,7 @@ impl<'a> Parser<'a> {
             self.consume_keyword("then");
             self.skip_ws();
 
-            let then_branch = self.parse_pipe_expr()?;
+            let then_branch = self.parse_expr()?;
             self.skip_ws();
 
             let else_branch = self.parse_else_branch()?;
@@ -1030,7 +1055,7 @@ impl<'a> Parser<'a> {
             self.consume_keyword("else");
             self.skip_ws();
 
-            let else_branch = self.parse_pipe_expr()?;
+            let else_branch = self.parse_expr()?;
             self.skip_ws();
 
             if !self.matches_keyword("end") {
@@ -1116,30 +1141,45 @@ impl<'a> Parser<'a> {
         self.consume_keyword("as");
         self.skip_ws();
 
-        // Parse variable name (with $)
-        self.expect('$')?;
-        let var = self.parse_ident()?;
+        // Parse `$var` or a destructuring pattern
+        let (var, pattern) = self.parse_loop_binding()?;
         self.skip_ws();
 
         // Parse (init; update)
         self.expect('(')?;
         self.skip_ws();
-        let init = self.parse_pipe_expr()?;
+        let init = self.parse_expr()?;
         self.skip_ws();
         self.expect(';')?;
         self.skip_ws();
-        let update = self.parse_pipe_expr()?;
+        let update = self.parse_expr()?;
         self.skip_ws();
         self.expect(')')?;
 
         Ok(Expr::Reduce {
             input: Box::new(input),
-            var,
             init: Box::new(init),
-            update: Box::new(update),
+            update: Box::new(bind_loop_pattern(&var, pattern, update)),
+            var,
         })
     }
 
+    /// Parse the binding after `as` in `reduce`/`foreach`: `$var` or a
+    /// destructuring pattern such as `[$a, $b]` or `{name: $n}`.
+    ///
+    /// A pattern is bound to the hidden variable [`LOOP_PATTERN_VAR`] and
+    /// returned for [`bind_loop_pattern`] to destructure inside each clause.
+    fn parse_loop_binding(&mut self) -> Result<(String, Option<Pattern>), ParseError> {
+        if self.peek() == Some('$') {
+            self.next();
+            return Ok((self.parse_ident()?, None));
+        }
+        match self.parse_pattern()? {
+            Pattern::Var(var) => Ok((var, None)),
+            pattern => Ok((LOOP_PATTERN_VAR.to_string(), Some(pattern))),
+        }
+    }
+
     /// Parse a foreach expression.
     /// Syntax: foreach EXPR as $VAR (INIT; UPDATE) or foreach EXPR as $VAR (INIT; UPDATE; EXTRACT)
     fn parse_foreach_expr(&mut self) -> Result<Expr, ParseError> {
@@ -1157,26 +1197,26 @@ impl<'a> Parser<'a> {
         self.consume_keyword("as");
         self.skip_ws();
 
-        // Parse variable name (with $)
-        self.expect('$')?;
-        let var = self.parse_ident()?;
+        // Parse `$var` or a destructuring pattern
+        let (var, pattern) = self.parse_loop_binding()?;
         self.skip_ws();
 
         // Parse (init; update[; extract])
         self.expect('(')?;
         self.skip_ws();
-        let init = self.parse_pipe_expr()?;
+        let init = self.parse_expr()?;
         self.skip_ws();
         self.expect(';')?;
         self.skip_ws();
-        let update = self.parse_pipe_expr()?;
+        let update = self.parse_expr()?;
         self.skip_ws();
 
         // Optional extract expression
         let extract = if self.peek() == Some(';') {
             self.next();
             self.skip_ws();
-            Some(Box::new(self.parse_pipe_expr()?))
+            let extract = self.parse_expr()?;
+            Some(Box::new(bind_loop_pattern(&var, pattern.clone(), extract)))
         } else {
             None
         };
@@ -1185,9 +1225,9 @@ impl<'a> Parser<'a> {
 
         Ok(Expr::Foreach {
             input: Box::new(input),
-            var,
             init: Box::new(init),
-            update: Box::new(update),
+            update: Box::new(bind_loop_pattern(&var, pattern, update)),
+            var,
             extract,
         })
     }
@@ -1199,11 +1239,11 @@ impl<'a> Parser<'a> {
         self.skip_ws();
         self.expect('(')?;
         self.skip_ws();
-        let n = self.parse_pipe_expr()?;
+        let n = self.parse_expr()?;
         self.skip_ws();
         self.expect(';')?;
         self.skip_ws();
-        let expr = self.parse_pipe_expr()?;
+        let expr = self.parse_expr()?;
         self.skip_ws();
         self.expect(')')?;
 
@@ -1277,7 +1317,7 @@ impl<'a> Parser<'a> {
         if self.peek() == Some('(') {
             self.next();
             self.skip_ws();
-            let expr = self.parse_pipe_expr()?;
+            let expr = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             Ok(Expr::FirstExpr(Box::new(expr)))
@@ -1294,7 +1334,7 @@ impl<'a> Parser<'a> {
         if self.peek() == Some('(') {
             self.next();
             self.skip_ws();
-            let expr = self.parse_pipe_expr()?;
+            let expr = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             Ok(Expr::LastExpr(Box::new(expr)))
@@ -1515,64 +1555,11 @@ impl<'a> Parser<'a> {
     /// Parse a function definition.
     /// Syntax: def NAME: BODY; or def NAME(PARAMS): BODY;
     fn parse_def_expr(&mut self) -> Result<Expr, ParseError> {
-        self.consume_keyword("def");
-        self.skip_ws();
-
-        // Parse function name
-        let name = self.parse_ident()?;
-        self.skip_ws();
-
-        // Parse optional parameters
-        let params = if self.peek() == Some('(') {
-            self.next();
-            self.skip_ws();
-            let mut params = Vec::new();
-
-            if self.peek() != Some(')') {
-                loop {
-                    // Parameters can be $var or just var
-                    if self.peek() == Some('$') {
-                        self.next();
-                    }
-                    let param = self.parse_ident()?;
-                    params.push(param);
-                    self.skip_ws();
-
-                    match self.peek() {
-                        Some(';' | ',') => {
-                            self.next();
-                            self.skip_ws();
-                        }
-                        Some(')') => break,
-                        _ => {
-                            return Err(ParseError::new(
-                                "expected ';', ',', or ')' in parameter list",
-                                self.pos,
-                            ));
-                        }
-                    }
-                }
-            }
-            self.expect(')')?;
-            params
-        } else {
-            Vec::new()
-        };
-
-        self.skip_ws();
-        self.expect(':')?;
-        self.skip_ws();
-
-        // Parse function body
-        let body = self.parse_pipe_expr()?;
-        self.skip_ws();
-
-        // Expect semicolon
-        self.expect(';')?;
+        let (name, params, body) = self.parse_func_def_parts()?;
         self.skip_ws();
 
         // Parse the rest of the expression where this function is in scope
-        let then = self.parse_pipe_expr()?;
+        let then = self.parse_expr()?;
 
         Ok(Expr::FuncDef {
             name,
@@ -1695,6 +1682,26 @@ impl<'a> Parser<'a> {
             }
         };
 
+        // `@fmt "...\(e)..."` applies the format to each interpolated value
+        self.skip_ws();
+        if self.peek() == Some('"') {
+            let format = Expr::Format(format_type);
+            return Ok(match self.parse_string_or_interpolation()? {
+                Expr::StringInterpolation(parts) => Expr::StringInterpolation(
+                    parts
+                        .into_iter()
+                        .map(|part| match part {
+                            StringPart::Expr(e) => {
+                                StringPart::Expr(Box::new(Expr::Pipe(vec![*e, format.clone()])))
+                            }
+                            literal => literal,
+                        })
+                        .collect(),
+                ),
+                literal => literal,
+            });
+        }
+
         Ok(Expr::Format(format_type))
     }
 
@@ -1822,7 +1829,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let cond = self.parse_pipe_expr()?;
+            let cond = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Select(Box::new(cond))));
@@ -1833,13 +1840,25 @@ impl<'a> Parser<'a> {
         }
 
         // Map functions
+        // map_select(f) - succinctly extension, shorthand for map(select(f))
+        if self.matches_keyword("map_select") {
+            self.consume_keyword("map_select");
+            self.skip_ws();
+            self.expect('(')?;
+            self.skip_ws();
+            let cond = self.parse_expr()?;
+            self.skip_ws();
+            self.expect(')')?;
+            let select = Expr::Builtin(Builtin::Select(Box::new(cond)));
+            return Ok(Some(Builtin::Map(Box::new(select))));
+        }
         if self.matches_keyword("map_values") {
             // Check map_values before map
             self.consume_keyword("map_values");
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::MapValues(Box::new(f))));
@@ -1849,7 +1868,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Map(Box::new(f))));
@@ -1861,13 +1880,36 @@ impl<'a> Parser<'a> {
             self.consume_keyword("add");
             return Ok(Some(Builtin::Add));
         }
-        if self.matches_keyword("any") && !self.peek_after_keyword_is_paren("any") {
-            self.consume_keyword("any");
-            return Ok(Some(Builtin::Any));
-        }
-        if self.matches_keyword("all") && !self.peek_after_keyword_is_paren("all") {
-            self.consume_keyword("all");
-            return Ok(Some(Builtin::All));
+        // any, any(cond), any(gen; cond) and the same for all
+        for (name, all) in [("any", false), ("all", true)] {
+            if !self.matches_keyword(name) {
+                continue;
+            }
+            self.consume_keyword(name);
+            self.skip_ws();
+            if self.peek() != Some('(') {
+                return Ok(Some(if all { Builtin::All } else { Builtin::Any }));
+            }
+            // any(cond) is any(.[]; cond)
+            self.expect('(')?;
+            self.skip_ws();
+            let mut generator = Expr::Iterate;
+            let mut cond = self.parse_expr()?;
+            self.skip_ws();
+            if self.peek() == Some(';') {
+                self.expect(';')?;
+                self.skip_ws();
+                generator = cond;
+                cond = self.parse_expr()?;
+                self.skip_ws();
+            }
+            self.expect(')')?;
+            let (generator, cond) = (Box::new(generator), Box::new(cond));
+            return Ok(Some(if all {
+                Builtin::AllOf(generator, cond)
+            } else {
+                Builtin::AnyOf(generator, cond)
+            }));
         }
         if self.matches_keyword("min_by") {
             // Check min_by before min
@@ -1875,7 +1917,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::MinBy(Box::new(f))));
@@ -1890,7 +1932,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::MaxBy(Box::new(f))));
@@ -1927,7 +1969,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Ltrimstr(Box::new(s))));
@@ -1937,7 +1979,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Rtrimstr(Box::new(s))));
@@ -1947,7 +1989,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Startswith(Box::new(s))));
@@ -1957,7 +1999,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Endswith(Box::new(s))));
@@ -1986,7 +2028,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             if self.peek() == Some(';') {
                 self.next(); // consume ';'
@@ -2005,12 +2047,12 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let re = self.parse_pipe_expr()?;
+            let re = self.parse_expr()?;
             self.skip_ws();
             if self.peek() == Some(';') {
                 self.next(); // consume ';'
                 self.skip_ws();
-                let flags = self.parse_pipe_expr()?;
+                let flags = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::MatchFlags(Box::new(re), Box::new(flags))));
@@ -2024,12 +2066,12 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let re = self.parse_pipe_expr()?;
+            let re = self.parse_expr()?;
             self.skip_ws();
             if self.peek() == Some(';') {
                 self.next(); // consume ';'
                 self.skip_ws();
-                let flags = self.parse_pipe_expr()?;
+                let flags = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::CaptureFlags(Box::new(re), Box::new(flags))));
@@ -2115,7 +2157,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Join(Box::new(s))));
@@ -2125,7 +2167,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let b = self.parse_pipe_expr()?;
+            let b = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Contains(Box::new(b))));
@@ -2135,7 +2177,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let b = self.parse_pipe_expr()?;
+            let b = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Inside(Box::new(b))));
@@ -2168,7 +2210,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::GroupBy(Box::new(f))));
@@ -2179,7 +2221,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::UniqueBy(Box::new(f))));
@@ -2194,7 +2236,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::SortBy(Box::new(f))));
@@ -2251,17 +2293,30 @@ impl<'a> Parser<'a> {
             self.consume_keyword("implode");
             return Ok(Some(Builtin::Implode));
         }
+        if self.matches_keyword("ascii") {
+            self.consume_keyword("ascii");
+            self.skip_ws();
+            if self.peek() == Some('(') {
+                self.next();
+                self.skip_ws();
+                let n = self.parse_expr()?;
+                self.skip_ws();
+                self.expect(')')?;
+                return Ok(Some(Builtin::Ascii(Box::new(n))));
+            }
+            return Ok(Some(Builtin::Ascii(Box::new(Expr::Identity))));
+        }
         if self.matches_keyword("test") {
             self.consume_keyword("test");
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let re = self.parse_pipe_expr()?;
+            let re = self.parse_expr()?;
             self.skip_ws();
             if self.peek() == Some(';') {
                 self.next(); // consume ';'
                 self.skip_ws();
-                let flags = self.parse_pipe_expr()?;
+                let flags = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::TestFlags(Box::new(re), Box::new(flags))));
@@ -2274,7 +2329,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Indices(Box::new(s))));
@@ -2285,7 +2340,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Rindex(Box::new(s))));
@@ -2295,7 +2350,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let s = self.parse_pipe_expr()?;
+            let s = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Index(Box::new(s))));
@@ -2349,7 +2404,7 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let f = self.parse_pipe_expr()?;
+            let f = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Walk(Box::new(f))));
@@ -2377,7 +2432,7 @@ impl<'a> Parser<'a> {
                 // path(expr) - jq style
                 self.next();
                 self.skip_ws();
-                let expr = self.parse_pipe_expr()?;
+                let expr = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::Path(Box::new(expr))));
@@ -2414,7 +2469,7 @@ impl<'a> Parser<'a> {
             if self.peek() == Some('(') {
                 self.next();
                 self.skip_ws();
-                let filter = self.parse_pipe_expr()?;
+                let filter = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::PathsFilter(Box::new(filter))));
@@ -2818,11 +2873,11 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let n = self.parse_pipe_expr()?;
+            let n = self.parse_expr()?;
             self.skip_ws();
             self.expect(';')?;
             self.skip_ws();
-            let expr = self.parse_pipe_expr()?;
+            let expr = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Limit(Box::new(n), Box::new(expr))));
@@ -2834,11 +2889,11 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let n = self.parse_pipe_expr()?;
+            let n = self.parse_expr()?;
             self.skip_ws();
             self.expect(';')?;
             self.skip_ws();
-            let expr = self.parse_pipe_expr()?;
+            let expr = self.parse_expr()?;
             self.skip_ws();
             self.expect(')')?;
             return Ok(Some(Builtin::Skip(Box::new(n), Box::new(expr))));
@@ -2853,7 +2908,7 @@ impl<'a> Parser<'a> {
             if self.peek() == Some('(') {
                 self.next();
                 self.skip_ws();
-                let expr = self.parse_pipe_expr()?;
+                let expr = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::FirstStream(Box::new(expr))));
@@ -2870,7 +2925,7 @@ impl<'a> Parser<'a> {
             if self.peek() == Some('(') {
                 self.next();
                 self.skip_ws();
-                let expr = self.parse_pipe_expr()?;
+                let expr = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::LastStream(Box::new(expr))));
@@ -2886,12 +2941,12 @@ impl<'a> Parser<'a> {
             self.skip_ws();
             self.expect('(')?;
             self.skip_ws();
-            let n = self.parse_pipe_expr()?;
+            let n = self.parse_expr()?;
             self.skip_ws();
             if self.peek() == Some(';') {
                 self.next();
                 self.skip_ws();
-                let expr = self.parse_pipe_expr()?;
+                let expr = self.parse_expr()?;
                 self.skip_ws();
                 self.expect(')')?;
                 return Ok(Some(Builtin::NthStream(Box::new(n), Box::new(expr))));
@@ -3064,6 +3119,19 @@ impl<'a> Parser<'a> {
             )));
         }
 
+        // Phase 24: Schema validation (succinctly extension)
+        // schema_validates(schema) - whether the input matches the schema
+        if self.matches_keyword("schema_validates") {
+            self.consume_keyword("schema_validates");
+            self.skip_ws();
+            self.expect('(')?;
+            self.skip_ws();
+            let schema_expr = self.parse_expr()?;
+            self.skip_ws();
+            self.expect(')')?;
+            return Ok(Some(Builtin::SchemaValidates(Box::new(schema_expr))));
+        }
+
         Ok(None)
     }
 
@@ -3140,6 +3208,19 @@ impl<'a> Parser<'a> {
                 Some('[') => {
                     chain.push(self.parse_index_bracket_with_optional()?);
                 }
+                // `term?` is shorthand for `try term` (but `?//` is destructuring)
+                Some('?') if self.peek_str(3) != "?//" => {
+                    self.next();
+                    let term = if chain.len() == 1 {
+                        chain.pop().unwrap()
+                    } else {
+                        Expr::Pipe(core::mem::take(&mut chain))
+                    };
+                    chain.push(Expr::Try {
+                        expr: Box::new(term),
+                        catch: None,
+                    });
+                }
                 _ => break,
             }
         }
@@ -3629,7 +3710,7 @@ impl<'a> Parser<'a> {
         self.skip_ws();
 
         // Parse function body
-        let body = self.parse_pipe_expr()?;
+        let body = self.parse_expr()?;
         self.skip_ws();
 
         // Expect semicolon
@@ -3677,13 +3758,12 @@ impl<'a> Parser<'a> {
         self.consume_keyword("as");
         self.skip_ws();
 
-        // Parse the alias (optionally prefixed with $)
-        let alias = if self.peek() == Some('$') {
+        // Parse the alias: `$name` imports data, a bare name definitions
+        let data = self.peek() == Some('$');
+        if data {
             self.next();
-            self.parse_ident()?
-        } else {
-            self.parse_ident()?
-        };
+        }
+        let alias = self.parse_ident()?;
 
         self.skip_ws();
 
@@ -3706,6 +3786,7 @@ impl<'a> Parser<'a> {
         Ok(Import {
             path,
             alias,
+            data,
             metadata,
         })
     }
@@ -3935,6 +4016,7 @@ pub fn parse_with_mode(input: &str, mode: ParserMode) -> Result<Expr, ParseError
 /// A jq program can optionally start with module directives:
 /// - `module { metadata };` - module metadata declaration
 /// - `import "path" as name;` - import a module with a namespace
+/// - `import "path" as $name;` - import a JSON data file as `$name`
 /// - `include "path";` - include a module's definitions directly
 ///
 /// Followed by the main expression (with optional function definitions).
@@ -3978,6 +4060,20 @@ pub fn parse_program_with_mode(input: &str, mode: ParserMode) -> Result<Program,
     Ok(program)
 }
 
+/// Read and parse a jq program file, such as one passed to `jq -f`.
+///
+/// The file may hold module directives and any number of `def`s before the
+/// main expression, as with [`parse_program`]. Imports are recorded, not
+/// loaded: resolving them against a search path is up to the caller.
+///
+/// A file that cannot be read is reported as a `ParseError` at position 0.
+#[cfg(feature = "std")]
+pub fn parse_file(path: &std::path::Path) -> Result<Program, ParseError> {
+    let input = std::fs::read_to_string(path)
+        .map_err(|error| ParseError::new(format!("cannot read {}: {error}", path.display()), 0))?;
+    parse_program(&input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4555,6 +4651,12 @@ mod tests {
         // try with complex expression
         let expr = parse("try .missing? catch null").unwrap();
         assert!(matches!(expr, Expr::Try { .. }));
+
+        // Postfix `?` on any term
+        let expr = parse("(1 / .)?").unwrap();
+        assert!(matches!(expr, Expr::Try { catch: None, .. }));
+        let expr = parse("tostring?").unwrap();
+        assert!(matches!(expr, Expr::Try { catch: None, .. }));
     }
 
     #[test]
@@ -4862,4 +4964,37 @@ mod tests {
         assert!(parse("1e+5").is_ok());
         assert!(parse("1e-5").is_ok());
     }
+
+    #[test]
+    fn test_parse_program_data_import() {
+        let prog =
+            parse_program(r#"import "lib" as lib; import "conf" as $conf; $conf::conf"#).unwrap();
+        assert_eq!(prog.imports.len(), 2);
+        assert_eq!(prog.imports[0].alias, "lib");
+        assert!(!prog.imports[0].data);
+        assert_eq!(prog.imports[1].path, "conf");
+        assert_eq!(prog.imports[1].alias, "conf");
+        assert!(prog.imports[1].data);
+        assert_eq!(prog.expr, Expr::Var("conf::conf".into()));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            b"# Helpers\ndef sq: . * .;\ndef sumsq: map(sq) | add;\n\nsumsq\n",
+        )
+        .unwrap();
+        let prog = parse_file(file.path()).unwrap();
+        assert_eq!(
+            prog.expr,
+            parse("def sq: . * .; def sumsq: map(sq) | add; sumsq").unwrap()
+        );
+
+        let missing = file.path().with_extension("missing");
+        let error = parse_file(&missing).unwrap_err();
+        assert_eq!(error.position, 0);
+        assert!(error.message.starts_with("cannot read"));
+    }
 }
@@ -0,0 +1,1211 @@
+//! Inferring a simplified JSON Schema from example values, and checking
+//! values against one.
+//!
+//! [`OwnedValue::type_schema`] describes the shape of one value, and
+//! [`JsonSchema::merge`] combines descriptions, so the schema of a set of
+//! example payloads is the merge of their schemas. Array elements are merged
+//! the same way. [`JsonSchema::to_json`] writes the result as a draft 7
+//! schema, and [`JsonSchema::from_value`] reads the subset of draft 7 that
+//! [`OwnedValue::schema_validate`] checks.
+
+#[cfg(not(test))]
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(test)]
+use std::collections::BTreeMap;
+
+use core::fmt;
+
+use indexmap::IndexMap;
+
+use super::value::{OwnedValue, PathComponent};
+
+/// The URI of the JSON Schema dialect written by [`JsonSchema::to_json`].
+const DRAFT_7: &str = "http://json-schema.org/draft-07/schema#";
+
+/// A simplified JSON Schema: the kinds of value allowed at each place.
+///
+/// Integers and floats are both [`Number`](Self::Number), which a schema
+/// read with `type` `integer` limits to whole numbers. Schemas inferred
+/// by [`OwnedValue::type_schema`] carry no bounds, list properties without
+/// saying which are required, and allow any other property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonSchema {
+    /// Any value
+    Any,
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool,
+    /// A number within the inclusive bounds
+    Number {
+        /// `minimum`
+        minimum: Option<f64>,
+        /// `maximum`
+        maximum: Option<f64>,
+        /// `type` `integer`: only numbers without a fractional part
+        integer: bool,
+    },
+    /// A string whose length in characters is within the bounds
+    String {
+        /// `minLength`
+        min_length: Option<usize>,
+        /// `maxLength`
+        max_length: Option<usize>,
+    },
+    /// An array whose elements all match the item schema
+    Array(Box<Self>),
+    /// An object whose listed properties match their schemas
+    Object {
+        /// `properties`
+        properties: BTreeMap<String, Self>,
+        /// `required`: properties that must be present
+        required: Vec<String>,
+        /// `additionalProperties`: whether properties not listed are allowed
+        additional_properties: bool,
+    },
+    /// Any of these schemas. As built by [`merge`](Self::merge), members
+    /// have distinct kinds, in the order of the variants above, and the
+    /// empty union is the item schema of an empty array.
+    Union(Vec<Self>),
+}
+
+/// A value that does not match a [`JsonSchema`], or a schema document that
+/// [`JsonSchema::from_value`] cannot read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// Path from the root to the offending value: into the checked value
+    /// for a violation, into the schema document for
+    /// [`InvalidSchema`](SchemaErrorKind::InvalidSchema).
+    pub path: Vec<PathComponent>,
+    /// What is wrong there.
+    pub kind: SchemaErrorKind,
+}
+
+/// The kinds of [`SchemaError`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaErrorKind {
+    /// The value is not of an allowed kind.
+    Type {
+        /// The allowed `type` names
+        expected: Vec<&'static str>,
+        /// The value's `type` name
+        found: &'static str,
+    },
+    /// A required property is missing from the object.
+    MissingProperty(String),
+    /// The object has a property its schema does not list, and
+    /// `additionalProperties` is false. The path ends at the property.
+    AdditionalProperty,
+    /// The string is shorter than `minLength`.
+    TooShort {
+        /// `minLength`
+        min_length: usize,
+        /// The string's length in characters
+        length: usize,
+    },
+    /// The string is longer than `maxLength`.
+    TooLong {
+        /// `maxLength`
+        max_length: usize,
+        /// The string's length in characters
+        length: usize,
+    },
+    /// The number is below `minimum`.
+    BelowMinimum {
+        /// `minimum`
+        minimum: f64,
+        /// The number
+        value: f64,
+    },
+    /// The number is above `maximum`.
+    AboveMaximum {
+        /// `maximum`
+        maximum: f64,
+        /// The number
+        value: f64,
+    },
+    /// The number has a fractional part and the schema's `type` is
+    /// `integer`.
+    NotInteger(f64),
+    /// The schema document uses a keyword wrongly or one that is not
+    /// supported.
+    InvalidSchema(String),
+}
+
+impl fmt::Display for SchemaErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Type { expected, found } if expected.is_empty() => {
+                write!(f, "no value is allowed, found {found}")
+            }
+            Self::Type { expected, found } => {
+                write!(f, "expected {}, found {found}", expected.join(" or "))
+            }
+            Self::MissingProperty(key) => write!(f, "missing required property {key:?}"),
+            Self::AdditionalProperty => write!(f, "property not allowed by the schema"),
+            Self::TooShort { min_length, length } => {
+                write!(f, "string of length {length} is shorter than {min_length}")
+            }
+            Self::TooLong { max_length, length } => {
+                write!(f, "string of length {length} is longer than {max_length}")
+            }
+            Self::BelowMinimum { minimum, value } => {
+                write!(f, "{value} is below the minimum {minimum}")
+            }
+            Self::AboveMaximum { maximum, value } => {
+                write!(f, "{value} is above the maximum {maximum}")
+            }
+            Self::NotInteger(value) => write!(f, "{value} is not an integer"),
+            Self::InvalidSchema(message) => write!(f, "invalid schema: {message}"),
+        }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    /// The path in jq syntax, such as `.items[2].name`, then the kind.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = PathComponent::display_path(&self.path);
+        write!(f, "{path}: {}", self.kind)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaError {}
+
+impl SchemaError {
+    fn new(kind: SchemaErrorKind) -> Self {
+        Self {
+            path: Vec::new(),
+            kind,
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(SchemaErrorKind::InvalidSchema(message.into()))
+    }
+
+    /// Prefix the path with `component`. Paths are built in reverse while
+    /// an error propagates, then put in order by [`finish`](Self::finish).
+    fn within(mut self, component: PathComponent) -> Self {
+        self.path.push(component);
+        self
+    }
+
+    fn finish(mut self) -> Self {
+        self.path.reverse();
+        self
+    }
+}
+
+impl JsonSchema {
+    /// The schema of values matching either `self` or `other`.
+    ///
+    /// Schemas of the same kind combine into one: arrays merge their item
+    /// schemas and objects merge their properties, a property found in
+    /// only one of them keeping its schema. Bounds widen, a property stays
+    /// required only if both require it, and additional properties are
+    /// allowed if either allows them. Different kinds become a
+    /// [`Union`](Self::Union).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::jq::{JsonSchema, OwnedValue};
+    ///
+    /// let a = OwnedValue::object_from([("id".into(), OwnedValue::Int(1))]);
+    /// let b = OwnedValue::object_from([("id".into(), OwnedValue::string("x"))]);
+    /// let schema = a.type_schema().merge(b.type_schema());
+    /// assert_eq!(
+    ///     schema.to_json(),
+    ///     r#"{"$schema":"http://json-schema.org/draft-07/schema#","type":"object","properties":{"id":{"type":["number","string"]}}}"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        if matches!(self, Self::Any) || matches!(other, Self::Any) {
+            return Self::Any;
+        }
+        let mut members: Vec<Self> = Vec::new();
+        for schema in self.into_members().into_iter().chain(other.into_members()) {
+            if matches!(schema, Self::Any) {
+                return Self::Any;
+            }
+            match members.iter().position(|m| m.rank() == schema.rank()) {
+                Some(i) => {
+                    let existing = members.remove(i);
+                    members.insert(i, existing.combine(schema));
+                }
+                None => {
+                    let at = members.partition_point(|m| m.rank() < schema.rank());
+                    members.insert(at, schema);
+                }
+            }
+        }
+        if members.len() == 1 {
+            members.pop().expect("one member")
+        } else {
+            Self::Union(members)
+        }
+    }
+
+    /// Read a schema from a JSON Schema document.
+    ///
+    /// The keywords read are `type` (a name or a list; `integer` allows
+    /// only whole numbers unless `number` is also listed), `properties`, `required`, `additionalProperties` (a
+    /// boolean), `items`, `minLength`, `maxLength`, `minimum`, `maximum`
+    /// and `anyOf`, plus `maxItems` 0 and `not` `{}` as
+    /// [`to_json`](Self::to_json) writes them. Annotations and other
+    /// keywords that do not constrain values, such as `$schema`,
+    /// `description` and `format`, are ignored. Without `type`, any kind
+    /// of value is allowed and each keyword constrains only values of its
+    /// kind. The documents `true` and `false` allow every and no value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SchemaErrorKind::InvalidSchema`], with the path into
+    /// `schema`, for a keyword with a value of the wrong type, an unknown
+    /// `type` name, a use of `additionalProperties`, `maxItems` or `not`
+    /// beyond the forms above, or a validation keyword that is not read,
+    /// such as `enum`, `pattern` or `allOf`, rather than allowing values
+    /// it would reject.
+    pub fn from_value(schema: &OwnedValue) -> Result<Self, SchemaError> {
+        Self::read(schema).map_err(SchemaError::finish)
+    }
+
+    fn read(schema: &OwnedValue) -> Result<Self, SchemaError> {
+        let keywords = match schema {
+            OwnedValue::Bool(true) => return Ok(Self::Any),
+            OwnedValue::Bool(false) => return Ok(Self::Union(Vec::new())),
+            OwnedValue::Object(keywords) => keywords,
+            other => {
+                return Err(SchemaError::invalid(format!(
+                    "a schema must be an object or a boolean, not {}",
+                    other.type_name()
+                )))
+            }
+        };
+        let keyword = |name: &str| keywords.get(name);
+        if let Some(name) = UNSUPPORTED_KEYWORDS
+            .iter()
+            .find(|name| keywords.contains_key(**name))
+        {
+            return Err(SchemaError::invalid(format!("{name} is not supported")).within(key(name)));
+        }
+
+        if let Some(not) = keyword("not") {
+            return match Self::read(not).map_err(|e| e.within(key("not")))? {
+                Self::Any => Ok(Self::Union(Vec::new())),
+                _ => Err(SchemaError::invalid("only {} is supported in not").within(key("not"))),
+            };
+        }
+        if let Some(any_of) = keyword("anyOf") {
+            let OwnedValue::Array(members) = any_of else {
+                return Err(SchemaError::invalid("anyOf must be an array").within(key("anyOf")));
+            };
+            return members
+                .iter()
+                .enumerate()
+                .map(|(i, member)| {
+                    Self::read(member)
+                        .map_err(|e| e.within(PathComponent::Index(i)).within(key("anyOf")))
+                })
+                .collect::<Result<_, _>>()
+                .map(Self::Union);
+        }
+
+        let kinds = match keyword("type") {
+            None => None,
+            Some(OwnedValue::String(name)) => Some(vec![kind_rank(name).ok_or_else(|| {
+                SchemaError::invalid(format!("unknown type {name:?}")).within(key("type"))
+            })?]),
+            Some(OwnedValue::Array(names)) => Some(
+                names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        name.as_str().and_then(kind_rank).ok_or_else(|| {
+                            SchemaError::invalid(format!("unknown type {}", name.to_json()))
+                                .within(PathComponent::Index(i))
+                                .within(key("type"))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Some(_) => {
+                return Err(
+                    SchemaError::invalid("type must be a string or an array").within(key("type"))
+                )
+            }
+        };
+
+        // `integer` narrows `number` only when `number` is not also allowed
+        let names_type = |name: &str| match keyword("type") {
+            Some(OwnedValue::String(names)) => names == name,
+            Some(OwnedValue::Array(names)) => names.iter().any(|n| n.as_str() == Some(name)),
+            _ => false,
+        };
+        let integer = names_type("integer") && !names_type("number");
+        let minimum = number_keyword(keywords, "minimum")?;
+        let maximum = number_keyword(keywords, "maximum")?;
+        let min_length = length_keyword(keywords, "minLength")?;
+        let max_length = length_keyword(keywords, "maxLength")?;
+        let items = match (keyword("items"), length_keyword(keywords, "maxItems")?) {
+            (_, Some(0)) => Some(Self::Union(Vec::new())),
+            (_, Some(_)) => {
+                return Err(
+                    SchemaError::invalid("only 0 is supported in maxItems").within(key("maxItems"))
+                )
+            }
+            (Some(items), None) => Some(Self::read(items).map_err(|e| e.within(key("items")))?),
+            (None, None) => None,
+        };
+        let properties = match keyword("properties") {
+            None => None,
+            Some(OwnedValue::Object(properties)) => Some(
+                properties
+                    .iter()
+                    .map(|(name, schema)| {
+                        Self::read(schema)
+                            .map(|schema| (name.clone(), schema))
+                            .map_err(|e| e.within(key(name)).within(key("properties")))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, _>>()?,
+            ),
+            Some(_) => {
+                return Err(
+                    SchemaError::invalid("properties must be an object").within(key("properties"))
+                )
+            }
+        };
+        let required = match keyword("required") {
+            None => None,
+            Some(OwnedValue::Array(names)) => Some(
+                names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        name.as_str().map(ToString::to_string).ok_or_else(|| {
+                            SchemaError::invalid("required must list strings")
+                                .within(PathComponent::Index(i))
+                                .within(key("required"))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Some(_) => {
+                return Err(
+                    SchemaError::invalid("required must be an array").within(key("required"))
+                )
+            }
+        };
+        let additional_properties = match keyword("additionalProperties") {
+            None => None,
+            Some(OwnedValue::Bool(allowed)) => Some(*allowed),
+            Some(_) => {
+                return Err(SchemaError::invalid(
+                    "only a boolean is supported in additionalProperties",
+                )
+                .within(key("additionalProperties")))
+            }
+        };
+
+        let constrained = minimum.is_some()
+            || maximum.is_some()
+            || min_length.is_some()
+            || max_length.is_some()
+            || items.is_some()
+            || properties.is_some()
+            || required.is_some()
+            || additional_properties.is_some();
+        let mut kinds = match kinds {
+            Some(kinds) => kinds,
+            None if constrained => (1..=6).collect(),
+            None => return Ok(Self::Any),
+        };
+        kinds.sort_unstable();
+        kinds.dedup();
+        let mut members: Vec<Self> = kinds
+            .into_iter()
+            .map(|rank| match rank {
+                1 => Self::Null,
+                2 => Self::Bool,
+                3 => Self::Number {
+                    minimum,
+                    maximum,
+                    integer,
+                },
+                4 => Self::String {
+                    min_length,
+                    max_length,
+                },
+                5 => Self::Array(Box::new(items.clone().unwrap_or(Self::Any))),
+                _ => Self::Object {
+                    properties: properties.clone().unwrap_or_default(),
+                    required: required.clone().unwrap_or_default(),
+                    additional_properties: additional_properties.unwrap_or(true),
+                },
+            })
+            .collect();
+        Ok(if members.len() == 1 {
+            members.pop().expect("one member")
+        } else {
+            Self::Union(members)
+        })
+    }
+
+    /// Check `value` against the schema.
+    fn check(&self, value: &OwnedValue) -> Result<(), SchemaError> {
+        match (self, value) {
+            (Self::Any, _) | (Self::Null, OwnedValue::Null) | (Self::Bool, OwnedValue::Bool(_)) => {
+                Ok(())
+            }
+            (
+                Self::Number {
+                    minimum,
+                    maximum,
+                    integer,
+                },
+                OwnedValue::Int(_) | OwnedValue::Float(_),
+            ) => {
+                let n = value.as_f64().expect("number");
+                if *integer && n.fract() != 0.0 {
+                    return Err(SchemaError::new(SchemaErrorKind::NotInteger(n)));
+                }
+                match (minimum, maximum) {
+                    (Some(minimum), _) if n < *minimum => {
+                        Err(SchemaError::new(SchemaErrorKind::BelowMinimum {
+                            minimum: *minimum,
+                            value: n,
+                        }))
+                    }
+                    (_, Some(maximum)) if n > *maximum => {
+                        Err(SchemaError::new(SchemaErrorKind::AboveMaximum {
+                            maximum: *maximum,
+                            value: n,
+                        }))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            (
+                Self::String {
+                    min_length,
+                    max_length,
+                },
+                OwnedValue::String(s),
+            ) => {
+                let length = s.chars().count();
+                match (min_length, max_length) {
+                    (Some(min_length), _) if length < *min_length => {
+                        Err(SchemaError::new(SchemaErrorKind::TooShort {
+                            min_length: *min_length,
+                            length,
+                        }))
+                    }
+                    (_, Some(max_length)) if length > *max_length => {
+                        Err(SchemaError::new(SchemaErrorKind::TooLong {
+                            max_length: *max_length,
+                            length,
+                        }))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            (Self::Array(items), OwnedValue::Array(elements)) => {
+                elements.iter().enumerate().try_for_each(|(i, element)| {
+                    items
+                        .check(element)
+                        .map_err(|e| e.within(PathComponent::Index(i)))
+                })
+            }
+            (
+                Self::Object {
+                    properties,
+                    required,
+                    additional_properties,
+                },
+                OwnedValue::Object(entries),
+            ) => {
+                if let Some(missing) = required.iter().find(|key| !entries.contains_key(*key)) {
+                    return Err(SchemaError::new(SchemaErrorKind::MissingProperty(
+                        missing.clone(),
+                    )));
+                }
+                entries.iter().try_for_each(|(name, entry)| {
+                    match properties.get(name) {
+                        Some(schema) => schema.check(entry),
+                        None if *additional_properties => Ok(()),
+                        None => Err(SchemaError::new(SchemaErrorKind::AdditionalProperty)),
+                    }
+                    .map_err(|e| e.within(key(name)))
+                })
+            }
+            (Self::Union(members), _) => {
+                // Report the first member of the value's kind, if any
+                let mut first_error = None;
+                for member in members {
+                    if matches!(member, Self::Any) || member.rank() == value_rank(value) {
+                        match member.check(value) {
+                            Ok(()) => return Ok(()),
+                            Err(e) => {
+                                first_error.get_or_insert(e);
+                            }
+                        }
+                    }
+                }
+                Err(first_error.unwrap_or_else(|| self.type_error(value)))
+            }
+            _ => Err(self.type_error(value)),
+        }
+    }
+
+    /// The error for a value of none of the schema's kinds.
+    fn type_error(&self, value: &OwnedValue) -> SchemaError {
+        let expected = self
+            .clone()
+            .into_members()
+            .iter()
+            .map(Self::type_name)
+            .collect();
+        SchemaError::new(SchemaErrorKind::Type {
+            expected,
+            found: value.type_name(),
+        })
+    }
+
+    /// Write the schema as a JSON Schema draft 7 document.
+    ///
+    /// A union of kinds without bounds or structure is a `type` list;
+    /// any other union is an `anyOf`. An empty array's schema has
+    /// `maxItems` 0 in place of `items`.
+    pub fn to_json(&self) -> String {
+        let mut document = IndexMap::new();
+        document.insert("$schema".into(), OwnedValue::string(DRAFT_7));
+        if let OwnedValue::Object(schema) = self.to_value() {
+            document.extend(schema);
+        }
+        OwnedValue::Object(document).to_json()
+    }
+
+    /// The schema as a JSON value, without `$schema`.
+    fn to_value(&self) -> OwnedValue {
+        let mut schema = IndexMap::new();
+        let name = |schema: &mut IndexMap<String, OwnedValue>| {
+            schema.insert("type".into(), OwnedValue::string(self.type_name()));
+        };
+        match self {
+            Self::Any => {}
+            Self::Null | Self::Bool => name(&mut schema),
+            Self::Number {
+                minimum, maximum, ..
+            } => {
+                name(&mut schema);
+                let bounds = [("minimum", minimum), ("maximum", maximum)];
+                for (keyword, bound) in bounds {
+                    if let Some(bound) = bound {
+                        schema.insert(keyword.into(), number_value(*bound));
+                    }
+                }
+            }
+            Self::String {
+                min_length,
+                max_length,
+            } => {
+                name(&mut schema);
+                let bounds = [("minLength", min_length), ("maxLength", max_length)];
+                for (keyword, bound) in bounds {
+                    if let Some(bound) = bound {
+                        schema.insert(keyword.into(), length_value(*bound));
+                    }
+                }
+            }
+            Self::Array(items) => {
+                name(&mut schema);
+                if matches!(**items, Self::Union(ref members) if members.is_empty()) {
+                    schema.insert("maxItems".into(), OwnedValue::Int(0));
+                } else {
+                    schema.insert("items".into(), items.to_value());
+                }
+            }
+            Self::Object {
+                properties,
+                required,
+                additional_properties,
+            } => {
+                name(&mut schema);
+                let properties = properties
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_value()))
+                    .collect();
+                schema.insert("properties".into(), OwnedValue::Object(properties));
+                if !required.is_empty() {
+                    let required = required.iter().map(|key| OwnedValue::string(key.as_str()));
+                    schema.insert("required".into(), OwnedValue::Array(required.collect()));
+                }
+                if !additional_properties {
+                    schema.insert("additionalProperties".into(), OwnedValue::Bool(false));
+                }
+            }
+            Self::Union(members) if members.is_empty() => {
+                schema.insert("not".into(), OwnedValue::object());
+            }
+            Self::Union(members) => {
+                let members: Vec<OwnedValue> = members.iter().map(Self::to_value).collect();
+                let names: Option<Vec<OwnedValue>> = members
+                    .iter()
+                    .map(|member| match member {
+                        OwnedValue::Object(keywords) if keywords.len() == 1 => {
+                            keywords.get("type").cloned()
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                match names {
+                    Some(names) => schema.insert("type".into(), OwnedValue::Array(names)),
+                    None => schema.insert("anyOf".into(), OwnedValue::Array(members)),
+                };
+            }
+        }
+        OwnedValue::Object(schema)
+    }
+
+    /// Position of this schema's kind in a [`Union`](Self::Union).
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Any => 0,
+            Self::Null => 1,
+            Self::Bool => 2,
+            Self::Number { .. } => 3,
+            Self::String { .. } => 4,
+            Self::Array(_) => 5,
+            Self::Object { .. } => 6,
+            Self::Union(_) => 7,
+        }
+    }
+
+    /// The `type` name of this schema's kind, for a schema of one kind.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Number { integer: true, .. } => "integer",
+            _ => RANK_NAMES[usize::from(self.rank()) - 1],
+        }
+    }
+
+    fn into_members(self) -> Vec<Self> {
+        match self {
+            Self::Union(members) => members,
+            schema => vec![schema],
+        }
+    }
+
+    /// Merge two schemas of the same kind.
+    fn combine(self, other: Self) -> Self {
+        match (self, other) {
+            (
+                Self::Number {
+                    minimum,
+                    maximum,
+                    integer,
+                },
+                Self::Number {
+                    minimum: other_minimum,
+                    maximum: other_maximum,
+                    integer: other_integer,
+                },
+            ) => Self::Number {
+                minimum: minimum.zip(other_minimum).map(|(a, b)| a.min(b)),
+                maximum: maximum.zip(other_maximum).map(|(a, b)| a.max(b)),
+                integer: integer && other_integer,
+            },
+            (
+                Self::String {
+                    min_length,
+                    max_length,
+                },
+                Self::String {
+                    min_length: other_min,
+                    max_length: other_max,
+                },
+            ) => Self::String {
+                min_length: min_length.zip(other_min).map(|(a, b)| a.min(b)),
+                max_length: max_length.zip(other_max).map(|(a, b)| a.max(b)),
+            },
+            (Self::Array(a), Self::Array(b)) => Self::Array(Box::new(a.merge(*b))),
+            (
+                Self::Object {
+                    mut properties,
+                    mut required,
+                    additional_properties,
+                },
+                Self::Object {
+                    properties: other_properties,
+                    required: other_required,
+                    additional_properties: other_additional,
+                },
+            ) => {
+                for (key, schema) in other_properties {
+                    let merged = match properties.remove(&key) {
+                        Some(existing) => existing.merge(schema),
+                        None => schema,
+                    };
+                    properties.insert(key, merged);
+                }
+                required.retain(|key| other_required.contains(key));
+                Self::Object {
+                    properties,
+                    required,
+                    additional_properties: additional_properties || other_additional,
+                }
+            }
+            (schema, _) => schema,
+        }
+    }
+}
+
+/// The `type` names, by [`JsonSchema::rank`] starting at 1.
+const RANK_NAMES: [&str; 6] = ["null", "boolean", "number", "string", "array", "object"];
+
+/// Draft 7 validation keywords [`JsonSchema::from_value`] does not read.
+/// A schema using one is rejected rather than allowing what it forbids.
+const UNSUPPORTED_KEYWORDS: [&str; 21] = [
+    "$ref",
+    "enum",
+    "const",
+    "multipleOf",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "pattern",
+    "minItems",
+    "uniqueItems",
+    "contains",
+    "additionalItems",
+    "minProperties",
+    "maxProperties",
+    "patternProperties",
+    "dependencies",
+    "propertyNames",
+    "allOf",
+    "oneOf",
+    "if",
+    "then",
+    "else",
+];
+
+/// The [`JsonSchema::rank`] of the kind a `type` name allows.
+fn kind_rank(name: &str) -> Option<u8> {
+    match name {
+        "integer" => Some(3),
+        _ => RANK_NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| i as u8 + 1),
+    }
+}
+
+/// The [`JsonSchema::rank`] of the kind of schema `value` needs.
+fn value_rank(value: &OwnedValue) -> u8 {
+    kind_rank(value.type_name()).expect("JSON type name")
+}
+
+fn key(name: &str) -> PathComponent {
+    PathComponent::Key(name.into())
+}
+
+/// A numeric keyword such as `minimum`.
+fn number_keyword(
+    keywords: &IndexMap<String, OwnedValue>,
+    name: &str,
+) -> Result<Option<f64>, SchemaError> {
+    keywords
+        .get(name)
+        .map(|value| {
+            value.as_f64().ok_or_else(|| {
+                SchemaError::invalid(format!("{name} must be a number")).within(key(name))
+            })
+        })
+        .transpose()
+}
+
+/// A length keyword such as `minLength`.
+fn length_keyword(
+    keywords: &IndexMap<String, OwnedValue>,
+    name: &str,
+) -> Result<Option<usize>, SchemaError> {
+    keywords
+        .get(name)
+        .map(|value| {
+            value
+                .as_i64()
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or_else(|| {
+                    SchemaError::invalid(format!("{name} must be a non-negative integer"))
+                        .within(key(name))
+                })
+        })
+        .transpose()
+}
+
+/// A bound as an integer when it is one.
+fn number_value(n: f64) -> OwnedValue {
+    if n.fract() == 0.0 && n.abs() < 9.0e15 {
+        OwnedValue::Int(n as i64)
+    } else {
+        OwnedValue::Float(n)
+    }
+}
+
+fn length_value(n: usize) -> OwnedValue {
+    OwnedValue::Int(i64::try_from(n).unwrap_or(i64::MAX))
+}
+
+impl OwnedValue {
+    /// Infer a [`JsonSchema`] describing this value.
+    ///
+    /// The item schema of an array is the [`merge`](JsonSchema::merge) of
+    /// its elements' schemas.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"name": "Ada", "age": 36, "tags": ["x", null]}"#;
+    /// let index = JsonIndex::build(json);
+    /// let schema = index.root(json).to_owned_value().type_schema();
+    /// assert_eq!(
+    ///     schema.to_json(),
+    ///     concat!(
+    ///         r#"{"$schema":"http://json-schema.org/draft-07/schema#","type":"object","properties":{"#,
+    ///         r#""age":{"type":"number"},"name":{"type":"string"},"#,
+    ///         r#""tags":{"type":"array","items":{"type":["null","string"]}}}}"#
+    ///     )
+    /// );
+    /// ```
+    pub fn type_schema(&self) -> JsonSchema {
+        match self {
+            Self::Null => JsonSchema::Null,
+            Self::Bool(_) => JsonSchema::Bool,
+            Self::Int(_) | Self::Float(_) => JsonSchema::Number {
+                minimum: None,
+                maximum: None,
+                integer: false,
+            },
+            Self::String(_) => JsonSchema::String {
+                min_length: None,
+                max_length: None,
+            },
+            Self::Array(items) => JsonSchema::Array(Box::new(
+                items
+                    .iter()
+                    .map(Self::type_schema)
+                    .fold(JsonSchema::Union(Vec::new()), JsonSchema::merge),
+            )),
+            Self::Object(entries) => JsonSchema::Object {
+                properties: entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.type_schema()))
+                    .collect(),
+                required: Vec::new(),
+                additional_properties: true,
+            },
+        }
+    }
+
+    /// Check this value against `schema`.
+    ///
+    /// This is a structural check of the keywords [`JsonSchema`] models,
+    /// not full JSON Schema validation. A value matches a union if it
+    /// matches any member; when none does, the error is that of the first
+    /// member of the value's kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first violation in document order, with the path to the
+    /// offending value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::jq::{JsonSchema, OwnedValue, PathComponent, SchemaErrorKind};
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let schema = br#"{"type": "object", "required": ["id"], "properties": {
+    ///     "id": {"type": "integer", "minimum": 1},
+    ///     "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}}
+    /// }}"#;
+    /// let index = JsonIndex::build(schema);
+    /// let schema = JsonSchema::from_value(&index.root(schema).to_owned_value()).unwrap();
+    ///
+    /// let value = OwnedValue::object_from([
+    ///     ("id".into(), OwnedValue::Int(7)),
+    ///     ("tags".into(), OwnedValue::Array(vec!["a".into(), "long".into()])),
+    /// ]);
+    /// let err = value.schema_validate(&schema).unwrap_err();
+    /// assert_eq!(
+    ///     err.path,
+    ///     [PathComponent::Key("tags".into()), PathComponent::Index(1)]
+    /// );
+    /// assert_eq!(err.kind, SchemaErrorKind::TooLong { max_length: 3, length: 4 });
+    /// assert_eq!(err.to_string(), ".tags[1]: string of length 4 is longer than 3");
+    /// ```
+    pub fn schema_validate(&self, schema: &JsonSchema) -> Result<(), SchemaError> {
+        schema.check(self).map_err(SchemaError::finish)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUMBER: JsonSchema = JsonSchema::Number {
+        minimum: None,
+        maximum: None,
+        integer: false,
+    };
+    const INTEGER: JsonSchema = JsonSchema::Number {
+        minimum: None,
+        maximum: None,
+        integer: true,
+    };
+    const STRING: JsonSchema = JsonSchema::String {
+        min_length: None,
+        max_length: None,
+    };
+
+    fn parse(json: &str) -> OwnedValue {
+        let index = crate::json::JsonIndex::build(json.as_bytes());
+        index.root(json.as_bytes()).to_owned_value()
+    }
+
+    fn schema_of(json: &str) -> JsonSchema {
+        parse(json).type_schema()
+    }
+
+    fn body(schema: &JsonSchema) -> String {
+        schema.to_value().to_json()
+    }
+
+    fn read(schema: &str) -> JsonSchema {
+        JsonSchema::from_value(&parse(schema)).unwrap()
+    }
+
+    /// The display of the error from checking `json` against `schema`.
+    fn violation(schema: &str, json: &str) -> String {
+        parse(json)
+            .schema_validate(&read(schema))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(schema_of("null"), JsonSchema::Null);
+        assert_eq!(schema_of("true"), JsonSchema::Bool);
+        assert_eq!(schema_of("1"), NUMBER);
+        assert_eq!(schema_of("-2.5e3"), NUMBER);
+        assert_eq!(schema_of(r#""x""#), STRING);
+        assert_eq!(
+            JsonSchema::Bool.to_json(),
+            r#"{"$schema":"http://json-schema.org/draft-07/schema#","type":"boolean"}"#
+        );
+    }
+
+    #[test]
+    fn test_array_union() {
+        assert_eq!(
+            schema_of("[1, 2.5, 3]"),
+            JsonSchema::Array(Box::new(NUMBER))
+        );
+        let mixed = schema_of(r#"["a", 1, null, "b", true]"#);
+        assert_eq!(
+            mixed,
+            JsonSchema::Array(Box::new(JsonSchema::Union(vec![
+                JsonSchema::Null,
+                JsonSchema::Bool,
+                NUMBER,
+                STRING,
+            ])))
+        );
+        assert_eq!(
+            body(&mixed),
+            r#"{"type":"array","items":{"type":["null","boolean","number","string"]}}"#
+        );
+        assert_eq!(body(&schema_of("[]")), r#"{"type":"array","maxItems":0}"#);
+    }
+
+    #[test]
+    fn test_objects_in_array_merge() {
+        let schema =
+            schema_of(r#"[{"id": 1, "name": "a"}, {"id": 2, "tags": []}, {"tags": ["t"]}]"#);
+        assert_eq!(
+            body(&schema),
+            concat!(
+                r#"{"type":"array","items":{"type":"object","properties":{"#,
+                r#""id":{"type":"number"},"name":{"type":"string"},"#,
+                r#""tags":{"type":"array","items":{"type":"string"}}}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_structured_union_is_any_of() {
+        let schema = schema_of(r#"[[1], {"a": null}, "s"]"#);
+        assert_eq!(
+            body(&schema),
+            concat!(
+                r#"{"type":"array","items":{"anyOf":[{"type":"string"},"#,
+                r#"{"type":"array","items":{"type":"number"}},"#,
+                r#"{"type":"object","properties":{"a":{"type":"null"}}}]}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let union = STRING.merge(JsonSchema::Null);
+        assert_eq!(union, JsonSchema::Union(vec![JsonSchema::Null, STRING]));
+        // Merging is idempotent and flattens unions
+        assert_eq!(union.clone().merge(union.clone()), union);
+        assert_eq!(
+            union
+                .clone()
+                .merge(JsonSchema::Union(vec![JsonSchema::Bool, STRING])),
+            JsonSchema::Union(vec![JsonSchema::Null, JsonSchema::Bool, STRING])
+        );
+        assert_eq!(union.merge(JsonSchema::Any), JsonSchema::Any);
+        assert_eq!(JsonSchema::Union(Vec::new()).merge(NUMBER), NUMBER);
+        assert_eq!(body(&JsonSchema::Union(Vec::new())), r#"{"not":{}}"#);
+
+        // Bounds widen, and only properties both require stay required
+        let a = read(
+            r#"{"type":"object","required":["a","b"],"properties":{"a":{"type":"string","maxLength":2}}}"#,
+        );
+        let b = read(
+            r#"{"type":"object","required":["b"],"additionalProperties":false,"properties":{"a":{"type":"string","minLength":1,"maxLength":5}}}"#,
+        );
+        assert_eq!(
+            body(&a.merge(b)),
+            r#"{"type":"object","properties":{"a":{"type":"string","maxLength":5}},"required":["b"]}"#
+        );
+    }
+
+    #[test]
+    fn test_from_value_round_trips_to_json() {
+        for schema in [
+            r#"{"type":"array","items":{"type":["null","boolean","number","string"]}}"#,
+            r#"{"type":"array","maxItems":0}"#,
+            r#"{"type":"number","minimum":-1,"maximum":2.5}"#,
+            r#"{"type":"integer","minimum":1}"#,
+            r#"{"type":["null","integer"]}"#,
+            r#"{"type":"string","minLength":1,"maxLength":8}"#,
+            r#"{"anyOf":[{"type":"string","minLength":1},{"type":"object","properties":{"a":{"type":"null"}},"required":["a"],"additionalProperties":false}]}"#,
+            r#"{"not":{}}"#,
+            "{}",
+        ] {
+            assert_eq!(body(&read(schema)), schema);
+        }
+        let inferred = schema_of(r#"[{"id": 1, "tags": ["a", null]}, [], true]"#);
+        assert_eq!(read(&inferred.to_json()), inferred);
+    }
+
+    #[test]
+    fn test_from_value_keywords() {
+        assert_eq!(read("true"), JsonSchema::Any);
+        assert_eq!(read("false"), JsonSchema::Union(Vec::new()));
+        assert_eq!(read(r#"{"type":"integer","description":"n"}"#), INTEGER);
+        assert_eq!(read(r#"{"type":["integer","number"]}"#), NUMBER);
+        assert_eq!(INTEGER.merge(NUMBER), NUMBER);
+        assert_eq!(
+            read(r#"{"type":["string","null","string"]}"#),
+            JsonSchema::Union(vec![JsonSchema::Null, STRING])
+        );
+        // Without type, keywords only constrain values of their kind
+        let schema = read(r#"{"minLength":2}"#);
+        assert!(parse("5").schema_validate(&schema).is_ok());
+        assert!(parse(r#""ab""#).schema_validate(&schema).is_ok());
+        assert!(parse(r#""a""#).schema_validate(&schema).is_err());
+
+        for (schema, error) in [
+            ("1", ".: invalid schema: a schema must be an object or a boolean, not number"),
+            (r#"{"type":"float"}"#, r#".type: invalid schema: unknown type "float""#),
+            (r#"{"type":["null",1]}"#, ".type[1]: invalid schema: unknown type 1"),
+            (r#"{"minLength":-1}"#, ".minLength: invalid schema: minLength must be a non-negative integer"),
+            (r#"{"maxItems":3}"#, ".maxItems: invalid schema: only 0 is supported in maxItems"),
+            (r#"{"properties":{"a b":{"items":{"maximum":"x"}}}}"#, r#".properties["a b"].items.maximum: invalid schema: maximum must be a number"#),
+            (r#"{"anyOf":[{},{"required":[1]}]}"#, ".anyOf[1].required[0]: invalid schema: required must list strings"),
+            (r#"{"additionalProperties":{}}"#, ".additionalProperties: invalid schema: only a boolean is supported in additionalProperties"),
+            (r#"{"enum":[1,2]}"#, ".enum: invalid schema: enum is not supported"),
+            (r#"{"type":"string","pattern":"^a"}"#, ".pattern: invalid schema: pattern is not supported"),
+            (r#"{"items":{"const":1}}"#, ".items.const: invalid schema: const is not supported"),
+            (r#"{"type":"array","minItems":1}"#, ".minItems: invalid schema: minItems is not supported"),
+            (r#"{"allOf":[{"type":"number"}]}"#, ".allOf: invalid schema: allOf is not supported"),
+        ] {
+            let err = JsonSchema::from_value(&parse(schema)).unwrap_err();
+            assert_eq!(err.to_string(), error, "{schema}");
+        }
+    }
+
+    #[test]
+    fn test_schema_validate_violations() {
+        let schema = r#"{"type":"object","required":["id"],"additionalProperties":false,"properties":{
+            "id":{"type":"integer","minimum":1,"maximum":100},
+            "name":{"type":["string","null"],"minLength":1},
+            "items":{"type":"array","items":{"anyOf":[{"type":"number"},{"type":"object","properties":{"sku":{"type":"string"}}}]}}
+        }}"#;
+        let schema = read(schema);
+        let valid = r#"{"id":5,"name":null,"items":[1,{"sku":"a","n":2},2.5]}"#;
+        assert_eq!(parse(valid).schema_validate(&schema), Ok(()));
+
+        for (json, error) in [
+            ("[]", ".: expected object, found array"),
+            (r#"{"name":"x"}"#, r#".: missing required property "id""#),
+            (r#"{"id":0}"#, ".id: 0 is below the minimum 1"),
+            (r#"{"id":100.5}"#, ".id: 100.5 is not an integer"),
+            (r#"{"id":101}"#, ".id: 101 is above the maximum 100"),
+            (
+                r#"{"id":1,"name":""}"#,
+                ".name: string of length 0 is shorter than 1",
+            ),
+            (
+                r#"{"id":1,"name":3}"#,
+                ".name: expected null or string, found number",
+            ),
+            (
+                r#"{"id":1,"extra key":3}"#,
+                r#".["extra key"]: property not allowed by the schema"#,
+            ),
+            (
+                r#"{"id":1,"items":[1,{"sku":"a"},{"sku":2}]}"#,
+                ".items[2].sku: expected string, found number",
+            ),
+            (
+                r#"{"id":1,"items":[true]}"#,
+                ".items[0]: expected number or object, found boolean",
+            ),
+        ] {
+            let err = parse(json).schema_validate(&schema).unwrap_err();
+            assert_eq!(err.to_string(), error, "{json}");
+        }
+    }
+
+    #[test]
+    fn test_schema_validate_inferred() {
+        let samples = r#"[{"id": 1, "tags": ["a"]}, {"id": 2.5, "tags": [], "note": null}]"#;
+        let schema = schema_of(samples);
+        assert_eq!(parse(samples).schema_validate(&schema), Ok(()));
+        assert_eq!(
+            violation(&schema.to_json(), r#"[{"id": 1, "tags": [null]}]"#),
+            ".[0].tags[0]: expected string, found null"
+        );
+        // Character length, not bytes
+        let short = read(r#"{"maxLength":2}"#);
+        assert_eq!(parse(r#""é✓""#).schema_validate(&short), Ok(()));
+    }
+}
@@ -4,8 +4,6 @@
 //! computations, we need to materialize them into owned values rather than
 //! references into the original JSON bytes.
 
-#[cfg(not(test))]
-use alloc::format;
 #[cfg(not(test))]
 use alloc::string::{String, ToString};
 #[cfg(not(test))]
@@ -38,6 +36,75 @@ pub enum OwnedValue {
     Object(IndexMap<String, Self>),
 }
 
+/// How [`OwnedValue::deep_merge_with`] combines two arrays found at the
+/// same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMergeStrategy {
+    /// The right-hand array replaces the left, as jq's `*` does.
+    #[default]
+    Replace,
+    /// The right-hand elements follow the left.
+    Append,
+    /// The right-hand elements precede the left.
+    Prepend,
+    /// Elements at the same index are merged recursively; the longer
+    /// array's extra elements are kept.
+    ElementWise,
+}
+
+/// One step of a jq path array such as `["key", 0, "nested"]`.
+///
+/// Used with [`OwnedValue::get_path`], [`OwnedValue::set_path`],
+/// [`OwnedValue::del_path`] and [`OwnedValue::del_paths`]. Indices are
+/// non-negative; jq's negative indices must be resolved against the array
+/// length first.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathComponent {
+    /// An object key
+    Key(String),
+    /// An array index
+    Index(usize),
+}
+
+impl PathComponent {
+    /// `path` in jq syntax, such as `.items[2].name` or `.["a b"]`; the
+    /// empty path is `.`.
+    pub(crate) fn display_path(path: &[Self]) -> String {
+        let mut out = String::new();
+        for component in path {
+            match component {
+                Self::Key(key) if is_identifier(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                Self::Key(key) => {
+                    out.push('[');
+                    out.push_str(&OwnedValue::string(key.as_str()).to_json());
+                    out.push(']');
+                }
+                Self::Index(i) => {
+                    out.push('[');
+                    out.push_str(&i.to_string());
+                    out.push(']');
+                }
+            }
+        }
+        if !out.starts_with('.') {
+            out.insert(0, '.');
+        }
+        out
+    }
+}
+
+/// Whether jq can write `key` as `.key`.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 impl OwnedValue {
     /// Create a null value.
     pub fn null() -> Self {
@@ -188,53 +255,202 @@ impl OwnedValue {
         }
     }
 
-    /// Format this value as JSON string.
-    pub fn to_json(&self) -> String {
-        match self {
-            Self::Null => "null".into(),
-            Self::Bool(true) => "true".into(),
-            Self::Bool(false) => "false".into(),
-            Self::Int(n) => format!("{n}"),
-            Self::Float(f) => {
-                if f.is_nan() || f.is_infinite() {
-                    "null".into() // JSON doesn't support NaN or Infinity
-                } else {
-                    format!("{f}")
+    /// Get the value at `path`, or `None` if the path leaves the tree.
+    ///
+    /// A path leaves the tree at a missing key, an index past the end of an
+    /// array, or a component of the wrong kind (a key on an array, an index
+    /// on an object, anything on a scalar). The empty path is `self`.
+    pub fn get_path(&self, path: &[PathComponent]) -> Option<&Self> {
+        path.iter()
+            .try_fold(self, |current, component| match (current, component) {
+                (Self::Object(entries), PathComponent::Key(key)) => entries.get(key),
+                (Self::Array(elements), PathComponent::Index(i)) => elements.get(*i),
+                _ => None,
+            })
+    }
+
+    /// Return this tree with the value at `path` replaced by `val`, like jq's
+    /// `setpath`.
+    ///
+    /// Missing keys are inserted at the end of their object (existing keys
+    /// keep their position), and arrays are padded with `null` up to an
+    /// index past their end. A `null` or a value of the wrong kind on the
+    /// way is replaced by a new object or array, as the component requires.
+    pub fn set_path(self, path: &[PathComponent], val: Self) -> Self {
+        let Some((first, rest)) = path.split_first() else {
+            return val;
+        };
+        match first {
+            PathComponent::Key(key) => {
+                let mut entries = match self {
+                    Self::Object(entries) => entries,
+                    _ => IndexMap::new(),
+                };
+                let slot = entries.entry(key.clone()).or_insert(Self::Null);
+                *slot = core::mem::replace(slot, Self::Null).set_path(rest, val);
+                Self::Object(entries)
+            }
+            PathComponent::Index(index) => {
+                let mut elements = match self {
+                    Self::Array(elements) => elements,
+                    _ => Vec::new(),
+                };
+                if elements.len() <= *index {
+                    elements.resize(index + 1, Self::Null);
                 }
+                let slot = &mut elements[*index];
+                *slot = core::mem::replace(slot, Self::Null).set_path(rest, val);
+                Self::Array(elements)
             }
-            Self::String(s) => format!("\"{}\"", escape_json_string(s)),
-            Self::Array(arr) => {
-                let elements: Vec<String> = arr.iter().map(Self::to_json).collect();
-                format!("[{}]", elements.join(","))
+        }
+    }
+
+    /// Return this tree with the value at `path` removed, like jq's `del`.
+    ///
+    /// Removing an array element shifts the later elements down. A path that
+    /// leaves the tree (see [`get_path`](Self::get_path)) changes nothing,
+    /// and the empty path replaces the whole value with `null`.
+    pub fn del_path(self, path: &[PathComponent]) -> Self {
+        let Some((first, rest)) = path.split_first() else {
+            return Self::Null;
+        };
+        match (self, first) {
+            (Self::Object(mut entries), PathComponent::Key(key)) => {
+                if rest.is_empty() {
+                    entries.shift_remove(key);
+                } else if let Some(slot) = entries.get_mut(key) {
+                    *slot = core::mem::replace(slot, Self::Null).del_path(rest);
+                }
+                Self::Object(entries)
             }
-            Self::Object(obj) => {
-                let entries: Vec<String> = obj
-                    .iter()
-                    .map(|(k, v)| format!("\"{}\":{}", escape_json_string(k), v.to_json()))
-                    .collect();
-                format!("{{{}}}", entries.join(","))
+            (Self::Array(mut elements), PathComponent::Index(index)) if *index < elements.len() => {
+                if rest.is_empty() {
+                    elements.remove(*index);
+                } else {
+                    let slot = &mut elements[*index];
+                    *slot = core::mem::replace(slot, Self::Null).del_path(rest);
+                }
+                Self::Array(elements)
             }
+            (other, _) => other,
         }
     }
-}
 
-/// Escape a string for JSON output.
-fn escape_json_string(s: &str) -> String {
-    let mut result = String::with_capacity(s.len());
-    for c in s.chars() {
-        match c {
-            '"' => result.push_str("\\\""),
-            '\\' => result.push_str("\\\\"),
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            c if c.is_control() => {
-                result.push_str(&format!("\\u{:04x}", c as u32));
+    /// Return this tree with every path in `paths` removed, like jq's
+    /// `delpaths`.
+    ///
+    /// All paths refer to the tree as it is before any deletion: they are
+    /// deleted from the last in path order to the first, so removing an array
+    /// element never shifts an index still to be deleted.
+    pub fn del_paths(self, paths: &[Vec<PathComponent>]) -> Self {
+        let mut sorted: Vec<&Vec<PathComponent>> = paths.iter().collect();
+        sorted.sort();
+        sorted.dedup();
+        sorted
+            .into_iter()
+            .rev()
+            .fold(self, |value, path| value.del_path(path))
+    }
+
+    /// Merge `other` into this value recursively, like jq's `*` on objects.
+    ///
+    /// When both values are objects, each key of `other` is merged into the
+    /// value of the same key here, or added at the end if it is missing;
+    /// existing keys keep their position. In every other case, including
+    /// two arrays, `other` replaces this value. This differs from `+` on
+    /// objects, which replaces nested objects instead of merging them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let parse = |json: &[u8]| JsonIndex::build(json).root(json).to_owned_value();
+    /// let defaults = parse(br#"{"db": {"host": "localhost", "port": 5432}, "tags": ["a"]}"#);
+    /// let config = parse(br#"{"db": {"port": 6543}, "tags": ["b"]}"#);
+    /// assert_eq!(
+    ///     defaults.deep_merge(config).to_json(),
+    ///     r#"{"db":{"host":"localhost","port":6543},"tags":["b"]}"#
+    /// );
+    /// ```
+    pub fn deep_merge(self, other: Self) -> Self {
+        self.deep_merge_with(other, ArrayMergeStrategy::Replace)
+    }
+
+    /// Like [`deep_merge`](Self::deep_merge), but two arrays at the same
+    /// place are combined according to `arrays` instead of replaced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::jq::ArrayMergeStrategy;
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let parse = |json: &[u8]| JsonIndex::build(json).root(json).to_owned_value();
+    /// let base = parse(br#"{"tags": ["a"]}"#);
+    /// let more = parse(br#"{"tags": ["b"]}"#);
+    /// assert_eq!(
+    ///     base.deep_merge_with(more, ArrayMergeStrategy::Append).to_json(),
+    ///     r#"{"tags":["a","b"]}"#
+    /// );
+    /// ```
+    pub fn deep_merge_with(self, other: Self, arrays: ArrayMergeStrategy) -> Self {
+        match (self, other) {
+            (Self::Object(mut entries), Self::Object(other_entries)) => {
+                for (key, value) in other_entries {
+                    let slot = entries.entry(key).or_insert(Self::Null);
+                    *slot = core::mem::replace(slot, Self::Null).deep_merge_with(value, arrays);
+                }
+                Self::Object(entries)
             }
-            c => result.push(c),
+            (Self::Array(mut elements), Self::Array(mut other_elements)) => match arrays {
+                ArrayMergeStrategy::Replace => Self::Array(other_elements),
+                ArrayMergeStrategy::Append => {
+                    elements.append(&mut other_elements);
+                    Self::Array(elements)
+                }
+                ArrayMergeStrategy::Prepend => {
+                    other_elements.append(&mut elements);
+                    Self::Array(other_elements)
+                }
+                ArrayMergeStrategy::ElementWise => {
+                    let extra = other_elements.split_off(elements.len().min(other_elements.len()));
+                    for (slot, value) in elements.iter_mut().zip(other_elements) {
+                        *slot = core::mem::replace(slot, Self::Null).deep_merge_with(value, arrays);
+                    }
+                    elements.extend(extra);
+                    Self::Array(elements)
+                }
+            },
+            (_, other) => other,
         }
     }
-    result
+
+    /// Format this value as JSON string.
+    ///
+    /// See [`compact_serialize`](crate::json::format::compact_serialize).
+    pub fn to_json(&self) -> String {
+        crate::json::format::compact_serialize(self)
+    }
+
+    /// Format this value as compact JSON; the same as [`to_json`](Self::to_json).
+    pub fn to_json_string(&self) -> String {
+        self.to_json()
+    }
+
+    /// Format this value as compact JSON bytes.
+    pub fn to_json_bytes(&self) -> Vec<u8> {
+        self.to_json().into_bytes()
+    }
+
+    /// Format this value as JSON indented by `indent` spaces per level, as
+    /// `jq --indent` prints it; an `indent` of 0 is compact.
+    ///
+    /// To sort object keys as well, use
+    /// [`serialize`](crate::json::format::serialize).
+    pub fn to_json_pretty(&self, indent: usize) -> String {
+        crate::json::format::serialize(self, indent, false)
+    }
 }
 
 impl From<Literal> for OwnedValue {
@@ -285,6 +501,84 @@ impl<T: Into<Self>> From<Vec<T>> for OwnedValue {
     }
 }
 
+/// Converts recursively. NaN has no JSON number form and becomes `null`, and
+/// the infinities become `f64::MAX` and `f64::MIN`, as in
+/// [`OwnedValue::to_json`].
+#[cfg(feature = "serde")]
+impl From<OwnedValue> for serde_json::Value {
+    fn from(value: OwnedValue) -> Self {
+        match value {
+            OwnedValue::Null => Self::Null,
+            OwnedValue::Bool(b) => Self::Bool(b),
+            OwnedValue::Int(n) => Self::Number(n.into()),
+            OwnedValue::Float(f) => {
+                let f = if f.is_infinite() {
+                    f64::MAX.copysign(f)
+                } else {
+                    f
+                };
+                serde_json::Number::from_f64(f).map_or(Self::Null, Self::Number)
+            }
+            OwnedValue::String(s) => Self::String(s),
+            OwnedValue::Array(elements) => {
+                Self::Array(elements.into_iter().map(Into::into).collect())
+            }
+            OwnedValue::Object(fields) => Self::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A `serde_json` number with no `i64` or `f64` form (only possible with
+/// serde_json's `arbitrary_precision` feature).
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnrepresentableNumber(pub serde_json::Number);
+
+#[cfg(feature = "serde")]
+impl core::fmt::Display for UnrepresentableNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "number {} cannot be represented as i64 or f64", self.0)
+    }
+}
+
+/// Converts recursively. Integers that fit in `i64` become [`OwnedValue::Int`];
+/// other numbers, including `u64` values above `i64::MAX`, become
+/// [`OwnedValue::Float`].
+#[cfg(feature = "serde")]
+impl TryFrom<serde_json::Value> for OwnedValue {
+    type Error = UnrepresentableNumber;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => Self::Int(i),
+                (None, Some(f)) => Self::Float(f),
+                (None, None) => return Err(UnrepresentableNumber(n)),
+            },
+            serde_json::Value::String(s) => Self::String(s),
+            serde_json::Value::Array(elements) => Self::Array(
+                elements
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(fields) => Self::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, Self::try_from(value)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,4 +766,220 @@ mod tests {
             ])
         );
     }
+
+    fn key(k: &str) -> PathComponent {
+        PathComponent::Key(k.into())
+    }
+
+    #[test]
+    fn test_deep_path_round_trip() {
+        // Ten levels alternating objects and arrays: ["k0", 1, "k2", 1, ...]
+        let path: Vec<PathComponent> = (0..10)
+            .map(|depth| {
+                if depth % 2 == 0 {
+                    key(&format!("k{depth}"))
+                } else {
+                    PathComponent::Index(1)
+                }
+            })
+            .collect();
+
+        let tree = OwnedValue::Null.set_path(&path, OwnedValue::from("leaf"));
+        assert_eq!(
+            tree.to_json(),
+            r#"{"k0":[null,{"k2":[null,{"k4":[null,{"k6":[null,{"k8":[null,"leaf"]}]}]}]}]}"#
+        );
+        assert_eq!(tree.get_path(&path), Some(&OwnedValue::from("leaf")));
+
+        let updated = tree.clone().set_path(&path, OwnedValue::Int(7));
+        assert_eq!(updated.get_path(&path), Some(&OwnedValue::Int(7)));
+        assert_eq!(tree.get_path(&path), Some(&OwnedValue::from("leaf")));
+
+        let deleted = updated.del_path(&path);
+        assert_eq!(deleted.get_path(&path), None);
+        assert_eq!(
+            deleted.get_path(&path[..9]),
+            Some(&OwnedValue::Array(vec![OwnedValue::Null]))
+        );
+    }
+
+    #[test]
+    fn test_get_path_misses() {
+        let tree = OwnedValue::object_from([("a".to_string(), vec![1i64, 2].into())]);
+        assert_eq!(tree.get_path(&[]), Some(&tree));
+        assert_eq!(
+            tree.get_path(&[key("a"), PathComponent::Index(1)]),
+            Some(&OwnedValue::Int(2))
+        );
+        assert_eq!(tree.get_path(&[key("b")]), None);
+        assert_eq!(tree.get_path(&[key("a"), PathComponent::Index(2)]), None);
+        assert_eq!(tree.get_path(&[key("a"), key("x")]), None);
+        assert_eq!(tree.get_path(&[PathComponent::Index(0)]), None);
+    }
+
+    #[test]
+    fn test_set_path_keeps_key_order() {
+        let tree = OwnedValue::object_from([
+            ("b".to_string(), OwnedValue::Int(1)),
+            ("a".to_string(), OwnedValue::Int(2)),
+        ]);
+        let tree = tree.set_path(&[key("b")], OwnedValue::Int(3));
+        let tree = tree.set_path(&[key("c")], OwnedValue::Int(4));
+        assert_eq!(tree.to_json(), r#"{"b":3,"a":2,"c":4}"#);
+        // A scalar in the way is replaced by the container the path needs
+        let tree = tree.set_path(&[key("a"), PathComponent::Index(1)], OwnedValue::Null);
+        assert_eq!(tree.to_json(), r#"{"b":3,"a":[null,null],"c":4}"#);
+    }
+
+    #[test]
+    fn test_del_paths() {
+        let tree: OwnedValue = vec![10i64, 11, 12, 13].into();
+        let paths = [
+            vec![PathComponent::Index(1)],
+            vec![PathComponent::Index(3)],
+            vec![PathComponent::Index(1)],
+            vec![PathComponent::Index(9)],
+        ];
+        // Indices refer to the original array, and duplicates delete once
+        assert_eq!(tree.clone().del_paths(&paths).to_json(), "[10,12]");
+        assert_eq!(tree.clone().del_path(&[]), OwnedValue::Null);
+        assert_eq!(tree.clone().del_path(&[key("a")]), tree);
+
+        let nested = OwnedValue::object_from([(
+            "a".to_string(),
+            OwnedValue::Array(vec![
+                OwnedValue::Int(1),
+                OwnedValue::object_from([("b".to_string(), OwnedValue::Int(2))]),
+            ]),
+        )]);
+        let paths = [
+            vec![key("a"), PathComponent::Index(0)],
+            vec![key("a"), PathComponent::Index(1), key("b")],
+        ];
+        assert_eq!(nested.del_paths(&paths).to_json(), r#"{"a":[{}]}"#);
+    }
+
+    /// Parse test JSON through the semi-index.
+    fn json(text: &str) -> OwnedValue {
+        let index = crate::json::JsonIndex::build(text.as_bytes());
+        index.root(text.as_bytes()).to_owned_value()
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let left = json(r#"{"a": {"x": 1, "y": {"p": 1}}, "b": [1], "c": {"k": 1}, "d": 1}"#);
+        let right = json(r#"{"e": 5, "a": {"y": {"q": 2}, "x": null}, "b": [2], "c": 3, "d": {}}"#);
+        // Nested objects merge; keys keep their position; anything else is replaced
+        assert_eq!(
+            left.deep_merge(right).to_json(),
+            r#"{"a":{"x":null,"y":{"p":1,"q":2}},"b":[2],"c":3,"d":{},"e":5}"#
+        );
+        assert_eq!(
+            json("[1]").deep_merge(json(r#"{"a":1}"#)),
+            json(r#"{"a":1}"#)
+        );
+        assert_eq!(
+            json(r#"{"a":1}"#).deep_merge(OwnedValue::Null),
+            OwnedValue::Null
+        );
+    }
+
+    #[test]
+    fn test_deep_merge_array_strategies() {
+        let left = json(r#"{"v": [1, {"a": 1}, [10]]}"#);
+        let right = json(r#"{"v": [2, {"b": 2}]}"#);
+        let merged = |arrays| {
+            left.clone()
+                .deep_merge_with(right.clone(), arrays)
+                .to_json()
+        };
+        assert_eq!(merged(ArrayMergeStrategy::Replace), r#"{"v":[2,{"b":2}]}"#);
+        assert_eq!(
+            merged(ArrayMergeStrategy::Append),
+            r#"{"v":[1,{"a":1},[10],2,{"b":2}]}"#
+        );
+        assert_eq!(
+            merged(ArrayMergeStrategy::Prepend),
+            r#"{"v":[2,{"b":2},1,{"a":1},[10]]}"#
+        );
+        assert_eq!(
+            merged(ArrayMergeStrategy::ElementWise),
+            r#"{"v":[2,{"a":1,"b":2},[10]]}"#
+        );
+
+        // Element-wise keeps the extra elements of whichever side is longer,
+        // and nested arrays use the same strategy
+        let longer =
+            json("[[1], 2, 3]").deep_merge_with(json("[[4, 5]]"), ArrayMergeStrategy::ElementWise);
+        assert_eq!(longer.to_json(), "[[4,5],2,3]");
+        let longer =
+            json("[[1, 2]]").deep_merge_with(json("[[3], 4]"), ArrayMergeStrategy::ElementWise);
+        assert_eq!(longer.to_json(), "[[3,2],4]");
+        assert_eq!(ArrayMergeStrategy::default(), ArrayMergeStrategy::Replace);
+    }
+
+    #[test]
+    fn test_to_json_forms() {
+        let value = OwnedValue::object_from([("k".to_string(), vec![1i64, 2].into())]);
+        assert_eq!(value.to_json_string(), r#"{"k":[1,2]}"#);
+        assert_eq!(value.to_json_bytes(), br#"{"k":[1,2]}"#);
+        assert_eq!(value.to_json_pretty(0), value.to_json());
+        assert_eq!(value.to_json_pretty(1), "{\n \"k\": [\n  1,\n  2\n ]\n}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_round_trip() {
+        let value = OwnedValue::object_from([
+            ("z".to_string(), OwnedValue::Null),
+            ("bool".to_string(), OwnedValue::Bool(true)),
+            ("int".to_string(), OwnedValue::Int(i64::MIN)),
+            ("float".to_string(), OwnedValue::Float(-0.25)),
+            ("string".to_string(), OwnedValue::string("a\"b")),
+            (
+                "array".to_string(),
+                vec![
+                    OwnedValue::Int(1),
+                    OwnedValue::array(),
+                    OwnedValue::object(),
+                ]
+                .into(),
+            ),
+        ]);
+        let json = serde_json::Value::from(value.clone());
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "z": null, "bool": true, "int": i64::MIN, "float": -0.25,
+                "string": "a\"b", "array": [1, [], {}]
+            })
+        );
+        // Key order survives in both directions
+        let keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(keys, ["z", "bool", "int", "float", "string", "array"]);
+        assert_eq!(OwnedValue::try_from(json).unwrap(), value);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_numbers() {
+        assert_eq!(
+            serde_json::Value::from(OwnedValue::Float(f64::NAN)),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            serde_json::Value::from(OwnedValue::Float(f64::NEG_INFINITY)),
+            serde_json::Value::from(f64::MIN)
+        );
+        // An integral float stays a float
+        assert_eq!(
+            OwnedValue::try_from(serde_json::Value::from(OwnedValue::Float(2.0))),
+            Ok(OwnedValue::Float(2.0))
+        );
+        // u64 beyond i64 falls back to a float
+        assert_eq!(
+            OwnedValue::try_from(serde_json::json!(u64::MAX)),
+            Ok(OwnedValue::Float(u64::MAX as f64))
+        );
+    }
 }
@@ -0,0 +1,445 @@
+//! JSON pretty-printing directly from the semi-index.
+//!
+//! [`pretty_print`] walks the BP tree and copies every scalar (string, number,
+//! `true`/`false`/`null`) verbatim from the original bytes, only adding
+//! indentation and newlines around containers. Nothing is decoded, so the
+//! output preserves the source representation of numbers (`1e10` stays
+//! `1e10`) and string escapes (`"\u00e9"` stays `"\u00e9"`).
+//!
+//! [`compact_serialize`] and [`serialize`] are the counterparts for computed
+//! values: they write an [`OwnedValue`] as compact JSON, as jq's `tojson`
+//! does, or indented with optionally sorted keys.
+//!
+//! # Example
+//!
+//! ```
+//! use succinctly::json::{format::pretty_print, JsonIndex};
+//!
+//! let json = br#"{"a":[1e10,true],"b":{}}"#;
+//! let index = JsonIndex::build(json);
+//! let pretty = pretty_print(index.root(json), json, 2);
+//! assert_eq!(pretty, "{\n  \"a\": [\n    1e10,\n    true\n  ],\n  \"b\": {}\n}");
+//! ```
+
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::jq::OwnedValue;
+use crate::json::light::JsonCursor;
+
+/// Pretty-print the value at `cursor`, indenting nested values by `indent` spaces.
+///
+/// `json` must be the text the cursor's index was built from. Taking the text
+/// separately from the cursor lets one [`JsonIndex`](crate::json::JsonIndex)
+/// serve any number of print calls, each starting from a different cursor.
+///
+/// An `indent` of 0 produces compact output with no whitespace at all
+/// (like `jq -c`). Empty containers print as `[]` and `{}`.
+///
+/// The traversal uses an explicit stack, so deeply nested input cannot
+/// overflow the call stack. Values the index cannot locate (e.g. the root of
+/// empty input) produce no output.
+pub fn pretty_print<W: AsRef<[u64]>>(
+    cursor: JsonCursor<'_, W>,
+    json: &[u8],
+    indent: usize,
+) -> String {
+    let mut out = String::with_capacity(json.len() + json.len() / 2);
+    let mut printer = Printer {
+        json,
+        indent,
+        out: &mut out,
+    };
+    printer.print(cursor);
+    out
+}
+
+/// A container whose children are still being printed.
+struct Frame<'a, W> {
+    /// Next child to print (for objects, the next key), or `None` when done
+    next: Option<JsonCursor<'a, W>>,
+    /// Closing bracket to emit once the children are exhausted
+    close: u8,
+    /// Whether any child has been printed yet
+    has_children: bool,
+}
+
+struct Printer<'o, 'j> {
+    json: &'j [u8],
+    indent: usize,
+    out: &'o mut String,
+}
+
+impl Printer<'_, '_> {
+    fn print<W: AsRef<[u64]>>(&mut self, root: JsonCursor<'_, W>) {
+        let mut stack: Vec<Frame<'_, W>> = Vec::new();
+        self.open_value(root, &mut stack);
+
+        loop {
+            let depth = stack.len();
+            let Some(frame) = stack.last_mut() else {
+                break;
+            };
+            let Some(child) = frame.next else {
+                let frame = stack.pop().expect("stack is non-empty");
+                if frame.has_children {
+                    self.newline(depth - 1);
+                }
+                self.out.push(frame.close as char);
+                continue;
+            };
+
+            if frame.has_children {
+                self.out.push(',');
+            }
+            frame.has_children = true;
+            self.newline(depth);
+
+            let value = if frame.close == b'}' {
+                // Object children alternate key, value
+                self.copy_span(child);
+                self.out.push(':');
+                if self.indent > 0 {
+                    self.out.push(' ');
+                }
+                let Some(value) = child.next_sibling() else {
+                    frame.next = None;
+                    continue;
+                };
+                value
+            } else {
+                child
+            };
+
+            stack.last_mut().expect("stack is non-empty").next = value.next_sibling();
+            self.open_value(value, &mut stack);
+        }
+    }
+
+    /// Print a scalar, or print the opening bracket of a container and push its frame.
+    fn open_value<'a, W: AsRef<[u64]>>(
+        &mut self,
+        cursor: JsonCursor<'a, W>,
+        stack: &mut Vec<Frame<'a, W>>,
+    ) {
+        let Some(&byte) = cursor.text_position().and_then(|pos| self.json.get(pos)) else {
+            return;
+        };
+
+        match byte {
+            b'{' | b'[' => {
+                self.out.push(byte as char);
+                stack.push(Frame {
+                    next: cursor.first_child(),
+                    close: if byte == b'{' { b'}' } else { b']' },
+                    has_children: false,
+                });
+            }
+            _ => self.copy_span(cursor),
+        }
+    }
+
+    /// Copy the scalar at `cursor` verbatim from the original text.
+    fn copy_span<W: AsRef<[u64]>>(&mut self, cursor: JsonCursor<'_, W>) {
+        if let Some(bytes) = cursor
+            .text_range()
+            .and_then(|(start, end)| self.json.get(start..end))
+        {
+            // Scalars start and end on ASCII delimiters, so a span of valid
+            // UTF-8 input is itself valid UTF-8.
+            self.out.push_str(&String::from_utf8_lossy(bytes));
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.indent > 0 {
+            self.out.push('\n');
+            for _ in 0..depth * self.indent {
+                self.out.push(' ');
+            }
+        }
+    }
+}
+
+/// Serialize `value` as compact JSON with no whitespace (jq's `tojson`).
+///
+/// Strings escape `"`, `\\`, `\n`, `\r`, `\t` and other control characters
+/// (as `\u00xx`); everything else, including non-ASCII text, is written as
+/// is. Floats use the shortest text that parses back to the same `f64`. JSON
+/// has no spelling for NaN or the infinities: as in jq, NaN becomes `null`
+/// and the infinities become the largest finite `f64`,
+/// `1.7976931348623157e+308` and its negation. Object keys keep their
+/// insertion order.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::jq::OwnedValue;
+/// use succinctly::json::format::compact_serialize;
+///
+/// let value = OwnedValue::Array(vec![OwnedValue::Int(1), OwnedValue::String("a\"b".into())]);
+/// assert_eq!(compact_serialize(&value), r#"[1,"a\"b"]"#);
+/// ```
+pub fn compact_serialize(value: &OwnedValue) -> String {
+    serialize(value, 0, false)
+}
+
+/// Serialize `value` as JSON, indenting nested values by `indent` spaces and
+/// optionally sorting object keys (jq's `--indent n` and `-S`).
+///
+/// An `indent` of 0 produces the same compact output as
+/// [`compact_serialize`]. Otherwise the layout matches jq's: one element or
+/// field per line, `": "` after keys, and `[]`/`{}` for empty containers.
+/// Keys sort by their UTF-8 bytes.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::jq::OwnedValue;
+/// use succinctly::json::format::serialize;
+///
+/// let value = OwnedValue::object_from([
+///     ("b".to_string(), OwnedValue::array()),
+///     ("a".to_string(), OwnedValue::Array(vec![OwnedValue::Null])),
+/// ]);
+/// assert_eq!(serialize(&value, 2, true), "{\n  \"a\": [\n    null\n  ],\n  \"b\": []\n}");
+/// ```
+pub fn serialize(value: &OwnedValue, indent: usize, sort_keys: bool) -> String {
+    let mut serializer = Serializer {
+        indent,
+        sort_keys,
+        out: String::new(),
+    };
+    serializer.write_value(value, 0);
+    serializer.out
+}
+
+struct Serializer {
+    indent: usize,
+    sort_keys: bool,
+    out: String,
+}
+
+impl Serializer {
+    fn write_value(&mut self, value: &OwnedValue, depth: usize) {
+        match value {
+            OwnedValue::Null => self.out.push_str("null"),
+            OwnedValue::Bool(true) => self.out.push_str("true"),
+            OwnedValue::Bool(false) => self.out.push_str("false"),
+            OwnedValue::Int(n) => {
+                let _ = write!(self.out, "{n}");
+            }
+            OwnedValue::Float(f) if f.is_nan() => self.out.push_str("null"),
+            OwnedValue::Float(f) if f.is_infinite() => self.out.push_str(if *f > 0.0 {
+                "1.7976931348623157e+308"
+            } else {
+                "-1.7976931348623157e+308"
+            }),
+            OwnedValue::Float(f) => {
+                let _ = write!(self.out, "{f}");
+            }
+            OwnedValue::String(s) => write_string(s, &mut self.out),
+            OwnedValue::Array(elements) => {
+                self.out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(depth + 1);
+                    self.write_value(element, depth + 1);
+                }
+                if !elements.is_empty() {
+                    self.newline(depth);
+                }
+                self.out.push(']');
+            }
+            OwnedValue::Object(fields) => {
+                let mut entries: Vec<_> = fields.iter().collect();
+                if self.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+                self.out.push('{');
+                for (i, (key, field)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(depth + 1);
+                    write_string(key, &mut self.out);
+                    self.out.push(':');
+                    if self.indent > 0 {
+                        self.out.push(' ');
+                    }
+                    self.write_value(field, depth + 1);
+                }
+                if !entries.is_empty() {
+                    self.newline(depth);
+                }
+                self.out.push('}');
+            }
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.indent > 0 {
+            self.out.push('\n');
+            for _ in 0..depth * self.indent {
+                self.out.push(' ');
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonIndex;
+
+    fn pretty(json: &[u8], indent: usize) -> String {
+        let index = JsonIndex::build(json);
+        pretty_print(index.root(json), json, indent)
+    }
+
+    #[test]
+    fn test_scalars_copied_verbatim() {
+        assert_eq!(pretty(b"1e10", 2), "1e10");
+        assert_eq!(pretty(b"-0.50", 2), "-0.50");
+        assert_eq!(pretty(br#""caf\u00e9""#, 2), r#""caf\u00e9""#);
+        assert_eq!(pretty("[\"café\"]".as_bytes(), 0), "[\"café\"]");
+        assert_eq!(pretty(b"true", 2), "true");
+        assert_eq!(pretty(b"null", 2), "null");
+    }
+
+    #[test]
+    fn test_empty_containers() {
+        assert_eq!(pretty(b"[]", 2), "[]");
+        assert_eq!(pretty(b"{ }", 2), "{}");
+        assert_eq!(
+            pretty(br#"{"a": [], "b": {}}"#, 2),
+            "{\n  \"a\": [],\n  \"b\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn test_nested_indent() {
+        let json = br#"{"a": [1, {"b": null}], "c": "x"}"#;
+        assert_eq!(
+            pretty(json, 2),
+            "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    }\n  ],\n  \"c\": \"x\"\n}"
+        );
+        assert_eq!(
+            pretty(b"[1,[2]]", 4),
+            "[\n    1,\n    [\n        2\n    ]\n]"
+        );
+    }
+
+    #[test]
+    fn test_indent_zero_is_compact() {
+        let json = b"{ \"a\" : [ 1 , 2 ] ,\n \"b\" : { } }";
+        assert_eq!(pretty(json, 0), r#"{"a":[1,2],"b":{}}"#);
+    }
+
+    #[test]
+    fn test_subtree_and_index_reuse() {
+        let json = br#"{"a": [1, 2], "b": {"c": 3}}"#;
+        let index = JsonIndex::build(json);
+        let root = index.root(json);
+        let a = root.first_child().unwrap().next_sibling().unwrap();
+        let b = a.next_sibling().unwrap().next_sibling().unwrap();
+        assert_eq!(pretty_print(a, json, 2), "[\n  1,\n  2\n]");
+        assert_eq!(pretty_print(b, json, 2), "{\n  \"c\": 3\n}");
+        assert_eq!(pretty_print(root, json, 0), r#"{"a":[1,2],"b":{"c":3}}"#);
+    }
+
+    #[test]
+    fn test_matches_serde_json_pretty() {
+        // Keys are sorted so the comparison holds with or without serde_json's
+        // `preserve_order` feature.
+        let json =
+            br#"{"n":null,"name":"Alice","nested":{"deep":[[1,2],[3]],"e":[]},"tags":["x","y"]}"#;
+        let value: serde_json::Value = serde_json::from_slice(json).unwrap();
+        assert_eq!(
+            pretty(json, 2),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(pretty(b"", 2), "");
+    }
+
+    #[test]
+    fn test_deep_nesting_no_stack_overflow() {
+        let depth = 10_000;
+        let mut json = vec![b'['; depth];
+        json.extend(core::iter::repeat(b']').take(depth));
+        let out = pretty(&json, 0);
+        assert_eq!(out.as_bytes(), &json[..]);
+    }
+
+    #[test]
+    fn test_compact_serialize() {
+        let mut fields = indexmap::IndexMap::new();
+        fields.insert("b".to_string(), OwnedValue::Float(1.5));
+        fields.insert("a\"".to_string(), OwnedValue::Array(vec![]));
+        let value = OwnedValue::Array(vec![
+            OwnedValue::Null,
+            OwnedValue::Bool(true),
+            OwnedValue::Int(-3),
+            OwnedValue::Float(f64::NAN),
+            OwnedValue::Float(f64::INFINITY),
+            OwnedValue::Float(f64::NEG_INFINITY),
+            OwnedValue::String("tab\t\u{1}é".to_string()),
+            OwnedValue::Object(fields),
+        ]);
+        assert_eq!(
+            compact_serialize(&value),
+            r#"[null,true,-3,null,1.7976931348623157e+308,-1.7976931348623157e+308,"tab\t\u0001é",{"b":1.5,"a\"":[]}]"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_indented_and_sorted() {
+        let value = OwnedValue::object_from([
+            ("z".to_string(), OwnedValue::object()),
+            (
+                "a".to_string(),
+                OwnedValue::Array(vec![OwnedValue::Int(1), OwnedValue::object_from([])]),
+            ),
+        ]);
+        assert_eq!(serialize(&value, 0, false), r#"{"z":{},"a":[1,{}]}"#);
+        assert_eq!(serialize(&value, 0, true), r#"{"a":[1,{}],"z":{}}"#);
+        assert_eq!(
+            serialize(&value, 4, false),
+            "{\n    \"z\": {},\n    \"a\": [\n        1,\n        {}\n    ]\n}"
+        );
+        assert_eq!(serialize(&OwnedValue::Int(1), 2, true), "1");
+    }
+
+    #[test]
+    fn test_serialize_floats_round_trip() {
+        for f in [0.1, -1.5e-7, 5e-324, f64::MAX, 1.0 / 3.0] {
+            let text = compact_serialize(&OwnedValue::Float(f));
+            assert_eq!(text.parse::<f64>().unwrap(), f, "{text}");
+        }
+    }
+}
@@ -50,6 +50,16 @@ use std::borrow::Cow;
 use crate::trees::BalancedParens;
 use crate::util::broadword::select_in_word;
 
+#[cfg(feature = "std")]
+mod field_index;
+#[cfg(feature = "std")]
+mod persist;
+#[cfg(feature = "std")]
+mod read;
+
+#[cfg(feature = "std")]
+pub use field_index::JsonFieldIndex;
+
 // ============================================================================
 // JsonIndex: Holds the IB and BP index structures
 // ============================================================================
@@ -158,31 +168,103 @@ impl JsonIndex<Vec<u64>> {
             "JsonIndex supports inputs up to u32::MAX (4294967295) bytes; got {} bytes (#188)",
             json.len()
         );
-        #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+        #[cfg(any(
+            target_arch = "aarch64",
+            target_arch = "x86_64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
         let semi = crate::json::simd::build_semi_index_standard(json);
 
-        #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+        #[cfg(not(any(
+            target_arch = "aarch64",
+            target_arch = "x86_64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        )))]
         let semi = crate::json::standard::build_semi_index(json);
 
+        Self::from_semi_index_words(json, semi.ib, semi.bp)
+    }
+
+    /// Assemble an index from the IB and BP words of a complete semi-index.
+    ///
+    /// Shared by [`build`](Self::build) and the parallel builder, which
+    /// produces the same words by concatenating per-chunk results.
+    pub(super) fn from_semi_index_words(json: &[u8], ib: Vec<u64>, bp: Vec<u64>) -> Self {
         let ib_len = json.len();
 
         // Count actual BP bits
-        let bp_bit_count = count_bp_bits(&semi.bp);
+        let bp_bit_count = count_bp_bits(&bp);
 
         // Build cumulative popcount index for IB
-        let ib_rank = build_ib_rank(&semi.ib);
+        let ib_rank = build_ib_rank(&ib);
 
         // Build newline index for fast line/column lookup
         let newlines = build_newline_index(json);
 
         Self {
-            ib: semi.ib,
+            ib,
             ib_len,
             ib_rank,
-            bp: BalancedParens::new(semi.bp, bp_bit_count),
+            bp: BalancedParens::new(bp, bp_bit_count),
             newlines,
         }
     }
+
+    /// Build a stand-alone index over the value under `cursor`.
+    ///
+    /// The result indexes `&json[start..end]`, where `(start, end)` is
+    /// `cursor.text_range()`, and must be used with that slice as its text:
+    /// its root is the cursor's value. `json` must be the text `cursor` was
+    /// created over.
+    ///
+    /// Rather than re-scanning the bytes, this copies the subtree's bits out
+    /// of the existing index: the BP range from the cursor's open paren to its
+    /// `find_close`, and the IB bits of the text range. Interest bits and BP
+    /// opens are in one-to-one correspondence, so the two slices form a
+    /// complete semi-index. The cost is O(subtree size), independent of the
+    /// size of the enclosing document.
+    ///
+    /// Returns `None` if the cursor does not point at a value, or the value
+    /// is unterminated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::{JsonIndex, StandardJson};
+    ///
+    /// let json = br#"{"meta": {"n": 2}, "results": [{"id": 1}, {"id": 2}]}"#;
+    /// let index = JsonIndex::build(json);
+    /// let results = index.root(json).first_child().unwrap()
+    ///     .next_sibling().unwrap().next_sibling().unwrap().next_sibling().unwrap();
+    ///
+    /// let (start, end) = results.text_range().unwrap();
+    /// let sub = JsonIndex::from_cursor_subrange(results, json).unwrap();
+    /// let text = &json[start..end];
+    /// let StandardJson::Array(elements) = sub.root(text).value() else { panic!() };
+    /// assert_eq!(elements.count(), 2);
+    /// ```
+    pub fn from_cursor_subrange<W: AsRef<[u64]>>(
+        cursor: JsonCursor<'_, W>,
+        json: &[u8],
+    ) -> Option<Self> {
+        let (start, end) = cursor.text_range()?;
+        let bp = cursor.index.bp();
+        let open = cursor.bp_pos;
+        let close = bp.find_close(open)?;
+        let bp_len = close - open + 1;
+
+        let text = &json[start..end];
+        let ib = crate::bits::extract_bits(cursor.index.ib(), start, text.len());
+        let ib_rank = build_ib_rank(&ib);
+
+        Some(Self {
+            ib,
+            ib_len: text.len(),
+            ib_rank,
+            bp: BalancedParens::new(crate::bits::extract_bits(bp.words(), open, bp_len), bp_len),
+            newlines: build_newline_index(text),
+        })
+    }
 }
 
 impl<W: AsRef<[u64]>> JsonIndex<W> {
@@ -537,6 +619,165 @@ fn count_bp_bits(bp_words: &[u64]) -> usize {
     total_ones * 2
 }
 
+// ============================================================================
+// JsonStats: Document summary computed from the semi-index
+// ============================================================================
+
+/// Summary statistics for a JSON document.
+///
+/// Produced by [`JsonIndex::stats`]. Counts cover values only: object keys
+/// are not counted as strings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonStats {
+    /// Number of objects
+    pub objects: usize,
+    /// Number of arrays
+    pub arrays: usize,
+    /// Number of strings (excluding object keys)
+    pub strings: usize,
+    /// Number of numbers
+    pub numbers: usize,
+    /// Number of booleans
+    pub booleans: usize,
+    /// Number of nulls
+    pub nulls: usize,
+    /// Maximum container nesting depth (a scalar root has depth 0, `[]` has depth 1)
+    pub max_depth: usize,
+    /// Total number of values (the sum of the per-type counts)
+    pub total_nodes: usize,
+}
+
+impl<W: AsRef<[u64]>> JsonIndex<W> {
+    /// Compute summary statistics for the document in a single DFS pass.
+    ///
+    /// The traversal walks the BP tree with an explicit stack (so deeply
+    /// nested input cannot overflow the call stack) and only reads the first
+    /// byte of each value to classify it. Nothing is decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The original JSON text (must match the text used to build the index)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"a": [1, "x", null], "b": {"c": true}}"#;
+    /// let stats = JsonIndex::build(json).stats(json);
+    /// assert_eq!(stats.objects, 2);
+    /// assert_eq!(stats.arrays, 1);
+    /// assert_eq!(stats.max_depth, 2);
+    /// assert_eq!(stats.total_nodes, 7);
+    /// ```
+    pub fn stats(&self, json: &[u8]) -> JsonStats {
+        let mut stats = JsonStats::default();
+        // (cursor, number of containers enclosing the cursor's value)
+        let mut stack = vec![(self.root(json), 0usize)];
+
+        while let Some((cursor, depth)) = stack.pop() {
+            let Some(&byte) = cursor.text_position().and_then(|pos| json.get(pos)) else {
+                continue;
+            };
+
+            match byte {
+                b'{' => {
+                    stats.objects += 1;
+                    stats.max_depth = stats.max_depth.max(depth + 1);
+                    // Object children alternate key, value: only visit values
+                    let mut next_key = cursor.first_child();
+                    while let Some(key) = next_key {
+                        let Some(value) = key.next_sibling() else {
+                            break;
+                        };
+                        stack.push((value, depth + 1));
+                        next_key = value.next_sibling();
+                    }
+                }
+                b'[' => {
+                    stats.arrays += 1;
+                    stats.max_depth = stats.max_depth.max(depth + 1);
+                    stack.extend(cursor.children().map(|child| (child, depth + 1)));
+                }
+                b'"' => stats.strings += 1,
+                b't' | b'f' => stats.booleans += 1,
+                b'n' => stats.nulls += 1,
+                b'-' | b'0'..=b'9' => stats.numbers += 1,
+                _ => {}
+            }
+        }
+
+        stats.total_nodes = stats.objects
+            + stats.arrays
+            + stats.strings
+            + stats.numbers
+            + stats.booleans
+            + stats.nulls;
+        stats
+    }
+}
+
+/// Memory occupied by each part of a [`JsonIndex`].
+///
+/// Produced by [`JsonIndex::memory_usage`]. Bit vectors are reported by their
+/// length in bits; their words, owned or borrowed, are counted in
+/// `total_bytes` along with every auxiliary index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexMemoryUsage {
+    /// Length of the balanced parentheses in bits
+    pub bp_bits: usize,
+    /// Bytes of the BP rank directory
+    pub bp_rank_bytes: usize,
+    /// Bytes of the BP select index (0, as JSON navigation does not use one)
+    pub bp_select_bytes: usize,
+    /// Bytes of the BP min-excess indices used to find matching parentheses
+    pub bp_excess_bytes: usize,
+    /// Length of the interest bits in bits (the JSON text length)
+    pub ib_bits: usize,
+    /// Bytes of the IB cumulative popcount directory
+    pub ib_rank_bytes: usize,
+    /// Bytes of the newline index used for line/column lookup, with its
+    /// rank and select indices
+    pub newline_bytes: usize,
+    /// Bytes of the whole index: the IB and BP words plus all of the above
+    pub total_bytes: usize,
+}
+
+impl<W: AsRef<[u64]>> JsonIndex<W> {
+    /// Report how much memory each part of the index occupies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"a": [1, 2, 3]}"#;
+    /// let usage = JsonIndex::build(json).memory_usage();
+    /// assert_eq!(usage.ib_bits, json.len());
+    /// assert_eq!(usage.bp_bits, 12);
+    /// assert!(usage.total_bytes >= usage.bp_rank_bytes + usage.ib_rank_bytes);
+    /// ```
+    pub fn memory_usage(&self) -> IndexMemoryUsage {
+        let mut usage = IndexMemoryUsage {
+            bp_bits: self.bp.len(),
+            bp_rank_bytes: self.bp.rank_heap_size(),
+            bp_select_bytes: self.bp.select_heap_size(),
+            bp_excess_bytes: self.bp.excess_heap_size(),
+            ib_bits: self.ib_len,
+            ib_rank_bytes: self.ib_rank.len() * 4,
+            newline_bytes: self.newlines.heap_size(),
+            total_bytes: 0,
+        };
+        usage.total_bytes = (self.bp.words().len() + self.ib.as_ref().len()) * 8
+            + usage.bp_rank_bytes
+            + usage.bp_select_bytes
+            + usage.bp_excess_bytes
+            + usage.ib_rank_bytes
+            + usage.newline_bytes;
+        usage
+    }
+}
+
 // ============================================================================
 // JsonCursor: Position in the JSON structure
 // ============================================================================
@@ -726,6 +967,7 @@ impl<'a, W: AsRef<[u64]>> JsonCursor<'a, W> {
     ///
     /// For containers (arrays/objects), uses BP structure to find the closing bracket.
     /// For scalars (strings/numbers/bools/null), scans text to find value end.
+    #[allow(clippy::collapsible_match)] // STYLE-0004: newer clippy flags the nested literal checks; left for a focused cleanup
     pub fn text_range(&self) -> Option<(usize, usize)> {
         let start = self.text_position()?;
 
@@ -1051,7 +1293,8 @@ impl<'a, W: AsRef<[u64]>> JsonFields<'a, W> {
     /// Find a field by name.
     ///
     /// Returns the value of the first field with the given name,
-    /// or `None` if not found.
+    /// or `None` if not found. This scans the fields; for many lookups in a
+    /// large object, hash them once with [`into_index`](Self::into_index).
     pub fn find(&self, name: &str) -> Option<StandardJson<'a, W>> {
         let mut fields = *self;
         while let Some((field, rest)) = fields.uncons() {
@@ -1191,41 +1434,26 @@ impl<'a, W: AsRef<[u64]>> JsonElements<'a, W> {
         Some((element_cursor, rest))
     }
 
-    /// Get element by index (slow path).
-    ///
-    /// Note: This is O(n) as it iterates through elements, calling `value()`
-    /// for each intermediate element.
+    /// Get element by index.
     ///
-    /// For better performance with random access, use [`get_fast`](Self::get_fast) which
-    /// only calls `value()` on the target element.
+    /// Finds the element with [`BalancedParens::nth_sibling`], which skips
+    /// nested elements through the min-excess indices and counts runs of
+    /// scalar elements a byte of the BP at a time, without visiting the
+    /// elements before it one by one. Only the target element's value is
+    /// decoded.
     pub fn get(&self, index: usize) -> Option<StandardJson<'a, W>> {
-        let mut elements = *self;
-        for _ in 0..index {
-            let (_, rest) = elements.uncons()?;
-            elements = rest;
-        }
-        elements.uncons().map(|(elem, _)| elem)
+        let cursor = self.element_cursor?;
+        let bp_pos = cursor.index.bp().nth_sibling(cursor.bp_pos, index)?;
+        Some(JsonCursor::from_bp_position(cursor.index, cursor.text, bp_pos).value())
     }
 
-    /// Get element by index (fast path for random access).
-    ///
-    /// This method navigates to the target element using only BP operations
-    /// (`next_sibling`), avoiding expensive `text_position()` calls for
-    /// intermediate elements.
+    /// Get element by index.
     ///
-    /// Complexity: O(n) BP operations + O(log n) IB select for final element.
-    /// This is faster than `get()` which does O(n) IB selects.
+    /// The same as [`get`](Self::get), which no longer visits intermediate
+    /// elements; kept for compatibility.
     #[inline]
     pub fn get_fast(&self, index: usize) -> Option<StandardJson<'a, W>> {
-        let mut cursor = self.element_cursor?;
-
-        // Navigate to the target element using only BP operations
-        for _ in 0..index {
-            cursor = cursor.next_sibling()?;
-        }
-
-        // Only call value() (which uses text_position/ib_select) on the target
-        Some(cursor.value())
+        self.get(index)
     }
 }
 
@@ -1344,7 +1572,7 @@ impl<'a> JsonString<'a> {
 /// Decode JSON string escape sequences.
 ///
 /// Handles: \\, \", \/, \b, \f, \n, \r, \t, and \uXXXX (including surrogate pairs)
-fn decode_escapes(bytes: &[u8]) -> Result<String, JsonError> {
+pub(crate) fn decode_escapes(bytes: &[u8]) -> Result<String, JsonError> {
     let mut result = String::with_capacity(bytes.len());
     let mut i = 0;
 
@@ -1450,7 +1678,8 @@ fn parse_hex4(hex: &[u8]) -> Result<u16, JsonError> {
 
 /// A JSON number that hasn't been parsed yet.
 ///
-/// Call `as_i64()` or `as_f64()` to parse the number.
+/// Call `as_i64()` or `as_f64()` to parse the number, or `as_exact_decimal()`
+/// to pass its text on without losing precision.
 #[derive(Clone, Copy, Debug)]
 pub struct JsonNumber<'a> {
     text: &'a [u8],
@@ -1464,6 +1693,53 @@ impl<'a> JsonNumber<'a> {
         &self.text[self.start..end]
     }
 
+    /// The text from the start of the number to the end of the input.
+    pub(crate) fn text_from_start(&self) -> &'a [u8] {
+        &self.text[self.start..]
+    }
+
+    /// Get the number's text exactly as written, without parsing it.
+    ///
+    /// Unlike [`as_f64`](Self::as_f64), this keeps every digit of values
+    /// such as `1.0000000000000001` or `12345678901234567890`, for handing to
+    /// a decimal library or database. Returns `None` if the text is not a
+    /// valid RFC 8259 number (e.g. `01` or `1.`), which the index accepts.
+    /// [`raw_bytes`](Self::raw_bytes) returns the same text unchecked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::{JsonIndex, StandardJson};
+    ///
+    /// let json = b"[1.0000000000000001, 01]";
+    /// let index = JsonIndex::build(json);
+    /// let numbers: Vec<_> = index
+    ///     .root(json)
+    ///     .children()
+    ///     .map(|c| match c.value() {
+    ///         StandardJson::Number(n) => n.as_exact_decimal(),
+    ///         _ => unreachable!(),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(numbers, [Some("1.0000000000000001"), None]);
+    /// ```
+    pub fn as_exact_decimal(&self) -> Option<&'a str> {
+        let bytes = self.raw_bytes();
+        crate::json::validate::validate(bytes).ok()?;
+        core::str::from_utf8(bytes).ok()
+    }
+
+    /// Returns true if the number has no fraction or exponent.
+    ///
+    /// This only inspects the text: `1e2` is not an integer here, and an
+    /// integer too large for `i64` still is.
+    pub fn is_integer(&self) -> bool {
+        !self
+            .raw_bytes()
+            .iter()
+            .any(|&b| matches!(b, b'.' | b'e' | b'E'))
+    }
+
     /// Parse as i64.
     pub fn as_i64(&self) -> Result<i64, JsonError> {
         let bytes = self.raw_bytes();
@@ -1534,6 +1810,127 @@ pub type OwnedJsonCursor<'a> = JsonCursor<'a, Vec<u64>>;
 /// JSON cursor with borrowed index.
 pub type BorrowedJsonCursor<'a> = JsonCursor<'a, &'a [u64]>;
 
+// ============================================================================
+// Owned conversion
+// ============================================================================
+
+use crate::jq::OwnedValue;
+
+impl<W: AsRef<[u64]>> JsonCursor<'_, W> {
+    /// Copy the value at this cursor, and everything below it, into an
+    /// [`OwnedValue`] that can be modified or serialized independently of
+    /// the text and index.
+    ///
+    /// Numbers that fit in an `i64` become [`OwnedValue::Int`], others
+    /// [`OwnedValue::Float`]. Objects keep their keys in document order; a
+    /// repeated key keeps its first position and its last value. This is the
+    /// conversion the jq evaluator uses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::jq::{OwnedValue, PathComponent};
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"a": [1, 2.5], "b": "x"}"#;
+    /// let index = JsonIndex::build(json);
+    /// let value = index.root(json).to_owned_value();
+    /// let value = value.set_path(&[PathComponent::Key("b".into())], OwnedValue::Null);
+    /// assert_eq!(value.to_json(), r#"{"a":[1,2.5],"b":null}"#);
+    /// ```
+    pub fn to_owned_value(&self) -> OwnedValue {
+        to_owned_value_limited(&self.value(), usize::MAX)
+    }
+
+    /// Like [`to_owned_value`](Self::to_owned_value), but copies at most
+    /// `max_depth` levels of arrays and objects. Deeper containers are
+    /// replaced by the string `"…"`; scalars are always copied.
+    ///
+    /// With `max_depth == 0` a container cursor yields just `"…"`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"a": {"b": [1]}, "c": 2}"#;
+    /// let index = JsonIndex::build(json);
+    /// let value = index.root(json).to_owned_value_depth_limit(2);
+    /// assert_eq!(value.to_json(), r#"{"a":{"b":"…"},"c":2}"#);
+    /// ```
+    pub fn to_owned_value_depth_limit(&self, max_depth: usize) -> OwnedValue {
+        to_owned_value_limited(&self.value(), max_depth)
+    }
+}
+
+/// Convert `value` to an [`OwnedValue`], replacing containers below
+/// `depth` more levels with `"…"`.
+pub(crate) fn to_owned_value_limited<W: AsRef<[u64]>>(
+    value: &StandardJson<'_, W>,
+    depth: usize,
+) -> OwnedValue {
+    to_owned_value_with(value, depth, number_to_owned)
+}
+
+/// An [`OwnedValue`] for a number: an `Int` if it fits in `i64`, else a
+/// `Float`.
+pub(crate) fn number_to_owned(n: &JsonNumber<'_>) -> OwnedValue {
+    if let Ok(i) = n.as_i64() {
+        OwnedValue::Int(i)
+    } else if let Ok(f) = n.as_f64() {
+        OwnedValue::Float(f)
+    } else {
+        // Fallback - shouldn't happen for valid JSON
+        OwnedValue::Float(0.0)
+    }
+}
+
+/// [`to_owned_value_limited`] with numbers converted by `number`.
+pub(crate) fn to_owned_value_with<W: AsRef<[u64]>>(
+    value: &StandardJson<'_, W>,
+    depth: usize,
+    number: fn(&JsonNumber<'_>) -> OwnedValue,
+) -> OwnedValue {
+    match value {
+        StandardJson::Null => OwnedValue::Null,
+        StandardJson::Bool(b) => OwnedValue::Bool(*b),
+        StandardJson::Number(n) => number(n),
+        StandardJson::String(s) => {
+            if let Ok(cow) = s.as_str() {
+                OwnedValue::String(cow.into_owned())
+            } else {
+                OwnedValue::String(String::new())
+            }
+        }
+        StandardJson::Array(_) | StandardJson::Object(_) if depth == 0 => {
+            OwnedValue::String(String::from("…"))
+        }
+        StandardJson::Array(elements) => OwnedValue::Array(
+            (*elements)
+                .map(|e| to_owned_value_with(&e, depth - 1, number))
+                .collect(),
+        ),
+        StandardJson::Object(fields) => {
+            let mut value = OwnedValue::object();
+            if let OwnedValue::Object(map) = &mut value {
+                for field in *fields {
+                    // Get the key as a string
+                    if let StandardJson::String(key_str_val) = field.key() {
+                        if let Ok(cow) = key_str_val.as_str() {
+                            map.insert(
+                                cow.into_owned(),
+                                to_owned_value_with(&field.value(), depth - 1, number),
+                            );
+                        }
+                    }
+                }
+            }
+            value
+        }
+        StandardJson::Error(_) => OwnedValue::Null,
+    }
+}
+
 // ============================================================================
 // Document trait implementations
 // ============================================================================
@@ -1640,7 +2037,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for StandardJson<'a, W> {
 
     fn as_f64(&self) -> Option<f64> {
         match self {
-            StandardJson::Number(n) => n.as_f64().ok(),
+            StandardJson::Number(n) => crate::jq::number_as_f64(n).ok(),
             _ => None,
         }
     }
@@ -2350,6 +2747,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_array_get_matches_iteration() {
+        // Scalars, small and deeply nested containers, long enough to span
+        // several BP words
+        let items: Vec<String> = (0..1000)
+            .map(|i| match i % 7 {
+                0 => format!("[{}{}]", "[".repeat(i % 90), "]".repeat(i % 90)),
+                1 => format!("{{\"k\": {i}}}"),
+                _ => i.to_string(),
+            })
+            .collect();
+        let json = format!("[{}]", items.join(","));
+        let index = JsonIndex::build(json.as_bytes());
+        let StandardJson::Array(elements) = index.root(json.as_bytes()).value() else {
+            panic!("expected array");
+        };
+
+        let kind = |value: StandardJson<'_>| match value {
+            StandardJson::Number(n) => String::from_utf8(n.raw_bytes().to_vec()).unwrap(),
+            StandardJson::Array(_) => "array".into(),
+            StandardJson::Object(_) => "object".into(),
+            _ => "other".into(),
+        };
+        for (i, expected) in elements.enumerate() {
+            let Some(value) = elements.get(i) else {
+                panic!("missing element {i}");
+            };
+            assert_eq!(kind(value), kind(expected), "element {i}");
+        }
+        assert!(elements.get(1000).is_none());
+
+        // From the second element
+        let (_, rest) = elements.uncons().unwrap();
+        assert!(matches!(rest.get(0), Some(StandardJson::Object(_))));
+        assert!(matches!(rest.get(998), Some(StandardJson::Number(_))));
+        assert!(rest.get(999).is_none());
+    }
+
     #[test]
     fn test_nested_object() {
         let json = br#"{"person": {"name": "Dave"}}"#;
@@ -2414,6 +2849,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_number_exact_decimal() {
+        let json = b"[12345678901234567890, -0.5e-3, 1.0000000000000001, 01, 1.]";
+        let index = JsonIndex::build(json);
+        let numbers: Vec<_> = index
+            .root(json)
+            .children()
+            .map(|c| match c.value() {
+                StandardJson::Number(n) => n,
+                _ => panic!("expected number"),
+            })
+            .collect();
+
+        assert_eq!(numbers[0].as_exact_decimal(), Some("12345678901234567890"));
+        assert!(numbers[0].is_integer());
+        assert!(numbers[0].as_i64().is_err());
+        assert_eq!(numbers[1].as_exact_decimal(), Some("-0.5e-3"));
+        assert!(!numbers[1].is_integer());
+        assert_eq!(numbers[2].as_exact_decimal(), Some("1.0000000000000001"));
+        assert_eq!(numbers[2].as_f64().unwrap(), 1.0);
+        // Accepted by the index but not RFC 8259 numbers
+        assert_eq!(numbers[3].as_exact_decimal(), None);
+        assert_eq!(numbers[3].raw_bytes(), b"01");
+        assert_eq!(numbers[4].as_exact_decimal(), None);
+    }
+
+    #[test]
+    fn test_number_rejects_nan_text() {
+        // JSON has no NaN, so none of its spellings parse as a number
+        for json in [&b"-NaN"[..], b"NaN", b"-nan"] {
+            let index = JsonIndex::build(json);
+            if let StandardJson::Number(n) = index.root(json).value() {
+                assert!(n.as_f64().is_err(), "{json:?}");
+            }
+        }
+    }
+
     #[test]
     fn test_float_number() {
         let json = b"1.23456";
@@ -3137,6 +3609,78 @@ mod tests {
         assert_eq!(range, (8, 25));
     }
 
+    #[test]
+    fn test_from_cursor_subrange_matches_rebuild() {
+        let mut json = String::from(r#"{"padding": "x", "meta": {"total": 300}, "results": ["#);
+        for i in 0..300 {
+            if i > 0 {
+                json.push_str(",\n  ");
+            }
+            json.push_str(&format!(
+                r#"{{"id": {i}, "name": "item \"{i}\"", "tags": [1, true]}}"#
+            ));
+        }
+        json.push_str(r#"], "tail": null}"#);
+        let json = json.as_bytes();
+        let index = JsonIndex::build(json);
+
+        // The third field, "results"
+        let results = index
+            .root(json)
+            .value()
+            .as_object()
+            .unwrap()
+            .nth(2)
+            .unwrap()
+            .value_cursor();
+        let (start, end) = results.text_range().unwrap();
+        let text = &json[start..end];
+
+        let sub = JsonIndex::from_cursor_subrange(results, json).unwrap();
+        let rebuilt = JsonIndex::build(text);
+        assert_eq!(sub.ib(), rebuilt.ib());
+        assert_eq!(sub.ib_len(), rebuilt.ib_len());
+        assert_eq!(sub.bp().len(), rebuilt.bp().len());
+        assert_eq!(sub.bp().words(), rebuilt.bp().words());
+        let last = text.len() - 1;
+        assert_eq!(sub.to_line_column(last), rebuilt.to_line_column(last));
+        assert_eq!(sub.to_line_column(last).0, 300);
+
+        let StandardJson::Array(elements) = sub.root(text).value() else {
+            panic!("expected array");
+        };
+        let item = elements.get(123).unwrap().as_object().unwrap();
+        let Some(StandardJson::Number(id)) = item.find("id") else {
+            panic!("expected id");
+        };
+        assert_eq!(id.as_i64().unwrap(), 123);
+        let Some(StandardJson::String(name)) = item.find("name") else {
+            panic!("expected name");
+        };
+        assert_eq!(name.as_str().unwrap(), "item \"123\"");
+    }
+
+    #[test]
+    fn test_from_cursor_subrange_scalar() {
+        let json = br#"[1, "two", 3.5]"#;
+        let index = JsonIndex::build(json);
+        let second = index
+            .root(json)
+            .first_child()
+            .unwrap()
+            .next_sibling()
+            .unwrap();
+        let (start, end) = second.text_range().unwrap();
+
+        let sub = JsonIndex::from_cursor_subrange(second, json).unwrap();
+        let text = &json[start..end];
+        assert_eq!(sub.bp().len(), 2);
+        let StandardJson::String(s) = sub.root(text).value() else {
+            panic!("expected string");
+        };
+        assert_eq!(s.as_str().unwrap(), "two");
+    }
+
     #[test]
     fn test_text_range_empty_object_value() {
         let json = br#"{"key": {}}"#;
@@ -3227,4 +3771,146 @@ mod tests {
         assert_eq!(range, (0, 9));
         assert_eq!(&json[range.0..range.1], b"[1, 2, 3]");
     }
+
+    #[test]
+    fn test_stats_counts_each_type() {
+        let json =
+            br#"{"s": "x", "n": -1.5e3, "t": true, "f": false, "z": null, "a": [], "o": {}}"#;
+        let index = JsonIndex::build(json);
+        let stats = index.stats(json);
+        assert_eq!(
+            stats,
+            JsonStats {
+                objects: 2,
+                arrays: 1,
+                strings: 1,
+                numbers: 1,
+                booleans: 2,
+                nulls: 1,
+                max_depth: 2,
+                total_nodes: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_keys_not_counted_as_strings() {
+        let json = br#"{"a": 1, "b": 2}"#;
+        let index = JsonIndex::build(json);
+        let stats = index.stats(json);
+        assert_eq!(stats.strings, 0);
+        assert_eq!(stats.numbers, 2);
+        assert_eq!(stats.total_nodes, 3);
+    }
+
+    #[test]
+    fn test_stats_max_depth() {
+        let json = br#"[[[1]], {"a": [[[]]]}]"#;
+        let index = JsonIndex::build(json);
+        assert_eq!(index.stats(json).max_depth, 5);
+
+        let json = b"42";
+        let index = JsonIndex::build(json);
+        let stats = index.stats(json);
+        assert_eq!(stats.max_depth, 0);
+        assert_eq!(stats.numbers, 1);
+        assert_eq!(stats.total_nodes, 1);
+    }
+
+    #[test]
+    fn test_stats_empty_input() {
+        let json = b"";
+        let index = JsonIndex::build(json);
+        assert_eq!(index.stats(json), JsonStats::default());
+    }
+
+    #[test]
+    fn test_stats_deep_nesting_no_stack_overflow() {
+        let depth = 10_000;
+        let mut json = vec![b'['; depth];
+        json.extend(core::iter::repeat(b']').take(depth));
+        let index = JsonIndex::build(&json);
+        let stats = index.stats(&json);
+        assert_eq!(stats.arrays, depth);
+        assert_eq!(stats.max_depth, depth);
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let json: Vec<u8> = format!(
+            "[{}]",
+            (0..10_000)
+                .map(|i| format!("{{\"id\": {i}}}"))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        )
+        .into_bytes();
+        let index = JsonIndex::build(&json);
+        let usage = index.memory_usage();
+        assert_eq!(usage.ib_bits, json.len());
+        assert_eq!(usage.bp_bits, index.bp().len());
+        assert_eq!(usage.bp_select_bytes, 0);
+        assert_eq!(usage.ib_rank_bytes, (index.ib().len() + 1) * 4);
+        assert!(usage.bp_rank_bytes > 0 && usage.bp_excess_bytes > 0);
+        assert!(usage.newline_bytes >= json.len().div_ceil(64) * 8);
+        assert_eq!(
+            usage.total_bytes,
+            (index.ib().len() + index.bp().words().len()) * 8
+                + usage.bp_rank_bytes
+                + usage.bp_excess_bytes
+                + usage.ib_rank_bytes
+                + usage.newline_bytes
+        );
+
+        // Loaded from parts, without a newline index
+        let parts = JsonIndex::from_parts(
+            index.ib().to_vec(),
+            index.ib_len(),
+            index.bp().words().to_vec(),
+            index.bp().len(),
+        );
+        assert_eq!(parts.memory_usage().newline_bytes, 0);
+    }
+
+    #[test]
+    fn test_to_owned_value() {
+        let json =
+            br#"{"n": null, "t": true, "i": -3, "f": 1e3, "s": "a\nb", "a": [[], {}], "n": 1}"#;
+        let index = JsonIndex::build(json);
+        let value = index.root(json).to_owned_value();
+        // The repeated key keeps its first position and last value
+        assert_eq!(
+            value.to_json(),
+            r#"{"n":1,"t":true,"i":-3,"f":1000,"s":"a\nb","a":[[],{}]}"#
+        );
+
+        let second = index
+            .root(json)
+            .first_child()
+            .unwrap()
+            .next_sibling()
+            .unwrap();
+        assert_eq!(second.to_owned_value(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_to_owned_value_depth_limit() {
+        let json = br#"[1, [2, [3, [4]]], {"k": {"j": 5}}]"#;
+        let index = JsonIndex::build(json);
+        let root = index.root(json);
+        let at = |depth| root.to_owned_value_depth_limit(depth).to_json();
+        assert_eq!(at(0), r#""…""#);
+        assert_eq!(at(1), r#"[1,"…","…"]"#);
+        assert_eq!(at(2), r#"[1,[2,"…"],{"k":"…"}]"#);
+        assert_eq!(at(4), r#"[1,[2,[3,[4]]],{"k":{"j":5}}]"#);
+        assert_eq!(root.to_owned_value_depth_limit(4), root.to_owned_value());
+
+        // Scalars are copied whatever the limit
+        let json = b"42";
+        let index = JsonIndex::build(json);
+        assert_eq!(
+            index.root(json).to_owned_value_depth_limit(0),
+            OwnedValue::Int(42)
+        );
+    }
 }
@@ -0,0 +1,130 @@
+//! Hashed field lookup for large objects (`std` feature).
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use super::{JsonCursor, JsonFields, StandardJson};
+
+/// The fields of one JSON object, hashed by key.
+///
+/// [`JsonFields::find`] scans the object on every call, which is the right
+/// trade-off for small objects and one-off lookups. When many fields of a
+/// large object are looked up, [`JsonFields::into_index`] scans it once and
+/// each [`get`](Self::get) is then a hash lookup.
+///
+/// As with `find`, the first of several fields with the same key wins.
+/// Keys are decoded once while building, borrowing the JSON text unless
+/// they contain escapes; fields whose keys fail to decode are left out.
+#[derive(Debug)]
+pub struct JsonFieldIndex<'a, W = Vec<u64>> {
+    fields: HashMap<Cow<'a, str>, JsonCursor<'a, W>>,
+}
+
+impl<'a, W: AsRef<[u64]>> JsonFields<'a, W> {
+    /// Hash the remaining fields by key, for repeated lookups.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::light::{JsonIndex, StandardJson};
+    ///
+    /// let json = br#"{"id": 7, "name": "Alice", "tags": []}"#;
+    /// let index = JsonIndex::build(json);
+    /// let StandardJson::Object(fields) = index.root(json).value() else {
+    ///     unreachable!()
+    /// };
+    ///
+    /// let fields = fields.into_index();
+    /// assert_eq!(fields.len(), 3);
+    /// assert!(matches!(fields.find("name"), Some(StandardJson::String(_))));
+    /// assert!(fields.get("missing").is_none());
+    /// ```
+    pub fn into_index(self) -> JsonFieldIndex<'a, W> {
+        let mut fields = HashMap::new();
+        for field in self {
+            if let StandardJson::String(key) = field.key() {
+                if let Ok(key) = key.as_str() {
+                    fields.entry(key).or_insert_with(|| field.value_cursor());
+                }
+            }
+        }
+        JsonFieldIndex { fields }
+    }
+}
+
+impl<'a, W: AsRef<[u64]>> JsonFieldIndex<'a, W> {
+    /// The cursor of the value with the given key.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<JsonCursor<'a, W>> {
+        self.fields.get(name).copied()
+    }
+
+    /// The value with the given key, like [`JsonFields::find`].
+    #[inline]
+    pub fn find(&self, name: &str) -> Option<StandardJson<'a, W>> {
+        self.get(name).map(|cursor| cursor.value())
+    }
+
+    /// The number of distinct keys.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether the object has no fields.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::light::{JsonFields, JsonIndex, StandardJson};
+
+    /// The BP position of the first value with the given key, by scanning.
+    fn scan(fields: JsonFields<'_>, name: &str) -> Option<usize> {
+        fields.into_iter().find_map(|field| match field.key() {
+            StandardJson::String(key) if key.as_str().ok()? == name => {
+                Some(field.value_cursor().bp_position())
+            }
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_matches_scan() {
+        let mut json = String::from("{");
+        for i in 0..200 {
+            json.push_str(&format!(r#""key{i}": {{"n": {i}}}, "#));
+        }
+        json.push_str(r#""esc\u0061ped": true, "key7": "duplicate"}"#);
+        let json = json.as_bytes();
+        let index = JsonIndex::build(json);
+        let StandardJson::Object(fields) = index.root(json).value() else {
+            panic!("expected object");
+        };
+
+        let hashed = fields.into_index();
+        assert_eq!(hashed.len(), 201);
+        for name in ["key0", "key7", "key199", "escaped", "key200", ""] {
+            let position = hashed.get(name).map(|cursor| cursor.bp_position());
+            assert_eq!(position, scan(fields, name), "{name}");
+        }
+        assert!(hashed.get("escaped").is_some());
+        // The first of duplicate keys wins, as with `find`
+        assert!(matches!(hashed.find("key7"), Some(StandardJson::Object(_))));
+    }
+
+    #[test]
+    fn test_empty_object() {
+        let json = b"{}";
+        let index = JsonIndex::build(json);
+        let StandardJson::Object(fields) = index.root(json).value() else {
+            panic!("expected object");
+        };
+        let hashed = fields.into_index();
+        assert!(hashed.is_empty());
+        assert!(hashed.get("").is_none());
+    }
+}
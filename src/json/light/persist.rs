@@ -0,0 +1,190 @@
+//! Saving a [`JsonIndex`] to disk and loading it back (`std` feature).
+//!
+//! The format is little-endian throughout:
+//!
+//! | Field     | Encoding                                          |
+//! |-----------|---------------------------------------------------|
+//! | magic     | `SIDX`                                            |
+//! | version   | `u16` ([`FORMAT_VERSION`])                        |
+//! | IB        | bit length `u64`, word count `u64`, words `[u64]` |
+//! | BP        | bit length `u64`, word count `u64`, words `[u64]` |
+//! | newlines  | bit length `u64`, word count `u64`, words `[u64]` |
+//!
+//! Only the bit vectors are stored. Their rank and select directories are
+//! derived data, rebuilt on load in one pass over the words: far cheaper
+//! than re-indexing the JSON, and the file does not depend on their layout.
+
+use std::io::{self, Read, Write};
+
+use super::JsonIndex;
+use crate::bits::BitVec;
+use crate::util::persist::{read_bits, read_header, write_bits, write_header};
+
+/// The first four bytes of a serialized index.
+const MAGIC: [u8; 4] = *b"SIDX";
+
+/// The format version written by [`JsonIndex::serialize`].
+const FORMAT_VERSION: u16 = 1;
+
+impl<W: AsRef<[u64]>> JsonIndex<W> {
+    /// Write the index to `writer`, to be loaded with
+    /// [`deserialize`](JsonIndex::deserialize) instead of re-indexing the
+    /// JSON text.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = br#"{"a": [1, 2]}"#;
+    /// let mut bytes = Vec::new();
+    /// JsonIndex::build(json).serialize(&mut bytes).unwrap();
+    ///
+    /// let index = JsonIndex::deserialize(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!(index.root(json).children().count(), 2);
+    /// ```
+    pub fn serialize(&self, writer: &mut impl Write) -> io::Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        write_bits(writer, self.ib_len, self.ib.as_ref())?;
+        write_bits(writer, self.bp.len(), self.bp.words())?;
+        write_bits(writer, self.newlines.len(), self.newlines.words())
+    }
+}
+
+impl JsonIndex<Vec<u64>> {
+    /// Read an index written by [`serialize`](JsonIndex::serialize).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidData`] if the input does not start
+    /// with the `SIDX` magic, has a different format version, or holds a bit
+    /// vector with fewer words than its length needs or more than
+    /// `u32::MAX` bits; [`io::ErrorKind::UnexpectedEof`] if it is truncated;
+    /// and any other error from `reader`.
+    pub fn deserialize(reader: &mut impl Read) -> io::Result<Self> {
+        read_header(reader, MAGIC, FORMAT_VERSION, "JsonIndex")?;
+
+        let (ib_len, ib) = read_bits(reader)?;
+        let (bp_len, bp) = read_bits(reader)?;
+        let (newlines_len, newlines) = read_bits(reader)?;
+        Ok(Self::from_parts_with_newlines(
+            ib,
+            ib_len,
+            bp,
+            bp_len,
+            BitVec::from_words(newlines, newlines_len),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::light::StandardJson;
+
+    fn roundtrip(json: &[u8]) -> (JsonIndex, JsonIndex) {
+        let index = JsonIndex::build(json);
+        let mut bytes = Vec::new();
+        index.serialize(&mut bytes).unwrap();
+        let loaded = JsonIndex::deserialize(&mut bytes.as_slice()).unwrap();
+        (index, loaded)
+    }
+
+    /// Every value's text range and kind, in document order.
+    fn walk(index: &JsonIndex, json: &[u8]) -> Vec<(Option<(usize, usize)>, bool)> {
+        let mut out = Vec::new();
+        let mut stack = vec![index.root(json)];
+        while let Some(cursor) = stack.pop() {
+            out.push((cursor.text_range(), cursor.is_container()));
+            stack.extend(cursor.children());
+        }
+        out
+    }
+
+    #[test]
+    fn test_roundtrip_navigation() {
+        let json = br#"{
+  "users": [
+    {"name": "Alice", "tags": ["a", "b"], "age": 30},
+    {"name": "Bob", "tags": [], "age": null}
+  ],
+  "count": 2
+}"#;
+        let (index, loaded) = roundtrip(json);
+        assert_eq!(loaded.ib(), index.ib());
+        assert_eq!(loaded.ib_len(), index.ib_len());
+        assert_eq!(loaded.bp().words(), index.bp().words());
+        assert_eq!(walk(&loaded, json), walk(&index, json));
+        assert_eq!(loaded.stats(json), index.stats(json));
+        for offset in [0, 20, json.len() - 1] {
+            assert_eq!(loaded.to_line_column(offset), index.to_line_column(offset));
+        }
+
+        let StandardJson::Object(fields) = loaded.root(json).value() else {
+            panic!("expected object");
+        };
+        assert!(matches!(
+            fields.find("count"),
+            Some(StandardJson::Number(_))
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip_large() {
+        let json: Vec<u8> = format!(
+            "[{}]",
+            (0..5000)
+                .map(|i| format!("{{\"id\": {i}, \"s\": \"x\\ny\"}}"))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        )
+        .into_bytes();
+        let (index, loaded) = roundtrip(&json);
+        assert_eq!(walk(&loaded, &json), walk(&index, &json));
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let mut bytes = Vec::new();
+        JsonIndex::build(b"[1]").serialize(&mut bytes).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let err = JsonIndex::deserialize(&mut bad_magic.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        let err = JsonIndex::deserialize(&mut bad_version.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("version 2"));
+    }
+
+    #[test]
+    fn test_corrupt_body() {
+        let mut bytes = Vec::new();
+        JsonIndex::build(b"[1, 2, 3]")
+            .serialize(&mut bytes)
+            .unwrap();
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let err = JsonIndex::deserialize(&mut &truncated[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // A word count far beyond the input fails without allocating it
+        let mut huge = bytes.clone();
+        huge[14..22].copy_from_slice(&(u64::MAX / 16).to_le_bytes());
+        let err = JsonIndex::deserialize(&mut huge.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Too few words for the bit length
+        let mut short = bytes;
+        short[14..22].copy_from_slice(&0u64.to_le_bytes());
+        let err = JsonIndex::deserialize(&mut short.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,163 @@
+//! Building a [`JsonIndex`] straight from a reader or a file (`std` feature).
+//!
+//! The index holds positions into the JSON text, not the text itself, so
+//! each constructor also hands back the bytes it indexed: the caller's
+//! buffer for [`build_from_reader`](JsonIndex::build_from_reader), and the
+//! mapping for [`build_mmap`](JsonIndex::build_mmap).
+
+use std::io::{self, Read};
+#[cfg(feature = "memmap2")]
+use std::path::Path;
+
+use super::JsonIndex;
+
+impl JsonIndex<Vec<u64>> {
+    /// Read all of `reader` into `buf`, replacing its contents, and index it.
+    ///
+    /// Navigate the result with `index.root(&buf)`. Reusing one buffer
+    /// across documents keeps its allocation.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `reader`; `buf` then holds the bytes read so
+    /// far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input exceeds `u32::MAX` bytes, as [`build`](Self::build)
+    /// does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let mut buf = Vec::new();
+    /// let reader = &br#"{"a": [1, 2]}"#[..];
+    /// let index = JsonIndex::build_from_reader(reader, &mut buf).unwrap();
+    /// assert_eq!(index.root(&buf).children().count(), 2);
+    /// ```
+    pub fn build_from_reader(mut reader: impl Read, buf: &mut Vec<u8>) -> io::Result<Self> {
+        buf.clear();
+        reader.read_to_end(buf)?;
+        Ok(Self::build(buf))
+    }
+
+    /// Memory-map the file at `path` and index it without copying the text
+    /// (`memmap2` feature).
+    ///
+    /// Navigate the result with `index.root(&mmap)`. Pages are read in by the
+    /// OS as indexing and navigation touch them.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error opening or mapping the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file exceeds `u32::MAX` bytes, as [`build`](Self::build)
+    /// does.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the mapping is
+    /// alive: the index would no longer match the text, and reading a
+    /// truncated page raises `SIGBUS`.
+    #[cfg(feature = "memmap2")]
+    pub fn build_mmap(path: impl AsRef<Path>) -> io::Result<(memmap2::Mmap, Self)> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the mapping is read-only; keeping the file unchanged while
+        // it is alive is left to the caller, as documented above.
+        #[allow(unsafe_code)]
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let index = Self::build(&mmap);
+        Ok((mmap, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Every value's text range and kind, in document order.
+    fn walk(index: &JsonIndex, json: &[u8]) -> Vec<(Option<(usize, usize)>, bool)> {
+        let mut out = Vec::new();
+        let mut stack = vec![index.root(json)];
+        while let Some(cursor) = stack.pop() {
+            out.push((cursor.text_range(), cursor.is_container()));
+            stack.extend(cursor.children());
+        }
+        out
+    }
+
+    fn document() -> Vec<u8> {
+        format!(
+            "{{\"items\": [{}], \"done\": true}}\n",
+            (0..2000)
+                .map(|i| format!("{{\"id\": {i}, \"tags\": [\"a\", \"b\\n\"], \"n\": null}}"))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_build_from_reader_matches_build() {
+        let json = document();
+        let in_memory = JsonIndex::build(&json);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&json).unwrap();
+        let mut buf = b"stale contents".to_vec();
+        let from_file =
+            JsonIndex::build_from_reader(std::fs::File::open(file.path()).unwrap(), &mut buf)
+                .unwrap();
+        assert_eq!(buf, json);
+        assert_eq!(from_file.ib(), in_memory.ib());
+        assert_eq!(from_file.bp().words(), in_memory.bp().words());
+        assert_eq!(walk(&from_file, &buf), walk(&in_memory, &json));
+        assert_eq!(
+            from_file.root(&buf).to_owned_value(),
+            in_memory.root(&json).to_owned_value()
+        );
+
+        // The buffer is reused, not appended to
+        let index = JsonIndex::build_from_reader(&b"[1, 2, 3]"[..], &mut buf).unwrap();
+        assert_eq!(buf, b"[1, 2, 3]");
+        assert_eq!(index.root(&buf).children().count(), 3);
+    }
+
+    #[test]
+    fn test_build_from_reader_error() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "disk on fire"))
+            }
+        }
+        let error = JsonIndex::build_from_reader(Failing, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.to_string(), "disk on fire");
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn test_build_mmap_matches_build() {
+        let json = document();
+        let in_memory = JsonIndex::build(&json);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&json).unwrap();
+        file.flush().unwrap();
+        let (mmap, mapped) = JsonIndex::build_mmap(file.path()).unwrap();
+        assert_eq!(&mmap[..], &json[..]);
+        assert_eq!(walk(&mapped, &mmap), walk(&in_memory, &json));
+        assert_eq!(
+            mapped.root(&mmap).to_owned_value(),
+            in_memory.root(&json).to_owned_value()
+        );
+
+        let missing = file.path().with_extension("missing");
+        assert!(JsonIndex::build_mmap(missing).is_err());
+    }
+}
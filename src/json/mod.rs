@@ -8,24 +8,40 @@
 //! - [`simple`](crate::json::simple): 3-state machine, marks all structural characters
 //! - [`standard`](crate::json::standard): 4-state machine, marks structural characters and value starts
 //!
-//! The [`light`](crate::json::light) module provides a lazy JSON navigation API using the standard cursor.
+//! The [`light`](crate::json::light) module provides a lazy JSON navigation API using the standard cursor,
+//! and [`format`](crate::json::format) pretty-prints straight from its index. With the
+//! `serde` feature, [`serde`](crate::json::serde) deserializes typed values from a cursor.
+//! For input that arrives in chunks, [`stream`](crate::json::stream) parses it into events
+//! without an index.
 //!
 //! SIMD-accelerated versions are available on supported platforms (x86_64, aarch64):
 //! - [`simd`](crate::json::simd): Platform-specific SIMD acceleration (AVX2, NEON, etc.)
 
 mod bit_writer;
+pub mod format;
 pub mod light;
 pub mod locate;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod pfsm_optimized;
 pub mod pfsm_tables;
+#[cfg(feature = "repair")]
+pub mod repair;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod simple;
 pub mod simple_light;
 pub mod standard;
+pub mod stream;
 pub mod validate;
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "x86_64",
+    all(target_arch = "wasm32", target_feature = "simd128")
+))]
 pub mod simd;
 
 pub use bit_writer::BitWriter;
-pub use light::{JsonIndex, StandardJson};
+pub use light::{IndexMemoryUsage, JsonIndex, JsonStats, StandardJson};
 pub use simple_light::SimpleJsonIndex;
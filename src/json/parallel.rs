@@ -0,0 +1,288 @@
+//! Parallel JSON semi-index construction (`parallel` feature).
+//!
+//! The standard cursor state machine only carries state across a byte
+//! boundary while inside a string or a scalar value. Valid JSON cannot contain
+//! a raw newline in either, so the machine is always back in `InJson` right
+//! after a `\n`. [`JsonIndex::build_parallel`] therefore splits the input just
+//! after newlines (e.g. between the lines of a pretty-printed top-level array),
+//! builds each chunk's IB and BP bits independently on the rayon thread pool,
+//! and concatenates them. The result is bit-for-bit identical to
+//! [`JsonIndex::build`].
+//!
+//! If a chunk does not end in `InJson` (a raw newline inside a string, i.e.
+//! invalid JSON), the chunked result cannot be trusted and the whole input is
+//! re-indexed sequentially.
+
+use rayon::prelude::*;
+
+use crate::json::light::JsonIndex;
+use crate::json::standard::{SemiIndex, State};
+use crate::json::BitWriter;
+
+/// Inputs smaller than two of these are indexed sequentially: below ~1 MiB per
+/// chunk, thread dispatch and the merge outweigh the parallel speedup.
+const MIN_CHUNK_SIZE: usize = 1 << 20;
+
+impl JsonIndex<Vec<u64>> {
+    /// Build a JSON index using all threads of the rayon pool.
+    ///
+    /// Produces exactly the same index as [`build`](Self::build). Speedups
+    /// require newlines in the input to split at (pretty-printed JSON,
+    /// newline-delimited JSON); single-line documents and inputs under
+    /// 2 MiB fall back to the sequential build.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input exceeds `u32::MAX` bytes, like [`build`](Self::build).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::JsonIndex;
+    ///
+    /// let json = b"[\n  {\"a\": 1},\n  {\"a\": 2}\n]";
+    /// let index = JsonIndex::build_parallel(json);
+    /// assert_eq!(index.root(json).children().count(), 2);
+    /// ```
+    pub fn build_parallel(json: &[u8]) -> Self {
+        let min_chunk = MIN_CHUNK_SIZE.max(json.len() / rayon::current_num_threads().max(1));
+        build_chunked(json, min_chunk)
+    }
+}
+
+/// Build an index from chunks of at least `min_chunk` bytes.
+fn build_chunked(json: &[u8], min_chunk: usize) -> JsonIndex {
+    let bounds = chunk_bounds(json, min_chunk);
+    if bounds.len() <= 2 {
+        return JsonIndex::build(json);
+    }
+    assert!(
+        u32::try_from(json.len()).is_ok(),
+        "JsonIndex supports inputs up to u32::MAX (4294967295) bytes; got {} bytes (#188)",
+        json.len()
+    );
+
+    let chunks: Vec<(SemiIndex, usize)> = bounds
+        .par_windows(2)
+        .map(|w| {
+            let text = &json[w[0]..w[1]];
+            let semi = build_semi_index(text);
+            let bp_len = chunk_bp_len(text, &semi);
+            (semi, bp_len)
+        })
+        .collect();
+
+    let (_, non_final) = chunks.split_last().expect("at least two chunks");
+    if non_final
+        .iter()
+        .any(|(semi, _)| semi.state != State::InJson)
+    {
+        return JsonIndex::build(json);
+    }
+
+    let mut ib = BitWriter::with_capacity(json.len().div_ceil(64));
+    let mut bp = BitWriter::with_capacity(json.len().div_ceil(32));
+    for ((semi, bp_len), w) in chunks.iter().zip(bounds.windows(2)) {
+        append_bits(&mut ib, &semi.ib, w[1] - w[0]);
+        append_bits(&mut bp, &semi.bp, *bp_len);
+    }
+
+    JsonIndex::from_semi_index_words(json, ib.finish(), bp.finish())
+}
+
+/// Chunk boundaries: `0`, then the position just after the first newline at or
+/// past each multiple of `min_chunk`, then `json.len()`.
+fn chunk_bounds(json: &[u8], min_chunk: usize) -> Vec<usize> {
+    let mut bounds = vec![0];
+    let mut start = 0;
+    while json.len() - start >= 2 * min_chunk {
+        let target = start + min_chunk;
+        let Some(nl) = json[target..].iter().position(|&b| b == b'\n') else {
+            break;
+        };
+        start = target + nl + 1;
+        if start >= json.len() {
+            break;
+        }
+        bounds.push(start);
+    }
+    bounds.push(json.len());
+    bounds
+}
+
+fn build_semi_index(text: &[u8]) -> SemiIndex {
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
+    return crate::json::simd::build_semi_index_standard(text);
+
+    #[cfg(not(any(
+        target_arch = "aarch64",
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    return crate::json::standard::build_semi_index(text);
+}
+
+/// Number of BP bits a chunk's semi-index actually wrote.
+///
+/// `SemiIndex` doesn't record this, and the final BP word is zero-padded, so
+/// trailing close parens (0-bits) are indistinguishable from padding. Recover
+/// the count from the text instead: everything up to and including the last
+/// 1-bit is known, a scalar started by the last interest bit adds its closing
+/// 0-bit, and every `}`/`]` after that scalar adds one more. (No string can
+/// start after the last interest bit, so those brackets are all structural.)
+fn chunk_bp_len(text: &[u8], semi: &SemiIndex) -> usize {
+    let Some(last_ib) = last_set_bit(&semi.ib) else {
+        // No values or opens: only closes
+        return count_closes(text);
+    };
+    let through_last_one = last_set_bit(&semi.bp).map_or(0, |p| p + 1);
+
+    let (scalar_close, tail_start) = match text[last_ib] {
+        b'{' | b'[' => (0, last_ib + 1),
+        b'"' => (1, string_end(text, last_ib)),
+        // Numbers and keywords contain no brackets
+        _ => (1, last_ib + 1),
+    };
+
+    through_last_one + scalar_close + count_closes(&text[tail_start.min(text.len())..])
+}
+
+/// Position just past the string starting at `start` (or `text.len()` if unterminated).
+fn string_end(text: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < text.len() {
+        match text[i] {
+            b'"' => return i + 1,
+            b'\\' => i += 2,
+            _ => i += 1,
+        }
+    }
+    text.len()
+}
+
+fn count_closes(text: &[u8]) -> usize {
+    text.iter().filter(|&&b| b == b'}' || b == b']').count()
+}
+
+fn last_set_bit(words: &[u64]) -> Option<usize> {
+    let (i, w) = words.iter().enumerate().rev().find(|(_, &w)| w != 0)?;
+    Some(i * 64 + 63 - w.leading_zeros() as usize)
+}
+
+/// Append the first `len` bits of `words` to `writer`.
+fn append_bits(writer: &mut BitWriter, words: &[u64], len: usize) {
+    let full = len / 64;
+    for &w in &words[..full] {
+        writer.write_bits(w, 64);
+    }
+    if len % 64 != 0 {
+        writer.write_bits(words[full], len % 64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::light::JsonCursor;
+
+    /// Pretty-printed array of mixed records, `n` elements long.
+    fn sample(n: usize) -> Vec<u8> {
+        let mut json = b"[\n".to_vec();
+        for i in 0..n {
+            let sep = if i + 1 == n { "" } else { "," };
+            json.extend_from_slice(
+                format!(
+                    "  {{\"id\": {i}, \"name\": \"user \\\"{i}\\\" [x]\", \"tags\": [true, null, -1.5e3], \"o\": {{}}}}{sep}\n"
+                )
+                .as_bytes(),
+            );
+        }
+        json.extend_from_slice(b"]\n");
+        json
+    }
+
+    /// Pre-order (bp_position, text_position) of every node.
+    fn walk<W: AsRef<[u64]>>(cursor: JsonCursor<'_, W>, out: &mut Vec<(usize, Option<usize>)>) {
+        out.push((cursor.bp_position(), cursor.text_position()));
+        for child in cursor.children() {
+            walk(child, out);
+        }
+    }
+
+    fn assert_same_index(json: &[u8], min_chunk: usize) {
+        let sequential = JsonIndex::build(json);
+        let parallel = build_chunked(json, min_chunk);
+
+        assert_eq!(parallel.ib(), sequential.ib(), "IB mismatch");
+        assert_eq!(parallel.ib_len(), sequential.ib_len());
+        assert_eq!(
+            parallel.bp().words(),
+            sequential.bp().words(),
+            "BP mismatch"
+        );
+        assert_eq!(parallel.bp().len(), sequential.bp().len());
+
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        walk(parallel.root(json), &mut a);
+        walk(sequential.root(json), &mut b);
+        assert_eq!(a, b, "navigation mismatch");
+        assert_eq!(parallel.stats(json), sequential.stats(json));
+        assert_eq!(
+            parallel.to_line_column(json.len() - 1),
+            sequential.to_line_column(json.len() - 1)
+        );
+    }
+
+    #[test]
+    fn test_chunk_bounds_split_after_newlines() {
+        let json = b"aaaa\nbbbb\ncccc\ndddd\n";
+        let bounds = chunk_bounds(json, 4);
+        assert_eq!(bounds, vec![0, 5, 10, 15, 20]);
+        assert!(bounds[1..bounds.len() - 1]
+            .iter()
+            .all(|&b| json[b - 1] == b'\n'));
+
+        // No newline to split at: a single chunk
+        assert_eq!(chunk_bounds(b"[1,2,3,4,5,6,7,8]", 2), vec![0, 17]);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let json = sample(500);
+        for min_chunk in [16, 64, 100, 1000, 7777] {
+            assert_same_index(&json, min_chunk);
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential_ndjson_and_scalars() {
+        // Multiple roots and chunks ending right after a scalar or a close
+        let json = b"1\n\"s\"\n[1,\n2]\n{\"a\":\n{}}\ntrue\nnull\n-0.5\n";
+        for min_chunk in 1..8 {
+            assert_same_index(json, min_chunk);
+        }
+    }
+
+    #[test]
+    fn test_newline_inside_string_falls_back() {
+        // Invalid JSON: raw newline inside a string. Chunks would start
+        // mid-string, so the builder must fall back to a sequential build.
+        let json = b"[\"a\nb\", \"c\nd\", 1,\n2]";
+        for min_chunk in 1..6 {
+            assert_same_index(json, min_chunk);
+        }
+    }
+
+    #[test]
+    fn test_build_parallel_small_input() {
+        let json = sample(3);
+        let parallel = JsonIndex::build_parallel(&json);
+        let sequential = JsonIndex::build(&json);
+        assert_eq!(parallel.bp().words(), sequential.bp().words());
+        assert_eq!(parallel.root(&json).children().count(), 3);
+    }
+}
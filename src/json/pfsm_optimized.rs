@@ -5,6 +5,25 @@
 use crate::json::bit_writer::BitWriter;
 use crate::json::pfsm_tables::{PfsmState, PHI_TABLE, TRANSITION_TABLE};
 
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+mod avx2;
+
+/// PFSM with runtime dispatch: the AVX2 shuffle-composed kernel when the CPU
+/// supports it, otherwise [`pfsm_process_chunk_optimized`].
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+pub fn pfsm_process_chunk_simd(
+    json: &[u8],
+    initial_state: PfsmState,
+    ib: &mut BitWriter,
+    bp: &mut BitWriter,
+) -> PfsmState {
+    if is_x86_feature_detected!("avx2") {
+        avx2::pfsm_process_chunk_avx2(json, initial_state, ib, bp)
+    } else {
+        pfsm_process_chunk_optimized(json, initial_state, ib, bp)
+    }
+}
+
 /// Single-pass PFSM: no intermediate vector allocation
 pub fn pfsm_process_chunk_optimized(
     json: &[u8],
@@ -0,0 +1,377 @@
+#![allow(unsafe_code)] // x86_64 AVX2 SIMD intrinsics
+//! AVX2 shuffle-composed PFSM for x86_64.
+//!
+//! Runs the PFSM tables 32 bytes at a time without a byte-serial dependency
+//! chain:
+//!
+//! 1. Classify each byte into one of 7 classes that the tables cannot tell
+//!    apart (other, open, close, delimiter, value char, quote, backslash).
+//! 2. `vpshufb` four 16-entry shuffle rows (one per start state) by the class
+//!    vector, giving each byte's transition function as 4 state vectors.
+//! 3. Compose the functions with a log-step prefix scan (shifts 1, 2, 4, 8)
+//!    inside each 128-bit lane, then carry the state out of the low lane into
+//!    the high lane.
+//! 4. Extract the actual state stream by selecting on the 2-bit state before
+//!    each byte, look up phi the same way, and split it into movemasks.
+//!
+//! The shuffle rows are derived from [`TRANSITION_TABLE`] and [`PHI_TABLE`],
+//! so the output is bit-identical to [`pfsm_process_chunk_optimized`].
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::pfsm_process_chunk_optimized;
+use crate::json::bit_writer::BitWriter;
+use crate::json::pfsm_tables::{PfsmState, PHI_TABLE, TRANSITION_TABLE};
+
+/// One representative byte per class, in class-id order.
+const CLASS_REPRESENTATIVES: [u8; 7] = [b' ', b'{', b'}', b',', b'a', b'"', b'\\'];
+
+/// Class ids produced by [`classify`].
+const CLASS_OPEN: i8 = 1;
+const CLASS_CLOSE: i8 = 2;
+const CLASS_DELIM: i8 = 3;
+const CLASS_VALUE: i8 = 4;
+const CLASS_QUOTE: i8 = 5;
+const CLASS_BACKSLASH: i8 = 6;
+
+/// Build the per-state shuffle rows (class → table byte) for a PFSM table.
+///
+/// Each row is duplicated into both 128-bit lanes because `vpshufb` looks up
+/// within a lane.
+const fn shuffle_rows(table: &[u32; 256]) -> [[u8; 32]; 4] {
+    let mut rows = [[0u8; 32]; 4];
+    let mut state = 0;
+    while state < 4 {
+        let mut class = 0;
+        while class < CLASS_REPRESENTATIVES.len() {
+            let entry = table[CLASS_REPRESENTATIVES[class] as usize];
+            let value = ((entry >> (state * 8)) & 0xFF) as u8;
+            rows[state][class] = value;
+            rows[state][class + 16] = value;
+            class += 1;
+        }
+        state += 1;
+    }
+    rows
+}
+
+const TRANSITION_ROWS: [[u8; 32]; 4] = shuffle_rows(&TRANSITION_TABLE);
+const PHI_ROWS: [[u8; 32]; 4] = shuffle_rows(&PHI_TABLE);
+
+#[inline]
+fn state_from_u8(state: u8) -> PfsmState {
+    match state {
+        0 => PfsmState::InJson,
+        1 => PfsmState::InString,
+        2 => PfsmState::InEscape,
+        _ => PfsmState::InValue,
+    }
+}
+
+/// Mask of bytes in `lo..=hi` (unsigned).
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn in_range(chunk: __m256i, lo: u8, hi: u8) -> __m256i {
+    let ge = _mm256_cmpeq_epi8(_mm256_max_epu8(chunk, _mm256_set1_epi8(lo as i8)), chunk);
+    let le = _mm256_cmpeq_epi8(_mm256_min_epu8(chunk, _mm256_set1_epi8(hi as i8)), chunk);
+    _mm256_and_si256(ge, le)
+}
+
+/// Mask of bytes equal to `byte`.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn eq(chunk: __m256i, byte: u8) -> __m256i {
+    _mm256_cmpeq_epi8(chunk, _mm256_set1_epi8(byte as i8))
+}
+
+/// `class` where `mask` is set, 0 elsewhere.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn tag(mask: __m256i, class: i8) -> __m256i {
+    _mm256_and_si256(mask, _mm256_set1_epi8(class))
+}
+
+/// Map 32 bytes to their class ids (0..=6).
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn classify(chunk: __m256i) -> __m256i {
+    unsafe {
+        let opens = _mm256_or_si256(eq(chunk, b'{'), eq(chunk, b'['));
+        let closes = _mm256_or_si256(eq(chunk, b'}'), eq(chunk, b']'));
+        let delims = _mm256_or_si256(eq(chunk, b','), eq(chunk, b':'));
+
+        // Value chars: letters, digits, '.', '-', '+'
+        let folded = _mm256_or_si256(chunk, _mm256_set1_epi8(0x20));
+        let letters = in_range(folded, b'a', b'z');
+        let digits = in_range(chunk, b'0', b'9');
+        let signs = _mm256_or_si256(
+            _mm256_or_si256(eq(chunk, b'.'), eq(chunk, b'-')),
+            eq(chunk, b'+'),
+        );
+        let values = _mm256_or_si256(_mm256_or_si256(letters, digits), signs);
+
+        // The masks are disjoint, so OR-ing the masked ids yields the class
+        let mut classes = tag(opens, CLASS_OPEN);
+        classes = _mm256_or_si256(classes, tag(closes, CLASS_CLOSE));
+        classes = _mm256_or_si256(classes, tag(delims, CLASS_DELIM));
+        classes = _mm256_or_si256(classes, tag(values, CLASS_VALUE));
+        classes = _mm256_or_si256(classes, tag(eq(chunk, b'"'), CLASS_QUOTE));
+        _mm256_or_si256(classes, tag(eq(chunk, b'\\'), CLASS_BACKSLASH))
+    }
+}
+
+/// Per byte, pick `vectors[index]` where `index` is a 2-bit state.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn select(vectors: &[__m256i; 4], index: __m256i) -> __m256i {
+    // blendv keys off the top bit of each byte, so move state bits 0 and 1 there
+    let bit0 = _mm256_slli_epi16::<7>(index);
+    let bit1 = _mm256_slli_epi16::<6>(index);
+    let low = _mm256_blendv_epi8(vectors[0], vectors[1], bit0);
+    let high = _mm256_blendv_epi8(vectors[2], vectors[3], bit0);
+    _mm256_blendv_epi8(low, high, bit1)
+}
+
+/// One prefix-scan step: compose each byte's function with the one `16 - ALIGN`
+/// bytes earlier in the same lane. Bytes with no predecessor compose with the
+/// identity.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn compose<const ALIGN: i32>(
+    functions: [__m256i; 4],
+    identity: &[__m256i; 4],
+) -> [__m256i; 4] {
+    unsafe {
+        let mut composed = functions;
+        for (start, out) in composed.iter_mut().enumerate() {
+            let earlier = _mm256_alignr_epi8::<ALIGN>(functions[start], identity[start]);
+            *out = select(&functions, earlier);
+        }
+        composed
+    }
+}
+
+/// Look up a 4-row shuffle table by class id.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn lookup(rows: &[__m256i; 4], classes: __m256i) -> [__m256i; 4] {
+    [
+        _mm256_shuffle_epi8(rows[0], classes),
+        _mm256_shuffle_epi8(rows[1], classes),
+        _mm256_shuffle_epi8(rows[2], classes),
+        _mm256_shuffle_epi8(rows[3], classes),
+    ]
+}
+
+/// Load the 4 rows of a shuffle table.
+#[inline]
+#[target_feature(enable = "avx2")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn load_rows(rows: &[[u8; 32]; 4]) -> [__m256i; 4] {
+    unsafe {
+        let load = |row: &[u8; 32]| _mm256_loadu_si256(row.as_ptr().cast::<__m256i>());
+        [
+            load(&rows[0]),
+            load(&rows[1]),
+            load(&rows[2]),
+            load(&rows[3]),
+        ]
+    }
+}
+
+/// Write BP bits for a 32-byte chunk: open before close for each byte.
+#[inline]
+fn write_bp(bp: &mut BitWriter, opens: u32, closes: u32) {
+    let mut marks = opens | closes;
+    while marks != 0 {
+        let bit = marks & marks.wrapping_neg();
+        if opens & bit != 0 {
+            bp.write_1();
+        }
+        if closes & bit != 0 {
+            bp.write_0();
+        }
+        marks ^= bit;
+    }
+}
+
+/// Shuffle-composed PFSM over a chunk, producing the same IB/BP bits and final
+/// state as [`pfsm_process_chunk_optimized`].
+#[cfg(target_arch = "x86_64")]
+pub fn pfsm_process_chunk_avx2(
+    json: &[u8],
+    initial_state: PfsmState,
+    ib: &mut BitWriter,
+    bp: &mut BitWriter,
+) -> PfsmState {
+    // SAFETY: Caller must ensure AVX2 is available
+    unsafe { pfsm_process_chunk_avx2_impl(json, initial_state, ib, bp) }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn pfsm_process_chunk_avx2_impl(
+    json: &[u8],
+    initial_state: PfsmState,
+    ib: &mut BitWriter,
+    bp: &mut BitWriter,
+) -> PfsmState {
+    unsafe {
+        let transitions = load_rows(&TRANSITION_ROWS);
+        let phis = load_rows(&PHI_ROWS);
+        let identity = [
+            _mm256_set1_epi8(0),
+            _mm256_set1_epi8(1),
+            _mm256_set1_epi8(2),
+            _mm256_set1_epi8(3),
+        ];
+
+        let mut state = initial_state as u8;
+        let mut chunks = json.chunks_exact(32);
+
+        for chunk in &mut chunks {
+            let bytes = _mm256_loadu_si256(chunk.as_ptr().cast::<__m256i>());
+            let classes = classify(bytes);
+
+            // functions[s][i] = state after byte i when the lane starts in state s
+            let mut functions = lookup(&transitions, classes);
+            functions = compose::<15>(functions, &identity);
+            functions = compose::<14>(functions, &identity);
+            functions = compose::<12>(functions, &identity);
+            functions = compose::<8>(functions, &identity);
+
+            // Low lane starts in `state`; the high lane starts where the low lane ends
+            let low = functions[state as usize];
+            let middle = _mm256_extract_epi8::<15>(low) as u8;
+            let after = _mm256_permute2x128_si256::<0x30>(low, functions[middle as usize]);
+
+            // State before each byte: shift `after` up one byte across both lanes
+            let carry = _mm256_permute2x128_si256::<0x20>(identity[state as usize], after);
+            let before = _mm256_alignr_epi8::<15>(after, carry);
+
+            let phi_by_state = lookup(&phis, classes);
+            let phi = select(&phi_by_state, before);
+
+            let ib_bits = _mm256_movemask_epi8(_mm256_slli_epi16::<5>(phi)) as u32;
+            let opens = _mm256_movemask_epi8(_mm256_slli_epi16::<6>(phi)) as u32;
+            let closes = _mm256_movemask_epi8(_mm256_slli_epi16::<7>(phi)) as u32;
+
+            ib.write_bits(ib_bits as u64, 32);
+            write_bp(bp, opens, closes);
+
+            state = _mm256_extract_epi8::<31>(after) as u8;
+        }
+
+        pfsm_process_chunk_optimized(chunks.remainder(), state_from_u8(state), ib, bp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(
+        f: fn(&[u8], PfsmState, &mut BitWriter, &mut BitWriter) -> PfsmState,
+        json: &[u8],
+        initial_state: PfsmState,
+    ) -> (PfsmState, Vec<u64>, Vec<u64>) {
+        let mut ib = BitWriter::new();
+        let mut bp = BitWriter::new();
+        let state = f(json, initial_state, &mut ib, &mut bp);
+        (state, ib.finish(), bp.finish())
+    }
+
+    fn assert_matches_scalar(json: &[u8], initial_state: PfsmState) {
+        assert_eq!(
+            run(pfsm_process_chunk_avx2, json, initial_state),
+            run(pfsm_process_chunk_optimized, json, initial_state),
+            "mismatch for {:?} from {initial_state:?}",
+            String::from_utf8_lossy(json)
+        );
+    }
+
+    #[test]
+    fn test_shuffle_rows_match_tables() {
+        // Seven classes must cover every distinct (transition, phi) table column
+        for byte in 0..=255usize {
+            assert!(
+                CLASS_REPRESENTATIVES.iter().any(|&rep| {
+                    TRANSITION_TABLE[rep as usize] == TRANSITION_TABLE[byte]
+                        && PHI_TABLE[rep as usize] == PHI_TABLE[byte]
+                }),
+                "byte {byte:#04x} has no class"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_scalar_pfsm() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+
+        let inputs: [&[u8]; 8] = [
+            b"",
+            br#"{"a":1}"#,
+            br#"{"name":"Alice","tags":["x","y\"z"],"n":-1.5e+3,"ok":true,"v":null}"#,
+            b"[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]",
+            br#"["\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\\","\"\"\"\"\"\"\"\"\"\"\"\"\"\""]"#,
+            "{\"ünïcödé\": \"日本語\", \"k\" : [ 1 , 2 ,\t3 ]\n}".as_bytes(),
+            br#"{"unterminated": "string"#,
+            b"[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25]",
+        ];
+        for json in inputs {
+            for state in [
+                PfsmState::InJson,
+                PfsmState::InString,
+                PfsmState::InEscape,
+                PfsmState::InValue,
+            ] {
+                assert_matches_scalar(json, state);
+            }
+        }
+
+        // Every byte value at every lane position, entered from every state
+        for byte in 0..=255u8 {
+            for prefix in [&b""[..], b"\"", b"\"\\", b"1"] {
+                for offset in [0, 15, 16, 31] {
+                    let mut json = b"                                ".to_vec();
+                    json.truncate(offset);
+                    json.extend_from_slice(prefix);
+                    json.extend(core::iter::repeat(byte).take(70));
+                    assert_matches_scalar(&json, PfsmState::InJson);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_scalar_pfsm_generated() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+
+        // Pseudo-random bytes drawn from the structural alphabet
+        let alphabet = br#"{}[],:"\ a1.-+xZ_"#;
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        for len in [31, 32, 33, 64, 100, 257, 1000] {
+            let json: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    alphabet[(seed >> 33) as usize % alphabet.len()]
+                })
+                .collect();
+            assert_matches_scalar(&json, PfsmState::InJson);
+        }
+    }
+}
@@ -0,0 +1,628 @@
+//! Lenient JSON repair (`repair` feature).
+//!
+//! Many real-world tools emit JSON that is *almost* valid: configuration files
+//! with comments, hand-edited data with trailing commas, JavaScript-style
+//! object literals with single-quoted strings or bare keys, or output cut off
+//! before the closing brackets. [`repair`] rewrites the most common of these
+//! violations into valid JSON:
+//!
+//! - `// line` and `/* block */` comments are removed
+//! - `'single-quoted'` strings become `"double-quoted"` (re-escaping any `"`
+//!   inside them)
+//! - bare identifier keys (`{a: 1}`) are quoted
+//! - trailing commas before `}` and `]` are removed
+//! - missing closing brackets at the end of input are added
+//!
+//! Everything else is copied through unchanged, and the result is checked
+//! with the strict [`Validator`](crate::json::validate::Validator), so a
+//! successful repair always yields RFC 8259 JSON. Input that is already valid
+//! comes back byte-for-byte identical.
+//!
+//! # Example
+//!
+//! ```
+//! use succinctly::json::repair::repair;
+//!
+//! let input = b"{name: 'Alice', // the user\n tags: ['a', 'b',],";
+//! let fixed = repair(input).unwrap();
+//! assert_eq!(fixed, b"{\"name\": \"Alice\", \n \"tags\": [\"a\", \"b\"]}");
+//! ```
+//!
+//! [`repair_with_warnings`] also reports each repair it made, and
+//! [`JsonIndex::build_lenient`] goes one step further and indexes the result:
+//!
+//! ```
+//! use succinctly::json::repair::RepairKind;
+//! use succinctly::json::JsonIndex;
+//!
+//! let (json, warnings) = JsonIndex::build_lenient(b"[1, 2, // two\n]").unwrap();
+//! assert_eq!(json.text(), b"[1, 2 \n]");
+//! assert_eq!(json.root().children().count(), 2);
+//!
+//! let kinds: Vec<_> = warnings.iter().map(|w| (w.kind, w.original_offset)).collect();
+//! assert_eq!(kinds, [(RepairKind::TrailingComma, 5), (RepairKind::Comment, 7)]);
+//! ```
+
+#[cfg(not(test))]
+use alloc::{format, string::String, vec::Vec};
+
+use core::fmt;
+
+use crate::json::light::JsonCursor;
+use crate::json::validate::{validate, ValidationError};
+use crate::json::JsonIndex;
+
+/// The kind of violation a repair fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// A `//` or `/* */` comment was removed.
+    Comment,
+    /// A single-quoted string was converted to a double-quoted one.
+    SingleQuotedString,
+    /// A bare identifier key was quoted.
+    UnquotedKey,
+    /// A comma before `}`, `]` or the end of input was removed.
+    TrailingComma,
+    /// A closing bracket was added at the end of input.
+    MissingClose,
+}
+
+/// One repair made by [`repair_with_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientWarning {
+    /// What was repaired.
+    pub kind: RepairKind,
+    /// Byte offset in the original input where the repaired construct
+    /// starts. For [`RepairKind::MissingClose`] this is the end of input.
+    pub original_offset: usize,
+    /// Human-readable description of the repair.
+    pub description: String,
+}
+
+impl fmt::Display for LenientWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: {}", self.original_offset, self.description)
+    }
+}
+
+/// Errors from [`repair`].
+#[derive(Debug, Clone)]
+pub enum RepairError {
+    /// Input ended inside a string.
+    UnterminatedString {
+        /// Byte offset of the opening quote.
+        offset: usize,
+    },
+    /// Input ended inside a `/* ... */` comment.
+    UnterminatedComment {
+        /// Byte offset of the `/*`.
+        offset: usize,
+    },
+    /// Input ended between an object key and its value, so there is no value
+    /// to close the object with.
+    Truncated {
+        /// Byte offset of the end of input.
+        offset: usize,
+    },
+    /// The repaired output is still not valid JSON. The error's position
+    /// refers to the repaired bytes, not the original input.
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedString { offset } => {
+                write!(f, "unterminated string starting at offset {offset}")
+            }
+            Self::UnterminatedComment { offset } => {
+                write!(f, "unterminated comment starting at offset {offset}")
+            }
+            Self::Truncated { offset } => {
+                write!(
+                    f,
+                    "input truncated inside a key/value pair at offset {offset}"
+                )
+            }
+            Self::Invalid(err) => write!(f, "could not repair JSON: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RepairError {}
+
+/// Rewrite common JSON violations into valid JSON.
+///
+/// See the [module documentation](self) for the list of repairs. Returns
+/// [`RepairError`] when the input is too malformed to fix automatically, such
+/// as a string or comment that never ends, input cut off between a key and its
+/// value, or anything the repairs above do not cover.
+pub fn repair(input: &[u8]) -> Result<Vec<u8>, RepairError> {
+    repair_with_warnings(input).map(|(out, _)| out)
+}
+
+/// Like [`repair`], but also returns a warning for each repair made, in
+/// input order. Valid input yields no warnings.
+pub fn repair_with_warnings(input: &[u8]) -> Result<(Vec<u8>, Vec<LenientWarning>), RepairError> {
+    let mut repairer = Repairer::new(input);
+    repairer.run()?;
+    validate(&repairer.out).map_err(RepairError::Invalid)?;
+    // Trailing commas are only recognised at the token after them
+    repairer.warnings.sort_by_key(|w| w.original_offset);
+    Ok((repairer.out, repairer.warnings))
+}
+
+/// Repaired JSON text together with its index.
+///
+/// Returned by [`JsonIndex::build_lenient`]. The index describes the repaired
+/// bytes, so cursor positions and [`text_range`](JsonCursor::text_range)
+/// offsets refer to [`text`](Self::text), not the original input.
+#[derive(Clone, Debug)]
+pub struct RepairedJson {
+    text: Vec<u8>,
+    index: JsonIndex,
+}
+
+impl RepairedJson {
+    /// The repaired JSON text.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// The index over [`text`](Self::text).
+    pub fn index(&self) -> &JsonIndex {
+        &self.index
+    }
+
+    /// Cursor at the root value of the repaired text.
+    pub fn root(&self) -> JsonCursor<'_> {
+        self.index.root(&self.text)
+    }
+
+    /// Split into the repaired text and its index.
+    pub fn into_parts(self) -> (Vec<u8>, JsonIndex) {
+        (self.text, self.index)
+    }
+}
+
+impl JsonIndex<Vec<u64>> {
+    /// Build an index over near-valid JSON.
+    ///
+    /// Where [`build`](Self::build) expects valid input, this first applies
+    /// [`repair_with_warnings`] and indexes the repaired bytes, which are kept
+    /// alongside the index in the returned [`RepairedJson`]. Each repair is
+    /// reported as a [`LenientWarning`] with its offset in `json`.
+    ///
+    /// Returns [`RepairError`] when the input cannot be repaired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::{JsonIndex, StandardJson};
+    ///
+    /// let (json, warnings) = JsonIndex::build_lenient(b"{name: 'Alice',}").unwrap();
+    /// assert_eq!(warnings.len(), 3);
+    /// let StandardJson::Object(fields) = json.root().value() else { panic!() };
+    /// assert!(fields.find("name").is_some());
+    /// ```
+    pub fn build_lenient(json: &[u8]) -> Result<(RepairedJson, Vec<LenientWarning>), RepairError> {
+        let (text, warnings) = repair_with_warnings(json)?;
+        let index = Self::build(&text);
+        Ok((RepairedJson { text, index }, warnings))
+    }
+}
+
+/// Single forward pass over the input.
+struct Repairer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    /// Open containers (`{` or `[`) and their offsets, innermost last
+    stack: Vec<(u8, usize)>,
+    /// Offset of a `,` that has been read but not yet emitted; it is dropped
+    /// if the next token closes the container
+    pending_comma: Option<usize>,
+    /// Whitespace read after a pending comma, emitted with the next token
+    held: Vec<u8>,
+    /// The next token is in object-key position
+    expect_key: bool,
+    /// A key has been emitted but its value has not started yet
+    incomplete_pair: bool,
+    /// Repairs made so far
+    warnings: Vec<LenientWarning>,
+}
+
+impl<'a> Repairer<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            pos: 0,
+            out: Vec::with_capacity(input.len() + 16),
+            stack: Vec::new(),
+            pending_comma: None,
+            held: Vec::new(),
+            expect_key: false,
+            incomplete_pair: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), RepairError> {
+        while let Some(&byte) = self.input.get(self.pos) {
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' => {
+                    self.whitespace(byte);
+                    self.pos += 1;
+                }
+                b'/' if self.peek(1) == Some(b'/') => {
+                    self.warn(RepairKind::Comment, self.pos, "removed line comment".into());
+                    // Drop up to (not including) the newline
+                    while self.input.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                b'/' if self.peek(1) == Some(b'*') => {
+                    let start = self.pos;
+                    let end = self.input[start + 2..]
+                        .windows(2)
+                        .position(|w| w == b"*/")
+                        .ok_or(RepairError::UnterminatedComment { offset: start })?;
+                    self.warn(RepairKind::Comment, start, "removed block comment".into());
+                    self.pos = start + 2 + end + 2;
+                    // Keep the tokens on either side apart
+                    self.whitespace(b' ');
+                }
+                b',' => {
+                    self.begin_token();
+                    self.pending_comma = Some(self.pos);
+                    self.expect_key = matches!(self.stack.last(), Some((b'{', _)));
+                    self.pos += 1;
+                }
+                b'}' | b']' => {
+                    // Trailing comma: drop it but keep the whitespace after it
+                    self.drop_trailing_comma();
+                    self.begin_token();
+                    self.stack.pop();
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+                b'{' | b'[' => {
+                    self.begin_value();
+                    self.stack.push((byte, self.pos));
+                    self.expect_key = byte == b'{';
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+                b':' => {
+                    self.begin_token();
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+                b'"' | b'\'' => {
+                    let is_key = self.expect_key;
+                    if byte == b'\'' {
+                        self.warn(
+                            RepairKind::SingleQuotedString,
+                            self.pos,
+                            "converted single-quoted string to double quotes".into(),
+                        );
+                    }
+                    self.begin_value();
+                    self.string(byte)?;
+                    self.incomplete_pair = is_key;
+                }
+                _ if self.expect_key && is_ident_start(byte) => {
+                    self.begin_value();
+                    let start = self.pos;
+                    while self
+                        .input
+                        .get(self.pos)
+                        .is_some_and(|&b| is_ident_continue(b))
+                    {
+                        self.pos += 1;
+                    }
+                    let key = &self.input[start..self.pos];
+                    self.out.push(b'"');
+                    self.out.extend_from_slice(key);
+                    self.out.push(b'"');
+                    self.incomplete_pair = true;
+                    // Identifier bytes are ASCII
+                    let description = format!(
+                        "quoted bare key `{}`",
+                        core::str::from_utf8(key).unwrap_or_default()
+                    );
+                    self.warn(RepairKind::UnquotedKey, start, description);
+                }
+                _ => {
+                    self.begin_value();
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        // A trailing comma at EOF is dropped along with the container it was in
+        self.drop_trailing_comma();
+        self.begin_token();
+
+        if self.incomplete_pair {
+            return Err(RepairError::Truncated { offset: self.pos });
+        }
+        while let Some((open, offset)) = self.stack.pop() {
+            let close = if open == b'{' { b'}' } else { b']' };
+            self.out.push(close);
+            let description = format!(
+                "added missing `{}` to close `{}` at offset {offset}",
+                char::from(close),
+                char::from(open)
+            );
+            self.warn(RepairKind::MissingClose, self.input.len(), description);
+        }
+        Ok(())
+    }
+
+    fn warn(&mut self, kind: RepairKind, original_offset: usize, description: String) {
+        self.warnings.push(LenientWarning {
+            kind,
+            original_offset,
+            description,
+        });
+    }
+
+    /// Discard a pending comma because the container (or input) ends after it.
+    fn drop_trailing_comma(&mut self) {
+        if let Some(offset) = self.pending_comma.take() {
+            self.warn(
+                RepairKind::TrailingComma,
+                offset,
+                "removed trailing comma".into(),
+            );
+        }
+    }
+
+    fn peek(&self, ahead: usize) -> Option<u8> {
+        self.input.get(self.pos + ahead).copied()
+    }
+
+    fn whitespace(&mut self, byte: u8) {
+        if self.pending_comma.is_some() {
+            self.held.push(byte);
+        } else {
+            self.out.push(byte);
+        }
+    }
+
+    /// Emit any pending comma and the whitespace held after it.
+    fn begin_token(&mut self) {
+        if self.pending_comma.take().is_some() {
+            self.out.push(b',');
+        }
+        self.out.append(&mut self.held);
+        self.expect_key = false;
+    }
+
+    /// Like [`begin_token`](Self::begin_token), for a token that starts a key or value.
+    fn begin_value(&mut self) {
+        self.begin_token();
+        self.incomplete_pair = false;
+    }
+
+    /// Copy a string delimited by `quote`, converting single quotes to double.
+    fn string(&mut self, quote: u8) -> Result<(), RepairError> {
+        let start = self.pos;
+        let unterminated = || RepairError::UnterminatedString { offset: start };
+        self.out.push(b'"');
+        self.pos += 1;
+
+        loop {
+            let &byte = self.input.get(self.pos).ok_or_else(unterminated)?;
+            match byte {
+                _ if byte == quote => {
+                    self.out.push(b'"');
+                    self.pos += 1;
+                    return Ok(());
+                }
+                b'\\' => {
+                    let &escaped = self.input.get(self.pos + 1).ok_or_else(unterminated)?;
+                    // `\'` is not a JSON escape; a bare `'` needs none
+                    if !(quote == b'\'' && escaped == b'\'') {
+                        self.out.push(b'\\');
+                    }
+                    self.out.push(escaped);
+                    self.pos += 2;
+                }
+                // Only reachable inside single-quoted strings
+                b'"' => {
+                    self.out.extend_from_slice(b"\\\"");
+                    self.pos += 1;
+                }
+                _ => {
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+fn is_ident_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$'
+}
+
+fn is_ident_continue(byte: u8) -> bool {
+    is_ident_start(byte) || byte.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repaired(input: &[u8]) -> String {
+        String::from_utf8(repair(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_valid_input_unchanged() {
+        for input in [
+            &br#"{"a": [1, 2.5e3, "x\"y", true, null], "b": {}}"#[..],
+            b"  [ ]\n",
+            br#""a // not a comment /* nor this */""#,
+            b"42",
+        ] {
+            assert_eq!(repair(input).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        assert_eq!(repaired(b"// header\n[1, // one\n 2]"), "\n[1, \n 2]");
+        assert_eq!(repaired(b"{/* c */\"a\":/**/1}"), "{ \"a\": 1}");
+        // A comment between two tokens does not merge them
+        assert!(matches!(
+            repair(b"[1/* c */2]"),
+            Err(RepairError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_single_quoted_strings() {
+        assert_eq!(repaired(b"['a', 'b']"), r#"["a", "b"]"#);
+        assert_eq!(repaired(br#"['say "hi"']"#), r#"["say \"hi\""]"#);
+        assert_eq!(repaired(br"['it\'s', 'a\nb']"), r#"["it's", "a\nb"]"#);
+        // Double-quoted strings may contain single quotes
+        assert_eq!(repaired(br#"["it's"]"#), r#"["it's"]"#);
+    }
+
+    #[test]
+    fn test_unquoted_keys() {
+        assert_eq!(
+            repaired(b"{a: 1, _b2: {$c: 'x'}}"),
+            r#"{"a": 1, "_b2": {"$c": "x"}}"#
+        );
+        // Bare words in value position are left alone (and rejected)
+        assert!(matches!(repair(b"{a: b}"), Err(RepairError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_trailing_commas() {
+        assert_eq!(repaired(b"[1, 2,]"), "[1, 2]");
+        assert_eq!(repaired(b"{\"a\": 1,\n}"), "{\"a\": 1\n}");
+        assert_eq!(repaired(b"[[1,],{\"a\":[],},]"), r#"[[1],{"a":[]}]"#);
+        // Empty elements are not trailing commas
+        assert!(matches!(repair(b"[1,,2]"), Err(RepairError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_missing_closing_brackets() {
+        assert_eq!(repaired(b"[1, [2, {\"a\": 3"), r#"[1, [2, {"a": 3}]]"#);
+        assert_eq!(repaired(b"{\"a\": [1, 2,"), r#"{"a": [1, 2]}"#);
+        assert_eq!(repaired(b"{"), "{}");
+    }
+
+    #[test]
+    fn test_truncated_errors() {
+        assert!(matches!(
+            repair(br#"{"key"#),
+            Err(RepairError::UnterminatedString { offset: 1 })
+        ));
+        assert!(matches!(
+            repair(br#"{"key""#),
+            Err(RepairError::Truncated { offset: 6 })
+        ));
+        assert!(matches!(
+            repair(br#"{"key": "#),
+            Err(RepairError::Truncated { .. })
+        ));
+        assert!(matches!(
+            repair(b"{key"),
+            Err(RepairError::Truncated { .. })
+        ));
+        assert!(matches!(
+            repair(b"[1 /* open"),
+            Err(RepairError::UnterminatedComment { offset: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_unrepairable_is_invalid() {
+        for input in [&b""[..], b"]", b"[1 2]", b"{\"a\" 1}", b"[tru"] {
+            assert!(
+                matches!(repair(input), Err(RepairError::Invalid(_))),
+                "{input:?}"
+            );
+        }
+    }
+
+    fn warnings(input: &[u8]) -> Vec<(RepairKind, usize)> {
+        let (_, warnings) = repair_with_warnings(input).unwrap();
+        warnings
+            .iter()
+            .map(|w| (w.kind, w.original_offset))
+            .collect()
+    }
+
+    #[test]
+    fn test_warnings() {
+        assert_eq!(warnings(br#"{"a": [1, 2.5], "b": {}}"#), []);
+        assert_eq!(
+            warnings(b"/* c */ {a: 'x', // y\n}"),
+            [
+                (RepairKind::Comment, 0),
+                (RepairKind::UnquotedKey, 9),
+                (RepairKind::SingleQuotedString, 12),
+                (RepairKind::TrailingComma, 15),
+                (RepairKind::Comment, 17),
+            ]
+        );
+        // Innermost container is closed first
+        assert_eq!(
+            warnings(b"[1, {\"a\": 2,"),
+            [
+                (RepairKind::TrailingComma, 11),
+                (RepairKind::MissingClose, 12),
+                (RepairKind::MissingClose, 12),
+            ]
+        );
+
+        let (_, warnings) = repair_with_warnings(b"[{ab: 1}").unwrap();
+        let descriptions: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "offset 2: quoted bare key `ab`",
+                "offset 8: added missing `]` to close `[` at offset 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_lenient() {
+        let (json, warnings) = JsonIndex::build_lenient(b"{users: ['a', 'b',]}").unwrap();
+        assert_eq!(json.text(), br#"{"users": ["a", "b"]}"#);
+        assert_eq!(warnings.len(), 4);
+        let users = json.root().first_child().unwrap().next_sibling().unwrap();
+        assert_eq!(users.children().count(), 2);
+
+        let (text, index) = json.into_parts();
+        assert_eq!(index.root(&text).children().count(), 2);
+
+        assert!(matches!(
+            JsonIndex::build_lenient(b"[1 2]"),
+            Err(RepairError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_repaired_output_indexes() {
+        let fixed = repair(b"{users: [{name: 'a',}, {name: 'b'}], // done\n").unwrap();
+        let index = crate::json::JsonIndex::build(&fixed);
+        let users = index
+            .root(&fixed)
+            .first_child()
+            .unwrap()
+            .next_sibling()
+            .unwrap();
+        assert_eq!(users.children().count(), 2);
+    }
+}
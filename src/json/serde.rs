@@ -0,0 +1,448 @@
+//! serde deserialization straight from a semi-indexed document (`serde` feature).
+//!
+//! [`CursorDeserializer`] drives any [`Deserialize`] type from a
+//! [`JsonCursor`], without parsing the text a second time: objects feed
+//! structs and maps, arrays feed sequences and tuples, and strings without
+//! escapes are borrowed from the original bytes, so `&str` fields work.
+//! Fields a type ignores are skipped without being visited, since the index
+//! can jump over any subtree.
+//!
+//! # Example
+//!
+//! ```
+//! use serde::Deserialize;
+//! use succinctly::json::{serde::from_cursor, JsonIndex};
+//!
+//! #[derive(Deserialize)]
+//! struct User<'a> {
+//!     name: &'a str,
+//!     age: u32,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let json = br#"{"name": "Ada", "age": 36, "tags": ["math"], "extra": [1, 2]}"#;
+//! let index = JsonIndex::build(json);
+//! let user: User = from_cursor(index.root(json)).unwrap();
+//! assert_eq!((user.name, user.age, user.tags.len()), ("Ada", 36, 1));
+//! ```
+
+use alloc::borrow::Cow;
+#[cfg(not(test))]
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use ::serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use ::serde::{forward_to_deserialize_any, Deserialize};
+
+use crate::json::light::{JsonCursor, JsonElements, JsonError, JsonFields, StandardJson};
+
+/// Deserialize a `T` from the value at `cursor`.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the value doesn't have the shape `T` expects, or
+/// if a string or number in it is malformed.
+pub fn from_cursor<'de, T, W>(cursor: JsonCursor<'de, W>) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+    W: AsRef<[u64]>,
+{
+    T::deserialize(CursorDeserializer::new(cursor))
+}
+
+/// An error from deserializing a [`JsonCursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl de::StdError for Error {}
+
+impl Error {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(msg.to_string())
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(e: JsonError) -> Self {
+        Self::new(e.to_string())
+    }
+}
+
+/// A serde [`Deserializer`] over the value at a [`JsonCursor`].
+///
+/// Strings without escape sequences are handed to the visitor borrowed from
+/// the JSON text (`visit_borrowed_str`); strings with escapes are decoded
+/// into an owned `String`. Integers that fit in `i64` arrive as `i64`,
+/// larger non-negative integers as `u64`, and everything else as `f64`.
+/// `null` is `None` for options and `()` for units.
+pub struct CursorDeserializer<'de, W = alloc::vec::Vec<u64>> {
+    value: StandardJson<'de, W>,
+}
+
+impl<'de, W: AsRef<[u64]>> CursorDeserializer<'de, W> {
+    /// Create a deserializer for the value at `cursor`.
+    pub fn new(cursor: JsonCursor<'de, W>) -> Self {
+        Self {
+            value: cursor.value(),
+        }
+    }
+
+    fn from_value(value: StandardJson<'de, W>) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de, W: AsRef<[u64]>> Deserializer<'de> for CursorDeserializer<'de, W> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            StandardJson::Null => visitor.visit_unit(),
+            StandardJson::Bool(b) => visitor.visit_bool(b),
+            StandardJson::Number(n) => {
+                if let Ok(i) = n.as_i64() {
+                    visitor.visit_i64(i)
+                } else if let Some(u) = core::str::from_utf8(n.raw_bytes())
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                {
+                    visitor.visit_u64(u)
+                } else {
+                    visitor.visit_f64(n.as_f64()?)
+                }
+            }
+            StandardJson::String(s) => match s.as_str()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            StandardJson::Array(elements) => visitor.visit_seq(Elements { elements }),
+            StandardJson::Object(fields) => visitor.visit_map(Fields {
+                fields,
+                value: None,
+            }),
+            StandardJson::Error(message) => Err(Error::new(message)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            StandardJson::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enums are externally tagged, as in serde_json: a unit variant is a
+    /// string, any other variant an object with a single key.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            StandardJson::String(_) => visitor.visit_enum(Variant {
+                tag: self.value,
+                content: None,
+            }),
+            StandardJson::Object(fields) => {
+                let mut fields = fields;
+                match (fields.next(), fields.next()) {
+                    (Some(field), None) => visitor.visit_enum(Variant {
+                        tag: field.key(),
+                        content: Some(field.value()),
+                    }),
+                    _ => Err(Error::new(
+                        "expected an object with a single key for an enum variant",
+                    )),
+                }
+            }
+            _ => Err(Error::new("expected a string or an object for an enum")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // The index already knows where this value ends; nothing to walk
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+/// Array elements as a serde sequence.
+struct Elements<'de, W> {
+    elements: JsonElements<'de, W>,
+}
+
+impl<'de, W: AsRef<[u64]>> SeqAccess<'de> for Elements<'de, W> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.elements
+            .next()
+            .map(|value| seed.deserialize(CursorDeserializer::from_value(value)))
+            .transpose()
+    }
+}
+
+/// Object fields as a serde map.
+struct Fields<'de, W> {
+    fields: JsonFields<'de, W>,
+    /// Value of the key most recently returned by `next_key_seed`
+    value: Option<StandardJson<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> MapAccess<'de> for Fields<'de, W> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some(field) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.value = Some(field.value());
+        seed.deserialize(CursorDeserializer::from_value(field.key()))
+            .map(Some)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::new("map value requested before its key"))?;
+        seed.deserialize(CursorDeserializer::from_value(value))
+    }
+}
+
+/// An enum variant: its tag (a JSON string) and, for non-unit variants, the
+/// value under that tag.
+struct Variant<'de, W> {
+    tag: StandardJson<'de, W>,
+    content: Option<StandardJson<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> EnumAccess<'de> for Variant<'de, W> {
+    type Error = Error;
+    type Variant = VariantContent<'de, W>;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<(T::Value, Self::Variant), Error> {
+        let tag = seed.deserialize(CursorDeserializer::from_value(self.tag))?;
+        Ok((
+            tag,
+            VariantContent {
+                content: self.content,
+            },
+        ))
+    }
+}
+
+/// The value under an enum variant's tag, `None` for a bare string tag.
+struct VariantContent<'de, W> {
+    content: Option<StandardJson<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> VariantAccess<'de> for VariantContent<'de, W> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.content {
+            None | Some(StandardJson::Null) => Ok(()),
+            Some(_) => Err(Error::new("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.content {
+            Some(value) => seed.deserialize(CursorDeserializer::from_value(value)),
+            None => seed.deserialize(().into_deserializer()),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.content_deserializer()?.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.content_deserializer()?.deserialize_map(visitor)
+    }
+}
+
+impl<'de, W: AsRef<[u64]>> VariantContent<'de, W> {
+    fn content_deserializer(self) -> Result<CursorDeserializer<'de, W>, Error> {
+        self.content
+            .map(CursorDeserializer::from_value)
+            .ok_or_else(|| Error::new("expected a tuple or struct variant, found a string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonIndex;
+    use std::collections::BTreeMap;
+
+    fn parse<'a, T: Deserialize<'a>>(index: &'a JsonIndex, json: &'a [u8]) -> Result<T, Error> {
+        from_cursor(index.root(json))
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        x: i64,
+        y: f64,
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Line(Point, Point),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[test]
+    fn test_derived_struct() {
+        let json = br#"{"y": 2.5, "x": -3, "label": null, "ignored": {"deep": [1, 2]}}"#;
+        let index = JsonIndex::build(json);
+        let point: Point = parse(&index, json).unwrap();
+        assert_eq!(
+            point,
+            Point {
+                x: -3,
+                y: 2.5,
+                label: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_borrowed_and_escaped_strings() {
+        #[derive(Deserialize)]
+        struct Names<'a> {
+            plain: &'a str,
+            escaped: Cow<'a, str>,
+        }
+
+        let json = br#"{"plain": "needs \"escaping\"", "escaped": ""}"#;
+        let index = JsonIndex::build(json);
+        // A string with escapes can't be borrowed
+        assert!(parse::<Names<'_>>(&index, json).is_err());
+
+        let json = br#"{"plain": "zero-copy", "escaped": "a\"b"}"#;
+        let index = JsonIndex::build(json);
+        let names: Names<'_> = parse(&index, json).unwrap();
+        assert_eq!(names.plain, "zero-copy");
+        assert_eq!(names.escaped, "a\"b");
+    }
+
+    #[test]
+    fn test_sequences_tuples_and_maps() {
+        let json = br#"{"list": [1, 2, 3], "pair": ["a", true], "map": {"k": [null, 1]}}"#;
+        let index = JsonIndex::build(json);
+
+        #[derive(Deserialize)]
+        struct All {
+            list: Vec<u8>,
+            pair: (String, bool),
+            map: BTreeMap<String, Vec<Option<u64>>>,
+        }
+        let all: All = parse(&index, json).unwrap();
+        assert_eq!(all.list, [1, 2, 3]);
+        assert_eq!(all.pair, ("a".to_string(), true));
+        assert_eq!(all.map["k"], [None, Some(1)]);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let json = b"[18446744073709551615, -9223372036854775808, 1e3, 0.5]";
+        let index = JsonIndex::build(json);
+        let numbers: (u64, i64, f64, f32) = parse(&index, json).unwrap();
+        assert_eq!(numbers, (u64::MAX, i64::MIN, 1000.0, 0.5));
+
+        let json = b"[1.5]";
+        let index = JsonIndex::build(json);
+        assert!(parse::<Vec<i64>>(&index, json).is_err());
+    }
+
+    #[test]
+    fn test_enums() {
+        let json = br#"["Empty", {"Circle": 2.0}, {"Line": [{"x": 0, "y": 0, "label": "a"}, {"x": 1, "y": 1}]}, {"Rect": {"w": 3, "h": 4}}]"#;
+        let index = JsonIndex::build(json);
+        let shapes: Vec<Shape> = parse(&index, json).unwrap();
+        assert_eq!(shapes.len(), 4);
+        assert_eq!(shapes[0], Shape::Empty);
+        assert_eq!(shapes[1], Shape::Circle(2.0));
+        assert!(matches!(&shapes[2], Shape::Line(a, _) if a.label.as_deref() == Some("a")));
+        assert_eq!(shapes[3], Shape::Rect { w: 3, h: 4 });
+    }
+
+    #[test]
+    fn test_type_mismatch_errors() {
+        let json = br#"{"x": "one", "y": 1}"#;
+        let index = JsonIndex::build(json);
+        let err = parse::<Point>(&index, json).unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{err}");
+
+        let json = br#"{"x": 1}"#;
+        let index = JsonIndex::build(json);
+        let err = parse::<Point>(&index, json).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `y`");
+
+        // JSON has no NaN
+        let json = br#"{"x": 1, "y": -NaN}"#;
+        let index = JsonIndex::build(json);
+        assert!(parse::<Point>(&index, json).is_err());
+    }
+
+    #[test]
+    fn test_nested_cursor() {
+        // Deserialize from a cursor below the root
+        let json = br#"{"meta": {}, "points": [{"x": 1, "y": 2}]}"#;
+        let index = JsonIndex::build(json);
+        let points = index.root(json).children().nth(3).expect("points value");
+        let points: Vec<Point> = from_cursor(points).unwrap();
+        assert_eq!(points[0].x, 1);
+    }
+}
@@ -0,0 +1,315 @@
+#![allow(unsafe_code)] // x86_64 AVX-512 SIMD intrinsics
+#![allow(clippy::incompatible_msrv)] // behind the `avx512` feature, which documents its Rust 1.89 requirement
+//! AVX-512BW-accelerated JSON semi-indexing for x86_64.
+//!
+//! Processes 64 bytes at a time. AVX-512BW byte compares write straight into
+//! a 64-bit mask register (`_mm512_cmpeq_epi8_mask`), so each character class
+//! is one compare per 64 bytes, with no `movemask` step and no need to OR two
+//! 32-bit halves together as the AVX2 path does. AVX-512BW is available on
+//! Intel Skylake-X (2017+) and AMD Zen 4 (2022+).
+//!
+//! Only the standard cursor is implemented; the simple cursor stays on AVX2.
+//!
+//! Requires the `avx512` feature: the AVX-512 intrinsics were stabilized in
+//! Rust 1.89, above the crate's MSRV.
+//!
+//! # Performance
+//!
+//! Character classification is only part of the work: the per-byte state
+//! machine and the IB/BP bit writers are unchanged from AVX2, so wider
+//! registers help only as far as classification is the bottleneck. An
+//! earlier AVX-512 attempt measured 7-17% *slower* than AVX2 on AMD Zen 4,
+//! which splits 512-bit ops into two 256-bit micro-ops
+//! (see `docs/optimizations/history.md`).
+//!
+//! Criterion medians for `build_semi_index_standard` on the generated
+//! `comprehensive` 100 MB file (84 MB on disk), measured with:
+//!
+//! ```bash
+//! mkdir -p data/bench/generated/comprehensive
+//! cargo run --features cli -- json generate 100mb -o data/bench/generated/comprehensive/100mb.json
+//! cargo bench --bench json_simd_indexing --features avx512 -- 'json_indexing/(AVX512|AVX2)/'
+//! ```
+//!
+//! The machine was a 1-vCPU VM reporting "Intel(R) Xeon(R) Processor" with
+//! AVX-512BW (exact CPU model not exposed), Rust 1.95.0. Two runs:
+//!
+//! | Backend | Run 1             | Run 2             |
+//! |---------|-------------------|-------------------|
+//! | AVX2    | 495 ms, 161 MiB/s | 502 ms, 159 MiB/s |
+//! | AVX-512 | 383 ms, 209 MiB/s | 432 ms, 185 MiB/s |
+//!
+//! Criterion's intervals were about ±5%, and a shared VM is noisy, so read
+//! this as "faster on this Intel part", not as a precise ratio. Re-measure on
+//! AMD before treating it as a win there.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::json::standard::{SemiIndex, State};
+use crate::json::BitWriter;
+
+/// Character classification results for a 64-byte chunk.
+#[derive(Debug, Clone, Copy)]
+struct CharClass {
+    /// Mask of bytes that are '"'
+    quotes: u64,
+    /// Mask of bytes that are '\'
+    backslashes: u64,
+    /// Mask of bytes that are '{' or '['
+    opens: u64,
+    /// Mask of bytes that are '}' or ']'
+    closes: u64,
+    /// Mask of bytes that are ',' or ':'
+    delims: u64,
+    /// Mask of bytes that could start/continue a value (alphanumeric, ., -, +)
+    value_chars: u64,
+}
+
+/// Classify 64 bytes at once using AVX-512BW.
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn classify_chars(chunk: __m512i) -> CharClass {
+    unsafe {
+        let quotes = eq_mask(chunk, b'"');
+        let backslashes = eq_mask(chunk, b'\\');
+        let opens = eq_mask(chunk, b'{') | eq_mask(chunk, b'[');
+        let closes = eq_mask(chunk, b'}') | eq_mask(chunk, b']');
+        let delims = eq_mask(chunk, b',') | eq_mask(chunk, b':');
+
+        // Value chars: alphanumeric, period, minus, plus
+        let alpha = range_mask(chunk, b'a', b'z') | range_mask(chunk, b'A', b'Z');
+        let digit = range_mask(chunk, b'0', b'9');
+        let punct = eq_mask(chunk, b'.') | eq_mask(chunk, b'-') | eq_mask(chunk, b'+');
+
+        CharClass {
+            quotes,
+            backslashes,
+            opens,
+            closes,
+            delims,
+            value_chars: alpha | digit | punct,
+        }
+    }
+}
+
+/// Mask of bytes equal to `c`.
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn eq_mask(chunk: __m512i, c: u8) -> u64 {
+    _mm512_cmpeq_epi8_mask(chunk, _mm512_set1_epi8(c as i8))
+}
+
+/// Mask of bytes in `lo..=hi`, via the unsigned check `(c - lo) <= (hi - lo)`.
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+#[cfg(target_arch = "x86_64")]
+unsafe fn range_mask(chunk: __m512i, lo: u8, hi: u8) -> u64 {
+    let shifted = _mm512_sub_epi8(chunk, _mm512_set1_epi8(lo as i8));
+    _mm512_cmple_epu8_mask(shifted, _mm512_set1_epi8((hi - lo) as i8))
+}
+
+/// Process a 64-byte chunk and update IB/BP writers.
+/// Returns the new state after processing all 64 bytes.
+#[inline]
+fn process_chunk_standard(
+    class: CharClass,
+    mut state: State,
+    ib: &mut BitWriter,
+    bp: &mut BitWriter,
+    bytes: &[u8],
+) -> State {
+    for i in 0..bytes.len().min(64) {
+        let bit = 1u64 << i;
+
+        let is_quote = (class.quotes & bit) != 0;
+        let is_backslash = (class.backslashes & bit) != 0;
+        let is_open = (class.opens & bit) != 0;
+        let is_close = (class.closes & bit) != 0;
+        let is_delim = (class.delims & bit) != 0;
+        let is_value_char = (class.value_chars & bit) != 0;
+
+        match state {
+            State::InJson => {
+                if is_open {
+                    ib.write_1();
+                    bp.write_1();
+                } else if is_close {
+                    ib.write_0();
+                    bp.write_0();
+                } else if is_delim {
+                    ib.write_0();
+                } else if is_value_char {
+                    ib.write_1();
+                    bp.write_1();
+                    bp.write_0();
+                    state = State::InValue;
+                } else if is_quote {
+                    ib.write_1();
+                    bp.write_1();
+                    bp.write_0();
+                    state = State::InString;
+                } else {
+                    // whitespace or other
+                    ib.write_0();
+                }
+            }
+            State::InString => {
+                ib.write_0();
+                if is_quote {
+                    state = State::InJson;
+                } else if is_backslash {
+                    state = State::InEscape;
+                }
+            }
+            State::InEscape => {
+                ib.write_0();
+                state = State::InString;
+            }
+            State::InValue => {
+                if is_open {
+                    ib.write_1();
+                    bp.write_1();
+                    state = State::InJson;
+                } else if is_close {
+                    ib.write_0();
+                    bp.write_0();
+                    state = State::InJson;
+                } else if is_delim {
+                    ib.write_0();
+                    state = State::InJson;
+                } else if is_value_char {
+                    ib.write_0();
+                } else {
+                    // whitespace ends value
+                    ib.write_0();
+                    state = State::InJson;
+                }
+            }
+        }
+    }
+
+    state
+}
+
+/// Build a semi-index from JSON bytes using SIMD-accelerated Standard Cursor algorithm.
+///
+/// Processes 64 bytes at a time using x86_64 AVX-512BW instructions for
+/// character classification, then processes the state machine transitions.
+#[cfg(target_arch = "x86_64")]
+pub fn build_semi_index_standard(json: &[u8]) -> SemiIndex {
+    // SAFETY: Caller must ensure AVX-512F and AVX-512BW are available
+    unsafe { build_semi_index_standard_avx512(json) }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn build_semi_index_standard_avx512(json: &[u8]) -> SemiIndex {
+    unsafe {
+        let word_capacity = json.len().div_ceil(64);
+        let mut ib = BitWriter::with_capacity(word_capacity);
+        let mut bp = BitWriter::with_capacity(word_capacity * 2);
+        let mut state = State::InJson;
+
+        let mut offset = 0;
+
+        // Process 64-byte chunks
+        while offset + 64 <= json.len() {
+            let chunk = _mm512_loadu_si512(json.as_ptr().add(offset).cast());
+            let class = classify_chars(chunk);
+            state =
+                process_chunk_standard(class, state, &mut ib, &mut bp, &json[offset..offset + 64]);
+            offset += 64;
+        }
+
+        // Process remaining bytes (less than 64)
+        if offset < json.len() {
+            // Pad with zeros and process
+            let mut padded = [0u8; 64];
+            let remaining = json.len() - offset;
+            padded[..remaining].copy_from_slice(&json[offset..]);
+
+            let chunk = _mm512_loadu_si512(padded.as_ptr().cast());
+            let class = classify_chars(chunk);
+            state = process_chunk_standard(class, state, &mut ib, &mut bp, &json[offset..]);
+        }
+
+        SemiIndex {
+            state,
+            ib: ib.finish(),
+            bp: bp.finish(),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+
+    fn has_avx512bw() -> bool {
+        is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw")
+    }
+
+    fn assert_matches_scalar(json: &[u8]) {
+        let simd_result = build_semi_index_standard(json);
+        let scalar_result = crate::json::standard::build_semi_index(json);
+
+        assert_eq!(simd_result.ib, scalar_result.ib, "IB mismatch for {json:?}");
+        assert_eq!(simd_result.bp, scalar_result.bp, "BP mismatch for {json:?}");
+        assert_eq!(simd_result.state, scalar_result.state);
+    }
+
+    #[test]
+    fn test_classify_chars() {
+        if !has_avx512bw() {
+            return;
+        }
+
+        let mut input = [b' '; 64];
+        input[..13].copy_from_slice(br#"{"hello":123}"#);
+        input[63] = b']';
+        let class = unsafe { classify_chars(_mm512_loadu_si512(input.as_ptr().cast())) };
+
+        assert_eq!(class.opens, 1 << 0);
+        assert_eq!(class.closes, (1 << 12) | (1 << 63));
+        assert_eq!(class.quotes, (1 << 1) | (1 << 7));
+        assert_eq!(class.delims, 1 << 8);
+        assert_eq!(class.backslashes, 0);
+        // "hello" (2..7) and "123" (9..12)
+        assert_eq!(class.value_chars, 0b1110_0111_1100);
+    }
+
+    #[test]
+    fn test_avx512_matches_scalar_small() {
+        if !has_avx512bw() {
+            return;
+        }
+
+        assert_matches_scalar(b"");
+        assert_matches_scalar(b"{}");
+        assert_matches_scalar(br#"{"a":"b\"c"}"#);
+        assert_matches_scalar(b"[1, -2.5e+3, true, false, null]");
+    }
+
+    #[test]
+    fn test_avx512_matches_scalar_chunk_boundaries() {
+        if !has_avx512bw() {
+            return;
+        }
+
+        // Lengths straddling the 64-byte chunk size, with strings, escapes and
+        // numbers crossing chunk boundaries
+        let unit = br#"{"k":"v\\\"","n":[12345,-0.5e10],"t":true,"z":null},"#;
+        let mut json = b"[".to_vec();
+        for _ in 0..8 {
+            json.extend_from_slice(unit);
+        }
+        json.extend_from_slice(b"0]");
+
+        for len in [63, 64, 65, 127, 128, 129, json.len()] {
+            assert_matches_scalar(&json[..len.min(json.len())]);
+        }
+    }
+}
@@ -11,6 +11,7 @@
 //!
 //! - **SSE2** (baseline): 16 bytes/iteration, universal availability
 //! - **AVX2** (optimal): 32 bytes/iteration, ~95% availability (2013+)
+//! - **AVX-512BW** (`avx512` feature): 64 bytes/iteration, standard cursor only (Skylake-X+, Zen 4+)
 //!
 //! The implementation uses runtime CPU detection to automatically select
 //! the best available instruction set.
@@ -26,6 +27,12 @@
 //!   - Enable with `SUCCINCTLY_SVE2=1` environment variable (not recommended);
 //!     see `docs/reference/environment-variables.md`
 //!
+//! ## WebAssembly
+//!
+//! On wasm32 built with `-C target-feature=+simd128`, the standard cursor
+//! uses **SIMD128** (16 bytes/iteration). WebAssembly has no runtime feature
+//! detection, so without that flag both cursors use the scalar implementation.
+//!
 //! ## Cursor Algorithms
 //!
 //! Both simple and standard cursor algorithms are provided:
@@ -44,6 +51,12 @@ pub mod x86;
 #[cfg(target_arch = "x86_64")]
 pub mod avx2;
 
+#[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+pub mod avx512;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub mod wasm32;
+
 // ============================================================================
 // ARM exports with optional runtime dispatch to SVE2 (requires std)
 // ============================================================================
@@ -104,9 +117,13 @@ pub use neon::build_semi_index_standard;
 // ============================================================================
 
 // Runtime dispatch when std is available (test mode or std feature)
-// Priority: AVX2 > SSE2
+// Priority: AVX-512BW > AVX2 > SSE2 (simple cursor: AVX2 > SSE2)
 #[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
 pub fn build_semi_index_standard(json: &[u8]) -> crate::json::standard::SemiIndex {
+    #[cfg(feature = "avx512")]
+    if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+        return avx512::build_semi_index_standard(json);
+    }
     if is_x86_feature_detected!("avx2") {
         avx2::build_semi_index_standard(json)
     } else {
@@ -138,5 +155,12 @@ pub use x86::build_semi_index_standard;
 #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
 pub use super::simple::build_semi_index as build_semi_index_simple;
 
-#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub use wasm32::build_semi_index_standard;
+
+#[cfg(not(any(
+    target_arch = "aarch64",
+    target_arch = "x86_64",
+    all(target_arch = "wasm32", target_feature = "simd128")
+)))]
 pub use super::standard::build_semi_index as build_semi_index_standard;
@@ -0,0 +1,245 @@
+#![allow(unsafe_code)] // wasm32 SIMD128 intrinsics
+//! WebAssembly SIMD128-accelerated JSON semi-indexing.
+//!
+//! Processes 16 bytes at a time using the `wasm32` SIMD128 instructions,
+//! the same chunk width and state machine as the SSE2 backend in
+//! [`x86`](super::x86). SIMD128 has no dynamic feature detection, so this
+//! module is only compiled when the crate itself is built with
+//! `-C target-feature=+simd128`; without it the scalar cursor is used.
+//!
+//! Only the standard cursor is vectorized; the simple cursor stays scalar.
+
+use core::arch::wasm32::*;
+
+use crate::json::standard::{SemiIndex, State};
+use crate::json::BitWriter;
+
+/// Character classification results for a 16-byte chunk.
+#[derive(Debug, Clone, Copy)]
+struct CharClass {
+    /// Mask of bytes that are '"'
+    quotes: u16,
+    /// Mask of bytes that are '\'
+    backslashes: u16,
+    /// Mask of bytes that are '{' or '['
+    opens: u16,
+    /// Mask of bytes that are '}' or ']'
+    closes: u16,
+    /// Mask of bytes that are ',' or ':'
+    delims: u16,
+    /// Mask of bytes that could start/continue a value (alphanumeric, ., -, +)
+    value_chars: u16,
+}
+
+/// Lanes of `chunk` in `lo..=hi`, as one unsigned `chunk - lo <= hi - lo`.
+#[inline]
+fn in_range(chunk: v128, lo: u8, hi: u8) -> v128 {
+    u8x16_le(u8x16_sub(chunk, u8x16_splat(lo)), u8x16_splat(hi - lo))
+}
+
+/// Classify 16 bytes at once using SIMD128.
+#[inline]
+fn classify_chars(chunk: v128) -> CharClass {
+    let eq = |byte: u8| u8x16_eq(chunk, u8x16_splat(byte));
+
+    let opens = v128_or(eq(b'{'), eq(b'['));
+    let closes = v128_or(eq(b'}'), eq(b']'));
+    let delims = v128_or(eq(b','), eq(b':'));
+
+    // Value chars: alphanumeric, period, minus, plus
+    let alpha = v128_or(in_range(chunk, b'a', b'z'), in_range(chunk, b'A', b'Z'));
+    let alnum = v128_or(alpha, in_range(chunk, b'0', b'9'));
+    let punct = v128_or(v128_or(eq(b'.'), eq(b'-')), eq(b'+'));
+    let value_chars = v128_or(alnum, punct);
+
+    CharClass {
+        quotes: i8x16_bitmask(eq(b'"')),
+        backslashes: i8x16_bitmask(eq(b'\\')),
+        opens: i8x16_bitmask(opens),
+        closes: i8x16_bitmask(closes),
+        delims: i8x16_bitmask(delims),
+        value_chars: i8x16_bitmask(value_chars),
+    }
+}
+
+/// Whether a chunk seen from inside a string is string content throughout:
+/// no quote ends the string and no backslash starts an escape.
+#[inline]
+fn is_plain_string(chunk: v128) -> bool {
+    let quotes = u8x16_eq(chunk, u8x16_splat(b'"'));
+    let backslashes = u8x16_eq(chunk, u8x16_splat(b'\\'));
+    !v128_any_true(v128_or(quotes, backslashes))
+}
+
+/// Process a 16-byte chunk and update IB/BP writers.
+/// Returns the new state after processing all 16 bytes.
+#[inline]
+fn process_chunk_standard(
+    class: CharClass,
+    mut state: State,
+    ib: &mut BitWriter,
+    bp: &mut BitWriter,
+    len: usize,
+) -> State {
+    for i in 0..len.min(16) {
+        let bit = 1u16 << i;
+
+        let is_quote = (class.quotes & bit) != 0;
+        let is_backslash = (class.backslashes & bit) != 0;
+        let is_open = (class.opens & bit) != 0;
+        let is_close = (class.closes & bit) != 0;
+        let is_delim = (class.delims & bit) != 0;
+        let is_value_char = (class.value_chars & bit) != 0;
+
+        match state {
+            State::InJson => {
+                if is_open {
+                    ib.write_1();
+                    bp.write_1();
+                } else if is_close {
+                    ib.write_0();
+                    bp.write_0();
+                } else if is_delim {
+                    ib.write_0();
+                } else if is_value_char {
+                    ib.write_1();
+                    bp.write_1();
+                    bp.write_0();
+                    state = State::InValue;
+                } else if is_quote {
+                    ib.write_1();
+                    bp.write_1();
+                    bp.write_0();
+                    state = State::InString;
+                } else {
+                    // whitespace or other
+                    ib.write_0();
+                }
+            }
+            State::InString => {
+                ib.write_0();
+                if is_quote {
+                    state = State::InJson;
+                } else if is_backslash {
+                    state = State::InEscape;
+                }
+            }
+            State::InEscape => {
+                ib.write_0();
+                state = State::InString;
+            }
+            State::InValue => {
+                if is_open {
+                    ib.write_1();
+                    bp.write_1();
+                    state = State::InJson;
+                } else if is_close {
+                    ib.write_0();
+                    bp.write_0();
+                    state = State::InJson;
+                } else if is_delim {
+                    ib.write_0();
+                    state = State::InJson;
+                } else if is_value_char {
+                    ib.write_0();
+                } else {
+                    // whitespace ends value
+                    ib.write_0();
+                    state = State::InJson;
+                }
+            }
+        }
+    }
+
+    state
+}
+
+/// Build a semi-index from JSON bytes using SIMD-accelerated Standard Cursor algorithm.
+///
+/// Processes 16 bytes at a time using SIMD128 instructions for character
+/// classification, then processes the state machine transitions. Chunks
+/// that fall entirely inside a string skip classification and emit their
+/// zero IB bits in one write.
+pub fn build_semi_index_standard(json: &[u8]) -> SemiIndex {
+    let word_capacity = json.len().div_ceil(64);
+    let mut ib = BitWriter::with_capacity(word_capacity);
+    let mut bp = BitWriter::with_capacity(word_capacity * 2);
+    let mut state = State::InJson;
+
+    let mut chunks = json.chunks_exact(16);
+    for chunk in &mut chunks {
+        // SAFETY: the chunk is 16 bytes long and v128_load has no alignment requirement
+        let chunk = unsafe { v128_load(chunk.as_ptr().cast::<v128>()) };
+        if state == State::InString && is_plain_string(chunk) {
+            ib.write_zeros(16);
+            continue;
+        }
+        state = process_chunk_standard(classify_chars(chunk), state, &mut ib, &mut bp, 16);
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        // Pad with zeros and process
+        let mut padded = [0u8; 16];
+        padded[..remainder.len()].copy_from_slice(remainder);
+        // SAFETY: `padded` is 16 bytes long
+        let chunk = unsafe { v128_load(padded.as_ptr().cast::<v128>()) };
+        state = process_chunk_standard(
+            classify_chars(chunk),
+            state,
+            &mut ib,
+            &mut bp,
+            remainder.len(),
+        );
+    }
+
+    SemiIndex {
+        state,
+        ib: ib.finish(),
+        bp: bp.finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_scalar(json: &[u8]) {
+        let simd = build_semi_index_standard(json);
+        let scalar = crate::json::standard::build_semi_index(json);
+        assert_eq!(simd.ib, scalar.ib, "IB mismatch for {json:?}");
+        assert_eq!(simd.bp, scalar.bp, "BP mismatch for {json:?}");
+        assert_eq!(simd.state, scalar.state, "state mismatch for {json:?}");
+    }
+
+    #[test]
+    fn test_classify_value_chars() {
+        let class = classify_chars(u8x16(
+            b'a', b'z', b'A', b'Z', b'0', b'9', b'.', b'-', b'+', b'`', b'{', b'@', b'[', b'/',
+            b':', 0xE1,
+        ));
+        assert_eq!(class.value_chars, 0b0000_0001_1111_1111);
+        assert_eq!(class.opens, (1 << 10) | (1 << 12));
+        assert_eq!(class.delims, 1 << 14);
+    }
+
+    #[test]
+    fn test_matches_scalar() {
+        let long_string = format!(r#"{{"text":"{}","n":1}}"#, "x".repeat(100));
+        let inputs: [&[u8]; 8] = [
+            b"",
+            b"{}",
+            br#"{"a":"b"}"#,
+            br#"[1, -2.5e+3, true, false, null]"#,
+            br#"{"escaped \"quote\" and \\":"v", "arr": [{"x": 1}]}"#,
+            br#"{"unterminated"#,
+            br#"{"split escape at 16\\\"x":0}"#,
+            long_string.as_bytes(),
+        ];
+        for json in inputs {
+            for end in 0..=json.len() {
+                assert_matches_scalar(&json[..end]);
+            }
+        }
+    }
+}
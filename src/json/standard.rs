@@ -55,6 +55,19 @@ pub enum State {
     InValue,
 }
 
+impl State {
+    fn from_pfsm(state: crate::json::pfsm_tables::PfsmState) -> Self {
+        use crate::json::pfsm_tables::PfsmState;
+
+        match state {
+            PfsmState::InJson => Self::InJson,
+            PfsmState::InString => Self::InString,
+            PfsmState::InEscape => Self::InEscape,
+            PfsmState::InValue => Self::InValue,
+        }
+    }
+}
+
 /// Result of standard cursor semi-indexing.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -250,12 +263,31 @@ pub fn build_semi_index(json: &[u8]) -> SemiIndex {
         pfsm_optimized::pfsm_process_chunk_optimized(json, PfsmState::InJson, &mut ib, &mut bp);
 
     SemiIndex {
-        state: match state {
-            PfsmState::InJson => State::InJson,
-            PfsmState::InString => State::InString,
-            PfsmState::InEscape => State::InEscape,
-            PfsmState::InValue => State::InValue,
-        },
+        state: State::from_pfsm(state),
+        ib: ib.finish(),
+        bp: bp.finish(),
+    }
+}
+
+/// Build a semi-index with the PFSM tables applied 32 bytes at a time.
+///
+/// On CPUs with AVX2 the per-byte transitions are looked up with `vpshufb` and
+/// composed with a parallel prefix scan instead of a byte-serial loop. Without
+/// AVX2 this is the same as [`build_semi_index`]. The result is identical
+/// either way.
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+pub fn build_semi_index_pfsm_simd(json: &[u8]) -> SemiIndex {
+    use crate::json::pfsm_optimized;
+    use crate::json::pfsm_tables::PfsmState;
+
+    let word_capacity = json.len().div_ceil(64);
+    let mut ib = BitWriter::with_capacity(word_capacity);
+    let mut bp = BitWriter::with_capacity(word_capacity * 2);
+
+    let state = pfsm_optimized::pfsm_process_chunk_simd(json, PfsmState::InJson, &mut ib, &mut bp);
+
+    SemiIndex {
+        state: State::from_pfsm(state),
         ib: ib.finish(),
         bp: bp.finish(),
     }
@@ -521,6 +553,20 @@ mod tests {
         }
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_pfsm_simd_matches_pfsm_semi_index() {
+        // Long enough to cover full 32-byte chunks plus a scalar tail
+        let json = br#"{"users":[{"name":"Al\"ice","age":30,"tags":["a","b"]},{"ok":true,"n":-1.5e+3}],"x":null}"#;
+        for len in 0..=json.len() {
+            let pfsm = build_semi_index(&json[..len]);
+            let simd = build_semi_index_pfsm_simd(&json[..len]);
+            assert_eq!(simd.ib, pfsm.ib, "IB mismatch at length {len}");
+            assert_eq!(simd.bp, pfsm.bp, "BP mismatch at length {len}");
+            assert_eq!(simd.state, pfsm.state, "state mismatch at length {len}");
+        }
+    }
+
     #[test]
     fn test_semi_index_byte_roundtrip() {
         // ib_as_bytes/bp_as_bytes -> from_bytes must reconstruct identical words.
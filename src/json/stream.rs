@@ -0,0 +1,621 @@
+//! Pull-style JSON parsing over input that arrives in chunks.
+//!
+//! [`JsonStreamParser`] accepts bytes as they arrive, from a socket or a
+//! pipe, and returns the [`ParseEvent`]s each chunk completes. Between
+//! calls it keeps a stack of open containers and the bytes of any token
+//! split across chunks, so events do not depend on where the input is cut.
+//! The stream may hold several top-level values separated by whitespace,
+//! as in NDJSON.
+//!
+//! Unlike [`JsonIndex`](super::JsonIndex), this never needs the whole
+//! document at once, but it decodes every string and number it passes.
+//!
+//! # Example
+//!
+//! ```
+//! use succinctly::json::stream::{to_owned_value_stream, JsonStreamParser, ParseEvent};
+//!
+//! let mut parser = JsonStreamParser::new();
+//! let mut events = parser.feed(br#"{"id": 4"#).unwrap();
+//! assert_eq!(events, [ParseEvent::StartObject, ParseEvent::Key("id".into())]);
+//! events.extend(parser.feed(b"2, \"ok\": true}").unwrap());
+//! events.extend(parser.finish().unwrap());
+//!
+//! let value = to_owned_value_stream(events);
+//! assert_eq!(value.to_json(), r#"{"id":42,"ok":true}"#);
+//! ```
+
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use indexmap::IndexMap;
+
+use super::light::{decode_escapes, JsonError};
+use crate::jq::OwnedValue;
+
+/// One step of a JSON document, in document order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseEvent {
+    /// `{`
+    StartObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    StartArray,
+    /// `]`
+    EndArray,
+    /// An object key, decoded
+    Key(String),
+    /// A string value, decoded
+    String(String),
+    /// A number value
+    Number(f64),
+    /// `true` or `false`
+    Bool(bool),
+    /// `null`
+    Null,
+}
+
+/// Why a [`JsonStreamParser`] rejected its input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonStreamError {
+    /// A byte that cannot appear where it was found
+    UnexpectedByte {
+        /// Offset of the byte from the start of the stream
+        offset: usize,
+        /// The byte
+        byte: u8,
+    },
+    /// A string or number that does not decode
+    InvalidToken {
+        /// Offset from the start of the stream of the byte that ended it
+        offset: usize,
+        /// What is wrong with it
+        error: JsonError,
+    },
+    /// The input ended inside a value
+    UnexpectedEnd,
+}
+
+impl fmt::Display for JsonStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedByte { offset, byte } => {
+                write!(f, "unexpected byte 0x{byte:02x} at offset {offset}")
+            }
+            Self::InvalidToken { offset, error } => write!(f, "{error} at offset {offset}"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonStreamError {}
+
+/// What the parser accepts next, outside a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Expect {
+    /// A top-level value, or the end of the stream
+    TopLevel,
+    /// A value after `:` or `,` in an array
+    Value,
+    /// A value or `]` right after `[`
+    ValueOrEndArray,
+    /// A key or `}` right after `{`
+    KeyOrEndObject,
+    /// A key after `,` in an object
+    Key,
+    /// `:` after a key
+    Colon,
+    /// `,` or the end of the innermost container
+    CommaOrEnd,
+}
+
+/// A token whose bytes may span several chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    /// A string, the buffer holding its raw contents so far
+    String {
+        /// Whether this string is an object key
+        key: bool,
+        /// Whether the last buffered byte is an unconsumed `\`
+        escaped: bool,
+    },
+    /// A number, the buffer holding its characters so far
+    Number,
+    /// `true`, `false` or `null`, the buffer holding its prefix so far
+    Literal(&'static [u8]),
+}
+
+/// An incremental JSON parser fed one chunk at a time.
+///
+/// See the [module documentation](self) for an example. After an error the
+/// parser's state is unspecified; start a new one to parse more input.
+#[derive(Clone, Debug)]
+pub struct JsonStreamParser {
+    /// Open containers, `true` for objects
+    stack: Vec<bool>,
+    expect: Expect,
+    token: Option<Token>,
+    buffer: Vec<u8>,
+    /// Stream offset of the first byte of the next chunk
+    offset: usize,
+}
+
+impl Default for JsonStreamParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonStreamParser {
+    /// Create a parser at the start of a stream.
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            expect: Expect::TopLevel,
+            token: None,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Parse the next chunk of the stream, returning the events of every
+    /// token it completes.
+    ///
+    /// A number at the end of a chunk is held back until a later byte (or
+    /// [`finish`](Self::finish)) shows where it ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonStreamError::UnexpectedByte`] for a byte JSON does not
+    /// allow at its position, and [`JsonStreamError::InvalidToken`] for a
+    /// malformed number or string.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<ParseEvent>, JsonStreamError> {
+        let mut events = Vec::new();
+        for (i, &byte) in chunk.iter().enumerate() {
+            self.byte(byte, self.offset + i, &mut events)?;
+        }
+        self.offset += chunk.len();
+        Ok(events)
+    }
+
+    /// End the stream, returning the events of a trailing top-level number.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonStreamError::UnexpectedEnd`] if the stream ends inside
+    /// a container, string or literal, or after a `,` or `:`.
+    pub fn finish(&mut self) -> Result<Vec<ParseEvent>, JsonStreamError> {
+        let mut events = Vec::new();
+        if self.token == Some(Token::Number) {
+            self.end_number(self.offset, &mut events)?;
+        }
+        if self.token.is_some() || self.expect != Expect::TopLevel {
+            return Err(JsonStreamError::UnexpectedEnd);
+        }
+        Ok(events)
+    }
+
+    /// Nesting depth of the containers open at the end of the input so far.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn byte(
+        &mut self,
+        byte: u8,
+        offset: usize,
+        events: &mut Vec<ParseEvent>,
+    ) -> Result<(), JsonStreamError> {
+        match self.token {
+            Some(Token::String { key, escaped }) => {
+                if byte == b'"' && !escaped {
+                    let text = decode_escapes(&self.buffer)
+                        .map_err(|error| JsonStreamError::InvalidToken { offset, error })?;
+                    self.buffer.clear();
+                    self.token = None;
+                    if key {
+                        events.push(ParseEvent::Key(text));
+                        self.expect = Expect::Colon;
+                    } else {
+                        events.push(ParseEvent::String(text));
+                        self.end_value();
+                    }
+                } else if byte < 0x20 {
+                    return Err(JsonStreamError::UnexpectedByte { offset, byte });
+                } else {
+                    self.buffer.push(byte);
+                    self.token = Some(Token::String {
+                        key,
+                        escaped: byte == b'\\' && !escaped,
+                    });
+                }
+                return Ok(());
+            }
+            Some(Token::Number) => {
+                if matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                    self.buffer.push(byte);
+                    return Ok(());
+                }
+                // The byte after a number is parsed on its own below
+                self.end_number(offset, events)?;
+            }
+            Some(Token::Literal(word)) => {
+                if word.get(self.buffer.len()) != Some(&byte) {
+                    return Err(JsonStreamError::UnexpectedByte { offset, byte });
+                }
+                self.buffer.push(byte);
+                if self.buffer.len() == word.len() {
+                    events.push(match word {
+                        b"true" => ParseEvent::Bool(true),
+                        b"false" => ParseEvent::Bool(false),
+                        _ => ParseEvent::Null,
+                    });
+                    self.buffer.clear();
+                    self.token = None;
+                    self.end_value();
+                }
+                return Ok(());
+            }
+            None => {}
+        }
+
+        if matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            return Ok(());
+        }
+        let unexpected = JsonStreamError::UnexpectedByte { offset, byte };
+        match self.expect {
+            Expect::TopLevel | Expect::Value | Expect::ValueOrEndArray => {
+                if byte == b']' && self.expect == Expect::ValueOrEndArray {
+                    self.close(false, events);
+                } else {
+                    self.start_value(byte, events).ok_or(unexpected)?;
+                }
+            }
+            Expect::KeyOrEndObject | Expect::Key => match byte {
+                b'"' => {
+                    self.token = Some(Token::String {
+                        key: true,
+                        escaped: false,
+                    });
+                }
+                b'}' if self.expect == Expect::KeyOrEndObject => self.close(true, events),
+                _ => return Err(unexpected),
+            },
+            Expect::Colon if byte == b':' => self.expect = Expect::Value,
+            Expect::CommaOrEnd => match (byte, self.stack.last()) {
+                (b',', Some(&object)) => {
+                    self.expect = if object { Expect::Key } else { Expect::Value };
+                }
+                (b'}', Some(true)) => self.close(true, events),
+                (b']', Some(false)) => self.close(false, events),
+                _ => return Err(unexpected),
+            },
+            Expect::Colon => return Err(unexpected),
+        }
+        Ok(())
+    }
+
+    /// Begin the value whose first byte is `byte`, or `None` if no value
+    /// starts with it.
+    fn start_value(&mut self, byte: u8, events: &mut Vec<ParseEvent>) -> Option<()> {
+        match byte {
+            b'{' => {
+                events.push(ParseEvent::StartObject);
+                self.stack.push(true);
+                self.expect = Expect::KeyOrEndObject;
+            }
+            b'[' => {
+                events.push(ParseEvent::StartArray);
+                self.stack.push(false);
+                self.expect = Expect::ValueOrEndArray;
+            }
+            b'"' => {
+                self.token = Some(Token::String {
+                    key: false,
+                    escaped: false,
+                });
+            }
+            b'-' | b'0'..=b'9' => {
+                self.buffer.push(byte);
+                self.token = Some(Token::Number);
+            }
+            b't' | b'f' | b'n' => {
+                let word: &'static [u8] = match byte {
+                    b't' => b"true",
+                    b'f' => b"false",
+                    _ => b"null",
+                };
+                self.buffer.push(byte);
+                self.token = Some(Token::Literal(word));
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn end_number(
+        &mut self,
+        offset: usize,
+        events: &mut Vec<ParseEvent>,
+    ) -> Result<(), JsonStreamError> {
+        let invalid = JsonStreamError::InvalidToken {
+            offset,
+            error: JsonError::InvalidNumber,
+        };
+        if !is_json_number(&self.buffer) {
+            return Err(invalid);
+        }
+        let number = core::str::from_utf8(&self.buffer)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or(invalid)?;
+        events.push(ParseEvent::Number(number));
+        self.buffer.clear();
+        self.token = None;
+        self.end_value();
+        Ok(())
+    }
+
+    fn close(&mut self, object: bool, events: &mut Vec<ParseEvent>) {
+        self.stack.pop();
+        events.push(if object {
+            ParseEvent::EndObject
+        } else {
+            ParseEvent::EndArray
+        });
+        self.end_value();
+    }
+
+    /// Move past a complete value.
+    fn end_value(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::TopLevel
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+}
+
+/// Whether `bytes` matches the JSON number grammar:
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`.
+fn is_json_number(bytes: &[u8]) -> bool {
+    fn digits(bytes: &[u8], mut i: usize) -> usize {
+        while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    }
+
+    let mut i = usize::from(bytes.first() == Some(&b'-'));
+    match bytes.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => i = digits(bytes, i),
+        _ => return false,
+    }
+    if bytes.get(i) == Some(&b'.') {
+        let end = digits(bytes, i + 1);
+        if end == i + 1 {
+            return false;
+        }
+        i = end;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let end = digits(bytes, i);
+        if end == i {
+            return false;
+        }
+        i = end;
+    }
+    i == bytes.len()
+}
+
+/// Assemble the first value of an event stream into an [`OwnedValue`].
+///
+/// Whole numbers that fit an `i64` become [`OwnedValue::Int`], others
+/// [`OwnedValue::Float`]. Containers the events leave open are closed at
+/// the end, and an empty stream gives [`OwnedValue::Null`]. An object key
+/// with no value, and unbalanced end events, are ignored.
+pub fn to_owned_value_stream(events: impl IntoIterator<Item = ParseEvent>) -> OwnedValue {
+    // Open containers, each with the key its value will be stored under
+    let mut stack: Vec<(OwnedValue, Option<String>)> = Vec::new();
+    let mut pending_key = None;
+
+    for event in events {
+        let value = match event {
+            ParseEvent::StartObject | ParseEvent::StartArray => {
+                let container = if event == ParseEvent::StartObject {
+                    OwnedValue::Object(IndexMap::new())
+                } else {
+                    OwnedValue::Array(Vec::new())
+                };
+                stack.push((container, pending_key.take()));
+                continue;
+            }
+            ParseEvent::Key(key) => {
+                pending_key = Some(key);
+                continue;
+            }
+            ParseEvent::EndObject | ParseEvent::EndArray => {
+                let Some((container, key)) = stack.pop() else {
+                    continue;
+                };
+                pending_key = key;
+                container
+            }
+            ParseEvent::String(s) => OwnedValue::String(s),
+            ParseEvent::Number(n) => number_value(n),
+            ParseEvent::Bool(b) => OwnedValue::Bool(b),
+            ParseEvent::Null => OwnedValue::Null,
+        };
+        match stack.last_mut() {
+            None => return value,
+            Some((parent, _)) => insert(parent, pending_key.take(), value),
+        }
+    }
+
+    // Close whatever the events left open, innermost first
+    while let Some((container, key)) = stack.pop() {
+        match stack.last_mut() {
+            None => return container,
+            Some((parent, _)) => insert(parent, key, container),
+        }
+    }
+    OwnedValue::Null
+}
+
+fn number_value(n: f64) -> OwnedValue {
+    if n.fract() == 0.0 && n.abs() < 9.223_372_036_854_776e18 {
+        OwnedValue::Int(n as i64)
+    } else {
+        OwnedValue::Float(n)
+    }
+}
+
+fn insert(parent: &mut OwnedValue, key: Option<String>, value: OwnedValue) {
+    match parent {
+        OwnedValue::Array(items) => items.push(value),
+        OwnedValue::Object(fields) => {
+            if let Some(key) = key {
+                fields.insert(key, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonIndex;
+
+    fn parse_chunks(chunks: &[&[u8]]) -> Result<Vec<ParseEvent>, JsonStreamError> {
+        let mut parser = JsonStreamParser::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(parser.feed(chunk)?);
+        }
+        events.extend(parser.finish()?);
+        Ok(events)
+    }
+
+    const DOCUMENT: &[u8] = r#"{"name": "caf\u00e9 \"x\"", "tags": ["a", "\ud83d\ude00", "日本"],
+ "n": [0, -12, 3.5e-2, 1E+3], "ok": true, "no": false, "none": null, "empty": {}, "e": []}"#
+        .as_bytes();
+
+    #[test]
+    fn test_events() {
+        use ParseEvent::*;
+        let events = parse_chunks(&[br#"{"a": [1, "x", {"b": null}], "c": true}"#]).unwrap();
+        assert_eq!(
+            events,
+            [
+                StartObject,
+                Key("a".into()),
+                StartArray,
+                Number(1.0),
+                String("x".into()),
+                StartObject,
+                Key("b".into()),
+                Null,
+                EndObject,
+                EndArray,
+                Key("c".into()),
+                Bool(true),
+                EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_split_point() {
+        let whole = parse_chunks(&[DOCUMENT]).unwrap();
+        for split in 0..=DOCUMENT.len() {
+            let chunks = [&DOCUMENT[..split], &DOCUMENT[split..]];
+            assert_eq!(parse_chunks(&chunks).unwrap(), whole, "split at {split}");
+        }
+        let bytes: Vec<&[u8]> = DOCUMENT.chunks(1).collect();
+        assert_eq!(parse_chunks(&bytes).unwrap(), whole);
+    }
+
+    #[test]
+    fn test_reassembles_like_index() {
+        let events = parse_chunks(&[DOCUMENT]).unwrap();
+        let index = JsonIndex::build(DOCUMENT);
+        // Compared as text: `1E+3` is an Int here and a Float from the index
+        assert_eq!(
+            to_owned_value_stream(events).to_json(),
+            index.root(DOCUMENT).to_owned_value().to_json()
+        );
+    }
+
+    #[test]
+    fn test_top_level_values() {
+        use ParseEvent::*;
+        assert_eq!(
+            parse_chunks(&[b"1 \"s\"\n[]", b"\nnull 4", b"2"]).unwrap(),
+            [
+                Number(1.0),
+                String("s".into()),
+                StartArray,
+                EndArray,
+                Null,
+                Number(42.0)
+            ]
+        );
+        assert_eq!(parse_chunks(&[b"  "]).unwrap(), []);
+    }
+
+    #[test]
+    fn test_errors() {
+        for bad in [
+            &b"[1,]"[..],
+            b"{\"a\" 1}",
+            b"{1: 2}",
+            b"[01]",
+            b"[1.]",
+            b"[-]",
+            b"tru",
+            b"trux",
+            b"\"a\nb\"",
+            b"\"\\x\"",
+            b"[1}",
+            b"}",
+            b"{\"a\": 1",
+        ] {
+            assert!(
+                parse_chunks(&[bad]).is_err(),
+                "{}",
+                String::from_utf8_lossy(bad)
+            );
+        }
+        assert_eq!(
+            parse_chunks(&[b"[1, ", b"?]"]),
+            Err(JsonStreamError::UnexpectedByte {
+                offset: 4,
+                byte: b'?'
+            })
+        );
+        assert_eq!(
+            parse_chunks(&[b"[\"abc"]),
+            Err(JsonStreamError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_to_owned_value_stream_partial() {
+        use ParseEvent::*;
+        assert_eq!(to_owned_value_stream([]), OwnedValue::Null);
+        assert_eq!(to_owned_value_stream([Number(2.5)]), OwnedValue::Float(2.5));
+        // Open containers are closed at the end
+        let value = to_owned_value_stream([StartObject, Key("a".into()), StartArray, Number(1.0)]);
+        assert_eq!(value.to_json(), r#"{"a":[1]}"#);
+    }
+}
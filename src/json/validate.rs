@@ -10,6 +10,11 @@
 //! - UTF-8 validity
 //! - No trailing content after root value
 //!
+//! [`ValidatorMode::Json5`] accepts JSON5 instead; see
+//! [`Validator::new_with_mode`]. [`StreamValidator`] checks strict JSON that
+//! arrives in chunks.
+//! [`validate_with_schema`] also checks a document against a JSON Schema.
+//!
 //! # Example
 //!
 //! ```
@@ -25,10 +30,24 @@
 //! ```
 
 #[cfg(not(test))]
-use alloc::string::String;
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 
 use core::fmt;
 
+use crate::jq::OwnedValue;
+use crate::json::format::compact_serialize;
+
+mod json5;
+mod schema;
+mod stream;
+
+pub use schema::{validate_with_schema, SchemaValidationError, SchemaValidationErrorKind};
+pub use stream::StreamValidator;
+
 /// Position information for error reporting.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
@@ -88,6 +107,10 @@ pub enum ValidationErrorKind {
     InvalidUtf8,
     /// Container nesting depth exceeded the limit.
     NestingTooDeep { limit: usize },
+    /// Container nesting depth exceeded [`ValidatorConfig::max_depth`].
+    DepthLimitExceeded { limit: usize },
+    /// A string exceeded [`ValidatorConfig::max_string_bytes`].
+    StringTooLong { limit: usize },
 }
 
 impl fmt::Display for ValidationErrorKind {
@@ -126,6 +149,12 @@ impl fmt::Display for ValidationErrorKind {
             Self::NestingTooDeep { limit } => {
                 write!(f, "nesting depth exceeds limit of {limit}")
             }
+            Self::DepthLimitExceeded { limit } => {
+                write!(f, "nesting depth exceeds configured limit of {limit}")
+            }
+            Self::StringTooLong { limit } => {
+                write!(f, "string exceeds limit of {limit} bytes")
+            }
         }
     }
 }
@@ -139,6 +168,59 @@ pub struct ValidationError {
     pub position: Position,
 }
 
+impl ValidationErrorKind {
+    /// The snake_case name of the variant, e.g. `"trailing_content"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnexpectedCharacter { .. } => "unexpected_character",
+            Self::UnexpectedEof { .. } => "unexpected_eof",
+            Self::TrailingContent => "trailing_content",
+            Self::UnclosedString => "unclosed_string",
+            Self::InvalidEscape { .. } => "invalid_escape",
+            Self::InvalidUnicodeEscape { .. } => "invalid_unicode_escape",
+            Self::UnpairedSurrogate { .. } => "unpaired_surrogate",
+            Self::ControlCharacter { .. } => "control_character",
+            Self::LeadingZero => "leading_zero",
+            Self::LeadingPlus => "leading_plus",
+            Self::InvalidNumber { .. } => "invalid_number",
+            Self::InvalidKeyword { .. } => "invalid_keyword",
+            Self::InvalidUtf8 => "invalid_utf8",
+            Self::NestingTooDeep { .. } => "nesting_too_deep",
+            Self::DepthLimitExceeded { .. } => "depth_limit_exceeded",
+            Self::StringTooLong { .. } => "string_too_long",
+        }
+    }
+}
+
+impl ValidationError {
+    /// The error as a one-line JSON object, for tools that parse the output.
+    ///
+    /// The fields are `error` (the message the kind displays as), `line`,
+    /// `column`, `offset` and `kind` (see [`ValidationErrorKind::name`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::validate;
+    ///
+    /// let err = validate(b"[1]\n x").unwrap_err();
+    /// assert_eq!(
+    ///     err.to_json_report(),
+    ///     r#"{"error":"trailing content after JSON value","line":2,"column":2,"offset":5,"kind":"trailing_content"}"#
+    /// );
+    /// ```
+    pub fn to_json_report(&self) -> String {
+        let number = |n: usize| OwnedValue::Int(i64::try_from(n).unwrap_or(i64::MAX));
+        compact_serialize(&OwnedValue::object_from([
+            ("error".into(), OwnedValue::String(self.kind.to_string())),
+            ("line".into(), number(self.position.line)),
+            ("column".into(), number(self.position.column)),
+            ("offset".into(), number(self.position.offset)),
+            ("kind".into(), OwnedValue::String(self.kind.name().into())),
+        ]))
+    }
+}
+
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} at {}", self.kind, self.position)
@@ -157,6 +239,49 @@ impl std::error::Error for ValidationError {}
 /// `tests/deep_nesting_valid_tests.rs`) valid.
 const MAX_NESTING_DEPTH: usize = 128;
 
+/// Resource limits for a [`Validator`].
+///
+/// Both limits are off by default. Input that is valid JSON but exceeds a
+/// limit fails validation, so a service can bound the work it accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidatorConfig {
+    /// Deepest allowed container nesting; `[[1]]` has depth 2. Exceeding it
+    /// is a [`ValidationErrorKind::DepthLimitExceeded`]. Independently of
+    /// this setting, nesting past 128 levels is always a
+    /// [`ValidationErrorKind::NestingTooDeep`].
+    pub max_depth: Option<usize>,
+    /// Longest allowed string, in bytes of its text between the quotes with
+    /// escapes counted as written. Exceeding it is a
+    /// [`ValidationErrorKind::StringTooLong`]. Applies to keys as well.
+    pub max_string_bytes: Option<usize>,
+}
+
+impl ValidatorConfig {
+    /// Set [`max_depth`](Self::max_depth).
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Set [`max_string_bytes`](Self::max_string_bytes).
+    pub fn with_max_string_bytes(mut self, max_string_bytes: usize) -> Self {
+        self.max_string_bytes = Some(max_string_bytes);
+        self
+    }
+}
+
+/// The syntax a [`Validator`] accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidatorMode {
+    /// Strict JSON (RFC 8259).
+    #[default]
+    Json,
+    /// JSON5 (<https://spec.json5.org>): comments, single-quoted strings,
+    /// unquoted keys, trailing commas, and ECMAScript number literals.
+    /// Unquoted keys must be ASCII identifiers.
+    Json5,
+}
+
 /// A strict JSON validator with position tracking.
 ///
 /// Uses recursive descent parsing to validate JSON according to RFC 8259.
@@ -167,6 +292,8 @@ pub struct Validator<'a> {
     column: usize,
     /// Current container nesting depth, capped at [`MAX_NESTING_DEPTH`].
     nesting_depth: usize,
+    config: ValidatorConfig,
+    mode: ValidatorMode,
 }
 
 impl<'a> Validator<'a> {
@@ -178,9 +305,73 @@ impl<'a> Validator<'a> {
             line: 1,
             column: 1,
             nesting_depth: 0,
+            config: ValidatorConfig::default(),
+            mode: ValidatorMode::Json,
+        }
+    }
+
+    /// Create a validator for the given input in the given syntax.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::{Validator, ValidatorMode};
+    ///
+    /// let input = b"{unquoted: 'single', hex: 0xFF, trailing: [1, 2,],} // done";
+    /// assert!(Validator::new_with_mode(input, ValidatorMode::Json5).validate().is_ok());
+    /// assert!(Validator::new(input).validate().is_err());
+    /// ```
+    pub fn new_with_mode(input: &'a [u8], mode: ValidatorMode) -> Self {
+        Self {
+            mode,
+            ..Self::new(input)
         }
     }
 
+    /// Use the limits in `config`.
+    pub fn with_config(mut self, config: ValidatorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Limit container nesting to `max_depth` levels.
+    ///
+    /// See [`ValidatorConfig::max_depth`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::{ValidationErrorKind, Validator};
+    ///
+    /// let err = Validator::new(b"[[[1]]]").with_max_depth(2).validate().unwrap_err();
+    /// assert_eq!(err.kind, ValidationErrorKind::DepthLimitExceeded { limit: 2 });
+    /// assert_eq!(err.position.offset, 2);
+    /// ```
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.config.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limit strings to `max_string_bytes` bytes.
+    ///
+    /// See [`ValidatorConfig::max_string_bytes`]. The error points at the
+    /// character that crosses the limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::{ValidationErrorKind, Validator};
+    ///
+    /// assert!(Validator::new(br#"["abc"]"#).with_max_string_bytes(3).validate().is_ok());
+    /// let err = Validator::new(br#"["ab\n"]"#).with_max_string_bytes(3).validate().unwrap_err();
+    /// assert_eq!(err.kind, ValidationErrorKind::StringTooLong { limit: 3 });
+    /// assert_eq!(err.position.offset, 4);
+    /// ```
+    pub fn with_max_string_bytes(mut self, max_string_bytes: usize) -> Self {
+        self.config.max_string_bytes = Some(max_string_bytes);
+        self
+    }
+
     /// Validate the entire input as JSON.
     ///
     /// Returns `Ok(())` if the input is valid JSON, or an error with position
@@ -204,12 +395,92 @@ impl<'a> Validator<'a> {
         Ok(())
     }
 
+    /// Validate `input` and collect every error instead of only the first.
+    ///
+    /// The first error is the one [`validate`](Self::validate) returns, so
+    /// the result is empty exactly when the input is valid. After an error,
+    /// validation resumes at the next top-level value: the next line that
+    /// starts, without indentation, with a character that can begin a value.
+    /// Lines inside an indented document are skipped rather than reported
+    /// again. Values that follow one another, as in NDJSON, are each
+    /// checked; they are not reported as trailing content once the input
+    /// has already failed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::Validator;
+    ///
+    /// let input = b"{\"a\": 1,}\n{\"b\": tru}\n{\"c\": 3}\n";
+    /// let errors = Validator::validate_all(input);
+    /// let lines: Vec<usize> = errors.iter().map(|e| e.position.line).collect();
+    /// assert_eq!(lines, [1, 2]);
+    /// ```
+    pub fn validate_all(input: &'a [u8]) -> Vec<ValidationError> {
+        let mut validator = Self::new(input);
+        let Err(first) = validator.validate() else {
+            return Vec::new();
+        };
+        let mut errors = vec![first];
+        while validator.skip_to_top_level_value() {
+            loop {
+                if let Err(err) = validator.validate_value() {
+                    errors.push(err);
+                    break;
+                }
+                validator.skip_whitespace();
+                if validator.is_eof() {
+                    return errors;
+                }
+            }
+        }
+        errors
+    }
+
+    /// Move past the current line to the next one that starts with a
+    /// top-level value, returning whether there is one.
+    fn skip_to_top_level_value(&mut self) -> bool {
+        self.nesting_depth = 0;
+        loop {
+            // Past the next line break
+            loop {
+                match self.peek() {
+                    None => return false,
+                    Some(b'\n' | b'\r') => break,
+                    Some(_) => {
+                        self.offset += 1;
+                        self.column += 1;
+                    }
+                }
+            }
+            if self.peek() == Some(b'\r') {
+                self.offset += 1;
+            }
+            if self.peek() == Some(b'\n') {
+                self.offset += 1;
+            }
+            self.line += 1;
+            self.column = 1;
+
+            if matches!(
+                self.peek(),
+                Some(b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n')
+            ) {
+                return true;
+            }
+        }
+    }
+
     /// Validate a JSON value (object, array, string, number, or keyword).
     fn validate_value(&mut self) -> Result<(), ValidationError> {
         match self.peek() {
             Some(b'{') => self.validate_object(),
             Some(b'[') => self.validate_array(),
             Some(b'"') => self.validate_string(),
+            Some(b'\'') if self.is_json5() => self.validate_string(),
+            Some(b'-' | b'+' | b'.' | b'0'..=b'9' | b'I' | b'N') if self.is_json5() => {
+                self.validate_json5_number()
+            }
             Some(b'-' | b'0'..=b'9') => self.validate_number(),
             Some(b't' | b'f' | b'n') => self.validate_keyword(),
             Some(b'+') => Err(self.error(ValidationErrorKind::LeadingPlus)),
@@ -223,12 +494,18 @@ impl<'a> Validator<'a> {
         }
     }
 
-    /// Enter a nested container, erroring past [`MAX_NESTING_DEPTH`].
+    /// Enter a nested container, erroring past the configured depth limit or
+    /// [`MAX_NESTING_DEPTH`].
     ///
     /// Callers must decrement `nesting_depth` when the container's frame
     /// exits so sibling containers do not accumulate depth.
     #[inline]
     fn enter_nested(&mut self) -> Result<(), ValidationError> {
+        if let Some(limit) = self.config.max_depth {
+            if self.nesting_depth >= limit {
+                return Err(self.error(ValidationErrorKind::DepthLimitExceeded { limit }));
+            }
+        }
         if self.nesting_depth >= MAX_NESTING_DEPTH {
             return Err(self.error(ValidationErrorKind::NestingTooDeep {
                 limit: MAX_NESTING_DEPTH,
@@ -258,13 +535,16 @@ impl<'a> Validator<'a> {
 
         loop {
             // Expect string key
-            if self.peek() != Some(b'"') {
+            if self.is_json5() {
+                self.validate_json5_key()?;
+            } else if self.peek() != Some(b'"') {
                 return Err(self.error(ValidationErrorKind::UnexpectedCharacter {
                     expected: "string key",
                     found: self.peek().map_or('\0', |b| b as char),
                 }));
+            } else {
+                self.validate_string()?;
             }
-            self.validate_string()?;
             self.skip_whitespace();
 
             // Expect colon
@@ -288,6 +568,10 @@ impl<'a> Validator<'a> {
                     self.skip_whitespace();
                     // Check for trailing comma
                     if self.peek() == Some(b'}') {
+                        if self.is_json5() {
+                            self.advance();
+                            return Ok(());
+                        }
                         return Err(self.error(ValidationErrorKind::UnexpectedCharacter {
                             expected: "string key",
                             found: '}',
@@ -341,6 +625,10 @@ impl<'a> Validator<'a> {
                     self.skip_whitespace();
                     // Check for trailing comma
                     if self.peek() == Some(b']') {
+                        if self.is_json5() {
+                            self.advance();
+                            return Ok(());
+                        }
                         return Err(self.error(ValidationErrorKind::UnexpectedCharacter {
                             expected: "JSON value",
                             found: ']',
@@ -367,19 +655,28 @@ impl<'a> Validator<'a> {
     }
 
     /// Validate a JSON string.
+    ///
+    /// In JSON5 mode the string may be single-quoted; it ends at the quote
+    /// it opened with.
     fn validate_string(&mut self) -> Result<(), ValidationError> {
-        self.advance(); // consume opening quote
+        let quote = self.advance(); // consume opening quote
+        let start = self.offset;
 
         loop {
+            let char_start = self.position();
             match self.peek() {
-                Some(b'"') => {
+                b if b == quote => {
                     self.advance();
                     return Ok(());
                 }
+                Some(b'\\') if self.is_json5() => {
+                    self.validate_json5_escape()?;
+                }
                 Some(b'\\') => {
                     self.validate_escape()?;
                 }
-                Some(b) if b < 0x20 => {
+                // JSON5 strings may hold any character but a line break
+                Some(b) if b < 0x20 && !(self.is_json5() && b != b'\n' && b != b'\r') => {
                     return Err(self.error(ValidationErrorKind::ControlCharacter { byte: b }));
                 }
                 Some(_) => {
@@ -390,6 +687,14 @@ impl<'a> Validator<'a> {
                     return Err(self.error(ValidationErrorKind::UnclosedString));
                 }
             }
+            if let Some(limit) = self.config.max_string_bytes {
+                if self.offset - start > limit {
+                    return Err(ValidationError {
+                        kind: ValidationErrorKind::StringTooLong { limit },
+                        position: char_start,
+                    });
+                }
+            }
         }
     }
 
@@ -647,6 +952,10 @@ impl<'a> Validator<'a> {
 
     /// Skip whitespace characters (space, tab, newline, carriage return).
     fn skip_whitespace(&mut self) {
+        if self.is_json5() {
+            self.skip_json5_whitespace();
+            return;
+        }
         while let Some(b) = self.peek() {
             match b {
                 b' ' | b'\t' => {
@@ -730,6 +1039,21 @@ pub fn validate(input: &[u8]) -> Result<(), ValidationError> {
     Validator::new(input).validate()
 }
 
+/// Validate JSON input within the limits in `config`.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::json::validate::{validate_with_config, ValidatorConfig};
+///
+/// let config = ValidatorConfig::default().with_max_depth(1);
+/// assert!(validate_with_config(b"[1, 2]", &config).is_ok());
+/// assert!(validate_with_config(b"[[1], 2]", &config).is_err());
+/// ```
+pub fn validate_with_config(input: &[u8], config: &ValidatorConfig) -> Result<(), ValidationError> {
+    Validator::new(input).with_config(*config).validate()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1505,4 +1829,101 @@ mod tests {
             ValidationErrorKind::UnexpectedCharacter { .. }
         ));
     }
+
+    #[test]
+    fn test_error_kind_names() {
+        assert_eq!(
+            ValidationErrorKind::TrailingContent.name(),
+            "trailing_content"
+        );
+        assert_eq!(ValidationErrorKind::InvalidUtf8.name(), "invalid_utf8");
+        assert_eq!(
+            ValidationErrorKind::UnexpectedEof { expected: "x" }.name(),
+            "unexpected_eof"
+        );
+    }
+
+    #[test]
+    fn test_to_json_report() {
+        let err = validate(b"{\n  \"k\": \"a\\qb\"\n}").unwrap_err();
+        let report = err.to_json_report();
+        assert!(validate(report.as_bytes()).is_ok());
+        assert_eq!(
+            report,
+            r#"{"error":"invalid escape sequence '\\q'","line":2,"column":11,"offset":12,"kind":"invalid_escape"}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_all() {
+        assert!(Validator::validate_all(br#"{"a": [1, 2]}"#).is_empty());
+
+        // One error per broken line of NDJSON
+        let input = b"{\"a\": 01}\n{\"ok\": 1} {\"b\": +1}\n[1, 2]\n\"unclosed";
+        let errors = Validator::validate_all(input);
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(kinds, ["leading_zero", "leading_plus", "unclosed_string"]);
+        assert_eq!(errors[0].position, validate(input).unwrap_err().position);
+        let lines: Vec<usize> = errors.iter().map(|e| e.position.line).collect();
+        assert_eq!(lines, [1, 2, 4]);
+
+        // Indented lines of a broken document are not reported again
+        let input = b"{\n  \"a\": ,\n  \"b\": 2\n}\n{\n  \"c\": nul\n}\n";
+        let errors = Validator::validate_all(input);
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(kinds, ["unexpected_character", "invalid_keyword"]);
+        assert_eq!(errors[1].position.line, 6);
+
+        // Trailing content is reported once, then later lines are checked
+        let errors = Validator::validate_all(b"1 2\r\n[}\r\n");
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(kinds, ["trailing_content", "unexpected_character"]);
+        assert_eq!(errors[1].position.line, 2);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let deep = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+        let err = Validator::new(deep.as_bytes())
+            .with_max_depth(100)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ValidationErrorKind::DepthLimitExceeded { limit: 100 }
+        );
+        assert_eq!(err.position.offset, 100);
+
+        let config = ValidatorConfig::default().with_max_depth(2);
+        assert!(validate_with_config(br#"{"a": [1, 2], "b": {}}"#, &config).is_ok());
+        assert!(validate_with_config(br#"{"a": [[]]}"#, &config).is_err());
+        assert!(validate_with_config(b"1", &ValidatorConfig::default().with_max_depth(0)).is_ok());
+
+        // The built-in cap still applies above it
+        let err = Validator::new(deep.as_bytes())
+            .with_max_depth(1_000)
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::NestingTooDeep { limit: 128 });
+    }
+
+    #[test]
+    fn test_max_string_bytes() {
+        let config = ValidatorConfig::default().with_max_string_bytes(4);
+        assert!(validate_with_config(br#"{"abcd": "\u00e9"}"#, &config).is_err());
+        assert!(validate_with_config(br#"{"abcd": "\t\n"}"#, &config).is_ok());
+        assert!(validate_with_config("[\"日本\", \"\"]".as_bytes(), &config).is_err());
+
+        // Keys count too, and the error is at the character that crosses
+        let err = validate_with_config(br#"{"abcde": 1}"#, &config).unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::StringTooLong { limit: 4 });
+        assert_eq!(err.position.column, 7);
+
+        let err = Validator::new(br#""""#).with_max_string_bytes(0).validate();
+        assert!(err.is_ok());
+        assert_eq!(
+            ValidationErrorKind::StringTooLong { limit: 4 }.to_string(),
+            "string exceeds limit of 4 bytes"
+        );
+    }
 }
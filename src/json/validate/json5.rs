@@ -0,0 +1,371 @@
+//! The JSON5 additions to [`Validator`], used in [`ValidatorMode::Json5`].
+//!
+//! JSON5 (<https://spec.json5.org>) extends JSON with ECMAScript 5 syntax:
+//!
+//! - `//` and `/* */` comments, and more whitespace (`\v`, `\f`, no-break
+//!   space, the byte order mark and the Unicode space separators)
+//! - Single-quoted strings, more escapes (`\'`, `\v`, `\0`, `\xHH`, any
+//!   other non-digit character, and a backslash before a line break to
+//!   continue the string), and U+2028/U+2029 unescaped in strings
+//! - Unquoted object keys
+//! - A trailing comma in objects and arrays
+//! - Numbers with a leading `+`, a leading or trailing decimal point,
+//!   hexadecimal digits (`0xFF`), and `Infinity` and `NaN`
+//!
+//! Unquoted keys are limited to ASCII identifiers (`[A-Za-z_$][A-Za-z0-9_$]*`);
+//! keys with other Unicode letters or `\u` escapes must be quoted.
+
+use super::{ValidationError, ValidationErrorKind, Validator, ValidatorMode};
+
+impl Validator<'_> {
+    pub(super) fn is_json5(&self) -> bool {
+        self.mode == ValidatorMode::Json5
+    }
+
+    /// Skip whitespace, line breaks and comments.
+    ///
+    /// An unclosed block comment is left in place, so the caller reports an
+    /// error at its `/*`.
+    pub(super) fn skip_json5_whitespace(&mut self) {
+        loop {
+            let rest = &self.input[self.offset..];
+            match rest {
+                [b' ' | b'\t' | 0x0B | 0x0C, ..] => {
+                    self.offset += 1;
+                    self.column += 1;
+                }
+                [b'\r', b'\n', ..] => self.line_break(2),
+                [b'\n' | b'\r', ..] => self.line_break(1),
+                [b'/', b'/', ..] => {
+                    while !matches!(self.peek(), None | Some(b'\n' | b'\r'))
+                        && line_separator_len(&self.input[self.offset..]) == 0
+                    {
+                        self.offset += 1;
+                        self.column += 1;
+                    }
+                }
+                [b'/', b'*', ..] => {
+                    if !self.skip_block_comment() {
+                        return;
+                    }
+                }
+                _ => {
+                    if line_separator_len(rest) > 0 {
+                        self.line_break(3);
+                    } else {
+                        let len = space_len(rest);
+                        if len == 0 {
+                            return;
+                        }
+                        self.offset += len;
+                        self.column += len;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Skip a `/* */` comment, returning false (and consuming nothing) if it
+    /// is not closed.
+    fn skip_block_comment(&mut self) -> bool {
+        let start = self.position();
+        self.offset += 2;
+        self.column += 2;
+        loop {
+            match &self.input[self.offset..] {
+                [] => {
+                    self.offset = start.offset;
+                    self.line = start.line;
+                    self.column = start.column;
+                    return false;
+                }
+                [b'*', b'/', ..] => {
+                    self.offset += 2;
+                    self.column += 2;
+                    return true;
+                }
+                [b'\r', b'\n', ..] => self.line_break(2),
+                [b'\n' | b'\r', ..] => self.line_break(1),
+                rest if line_separator_len(rest) > 0 => self.line_break(3),
+                _ => {
+                    self.offset += 1;
+                    self.column += 1;
+                }
+            }
+        }
+    }
+
+    /// Step over a line break of `len` bytes.
+    fn line_break(&mut self, len: usize) {
+        self.offset += len;
+        self.line += 1;
+        self.column = 1;
+    }
+
+    /// Validate an object key: a string in either quotes or an identifier.
+    pub(super) fn validate_json5_key(&mut self) -> Result<(), ValidationError> {
+        match self.peek() {
+            Some(b'"' | b'\'') => self.validate_string(),
+            Some(b'A'..=b'Z' | b'a'..=b'z' | b'_' | b'$') => {
+                while matches!(
+                    self.peek(),
+                    Some(b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'$')
+                ) {
+                    self.advance();
+                }
+                Ok(())
+            }
+            Some(c) => Err(self.error(ValidationErrorKind::UnexpectedCharacter {
+                expected: "string key or identifier",
+                found: c as char,
+            })),
+            None => Err(self.error(ValidationErrorKind::UnexpectedEof {
+                expected: "string key or identifier",
+            })),
+        }
+    }
+
+    /// Validate an escape sequence in a JSON5 string.
+    pub(super) fn validate_json5_escape(&mut self) -> Result<(), ValidationError> {
+        self.advance(); // consume backslash
+
+        let rest = &self.input[self.offset..];
+        match rest {
+            [] => Err(self.error(ValidationErrorKind::UnclosedString)),
+            // Line continuations
+            [b'\r', b'\n', ..] => {
+                self.line_break(2);
+                Ok(())
+            }
+            [b'\n' | b'\r', ..] => {
+                self.line_break(1);
+                Ok(())
+            }
+            _ if line_separator_len(rest) > 0 => {
+                self.line_break(3);
+                Ok(())
+            }
+            [b'0', b'0'..=b'9', ..] | [b'1'..=b'9', ..] => {
+                let digit = if rest[0] == b'0' { rest[1] } else { rest[0] };
+                Err(self.error(ValidationErrorKind::InvalidEscape {
+                    sequence: digit as char,
+                }))
+            }
+            [b'x', ..] => {
+                self.advance();
+                for _ in 0..2 {
+                    if !matches!(self.peek(), Some(b) if b.is_ascii_hexdigit()) {
+                        return Err(self.error(ValidationErrorKind::InvalidUnicodeEscape {
+                            reason: "expected 2 hex digits after \\x",
+                        }));
+                    }
+                    self.advance();
+                }
+                Ok(())
+            }
+            // ECMAScript strings may hold unpaired surrogates
+            [b'u', ..] => {
+                self.advance();
+                self.validate_unicode_escape().map(|_| ())
+            }
+            // Any other character escapes itself
+            _ => self.validate_utf8_char(),
+        }
+    }
+
+    /// Validate a JSON5 number, including `Infinity`, `NaN` and hex.
+    pub(super) fn validate_json5_number(&mut self) -> Result<(), ValidationError> {
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.advance();
+        }
+
+        let rest = &self.input[self.offset..];
+        for literal in [&b"Infinity"[..], b"NaN"] {
+            if rest.starts_with(literal) {
+                for _ in 0..literal.len() {
+                    self.advance();
+                }
+                return Ok(());
+            }
+        }
+
+        if matches!(rest, [b'0', b'x' | b'X', ..]) {
+            self.advance();
+            self.advance();
+            if !matches!(self.peek(), Some(b) if b.is_ascii_hexdigit()) {
+                return Err(self.error(ValidationErrorKind::InvalidNumber {
+                    reason: "expected hex digit",
+                }));
+            }
+            while matches!(self.peek(), Some(b) if b.is_ascii_hexdigit()) {
+                self.advance();
+            }
+            return Ok(());
+        }
+
+        // Integer part, which may be empty before a decimal point
+        let mut digits = 0;
+        if self.peek() == Some(b'0') {
+            self.advance();
+            digits += 1;
+            if matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error(ValidationErrorKind::LeadingZero));
+            }
+        } else {
+            while matches!(self.peek(), Some(b'0'..=b'9')) {
+                self.advance();
+                digits += 1;
+            }
+        }
+
+        // Fractional part, which may be empty after integer digits
+        if self.peek() == Some(b'.') {
+            self.advance();
+            while matches!(self.peek(), Some(b'0'..=b'9')) {
+                self.advance();
+                digits += 1;
+            }
+        }
+
+        if digits == 0 {
+            return Err(self.error(ValidationErrorKind::InvalidNumber {
+                reason: "expected digit",
+            }));
+        }
+
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.advance();
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.advance();
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error(ValidationErrorKind::InvalidNumber {
+                    reason: "expected digit in exponent",
+                }));
+            }
+            while matches!(self.peek(), Some(b'0'..=b'9')) {
+                self.advance();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Length of a U+2028 or U+2029 line separator at the start of `bytes`, or 0.
+fn line_separator_len(bytes: &[u8]) -> usize {
+    match bytes {
+        [0xE2, 0x80, 0xA8 | 0xA9, ..] => 3,
+        _ => 0,
+    }
+}
+
+/// Length of a non-ASCII JSON5 space at the start of `bytes`, or 0.
+fn space_len(bytes: &[u8]) -> usize {
+    match bytes {
+        // U+00A0 no-break space
+        [0xC2, 0xA0, ..] => 2,
+        // U+1680 ogham space mark
+        [0xE1, 0x9A, 0x80, ..]
+        // U+2000..U+200A, U+202F, U+205F
+        | [0xE2, 0x80, 0x80..=0x8A | 0xAF, ..]
+        | [0xE2, 0x81, 0x9F, ..]
+        // U+3000 ideographic space
+        | [0xE3, 0x80, 0x80, ..]
+        // U+FEFF byte order mark
+        | [0xEF, 0xBB, 0xBF, ..] => 3,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn validate_json5(input: &[u8]) -> Result<(), ValidationError> {
+        Validator::new_with_mode(input, ValidatorMode::Json5).validate()
+    }
+
+    #[test]
+    fn test_spec_example() {
+        let example = include_bytes!("../../../tests/testdata/json5_example.json5");
+        assert!(validate_json5(example).is_ok());
+        // None of it is JSON
+        assert!(validate(example).is_err());
+    }
+
+    #[test]
+    fn test_valid_json5() {
+        for input in [
+            &b"{a: 1, $b_2: 'x', _: [1, 2,],}"[..],
+            b"// comment\n/* block\n comment */ [1 /* inline */, 2] // end",
+            b"'single \"quoted\"'",
+            b"\"\\x41\\v\\0\\' \\A \\\n continued\"",
+            b"[+1, -.5, 5., .5e10, 0xDEADbeef, -0X1, +Infinity, -NaN, NaN, 1e+5]",
+            "\u{FEFF}{\u{00A0}a\u{2028}:\u{3000}'\u{2029}'}".as_bytes(),
+            b"\x0B\x0C null \r\n",
+            b"\"\\uD800 lone surrogates are ECMAScript\"",
+            b"{\"json\": [\"is\", \"json5\", true, false, null, 0, -1.5e3]}",
+        ] {
+            assert!(
+                validate_json5(input).is_ok(),
+                "{}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_json5() {
+        for (input, kind) in [
+            (&b"{a: 1,,}"[..], "unexpected_character"),
+            (b"[1,,]", "unexpected_character"),
+            (b"{1a: 1}", "unexpected_character"),
+            (b"{\xC3\xA9: 1}", "unexpected_character"),
+            (b"[1] /* unclosed", "trailing_content"),
+            (b"'multi\nline'", "control_character"),
+            (b"'\\1'", "invalid_escape"),
+            (b"'\\01'", "invalid_escape"),
+            (b"'\\xG0'", "invalid_unicode_escape"),
+            (b"0x", "invalid_number"),
+            (b"+.", "invalid_number"),
+            (b"01", "leading_zero"),
+            (b"1e", "invalid_number"),
+            (b"infinity", "unexpected_character"),
+            (b"Infinit", "invalid_number"),
+            (b"'unclosed", "unclosed_string"),
+        ] {
+            let err = validate_json5(input).unwrap_err();
+            assert_eq!(err.kind.name(), kind, "{}", String::from_utf8_lossy(input));
+        }
+    }
+
+    #[test]
+    fn test_positions_after_comments() {
+        let err = validate_json5(b"/* a\n b */ {\n  // c\r\n  k: ?\n}").unwrap_err();
+        assert_eq!(err.position.line, 4);
+        assert_eq!(err.position.column, 6);
+
+        let err = validate_json5(b"'a\\\nb' x").unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::TrailingContent);
+        assert_eq!((err.position.line, err.position.column), (2, 4));
+    }
+
+    #[test]
+    fn test_json_mode_is_unchanged() {
+        for input in [
+            &b"{a: 1}"[..],
+            b"[1,]",
+            b"'x'",
+            b"// c\n1",
+            b"+1",
+            b".5",
+            b"NaN",
+        ] {
+            assert!(validate(input).is_err());
+            assert!(Validator::new_with_mode(input, ValidatorMode::Json)
+                .validate()
+                .is_err());
+        }
+    }
+}
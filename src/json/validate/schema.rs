@@ -0,0 +1,777 @@
+//! Checking a JSON document against a JSON Schema.
+//!
+//! [`validate_with_schema`] reads the schema once, rejecting keywords it
+//! cannot honour, then walks the document through a [`JsonIndex`] cursor
+//! and reports every value that breaks a constraint.
+
+#[cfg(not(test))]
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(test)]
+use std::borrow::Cow;
+
+use core::fmt;
+
+use indexmap::IndexMap;
+
+use crate::jq::{OwnedValue, PathComponent};
+use crate::json::light::{to_owned_value_limited, JsonField, StandardJson};
+use crate::json::JsonIndex;
+
+use super::{validate, ValidationError};
+
+/// A reason [`validate_with_schema`] rejected a document.
+#[derive(Debug, Clone)]
+pub struct SchemaValidationError {
+    /// Path from the root to the offending value: into the document for
+    /// [`Constraint`](SchemaValidationErrorKind::Constraint), into the
+    /// schema for [`InvalidSchema`](SchemaValidationErrorKind::InvalidSchema),
+    /// and empty for [`Syntax`](SchemaValidationErrorKind::Syntax).
+    pub path: Vec<PathComponent>,
+    /// What is wrong there.
+    pub kind: SchemaValidationErrorKind,
+}
+
+/// The kinds of [`SchemaValidationError`].
+#[derive(Debug, Clone)]
+pub enum SchemaValidationErrorKind {
+    /// The document is not valid JSON.
+    Syntax(ValidationError),
+    /// The schema gives a keyword a value of the wrong type, or uses a
+    /// keyword that is not supported.
+    InvalidSchema(String),
+    /// The value breaks the constraint of a schema keyword.
+    Constraint {
+        /// The keyword, such as `"minimum"`; `"false"` for the schema
+        /// `false`
+        keyword: &'static str,
+        /// How the value breaks it
+        message: String,
+    },
+}
+
+impl fmt::Display for SchemaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = PathComponent::display_path(&self.path);
+        match &self.kind {
+            SchemaValidationErrorKind::Syntax(err) => write!(f, "invalid JSON: {err}"),
+            SchemaValidationErrorKind::InvalidSchema(message) => {
+                write!(f, "invalid schema at {path}: {message}")
+            }
+            SchemaValidationErrorKind::Constraint { keyword, message } => {
+                write!(f, "{path}: {message} ({keyword})")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaValidationError {}
+
+/// Validate `json` against the JSON Schema (draft 2020-12) `schema`.
+///
+/// The keywords checked are `type`, `enum`, `const`, `minimum`, `maximum`,
+/// `exclusiveMinimum`, `exclusiveMaximum`, `minLength`, `maxLength`,
+/// `pattern` (`regex` feature), `items`, `minItems`, `maxItems`,
+/// `properties`, `required`, `additionalProperties`, `allOf`, `anyOf`,
+/// `oneOf` and `not`. Other keywords, such as `$schema` and `title`, are
+/// ignored, except `$ref` and `$dynamicRef`, which are rejected rather
+/// than skipped. The schemas `true` and `false` allow every and no value.
+///
+/// String lengths count characters, and `pattern` matches anywhere in the
+/// string. The document is navigated in place; only values compared with
+/// `enum` or `const` are copied.
+///
+/// # Errors
+///
+/// Returns every constraint the document breaks, in document order. If
+/// `json` is not valid JSON, or the schema cannot be read, the single
+/// error says so instead.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::json::validate::validate_with_schema;
+/// use succinctly::json::JsonIndex;
+///
+/// let schema = br#"{"type": "object", "required": ["id"], "properties": {
+///     "id": {"type": "integer", "exclusiveMinimum": 0},
+///     "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "maxItems": 2}
+/// }}"#;
+/// let index = JsonIndex::build(schema);
+/// let schema = index.root(schema).to_owned_value();
+///
+/// assert!(validate_with_schema(br#"{"id": 3, "tags": ["a"]}"#, &schema).is_ok());
+///
+/// let errors = validate_with_schema(br#"{"id": 0, "tags": ["a", "c"]}"#, &schema).unwrap_err();
+/// let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     messages,
+///     [
+///         ".id: 0 is not greater than 0 (exclusiveMinimum)",
+///         ".tags[1]: value is not one of the enum values (enum)",
+///     ]
+/// );
+/// ```
+pub fn validate_with_schema(
+    json: &[u8],
+    schema: &OwnedValue,
+) -> Result<(), Vec<SchemaValidationError>> {
+    if let Err(err) = validate(json) {
+        return Err(vec![SchemaValidationError {
+            path: Vec::new(),
+            kind: SchemaValidationErrorKind::Syntax(err),
+        }]);
+    }
+    let mut schema_path = Vec::new();
+    let schema = Schema::read(schema, &mut schema_path).map_err(|message| {
+        vec![SchemaValidationError {
+            path: schema_path,
+            kind: SchemaValidationErrorKind::InvalidSchema(message),
+        }]
+    })?;
+
+    let index = JsonIndex::build(json);
+    let mut errors = Vec::new();
+    schema.check(&index.root(json).value(), &mut Vec::new(), &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// A schema read by [`Schema::read`].
+enum Schema {
+    /// `true` or `false`
+    Bool(bool),
+    Keywords(Box<Keywords>),
+}
+
+/// The supported keywords of an object schema.
+#[derive(Default)]
+struct Keywords {
+    types: Option<Vec<&'static str>>,
+    enum_values: Option<Vec<OwnedValue>>,
+    const_value: Option<OwnedValue>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    #[cfg(feature = "regex")]
+    pattern: Option<regex::Regex>,
+    items: Option<Schema>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    properties: IndexMap<String, Schema>,
+    required: Vec<String>,
+    additional_properties: Option<Schema>,
+    all_of: Vec<Schema>,
+    any_of: Vec<Schema>,
+    one_of: Vec<Schema>,
+    not: Option<Schema>,
+}
+
+/// The `type` names of JSON Schema.
+const TYPE_NAMES: [&str; 7] = [
+    "null", "boolean", "integer", "number", "string", "array", "object",
+];
+
+impl Schema {
+    /// Read `schema`. On error, `path` is left at the offending keyword.
+    fn read(schema: &OwnedValue, path: &mut Vec<PathComponent>) -> Result<Self, String> {
+        let keywords = match schema {
+            OwnedValue::Bool(allowed) => return Ok(Self::Bool(*allowed)),
+            OwnedValue::Object(keywords) => keywords,
+            other => {
+                return Err(format!(
+                    "a schema must be an object or a boolean, not {}",
+                    other.type_name()
+                ))
+            }
+        };
+        let mut out = Keywords::default();
+        for (name, value) in keywords {
+            path.push(PathComponent::Key(name.clone()));
+            match name.as_str() {
+                "type" => out.types = Some(read_types(value, path)?),
+                "enum" => match value {
+                    OwnedValue::Array(values) => out.enum_values = Some(values.clone()),
+                    _ => return Err("enum must be an array".into()),
+                },
+                "const" => out.const_value = Some(value.clone()),
+                "minimum" => out.minimum = Some(read_number(name, value)?),
+                "maximum" => out.maximum = Some(read_number(name, value)?),
+                "exclusiveMinimum" => out.exclusive_minimum = Some(read_number(name, value)?),
+                "exclusiveMaximum" => out.exclusive_maximum = Some(read_number(name, value)?),
+                "minLength" => out.min_length = Some(read_count(name, value)?),
+                "maxLength" => out.max_length = Some(read_count(name, value)?),
+                "minItems" => out.min_items = Some(read_count(name, value)?),
+                "maxItems" => out.max_items = Some(read_count(name, value)?),
+                "pattern" => {
+                    let OwnedValue::String(pattern) = value else {
+                        return Err("pattern must be a string".into());
+                    };
+                    #[cfg(feature = "regex")]
+                    {
+                        out.pattern = Some(
+                            regex::Regex::new(pattern)
+                                .map_err(|e| format!("invalid pattern: {e}"))?,
+                        );
+                    }
+                    #[cfg(not(feature = "regex"))]
+                    {
+                        let _ = pattern;
+                        return Err("pattern requires the 'regex' feature".into());
+                    }
+                }
+                "items" => {
+                    if let OwnedValue::Array(_) = value {
+                        return Err(
+                            "items must be a single schema; prefixItems is not supported".into(),
+                        );
+                    }
+                    out.items = Some(Self::read(value, path)?);
+                }
+                "properties" => {
+                    let OwnedValue::Object(properties) = value else {
+                        return Err("properties must be an object".into());
+                    };
+                    for (key, schema) in properties {
+                        path.push(PathComponent::Key(key.clone()));
+                        out.properties
+                            .insert(key.clone(), Self::read(schema, path)?);
+                        path.pop();
+                    }
+                }
+                "required" => {
+                    let OwnedValue::Array(keys) = value else {
+                        return Err("required must be an array".into());
+                    };
+                    for (i, key) in keys.iter().enumerate() {
+                        let Some(key) = key.as_str() else {
+                            path.push(PathComponent::Index(i));
+                            return Err("required must list strings".into());
+                        };
+                        out.required.push(key.to_string());
+                    }
+                }
+                "additionalProperties" => {
+                    out.additional_properties = Some(Self::read(value, path)?);
+                }
+                "allOf" => out.all_of = Self::read_list(name, value, path)?,
+                "anyOf" => out.any_of = Self::read_list(name, value, path)?,
+                "oneOf" => out.one_of = Self::read_list(name, value, path)?,
+                "not" => out.not = Some(Self::read(value, path)?),
+                "$ref" | "$dynamicRef" => return Err(format!("{name} is not supported")),
+                _ => {}
+            }
+            path.pop();
+        }
+        Ok(Self::Keywords(Box::new(out)))
+    }
+
+    /// Read the non-empty array of schemas of `allOf`, `anyOf` or `oneOf`.
+    fn read_list(
+        name: &str,
+        value: &OwnedValue,
+        path: &mut Vec<PathComponent>,
+    ) -> Result<Vec<Self>, String> {
+        match value {
+            OwnedValue::Array(schemas) if !schemas.is_empty() => {
+                let mut out = Vec::with_capacity(schemas.len());
+                for (i, schema) in schemas.iter().enumerate() {
+                    path.push(PathComponent::Index(i));
+                    out.push(Self::read(schema, path)?);
+                    path.pop();
+                }
+                Ok(out)
+            }
+            _ => Err(format!("{name} must be a non-empty array")),
+        }
+    }
+
+    /// Whether `value` satisfies the schema.
+    fn matches(&self, value: &StandardJson<'_>) -> bool {
+        let mut errors = Vec::new();
+        self.check(value, &mut Vec::new(), &mut errors);
+        errors.is_empty()
+    }
+
+    /// Push an error onto `errors` for each constraint `value`, found at
+    /// `path`, breaks.
+    fn check(
+        &self,
+        value: &StandardJson<'_>,
+        path: &mut Vec<PathComponent>,
+        errors: &mut Vec<SchemaValidationError>,
+    ) {
+        let keywords = match self {
+            Self::Bool(true) => return,
+            Self::Bool(false) => {
+                let message = "no value is allowed here".to_string();
+                return errors.push(violation(path, "false", message));
+            }
+            Self::Keywords(keywords) => keywords,
+        };
+        if let Some(types) = &keywords.types {
+            if !types.iter().any(|name| has_type(value, name)) {
+                let message = format!(
+                    "expected {}, found {}",
+                    types.join(" or "),
+                    type_name(value)
+                );
+                errors.push(violation(path, "type", message));
+            }
+        }
+        if keywords.enum_values.is_some() || keywords.const_value.is_some() {
+            let owned = to_owned_value_limited(value, usize::MAX);
+            if let Some(values) = &keywords.enum_values {
+                if !values.iter().any(|v| json_equal(v, &owned)) {
+                    let message = "value is not one of the enum values".to_string();
+                    errors.push(violation(path, "enum", message));
+                }
+            }
+            if let Some(expected) = &keywords.const_value {
+                if !json_equal(expected, &owned) {
+                    let message = format!("value is not {}", expected.to_json());
+                    errors.push(violation(path, "const", message));
+                }
+            }
+        }
+
+        match value {
+            StandardJson::Number(n) => {
+                let n = n.as_f64().unwrap_or(f64::NAN);
+                if let Some(minimum) = keywords.minimum.filter(|m| n < *m) {
+                    let message = format!("{n} is less than the minimum {minimum}");
+                    errors.push(violation(path, "minimum", message));
+                }
+                if let Some(maximum) = keywords.maximum.filter(|m| n > *m) {
+                    let message = format!("{n} is greater than the maximum {maximum}");
+                    errors.push(violation(path, "maximum", message));
+                }
+                if let Some(minimum) = keywords.exclusive_minimum.filter(|m| n <= *m) {
+                    let message = format!("{n} is not greater than {minimum}");
+                    errors.push(violation(path, "exclusiveMinimum", message));
+                }
+                if let Some(maximum) = keywords.exclusive_maximum.filter(|m| n >= *m) {
+                    let message = format!("{n} is not less than {maximum}");
+                    errors.push(violation(path, "exclusiveMaximum", message));
+                }
+            }
+            StandardJson::String(s) => {
+                let s = s.as_str().unwrap_or_default();
+                let length = s.chars().count();
+                if let Some(min) = keywords.min_length.filter(|min| length < *min) {
+                    let message = format!("string of length {length} is shorter than {min}");
+                    errors.push(violation(path, "minLength", message));
+                }
+                if let Some(max) = keywords.max_length.filter(|max| length > *max) {
+                    let message = format!("string of length {length} is longer than {max}");
+                    errors.push(violation(path, "maxLength", message));
+                }
+                #[cfg(feature = "regex")]
+                if let Some(pattern) = keywords.pattern.as_ref().filter(|p| !p.is_match(&s)) {
+                    let message = format!("string does not match {:?}", pattern.as_str());
+                    errors.push(violation(path, "pattern", message));
+                }
+            }
+            StandardJson::Array(elements) => {
+                let length = elements.count();
+                if let Some(min) = keywords.min_items.filter(|min| length < *min) {
+                    let message = format!("array of length {length} has fewer than {min} items");
+                    errors.push(violation(path, "minItems", message));
+                }
+                if let Some(max) = keywords.max_items.filter(|max| length > *max) {
+                    let message = format!("array of length {length} has more than {max} items");
+                    errors.push(violation(path, "maxItems", message));
+                }
+                if let Some(items) = &keywords.items {
+                    for (i, element) in (*elements).enumerate() {
+                        path.push(PathComponent::Index(i));
+                        items.check(&element, path, errors);
+                        path.pop();
+                    }
+                }
+            }
+            StandardJson::Object(fields) => {
+                let keys: Vec<Cow<'_, str>> = (*fields).map(|field| field_key(&field)).collect();
+                for key in &keywords.required {
+                    if !keys.iter().any(|k| k == key) {
+                        let message = format!("missing required property {key:?}");
+                        errors.push(violation(path, "required", message));
+                    }
+                }
+                for (field, key) in (*fields).zip(keys) {
+                    let schema = match keywords.properties.get(key.as_ref()) {
+                        Some(schema) => schema,
+                        None => match &keywords.additional_properties {
+                            Some(Self::Bool(false)) => {
+                                path.push(PathComponent::Key(key.into_owned()));
+                                let message = "property not allowed by the schema".to_string();
+                                errors.push(violation(path, "additionalProperties", message));
+                                path.pop();
+                                continue;
+                            }
+                            Some(schema) => schema,
+                            None => continue,
+                        },
+                    };
+                    path.push(PathComponent::Key(key.into_owned()));
+                    schema.check(&field.value(), path, errors);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+
+        for schema in &keywords.all_of {
+            schema.check(value, path, errors);
+        }
+        if !keywords.any_of.is_empty() && !keywords.any_of.iter().any(|s| s.matches(value)) {
+            let message = "value matches none of the anyOf schemas".to_string();
+            errors.push(violation(path, "anyOf", message));
+        }
+        if !keywords.one_of.is_empty() {
+            match keywords.one_of.iter().filter(|s| s.matches(value)).count() {
+                1 => {}
+                0 => {
+                    let message = "value matches none of the oneOf schemas".to_string();
+                    errors.push(violation(path, "oneOf", message));
+                }
+                n => {
+                    let message =
+                        format!("value matches {n} of the oneOf schemas, not exactly one");
+                    errors.push(violation(path, "oneOf", message));
+                }
+            }
+        }
+        if keywords.not.as_ref().is_some_and(|s| s.matches(value)) {
+            let message = "value matches the schema in not".to_string();
+            errors.push(violation(path, "not", message));
+        }
+    }
+}
+
+fn violation(
+    path: &[PathComponent],
+    keyword: &'static str,
+    message: String,
+) -> SchemaValidationError {
+    SchemaValidationError {
+        path: path.to_vec(),
+        kind: SchemaValidationErrorKind::Constraint { keyword, message },
+    }
+}
+
+/// Read `type`: one name or a list of them.
+fn read_types(
+    value: &OwnedValue,
+    path: &mut Vec<PathComponent>,
+) -> Result<Vec<&'static str>, String> {
+    let lookup = |name: &OwnedValue| {
+        name.as_str()
+            .and_then(|name| TYPE_NAMES.iter().find(|n| **n == name).copied())
+            .ok_or_else(|| format!("unknown type {}", name.to_json()))
+    };
+    match value {
+        OwnedValue::Array(names) => {
+            let mut types = Vec::with_capacity(names.len());
+            for (i, name) in names.iter().enumerate() {
+                path.push(PathComponent::Index(i));
+                types.push(lookup(name)?);
+                path.pop();
+            }
+            Ok(types)
+        }
+        name => Ok(vec![lookup(name)?]),
+    }
+}
+
+fn read_number(name: &str, value: &OwnedValue) -> Result<f64, String> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("{name} must be a number"))
+}
+
+fn read_count(name: &str, value: &OwnedValue) -> Result<usize, String> {
+    value
+        .as_i64()
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| format!("{name} must be a non-negative integer"))
+}
+
+/// The decoded key of an object field.
+fn field_key<'a>(field: &JsonField<'a>) -> Cow<'a, str> {
+    match field.key() {
+        StandardJson::String(key) => key.as_str().unwrap_or_default(),
+        _ => Cow::Borrowed(""),
+    }
+}
+
+/// Whether `value` is of the JSON Schema type `name`. Integers are numbers
+/// without a fractional part, such as `2` or `2.0`.
+fn has_type(value: &StandardJson<'_>, name: &str) -> bool {
+    match value {
+        StandardJson::Number(n) if name == "integer" => {
+            n.as_i64().is_ok() || n.as_f64().is_ok_and(|f| f.fract() == 0.0)
+        }
+        _ => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &StandardJson<'_>) -> &'static str {
+    match value {
+        StandardJson::Null | StandardJson::Error(_) => "null",
+        StandardJson::Bool(_) => "boolean",
+        StandardJson::Number(_) => "number",
+        StandardJson::String(_) => "string",
+        StandardJson::Array(_) => "array",
+        StandardJson::Object(_) => "object",
+    }
+}
+
+/// JSON equality, as `enum` and `const` compare: numbers by value, so `1`
+/// equals `1.0`, and objects regardless of key order.
+fn json_equal(a: &OwnedValue, b: &OwnedValue) -> bool {
+    match (a, b) {
+        (OwnedValue::Int(x), OwnedValue::Int(y)) => x == y,
+        (OwnedValue::Int(_) | OwnedValue::Float(_), OwnedValue::Int(_) | OwnedValue::Float(_)) => {
+            a.as_f64() == b.as_f64()
+        }
+        (OwnedValue::Array(x), OwnedValue::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y))
+        }
+        (OwnedValue::Object(x), OwnedValue::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, x)| y.get(key).is_some_and(|y| json_equal(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> OwnedValue {
+        let index = JsonIndex::build(json.as_bytes());
+        index.root(json.as_bytes()).to_owned_value()
+    }
+
+    /// The displayed errors from checking `json` against `schema`.
+    fn errors(schema: &str, json: &str) -> Vec<String> {
+        match validate_with_schema(json.as_bytes(), &parse(schema)) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_keywords() {
+        for (schema, json, expected) in [
+            (r#"{"type":"integer"}"#, "2.0", vec![]),
+            (
+                r#"{"type":"integer"}"#,
+                "2.5",
+                vec![".: expected integer, found number (type)"],
+            ),
+            (r#"{"type":["string","null"]}"#, "null", vec![]),
+            (
+                r#"{"type":["string","null"]}"#,
+                "[]",
+                vec![".: expected string or null, found array (type)"],
+            ),
+            (r#"{"enum":[1,"a",{"x":[1]}]}"#, "1.0", vec![]),
+            (r#"{"enum":[1,"a",{"x":[1]}]}"#, r#"{"x":[1.0]}"#, vec![]),
+            (
+                r#"{"enum":[1,"a"]}"#,
+                r#""b""#,
+                vec![".: value is not one of the enum values (enum)"],
+            ),
+            (r#"{"const":{"a":1,"b":2}}"#, r#"{"b":2,"a":1}"#, vec![]),
+            (
+                r#"{"const":null}"#,
+                "false",
+                vec![".: value is not null (const)"],
+            ),
+            (r#"{"minimum":1,"maximum":3}"#, "3", vec![]),
+            (
+                r#"{"minimum":1,"maximum":3}"#,
+                "0.5",
+                vec![".: 0.5 is less than the minimum 1 (minimum)"],
+            ),
+            (
+                r#"{"minimum":1,"maximum":3}"#,
+                "4",
+                vec![".: 4 is greater than the maximum 3 (maximum)"],
+            ),
+            (
+                r#"{"exclusiveMaximum":3}"#,
+                "3",
+                vec![".: 3 is not less than 3 (exclusiveMaximum)"],
+            ),
+            (r#"{"minLength":2,"maxLength":3}"#, r#""é✓""#, vec![]),
+            (
+                r#"{"minLength":2}"#,
+                r#""é""#,
+                vec![".: string of length 1 is shorter than 2 (minLength)"],
+            ),
+            (
+                r#"{"maxLength":1}"#,
+                r#""ab""#,
+                vec![".: string of length 2 is longer than 1 (maxLength)"],
+            ),
+            (
+                r#"{"minItems":1,"maxItems":2}"#,
+                "[1,2,3]",
+                vec![".: array of length 3 has more than 2 items (maxItems)"],
+            ),
+            (
+                r#"{"minItems":1}"#,
+                "[]",
+                vec![".: array of length 0 has fewer than 1 items (minItems)"],
+            ),
+            // Keywords for other kinds do not apply
+            (
+                r#"{"minLength":5,"minItems":5,"required":["a"]}"#,
+                "7",
+                vec![],
+            ),
+            ("true", r#"{"any":[]}"#, vec![]),
+            ("false", "null", vec![".: no value is allowed here (false)"]),
+        ] {
+            assert_eq!(errors(schema, json), expected, "{schema} on {json}");
+        }
+    }
+
+    #[test]
+    fn test_objects_and_arrays_report_every_error_in_order() {
+        let schema = r#"{
+            "type": "object",
+            "required": ["id", "items"],
+            "properties": {
+                "id": {"type": "integer"},
+                "items": {"type": "array", "items": {
+                    "type": "object",
+                    "properties": {"sku": {"type": "string"}},
+                    "additionalProperties": {"type": "number"}
+                }}
+            },
+            "additionalProperties": false
+        }"#;
+        assert_eq!(
+            errors(schema, r#"{"id": 1, "items": [{"sku": "a", "qty": 2}]}"#),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors(
+                schema,
+                r#"{"items": [{"sku": 1}, {"qty": "x"}], "extra key": true, "id": "7"}"#
+            ),
+            [
+                ".items[0].sku: expected string, found number (type)",
+                ".items[1].qty: expected number, found string (type)",
+                r#".["extra key"]: property not allowed by the schema (additionalProperties)"#,
+                ".id: expected integer, found string (type)",
+            ]
+        );
+        assert_eq!(
+            errors(schema, "{}"),
+            [
+                r#".: missing required property "id" (required)"#,
+                r#".: missing required property "items" (required)"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = r#"{"allOf":[{"type":"number"},{"minimum":0}],"oneOf":[{"maximum":10},{"minimum":5}],"not":{"const":7}}"#;
+        assert_eq!(errors(schema, "2"), Vec::<String>::new());
+        assert_eq!(
+            errors(schema, "-1"),
+            [".: -1 is less than the minimum 0 (minimum)"]
+        );
+        assert_eq!(
+            errors(schema, "7"),
+            [
+                ".: value matches 2 of the oneOf schemas, not exactly one (oneOf)",
+                ".: value matches the schema in not (not)",
+            ]
+        );
+        assert_eq!(
+            errors(schema, r#""x""#),
+            [
+                ".: expected number, found string (type)",
+                // Bounds only apply to numbers, so both branches match
+                ".: value matches 2 of the oneOf schemas, not exactly one (oneOf)",
+            ]
+        );
+        let any_of = r#"{"items":{"anyOf":[{"type":"string"},{"type":"integer","minimum":1}]}}"#;
+        assert_eq!(errors(any_of, r#"["a", 2]"#), Vec::<String>::new());
+        assert_eq!(
+            errors(any_of, r#"["a", 0]"#),
+            [".[1]: value matches none of the anyOf schemas (anyOf)"]
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_pattern() {
+        let schema = r#"{"pattern":"^[a-z]+-[0-9]+$"}"#;
+        assert_eq!(errors(schema, r#""abc-12""#), Vec::<String>::new());
+        assert_eq!(
+            errors(schema, r#""abc-x""#),
+            [r#".: string does not match "^[a-z]+-[0-9]+$" (pattern)"#]
+        );
+        // Unanchored patterns match anywhere
+        assert_eq!(
+            errors(r#"{"pattern":"b"}"#, r#""abc""#),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors(r#"{"pattern":"("}"#, r#""""#)[0].split(':').next(),
+            Some("invalid schema at .pattern")
+        );
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn test_pattern_needs_regex_feature() {
+        assert_eq!(
+            errors(r#"{"pattern":"a"}"#, r#""a""#),
+            ["invalid schema at .pattern: pattern requires the 'regex' feature"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_input_and_schema() {
+        assert_eq!(
+            errors("true", "[1,]"),
+            ["invalid JSON: expected JSON value, found ']' at line 1, column 4 (offset 3)"]
+        );
+        for (schema, error) in [
+            ("1", "invalid schema at .: a schema must be an object or a boolean, not number"),
+            (r#"{"type":"float"}"#, r#"invalid schema at .type: unknown type "float""#),
+            (r#"{"type":["null",2]}"#, "invalid schema at .type[1]: unknown type 2"),
+            (r#"{"properties":{"a":{"items":{"maxItems":-1}}}}"#, "invalid schema at .properties.a.items.maxItems: maxItems must be a non-negative integer"),
+            (r#"{"oneOf":[]}"#, "invalid schema at .oneOf: oneOf must be a non-empty array"),
+            (r#"{"anyOf":[{},{"required":[1]}]}"#, "invalid schema at .anyOf[1].required[0]: required must list strings"),
+            (r#"{"items":[{}]}"#, "invalid schema at .items: items must be a single schema; prefixItems is not supported"),
+            (r##"{"$ref":"#/$defs/a"}"##, r#"invalid schema at .["$ref"]: $ref is not supported"#),
+        ] {
+            assert_eq!(errors(schema, "null"), [error], "{schema}");
+        }
+    }
+}
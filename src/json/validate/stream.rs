@@ -0,0 +1,949 @@
+//! Validating strict JSON that arrives in chunks.
+//!
+//! [`Validator`] descends recursively through one slice. [`StreamValidator`]
+//! checks the same grammar as a state machine instead, one byte at a time,
+//! so it can stop at the end of any chunk and carry on with the next: the
+//! open containers are a stack, and a token cut off by a chunk boundary
+//! (a string, escape, multi-byte character, number or keyword) keeps its
+//! partial state. Errors match those of [`Validator::validate`] on the
+//! whole input, in kind and position, however the input is split.
+
+#[cfg(not(test))]
+use alloc::{string::String, vec::Vec};
+
+use super::{
+    Position, ValidationError, ValidationErrorKind, Validator, ValidatorConfig, MAX_NESTING_DEPTH,
+};
+
+/// An open container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// What comes next at a structural position, after any whitespace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Expect {
+    /// A value: the root, an array element after `,`, or an object value
+    Value,
+    /// An array element or `]`, just after `[`
+    FirstElement,
+    /// A key or `}`, just after `{`
+    FirstKey,
+    /// A key, after `,` in an object
+    Key,
+    /// `:` after a key
+    Colon,
+    /// `,` or the closing bracket; for the root, the end of input
+    AfterValue,
+}
+
+/// A token that may continue into the next chunk.
+#[derive(Clone, Debug)]
+enum Token {
+    /// Between tokens
+    None,
+    String(StringState),
+    Number(NumberPart),
+    /// The keyword's letters so far, and where it started
+    Keyword {
+        start: Position,
+        letters: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct StringState {
+    /// Whether the string is an object key
+    key: bool,
+    /// Offset of the first byte after the opening quote
+    start: usize,
+    /// Where the current character or escape started
+    unit: Position,
+    part: StringPart,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum StringPart {
+    /// Between characters
+    Plain,
+    /// Inside a multi-byte character: the code point so far, the
+    /// continuation bytes still to come, and the code points its length
+    /// may encode
+    Utf8 {
+        code_point: u32,
+        remaining: u8,
+        min: u32,
+        max: u32,
+    },
+    /// After a backslash
+    Escape,
+    /// In the hex digits of a `\u` escape; `high` is the high surrogate
+    /// this escape must complete, if any
+    Hex {
+        digits: u8,
+        value: u16,
+        high: Option<u16>,
+    },
+    /// After a high surrogate escape, before (or after) the `\` of the low
+    /// surrogate's escape
+    Low { high: u16, backslash: bool },
+}
+
+/// How far into a number the input is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NumberPart {
+    Minus,
+    Zero,
+    Integer,
+    Point,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl NumberPart {
+    /// The part after `byte`, `None` if the number ended before it, or the
+    /// error `byte` (`None` for the end of input) makes.
+    fn next(self, byte: Option<u8>) -> Result<Option<Self>, ValidationErrorKind> {
+        let digit = matches!(byte, Some(b'0'..=b'9'));
+        let exponent = matches!(byte, Some(b'e' | b'E'));
+        Ok(Some(match self {
+            Self::Minus => match byte {
+                Some(b'0') => Self::Zero,
+                _ if digit => Self::Integer,
+                _ => {
+                    return Err(ValidationErrorKind::InvalidNumber {
+                        reason: "expected digit after minus sign",
+                    })
+                }
+            },
+            Self::Zero if digit => return Err(ValidationErrorKind::LeadingZero),
+            Self::Integer if digit => Self::Integer,
+            Self::Zero | Self::Integer if byte == Some(b'.') => Self::Point,
+            Self::Point | Self::Fraction if digit => Self::Fraction,
+            Self::Point => {
+                return Err(ValidationErrorKind::InvalidNumber {
+                    reason: "expected digit after decimal point",
+                })
+            }
+            Self::Zero | Self::Integer | Self::Fraction if exponent => Self::Exponent,
+            Self::Exponent if matches!(byte, Some(b'+' | b'-')) => Self::ExponentSign,
+            Self::Exponent | Self::ExponentSign | Self::ExponentDigits if digit => {
+                Self::ExponentDigits
+            }
+            Self::Exponent | Self::ExponentSign => {
+                return Err(ValidationErrorKind::InvalidNumber {
+                    reason: "expected digit in exponent",
+                })
+            }
+            Self::Zero | Self::Integer | Self::Fraction | Self::ExponentDigits => return Ok(None),
+        }))
+    }
+}
+
+/// A strict JSON validator fed one chunk at a time.
+///
+/// Call [`feed`](Self::feed) with each chunk as it arrives and
+/// [`finish`](Self::finish) at the end of input. The first error is
+/// reported as soon as the chunk holding it is fed, with the kind and
+/// position [`Validator::validate`] would give for the whole input, and
+/// every later call returns it again. [`ValidatorConfig`] limits apply as
+/// they do there; JSON5 is not supported.
+///
+/// # Example
+///
+/// ```
+/// use succinctly::json::validate::{StreamValidator, ValidationErrorKind};
+///
+/// let mut validator = StreamValidator::new();
+/// validator.feed(br#"{"name": "Al"#).unwrap();
+/// validator.feed(br#"ice", "age": 3"#).unwrap();
+/// validator.feed(b"0}").unwrap();
+/// assert!(validator.finish().is_ok());
+///
+/// let mut validator = StreamValidator::new();
+/// validator.feed(b"[1, 2,\n").unwrap();
+/// let err = validator.feed(b" ]").unwrap_err();
+/// assert_eq!(err.position.line, 2);
+/// assert_eq!(err.position.column, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamValidator {
+    config: ValidatorConfig,
+    /// Position of the next byte
+    position: Position,
+    /// Whether the last byte was a `\r` line break, which a `\n` joins
+    after_cr: bool,
+    stack: Vec<Container>,
+    expect: Expect,
+    token: Token,
+    /// The first error, returned again by later calls
+    error: Option<ValidationError>,
+}
+
+impl Default for StreamValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamValidator {
+    /// Create a validator expecting the start of a JSON document.
+    pub fn new() -> Self {
+        Self {
+            config: ValidatorConfig::default(),
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            after_cr: false,
+            stack: Vec::new(),
+            expect: Expect::Value,
+            token: Token::None,
+            error: None,
+        }
+    }
+
+    /// Use the limits in `config`.
+    pub fn with_config(mut self, config: ValidatorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Validate the next chunk of input.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error in the input so far.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), ValidationError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        let result = self.feed_bytes(chunk);
+        if let Err(error) = &result {
+            self.error = Some(error.clone());
+        }
+        result
+    }
+
+    /// Check that the input fed so far is a complete JSON document.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error in the input, which may be that it ends too
+    /// early.
+    pub fn finish(mut self) -> Result<(), ValidationError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        match core::mem::replace(&mut self.token, Token::None) {
+            Token::None => {}
+            Token::String(string) => {
+                let (kind, position) = match string.part {
+                    StringPart::Plain | StringPart::Escape => {
+                        (ValidationErrorKind::UnclosedString, self.position)
+                    }
+                    StringPart::Utf8 { .. } => (ValidationErrorKind::InvalidUtf8, string.unit),
+                    StringPart::Hex { .. } => (
+                        ValidationErrorKind::InvalidUnicodeEscape {
+                            reason: "unexpected end of input",
+                        },
+                        self.position,
+                    ),
+                    StringPart::Low { high, .. } => (
+                        ValidationErrorKind::UnpairedSurrogate { codepoint: high },
+                        self.position,
+                    ),
+                };
+                return Err(ValidationError { kind, position });
+            }
+            Token::Number(part) => {
+                part.next(None).map_err(|kind| self.error(kind))?;
+                self.expect = Expect::AfterValue;
+            }
+            Token::Keyword { start, letters } => self.end_keyword(start, &letters)?,
+        }
+        let kind = match (self.expect, self.stack.last()) {
+            (Expect::AfterValue, None) => return Ok(()),
+            (Expect::Value | Expect::FirstElement, _) => ValidationErrorKind::UnexpectedEof {
+                expected: "JSON value",
+            },
+            (Expect::FirstKey | Expect::Key, _) => ValidationErrorKind::UnexpectedCharacter {
+                expected: "string key",
+                found: '\0',
+            },
+            (Expect::Colon, _) => ValidationErrorKind::UnexpectedCharacter {
+                expected: "':'",
+                found: '\0',
+            },
+            (Expect::AfterValue, Some(Container::Array)) => ValidationErrorKind::UnexpectedEof {
+                expected: "',' or ']'",
+            },
+            (Expect::AfterValue, Some(Container::Object)) => ValidationErrorKind::UnexpectedEof {
+                expected: "',' or '}'",
+            },
+        };
+        Err(self.error(kind))
+    }
+
+    fn feed_bytes(&mut self, chunk: &[u8]) -> Result<(), ValidationError> {
+        let mut i = 0;
+        while i < chunk.len() {
+            if let Token::String(string) = self.token {
+                if matches!(string.part, StringPart::Plain) {
+                    i += self.plain_run(&chunk[i..], string)?;
+                    if i == chunk.len() {
+                        break;
+                    }
+                }
+            }
+            if self.step(chunk[i])? {
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Consume the leading run of `bytes` that are plain ASCII characters
+    /// of the string `string`, returning its length.
+    fn plain_run(&mut self, bytes: &[u8], string: StringState) -> Result<usize, ValidationError> {
+        let run = bytes
+            .iter()
+            .position(|&b| !(0x20..0x80).contains(&b) || b == b'"' || b == b'\\')
+            .unwrap_or(bytes.len());
+        if let Some(limit) = self.config.max_string_bytes {
+            // The first character that ends past the limit starts at it
+            let too_long = (string.start + limit).saturating_sub(self.position.offset);
+            if too_long < run {
+                self.skip(too_long);
+                return Err(self.error(ValidationErrorKind::StringTooLong { limit }));
+            }
+        }
+        self.skip(run);
+        Ok(run)
+    }
+
+    /// Process one byte, returning whether it was consumed. A byte that
+    /// ends a number or keyword is left for the next structural position.
+    fn step(&mut self, byte: u8) -> Result<bool, ValidationError> {
+        match &mut self.token {
+            Token::None => {}
+            Token::String(_) => {
+                self.string_byte(byte)?;
+                return Ok(true);
+            }
+            Token::Number(part) => match part.next(Some(byte)) {
+                Ok(Some(next)) => {
+                    *part = next;
+                    self.skip(1);
+                    return Ok(true);
+                }
+                Ok(None) => {
+                    self.token = Token::None;
+                    self.expect = Expect::AfterValue;
+                }
+                Err(kind) => return Err(self.error(kind)),
+            },
+            Token::Keyword { letters, .. } if byte.is_ascii_lowercase() => {
+                letters.push(byte as char);
+                self.skip(1);
+                return Ok(true);
+            }
+            Token::Keyword { .. } => {
+                if let Token::Keyword { start, letters } =
+                    core::mem::replace(&mut self.token, Token::None)
+                {
+                    self.end_keyword(start, &letters)?;
+                }
+            }
+        }
+        self.structural(byte)?;
+        Ok(true)
+    }
+
+    /// Process a byte between tokens.
+    fn structural(&mut self, byte: u8) -> Result<(), ValidationError> {
+        if matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            self.whitespace(byte);
+            return Ok(());
+        }
+        match self.expect {
+            Expect::Value => self.start_value(byte),
+            Expect::FirstElement if byte == b']' => self.close(),
+            Expect::FirstElement => self.start_value(byte),
+            Expect::FirstKey if byte == b'}' => self.close(),
+            Expect::FirstKey | Expect::Key if byte == b'"' => {
+                self.start_string(true);
+                Ok(())
+            }
+            Expect::FirstKey | Expect::Key => Err(self.unexpected("string key", byte)),
+            Expect::Colon if byte == b':' => {
+                self.skip(1);
+                self.expect = Expect::Value;
+                Ok(())
+            }
+            Expect::Colon => Err(self.unexpected("':'", byte)),
+            Expect::AfterValue => match (self.stack.last(), byte) {
+                (None, _) => Err(self.error(ValidationErrorKind::TrailingContent)),
+                (Some(Container::Array), b',') => {
+                    self.skip(1);
+                    self.expect = Expect::Value;
+                    Ok(())
+                }
+                (Some(Container::Object), b',') => {
+                    self.skip(1);
+                    self.expect = Expect::Key;
+                    Ok(())
+                }
+                (Some(Container::Array), b']') | (Some(Container::Object), b'}') => self.close(),
+                (Some(Container::Array), _) => Err(self.unexpected("',' or ']'", byte)),
+                (Some(Container::Object), _) => Err(self.unexpected("',' or '}'", byte)),
+            },
+        }
+    }
+
+    /// Begin the value that starts with `byte`.
+    fn start_value(&mut self, byte: u8) -> Result<(), ValidationError> {
+        match byte {
+            b'{' | b'[' => {
+                if let Some(limit) = self.config.max_depth {
+                    if self.stack.len() >= limit {
+                        return Err(self.error(ValidationErrorKind::DepthLimitExceeded { limit }));
+                    }
+                }
+                if self.stack.len() >= MAX_NESTING_DEPTH {
+                    return Err(self.error(ValidationErrorKind::NestingTooDeep {
+                        limit: MAX_NESTING_DEPTH,
+                    }));
+                }
+                let (container, expect) = if byte == b'{' {
+                    (Container::Object, Expect::FirstKey)
+                } else {
+                    (Container::Array, Expect::FirstElement)
+                };
+                self.stack.push(container);
+                self.expect = expect;
+                self.skip(1);
+            }
+            b'"' => self.start_string(false),
+            b'-' | b'0'..=b'9' => {
+                self.token = Token::Number(match byte {
+                    b'-' => NumberPart::Minus,
+                    b'0' => NumberPart::Zero,
+                    _ => NumberPart::Integer,
+                });
+                self.skip(1);
+            }
+            b't' | b'f' | b'n' => {
+                let mut letters = String::new();
+                letters.push(byte as char);
+                self.token = Token::Keyword {
+                    start: self.position,
+                    letters,
+                };
+                self.skip(1);
+            }
+            b'+' => return Err(self.error(ValidationErrorKind::LeadingPlus)),
+            _ => return Err(self.unexpected("JSON value", byte)),
+        }
+        Ok(())
+    }
+
+    fn start_string(&mut self, key: bool) {
+        self.skip(1);
+        self.token = Token::String(StringState {
+            key,
+            start: self.position.offset,
+            unit: self.position,
+            part: StringPart::Plain,
+        });
+    }
+
+    /// Process a byte inside a string.
+    fn string_byte(&mut self, byte: u8) -> Result<(), ValidationError> {
+        let Token::String(mut string) = self.token else {
+            unreachable!("not in a string")
+        };
+        let mut unit_done = false;
+        match string.part {
+            StringPart::Plain => {
+                string.unit = self.position;
+                match byte {
+                    b'"' => {
+                        self.skip(1);
+                        self.token = Token::None;
+                        self.expect = if string.key {
+                            Expect::Colon
+                        } else {
+                            Expect::AfterValue
+                        };
+                        return Ok(());
+                    }
+                    b'\\' => string.part = StringPart::Escape,
+                    0..=0x1F => {
+                        return Err(self.error(ValidationErrorKind::ControlCharacter { byte }));
+                    }
+                    0x20..=0x7F => unit_done = true,
+                    0xC0..=0xDF => string.part = utf8_part(byte & 0x1F, 1, 0x80, 0x7FF),
+                    0xE0..=0xEF => string.part = utf8_part(byte & 0x0F, 2, 0x800, 0xFFFF),
+                    0xF0..=0xF7 => string.part = utf8_part(byte & 0x07, 3, 0x1_0000, 0x10_FFFF),
+                    _ => return Err(self.error(ValidationErrorKind::InvalidUtf8)),
+                }
+                self.skip(1);
+            }
+            StringPart::Utf8 {
+                code_point,
+                remaining,
+                min,
+                max,
+            } => {
+                if byte & 0xC0 != 0x80 {
+                    return Err(ValidationError {
+                        kind: ValidationErrorKind::InvalidUtf8,
+                        position: string.unit,
+                    });
+                }
+                let code_point = (code_point << 6) | u32::from(byte & 0x3F);
+                self.skip(1);
+                if remaining > 1 {
+                    string.part = StringPart::Utf8 {
+                        code_point,
+                        remaining: remaining - 1,
+                        min,
+                        max,
+                    };
+                } else if code_point < min
+                    || code_point > max
+                    || (0xD800..=0xDFFF).contains(&code_point)
+                {
+                    return Err(ValidationError {
+                        kind: ValidationErrorKind::InvalidUtf8,
+                        position: string.unit,
+                    });
+                } else {
+                    unit_done = true;
+                }
+            }
+            StringPart::Escape => {
+                match byte {
+                    b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => unit_done = true,
+                    b'u' => {
+                        string.part = StringPart::Hex {
+                            digits: 0,
+                            value: 0,
+                            high: None,
+                        };
+                    }
+                    _ => {
+                        return Err(self.error(ValidationErrorKind::InvalidEscape {
+                            sequence: byte as char,
+                        }));
+                    }
+                }
+                self.skip(1);
+            }
+            StringPart::Hex {
+                digits,
+                value,
+                high,
+            } => {
+                let Some(digit) = (byte as char).to_digit(16) else {
+                    return Err(self.error(ValidationErrorKind::InvalidUnicodeEscape {
+                        reason: "expected 4 hex digits",
+                    }));
+                };
+                let value = value * 16 + digit as u16;
+                self.skip(1);
+                if digits < 3 {
+                    string.part = StringPart::Hex {
+                        digits: digits + 1,
+                        value,
+                        high,
+                    };
+                } else {
+                    match high {
+                        None if (0xD800..=0xDBFF).contains(&value) => {
+                            string.part = StringPart::Low {
+                                high: value,
+                                backslash: false,
+                            };
+                        }
+                        None if !(0xDC00..=0xDFFF).contains(&value) => unit_done = true,
+                        Some(_) if (0xDC00..=0xDFFF).contains(&value) => unit_done = true,
+                        _ => {
+                            return Err(self.error(ValidationErrorKind::UnpairedSurrogate {
+                                codepoint: high.unwrap_or(value),
+                            }));
+                        }
+                    }
+                }
+            }
+            StringPart::Low { high, backslash } => {
+                let (wanted, next) = if backslash {
+                    (
+                        b'u',
+                        StringPart::Hex {
+                            digits: 0,
+                            value: 0,
+                            high: Some(high),
+                        },
+                    )
+                } else {
+                    (
+                        b'\\',
+                        StringPart::Low {
+                            high,
+                            backslash: true,
+                        },
+                    )
+                };
+                if byte != wanted {
+                    return Err(
+                        self.error(ValidationErrorKind::UnpairedSurrogate { codepoint: high })
+                    );
+                }
+                string.part = next;
+                self.skip(1);
+            }
+        }
+        if unit_done {
+            string.part = StringPart::Plain;
+            if let Some(limit) = self.config.max_string_bytes {
+                if self.position.offset - string.start > limit {
+                    return Err(ValidationError {
+                        kind: ValidationErrorKind::StringTooLong { limit },
+                        position: string.unit,
+                    });
+                }
+            }
+        }
+        self.token = Token::String(string);
+        Ok(())
+    }
+
+    fn end_keyword(&mut self, start: Position, letters: &str) -> Result<(), ValidationError> {
+        if !matches!(letters, "null" | "true" | "false") {
+            return Err(ValidationError {
+                kind: ValidationErrorKind::InvalidKeyword {
+                    found: letters.into(),
+                },
+                position: start,
+            });
+        }
+        self.expect = Expect::AfterValue;
+        Ok(())
+    }
+
+    /// Consume the closing bracket of the innermost container.
+    fn close(&mut self) -> Result<(), ValidationError> {
+        self.stack.pop();
+        self.skip(1);
+        self.expect = Expect::AfterValue;
+        Ok(())
+    }
+
+    /// Consume a whitespace byte, counting `\n`, `\r` and `\r\n` as one
+    /// line break each.
+    fn whitespace(&mut self, byte: u8) {
+        let joins_cr = byte == b'\n' && self.after_cr;
+        self.position.offset += 1;
+        self.after_cr = byte == b'\r';
+        if joins_cr {
+            return;
+        }
+        if matches!(byte, b'\n' | b'\r') {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+    }
+
+    /// Consume `count` bytes on the current line.
+    fn skip(&mut self, count: usize) {
+        self.position.offset += count;
+        self.position.column += count;
+        self.after_cr = false;
+    }
+
+    fn unexpected(&self, expected: &'static str, byte: u8) -> ValidationError {
+        self.error(ValidationErrorKind::UnexpectedCharacter {
+            expected,
+            found: byte as char,
+        })
+    }
+
+    fn error(&self, kind: ValidationErrorKind) -> ValidationError {
+        ValidationError {
+            kind,
+            position: self.position,
+        }
+    }
+}
+
+const fn utf8_part(bits: u8, remaining: u8, min: u32, max: u32) -> StringPart {
+    StringPart::Utf8 {
+        code_point: bits as u32,
+        remaining,
+        min,
+        max,
+    }
+}
+
+impl Validator<'_> {
+    /// Validate strict JSON delivered as a sequence of chunks, such as reads
+    /// from a socket, without joining them.
+    ///
+    /// The result is the one [`validate`](Self::validate) gives for the
+    /// chunks concatenated. See [`StreamValidator`] to feed chunks as they
+    /// arrive, or to set limits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::json::validate::Validator;
+    ///
+    /// let chunks: [&[u8]; 3] = [b"{\"a\": [1, 2", b".5e", b"3]}"];
+    /// assert!(Validator::validate_stream(chunks).is_ok());
+    ///
+    /// let err = Validator::validate_stream([&b"[tr"[..], b"ue, nul", b"]"]).unwrap_err();
+    /// assert_eq!(err.position.offset, 7);
+    /// ```
+    pub fn validate_stream<'c>(
+        chunks: impl IntoIterator<Item = &'c [u8]>,
+    ) -> Result<(), ValidationError> {
+        let mut validator = StreamValidator::new();
+        for chunk in chunks {
+            validator.feed(chunk)?;
+        }
+        validator.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inputs covering every error kind and every token split point.
+    const CORPUS: &[&[u8]] = &[
+        b"null",
+        b" true ",
+        b"false\r\n",
+        b"{}",
+        b"[]",
+        b"[ ]",
+        b"0",
+        b"-0.5e+10",
+        b"12.25E-3",
+        b"[1,2.0,3e4,-0,0.0]",
+        br#"{"a": {"b": [true, false, null]}, "c": "d"}"#,
+        b"{\r\n  \"k\": [1,\r\r\n 2]\n}\n",
+        br#""esc \" \\ \/ \b \f \n \r \t \u00e9 \uD83D\uDE00""#,
+        "\"caf\u{e9} \u{65e5}\u{672c} \u{1F389}\"".as_bytes(),
+        b"",
+        b"   ",
+        b"nul",
+        b"nulll",
+        b"[truex]",
+        b"tru e",
+        b"null1",
+        b"[1 2]",
+        b"{\"a\" 1}",
+        b"{\"a\":1,}",
+        b"{,}",
+        b"{\"a\":1 \"b\":2}",
+        b"[1,]",
+        b"[1,",
+        b"[",
+        b"{",
+        b"{\"a\"",
+        b"{\"a\":",
+        b"{\"a\":1",
+        b"[1",
+        b"[1}",
+        b"{\"a\":1]",
+        b"{1:2}",
+        b"]",
+        b"+1",
+        b"-",
+        b"-a",
+        b"01",
+        b"-01",
+        b"1.",
+        b"1.e5",
+        b"1e",
+        b"1e+",
+        b"1E+x",
+        b".5",
+        b"[1]x",
+        b"[1]\n\n  [2]",
+        b"\"unterminated",
+        b"\"bad \\x escape\"",
+        b"\"trailing backslash \\",
+        b"\"\\u12\"",
+        b"\"\\u12",
+        b"\"\\uD800\"",
+        b"\"\\uD800",
+        b"\"\\uD800\\",
+        b"\"\\uD800\\n\"",
+        b"\"\\uD800\\u0041\"",
+        b"\"\\uDC00\"",
+        b"\"tab\there\"",
+        b"\"new\nline\"",
+        b"\"\x80\"",
+        b"\"\xC3\"",
+        b"\"\xC3",
+        b"\"\xC3(\"",
+        b"\"\xC0\x80\"",
+        b"\"\xED\xA0\x80\"",
+        b"\"\xF4\x90\x80\x80\"",
+        b"\"\xF8\"",
+        b"\"\xE2\x82",
+        b"[\"\xE2\x82\xAC\", \"\xF0\x9F\x8E\x89\"]",
+        b"@",
+        b"[@]",
+        b"{\"a\":@}",
+        b"\xEF\xBB\xBF{}",
+    ];
+
+    fn assert_same(
+        input: &[u8],
+        expected: &Result<(), ValidationError>,
+        actual: Result<(), ValidationError>,
+        what: &str,
+    ) {
+        match (expected, &actual) {
+            (Ok(()), Ok(())) => {}
+            (Err(e), Err(a)) => {
+                assert_eq!(
+                    a.kind,
+                    e.kind,
+                    "{what} {:?}",
+                    String::from_utf8_lossy(input)
+                );
+                assert_eq!(
+                    a.position,
+                    e.position,
+                    "{what} {:?}",
+                    String::from_utf8_lossy(input)
+                );
+            }
+            _ => panic!(
+                "{what} {:?}: expected {expected:?}, got {actual:?}",
+                String::from_utf8_lossy(input)
+            ),
+        }
+    }
+
+    fn stream(
+        input: &[u8],
+        config: ValidatorConfig,
+        splits: &[usize],
+    ) -> Result<(), ValidationError> {
+        let mut validator = StreamValidator::new().with_config(config);
+        let mut start = 0;
+        for &end in splits.iter().chain([&input.len()]) {
+            validator.feed(&input[start..end])?;
+            start = end;
+        }
+        validator.finish()
+    }
+
+    fn check(input: &[u8], config: ValidatorConfig) {
+        let expected = Validator::new(input).with_config(config).validate();
+        assert_same(input, &expected, stream(input, config, &[]), "whole");
+        for split in 0..=input.len() {
+            assert_same(input, &expected, stream(input, config, &[split]), "split");
+        }
+        let every: Vec<usize> = (1..input.len()).collect();
+        assert_same(input, &expected, stream(input, config, &every), "bytes");
+    }
+
+    #[test]
+    fn test_matches_validator_at_every_split() {
+        for input in CORPUS {
+            check(input, ValidatorConfig::default());
+        }
+    }
+
+    #[test]
+    fn test_limits_match_validator() {
+        let configs = [
+            ValidatorConfig::default().with_max_depth(0),
+            ValidatorConfig::default().with_max_depth(2),
+            ValidatorConfig::default().with_max_string_bytes(0),
+            ValidatorConfig::default().with_max_string_bytes(3),
+            ValidatorConfig::default().with_max_string_bytes(8),
+        ];
+        for config in configs {
+            for input in CORPUS {
+                check(input, config);
+            }
+            check(
+                br#"{"abcd": [[["x"]]], "\u00e9\u00e9": "\uD83D\uDE00!"}"#,
+                config,
+            );
+        }
+
+        let deep = [b"[".repeat(129), b"]".repeat(129)].concat();
+        check(&deep, ValidatorConfig::default());
+        let deep = [b"[".repeat(128), b"]".repeat(128)].concat();
+        check(&deep, ValidatorConfig::default());
+    }
+
+    #[test]
+    fn test_mutations_match_validator() {
+        let base = br#"{"id": -12.5e3, "tags": ["a\u00e9", "\uD834\uDD1E"], "ok": [true, null], "s": "x"}"#;
+        let replacements = b" \n\"\\{}[],:0-.eE+tnu\x01\xC3\xA9\xFF";
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..400 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let mut input = base.to_vec();
+            let at = state as usize % input.len();
+            let with = replacements[(state >> 32) as usize % replacements.len()];
+            if state & 1 == 0 {
+                input[at] = with;
+            } else {
+                input.insert(at, with);
+            }
+            let expected = Validator::new(&input).validate();
+            let split = (state >> 16) as usize % (input.len() + 1);
+            let every: Vec<usize> = (1..input.len()).collect();
+            assert_same(
+                &input,
+                &expected,
+                stream(&input, ValidatorConfig::default(), &[split]),
+                "split",
+            );
+            assert_same(
+                &input,
+                &expected,
+                stream(&input, ValidatorConfig::default(), &every),
+                "bytes",
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_is_sticky() {
+        let mut validator = StreamValidator::new();
+        let err = validator.feed(b"[1,,").unwrap_err();
+        assert_eq!(err.position.offset, 3);
+        assert_eq!(validator.feed(b"2]").unwrap_err().position, err.position);
+        assert_eq!(validator.finish().unwrap_err().kind, err.kind);
+    }
+
+    #[test]
+    fn test_validate_stream() {
+        assert!(Validator::validate_stream([&b"[1, "[..], b"2]"]).is_ok());
+        assert!(Validator::validate_stream(core::iter::empty()).is_err());
+        let chunks = br#"{"a": "bc", "d": [1e3, null]}"#.chunks(4);
+        assert!(Validator::validate_stream(chunks).is_ok());
+    }
+}
@@ -39,7 +39,12 @@
 //! - `portable-popcount` - Use portable bitwise algorithm (no intrinsics)
 //!
 //! Other features:
-//! - `serde` - Enable serialization/deserialization support
+//! - `serde` - Enable serialization/deserialization support, including
+//!   `json::serde` and `yaml::serde` for deserializing typed values from a
+//!   `JsonCursor` or `YamlCursor`
+//! - `avx512` - AVX-512BW JSON indexing backend on x86_64 (requires Rust 1.89+)
+//! - `parallel` - `JsonIndex::build_parallel` using the rayon thread pool
+//! - `repair` - `json::repair` for fixing common JSON violations (comments, trailing commas, ...)
 
 // Use no_std unless std feature is enabled or we're in test mode
 #![cfg_attr(not(any(test, feature = "std")), no_std)]
@@ -95,10 +100,10 @@ pub mod text;
 // =============================================================================
 
 // Core types
-pub use bits::BitVec;
 pub use bits::{popcount_word, popcount_word_portable, popcount_words, RankDirectory, SelectIndex};
+pub use bits::{BitVec, BitVecBuilder};
 pub use trees::BalancedParens;
-pub use util::select_in_word;
+pub use util::{select_in_word, select_in_word_broadword};
 
 // DSV types
 pub use dsv::{Dsv, DsvConfig, DsvCursor, DsvIndex};
@@ -141,6 +146,30 @@ pub trait RankSelect {
         i - self.rank1(i)
     }
 
+    /// Count 1-bits in positions `[lo, hi)`.
+    ///
+    /// Returns 0 if `lo >= hi`. Default implementation: `rank1(hi) - rank1(lo)`;
+    /// implementations can override it to answer short ranges without two
+    /// full rank lookups.
+    #[inline]
+    fn rank1_range(&self, lo: usize, hi: usize) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+        self.rank1(hi) - self.rank1(lo)
+    }
+
+    /// Count 0-bits in positions `[lo, hi)`.
+    ///
+    /// Returns 0 if `lo >= hi`. Default implementation: `rank0(hi) - rank0(lo)`.
+    #[inline]
+    fn rank0_range(&self, lo: usize, hi: usize) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+        self.rank0(hi) - self.rank0(lo)
+    }
+
     /// Find position of the k-th 1-bit (0-indexed).
     ///
     /// Returns `None` if fewer than `k+1` ones exist.
@@ -152,21 +181,79 @@ pub trait RankSelect {
 // =============================================================================
 
 /// Configuration for building indices.
+///
+/// Construct with [`Config::default`] or [`Config::builder`]. The struct is
+/// `#[non_exhaustive]` so that more components can gain settings without
+/// breaking callers; its fields can still be read and assigned.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Config {
-    /// Sample rate for select acceleration (default: 256)
+    /// Sample rate for [`BitVec`] select acceleration (default: 256)
     pub select_sample_rate: u32,
+    /// Sample rate for select on the balanced parentheses of a
+    /// [`YamlIndex`](yaml::YamlIndex) (default:
+    /// [`DEFAULT_SELECT_SAMPLE_RATE`](trees::DEFAULT_SELECT_SAMPLE_RATE))
+    pub bp_select_sample_rate: u32,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             select_sample_rate: 256,
+            bp_select_sample_rate: trees::DEFAULT_SELECT_SAMPLE_RATE,
         }
     }
 }
 
+impl Config {
+    /// Start from the default configuration and override components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::{YamlIndex, YamlNodeType};
+    /// use succinctly::Config;
+    ///
+    /// let config = Config::builder().bp_select_sample_rate(64).build();
+    /// let index = YamlIndex::build_with_config(b"a: [1, 2]", config).unwrap();
+    /// assert_eq!(index.root_type(), YamlNodeType::Mapping);
+    /// assert_eq!(index.bp().select_heap_size(), 16);
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Builder for [`Config`], starting from its defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Set the sample rate for [`BitVec`] select: one sample every `rate`
+    /// 1-bits.
+    pub fn select_sample_rate(mut self, rate: u32) -> Self {
+        self.config.select_sample_rate = rate;
+        self
+    }
+
+    /// Set the sample rate for select on YAML balanced parentheses: one
+    /// sample every `rate` opens. Lower rates trade memory for faster
+    /// offset-to-node lookups.
+    pub fn bp_select_sample_rate(mut self, rate: u32) -> Self {
+        self.config.bp_select_sample_rate = rate;
+        self
+    }
+
+    /// Finish the configuration.
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::RankSelect;
@@ -199,4 +286,29 @@ mod tests {
         assert_eq!(w.select1(3), Some(6));
         assert_eq!(w.select1(4), None);
     }
+
+    #[test]
+    fn default_range_ranks() {
+        let w = WordRank(0b0100_1101); // bits set at 0, 2, 3, 6
+        assert_eq!(w.rank1_range(2, 7), 3);
+        assert_eq!(w.rank0_range(2, 7), 2);
+        assert_eq!(w.rank1_range(3, 3), 0);
+        assert_eq!(w.rank1_range(5, 2), 0);
+        assert_eq!(w.rank0_range(5, 2), 0);
+    }
+
+    #[test]
+    fn config_builder_overrides_defaults() {
+        let default = super::Config::default();
+        let built = super::Config::builder().build();
+        assert_eq!(built.select_sample_rate, default.select_sample_rate);
+        assert_eq!(built.bp_select_sample_rate, default.bp_select_sample_rate);
+
+        let config = super::Config::builder()
+            .select_sample_rate(32)
+            .bp_select_sample_rate(8)
+            .build();
+        assert_eq!(config.select_sample_rate, 32);
+        assert_eq!(config.bp_select_sample_rate, 8);
+    }
 }
@@ -0,0 +1,294 @@
+#![allow(unsafe_code)] // SSE2/AVX2/NEON byte-counting intrinsics
+//! Counting characters and lines, re-exported from [`utf8`](super::utf8).
+//!
+//! Both counts are byte classifications followed by a popcount, so each
+//! SIMD backend compares a whole vector at once, extracts the result as a
+//! bitmask and counts its ones:
+//!
+//! - **Characters**: every byte outside `0x80..=0xBF` starts a character.
+//!   As signed bytes the continuation range is `-128..=-65`, so one signed
+//!   `byte > -65` comparison marks the lead bytes.
+//! - **Lines**: every `\n` byte ends a line.
+//!
+//! On x86_64 AVX2 (32 bytes per iteration) is selected at runtime when `std`
+//! is enabled, with SSE2 (16 bytes) as the baseline; aarch64 uses NEON.
+//! Other targets use the scalar loops.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+
+/// Largest signed byte value of a UTF-8 continuation byte (`0xBF`).
+const LAST_CONTINUATION: i8 = 0xBF_u8 as i8;
+
+/// Count the characters in UTF-8 text.
+///
+/// Counts the bytes that are not continuation bytes (`0x80..=0xBF`), which
+/// for valid UTF-8 is the number of code points. Invalid input is not
+/// rejected: each stray lead byte counts as one character, and stray
+/// continuation bytes count as none. Validate first with
+/// [`validate_utf8`](super::utf8::validate_utf8) if that matters.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::count_chars;
+///
+/// assert_eq!(count_chars(b"hello"), 5);
+/// assert_eq!(count_chars("日本語".as_bytes()), 3);
+/// assert_eq!(count_chars("émoji: 🎉".as_bytes()), 8);
+/// ```
+#[inline]
+pub fn count_chars(input: &[u8]) -> usize {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just detected
+            return unsafe { count_chars_avx2(input) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { count_chars_sse2(input) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is mandatory on aarch64
+        unsafe { count_chars_neon(input) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        count_chars_scalar(input)
+    }
+}
+
+/// Count the characters in UTF-8 text one byte at a time.
+///
+/// The portable reference for [`count_chars`], which gives the same result.
+pub fn count_chars_scalar(input: &[u8]) -> usize {
+    input
+        .iter()
+        .filter(|&&byte| byte as i8 > LAST_CONTINUATION)
+        .count()
+}
+
+/// Count the `\n` bytes in the input.
+///
+/// This is the number of line breaks, so text whose last line has no
+/// trailing newline has one line more than the count. A `\r\n` pair counts
+/// once and a lone `\r` not at all.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::count_lines;
+///
+/// assert_eq!(count_lines(b"a\nb\n"), 2);
+/// assert_eq!(count_lines(b"a\r\nb"), 1);
+/// assert_eq!(count_lines(b""), 0);
+/// ```
+#[inline]
+pub fn count_lines(input: &[u8]) -> usize {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just detected
+            return unsafe { count_lines_avx2(input) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { count_lines_sse2(input) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is mandatory on aarch64
+        unsafe { count_lines_neon(input) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        count_lines_scalar(input)
+    }
+}
+
+/// Count the `\n` bytes in the input one byte at a time.
+///
+/// The portable reference for [`count_lines`], which gives the same result.
+pub fn count_lines_scalar(input: &[u8]) -> usize {
+    input.iter().filter(|&&byte| byte == b'\n').count()
+}
+
+// ============================================================================
+// x86_64
+// ============================================================================
+
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+#[target_feature(enable = "avx2")]
+unsafe fn count_chars_avx2(input: &[u8]) -> usize {
+    let threshold = _mm256_set1_epi8(LAST_CONTINUATION);
+    let mut chunks = input.chunks_exact(32);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+        let leads = _mm256_cmpgt_epi8(bytes, threshold);
+        count += (_mm256_movemask_epi8(leads) as u32).count_ones() as usize;
+    }
+    count + count_chars_sse2(chunks.remainder())
+}
+
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+#[target_feature(enable = "avx2")]
+unsafe fn count_lines_avx2(input: &[u8]) -> usize {
+    let newline = _mm256_set1_epi8(b'\n' as i8);
+    let mut chunks = input.chunks_exact(32);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+        let newlines = _mm256_cmpeq_epi8(bytes, newline);
+        count += (_mm256_movemask_epi8(newlines) as u32).count_ones() as usize;
+    }
+    count + count_lines_sse2(chunks.remainder())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn count_chars_sse2(input: &[u8]) -> usize {
+    let threshold = _mm_set1_epi8(LAST_CONTINUATION);
+    let mut chunks = input.chunks_exact(16);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let leads = _mm_cmpgt_epi8(bytes, threshold);
+        count += (_mm_movemask_epi8(leads) as u32).count_ones() as usize;
+    }
+    count + count_chars_scalar(chunks.remainder())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn count_lines_sse2(input: &[u8]) -> usize {
+    let newline = _mm_set1_epi8(b'\n' as i8);
+    let mut chunks = input.chunks_exact(16);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let newlines = _mm_cmpeq_epi8(bytes, newline);
+        count += (_mm_movemask_epi8(newlines) as u32).count_ones() as usize;
+    }
+    count + count_lines_scalar(chunks.remainder())
+}
+
+// ============================================================================
+// aarch64
+// ============================================================================
+
+// NEON has no movemask; each comparison lane is 0xFF or 0, so shifting it
+// down to 0 or 1 and summing the lanes counts the matches instead.
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn count_chars_neon(input: &[u8]) -> usize {
+    let threshold = vdupq_n_s8(LAST_CONTINUATION);
+    let mut chunks = input.chunks_exact(16);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let bytes = vreinterpretq_s8_u8(vld1q_u8(chunk.as_ptr()));
+        let leads = vshrq_n_u8::<7>(vcgtq_s8(bytes, threshold));
+        count += vaddvq_u8(leads) as usize;
+    }
+    count + count_chars_scalar(chunks.remainder())
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn count_lines_neon(input: &[u8]) -> usize {
+    let newline = vdupq_n_u8(b'\n');
+    let mut chunks = input.chunks_exact(16);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let newlines = vshrq_n_u8::<7>(vceqq_u8(vld1q_u8(chunk.as_ptr()), newline));
+        count += vaddvq_u8(newlines) as usize;
+    }
+    count + count_lines_scalar(chunks.remainder())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inputs whose lengths cover every tail length of both vector widths.
+    fn samples() -> Vec<Vec<u8>> {
+        let text = "línea 1\n日本語\r\n🎉 x\n\nend".repeat(5);
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        let mut samples: Vec<Vec<u8>> = (0..=text.len())
+            .map(|len| text.as_bytes()[..len].to_vec())
+            .collect();
+        samples.push(all_bytes.repeat(3));
+        samples.push(vec![0xBF; 100]);
+        samples.push(vec![0xC0; 100]);
+        samples
+    }
+
+    #[test]
+    fn test_count_chars() {
+        assert_eq!(count_chars(b""), 0);
+        assert_eq!(count_chars("aé日🎉".as_bytes()), 4);
+        // Invalid input: stray continuation bytes count as nothing
+        assert_eq!(count_chars(&[0x80, 0xBF, b'a', 0xC0, 0xFF]), 3);
+        for input in samples() {
+            let expected = count_chars_scalar(&input);
+            assert_eq!(count_chars(&input), expected);
+            if let Ok(text) = core::str::from_utf8(&input) {
+                assert_eq!(expected, text.chars().count());
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines(b"no newline"), 0);
+        assert_eq!(count_lines(&[b'\n'; 100]), 100);
+        for input in samples() {
+            let expected = input.iter().filter(|&&b| b == b'\n').count();
+            assert_eq!(count_lines_scalar(&input), expected);
+            assert_eq!(count_lines(&input), expected);
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_sse2_matches_scalar() {
+        for input in samples() {
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe {
+                assert_eq!(count_chars_sse2(&input), count_chars_scalar(&input));
+                assert_eq!(count_lines_sse2(&input), count_lines_scalar(&input));
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_avx2_matches_scalar() {
+        if !crate::util::simd::note_simd_skip_unless(is_x86_feature_detected!("avx2"), "avx2") {
+            return;
+        }
+        for input in samples() {
+            // SAFETY: AVX2 support was just detected
+            unsafe {
+                assert_eq!(count_chars_avx2(&input), count_chars_scalar(&input));
+                assert_eq!(count_lines_avx2(&input), count_lines_scalar(&input));
+            }
+        }
+    }
+}
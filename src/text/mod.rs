@@ -1,6 +1,7 @@
 //! Text processing utilities.
 //!
-//! This module provides utilities for text processing, including UTF-8 validation.
+//! This module provides utilities for text processing, including UTF-8 validation
+//! and SIMD-accelerated character and line counting.
 //!
 //! ## UTF-8 Validation
 //!
@@ -22,7 +23,10 @@
 //! assert_eq!(err.offset, 0);
 //! ```
 
+mod count;
 pub mod utf8;
+#[cfg(target_arch = "aarch64")]
+mod utf8_neon;
 
 // Re-export commonly used types
-pub use utf8::{validate_utf8, Utf8Error, Utf8ErrorKind};
+pub use utf8::{count_chars, count_lines, validate_utf8, Utf8Error, Utf8ErrorKind};
@@ -1,3 +1,4 @@
+#![allow(unsafe_code)] // runtime AVX2 dispatch in validate_utf8
 //! UTF-8 validation with detailed error reporting.
 //!
 //! This module provides UTF-8 validation that reports:
@@ -27,7 +28,13 @@
 //! 5. **Out of range**: Code points above U+10FFFF
 //! 6. **Truncated sequences**: Multi-byte sequence cut off at end of input
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
+
+use crate::util::simd::scan::find_first_of_2;
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+use crate::util::simd::utf8_avx2;
+
+pub use super::count::{count_chars, count_chars_scalar, count_lines, count_lines_scalar};
 
 /// Error information for UTF-8 validation failures.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -115,8 +122,29 @@ impl core::fmt::Display for Utf8ErrorKind {
 /// // Invalid: truncated sequence
 /// assert!(validate_utf8(&[0xC2]).is_err());
 /// ```
+///
+/// Valid input is confirmed with SIMD where available: 32 bytes at a time
+/// with AVX2 on x86_64 (detected at runtime, `std` only) and 16 bytes at a
+/// time with NEON on aarch64 (which every aarch64 CPU has). Only input that
+/// fails that check goes through [`validate_utf8_scalar`] to describe the
+/// error.
 #[inline]
 pub fn validate_utf8(input: &[u8]) -> Result<(), Utf8Error> {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        // SAFETY: AVX2 support is checked first
+        if is_x86_feature_detected!("avx2")
+            && unsafe { utf8_avx2::validate_bytes_avx2(input) }.is_ok()
+        {
+            return Ok(());
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if super::utf8_neon::is_valid(input) {
+            return Ok(());
+        }
+    }
     validate_utf8_scalar(input)
 }
 
@@ -462,6 +490,179 @@ pub fn encode_code_point(cp: u32) -> Option<([u8; 4], usize)> {
     Some((buf, len))
 }
 
+/// Truncate UTF-8 text to at most `max_bytes` without splitting a character.
+///
+/// Returns the longest prefix of `input` that is at most `max_bytes` long
+/// and ends at a character boundary: the end of the input, or a byte that
+/// is not a continuation byte (`0x80..=0xBF`). A `max_bytes` inside a
+/// multi-byte sequence backs up to the start of that character, so the
+/// result of truncating valid UTF-8 is always valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::truncate_at_char_boundary;
+///
+/// let text = "ok 🎉".as_bytes();
+/// assert_eq!(truncate_at_char_boundary(text, 5), b"ok ");
+/// assert_eq!(truncate_at_char_boundary(text, 7), text);
+/// assert_eq!(truncate_at_char_boundary(text, 100), text);
+/// ```
+pub fn truncate_at_char_boundary(input: &[u8], max_bytes: usize) -> &[u8] {
+    split_at_char_boundary(input, max_bytes).0
+}
+
+/// Split UTF-8 text at the last character boundary at or before `byte_pos`.
+///
+/// The first half is what [`truncate_at_char_boundary`] returns for
+/// `byte_pos` and the second half is the rest of the input, so a `byte_pos`
+/// inside a multi-byte sequence moves the whole character to the second
+/// half. A `byte_pos` past the end splits off an empty second half.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::split_at_char_boundary;
+///
+/// let (head, tail) = split_at_char_boundary("日本".as_bytes(), 4);
+/// assert_eq!(head, "日".as_bytes());
+/// assert_eq!(tail, "本".as_bytes());
+/// ```
+pub fn split_at_char_boundary(input: &[u8], byte_pos: usize) -> (&[u8], &[u8]) {
+    if byte_pos >= input.len() {
+        return (input, &[]);
+    }
+    let mut end = byte_pos;
+    while end > 0 && is_continuation_byte(input[end]) {
+        end -= 1;
+    }
+    input.split_at(end)
+}
+
+/// Copy bytes to valid UTF-8, replacing each invalid sequence with `replacement`.
+///
+/// Valid text is copied verbatim. Each invalid span is replaced by one
+/// `replacement`, encoded as UTF-8, where a span is the longest prefix of a
+/// well-formed sequence that the next byte breaks off, or a single byte
+/// that cannot start one. This is the "maximal subpart" rule that
+/// [`String::from_utf8_lossy`] follows, so with `'\u{FFFD}'` the output is
+/// the same.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::sanitize;
+///
+/// assert_eq!(sanitize(b"ok", '\u{FFFD}'), b"ok");
+/// // A truncated 3-byte sequence is one span, a stray 0x80 another
+/// assert_eq!(sanitize(b"a\xE2\x82b\x80", '?'), b"a?b?");
+/// ```
+pub fn sanitize(input: &[u8], replacement: char) -> Vec<u8> {
+    let mut buf = [0; 4];
+    let replacement = replacement.encode_utf8(&mut buf).as_bytes();
+
+    let mut out = Vec::with_capacity(input.len());
+    let mut rest = input;
+    loop {
+        match core::str::from_utf8(rest) {
+            Ok(_) => {
+                out.extend_from_slice(rest);
+                return out;
+            }
+            Err(err) => {
+                let (valid, after) = rest.split_at(err.valid_up_to());
+                out.extend_from_slice(valid);
+                out.extend_from_slice(replacement);
+                match err.error_len() {
+                    Some(len) => rest = &after[len..],
+                    // The input ends inside a sequence
+                    None => return out,
+                }
+            }
+        }
+    }
+}
+
+/// Replace invalid UTF-8 in `buf` as [`sanitize`] does.
+///
+/// A buffer that is already valid UTF-8 is left untouched, without
+/// allocating.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::sanitize_in_place;
+///
+/// let mut buf = b"caf\xE9".to_vec();
+/// sanitize_in_place(&mut buf, '\u{FFFD}');
+/// assert_eq!(buf, "caf\u{FFFD}".as_bytes());
+/// ```
+pub fn sanitize_in_place(buf: &mut Vec<u8>, replacement: char) {
+    if core::str::from_utf8(buf).is_err() {
+        *buf = sanitize(buf, replacement);
+    }
+}
+
+/// Byte offset of the first occurrence of `needle` in UTF-8 text.
+///
+/// The offset counts bytes, not characters: it can be used to slice
+/// `input`, and [`count_chars`] of the slice before it gives the character
+/// index. Nothing is decoded. An ASCII needle is one byte, found with a SIMD
+/// scan (AVX2 or SSE2 on x86_64, NEON on aarch64); for a longer needle the
+/// same scan finds each candidate lead byte and the rest of the encoding is
+/// compared. Since a lead byte never occurs inside another character's
+/// encoding, every match in valid UTF-8 starts at a character boundary.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::find_char;
+///
+/// assert_eq!(find_char(b"key: value", ':'), Some(3));
+/// assert_eq!(find_char("日本語".as_bytes(), '語'), Some(6));
+/// assert_eq!(find_char(b"abc", '\u{E9}'), None);
+/// ```
+pub fn find_char(input: &[u8], needle: char) -> Option<usize> {
+    let mut buf = [0; 4];
+    find_encoded(input, needle.encode_utf8(&mut buf).as_bytes())
+}
+
+/// Byte offsets of every occurrence of `needle` in UTF-8 text, in order.
+///
+/// Each offset is found as [`find_char`] finds the first.
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::text::utf8::find_char_all;
+///
+/// let offsets: Vec<usize> = find_char_all("a→b→c".as_bytes(), '→').collect();
+/// assert_eq!(offsets, [1, 5]);
+/// ```
+pub fn find_char_all(input: &[u8], needle: char) -> impl Iterator<Item = usize> + '_ {
+    let mut buf = [0; 4];
+    let len = needle.encode_utf8(&mut buf).len();
+    let mut start = 0;
+    core::iter::from_fn(move || {
+        let found = start + find_encoded(&input[start..], &buf[..len])?;
+        start = found + len;
+        Some(found)
+    })
+}
+
+/// Offset of the first occurrence of the encoded character `needle`.
+fn find_encoded(input: &[u8], needle: &[u8]) -> Option<usize> {
+    let lead = needle[0];
+    let mut start = 0;
+    loop {
+        let found = start + find_first_of_2(&input[start..], lead, lead)?;
+        if input[found..].starts_with(needle) {
+            return Some(found);
+        }
+        start = found + 1;
+    }
+}
+
 /// Format a byte as a human-readable string for error messages.
 pub fn format_byte(byte: u8) -> String {
     if byte.is_ascii_graphic() || byte == b' ' {
@@ -1541,4 +1742,149 @@ mod tests {
             assert_eq!(decode_code_point(&[0x80]), None);
         }
     }
+
+    mod char_boundary {
+        use super::*;
+
+        #[test]
+        fn never_splits_a_character() {
+            let text = "a\u{00E9}\u{20AC}\u{1F389}z".as_bytes();
+            for pos in 0..=text.len() + 1 {
+                let (head, tail) = split_at_char_boundary(text, pos);
+                assert!(validate_utf8(head).is_ok(), "head at {pos}");
+                assert!(validate_utf8(tail).is_ok(), "tail at {pos}");
+                assert_eq!([head, tail].concat(), text);
+                assert!(head.len() <= pos);
+                assert_eq!(truncate_at_char_boundary(text, pos), head);
+
+                // Agrees with std on where the boundaries are
+                let str_text = core::str::from_utf8(text).unwrap();
+                let floor = (0..=pos.min(text.len()))
+                    .rev()
+                    .find(|&i| str_text.is_char_boundary(i))
+                    .unwrap();
+                assert_eq!(head.len(), floor);
+            }
+        }
+
+        #[test]
+        fn inside_a_four_byte_emoji() {
+            let text = "ab\u{1F389}".as_bytes();
+            for pos in 2..6 {
+                assert_eq!(truncate_at_char_boundary(text, pos), b"ab");
+                assert_eq!(split_at_char_boundary(text, pos).1, "\u{1F389}".as_bytes());
+            }
+            assert_eq!(truncate_at_char_boundary(text, 6), text);
+        }
+
+        #[test]
+        fn edge_cases() {
+            assert_eq!(truncate_at_char_boundary(b"", 0), b"");
+            assert_eq!(truncate_at_char_boundary(b"", 5), b"");
+            assert_eq!(truncate_at_char_boundary("\u{1F389}".as_bytes(), 3), b"");
+            assert_eq!(split_at_char_boundary(b"abc", 0), (&b""[..], &b"abc"[..]));
+            // Stray continuation bytes belong to the character before them
+            assert_eq!(truncate_at_char_boundary(&[b'a', 0x80, 0x80], 2), b"");
+            assert_eq!(truncate_at_char_boundary(&[b'a', 0x80, b'b'], 2), b"a\x80");
+        }
+    }
+
+    mod sanitize_tests {
+        use super::*;
+
+        #[test]
+        fn matches_from_utf8_lossy() {
+            let inputs: [&[u8]; 9] = [
+                b"",
+                "plain \u{1F389} text".as_bytes(),
+                &[0x80],
+                &[0xC3, 0x28],
+                &[0xE2, 0x82],
+                &[0xF0, 0x9F, 0x8E, b'x', 0xF0],
+                // Overlong, surrogate and out-of-range encodings
+                &[0xC0, 0xAF, 0xED, 0xA0, 0x80, 0xF4, 0x90, 0x80, 0x80],
+                &[0xFF, 0xFE, b'a', 0xE0, 0x80],
+                &(0..=255).collect::<Vec<u8>>(),
+            ];
+            for input in inputs {
+                let sanitized = sanitize(input, '\u{FFFD}');
+                assert!(validate_utf8(&sanitized).is_ok());
+                assert_eq!(sanitized, String::from_utf8_lossy(input).as_bytes());
+            }
+        }
+
+        #[test]
+        fn custom_replacement() {
+            assert_eq!(sanitize(&[b'a', 0xFF, 0xFF, b'b'], '?'), b"a??b");
+            assert_eq!(sanitize(&[0xE2, 0x82], '\u{1F389}'), "\u{1F389}".as_bytes());
+        }
+
+        #[test]
+        fn in_place() {
+            let mut buf = Vec::with_capacity(64);
+            buf.extend_from_slice("valid \u{00E9}".as_bytes());
+            let ptr = buf.as_ptr();
+            sanitize_in_place(&mut buf, '?');
+            assert_eq!(buf, "valid \u{00E9}".as_bytes());
+            // Valid input keeps its allocation
+            assert_eq!(buf.as_ptr(), ptr);
+            assert_eq!(buf.capacity(), 64);
+
+            let mut buf = vec![b'x', 0xC3];
+            sanitize_in_place(&mut buf, '?');
+            assert_eq!(buf, b"x?");
+        }
+    }
+
+    mod find_char_tests {
+        use super::*;
+
+        /// Byte offsets of `needle`, found by decoding every character.
+        fn decoded_offsets(text: &str, needle: char) -> Vec<usize> {
+            text.char_indices()
+                .filter(|&(_, c)| c == needle)
+                .map(|(i, _)| i)
+                .collect()
+        }
+
+        #[test]
+        fn matches_decoding() {
+            // Long enough to cross several vector widths, with the needles
+            // at the edges of the scan blocks
+            let mut text = "x".repeat(31);
+            text.push_str("é日🎉:");
+            text.push_str(&"語a".repeat(40));
+            text.push_str("🎉é:");
+            for needle in ['x', ':', 'a', 'é', '日', '語', '🎉', 'z', '\u{10FFFF}'] {
+                let expected = decoded_offsets(&text, needle);
+                assert_eq!(
+                    find_char(text.as_bytes(), needle),
+                    expected.first().copied()
+                );
+                assert_eq!(
+                    find_char_all(text.as_bytes(), needle).collect::<Vec<_>>(),
+                    expected,
+                    "{needle:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn lead_byte_without_match() {
+            // 'é' is C3 A9: C3 followed by other continuations is skipped
+            let text = "ÃÄÅé";
+            assert_eq!(find_char(text.as_bytes(), 'é'), Some(6));
+            // A truncated encoding at the end is not a match
+            assert_eq!(find_char(&[b'a', 0xC3], 'é'), None);
+            assert_eq!(find_char(b"", 'a'), None);
+            assert_eq!(find_char_all(b"", 'a').count(), 0);
+        }
+
+        #[test]
+        fn nul_and_repeats() {
+            assert_eq!(find_char(b"a\0b", '\0'), Some(1));
+            let offsets: Vec<usize> = find_char_all("ééé".as_bytes(), 'é').collect();
+            assert_eq!(offsets, [0, 2, 4]);
+        }
+    }
 }
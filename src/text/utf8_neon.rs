@@ -0,0 +1,156 @@
+#![allow(unsafe_code)] // ARM64 NEON SIMD intrinsics
+//! NEON UTF-8 validation for aarch64.
+//!
+//! Implements the lookup algorithm of Keiser & Lemire, ["Validating UTF-8 In
+//! Less Than One Instruction Per Byte"](https://arxiv.org/abs/2010.03090)
+//! (2021), 16 bytes per iteration, with the lookup tables shared with the
+//! x86_64 AVX2 validator. Pairs of adjacent bytes are classified by three
+//! nibble lookups; third and fourth continuation bytes, which a two-byte
+//! window cannot see, are checked against the lead bytes two and three
+//! positions back.
+//!
+//! The previous block is carried across iterations (`vextq_u8` shifts its
+//! tail in front of the current block), as is a mask of lead bytes at the
+//! end of the block that still need continuation bytes. All-ASCII blocks
+//! skip the lookups and only check that carried mask.
+//!
+//! This is a yes/no check: on failure `validate_utf8` reruns the scalar
+//! validator to locate and describe the error.
+
+use core::arch::aarch64::*;
+
+use crate::util::simd::utf8_tables::{BYTE_1_HIGH, BYTE_1_LOW, BYTE_2_HIGH};
+
+/// Largest byte allowed in each of the last three positions of a block
+/// without continuation bytes in the next block.
+const INCOMPLETE_MAX: [u8; 16] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xEF, 0xDF, 0xBF,
+];
+
+/// Whether `input` is valid UTF-8.
+#[inline]
+pub fn is_valid(input: &[u8]) -> bool {
+    // SAFETY: NEON is mandatory on aarch64
+    unsafe { is_valid_neon(input) }
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn is_valid_neon(input: &[u8]) -> bool {
+    let byte_1_high = vld1q_u8(BYTE_1_HIGH.as_ptr());
+    let byte_1_low = vld1q_u8(BYTE_1_LOW.as_ptr());
+    let byte_2_high = vld1q_u8(BYTE_2_HIGH.as_ptr());
+    let incomplete_max = vld1q_u8(INCOMPLETE_MAX.as_ptr());
+
+    let mut error = vdupq_n_u8(0);
+    let mut prev = vdupq_n_u8(0);
+    let mut prev_incomplete = vdupq_n_u8(0);
+
+    let mut chunks = input.chunks_exact(16);
+    for chunk in &mut chunks {
+        let block = vld1q_u8(chunk.as_ptr());
+        check_block(
+            block,
+            prev,
+            prev_incomplete,
+            &mut error,
+            [byte_1_high, byte_1_low, byte_2_high],
+        );
+        prev_incomplete = vqsubq_u8(block, incomplete_max);
+        prev = block;
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        // Pad with ASCII, which ends any sequence still open
+        let mut padded = [0u8; 16];
+        padded[..remainder.len()].copy_from_slice(remainder);
+        let block = vld1q_u8(padded.as_ptr());
+        check_block(
+            block,
+            prev,
+            prev_incomplete,
+            &mut error,
+            [byte_1_high, byte_1_low, byte_2_high],
+        );
+        prev_incomplete = vdupq_n_u8(0);
+    }
+
+    // A sequence cut off by the end of the input
+    error = vorrq_u8(error, prev_incomplete);
+    vmaxvq_u8(error) == 0
+}
+
+/// Accumulate the errors of `block`, given the block before it.
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn check_block(
+    block: uint8x16_t,
+    prev: uint8x16_t,
+    prev_incomplete: uint8x16_t,
+    error: &mut uint8x16_t,
+    [byte_1_high, byte_1_low, byte_2_high]: [uint8x16_t; 3],
+) {
+    if vmaxvq_u8(block) < 0x80 {
+        *error = vorrq_u8(*error, prev_incomplete);
+        return;
+    }
+
+    let prev1 = vextq_u8::<15>(prev, block);
+    let special_cases = vandq_u8(
+        vandq_u8(
+            vqtbl1q_u8(byte_1_high, vshrq_n_u8::<4>(prev1)),
+            vqtbl1q_u8(byte_1_low, vandq_u8(prev1, vdupq_n_u8(0x0F))),
+        ),
+        vqtbl1q_u8(byte_2_high, vshrq_n_u8::<4>(block)),
+    );
+
+    // Bytes that must be a third or fourth continuation byte: two after a
+    // three- or four-byte lead, or three after a four-byte lead
+    let prev2 = vextq_u8::<14>(prev, block);
+    let prev3 = vextq_u8::<13>(prev, block);
+    let is_third = vqsubq_u8(prev2, vdupq_n_u8(0xE0 - 0x80));
+    let is_fourth = vqsubq_u8(prev3, vdupq_n_u8(0xF0 - 0x80));
+    let must_be_continuation = vandq_u8(vorrq_u8(is_third, is_fourth), vdupq_n_u8(0x80));
+
+    *error = vorrq_u8(*error, veorq_u8(must_be_continuation, special_cases));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_std() {
+        let text = "ASCII, caf\u{00E9}, \u{65E5}\u{672C}\u{8A9E}, \u{1F389}\n".repeat(4);
+        let bytes = text.as_bytes();
+        for start in 0..bytes.len() {
+            for end in start..=bytes.len() {
+                let slice = &bytes[start..end];
+                assert_eq!(is_valid(slice), core::str::from_utf8(slice).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_at_every_offset() {
+        let invalid: [&[u8]; 8] = [
+            &[0x80],
+            &[0xC0, 0xAF],
+            &[0xE0, 0x80, 0x80],
+            &[0xED, 0xA0, 0x80],
+            &[0xF4, 0x90, 0x80, 0x80],
+            &[0xF5, 0x80, 0x80, 0x80],
+            &[0xE2, 0x82, b'a'],
+            &[0xF0, 0x9F, 0x8E],
+        ];
+        for sequence in invalid {
+            for offset in 0..40 {
+                let mut input = vec![b'a'; offset];
+                input.extend_from_slice(sequence);
+                assert!(!is_valid(&input), "{sequence:02X?} at {offset}");
+                input.extend_from_slice(&[b'a'; 20]);
+                assert!(!is_valid(&input), "{sequence:02X?} at {offset}");
+            }
+        }
+    }
+}
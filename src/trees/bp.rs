@@ -34,8 +34,10 @@ use alloc::vec::Vec;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use core::fmt;
+
 use crate::bits::popcount::popcount_word;
-use crate::bits::SelectIndex;
+use crate::bits::{BitVec, SelectIndex};
 use crate::util::broadword::select_in_word;
 
 // ============================================================================
@@ -53,6 +55,16 @@ pub trait SelectSupport: Clone + Default {
     /// Perform select1 query: find the position of the k-th 1-bit (0-indexed).
     /// Returns None if k >= total_ones.
     fn select1(&self, words: &[u64], len: usize, total_ones: usize, k: usize) -> Option<usize>;
+
+    /// Heap memory used by the select index, in bytes.
+    fn heap_size(&self) -> usize {
+        0
+    }
+
+    /// Opens between select samples, or `None` without a select index.
+    fn sample_rate(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// No select support (zero-sized type for JSON).
@@ -85,6 +97,10 @@ pub struct WithSelect {
     select_idx: SelectIndex,
 }
 
+/// Opens between select samples when [`WithSelect`] is built without an
+/// explicit rate.
+pub const DEFAULT_SELECT_SAMPLE_RATE: u32 = 256;
+
 impl SelectSupport for WithSelect {
     fn build(words: &[u64], total_ones: usize) -> Self {
         // INVARIANT: `total_ones` must count only the valid bits below `len`
@@ -97,7 +113,7 @@ impl SelectSupport for WithSelect {
         //
         // Use default sample rate of 256 for ~3% overhead
         Self {
-            select_idx: SelectIndex::build(words, total_ones, 256),
+            select_idx: SelectIndex::build(words, total_ones, DEFAULT_SELECT_SAMPLE_RATE),
         }
     }
 
@@ -125,6 +141,14 @@ impl SelectSupport for WithSelect {
 
         None
     }
+
+    fn heap_size(&self) -> usize {
+        self.select_idx.heap_size()
+    }
+
+    fn sample_rate(&self) -> Option<u32> {
+        Some(self.select_idx.sample_rate())
+    }
 }
 
 // ============================================================================
@@ -206,6 +230,39 @@ const BYTE_TOTAL_EXCESS: [i8; 256] = {
     table
 };
 
+/// Lookup table counting the closes in a byte that bring the excess back to
+/// its starting level. Index: `[byte_value][e]` for a starting excess `e` of
+/// 0-8 above that level. Only meaningful when the excess never drops below
+/// the level within the byte (`e + BYTE_MIN_EXCESS[byte] >= 0`); used by
+/// `nth_sibling` to count whole siblings a byte at a time.
+const BYTE_LEVEL_RETURNS: [[u8; 9]; 256] = {
+    let mut table = [[0u8; 9]; 256];
+    let mut byte_val: usize = 0;
+    while byte_val < 256 {
+        let mut start: usize = 0;
+        while start < 9 {
+            let mut excess = start as i8;
+            let mut count = 0;
+            let mut bit = 0;
+            while bit < 8 {
+                if (byte_val >> bit) & 1 == 1 {
+                    excess += 1;
+                } else {
+                    excess -= 1;
+                    if excess == 0 {
+                        count += 1;
+                    }
+                }
+                bit += 1;
+            }
+            table[byte_val][start] = count;
+            start += 1;
+        }
+        byte_val += 1;
+    }
+    table
+};
+
 /// Lookup table to find position where excess drops to target within a byte.
 /// Index: byte_value * 16 + initial_excess (initial_excess 1-16 mapped to 0-15)
 /// Value: bit position (0-7) where excess reaches 0, or 8 if not found in this byte.
@@ -1745,6 +1802,30 @@ fn mask_final_word_in_place(words: &mut [u64], len: usize) {
     }
 }
 
+/// Why a bit sequence is not a balanced parentheses sequence.
+///
+/// Returned by [`BalancedParens::validate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BpValidationError {
+    /// The close at this bit position has no matching open: the excess
+    /// (opens minus closes) of the prefix ending there is negative.
+    ExcessNegative(usize),
+    /// The length is odd, or the sequence ends with unclosed opens.
+    UnbalancedLength,
+}
+
+impl fmt::Display for BpValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExcessNegative(pos) => write!(f, "unmatched close parenthesis at bit {pos}"),
+            Self::UnbalancedLength => write!(f, "unequal numbers of open and close parentheses"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BpValidationError {}
+
 impl BalancedParens<Vec<u64>, NoSelect> {
     /// Build from an owned bitvector representing balanced parentheses.
     ///
@@ -1787,6 +1868,81 @@ impl BalancedParens<Vec<u64>, NoSelect> {
             select: NoSelect,
         }
     }
+
+    /// Check that `bp` is a balanced parentheses sequence.
+    ///
+    /// Use this on bits built by hand or loaded from disk before passing
+    /// them to [`new`](Self::new), whose navigation assumes a valid
+    /// sequence. See [`validate_words`](Self::validate_words).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::bp::{BalancedParens, BpValidationError};
+    /// use succinctly::BitVec;
+    ///
+    /// // "(()())"
+    /// assert!(BalancedParens::validate(&BitVec::from_words(vec![0b001011], 6)).is_ok());
+    /// // "())(" closes at bit 2 with nothing open
+    /// assert_eq!(
+    ///     BalancedParens::validate(&BitVec::from_words(vec![0b1001], 4)),
+    ///     Err(BpValidationError::ExcessNegative(2))
+    /// );
+    /// ```
+    pub fn validate(bp: &BitVec) -> Result<(), BpValidationError> {
+        Self::validate_words(bp.words(), bp.len())
+    }
+
+    /// Check that the first `len` bits of `words` are a balanced
+    /// parentheses sequence.
+    ///
+    /// An odd `len` fails with [`BpValidationError::UnbalancedLength`].
+    /// Otherwise each word's minimum excess comes from the byte lookup
+    /// tables used to build the index, and only a word whose minimum dips
+    /// below zero is scanned bit by bit for the offending close. A sequence
+    /// that never goes negative but ends with opens left over fails with
+    /// `UnbalancedLength`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > words.len() * 64`.
+    pub fn validate_words(words: &[u64], len: usize) -> Result<(), BpValidationError> {
+        assert!(
+            len <= words.len().saturating_mul(64),
+            "len {} exceeds capacity {}",
+            len,
+            words.len().saturating_mul(64)
+        );
+        if len % 2 != 0 {
+            return Err(BpValidationError::UnbalancedLength);
+        }
+
+        let mut excess: i64 = 0;
+        for (word_idx, &word) in words[..len.div_ceil(64)].iter().enumerate() {
+            let valid_bits = (len - word_idx * 64).min(64);
+            let (min_e, total_e) = if valid_bits == 64 {
+                word_min_excess_unrolled(word)
+            } else {
+                word_min_excess(word, valid_bits)
+            };
+            if excess + i64::from(min_e) < 0 {
+                let mut e = excess;
+                for bit in 0..valid_bits {
+                    e += if (word >> bit) & 1 == 1 { 1 } else { -1 };
+                    if e < 0 {
+                        return Err(BpValidationError::ExcessNegative(word_idx * 64 + bit));
+                    }
+                }
+            }
+            excess += i64::from(total_e);
+        }
+
+        if excess == 0 {
+            Ok(())
+        } else {
+            Err(BpValidationError::UnbalancedLength)
+        }
+    }
 }
 
 impl BalancedParens<Vec<u64>, WithSelect> {
@@ -1801,7 +1957,19 @@ impl BalancedParens<Vec<u64>, WithSelect> {
     ///
     /// Panics if `len` exceeds `u32::MAX` bits (#188): the rank directory
     /// stores absolute cumulative counts as `u32`.
-    pub fn new_with_select(mut words: Vec<u64>, len: usize) -> Self {
+    pub fn new_with_select(words: Vec<u64>, len: usize) -> Self {
+        Self::new_with_select_sample_rate(words, len, DEFAULT_SELECT_SAMPLE_RATE)
+    }
+
+    /// Like [`new_with_select`](Self::new_with_select), but samples every
+    /// `sample_rate`-th open for select1 instead of every
+    /// [`DEFAULT_SELECT_SAMPLE_RATE`]-th. Lower rates make select1 scan
+    /// less and the select index larger (16 bytes per sample).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` exceeds `u32::MAX` bits (#188).
+    pub fn new_with_select_sample_rate(mut words: Vec<u64>, len: usize, sample_rate: u32) -> Self {
         mask_final_word_in_place(&mut words, len);
         let (
             l0_min_excess,
@@ -1815,7 +1983,9 @@ impl BalancedParens<Vec<u64>, WithSelect> {
             total_ones,
         ) = build_bp_index(&words, len);
 
-        let select = WithSelect::build(&words, total_ones);
+        let select = WithSelect {
+            select_idx: SelectIndex::build(&words, total_ones, sample_rate),
+        };
 
         Self {
             words,
@@ -1949,6 +2119,32 @@ impl<W: AsRef<[u64]>, S: SelectSupport> BalancedParens<W, S> {
         self.total_ones
     }
 
+    /// Heap memory used by the rank directory, in bytes.
+    pub fn rank_heap_size(&self) -> usize {
+        self.rank_l1.len() * 4 + self.rank_l2.len() * 8
+    }
+
+    /// Heap memory used by the min-excess indices behind `find_close` and
+    /// `find_open`, in bytes.
+    pub fn excess_heap_size(&self) -> usize {
+        self.l0_min_excess.len()
+            + self.l0_word_excess.len() * 2
+            + self.l1_min_excess.len() * 2
+            + self.l1_block_excess.len() * 2
+            + (self.l2_min_excess.len() + self.l2_block_excess.len()) * 4
+    }
+
+    /// Heap memory used by the select index, in bytes (0 without select
+    /// support).
+    pub fn select_heap_size(&self) -> usize {
+        self.select.heap_size()
+    }
+
+    /// Opens between select samples, or `None` without select support.
+    pub fn select_sample_rate(&self) -> Option<u32> {
+        self.select.sample_rate()
+    }
+
     /// Find the position of the k-th 1-bit (0-indexed).
     ///
     /// Returns `None` if k >= total_ones.
@@ -2299,6 +2495,90 @@ impl<W: AsRef<[u64]>, S: SelectSupport> BalancedParens<W, S> {
         }
     }
 
+    /// Navigate `k` siblings forward: the open of the `k`-th next sibling of
+    /// the open at `p` (`p` itself for `k == 0`).
+    ///
+    /// Equivalent to applying [`next_sibling`](Self::next_sibling) `k` times,
+    /// but instead of matching each sibling's close it scans for the places
+    /// where the excess returns to the siblings' level. Words whose minimum
+    /// excess stays above the level (inside deep subtrees) are skipped via
+    /// the L0/L1 min-excess indices, and the remaining bytes count their
+    /// returns with one table lookup, so dense runs of leaves cost a lookup
+    /// per 8 bits rather than a `find_close` per sibling.
+    ///
+    /// Returns `None` if `p` is not an open or there are fewer than `k`
+    /// siblings after it.
+    pub fn nth_sibling(&self, p: usize, k: usize) -> Option<usize> {
+        if !self.is_open(p) {
+            return None;
+        }
+        if k == 0 {
+            return Some(p);
+        }
+
+        let words = self.words.as_ref();
+        let mut remaining = k;
+        // Excess above the siblings' level before `pos`
+        let mut excess: i32 = 0;
+        let mut pos = p;
+
+        while pos < self.len {
+            if pos % 64 == 0 && excess > 0 {
+                let word_idx = pos / 64;
+                if pos % (64 * FACTOR_L1) == 0 && pos + 64 * FACTOR_L1 <= self.len {
+                    let l1_idx = pos / (64 * FACTOR_L1);
+                    if excess + i32::from(self.l1_min_excess[l1_idx]) > 0 {
+                        excess += i32::from(self.l1_block_excess[l1_idx]);
+                        pos += 64 * FACTOR_L1;
+                        continue;
+                    }
+                }
+                if pos + 64 <= self.len && excess + i32::from(self.l0_min_excess[word_idx]) > 0 {
+                    excess += i32::from(self.l0_word_excess[word_idx]);
+                    pos += 64;
+                    continue;
+                }
+            }
+
+            if pos % 8 == 0 && pos + 8 <= self.len {
+                let byte = ((words[pos / 64] >> (pos % 64)) & 0xFF) as usize;
+                let returns = if excess > 8 {
+                    Some(0)
+                } else if excess + i32::from(BYTE_MIN_EXCESS[byte]) >= 0 {
+                    Some(usize::from(BYTE_LEVEL_RETURNS[byte][excess as usize]))
+                } else {
+                    // Reaches the parent's close in this byte
+                    None
+                };
+                if let Some(returns) = returns.filter(|&returns| returns < remaining) {
+                    remaining -= returns;
+                    excess += i32::from(BYTE_TOTAL_EXCESS[byte]);
+                    pos += 8;
+                    continue;
+                }
+            }
+
+            // Bit by bit up to the next byte boundary or the target
+            if self.is_open(pos) {
+                excess += 1;
+            } else {
+                excess -= 1;
+                if excess < 0 {
+                    return None;
+                }
+                if excess == 0 {
+                    remaining -= 1;
+                    if remaining == 0 {
+                        let next = pos + 1;
+                        return (next < self.len && self.is_open(next)).then_some(next);
+                    }
+                }
+            }
+            pos += 1;
+        }
+        None
+    }
+
     /// Navigate to first child.
     pub fn first_child(&self, p: usize) -> Option<usize> {
         if !self.is_open(p) || p + 1 >= self.len {
@@ -2357,6 +2637,90 @@ impl<W: AsRef<[u64]>, S: SelectSupport> BalancedParens<W, S> {
         let close = self.find_close(p)?;
         Some((close - p) / 2)
     }
+
+    /// Count open parentheses in positions `[0, pos)`; the same as
+    /// [`rank1`](Self::rank1).
+    ///
+    /// Opens appear in preorder, so for an open at `pos` this is the node's
+    /// preorder number, counting the root as 0.
+    /// [`nth_open`](Self::nth_open) maps the number back to the position.
+    #[inline]
+    pub fn rank_open(&self, pos: usize) -> usize {
+        self.rank1(pos)
+    }
+
+    /// Position of the `k`-th open parenthesis (0-indexed), which is node
+    /// `k` in preorder.
+    ///
+    /// Returns `None` if there are not more than `k` opens. This is
+    /// [`select1`](Self::select1) where there is a select index
+    /// ([`WithSelect`]); without one it binary searches the rank
+    /// directory, in O(log n).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::trees::BalancedParens;
+    ///
+    /// // "(()(()()))" = 1101101000
+    /// let bp = BalancedParens::new(vec![0b0001011011], 10);
+    /// assert_eq!(bp.nth_open(3), Some(4));
+    /// assert_eq!(bp.rank_open(4), 3);
+    /// assert_eq!(bp.nth_open(5), None);
+    /// ```
+    pub fn nth_open(&self, k: usize) -> Option<usize> {
+        if k >= self.total_ones {
+            return None;
+        }
+        if self.select.sample_rate().is_some() {
+            return self.select1(k);
+        }
+        self.select_by_rank(k, true)
+    }
+
+    /// Position of the `k`-th close parenthesis (0-indexed).
+    ///
+    /// Closes appear in postorder, so this ends the `k`-th node to finish.
+    /// Returns `None` if there are not more than `k` closes. Binary searches
+    /// the rank directory, in O(log n).
+    pub fn nth_close(&self, k: usize) -> Option<usize> {
+        if k >= self.len - self.total_ones {
+            return None;
+        }
+        self.select_by_rank(k, false)
+    }
+
+    /// Position of the `k`-th open (or close), which must exist, found by
+    /// binary searching `rank1` at word boundaries.
+    fn select_by_rank(&self, k: usize, open: bool) -> Option<usize> {
+        let words = self.words.as_ref();
+        let before = |word_idx: usize| {
+            let opens = self.rank1(word_idx * 64);
+            if open {
+                opens
+            } else {
+                word_idx * 64 - opens
+            }
+        };
+        // The last word with at most `k` matches before it holds the target
+        let (mut lo, mut hi) = (1, self.len.div_ceil(64));
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if before(mid) <= k {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let word_idx = lo - 1;
+        let word = if open {
+            words[word_idx]
+        } else {
+            !words[word_idx]
+        };
+        let p = word_idx * 64 + select_in_word(word, (k - before(word_idx)) as u32) as usize;
+        (p < self.len).then_some(p)
+    }
 }
 
 #[cfg(test)]
@@ -2668,12 +3032,10 @@ mod tests {
     /// Build `depth` opens followed by `depth` closes: `(((...)))`.
     /// The excess peaks at `depth` in the middle.
     fn deeply_nested(depth: usize) -> (Vec<u64>, usize) {
-        let total = 2 * depth;
-        let mut words = vec![0u64; total.div_ceil(64)];
-        for i in 0..depth {
-            words[i / 64] |= 1u64 << (i % 64);
-        }
-        (words, total)
+        let mut builder = crate::BitVecBuilder::with_capacity(2 * depth);
+        builder.push_ones(depth);
+        builder.push_zeros(depth);
+        builder.into_words()
     }
 
     #[test]
@@ -2959,6 +3321,59 @@ mod tests {
         assert_eq!(bp.subtree_size(6), Some(0));
     }
 
+    /// A balanced sequence of `nodes` pairs with a pseudo-random shape.
+    fn random_tree(nodes: usize, seed: u64) -> (Vec<u64>, usize) {
+        let mut builder = crate::BitVecBuilder::with_capacity(2 * nodes);
+        let mut state = seed;
+        let (mut opened, mut depth) = (0, 0);
+        while opened < nodes || depth > 0 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let open = opened < nodes && (depth == 0 || (state >> 33) % 5 < 3);
+            builder.push_bit(open);
+            if open {
+                opened += 1;
+                depth += 1;
+            } else {
+                depth -= 1;
+            }
+        }
+        builder.into_words()
+    }
+
+    #[test]
+    fn test_rank_open_nth_open_nth_close() {
+        for (nodes, seed) in [(1, 1), (5, 2), (32, 3), (700, 4), (5000, 5)] {
+            let (words, len) = random_tree(nodes, seed);
+            let bp = BalancedParens::new(words.clone(), len);
+            let with_select = BalancedParens::new_with_select(words, len);
+            let opens: Vec<usize> = (0..len).filter(|&p| bp.is_open(p)).collect();
+            let closes: Vec<usize> = (0..len).filter(|&p| bp.is_close(p)).collect();
+            assert_eq!(opens.len(), nodes);
+
+            for (k, &p) in opens.iter().enumerate() {
+                assert_eq!(bp.nth_open(k), Some(p), "nodes {nodes}, k {k}");
+                assert_eq!(with_select.nth_open(k), Some(p));
+                assert_eq!(bp.rank_open(p), k);
+            }
+            for (k, &p) in closes.iter().enumerate() {
+                assert_eq!(bp.nth_close(k), Some(p), "nodes {nodes}, k {k}");
+                assert_eq!(with_select.nth_close(k), Some(p));
+            }
+            assert_eq!(bp.nth_open(nodes), None);
+            assert_eq!(with_select.nth_open(nodes), None);
+            assert_eq!(bp.nth_close(nodes), None);
+            assert_eq!(bp.rank_open(len), nodes);
+            assert_eq!(bp.rank_open(len + 100), nodes);
+        }
+
+        let empty = BalancedParens::new(Vec::new(), 0);
+        assert_eq!(empty.rank_open(0), 0);
+        assert_eq!(empty.nth_open(0), None);
+        assert_eq!(empty.nth_close(0), None);
+    }
+
     #[test]
     fn test_subtree_size_simple() {
         // "(())" = 0b0011
@@ -3347,6 +3762,52 @@ mod tests {
         assert_eq!(bp.next_sibling(62), None);
     }
 
+    #[test]
+    fn test_nth_sibling_matches_next_sibling() {
+        // A root whose children mix leaves, small subtrees, chains deep
+        // enough to skip whole words, and subtrees spanning L1 blocks
+        let mut bits = crate::BitVecBuilder::new();
+        bits.push_bit(true);
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        for _ in 0..400 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let depth = match state % 10 {
+                0..=5 => 1,
+                6 | 7 => 1 + (state >> 8) as usize % 8,
+                8 => 40 + (state >> 8) as usize % 100,
+                _ => 1500,
+            };
+            bits.push_ones(depth);
+            bits.push_zeros(depth);
+        }
+        bits.push_bit(false);
+        let (words, len) = bits.into_words();
+        let bp = BalancedParens::new(words, len);
+
+        let mut children = Vec::new();
+        let mut child = bp.first_child(0);
+        while let Some(p) = child {
+            children.push(p);
+            child = bp.next_sibling(p);
+        }
+        assert_eq!(children.len(), 400);
+
+        for start in [0, 1, 7, 150, 399] {
+            for k in 0..=400 - start {
+                assert_eq!(
+                    bp.nth_sibling(children[start], k),
+                    children.get(start + k).copied(),
+                    "start {start}, k {k}"
+                );
+            }
+        }
+        assert_eq!(bp.nth_sibling(0, 0), Some(0));
+        assert_eq!(bp.nth_sibling(0, 1), None);
+        assert_eq!(bp.nth_sibling(len - 1, 0), None);
+    }
+
     #[test]
     fn test_no_first_child_for_leaf() {
         // "()" - leaf node has no first child
@@ -3542,6 +4003,119 @@ mod tests {
         );
     }
 
+    // ========================================================================
+    // Validation
+    // ========================================================================
+
+    /// Reference validation: scan bit by bit.
+    fn validate_naive(words: &[u64], len: usize) -> Result<(), BpValidationError> {
+        if len % 2 != 0 {
+            return Err(BpValidationError::UnbalancedLength);
+        }
+        let mut excess = 0i64;
+        for i in 0..len {
+            excess += if (words[i / 64] >> (i % 64)) & 1 == 1 {
+                1
+            } else {
+                -1
+            };
+            if excess < 0 {
+                return Err(BpValidationError::ExcessNegative(i));
+            }
+        }
+        if excess == 0 {
+            Ok(())
+        } else {
+            Err(BpValidationError::UnbalancedLength)
+        }
+    }
+
+    #[test]
+    fn test_validate_small() {
+        let check =
+            |words: Vec<u64>, len| BalancedParens::validate(&BitVec::from_words(words, len));
+        assert_eq!(check(vec![], 0), Ok(()));
+        assert_eq!(check(vec![0b01], 2), Ok(()));
+        assert_eq!(check(vec![0b001011], 6), Ok(()));
+        assert_eq!(
+            check(vec![0b10], 2),
+            Err(BpValidationError::ExcessNegative(0))
+        );
+        assert_eq!(
+            check(vec![0b1], 1),
+            Err(BpValidationError::UnbalancedLength)
+        );
+        assert_eq!(
+            check(vec![0b0111], 4),
+            Err(BpValidationError::UnbalancedLength)
+        );
+        // Stray bits past `len` are ignored
+        assert_eq!(check(vec![0b1111_0001], 2), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_across_words() {
+        let (words, len) = deeply_nested(1_000);
+        assert_eq!(BalancedParens::validate_words(&words, len), Ok(()));
+
+        // ")(" after a valid prefix: the excess first dips in word 31
+        let mut builder = crate::BitVecBuilder::new();
+        builder.push_ones(1_000);
+        builder.push_zeros(1_001);
+        builder.push_bit(true);
+        let (broken, broken_len) = builder.into_words();
+        assert_eq!(
+            BalancedParens::validate_words(&broken, broken_len),
+            Err(BpValidationError::ExcessNegative(2_000))
+        );
+
+        // Unclosed opens at the end of a valid prefix
+        assert_eq!(
+            BalancedParens::validate_words(&words, 1_000),
+            Err(BpValidationError::UnbalancedLength)
+        );
+    }
+
+    #[test]
+    fn test_validate_matches_naive() {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..300 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // Random walks clamped at zero are valid; flipping a bit usually breaks them
+            let len = 2 * ((state >> 40) as usize % 300);
+            let mut builder = crate::BitVecBuilder::new();
+            let mut excess = 0usize;
+            let mut walk = state;
+            for i in 0..len {
+                walk = walk.rotate_left(7) ^ (i as u64).wrapping_mul(0x2545_F491_4F6C_DD1D);
+                let remaining = len - i;
+                let open = excess == 0 || (excess < remaining && walk & 1 == 1);
+                builder.push_bit(open);
+                excess = if open { excess + 1 } else { excess - 1 };
+            }
+            let (mut words, len) = builder.into_words();
+            assert_eq!(BalancedParens::validate_words(&words, len), Ok(()));
+
+            if len > 0 {
+                let flip = (state as usize) % len;
+                words[flip / 64] ^= 1 << (flip % 64);
+                assert_eq!(
+                    BalancedParens::validate_words(&words, len),
+                    validate_naive(&words, len),
+                    "len={len} flip={flip}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds capacity")]
+    fn test_validate_len_exceeds_words() {
+        let _ = BalancedParens::validate_words(&[0], 66);
+    }
+
     // ========================================================================
     // SSE4.1 vs Scalar Equivalence Tests
     // ========================================================================
@@ -18,7 +18,9 @@
 //! ```
 
 mod bp;
+#[cfg(feature = "std")]
+mod persist;
 
-pub use bp::BalancedParens;
 pub use bp::{enclose, find_close, find_close_in_word, find_open, find_unmatched_close_in_word};
-pub use bp::{NoSelect, SelectSupport, WithSelect};
+pub use bp::{BalancedParens, BpValidationError};
+pub use bp::{NoSelect, SelectSupport, WithSelect, DEFAULT_SELECT_SAMPLE_RATE};
@@ -0,0 +1,199 @@
+//! Saving a [`BalancedParens`] to disk and loading it back (`std` feature).
+//!
+//! The format is little-endian throughout:
+//!
+//! | Field       | Encoding                                          |
+//! |-------------|---------------------------------------------------|
+//! | magic       | `SBPX`                                            |
+//! | version     | `u16` ([`FORMAT_VERSION`])                        |
+//! | select rate | `u32` opens per select sample, 0 for no select    |
+//! | BP          | bit length `u64`, word count `u64`, words `[u64]` |
+//!
+//! As for [`JsonIndex`](crate::json::JsonIndex), only the bits are stored:
+//! the rank, min-excess and select directories are rebuilt on load in one
+//! pass over the words, so the file does not depend on their layout.
+
+use std::io::{self, Read, Write};
+
+use super::bp::{BalancedParens, NoSelect, SelectSupport, WithSelect, DEFAULT_SELECT_SAMPLE_RATE};
+use crate::util::persist::{read_bits, read_header, read_u32, write_bits, write_header};
+
+/// The first four bytes of a serialized tree.
+const MAGIC: [u8; 4] = *b"SBPX";
+
+/// The format version written by [`BalancedParens::serialize_binary`].
+const FORMAT_VERSION: u16 = 1;
+
+impl<W: AsRef<[u64]>, S: SelectSupport> BalancedParens<W, S> {
+    /// Write the tree to `writer`, to be loaded with
+    /// [`deserialize_binary`](BalancedParens::deserialize_binary) or
+    /// [`deserialize_binary_with_select`](BalancedParens::deserialize_binary_with_select).
+    ///
+    /// Named apart from serde's `serialize`, which this type also derives
+    /// under the `serde` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::trees::BalancedParens;
+    ///
+    /// // ((()())())
+    /// let bp = BalancedParens::new(vec![0b0010010111], 10);
+    /// let mut bytes = Vec::new();
+    /// bp.serialize_binary(&mut bytes).unwrap();
+    ///
+    /// let loaded = BalancedParens::deserialize_binary(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!(loaded.find_close(0), Some(9));
+    /// ```
+    pub fn serialize_binary(&self, writer: &mut impl Write) -> io::Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        writer.write_all(&self.select_sample_rate().unwrap_or(0).to_le_bytes())?;
+        write_bits(writer, self.len(), self.words())
+    }
+}
+
+impl BalancedParens<Vec<u64>, NoSelect> {
+    /// Read a tree written by [`serialize_binary`](BalancedParens::serialize_binary),
+    /// without select support whether or not it was saved with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidData`] if the input does not start
+    /// with the `SBPX` magic, has a different format version, or holds fewer
+    /// words than its length needs or more than `u32::MAX` bits;
+    /// [`io::ErrorKind::UnexpectedEof`] if it is truncated; and any other
+    /// error from `reader`.
+    pub fn deserialize_binary(reader: &mut impl Read) -> io::Result<Self> {
+        let (_, words, len) = read_tree(reader)?;
+        Ok(Self::new(words, len))
+    }
+}
+
+impl BalancedParens<Vec<u64>, WithSelect> {
+    /// Read a tree written by [`serialize_binary`](BalancedParens::serialize_binary)
+    /// with select support, at the saved sample rate or
+    /// [`DEFAULT_SELECT_SAMPLE_RATE`] if it was saved without one.
+    ///
+    /// # Errors
+    ///
+    /// As for [`deserialize_binary`](BalancedParens::deserialize_binary).
+    pub fn deserialize_binary_with_select(reader: &mut impl Read) -> io::Result<Self> {
+        let (sample_rate, words, len) = read_tree(reader)?;
+        let sample_rate = if sample_rate == 0 {
+            DEFAULT_SELECT_SAMPLE_RATE
+        } else {
+            sample_rate
+        };
+        Ok(Self::new_with_select_sample_rate(words, len, sample_rate))
+    }
+}
+
+/// Read the header, select rate and bits of a serialized tree.
+fn read_tree(reader: &mut impl Read) -> io::Result<(u32, Vec<u64>, usize)> {
+    read_header(reader, MAGIC, FORMAT_VERSION, "BalancedParens")?;
+    let sample_rate = read_u32(reader)?;
+    let (len, words) = read_bits(reader)?;
+    Ok((sample_rate, words, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tree of `nodes` nodes mixing deep chains and wide fan-out.
+    fn build_tree(nodes: usize) -> (Vec<u64>, usize) {
+        let len = 2 * nodes;
+        let mut words = vec![0u64; len.div_ceil(64)];
+        let mut pos = 0;
+        let mut open = 0;
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        for _ in 0..nodes {
+            // Close a random number of pending nodes before each new one
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let closes = (state % 3) as usize;
+            for _ in 0..closes.min(open) {
+                pos += 1;
+                open -= 1;
+            }
+            words[pos / 64] |= 1 << (pos % 64);
+            pos += 1;
+            open += 1;
+        }
+        (words, len)
+    }
+
+    fn roundtrip<S: SelectSupport>(bp: &BalancedParens<Vec<u64>, S>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bp.serialize_binary(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip_million_nodes() {
+        let (words, len) = build_tree(1_000_000);
+        let bp = BalancedParens::new(words, len);
+        let bytes = roundtrip(&bp);
+        let loaded = BalancedParens::deserialize_binary(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.len(), bp.len());
+        assert_eq!(loaded.words(), bp.words());
+        assert_eq!(loaded.select_sample_rate(), None);
+        for p in (0..len).step_by(97).chain([0, 1, len - 1]) {
+            assert_eq!(loaded.find_close(p), bp.find_close(p), "find_close({p})");
+            assert_eq!(loaded.parent(p), bp.parent(p), "parent({p})");
+            assert_eq!(loaded.first_child(p), bp.first_child(p), "first_child({p})");
+            assert_eq!(
+                loaded.next_sibling(p),
+                bp.next_sibling(p),
+                "next_sibling({p})"
+            );
+            assert_eq!(loaded.rank1(p), bp.rank1(p), "rank1({p})");
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_select_rate() {
+        let (words, len) = build_tree(10_000);
+        let bp = BalancedParens::new_with_select_sample_rate(words.clone(), len, 64);
+        let bytes = roundtrip(&bp);
+
+        let loaded = BalancedParens::deserialize_binary_with_select(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.select_sample_rate(), Some(64));
+        for k in (0..10_000).step_by(37) {
+            assert_eq!(loaded.select1(k), bp.select1(k), "select1({k})");
+        }
+
+        // Saved without select: loading with select uses the default rate
+        let bytes = roundtrip(&BalancedParens::new(words, len));
+        let loaded = BalancedParens::deserialize_binary_with_select(&mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            loaded.select_sample_rate(),
+            Some(DEFAULT_SELECT_SAMPLE_RATE)
+        );
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let bytes = roundtrip(&BalancedParens::new(vec![0b0011], 4));
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let err = BalancedParens::deserialize_binary(&mut bad_magic.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("BalancedParens"));
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        let err = BalancedParens::deserialize_binary(&mut bad_version.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("version 2"));
+
+        let err = BalancedParens::deserialize_binary(&mut &bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
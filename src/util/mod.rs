@@ -4,9 +4,10 @@
 //! Most users should not need to use these directly.
 
 pub(crate) mod broadword;
+#[cfg(feature = "std")]
+pub(crate) mod persist;
 pub(crate) mod table;
 
-#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub(crate) mod simd;
 
-pub use broadword::select_in_word;
+pub use broadword::{select_in_word, select_in_word_broadword};
@@ -0,0 +1,104 @@
+//! Binary encoding shared by the index persistence formats (`std` feature).
+//!
+//! Each format starts with a four-byte magic and a `u16` version, followed
+//! by bit vectors written as bit length `u64`, word count `u64`, and words
+//! `[u64]`, all little-endian.
+
+use std::io::{self, Read, Write};
+
+/// Write the magic and format version that start a serialized index.
+pub(crate) fn write_header(
+    writer: &mut impl Write,
+    magic: [u8; 4],
+    version: u16,
+) -> io::Result<()> {
+    writer.write_all(&magic)?;
+    writer.write_all(&version.to_le_bytes())
+}
+
+/// Check the magic and format version written by [`write_header`].
+///
+/// `name` is the type being read, for error messages.
+pub(crate) fn read_header(
+    reader: &mut impl Read,
+    magic: [u8; 4],
+    version: u16,
+    name: &str,
+) -> io::Result<()> {
+    let mut found = [0; 4];
+    reader.read_exact(&mut found)?;
+    if found != magic {
+        return Err(invalid_data(&format!(
+            "not a serialized {name} (bad magic)"
+        )));
+    }
+    let mut found = [0; 2];
+    reader.read_exact(&mut found)?;
+    let found = u16::from_le_bytes(found);
+    if found != version {
+        return Err(invalid_data(&format!(
+            "unsupported {name} format version {found} (expected {version})"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_bits(writer: &mut impl Write, len: usize, words: &[u64]) -> io::Result<()> {
+    writer.write_all(&(len as u64).to_le_bytes())?;
+    writer.write_all(&(words.len() as u64).to_le_bytes())?;
+    for word in words {
+        writer.write_all(&word.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read a bit vector's length and words, checking that they fit an index.
+pub(crate) fn read_bits(reader: &mut impl Read) -> io::Result<(usize, Vec<u64>)> {
+    let len = read_u64(reader)?;
+    let word_count = read_u64(reader)?;
+    if u32::try_from(len).is_err() {
+        return Err(invalid_data(&format!(
+            "bit vector of {len} bits exceeds u32::MAX"
+        )));
+    }
+    if word_count < len.div_ceil(64) {
+        return Err(invalid_data(&format!(
+            "{word_count} words cannot hold {len} bits"
+        )));
+    }
+    let byte_count = word_count
+        .checked_mul(8)
+        .ok_or_else(|| invalid_data("word count overflows"))?;
+
+    // Read through `take` so a corrupt word count fails at the end of the
+    // input instead of allocating it up front
+    let mut bytes = Vec::new();
+    reader.take(byte_count).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != byte_count {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "serialized index is truncated",
+        ));
+    }
+    let words = bytes
+        .chunks_exact(8)
+        .map(|word| u64::from_le_bytes(word.try_into().expect("8-byte chunk")))
+        .collect();
+    Ok((len as usize, words))
+}
+
+pub(crate) fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
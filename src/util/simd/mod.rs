@@ -2,7 +2,8 @@
 //! SIMD-accelerated operations.
 //!
 //! This module provides platform-specific SIMD implementations for
-//! performance-critical operations like popcount.
+//! performance-critical operations like popcount, UTF-8 validation and
+//! byte scanning.
 
 #[cfg(target_arch = "aarch64")]
 pub mod neon;
@@ -13,6 +14,17 @@ pub mod sve2;
 #[cfg(target_arch = "x86_64")]
 pub mod x86;
 
+pub mod scan;
+
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+pub mod utf8_avx2;
+
+#[cfg(any(
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", any(test, feature = "std"))
+))]
+pub mod utf8_tables;
+
 /// Popcount of a 512-bit (64-byte) block.
 ///
 /// Uses the best available implementation for the current platform.
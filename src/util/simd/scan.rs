@@ -0,0 +1,487 @@
+#![allow(unsafe_code)] // SSE2/AVX2/NEON byte-scanning intrinsics
+//! Finding the first byte of a small set, shared by the parsers and by
+//! [`find_char`](crate::text::utf8::find_char).
+//!
+//! Each scan compares a whole vector against the wanted bytes, extracts the
+//! result as a bitmask and stops at its lowest set bit:
+//!
+//! - [`find_first_of_2`] and [`find_first_of_4`]: bytes equal to one of two
+//!   or four values. To find a single byte, pass it twice.
+//! - [`find_first_lt`]: bytes below a threshold, compared unsigned, such as
+//!   the control characters below `0x20`.
+//!
+//! On x86_64 AVX2 (32 bytes per iteration) is selected at runtime when `std`
+//! is enabled, with SSE2 (16 bytes) as the baseline; aarch64 uses NEON.
+//! Other targets use the scalar loops. The SSE2 and AVX2 kernels of
+//! [`find_first_of_2`] are public for callers with their own dispatch, such
+//! as the YAML parser's `SUCCINCTLY_SIMD` clamp.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+
+/// Offset of the first byte of `input` equal to `a` or `b`.
+#[inline]
+pub fn find_first_of_2(input: &[u8], a: u8, b: u8) -> Option<usize> {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just detected
+            return unsafe { find_first_of_2_avx2(input, a, b) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { find_first_of_2_sse2(input, a, b) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is mandatory on aarch64
+        unsafe { find_first_of_2_neon(input, a, b) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        find_first_of_2_scalar(input, a, b)
+    }
+}
+
+/// Offset of the first byte of `input` equal to `a`, `b`, `c` or `d`.
+#[inline]
+pub fn find_first_of_4(input: &[u8], a: u8, b: u8, c: u8, d: u8) -> Option<usize> {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just detected
+            return unsafe { find_first_of_4_avx2(input, [a, b, c, d]) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { find_first_of_4_sse2(input, [a, b, c, d]) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is mandatory on aarch64
+        unsafe { find_first_of_4_neon(input, [a, b, c, d]) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        find_first_of_4_scalar(input, [a, b, c, d])
+    }
+}
+
+/// Offset of the first byte of `input` below `threshold`, unsigned.
+///
+/// A `threshold` of 0 matches nothing.
+#[inline]
+pub fn find_first_lt(input: &[u8], threshold: u8) -> Option<usize> {
+    if threshold == 0 {
+        return None;
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just detected
+            return unsafe { find_first_lt_avx2(input, threshold) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { find_first_lt_sse2(input, threshold) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: NEON is mandatory on aarch64
+        unsafe { find_first_lt_neon(input, threshold) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        find_first_lt_scalar(input, threshold)
+    }
+}
+
+/// [`find_first_of_2`] one byte at a time.
+fn find_first_of_2_scalar(input: &[u8], a: u8, b: u8) -> Option<usize> {
+    input.iter().position(|&byte| byte == a || byte == b)
+}
+
+/// [`find_first_of_4`] one byte at a time.
+fn find_first_of_4_scalar(input: &[u8], set: [u8; 4]) -> Option<usize> {
+    input.iter().position(|byte| set.contains(byte))
+}
+
+/// [`find_first_lt`] one byte at a time.
+fn find_first_lt_scalar(input: &[u8], threshold: u8) -> Option<usize> {
+    input.iter().position(|&byte| byte < threshold)
+}
+
+/// Continue a scalar scan of `input` at `offset`, reporting offsets into
+/// all of `input`.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[inline]
+fn finish_scalar(
+    input: &[u8],
+    offset: usize,
+    scan: impl FnOnce(&[u8]) -> Option<usize>,
+) -> Option<usize> {
+    scan(&input[offset..]).map(|found| offset + found)
+}
+
+// ============================================================================
+// x86_64
+// ============================================================================
+
+/// AVX2 kernel of [`find_first_of_2`], for callers with their own dispatch.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+#[target_feature(enable = "avx2")]
+pub unsafe fn find_first_of_2_avx2(input: &[u8], a: u8, b: u8) -> Option<usize> {
+    let (a_vec, b_vec) = (_mm256_set1_epi8(a as i8), _mm256_set1_epi8(b as i8));
+    let mut chunks = input.chunks_exact(32);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+        let matches = _mm256_or_si256(
+            _mm256_cmpeq_epi8(bytes, a_vec),
+            _mm256_cmpeq_epi8(bytes, b_vec),
+        );
+        let mask = _mm256_movemask_epi8(matches) as u32;
+        if mask != 0 {
+            return Some(i * 32 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_2_sse2(rest, a, b))
+}
+
+/// SSE2 kernel of [`find_first_of_2`], for callers with their own dispatch.
+///
+/// # Safety
+///
+/// Always safe on x86_64, where SSE2 is baseline; `unsafe` only because of
+/// the `target_feature` attribute.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn find_first_of_2_sse2(input: &[u8], a: u8, b: u8) -> Option<usize> {
+    let (a_vec, b_vec) = (_mm_set1_epi8(a as i8), _mm_set1_epi8(b as i8));
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let matches = _mm_or_si128(_mm_cmpeq_epi8(bytes, a_vec), _mm_cmpeq_epi8(bytes, b_vec));
+        let mask = _mm_movemask_epi8(matches) as u32;
+        if mask != 0 {
+            return Some(i * 16 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_2_scalar(rest, a, b))
+}
+
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+#[target_feature(enable = "avx2")]
+unsafe fn find_first_of_4_avx2(input: &[u8], set: [u8; 4]) -> Option<usize> {
+    let set_vec = set.map(|byte| _mm256_set1_epi8(byte as i8));
+    let mut chunks = input.chunks_exact(32);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+        let matches = _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_cmpeq_epi8(bytes, set_vec[0]),
+                _mm256_cmpeq_epi8(bytes, set_vec[1]),
+            ),
+            _mm256_or_si256(
+                _mm256_cmpeq_epi8(bytes, set_vec[2]),
+                _mm256_cmpeq_epi8(bytes, set_vec[3]),
+            ),
+        );
+        let mask = _mm256_movemask_epi8(matches) as u32;
+        if mask != 0 {
+            return Some(i * 32 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_4_sse2(rest, set))
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn find_first_of_4_sse2(input: &[u8], set: [u8; 4]) -> Option<usize> {
+    let set_vec = set.map(|byte| _mm_set1_epi8(byte as i8));
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let matches = _mm_or_si128(
+            _mm_or_si128(
+                _mm_cmpeq_epi8(bytes, set_vec[0]),
+                _mm_cmpeq_epi8(bytes, set_vec[1]),
+            ),
+            _mm_or_si128(
+                _mm_cmpeq_epi8(bytes, set_vec[2]),
+                _mm_cmpeq_epi8(bytes, set_vec[3]),
+            ),
+        );
+        let mask = _mm_movemask_epi8(matches) as u32;
+        if mask != 0 {
+            return Some(i * 16 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_4_scalar(rest, set))
+}
+
+// x86 has no unsigned byte comparison: `min(byte, threshold - 1) == byte`
+// holds exactly when `byte < threshold`. Callers rule out a threshold of 0.
+
+#[cfg(all(target_arch = "x86_64", any(test, feature = "std")))]
+#[target_feature(enable = "avx2")]
+unsafe fn find_first_lt_avx2(input: &[u8], threshold: u8) -> Option<usize> {
+    let max = _mm256_set1_epi8((threshold - 1) as i8);
+    let mut chunks = input.chunks_exact(32);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm256_loadu_si256(chunk.as_ptr().cast());
+        let below = _mm256_cmpeq_epi8(_mm256_min_epu8(bytes, max), bytes);
+        let mask = _mm256_movemask_epi8(below) as u32;
+        if mask != 0 {
+            return Some(i * 32 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_lt_sse2(rest, threshold))
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn find_first_lt_sse2(input: &[u8], threshold: u8) -> Option<usize> {
+    let max = _mm_set1_epi8((threshold - 1) as i8);
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let below = _mm_cmpeq_epi8(_mm_min_epu8(bytes, max), bytes);
+        let mask = _mm_movemask_epi8(below) as u32;
+        if mask != 0 {
+            return Some(i * 16 + mask.trailing_zeros() as usize);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_lt_scalar(rest, threshold))
+}
+
+// ============================================================================
+// aarch64
+// ============================================================================
+
+// NEON has no movemask. Narrowing each 16-bit lane pair by 4 bits packs the
+// 0xFF/0 comparison lanes into a 64-bit word with a nibble per byte, whose
+// trailing zeros divided by 4 give the first match.
+
+/// Offset of the first set lane of a NEON comparison result, if any.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn first_set_lane(matches: uint8x16_t) -> Option<usize> {
+    let nibbles = vshrn_n_u16::<4>(vreinterpretq_u16_u8(matches));
+    let mask = vget_lane_u64::<0>(vreinterpret_u64_u8(nibbles));
+    (mask != 0).then(|| mask.trailing_zeros() as usize / 4)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn find_first_of_2_neon(input: &[u8], a: u8, b: u8) -> Option<usize> {
+    let (a_vec, b_vec) = (vdupq_n_u8(a), vdupq_n_u8(b));
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = vld1q_u8(chunk.as_ptr());
+        let matches = vorrq_u8(vceqq_u8(bytes, a_vec), vceqq_u8(bytes, b_vec));
+        if let Some(lane) = first_set_lane(matches) {
+            return Some(i * 16 + lane);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_2_scalar(rest, a, b))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn find_first_of_4_neon(input: &[u8], set: [u8; 4]) -> Option<usize> {
+    let set_vec = [
+        vdupq_n_u8(set[0]),
+        vdupq_n_u8(set[1]),
+        vdupq_n_u8(set[2]),
+        vdupq_n_u8(set[3]),
+    ];
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let bytes = vld1q_u8(chunk.as_ptr());
+        let matches = vorrq_u8(
+            vorrq_u8(vceqq_u8(bytes, set_vec[0]), vceqq_u8(bytes, set_vec[1])),
+            vorrq_u8(vceqq_u8(bytes, set_vec[2]), vceqq_u8(bytes, set_vec[3])),
+        );
+        if let Some(lane) = first_set_lane(matches) {
+            return Some(i * 16 + lane);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_of_4_scalar(rest, set))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn find_first_lt_neon(input: &[u8], threshold: u8) -> Option<usize> {
+    let threshold_vec = vdupq_n_u8(threshold);
+    let mut chunks = input.chunks_exact(16);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let below = vcltq_u8(vld1q_u8(chunk.as_ptr()), threshold_vec);
+        if let Some(lane) = first_set_lane(below) {
+            return Some(i * 16 + lane);
+        }
+    }
+    let offset = input.len() - chunks.remainder().len();
+    finish_scalar(input, offset, |rest| find_first_lt_scalar(rest, threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Inputs with one match at every offset, covering every tail length of
+    /// both vector widths.
+    fn samples(needle: u8) -> Vec<Vec<u8>> {
+        let mut samples = vec![Vec::new()];
+        for len in 1..100 {
+            samples.push(vec![b'a'; len]);
+            for at in 0..len {
+                let mut input = vec![b'a'; len];
+                input[at] = needle;
+                samples.push(input);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_find_first_of_2() {
+        assert_eq!(find_first_of_2(b"hello\"world", b'"', b'\\'), Some(5));
+        assert_eq!(find_first_of_2(b"hello\\world", b'"', b'\\'), Some(5));
+        assert_eq!(find_first_of_2(b"hello'world", b'\'', b'\''), Some(5));
+        assert_eq!(find_first_of_2(b"", b'"', b'\\'), None);
+        for input in samples(b'\\') {
+            let expected = find_first_of_2_scalar(&input, b'"', b'\\');
+            assert_eq!(find_first_of_2(&input, b'"', b'\\'), expected);
+        }
+    }
+
+    #[test]
+    fn test_find_first_of_4() {
+        assert_eq!(
+            find_first_of_4(b"a,b\"c", b',', b'"', b'\n', b'\r'),
+            Some(1)
+        );
+        assert_eq!(find_first_of_4(b"plain", b',', b'"', b'\n', b'\r'), None);
+        for input in samples(b'\r') {
+            let expected = find_first_of_4_scalar(&input, *b",\"\n\r");
+            assert_eq!(find_first_of_4(&input, b',', b'"', b'\n', b'\r'), expected);
+        }
+    }
+
+    #[test]
+    fn test_find_first_lt() {
+        assert_eq!(find_first_lt(b"tab\there", 0x20), Some(3));
+        assert_eq!(find_first_lt(b"no controls", 0x20), None);
+        // Unsigned: non-ASCII bytes are not below 0x20
+        assert_eq!(find_first_lt(&[0x80, 0xFF, 0x1F], 0x20), Some(2));
+        assert_eq!(find_first_lt(&[0, 1, 2], 0), None);
+        assert_eq!(find_first_lt(&[0xFF, 0xFE], 0xFF), Some(1));
+        for input in samples(0x1F) {
+            assert_eq!(
+                find_first_lt(&input, 0x20),
+                find_first_lt_scalar(&input, 0x20)
+            );
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_sse2_matches_scalar() {
+        for input in samples(0x0A) {
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe {
+                assert_eq!(
+                    find_first_of_2_sse2(&input, b'\n', b'\n'),
+                    find_first_of_2_scalar(&input, b'\n', b'\n')
+                );
+                assert_eq!(
+                    find_first_of_4_sse2(&input, *b"\n,;|"),
+                    find_first_of_4_scalar(&input, *b"\n,;|")
+                );
+                assert_eq!(
+                    find_first_lt_sse2(&input, 0x20),
+                    find_first_lt_scalar(&input, 0x20)
+                );
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_avx2_matches_scalar() {
+        if !crate::util::simd::note_simd_skip_unless(is_x86_feature_detected!("avx2"), "avx2") {
+            return;
+        }
+        for input in samples(0x0A) {
+            // SAFETY: AVX2 support was just detected
+            unsafe {
+                assert_eq!(
+                    find_first_of_2_avx2(&input, b'\n', b'\n'),
+                    find_first_of_2_scalar(&input, b'\n', b'\n')
+                );
+                assert_eq!(
+                    find_first_of_4_avx2(&input, *b"\n,;|"),
+                    find_first_of_4_scalar(&input, *b"\n,;|")
+                );
+                assert_eq!(
+                    find_first_lt_avx2(&input, 0x20),
+                    find_first_lt_scalar(&input, 0x20)
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_scalar(
+            input in prop::collection::vec(any::<u8>(), 0..200),
+            set in any::<[u8; 4]>(),
+        ) {
+            prop_assert_eq!(
+                find_first_of_2(&input, set[0], set[1]),
+                find_first_of_2_scalar(&input, set[0], set[1])
+            );
+            prop_assert_eq!(
+                find_first_of_4(&input, set[0], set[1], set[2], set[3]),
+                find_first_of_4_scalar(&input, set)
+            );
+            prop_assert_eq!(
+                find_first_lt(&input, set[0]),
+                find_first_lt_scalar(&input, set[0])
+            );
+        }
+    }
+}
@@ -0,0 +1,301 @@
+#![allow(unsafe_code)] // x86_64 AVX2 SIMD intrinsics
+//! AVX2 UTF-8 validation for x86_64.
+//!
+//! The Keiser & Lemire lookup algorithm (see
+//! [`utf8_tables`](super::utf8_tables)), 32 bytes per iteration. It works
+//! like the NEON version in `text::utf8_neon`, except that AVX2 shuffles
+//! and byte shifts stay within 128-bit lanes: the tables are broadcast to
+//! both lanes, and the bytes one to three positions back are assembled with
+//! `vperm2i128` + `vpalignr` rather than a single `ext`.
+//!
+//! [`validate_chunk_avx2`] checks one 32-byte chunk given a [`Utf8Carry`]
+//! from the chunk before it, for callers that already hold the input in
+//! vector registers; [`validate_bytes_avx2`] validates a whole slice and
+//! locates the first error.
+
+use core::arch::x86_64::*;
+
+use super::utf8_tables::{BYTE_1_HIGH, BYTE_1_LOW, BYTE_2_HIGH};
+
+/// Largest byte allowed in each of the last three positions of a chunk
+/// without continuation bytes in the next chunk.
+const INCOMPLETE_MAX: [u8; 32] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xEF, 0xDF, 0xBF,
+];
+
+/// State carried from one chunk to the next.
+#[derive(Clone, Copy)]
+pub struct Utf8Carry {
+    /// The previous chunk, all zero before the first.
+    prev: __m256i,
+    /// Non-zero where the previous chunk ends in a lead byte that still
+    /// needs continuation bytes.
+    prev_incomplete: __m256i,
+}
+
+impl Utf8Carry {
+    /// The carry before the first chunk of the input.
+    #[inline]
+    pub fn new() -> Self {
+        // SAFETY: all-zero bytes are a valid `__m256i`
+        unsafe { core::mem::zeroed() }
+    }
+
+    /// Whether the chunks so far end on a character boundary, that is, the
+    /// input is complete if it ends here.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn is_complete(&self) -> bool {
+        _mm256_testz_si256(self.prev_incomplete, self.prev_incomplete) == 1
+    }
+}
+
+impl Default for Utf8Carry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check one 32-byte chunk, given the carry from the chunk before it.
+///
+/// Returns `false` if the chunk contains an error, or an error ending in it
+/// that started in the previous chunk. `carry` is updated for the next
+/// chunk either way; check [`Utf8Carry::is_complete`] after the last one.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[inline]
+#[target_feature(enable = "avx2")]
+pub unsafe fn validate_chunk_avx2(chunk: __m256i, carry: &mut Utf8Carry) -> bool {
+    let error = if _mm256_movemask_epi8(chunk) == 0 {
+        // All ASCII: only a sequence left open by the previous chunk can fail
+        carry.prev_incomplete
+    } else {
+        chunk_errors(chunk, carry.prev)
+    };
+    carry.prev_incomplete =
+        _mm256_subs_epu8(chunk, _mm256_loadu_si256(INCOMPLETE_MAX.as_ptr().cast()));
+    carry.prev = chunk;
+    _mm256_testz_si256(error, error) == 1
+}
+
+/// Validate `input` as UTF-8, 32 bytes at a time.
+///
+/// Returns the length of the longest valid prefix on failure, the same
+/// offset as [`core::str::Utf8Error::valid_up_to`].
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub unsafe fn validate_bytes_avx2(input: &[u8]) -> Result<(), usize> {
+    let mut carry = Utf8Carry::new();
+    let mut chunks = input.chunks_exact(32);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        if !validate_chunk_avx2(_mm256_loadu_si256(chunk.as_ptr().cast()), &mut carry) {
+            return locate_error(input, offset);
+        }
+        offset += 32;
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        // Pad with ASCII, which ends any sequence still open
+        let mut padded = [0u8; 32];
+        padded[..remainder.len()].copy_from_slice(remainder);
+        if !validate_chunk_avx2(_mm256_loadu_si256(padded.as_ptr().cast()), &mut carry) {
+            return locate_error(input, offset);
+        }
+    } else if !carry.is_complete() {
+        return locate_error(input, offset);
+    }
+    Ok(())
+}
+
+/// The errors of `chunk` as a non-zero mask, given the chunk before it.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn chunk_errors(chunk: __m256i, prev: __m256i) -> __m256i {
+    // The 16 bytes before each lane: the previous lane, which for the low
+    // lane is the high lane of `prev`
+    let before = _mm256_permute2x128_si256::<0x21>(prev, chunk);
+    let prev1 = _mm256_alignr_epi8::<15>(chunk, before);
+    let prev2 = _mm256_alignr_epi8::<14>(chunk, before);
+    let prev3 = _mm256_alignr_epi8::<13>(chunk, before);
+
+    let low_nibble = _mm256_set1_epi8(0x0F);
+    let special_cases = _mm256_and_si256(
+        _mm256_and_si256(
+            lookup(
+                &BYTE_1_HIGH,
+                _mm256_and_si256(_mm256_srli_epi16::<4>(prev1), low_nibble),
+            ),
+            lookup(&BYTE_1_LOW, _mm256_and_si256(prev1, low_nibble)),
+        ),
+        lookup(
+            &BYTE_2_HIGH,
+            _mm256_and_si256(_mm256_srli_epi16::<4>(chunk), low_nibble),
+        ),
+    );
+
+    // Bytes that must be a third or fourth continuation byte: two after a
+    // three- or four-byte lead, or three after a four-byte lead
+    let is_third = _mm256_subs_epu8(prev2, _mm256_set1_epi8((0xE0 - 0x80) as i8));
+    let is_fourth = _mm256_subs_epu8(prev3, _mm256_set1_epi8((0xF0 - 0x80) as i8));
+    let must_be_continuation = _mm256_and_si256(
+        _mm256_or_si256(is_third, is_fourth),
+        _mm256_set1_epi8(0x80_u8 as i8),
+    );
+
+    _mm256_xor_si256(must_be_continuation, special_cases)
+}
+
+/// Look up each nibble (`0..16`) of `nibbles` in `table`.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn lookup(table: &[u8; 16], nibbles: __m256i) -> __m256i {
+    let table = _mm256_broadcastsi128_si256(_mm_loadu_si128(table.as_ptr().cast()));
+    _mm256_shuffle_epi8(table, nibbles)
+}
+
+/// The first error at or after the character boundary at or before `pos`,
+/// where the previous chunks found no error before `pos`.
+fn locate_error(input: &[u8], pos: usize) -> Result<(), usize> {
+    // A sequence open at `pos` starts at the last lead byte of the three
+    // before it; without one, `pos` is a boundary
+    let start = (pos.saturating_sub(3)..pos)
+        .rev()
+        .find(|&i| input[i] >= 0xC0)
+        .unwrap_or(pos);
+    core::str::from_utf8(&input[start..])
+        .map(|_| ())
+        .map_err(|error| start + error.valid_up_to())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn avx2() -> bool {
+        crate::util::simd::note_simd_skip_unless(is_x86_feature_detected!("avx2"), "avx2")
+    }
+
+    fn expected(input: &[u8]) -> Result<(), usize> {
+        core::str::from_utf8(input)
+            .map(|_| ())
+            .map_err(|error| error.valid_up_to())
+    }
+
+    fn validate(input: &[u8]) -> Result<(), usize> {
+        // SAFETY: callers check for AVX2 first
+        unsafe { validate_bytes_avx2(input) }
+    }
+
+    #[test]
+    fn test_matches_std() {
+        if !avx2() {
+            return;
+        }
+        let text = "ASCII, caf\u{00E9}, \u{65E5}\u{672C}\u{8A9E}, \u{1F389}\n".repeat(4);
+        let bytes = text.as_bytes();
+        for start in 0..bytes.len() {
+            for end in start..=bytes.len() {
+                let slice = &bytes[start..end];
+                assert_eq!(validate(slice), expected(slice));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_at_every_offset() {
+        if !avx2() {
+            return;
+        }
+        let invalid: [&[u8]; 8] = [
+            &[0x80],
+            &[0xC0, 0xAF],
+            &[0xE0, 0x80, 0x80],
+            &[0xED, 0xA0, 0x80],
+            &[0xF4, 0x90, 0x80, 0x80],
+            &[0xF5, 0x80, 0x80, 0x80],
+            &[0xE2, 0x82, b'a'],
+            &[0xF0, 0x9F, 0x8E],
+        ];
+        for sequence in invalid {
+            for offset in 0..70 {
+                let mut input = vec![b'a'; offset];
+                input.extend_from_slice(sequence);
+                assert_eq!(validate(&input), Err(offset), "{sequence:02X?} at {offset}");
+                input.extend_from_slice(&[b'a'; 40]);
+                assert_eq!(validate(&input), Err(offset), "{sequence:02X?} at {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunks_with_carry() {
+        if !avx2() {
+            return;
+        }
+        // A four-byte character split across the chunk boundary
+        let mut input = [b'a'; 64];
+        input[30..34].copy_from_slice("\u{1F389}".as_bytes());
+        // SAFETY: AVX2 support was just detected
+        unsafe {
+            let mut carry = Utf8Carry::new();
+            assert!(validate_chunk_avx2(
+                _mm256_loadu_si256(input.as_ptr().cast()),
+                &mut carry
+            ));
+            assert!(!carry.is_complete());
+            assert!(validate_chunk_avx2(
+                _mm256_loadu_si256(input[32..].as_ptr().cast()),
+                &mut carry
+            ));
+            assert!(carry.is_complete());
+        }
+    }
+
+    /// Valid text with a few bytes overwritten, so errors fall anywhere in
+    /// otherwise multi-byte input rather than almost always at offset 0.
+    fn mostly_valid() -> impl Strategy<Value = Vec<u8>> {
+        (
+            any::<String>(),
+            prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 0..3),
+        )
+            .prop_map(|(text, edits)| {
+                let mut bytes = text.repeat(4).into_bytes();
+                if !bytes.is_empty() {
+                    for (index, byte) in edits {
+                        let i = index.index(bytes.len());
+                        bytes[i] = byte;
+                    }
+                }
+                bytes
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_std(input in mostly_valid()) {
+            if avx2() {
+                prop_assert_eq!(validate(&input), expected(&input));
+            }
+        }
+
+        #[test]
+        fn prop_matches_std_on_any_bytes(input in prop::collection::vec(any::<u8>(), 0..200)) {
+            if avx2() {
+                prop_assert_eq!(validate(&input), expected(&input));
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Lookup tables of the Keiser & Lemire UTF-8 validation algorithm,
+//! ["Validating UTF-8 In Less Than One Instruction Per Byte"](https://arxiv.org/abs/2010.03090)
+//! (2021), shared by the AVX2 (`util::simd::utf8_avx2`) and NEON
+//! (`text::utf8_neon`) validators.
+//!
+//! Every error in a two-byte window is classified by three table lookups:
+//! the high and low nibble of the first byte and the high nibble of the
+//! second. Each table maps its nibble to the set of error kinds that nibble
+//! is consistent with, so the AND of the three is non-zero exactly when the
+//! pair is invalid. The 16-entry tables fit one 128-bit shuffle (`pshufb`,
+//! `tbl`), so both backends look them up directly.
+
+// Error kinds, one bit each; a nibble table entry is the set of kinds its
+// nibble allows. OVERLONG_4 and TOO_LARGE_1000 share a bit, as they are
+// never both possible for the same second byte.
+const TOO_SHORT: u8 = 1 << 0;
+const TOO_LONG: u8 = 1 << 1;
+const OVERLONG_3: u8 = 1 << 2;
+const TOO_LARGE: u8 = 1 << 3;
+const SURROGATE: u8 = 1 << 4;
+const OVERLONG_2: u8 = 1 << 5;
+const TOO_LARGE_1000: u8 = 1 << 6;
+const OVERLONG_4: u8 = 1 << 6;
+const TWO_CONTS: u8 = 1 << 7;
+/// Kinds decided by the first byte's high nibble alone.
+const CARRY: u8 = TOO_SHORT | TOO_LONG | TWO_CONTS;
+
+/// Indexed by the high nibble of the first byte.
+pub const BYTE_1_HIGH: [u8; 16] = [
+    // 0xxx: ASCII
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    TOO_LONG,
+    // 10xx: continuation
+    TWO_CONTS,
+    TWO_CONTS,
+    TWO_CONTS,
+    TWO_CONTS,
+    // 1100: two-byte lead, C0/C1 overlong
+    TOO_SHORT | OVERLONG_2,
+    // 1101: two-byte lead
+    TOO_SHORT,
+    // 1110: three-byte lead
+    TOO_SHORT | OVERLONG_3 | SURROGATE,
+    // 1111: four-byte lead
+    TOO_SHORT | TOO_LARGE | TOO_LARGE_1000 | OVERLONG_4,
+];
+
+/// Indexed by the low nibble of the first byte.
+pub const BYTE_1_LOW: [u8; 16] = [
+    // 0000: C0, E0, F0
+    CARRY | OVERLONG_3 | OVERLONG_2 | OVERLONG_4,
+    // 0001: C1
+    CARRY | OVERLONG_2,
+    CARRY,
+    CARRY,
+    // 0100: F4
+    CARRY | TOO_LARGE,
+    // 0101 and up: F5..FF
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    // 1101: ED
+    CARRY | TOO_LARGE | TOO_LARGE_1000 | SURROGATE,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+    CARRY | TOO_LARGE | TOO_LARGE_1000,
+];
+
+/// Indexed by the high nibble of the second byte.
+pub const BYTE_2_HIGH: [u8; 16] = [
+    // 0xxx: ASCII
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    // 1000: 80..8F
+    TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE_1000 | OVERLONG_4,
+    // 1001: 90..9F
+    TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE,
+    // 101x: A0..BF
+    TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+    TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+    // 11xx: lead
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+    TOO_SHORT,
+];
@@ -97,6 +97,7 @@ pub unsafe fn popcount_words_popcnt(ptr: *const u64, word_count: usize) -> u32 {
 /// Requires BMI2 support. Caller must check `is_x86_feature_detected!("bmi2")`.
 /// For best performance, also check for fast BMI2 (avoid AMD Zen 1/2).
 #[cfg(target_arch = "x86_64")]
+#[cfg_attr(feature = "select-no-pdep", allow(dead_code))] // STYLE-0005: unused when select-no-pdep compiles out PDEP select
 #[target_feature(enable = "bmi2")]
 #[inline]
 pub unsafe fn select_in_word_pdep(x: u64, k: u32) -> u32 {
@@ -146,6 +147,7 @@ pub unsafe fn select_in_word_pdep(x: u64, k: u32) -> u32 {
 /// The detection is conservative and may return `false` for some CPUs that
 /// actually have fast BMI2 (e.g., older Intel without AVX-512).
 #[cfg(all(target_arch = "x86_64", any(feature = "std", test)))]
+#[cfg_attr(feature = "select-no-pdep", allow(dead_code))] // STYLE-0005: unused when select-no-pdep compiles out PDEP select
 pub fn has_fast_bmi2() -> bool {
     use core::sync::atomic::{AtomicU8, Ordering};
 
@@ -165,6 +167,7 @@ pub fn has_fast_bmi2() -> bool {
 }
 
 #[cfg(all(target_arch = "x86_64", any(feature = "std", test)))]
+#[cfg_attr(feature = "select-no-pdep", allow(dead_code))] // STYLE-0005: unused when select-no-pdep compiles out PDEP select
 fn detect_fast_bmi2() -> bool {
     // First check if BMI2 is supported at all
     if !is_x86_feature_detected!("bmi2") {
@@ -0,0 +1,423 @@
+//! Structural diff of two YAML documents.
+//!
+//! [`YamlIndex::diff`] walks both trees together: mappings are compared key
+//! by key, sequences are aligned on their longest common subsequence of
+//! equal elements, and any other pair of differing nodes is one change.
+//! Values in the resulting [`YamlPatchOp`]s are compact JSON, as
+//! [`YamlCursor::to_json`] renders them, so a string and a number with the
+//! same text stay distinguishable.
+
+#[cfg(not(test))]
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(test)]
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::error::YamlError;
+use super::index::YamlIndex;
+use super::light::{YamlCursor, YamlElements, YamlValue};
+
+/// One difference between two YAML documents.
+///
+/// A path lists the mapping keys and sequence indices (as decimal strings)
+/// from the document root. `Remove` and `Change` paths index into the old
+/// document, `Add` paths into the new one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum YamlPatchOp {
+    /// A value present only in the new document
+    Add {
+        /// Where the value is in the new document
+        path: Vec<String>,
+        /// The added value, as compact JSON
+        value: String,
+    },
+    /// A value present only in the old document
+    Remove {
+        /// Where the value was in the old document
+        path: Vec<String>,
+    },
+    /// A value that differs between the documents
+    Change {
+        /// Where the value is in the old document
+        path: Vec<String>,
+        /// The old value, as compact JSON
+        from: String,
+        /// The new value, as compact JSON
+        to: String,
+    },
+}
+
+impl YamlPatchOp {
+    /// The path this operation applies to.
+    pub fn path(&self) -> &[String] {
+        match self {
+            Self::Add { path, .. } | Self::Remove { path } | Self::Change { path, .. } => path,
+        }
+    }
+
+    /// Render the operation as `+`/`-` lines, one per side it touches.
+    ///
+    /// The path is written with `.` between segments, or `.` alone for the
+    /// document root.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlPatchOp;
+    ///
+    /// let op = YamlPatchOp::Change {
+    ///     path: vec!["spec".into(), "replicas".into()],
+    ///     from: "2".into(),
+    ///     to: "3".into(),
+    /// };
+    /// assert_eq!(op.to_diff_string(), "- spec.replicas: 2\n+ spec.replicas: 3\n");
+    /// ```
+    pub fn to_diff_string(&self) -> String {
+        let path = if self.path().is_empty() {
+            ".".to_string()
+        } else {
+            self.path().join(".")
+        };
+        match self {
+            Self::Add { value, .. } => format!("+ {path}: {value}\n"),
+            Self::Remove { .. } => format!("- {path}\n"),
+            Self::Change { from, to, .. } => format!("- {path}: {from}\n+ {path}: {to}\n"),
+        }
+    }
+}
+
+impl YamlIndex<Vec<u64>> {
+    /// Compute the structural differences that turn `a_yaml` into `b_yaml`.
+    ///
+    /// Single-document inputs are compared document to document. If either
+    /// input has several documents, the streams are compared as sequences
+    /// of documents, so each path starts with a document index.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from [`YamlIndex::build`] if either input is not
+    /// valid YAML.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::{YamlIndex, YamlPatchOp};
+    ///
+    /// let ops = YamlIndex::diff(b"name: app\nport: 80\n", b"name: app\nport: 8080\ntls: true\n")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     ops,
+    ///     [
+    ///         YamlPatchOp::Change { path: vec!["port".into()], from: "80".into(), to: "8080".into() },
+    ///         YamlPatchOp::Add { path: vec!["tls".into()], value: "true".into() },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(a_yaml: &[u8], b_yaml: &[u8]) -> Result<Vec<YamlPatchOp>, YamlError> {
+        let a_index = Self::build(a_yaml)?;
+        let b_index = Self::build(b_yaml)?;
+        let (a_root, b_root) = (a_index.root(a_yaml), b_index.root(b_yaml));
+
+        let mut ops = Vec::new();
+        let mut path = Vec::new();
+        match (single_document(a_root), single_document(b_root)) {
+            (Some(a), Some(b)) => diff_node(a, b, &mut path, &mut ops),
+            _ => diff_node(a_root, b_root, &mut path, &mut ops),
+        }
+        Ok(ops)
+    }
+}
+
+/// The only document of a stream, or `None` if it has zero or several.
+fn single_document<W: AsRef<[u64]>>(root: YamlCursor<'_, W>) -> Option<YamlCursor<'_, W>> {
+    let first = root.first_child()?;
+    first.next_sibling().is_none().then_some(first)
+}
+
+/// The cursor an alias refers to, or `cursor` itself.
+fn resolve<W: AsRef<[u64]>>(cursor: YamlCursor<'_, W>) -> YamlCursor<'_, W> {
+    match cursor.value() {
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => target,
+        _ => cursor,
+    }
+}
+
+fn diff_node<W: AsRef<[u64]>>(
+    a: YamlCursor<'_, W>,
+    b: YamlCursor<'_, W>,
+    path: &mut Vec<String>,
+    ops: &mut Vec<YamlPatchOp>,
+) {
+    let (a, b) = (resolve(a), resolve(b));
+    match (a.value(), b.value()) {
+        (YamlValue::Mapping(a_fields), YamlValue::Mapping(b_fields)) => {
+            let b_by_key: BTreeMap<_, _> = b_fields
+                .map(|field| (field.key().key_string(), field.value_cursor()))
+                .collect();
+            let mut a_keys = BTreeSet::new();
+            for field in a_fields {
+                let key = field.key().key_string();
+                path.push(key.to_string());
+                match b_by_key.get(&key) {
+                    Some(&b_value) => diff_node(field.value_cursor(), b_value, path, ops),
+                    None => ops.push(YamlPatchOp::Remove { path: path.clone() }),
+                }
+                path.pop();
+                a_keys.insert(key);
+            }
+            for field in b_fields {
+                let key = field.key().key_string();
+                if !a_keys.contains(&key) {
+                    path.push(key.to_string());
+                    ops.push(YamlPatchOp::Add {
+                        path: path.clone(),
+                        value: field.value_cursor().to_json(),
+                    });
+                    path.pop();
+                }
+            }
+        }
+        (YamlValue::Sequence(a_elements), YamlValue::Sequence(b_elements)) => {
+            let a_items = collect_elements(a_elements);
+            let b_items = collect_elements(b_elements);
+            diff_sequences(&a_items, &b_items, path, ops);
+        }
+        _ => {
+            let (from, to) = (a.to_json(), b.to_json());
+            if from != to {
+                ops.push(YamlPatchOp::Change {
+                    path: path.clone(),
+                    from,
+                    to,
+                });
+            }
+        }
+    }
+}
+
+/// Each element's cursor with its JSON rendering, used to test equality.
+fn collect_elements<W: AsRef<[u64]>>(
+    mut elements: YamlElements<'_, W>,
+) -> Vec<(YamlCursor<'_, W>, String)> {
+    let mut items = Vec::new();
+    while let Some((cursor, rest)) = elements.uncons_cursor() {
+        items.push((cursor, cursor.to_json()));
+        elements = rest;
+    }
+    items
+}
+
+/// Diff two sequences along their longest common subsequence.
+///
+/// Between two matched elements, removed and added elements are paired up
+/// and diffed in place, so an edited element yields its inner changes; the
+/// unpaired rest are removals or additions.
+fn diff_sequences<W: AsRef<[u64]>>(
+    a: &[(YamlCursor<'_, W>, String)],
+    b: &[(YamlCursor<'_, W>, String)],
+    path: &mut Vec<String>,
+    ops: &mut Vec<YamlPatchOp>,
+) {
+    // lcs[i][j]: LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i].1 == b[j].1 {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].1 == b[j].1 {
+            flush_gap(a, b, &removed, &added, path, ops);
+            removed.clear();
+            added.clear();
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    flush_gap(a, b, &removed, &added, path, ops);
+}
+
+/// Emit the operations for one gap between matched sequence elements.
+fn flush_gap<W: AsRef<[u64]>>(
+    a: &[(YamlCursor<'_, W>, String)],
+    b: &[(YamlCursor<'_, W>, String)],
+    removed: &[usize],
+    added: &[usize],
+    path: &mut Vec<String>,
+    ops: &mut Vec<YamlPatchOp>,
+) {
+    let paired = removed.len().min(added.len());
+    for (&i, &j) in removed.iter().zip(added) {
+        path.push(i.to_string());
+        diff_node(a[i].0, b[j].0, path, ops);
+        path.pop();
+    }
+    for &i in &removed[paired..] {
+        path.push(i.to_string());
+        ops.push(YamlPatchOp::Remove { path: path.clone() });
+        path.pop();
+    }
+    for &j in &added[paired..] {
+        path.push(j.to_string());
+        ops.push(YamlPatchOp::Add {
+            path: path.clone(),
+            value: b[j].1.clone(),
+        });
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(a: &str, b: &str) -> Vec<YamlPatchOp> {
+        YamlIndex::diff(a.as_bytes(), b.as_bytes()).unwrap()
+    }
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|&s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_identical() {
+        let yaml = "a: 1\nb:\n  - x\n  - {y: [1, 2]}\n";
+        assert!(diff(yaml, yaml).is_empty());
+        // Same structure in a different style
+        assert!(diff(yaml, "{a: 1, b: [x, {y: [1, 2]}]}").is_empty());
+    }
+
+    #[test]
+    fn test_mapping_by_key() {
+        let ops = diff(
+            "name: app\nimage: v1\nold: true\n",
+            "image: v2\nname: app\nnew: [1]\n",
+        );
+        assert_eq!(
+            ops,
+            [
+                YamlPatchOp::Change {
+                    path: path(&["image"]),
+                    from: "\"v1\"".into(),
+                    to: "\"v2\"".into()
+                },
+                YamlPatchOp::Remove {
+                    path: path(&["old"])
+                },
+                YamlPatchOp::Add {
+                    path: path(&["new"]),
+                    value: "[1]".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let ops = diff(
+            "spec:\n  containers:\n    - name: web\n      port: 80\n",
+            "spec:\n  containers:\n    - name: web\n      port: 8080\n",
+        );
+        assert_eq!(
+            ops,
+            [YamlPatchOp::Change {
+                path: path(&["spec", "containers", "0", "port"]),
+                from: "80".into(),
+                to: "8080".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sequence_lcs() {
+        // Insert in the middle, remove at the end
+        let ops = diff("[a, b, c, d]", "[a, x, b, c]");
+        assert_eq!(
+            ops,
+            [
+                YamlPatchOp::Add {
+                    path: path(&["1"]),
+                    value: "\"x\"".into()
+                },
+                YamlPatchOp::Remove { path: path(&["3"]) },
+            ]
+        );
+
+        // A replaced element is a change at its index
+        let ops = diff("[1, 2, 3]", "[1, 5, 3]");
+        assert_eq!(
+            ops,
+            [YamlPatchOp::Change {
+                path: path(&["1"]),
+                from: "2".into(),
+                to: "5".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_type_change_and_root() {
+        let ops = diff("a: [1]\n", "a: {b: 1}\n");
+        assert_eq!(
+            ops,
+            [YamlPatchOp::Change {
+                path: path(&["a"]),
+                from: "[1]".into(),
+                to: "{\"b\":1}".into()
+            }]
+        );
+        assert_eq!(diff("1", "2")[0].path(), &[] as &[String]);
+        assert_eq!(diff("1", "2")[0].to_diff_string(), "- .: 1\n+ .: 2\n");
+    }
+
+    #[test]
+    fn test_aliases_resolved() {
+        assert!(diff("base: &b {x: 1}\nuse: *b\n", "base: {x: 1}\nuse: {x: 1}\n").is_empty());
+    }
+
+    #[test]
+    fn test_multi_document() {
+        let ops = diff("a: 1\n---\nb: 2\n", "a: 1\n---\nb: 3\n");
+        assert_eq!(
+            ops,
+            [YamlPatchOp::Change {
+                path: path(&["1", "b"]),
+                from: "2".into(),
+                to: "3".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_to_diff_string() {
+        let ops = diff("a: 1\nb: 2\n", "a: 1\nc: x\n");
+        let text: String = ops.iter().map(YamlPatchOp::to_diff_string).collect();
+        assert_eq!(text, "- b\n+ c: \"x\"\n");
+    }
+
+    #[test]
+    fn test_invalid_yaml() {
+        assert!(YamlIndex::diff(b"a: [", b"a: 1").is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! Splitting a YAML stream into separately indexed documents.
+//!
+//! [`YamlIndex::build`] indexes a whole stream under one implicit root
+//! sequence. For streams of independent documents, such as a Kubernetes
+//! manifest with several resources, [`YamlIndex::build_multi`] and
+//! [`YamlIndex::build_streaming`] build one index per document instead, so
+//! each document can be processed (and dropped) on its own.
+//!
+//! Document markers are found by a line scan: `---` or `...` at the start of
+//! a line, followed by whitespace or the end of the line. YAML forbids
+//! either marker at column 0 inside a scalar, so a `---` within a quoted or
+//! block scalar is indented and never mistaken for a separator.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use core::ops::Range;
+
+use super::error::YamlError;
+use super::index::YamlIndex;
+
+/// How a line takes part in document splitting.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    /// `---`: starts a document (its rest of line belongs to it)
+    Start,
+    /// `...`: ends the current document
+    End,
+    /// Blank or a comment
+    Trivia,
+    /// Anything else
+    Content,
+}
+
+fn classify(line: &[u8]) -> Line {
+    let is_marker = |marker: &[u8]| {
+        line.starts_with(marker)
+            && matches!(
+                line.get(3).copied().unwrap_or(b'\n'),
+                b' ' | b'\t' | b'\n' | b'\r'
+            )
+    };
+    if is_marker(b"---") {
+        return Line::Start;
+    }
+    if is_marker(b"...") {
+        return Line::End;
+    }
+    match line
+        .iter()
+        .find(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+    {
+        None | Some(b'#') => Line::Trivia,
+        Some(_) => Line::Content,
+    }
+}
+
+impl YamlIndex<Vec<u64>> {
+    /// Byte ranges of the documents in a YAML stream.
+    ///
+    /// A document runs from its `---` line (or the start of the input, for a
+    /// first document without one) up to the next `---` line, or through its
+    /// `...` line. Comments and blank lines before a `---` belong to that
+    /// document. Stretches with no content, such as an empty `---` section or
+    /// a trailing comment after `...`, are not documents, just as
+    /// [`build`](Self::build) has no root element for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let yaml = b"a: 1\n---\nb: 2\n...\n# done\n";
+    /// assert_eq!(YamlIndex::document_ranges(yaml), [0..5, 5..18]);
+    /// ```
+    pub fn document_ranges(input: &[u8]) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        // Whether the pending document has content
+        let mut pending = false;
+
+        let mut pos = 0;
+        while pos < input.len() {
+            let line_end = input[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(input.len(), |i| pos + i + 1);
+            match classify(&input[pos..line_end]) {
+                Line::Start => {
+                    // Otherwise the empty stretch since `start` stays with
+                    // the document this line begins
+                    if pending {
+                        ranges.push(start..pos);
+                        start = pos;
+                    }
+                    // Content after the marker, as in `--- value`
+                    pending = classify(&input[pos + 3..line_end]) == Line::Content;
+                }
+                Line::End => {
+                    if pending {
+                        ranges.push(start..line_end);
+                    }
+                    start = line_end;
+                    pending = false;
+                }
+                Line::Content => pending = true,
+                Line::Trivia => {}
+            }
+            pos = line_end;
+        }
+        if pending {
+            ranges.push(start..input.len());
+        }
+        ranges
+    }
+
+    /// Build one index per document of a YAML stream.
+    ///
+    /// The index at position `i` is built from `&input[range]`, where `range`
+    /// is the `i`-th entry of [`document_ranges`](Self::document_ranges);
+    /// pass that slice to [`root`](Self::root). Each root is a one-element
+    /// sequence holding the document, as for [`build`](Self::build).
+    ///
+    /// # Errors
+    ///
+    /// Returns the first document's error, if any fails to build. Offsets
+    /// and line numbers in it are relative to that document's slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let yaml = b"kind: Service\n---\nkind: Deployment\n";
+    /// let indexes = YamlIndex::build_multi(yaml).unwrap();
+    /// let ranges = YamlIndex::document_ranges(yaml);
+    /// let kinds: Vec<String> = indexes
+    ///     .iter()
+    ///     .zip(ranges)
+    ///     .map(|(index, range)| index.root(&yaml[range]).to_json_document())
+    ///     .collect();
+    /// assert_eq!(kinds, [r#"{"kind":"Service"}"#, r#"{"kind":"Deployment"}"#]);
+    /// ```
+    pub fn build_multi(input: &[u8]) -> Result<Vec<Self>, YamlError> {
+        Self::build_streaming(input).collect()
+    }
+
+    /// Lazily build one index per document of a YAML stream.
+    ///
+    /// Yields the same indexes as [`build_multi`](Self::build_multi), each
+    /// built only when the iterator reaches it, so a stream can be processed
+    /// one document at a time. A document that fails to build yields its
+    /// error and iteration continues with the next document.
+    pub fn build_streaming(input: &[u8]) -> impl Iterator<Item = Result<Self, YamlError>> + '_ {
+        Self::document_ranges(input)
+            .into_iter()
+            .map(move |range| Self::build(&input[range]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each document's JSON, built separately.
+    fn documents(yaml: &[u8]) -> Vec<String> {
+        let indexes = YamlIndex::build_multi(yaml).unwrap();
+        let ranges = YamlIndex::document_ranges(yaml);
+        assert_eq!(indexes.len(), ranges.len());
+        indexes
+            .iter()
+            .zip(ranges)
+            .map(|(index, range)| index.root(&yaml[range]).to_json_document())
+            .collect()
+    }
+
+    #[test]
+    fn test_kubernetes_manifest() {
+        let yaml = b"\
+# app.yaml
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 3
+---
+# an empty resource, skipped like kubectl does
+---
+apiVersion: v1
+kind: ConfigMap
+data:
+  banner: |
+    --- not a marker
+    ...
+";
+        let docs = documents(yaml);
+        assert_eq!(docs.len(), 3);
+        assert_eq!(
+            docs[0],
+            r#"{"apiVersion":"v1","kind":"Service","metadata":{"name":"web"}}"#
+        );
+        assert_eq!(
+            docs[1],
+            r#"{"apiVersion":"apps/v1","kind":"Deployment","metadata":{"name":"web"},"spec":{"replicas":3}}"#
+        );
+        assert_eq!(
+            docs[2],
+            r#"{"apiVersion":"v1","kind":"ConfigMap","data":{"banner":"--- not a marker\n...\n"}}"#
+        );
+    }
+
+    #[test]
+    fn test_first_document_without_marker() {
+        assert_eq!(
+            documents(b"a: 1\n---\nb: 2\n"),
+            [r#"{"a":1}"#, r#"{"b":2}"#]
+        );
+        assert_eq!(
+            YamlIndex::document_ranges(b"a: 1\n---\nb: 2\n"),
+            [0..5, 5..14]
+        );
+
+        // Leading comments belong to the first document
+        let yaml = b"# c\n\n---\nx: 1\n";
+        assert_eq!(YamlIndex::document_ranges(yaml), vec![0..yaml.len()]);
+        assert_eq!(documents(yaml), [r#"{"x":1}"#]);
+    }
+
+    #[test]
+    fn test_markers_inside_strings() {
+        let yaml = b"a: \"x\n  --- y\"\nb: '---'\nc: ---word\n---word: 1\n- ---\n";
+        assert_eq!(YamlIndex::document_ranges(yaml), vec![0..yaml.len()]);
+
+        let yaml = b"a: \"--- \"\n--- \"...\"\n";
+        assert_eq!(documents(yaml), [r#"{"a":"--- "}"#, r#""...""#]);
+    }
+
+    #[test]
+    fn test_document_end_marker() {
+        let yaml = b"a: 1\n...\n# trailing comment\n";
+        assert_eq!(YamlIndex::document_ranges(yaml), vec![0..9]);
+        assert_eq!(documents(yaml), [r#"{"a":1}"#]);
+
+        // `...` then a bare document, then `---` on the same line as content
+        let yaml = b"a: 1\n...\nb: 2\n--- c\n";
+        assert_eq!(documents(yaml), [r#"{"a":1}"#, r#"{"b":2}"#, r#""c""#]);
+    }
+
+    #[test]
+    fn test_empty_streams() {
+        assert!(YamlIndex::build_multi(b"").unwrap().is_empty());
+        assert!(YamlIndex::build_multi(b"# only a comment\n\n")
+            .unwrap()
+            .is_empty());
+        assert!(YamlIndex::build_multi(b"---\n---\n# c\n...\n")
+            .unwrap()
+            .is_empty());
+        assert_eq!(YamlIndex::document_ranges(b"---\n---\nx\n"), vec![0..10]);
+    }
+
+    #[test]
+    fn test_streaming_is_lazy_per_document() {
+        let yaml = b"a: 1\n---\nb: \"unclosed\n---\nc: 3\n";
+        let mut docs = YamlIndex::build_streaming(yaml);
+        assert!(docs.next().unwrap().is_ok());
+        assert!(matches!(
+            docs.next().unwrap(),
+            Err(YamlError::UnclosedQuote { .. })
+        ));
+        assert!(docs.next().unwrap().is_ok());
+        assert!(docs.next().is_none());
+
+        assert!(YamlIndex::build_multi(yaml).is_err());
+    }
+}
@@ -155,6 +155,73 @@ pub enum YamlError {
     },
 }
 
+impl YamlError {
+    /// Byte offset in the input where the error was detected, if known.
+    ///
+    /// [`InvalidIndentation`](Self::InvalidIndentation) only records a line
+    /// and [`UnexpectedEof`](Self::UnexpectedEof) nothing; see
+    /// [`offset_in`](Self::offset_in).
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::TabIndentation { offset, .. }
+            | Self::UnexpectedCharacter { offset, .. }
+            | Self::InvalidEscape { offset, .. }
+            | Self::InvalidUtf8 { offset }
+            | Self::InvalidAnchorName { offset, .. }
+            | Self::DuplicateAnchor { offset, .. }
+            | Self::AliasCycle { offset, .. }
+            | Self::ExplicitKeyNotSupported { offset }
+            | Self::TagNotSupported { offset }
+            | Self::ColonWithoutSpace { offset }
+            | Self::KeyWithoutValue { offset, .. }
+            | Self::NestingTooDeep { offset, .. } => Some(*offset),
+            Self::UnclosedQuote { start_offset, .. } => Some(*start_offset),
+            #[allow(deprecated)]
+            Self::MultiDocumentNotSupported { offset }
+            | Self::FlowStyleNotSupported { offset, .. } => Some(*offset),
+            Self::InvalidIndentation { .. }
+            | Self::EmptyInput
+            | Self::UnexpectedEof { .. }
+            | Self::InputTooLarge { .. } => None,
+        }
+    }
+
+    /// Byte offset of the error in `input`, the text that failed to parse.
+    ///
+    /// Like [`offset`](Self::offset), but also locates
+    /// [`UnexpectedEof`](Self::UnexpectedEof) at the end of `input` and
+    /// [`InvalidIndentation`](Self::InvalidIndentation) at the start of its
+    /// line.
+    pub fn offset_in(&self, input: &[u8]) -> Option<usize> {
+        match self {
+            Self::UnexpectedEof { .. } => Some(input.len()),
+            Self::InvalidIndentation { line, .. } => match line.checked_sub(1)? {
+                0 => Some(0),
+                n => input
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &b)| b == b'\n')
+                    .nth(n - 1)
+                    .map(|(i, _)| i + 1),
+            },
+            _ => self.offset(),
+        }
+    }
+
+    /// Line number (1-indexed) recorded with the error, if any.
+    ///
+    /// Errors that only carry an [`offset`](Self::offset) return `None`; the
+    /// line can be recovered from the input text.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::InvalidIndentation { line, .. }
+            | Self::TabIndentation { line, .. }
+            | Self::KeyWithoutValue { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for YamlError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -273,6 +340,94 @@ impl fmt::Display for YamlError {
 #[cfg(feature = "std")]
 impl std::error::Error for YamlError {}
 
+/// Errors from converting YAML to JSON with
+/// [`YamlIndex::to_json`](super::YamlIndex::to_json).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The input could not be indexed.
+    Yaml(YamlError),
+
+    /// A value could not be decoded, such as a double-quoted scalar with an
+    /// invalid escape sequence.
+    InvalidValue {
+        /// Byte offset of the value
+        offset: usize,
+        /// Why it could not be decoded
+        reason: &'static str,
+    },
+
+    /// An alias names an anchor that is not defined before it.
+    UnknownAlias {
+        /// Byte offset of the alias
+        offset: usize,
+        /// The anchor name
+        name: String,
+    },
+
+    /// A mapping key is itself a mapping or sequence, which has no JSON
+    /// object key.
+    ComplexKey {
+        /// Byte offset of the key
+        offset: usize,
+    },
+}
+
+impl From<YamlError> for ConversionError {
+    fn from(err: YamlError) -> Self {
+        Self::Yaml(err)
+    }
+}
+
+impl ConversionError {
+    /// Byte offset in the input where the error was detected, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Yaml(err) => err.offset(),
+            Self::InvalidValue { offset, .. }
+            | Self::UnknownAlias { offset, .. }
+            | Self::ComplexKey { offset } => Some(*offset),
+        }
+    }
+
+    /// Byte offset of the error in `input`; see [`YamlError::offset_in`].
+    pub fn offset_in(&self, input: &[u8]) -> Option<usize> {
+        match self {
+            Self::Yaml(err) => err.offset_in(input),
+            _ => self.offset(),
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Yaml(err) => err.fmt(f),
+            Self::InvalidValue { offset, reason } => {
+                write!(f, "cannot convert value at offset {offset}: {reason}")
+            }
+            Self::UnknownAlias { offset, name } => {
+                write!(f, "unknown anchor '{name}' for alias at offset {offset}")
+            }
+            Self::ComplexKey { offset } => {
+                write!(
+                    f,
+                    "cannot convert key at offset {offset}: JSON keys must be scalars"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConversionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Yaml(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -464,4 +619,38 @@ mod tests {
         assert_eq!(err.clone(), err);
         assert_ne!(err, YamlError::EmptyInput);
     }
+
+    #[test]
+    fn test_error_location() {
+        let err = YamlError::UnclosedQuote {
+            start_offset: 7,
+            quote_type: '"',
+        };
+        assert_eq!(err.offset(), Some(7));
+        assert_eq!(err.line(), None);
+
+        let err = YamlError::KeyWithoutValue { offset: 5, line: 2 };
+        assert_eq!((err.offset(), err.line()), (Some(5), Some(2)));
+
+        let err = YamlError::InvalidIndentation {
+            line: 3,
+            expected: 2,
+            found: 4,
+        };
+        assert_eq!((err.offset(), err.line()), (None, Some(3)));
+        assert_eq!(err.offset_in(b"a:\n  b: 1\n    c: 2\n"), Some(10));
+        assert_eq!(err.offset_in(b"a:\n"), None);
+        assert_eq!(YamlError::EmptyInput.offset(), None);
+
+        let err = YamlError::UnexpectedEof { context: "flow" };
+        assert_eq!((err.offset(), err.offset_in(b"[1, 2")), (None, Some(5)));
+        assert_eq!(ConversionError::from(err).offset_in(b"[1"), Some(2));
+
+        let err = ConversionError::UnknownAlias {
+            offset: 9,
+            name: "a".into(),
+        };
+        assert_eq!(err.offset(), Some(9));
+        assert_eq!(ConversionError::from(YamlError::EmptyInput).offset(), None);
+    }
 }
@@ -17,12 +17,43 @@ use core::cell::OnceCell;
 
 use crate::trees::{BalancedParens, WithSelect};
 use crate::util::broadword::select_in_word;
+use crate::Config;
 
 use super::advance_positions::{build_cumulative_rank, OpenPositions};
 use super::end_positions::EndPositions;
 use super::error::YamlError;
 use super::light::YamlCursor;
 use super::parser::build_semi_index;
+use super::scalar::{resolve_plain, ResolvedScalar};
+
+/// Kind of a YAML node, as recorded by the structural index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YamlNodeType {
+    /// A block or flow mapping.
+    Mapping,
+    /// A block or flow sequence.
+    Sequence,
+    /// Any scalar, including an empty document (an implicit `null`).
+    Scalar,
+}
+
+/// Summary statistics for a YAML stream.
+///
+/// Produced by [`YamlIndex::stats`]. Counts cover values only: mapping keys
+/// are not counted as nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct YamlStats {
+    /// Number of values: mappings, sequences, scalars and aliases
+    pub nodes: usize,
+    /// Number of mappings and sequences
+    pub containers: usize,
+    /// Maximum container nesting depth (a scalar document has depth 0, `[]` has depth 1)
+    pub max_depth: usize,
+    /// Number of anchor definitions (`&name`)
+    pub anchors: usize,
+    /// Number of aliases (`*name`)
+    pub aliases: usize,
+}
 
 /// Index structures for navigating YAML.
 ///
@@ -71,6 +102,15 @@ pub struct YamlIndex<W = Vec<u64>> {
     /// Bit i is set if position i is the start of a new line (immediately after a line terminator).
     /// Only needed by `to_line_column()` and `to_offset()` (used by `yq-locate` CLI).
     newlines: OnceCell<crate::bits::BitVec>,
+    /// Flattened fields of each mapping with a merge key (`<<`), keyed by
+    /// the BP position of its first key (built lazily on first use)
+    merged_fields: OnceCell<BTreeMap<usize, Vec<(usize, usize)>>>,
+    /// Whether `YamlCursor::value()` resolves plain scalars to `Null`,
+    /// `Bool`, `Int` and `Float` (on by default)
+    type_inference: bool,
+    /// Whether type inference also reads the YAML 1.1 booleans `yes`, `no`,
+    /// `on` and `off` (off by default)
+    yaml11_booleans: bool,
 }
 
 /// Build cumulative popcount index for IB.
@@ -142,6 +182,20 @@ impl YamlIndex<Vec<u64>> {
     /// (just under 4 GiB): the semi-index stores text positions as `u32`
     /// (#188). Other variants report malformed YAML.
     pub fn build(yaml: &[u8]) -> Result<Self, YamlError> {
+        Self::build_with_config(yaml, Config::default())
+    }
+
+    /// Build a YAML index with custom configuration.
+    ///
+    /// [`Config::bp_select_sample_rate`] sets how often the balanced
+    /// parentheses sample an open for select1, which backs offset-to-node
+    /// lookups such as [`find_bp_at_text_pos`](Self::find_bp_at_text_pos).
+    /// Other fields do not apply to YAML indexes.
+    ///
+    /// # Errors
+    ///
+    /// As for [`build`](Self::build).
+    pub fn build_with_config(yaml: &[u8], config: Config) -> Result<Self, YamlError> {
         let semi = build_semi_index(yaml)?;
 
         let ib_len = yaml.len();
@@ -162,7 +216,11 @@ impl YamlIndex<Vec<u64>> {
             ib: semi.ib,
             ib_len,
             ib_rank,
-            bp: BalancedParens::new_with_select(semi.bp, semi.bp_len),
+            bp: BalancedParens::new_with_select_sample_rate(
+                semi.bp,
+                semi.bp_len,
+                config.bp_select_sample_rate,
+            ),
             ty: semi.ty,
             ty_len: semi.ty_len,
             open_positions,
@@ -173,6 +231,9 @@ impl YamlIndex<Vec<u64>> {
             bp_to_anchor,
             aliases: semi.aliases,
             newlines: OnceCell::new(),
+            merged_fields: OnceCell::new(),
+            type_inference: true,
+            yaml11_booleans: false,
         };
         index.validate_alias_acyclicity()?;
         Ok(index)
@@ -224,6 +285,9 @@ impl<W: AsRef<[u64]>> YamlIndex<W> {
             bp_to_anchor,
             aliases,
             newlines: OnceCell::new(),
+            merged_fields: OnceCell::new(),
+            type_inference: true,
+            yaml11_booleans: false,
         }
     }
 
@@ -272,9 +336,78 @@ impl<W: AsRef<[u64]>> YamlIndex<W> {
             bp_to_anchor,
             aliases,
             newlines: OnceCell::from(newlines),
+            merged_fields: OnceCell::new(),
+            type_inference: true,
+            yaml11_booleans: false,
         }
     }
 
+    /// Set whether [`YamlCursor::value`] infers the types of plain scalars.
+    ///
+    /// With inference on (the default), plain scalars resolve by the YAML 1.2
+    /// core schema (see [`resolve_plain`](super::resolve_plain)) to
+    /// [`YamlValue::Null`](super::YamlValue::Null), `Bool`, `Int` or `Float`.
+    /// Turn it off to get every scalar as a [`YamlValue::String`](super::YamlValue::String).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::{YamlIndex, YamlValue};
+    ///
+    /// let yaml = b"8080";
+    /// let index = YamlIndex::build(yaml).unwrap();
+    /// let port = index.root(yaml).first_child().unwrap();
+    /// assert!(matches!(port.value(), YamlValue::Int(8080)));
+    ///
+    /// let index = index.with_type_inference(false);
+    /// let port = index.root(yaml).first_child().unwrap();
+    /// assert!(matches!(port.value(), YamlValue::String(_)));
+    /// ```
+    pub fn with_type_inference(mut self, enabled: bool) -> Self {
+        self.type_inference = enabled;
+        self
+    }
+
+    /// Whether [`YamlCursor::value`] infers the types of plain scalars.
+    #[inline]
+    pub fn type_inference(&self) -> bool {
+        self.type_inference
+    }
+
+    /// Set whether type inference also resolves the YAML 1.1 booleans
+    /// `yes`/`no` and `on`/`off` (see
+    /// [`resolve_yaml11_bool`](super::resolve_yaml11_bool)).
+    ///
+    /// Off by default, as the YAML 1.2 core schema keeps them strings (so
+    /// `country: NO` stays text). Documents written for YAML 1.1 tools,
+    /// such as older configuration files, may need it. It has no effect
+    /// with [`with_type_inference(false)`](Self::with_type_inference).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::{YamlIndex, YamlValue};
+    ///
+    /// let yaml = b"on";
+    /// let index = YamlIndex::build(yaml).unwrap();
+    /// let enabled = index.root(yaml).first_child().unwrap();
+    /// assert!(matches!(enabled.value(), YamlValue::String(_)));
+    ///
+    /// let index = index.with_yaml11_booleans(true);
+    /// let enabled = index.root(yaml).first_child().unwrap();
+    /// assert!(matches!(enabled.value(), YamlValue::Bool(true)));
+    /// ```
+    pub fn with_yaml11_booleans(mut self, enabled: bool) -> Self {
+        self.yaml11_booleans = enabled;
+        self
+    }
+
+    /// Whether type inference resolves the YAML 1.1 booleans.
+    #[inline]
+    pub fn yaml11_booleans(&self) -> bool {
+        self.yaml11_booleans
+    }
+
     /// Get the text byte offset for a BP position.
     ///
     /// The BP position must be at an open parenthesis (1-bit).
@@ -357,6 +490,144 @@ impl<W: AsRef<[u64]>> YamlIndex<W> {
         YamlCursor::new(self, text, 0)
     }
 
+    /// BP position of the first document's node, if the stream has one.
+    #[inline]
+    fn first_document_bp(&self) -> Option<usize> {
+        self.bp.first_child(0)
+    }
+
+    /// Kind of the first document's root node.
+    ///
+    /// Reads the BP and TY bits at the first document, so unlike
+    /// [`root`](Self::root) it needs neither the text nor a cursor. A stream
+    /// with no document (empty, comments only, or a bare `---`) is an
+    /// implicit `null` and reports [`YamlNodeType::Scalar`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::{YamlIndex, YamlNodeType};
+    ///
+    /// let index = YamlIndex::build(b"---\n- a\n- b\n").unwrap();
+    /// assert_eq!(index.root_type(), YamlNodeType::Sequence);
+    /// ```
+    pub fn root_type(&self) -> YamlNodeType {
+        match self.first_document_bp() {
+            Some(bp_pos) if self.is_container(bp_pos) => {
+                if self.is_sequence_at_bp(bp_pos) {
+                    YamlNodeType::Sequence
+                } else {
+                    YamlNodeType::Mapping
+                }
+            }
+            _ => YamlNodeType::Scalar,
+        }
+    }
+
+    /// Check if the first document is empty.
+    ///
+    /// True when the stream has no document, when the document is a plain
+    /// `null` (`null`, `Null`, `NULL`, or `~`), or when it is a mapping or
+    /// sequence with no entries. Quoted and block scalars are never empty.
+    /// `text` is the YAML the index was built from, needed to tell a `null`
+    /// from other scalars.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// for yaml in [&b"---\n"[..], b"~", b"{}", b"[]"] {
+    ///     assert!(YamlIndex::build(yaml).unwrap().is_empty(yaml));
+    /// }
+    /// let yaml = b"a: 1";
+    /// assert!(!YamlIndex::build(yaml).unwrap().is_empty(yaml));
+    /// ```
+    pub fn is_empty(&self, text: &[u8]) -> bool {
+        let Some(bp_pos) = self.first_document_bp() else {
+            return true;
+        };
+        if self.is_container(bp_pos) {
+            return self.bp.first_child(bp_pos).is_none();
+        }
+        self.cursor_at(bp_pos, text)
+            .raw_bytes()
+            .and_then(|raw| core::str::from_utf8(raw).ok())
+            .map_or(true, |raw| {
+                matches!(resolve_plain(raw), ResolvedScalar::Null)
+            })
+    }
+
+    /// Compute summary statistics for the stream in a single DFS pass.
+    ///
+    /// Like [`JsonIndex::stats`](crate::json::JsonIndex::stats) the
+    /// traversal uses an explicit stack and decodes nothing. Every document
+    /// is visited. Block sequence items are counted once, not once for the
+    /// `- ` marker and again for the value.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The original YAML text (must match the text used to build the index)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let yaml = b"base: &b {x: 1}\nitems:\n  - *b\n  - [2, 3]\n";
+    /// let stats = YamlIndex::build(yaml).unwrap().stats(yaml);
+    /// assert_eq!(stats.nodes, 8);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!((stats.anchors, stats.aliases), (1, 1));
+    /// ```
+    pub fn stats(&self, text: &[u8]) -> YamlStats {
+        let mut stats = YamlStats {
+            anchors: self.anchors.len(),
+            aliases: self.aliases.len(),
+            ..YamlStats::default()
+        };
+        // (BP position, number of containers enclosing the node)
+        let mut stack = Vec::new();
+        let mut next_document = self.first_document_bp();
+        while let Some(bp_pos) = next_document {
+            stack.push((bp_pos, 0usize));
+            next_document = self.bp.next_sibling(bp_pos);
+        }
+
+        while let Some((bp_pos, depth)) = stack.pop() {
+            stats.nodes += 1;
+            if !self.is_container(bp_pos) {
+                continue;
+            }
+            stats.containers += 1;
+            stats.max_depth = stats.max_depth.max(depth + 1);
+
+            let is_sequence = self.is_sequence_at_bp(bp_pos);
+            let mut next_child = self.bp.first_child(bp_pos);
+            while let Some(child) = next_child {
+                if is_sequence {
+                    // A block item's `- ` node wraps its value (absent for `-` alone)
+                    let item = if self.is_seq_item(text, child) {
+                        self.bp.first_child(child).unwrap_or(child)
+                    } else {
+                        child
+                    };
+                    stack.push((item, depth + 1));
+                    next_child = self.bp.next_sibling(child);
+                } else {
+                    // Mapping children alternate key, value: only visit values
+                    let Some(value) = self.bp.next_sibling(child) else {
+                        break;
+                    };
+                    stack.push((value, depth + 1));
+                    next_child = self.bp.next_sibling(value);
+                }
+            }
+        }
+
+        stats
+    }
+
     /// Check if the container at the given TY index is a sequence.
     ///
     /// Returns `true` for sequence, `false` for mapping.
@@ -586,6 +857,26 @@ impl<W: AsRef<[u64]>> YamlIndex<W> {
         Ok(())
     }
 
+    /// Fields of the mapping whose first key is at `first_key_bp`, with its
+    /// merge keys applied, or `None` if it has no merge key.
+    ///
+    /// The first call with aliases in the index scans `text` once for merge
+    /// keys; a merged value must be an alias, so without aliases there is
+    /// nothing to look up.
+    pub(crate) fn merged_fields(
+        &self,
+        text: &[u8],
+        first_key_bp: usize,
+    ) -> Option<&[(usize, usize)]> {
+        if self.aliases.is_empty() {
+            return None;
+        }
+        self.merged_fields
+            .get_or_init(|| super::merge::merge_table(self, text))
+            .get(&first_key_bp)
+            .map(Vec::as_slice)
+    }
+
     /// Create a cursor at the given BP position.
     ///
     /// This is useful for navigating to a specific position in the index.
@@ -870,6 +1161,101 @@ mod tests {
         assert!(index.is_ok());
     }
 
+    #[test]
+    fn test_root_type() {
+        let cases: &[(&[u8], YamlNodeType)] = &[
+            (b"a: 1", YamlNodeType::Mapping),
+            (b"{}", YamlNodeType::Mapping),
+            (b"--- &x {k: v}", YamlNodeType::Mapping),
+            (b"? a\n: b", YamlNodeType::Mapping),
+            (b"- 1\n- 2", YamlNodeType::Sequence),
+            (b"---\n[]", YamlNodeType::Sequence),
+            (b"foo", YamlNodeType::Scalar),
+            (b"\"q\"", YamlNodeType::Scalar),
+            (b"|\n  text\n", YamlNodeType::Scalar),
+            (b"---\n", YamlNodeType::Scalar),
+            (b"# only a comment\n", YamlNodeType::Scalar),
+            // Only the first document counts
+            (b"- a\n---\nb: 1", YamlNodeType::Sequence),
+        ];
+        for &(yaml, expected) in cases {
+            let index = YamlIndex::build(yaml).unwrap();
+            assert_eq!(
+                index.root_type(),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(yaml)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let empty: &[&[u8]] = &[
+            b"---\n",
+            b"---\n...\n",
+            b"# comment\n",
+            b"null",
+            b"--- ~\n",
+            b"NULL # comment",
+            b"{}",
+            b"[ ]",
+            b"--- {}\n--- a: 1",
+        ];
+        for &yaml in empty {
+            let index = YamlIndex::build(yaml).unwrap();
+            assert!(index.is_empty(yaml), "{:?}", String::from_utf8_lossy(yaml));
+        }
+
+        let non_empty: &[&[u8]] = &[
+            b"a: 1",
+            b"- ~",
+            b"[null]",
+            b"nullx",
+            b"\"null\"",
+            b"'~'",
+            b"0",
+            b"|\n  null\n",
+        ];
+        for &yaml in non_empty {
+            let index = YamlIndex::build(yaml).unwrap();
+            assert!(!index.is_empty(yaml), "{:?}", String::from_utf8_lossy(yaml));
+        }
+    }
+
+    #[test]
+    fn test_build_with_config_bp_select_sample_rate() {
+        let yaml: Vec<u8> = (0..2_000)
+            .flat_map(|i| format!("- k{i}: [{i}, x]\n").into_bytes())
+            .collect();
+        let build = |rate| {
+            let config = Config::builder().bp_select_sample_rate(rate).build();
+            YamlIndex::build_with_config(&yaml, config).unwrap()
+        };
+        let dense = build(1);
+        let default = YamlIndex::build(&yaml).unwrap();
+        let sparse = build(4096);
+
+        // Denser sampling uses more memory
+        let dense_size = dense.bp().select_heap_size();
+        let default_size = default.bp().select_heap_size();
+        assert!(
+            dense_size > 100 * default_size,
+            "{dense_size} vs {default_size}"
+        );
+        assert!(default_size > sparse.bp().select_heap_size());
+
+        // ...but every rate answers the same
+        let mut found = 0;
+        for pos in (0..yaml.len()).step_by(7) {
+            let expected = default.find_bp_at_text_pos(pos);
+            found += usize::from(expected.is_some());
+            assert_eq!(dense.find_bp_at_text_pos(pos), expected, "pos={pos}");
+            assert_eq!(sparse.find_bp_at_text_pos(pos), expected, "pos={pos}");
+        }
+        assert!(found > 1_000, "{found}");
+    }
+
     // ------------------------------------------------------------------------
     // Alias cycle validation tests (#153)
     // ------------------------------------------------------------------------
@@ -1115,4 +1501,25 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_stats() {
+        let stats = |yaml: &[u8]| YamlIndex::build(yaml).unwrap().stats(yaml);
+
+        assert_eq!(
+            stats(b"x"),
+            YamlStats {
+                nodes: 1,
+                ..YamlStats::default()
+            }
+        );
+        // Block items count once; an empty item is a null value
+        let seq = stats(b"- a\n-\n- [1, 2]\n");
+        assert_eq!((seq.nodes, seq.containers, seq.max_depth), (6, 2, 2));
+        // Keys are not nodes; every document is visited
+        let docs = stats(b"a: 1\n---\nb: {c: 2}\n");
+        assert_eq!((docs.nodes, docs.containers, docs.max_depth), (5, 3, 2));
+        let refs = stats(b"a: &x 1\nb: *x\nc: *x\n");
+        assert_eq!((refs.nodes, refs.anchors, refs.aliases), (4, 1, 2));
+    }
 }
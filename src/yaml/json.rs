@@ -0,0 +1,293 @@
+//! Converting YAML to JSON in one call.
+//!
+//! [`YamlCursor::to_json_document`] is the lenient, streaming conversion used
+//! by `yq`: a value it cannot decode becomes `null`. [`YamlIndex::to_json`]
+//! is the strict counterpart for library use. It builds the index, decodes
+//! every scalar and reports the first value it cannot convert, so its output
+//! is always valid JSON.
+
+#[cfg(not(test))]
+use alloc::vec::Vec;
+
+use indexmap::IndexMap;
+
+use super::error::ConversionError;
+use super::index::YamlIndex;
+use super::light::{YamlCursor, YamlStringError, YamlValue};
+use super::scalar::{resolve_plain, ResolvedScalar};
+use crate::jq::OwnedValue;
+use crate::json::format::serialize;
+
+impl YamlIndex<Vec<u64>> {
+    /// Convert a YAML document to compact JSON.
+    ///
+    /// Mappings become objects and sequences arrays. Plain scalars resolve
+    /// by the YAML 1.2 core schema to `null`, booleans and numbers; quoted
+    /// and block scalars are strings, so `"true"` stays a string. Keys are
+    /// always strings: `1: one` gives `{"1":"one"}`. If a key repeats, the
+    /// last value wins. Aliases are replaced by a copy of their anchored value, and
+    /// `.inf` and `.nan`, which JSON cannot represent, become `null`.
+    ///
+    /// A stream with one document converts to that document; with several
+    /// (or none), to an array of them, as `yq` prints it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConversionError::Yaml`] if the input cannot be indexed,
+    /// [`ConversionError::InvalidValue`] for a scalar that cannot be decoded
+    /// (an invalid escape or invalid UTF-8),
+    /// [`ConversionError::UnknownAlias`] for an alias without an anchor, and
+    /// [`ConversionError::ComplexKey`] for a key that is a mapping or
+    /// sequence, which [`YamlCursor::to_json_document`] turns into `""`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let yaml = b"name: web\nports: [80, 443]\n1: \"true\"\n";
+    /// assert_eq!(
+    ///     YamlIndex::to_json(yaml).unwrap(),
+    ///     br#"{"name":"web","ports":[80,443],"1":"true"}"#
+    /// );
+    /// ```
+    pub fn to_json(yaml: &[u8]) -> Result<Vec<u8>, ConversionError> {
+        Self::to_json_pretty(yaml, 0)
+    }
+
+    /// Convert a YAML document to JSON indented by `indent` spaces per level.
+    ///
+    /// The values are those of [`to_json`](Self::to_json), laid out as
+    /// `jq --indent` prints them; an `indent` of 0 is compact.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`to_json`](Self::to_json).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let json = YamlIndex::to_json_pretty(b"a: [1, x]\n", 2).unwrap();
+    /// assert_eq!(json, b"{\n  \"a\": [\n    1,\n    \"x\"\n  ]\n}");
+    /// ```
+    pub fn to_json_pretty(yaml: &[u8], indent: usize) -> Result<Vec<u8>, ConversionError> {
+        let index = Self::build(yaml)?;
+        let mut documents = Vec::new();
+        for document in index.root(yaml).children() {
+            documents.push(convert(document)?);
+        }
+        let value = if documents.len() == 1 {
+            documents.pop().unwrap_or(OwnedValue::Null)
+        } else {
+            OwnedValue::Array(documents)
+        };
+        Ok(serialize(&value, indent, false).into_bytes())
+    }
+}
+
+/// A float as JSON has it: `.inf` and `.nan` have no JSON number, so they
+/// become `null`.
+fn json_float(f: f64) -> OwnedValue {
+    if f.is_finite() {
+        OwnedValue::Float(f)
+    } else {
+        OwnedValue::Null
+    }
+}
+
+/// Convert the value at `cursor`, decoding every scalar.
+fn convert<W: AsRef<[u64]>>(cursor: YamlCursor<'_, W>) -> Result<OwnedValue, ConversionError> {
+    let offset = cursor.text_position().unwrap_or(0);
+    let invalid = |reason| ConversionError::InvalidValue { offset, reason };
+    Ok(match cursor.raw_value() {
+        YamlValue::Null => OwnedValue::Null,
+        YamlValue::Bool(b) => OwnedValue::Bool(b),
+        YamlValue::Int(n) => OwnedValue::Int(n),
+        YamlValue::Float(f) => json_float(f),
+        YamlValue::String(s) => {
+            let text = s.as_str().map_err(|e| invalid(string_error(e)))?;
+            if !s.is_unquoted() {
+                return Ok(OwnedValue::String(text.into_owned()));
+            }
+            match resolve_plain(&text) {
+                ResolvedScalar::Null => OwnedValue::Null,
+                ResolvedScalar::Bool(b) => OwnedValue::Bool(b),
+                ResolvedScalar::Int(n) => OwnedValue::Int(n),
+                ResolvedScalar::Float(f) => json_float(f),
+                ResolvedScalar::Str => OwnedValue::String(text.into_owned()),
+            }
+        }
+        YamlValue::Mapping(fields) => {
+            let mut object = IndexMap::new();
+            for field in fields {
+                let key_offset = field.key_cursor().text_position().unwrap_or(offset);
+                let key = match field.key() {
+                    YamlValue::String(s) => {
+                        s.as_str().map_err(|e| ConversionError::InvalidValue {
+                            offset: key_offset,
+                            reason: string_error(e),
+                        })?
+                    }
+                    key if is_complex_key(&key) => {
+                        return Err(ConversionError::ComplexKey { offset: key_offset })
+                    }
+                    // Other keys stringify as for `yq` (#222)
+                    other => other.key_string(),
+                };
+                object.insert(key.into_owned(), convert(field.value_cursor())?);
+            }
+            OwnedValue::Object(object)
+        }
+        YamlValue::Sequence(mut elements) => {
+            let mut array = Vec::new();
+            while let Some((element, rest)) = elements.uncons_cursor() {
+                array.push(convert(element)?);
+                elements = rest;
+            }
+            OwnedValue::Array(array)
+        }
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => convert(target)?,
+        YamlValue::Alias {
+            anchor_name,
+            target: None,
+        } => {
+            return Err(ConversionError::UnknownAlias {
+                offset,
+                name: anchor_name.into(),
+            })
+        }
+        YamlValue::Error(reason) => return Err(invalid(reason)),
+    })
+}
+
+/// Whether a key is a mapping or sequence, directly or through an alias.
+fn is_complex_key<W: AsRef<[u64]>>(key: &YamlValue<'_, W>) -> bool {
+    match key {
+        YamlValue::Mapping(_) | YamlValue::Sequence(_) => true,
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => matches!(
+            target.raw_value(),
+            YamlValue::Mapping(_) | YamlValue::Sequence(_)
+        ),
+        _ => false,
+    }
+}
+
+fn string_error(err: YamlStringError) -> &'static str {
+    match err {
+        YamlStringError::InvalidUtf8 => "invalid UTF-8",
+        YamlStringError::InvalidEscape => "invalid escape sequence",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::validate::validate;
+    use crate::yaml::YamlError;
+
+    fn json(yaml: &[u8]) -> String {
+        let json = YamlIndex::to_json(yaml).unwrap();
+        validate(&json).unwrap();
+        String::from_utf8(json).unwrap()
+    }
+
+    #[test]
+    fn test_to_json() {
+        let yaml = b"\
+name: \"web \\u00e9\\t\"
+replicas: 3
+ratio: 0.5
+enabled: yes
+debug: false
+owner: ~
+quoted: 'null'
+inf: .inf
+note: |
+  line \"one\"
+  two
+tags: [a, 'b c', 0x10]
+";
+        assert_eq!(
+            json(yaml),
+            r#"{"name":"web é\t","replicas":3,"ratio":0.5,"enabled":"yes","debug":false,"owner":null,"quoted":"null","inf":null,"note":"line \"one\"\ntwo\n","tags":["a","b c",16]}"#
+        );
+    }
+
+    #[test]
+    fn test_keys_are_strings() {
+        assert_eq!(
+            json(b"1: a\ntrue: b\nnull: c\n\"x\\ty\": d\n"),
+            r#"{"1":"a","true":"b","null":"c","x\ty":"d"}"#
+        );
+        assert_eq!(json(b"k: 1\nk: 2\n"), r#"{"k":2}"#);
+        assert_eq!(json(b"base: &b x\n*b : v\n"), r#"{"base":"x","x":"v"}"#);
+    }
+
+    #[test]
+    fn test_aliases_and_documents() {
+        assert_eq!(
+            json(b"base: &b {x: 1}\ncopy: *b\n"),
+            r#"{"base":{"x":1},"copy":{"x":1}}"#
+        );
+        assert_eq!(json(b"a: x\n---\n- 1\n"), r#"[{"a":"x"},[1]]"#);
+        assert_eq!(json(b"# nothing\n"), "[]");
+    }
+
+    #[test]
+    fn test_to_json_pretty() {
+        let pretty = YamlIndex::to_json_pretty(b"a:\n  b: [1, {}]\nc: []\n", 4).unwrap();
+        validate(&pretty).unwrap();
+        assert_eq!(
+            String::from_utf8(pretty).unwrap(),
+            "{\n    \"a\": {\n        \"b\": [\n            1,\n            {}\n        ]\n    },\n    \"c\": []\n}"
+        );
+        assert_eq!(
+            YamlIndex::to_json_pretty(b"a: 1", 0).unwrap(),
+            YamlIndex::to_json(b"a: 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            YamlIndex::to_json(b""),
+            Err(ConversionError::Yaml(YamlError::EmptyInput))
+        );
+        assert_eq!(
+            YamlIndex::to_json(b"a: \"\\q\"\n"),
+            Err(ConversionError::InvalidValue {
+                offset: 3,
+                reason: "invalid escape sequence"
+            })
+        );
+        assert_eq!(
+            YamlIndex::to_json(b"a: 1\nb: *nope\n"),
+            Err(ConversionError::UnknownAlias {
+                offset: 8,
+                name: "nope".into()
+            })
+        );
+
+        // Complex keys would all become "" and collide
+        assert_eq!(
+            YamlIndex::to_json(b"? [a, b]\n: v\n? [c]\n: w\n"),
+            Err(ConversionError::ComplexKey { offset: 2 })
+        );
+        assert_eq!(
+            YamlIndex::to_json(b"k: v\n? {a: 1}\n: w\n"),
+            Err(ConversionError::ComplexKey { offset: 7 })
+        );
+        assert_eq!(
+            YamlIndex::to_json(b"base: &b [1]\n*b : v\n"),
+            Err(ConversionError::ComplexKey { offset: 13 })
+        );
+    }
+}
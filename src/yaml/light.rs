@@ -15,8 +15,9 @@ use std::borrow::Cow;
 use std::string::ToString;
 
 use super::index::YamlIndex;
-use super::scalar::{could_be_null_or_bool, resolve_plain, ResolvedScalar};
+use super::scalar::{could_be_null_or_bool, resolve_plain, resolve_yaml11_bool, ResolvedScalar};
 use super::simd::find_json_escape;
+use crate::util::simd::scan::{find_first_lt, find_first_of_2};
 
 // ============================================================================
 // YamlCursor: Position in the YAML structure
@@ -123,7 +124,40 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
     }
 
     /// Get the YAML value at this cursor position.
+    ///
+    /// Plain scalars resolve by the YAML 1.2 core schema (see
+    /// [`resolve_plain`]) to [`YamlValue::Null`], [`YamlValue::Bool`],
+    /// [`YamlValue::Int`] or [`YamlValue::Float`], unless the index was built
+    /// [`with_type_inference(false)`](YamlIndex::with_type_inference). YAML 1.1
+    /// forms such as `yes` or `on` are strings unless the index was built
+    /// [`with_yaml11_booleans(true)`](YamlIndex::with_yaml11_booleans).
+    /// Quoted and block scalars are always strings.
     pub fn value(&self) -> YamlValue<'a, W> {
+        let value = self.raw_value();
+        if self.index.type_inference() {
+            infer_plain_scalar(value, self.index.yaml11_booleans())
+        } else {
+            value
+        }
+    }
+
+    /// The value at this cursor with plain scalars left as strings.
+    ///
+    /// Keys and the crate's own serializers use this, so their output does
+    /// not depend on [`YamlIndex::with_type_inference`].
+    #[inline]
+    pub(crate) fn raw_value(&self) -> YamlValue<'a, W> {
+        self.decode_value(true)
+    }
+
+    /// [`raw_value`](Self::raw_value) without merge keys applied: a mapping
+    /// lists its `<<` entries as they appear in the text.
+    #[inline]
+    pub(crate) fn unmerged_value(&self) -> YamlValue<'a, W> {
+        self.decode_value(false)
+    }
+
+    fn decode_value(&self, merge_keys: bool) -> YamlValue<'a, W> {
         // Special case: the root (bp_pos=0) is always the virtual document sequence,
         // even if it's empty (no documents). Check it explicitly FIRST before
         // looking at text bytes, since the root's text_position may point to the
@@ -142,7 +176,7 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
             if self.index.is_sequence_at_bp(self.bp_pos) {
                 return YamlValue::Sequence(YamlElements::from_sequence_cursor(*self));
             }
-            return YamlValue::Mapping(YamlFields::from_mapping_cursor(*self));
+            return YamlValue::Mapping(YamlFields::new(*self, merge_keys));
         }
 
         // Compute open_idx once — reused for both text_pos and text_end_pos
@@ -170,7 +204,7 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
             )
         {
             if let Some(child) = self.first_child() {
-                return child.value();
+                return child.decode_value(merge_keys);
             }
             // Empty sequence item (null)
             return YamlValue::Null;
@@ -205,7 +239,7 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
         }
         if byte == b'{' {
             // Flow mapping
-            return YamlValue::Mapping(YamlFields::from_mapping_cursor(*self));
+            return YamlValue::Mapping(YamlFields::new(*self, merge_keys));
         }
 
         // Note: block-style sequences (`- item`) never reach this point. Real
@@ -229,7 +263,7 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
                         let first_byte = self.text[first_child_text_pos];
                         // If first child text doesn't start with '-', this is a mapping key
                         if first_byte != b'-' {
-                            return YamlValue::Mapping(YamlFields::from_mapping_cursor(*self));
+                            return YamlValue::Mapping(YamlFields::new(*self, merge_keys));
                         }
                     }
                 }
@@ -1151,8 +1185,12 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
         current_indent: usize,
         indent_spaces: usize,
     ) -> core::fmt::Result {
-        match self.value() {
+        // YAML output keeps `<<` entries as written
+        match self.unmerged_value() {
             YamlValue::Null => out.write_str("null"),
+            YamlValue::Bool(b) => write!(out, "{b}"),
+            YamlValue::Int(n) => write!(out, "{n}"),
+            YamlValue::Float(f) => stream_yaml_float(out, f),
             YamlValue::String(s) => stream_yaml_string_value(out, &s),
             YamlValue::Mapping(fields) => {
                 if fields.is_empty() {
@@ -1278,8 +1316,11 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
 
     /// Internal: stream this cursor's value as JSON.
     fn stream_json_value<Out: core::fmt::Write>(&self, out: &mut Out) -> core::fmt::Result {
-        match self.value() {
+        match self.raw_value() {
             YamlValue::Null => out.write_str("null"),
+            value @ (YamlValue::Bool(_) | YamlValue::Int(_) | YamlValue::Float(_)) => {
+                stream_yaml_value_as_json(out, value)
+            }
             YamlValue::String(s) => {
                 // Direct transcoding optimization
                 match stream_yaml_string_to_json(out, &s) {
@@ -1357,8 +1398,11 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
 
     /// Write this YAML value as JSON to a string buffer.
     fn write_json_to(&self, output: &mut String) {
-        match self.value() {
+        match self.raw_value() {
             YamlValue::Null => output.push_str("null"),
+            value @ (YamlValue::Bool(_) | YamlValue::Int(_) | YamlValue::Float(_)) => {
+                write_yaml_value_as_json(output, value);
+            }
             YamlValue::String(s) => {
                 // Direct transcoding optimization (avoids intermediate allocation for quoted strings)
                 match write_yaml_string_to_json(output, &s) {
@@ -1653,8 +1697,11 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
     /// Note: This returns inferred tags. Explicit tags in the YAML source
     /// (like `!mytag`) are not currently preserved.
     pub fn tag(&self) -> &'static str {
-        match self.value() {
+        match self.raw_value() {
             YamlValue::Null => "!!null",
+            YamlValue::Bool(_) => "!!bool",
+            YamlValue::Int(_) => "!!int",
+            YamlValue::Float(_) => "!!float",
             YamlValue::String(s) => {
                 // Infer type from plain scalars per the YAML 1.2 core schema
                 if s.is_unquoted() {
@@ -1692,8 +1739,12 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
         if self.is_alias() {
             return "alias";
         }
-        match self.value() {
-            YamlValue::Null | YamlValue::String(_) => "scalar",
+        match self.raw_value() {
+            YamlValue::Null
+            | YamlValue::Bool(_)
+            | YamlValue::Int(_)
+            | YamlValue::Float(_)
+            | YamlValue::String(_) => "scalar",
             YamlValue::Sequence(_) => "seq",
             YamlValue::Mapping(_) => "map",
             YamlValue::Alias { .. } => "alias",
@@ -1769,6 +1820,37 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
         }
     }
 
+    /// Follow a jq-style path such as `.users[0].name` from this cursor.
+    ///
+    /// A path is `.` (this cursor) or a series of steps: `.key` looks up a
+    /// mapping key, `[n]` a sequence element and `["key"]` a key that is not
+    /// an identifier; the first step starts with a `.`, as in `.[0]`. Aliases
+    /// are followed and merge keys applied.
+    ///
+    /// This is the syntax [`locate_offset`](super::locate_offset) produces.
+    /// Its paths start at [`YamlIndex::root`], the stream of documents, so
+    /// they begin with the document number; from a document's own cursor,
+    /// leave that step out.
+    ///
+    /// Returns `None` if the path does not parse or any step fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use succinctly::yaml::YamlIndex;
+    ///
+    /// let yaml = b"users:\n  - name: Alice\n  - name: Bob\n";
+    /// let index = YamlIndex::build(yaml).unwrap();
+    /// let doc = index.root(yaml).first_child().unwrap();
+    ///
+    /// let name = doc.navigate(".users[1].name").unwrap();
+    /// assert_eq!(name.to_json(), r#""Bob""#);
+    /// assert!(doc.navigate(".users[2]").is_none());
+    /// ```
+    pub fn navigate(&self, path: &str) -> Option<Self> {
+        super::locate::navigate(*self, path)
+    }
+
     /// Create a cursor at the specified line and column (1-indexed).
     ///
     /// Returns `None` if:
@@ -1790,6 +1872,12 @@ impl<'a, W: AsRef<[u64]>> YamlCursor<'a, W> {
 fn write_yaml_value_as_json<W: AsRef<[u64]>>(output: &mut String, value: YamlValue<'_, W>) {
     match value {
         YamlValue::Null => output.push_str("null"),
+        YamlValue::Bool(true) => output.push_str("true"),
+        YamlValue::Bool(false) => output.push_str("false"),
+        YamlValue::Int(n) => write_i64(output, n),
+        YamlValue::Float(f) if f.is_finite() => write_f64(output, f),
+        // JSON cannot represent the `.inf`/`.nan` family.
+        YamlValue::Float(_) => output.push_str("null"),
         YamlValue::String(s) => {
             // Direct transcoding optimization (avoids intermediate allocation for quoted strings)
             match write_yaml_string_to_json(output, &s) {
@@ -2125,15 +2213,12 @@ fn transcode_double_quoted_to_json(
                 i += 1;
             }
             _ => {
-                // Regular content - copy until we hit special char
+                // Regular content - copy until we hit special char (a quote,
+                // a backslash or a control character, line breaks included)
                 let start = i;
-                while i < bytes.len() {
-                    let b = bytes[i];
-                    if matches!(b, b'\\' | b'\n' | b'\r' | b'"') || b < 0x20 {
-                        break;
-                    }
-                    i += 1;
-                }
+                let rest = &bytes[i..];
+                let special = find_first_of_2(rest, b'"', b'\\').unwrap_or(rest.len());
+                i += find_first_lt(&rest[..special], 0x20).unwrap_or(special);
                 // Trailing literal whitespace folds away before a literal
                 // line break (the span can only end in spaces; tabs break it)
                 let mut end = i;
@@ -2318,8 +2403,13 @@ fn write_yaml_string_to_json(
                 let s = core::str::from_utf8(bytes).map_err(|_| YamlStringError::InvalidUtf8)?;
                 write_json_string(output, s);
             } else {
-                // Transcode directly: YAML escapes → JSON escapes
-                transcode_double_quoted_to_json(output, bytes)?;
+                // Transcode directly: YAML escapes → JSON escapes. On failure,
+                // drop the partial string so the caller's fallback stays valid
+                let mark = output.len();
+                transcode_double_quoted_to_json(output, bytes).map_err(|e| {
+                    output.truncate(mark);
+                    e
+                })?;
             }
             Ok(true) // Always a string, no type detection
         }
@@ -2431,6 +2521,11 @@ fn stream_yaml_value_as_json<W: AsRef<[u64]>, Out: core::fmt::Write>(
 ) -> core::fmt::Result {
     match value {
         YamlValue::Null => out.write_str("null"),
+        YamlValue::Bool(b) => write!(out, "{b}"),
+        YamlValue::Int(n) => write!(out, "{n}"),
+        YamlValue::Float(f) if f.is_finite() => write!(out, "{f}"),
+        // JSON cannot represent the `.inf`/`.nan` family.
+        YamlValue::Float(_) => out.write_str("null"),
         YamlValue::String(s) => match stream_yaml_string_to_json(out, &s) {
             Ok(true) => Ok(()),
             Ok(false) => {
@@ -2971,6 +3066,18 @@ fn stream_yaml_scalar_as_json<Out: core::fmt::Write>(
     }
 }
 
+/// Stream a float as a plain YAML scalar that resolves back to a float.
+fn stream_yaml_float<Out: core::fmt::Write>(out: &mut Out, f: f64) -> core::fmt::Result {
+    if f.is_nan() {
+        out.write_str(".nan")
+    } else if f.is_infinite() {
+        out.write_str(if f > 0.0 { ".inf" } else { "-.inf" })
+    } else {
+        // `Debug` keeps a fractional part (`1.0`), so the text is not an int
+        write!(out, "{f:?}")
+    }
+}
+
 // ============================================================================
 // YamlChildren: Iterator over children
 // ============================================================================
@@ -3000,6 +3107,38 @@ impl<'a, W: AsRef<[u64]>> Iterator for YamlChildren<'a, W> {
     }
 }
 
+/// Resolve a plain scalar to `Null`, `Bool`, `Int` or `Float` per the YAML
+/// 1.2 core schema, plus the YAML 1.1 booleans if `yaml11_booleans`; other
+/// values, and plain strings, are returned unchanged.
+fn infer_plain_scalar<W>(value: YamlValue<'_, W>, yaml11_booleans: bool) -> YamlValue<'_, W> {
+    let YamlValue::String(
+        s @ YamlString::Unquoted {
+            text, start, end, ..
+        },
+    ) = &value
+    else {
+        return value;
+    };
+    // A multi-line plain scalar folds to text containing a space, so it is
+    // always a string
+    if text[*start..*end].contains(&b'\n') {
+        return value;
+    }
+    let Ok(str_val) = s.as_str() else {
+        return value;
+    };
+    match resolve_plain(&str_val) {
+        ResolvedScalar::Null => YamlValue::Null,
+        ResolvedScalar::Bool(b) => YamlValue::Bool(b),
+        ResolvedScalar::Int(n) => YamlValue::Int(n),
+        ResolvedScalar::Float(f) => YamlValue::Float(f),
+        ResolvedScalar::Str => match resolve_yaml11_bool(&str_val) {
+            Some(b) if yaml11_booleans => YamlValue::Bool(b),
+            _ => value,
+        },
+    }
+}
+
 // ============================================================================
 // YamlValue: The value type
 // ============================================================================
@@ -3007,9 +3146,17 @@ impl<'a, W: AsRef<[u64]>> Iterator for YamlChildren<'a, W> {
 /// A YAML value with lazy decoding.
 #[derive(Clone, Debug)]
 pub enum YamlValue<'a, W = Vec<u64>> {
-    /// A YAML null value (empty entry)
+    /// A YAML null value: an empty entry, or with type inference a plain
+    /// `null`, `Null`, `NULL` or `~`
     Null,
-    /// A YAML string (various quote styles)
+    /// A plain boolean (`true`, `False`, ...), with type inference
+    Bool(bool),
+    /// A plain integer (`42`, `-7`, `0x2A`, `0o52`), with type inference
+    Int(i64),
+    /// A plain float (`3.14`, `1e-2`, `.inf`, `.nan`), with type inference
+    Float(f64),
+    /// A YAML string (various quote styles); with type inference, a plain
+    /// scalar only if it is not a null, boolean or number
     String(YamlString<'a>),
     /// A YAML mapping (object-like)
     Mapping(YamlFields<'a, W>),
@@ -3031,10 +3178,19 @@ pub enum YamlValue<'a, W = Vec<u64>> {
 // ============================================================================
 
 /// Immutable "list" of YAML mapping fields.
+///
+/// Merge keys are applied: a `<<: *anchor` entry (or `<<: [*a, *b]`) is
+/// replaced by the fields of the anchored mappings, at its position, except
+/// those the mapping defines itself or an earlier merged mapping supplied.
+/// The merged value must be an alias.
 #[derive(Debug)]
 pub struct YamlFields<'a, W = Vec<u64>> {
     /// Cursor pointing to the current field key, or None if exhausted
     key_cursor: Option<YamlCursor<'a, W>>,
+    /// For a mapping with merge keys, its remaining fields as key and value
+    /// BP positions, merged fields included; `key_cursor` then only lends
+    /// its text and index
+    merged: Option<&'a [(usize, usize)]>,
 }
 
 impl<W> Clone for YamlFields<'_, W> {
@@ -3048,24 +3204,52 @@ impl<W> Copy for YamlFields<'_, W> {}
 impl<'a, W: AsRef<[u64]>> YamlFields<'a, W> {
     /// Create a new YamlFields from a mapping cursor.
     pub fn from_mapping_cursor(mapping_cursor: YamlCursor<'a, W>) -> Self {
-        Self {
-            key_cursor: mapping_cursor.first_child(),
-        }
+        Self::new(mapping_cursor, true)
+    }
+
+    pub(crate) fn new(mapping_cursor: YamlCursor<'a, W>, merge_keys: bool) -> Self {
+        let key_cursor = mapping_cursor.first_child();
+        let merged = match key_cursor {
+            Some(first) if merge_keys => mapping_cursor
+                .index
+                .merged_fields(mapping_cursor.text, first.bp_pos),
+            _ => None,
+        };
+        Self { key_cursor, merged }
     }
 
     /// Check if there are no more fields.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.key_cursor.is_none()
+        match self.merged {
+            Some(merged) => merged.is_empty(),
+            None => self.key_cursor.is_none(),
+        }
     }
 
     /// Get the first field and the remaining fields.
     pub fn uncons(&self) -> Option<(YamlField<'a, W>, Self)> {
+        if let Some(merged) = self.merged {
+            let (&(key_bp, value_bp), rest) = merged.split_first()?;
+            let cursor = self.key_cursor?;
+            let at = |bp_pos| YamlCursor { bp_pos, ..cursor };
+            let field = YamlField {
+                key_cursor: at(key_bp),
+                value_cursor: at(value_bp),
+            };
+            let rest = YamlFields {
+                key_cursor: self.key_cursor,
+                merged: Some(rest),
+            };
+            return Some((field, rest));
+        }
+
         let key_cursor = self.key_cursor?;
         let value_cursor = key_cursor.next_sibling()?;
 
         let rest = YamlFields {
             key_cursor: value_cursor.next_sibling(),
+            merged: None,
         };
 
         let field = YamlField {
@@ -3122,9 +3306,12 @@ impl<W> Copy for YamlField<'_, W> {}
 
 impl<'a, W: AsRef<[u64]>> YamlField<'a, W> {
     /// Get the field key.
+    ///
+    /// Keys are never type-inferred: a plain key such as `1` or `true` is a
+    /// [`YamlValue::String`].
     #[inline]
     pub fn key(&self) -> YamlValue<'a, W> {
-        self.key_cursor.value()
+        self.key_cursor.raw_value()
     }
 
     /// Get the field value.
@@ -3138,6 +3325,12 @@ impl<'a, W: AsRef<[u64]>> YamlField<'a, W> {
     pub fn value_cursor(&self) -> YamlCursor<'a, W> {
         self.value_cursor
     }
+
+    /// The key's cursor.
+    #[inline]
+    pub(crate) fn key_cursor(&self) -> YamlCursor<'a, W> {
+        self.key_cursor
+    }
 }
 
 // ============================================================================
@@ -4327,12 +4520,16 @@ fn decode_block_folded(
         YamlString::detect_block_indent(text, content_start).unwrap_or(0)
     };
 
-    // Build result by folding newlines
+    // Build result by folding newlines. A line break between two text lines
+    // becomes a space; one followed by empty lines is dropped and each empty
+    // line becomes `\n`. Breaks next to a more-indented line are kept.
     let mut result = String::with_capacity(content.len());
     let mut pos = 0;
-    let mut prev_was_blank = false;
+    let mut blank_lines = 0;
     let mut prev_was_more_indented = false;
     let mut first_line = true;
+    // Line breaks after the last text line (its own, plus one per blank line)
+    let mut trailing_breaks = 0;
 
     while pos < content.len() {
         // Count indentation
@@ -4347,26 +4544,8 @@ fn decode_block_folded(
             || content[pos + line_indent] == b'\r';
 
         if is_blank {
-            // Blank line = paragraph break
-            if !result.is_empty() && !result.ends_with('\n') {
-                result.push('\n');
-            }
-            result.push('\n');
-            prev_was_blank = true;
-            prev_was_more_indented = false;
-
-            // Skip to next line
             pos += line_indent;
-            if pos < content.len() {
-                if content[pos] == b'\r' {
-                    pos += 1;
-                    if pos < content.len() && content[pos] == b'\n' {
-                        pos += 1;
-                    }
-                } else if content[pos] == b'\n' {
-                    pos += 1;
-                }
-            }
+            blank_lines += 1;
         } else {
             // Strip the common indent
             let skip = line_indent.min(indent);
@@ -4384,13 +4563,15 @@ fn decode_block_folded(
                 pos += 1;
             }
 
-            // Add joining character
-            if !first_line && !result.is_empty() && !result.ends_with('\n') {
-                if is_more_indented || prev_was_more_indented || prev_was_blank {
-                    result.push('\n');
-                } else {
-                    result.push(' ');
-                }
+            // Add joining characters for the break(s) since the previous line
+            if first_line {
+                result.extend(core::iter::repeat('\n').take(blank_lines));
+            } else if is_more_indented || prev_was_more_indented {
+                result.extend(core::iter::repeat('\n').take(blank_lines + 1));
+            } else if blank_lines > 0 {
+                result.extend(core::iter::repeat('\n').take(blank_lines));
+            } else {
+                result.push(' ');
             }
 
             // Append line content
@@ -4398,41 +4579,41 @@ fn decode_block_folded(
                 .map_err(|_| YamlStringError::InvalidUtf8)?;
             result.push_str(line);
 
-            prev_was_blank = false;
+            blank_lines = 0;
+            trailing_breaks = 0;
             prev_was_more_indented = is_more_indented;
             first_line = false;
+        }
 
-            // Skip line ending
-            if pos < content.len() {
-                if content[pos] == b'\r' {
-                    pos += 1;
-                    if pos < content.len() && content[pos] == b'\n' {
-                        pos += 1;
-                    }
-                } else if content[pos] == b'\n' {
+        // Skip line ending
+        if pos < content.len() {
+            trailing_breaks += 1;
+            if content[pos] == b'\r' {
+                pos += 1;
+                if pos < content.len() && content[pos] == b'\n' {
                     pos += 1;
                 }
+            } else if content[pos] == b'\n' {
+                pos += 1;
             }
         }
     }
 
     // Apply chomping at the end
     match chomping {
-        ChompingIndicator::Strip => {
-            while result.ends_with('\n') {
-                result.pop();
-            }
-        }
+        ChompingIndicator::Strip => {}
         ChompingIndicator::Clip => {
-            while result.ends_with("\n\n") {
-                result.pop();
-            }
-            if !result.is_empty() && !result.ends_with('\n') {
+            if !first_line {
                 result.push('\n');
             }
         }
         ChompingIndicator::Keep => {
-            // Keep all trailing newlines as-is
+            if first_line {
+                // Only empty lines: each is kept as a line break
+                result.extend(core::iter::repeat('\n').take(blank_lines));
+            } else {
+                result.extend(core::iter::repeat('\n').take(trailing_breaks));
+            }
         }
     }
 
@@ -4579,7 +4760,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentCursor for YamlCursor<'a, W> {
     fn is_falsy(&self) -> bool {
         // A value is falsy if it's null or false
         match self.value() {
-            YamlValue::Null => true,
+            YamlValue::Null | YamlValue::Bool(false) => true,
             YamlValue::String(s) if s.is_unquoted() => {
                 if let Ok(str_val) = s.as_str() {
                     could_be_null_or_bool(&str_val)
@@ -4614,7 +4795,7 @@ impl<'a, W: AsRef<[u64]>> YamlValue<'a, W> {
                 target: Some(target),
                 ..
             } => {
-                if let YamlValue::String(s) = target.value() {
+                if let YamlValue::String(s) = target.raw_value() {
                     s.as_str().unwrap_or(Cow::Borrowed(""))
                 } else {
                     Cow::Borrowed("")
@@ -4652,6 +4833,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for YamlValue<'a, W> {
 
     fn as_bool(&self) -> Option<bool> {
         match self {
+            YamlValue::Bool(b) => Some(*b),
             YamlValue::String(s) if s.is_unquoted() => {
                 let str_val = s.as_str().ok()?;
                 if !could_be_null_or_bool(&str_val) {
@@ -4669,6 +4851,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for YamlValue<'a, W> {
 
     fn as_i64(&self) -> Option<i64> {
         match self {
+            YamlValue::Int(n) => Some(*n),
             YamlValue::String(s) if s.is_unquoted() => {
                 let str_val = s.as_str().ok()?;
                 match resolve_plain(&str_val) {
@@ -4683,6 +4866,8 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for YamlValue<'a, W> {
 
     fn as_f64(&self) -> Option<f64> {
         match self {
+            YamlValue::Int(n) => Some(*n as f64),
+            YamlValue::Float(f) => Some(*f),
             YamlValue::String(s) if s.is_unquoted() => {
                 let str_val = s.as_str().ok()?;
                 // Non-finite floats arise only from the `.inf`/`.nan` family
@@ -4704,7 +4889,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for YamlValue<'a, W> {
                 // For aliases, we need to return an owned string since the
                 // target value is created temporarily
                 target.and_then(|t| {
-                    if let YamlValue::String(s) = t.value() {
+                    if let YamlValue::String(s) = t.raw_value() {
                         s.as_str().ok().map(|cow| Cow::Owned(cow.into_owned()))
                     } else {
                         None
@@ -4740,6 +4925,8 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentValue for YamlValue<'a, W> {
     fn type_name(&self) -> &'static str {
         match self {
             YamlValue::Null => "null",
+            YamlValue::Bool(_) => "boolean",
+            YamlValue::Int(_) | YamlValue::Float(_) => "number",
             YamlValue::String(s) => {
                 // Determine effective type per the YAML 1.2 core schema
                 if s.is_unquoted() {
@@ -4820,7 +5007,7 @@ impl<'a, W: AsRef<[u64]> + Clone> DocumentElements for YamlElements<'a, W> {
 
 /// Check if a cursor points to a non-empty container.
 fn is_yaml_cursor_container<W: AsRef<[u64]>>(cursor: &YamlCursor<'_, W>) -> bool {
-    match cursor.value() {
+    match cursor.unmerged_value() {
         YamlValue::Mapping(fields) => !fields.is_empty(),
         YamlValue::Sequence(elements) => !elements.is_empty(),
         _ => false,
@@ -5280,6 +5467,14 @@ mod tests {
         assert!(json.contains("\"f\":1.5"), "got {json}");
     }
 
+    #[test]
+    fn test_to_json_invalid_escape_is_null() {
+        // A failed transcode must not leave a dangling `"` before the `null`
+        let yaml = b"a: \"x\\qy\"\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        assert_eq!(index.root(yaml).to_json_document(), r#"{"a":null}"#);
+    }
+
     #[test]
     fn test_stream_json_double_quoted_non_ascii_and_leading_plus() {
         // Streaming counterpart of the test above: exercises
@@ -5750,11 +5945,7 @@ mod tests {
                 assert_eq!(items.len(), 3);
 
                 // Check first element
-                if let YamlValue::String(s) = &items[0] {
-                    assert_eq!(&*s.as_str().unwrap(), "1");
-                } else {
-                    panic!("expected string value for item");
-                }
+                assert!(matches!(items[0], YamlValue::Int(1)));
             } else {
                 panic!("expected sequence for items");
             }
@@ -5763,6 +5954,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_value_type_inference() {
+        let yaml =
+            b"[~, null, true, False, 42, 0x2A, -7, 1.5, .inf, 3.0.1, yes, '1', \"true\", |\n  7\n]";
+        let index = YamlIndex::build(yaml).unwrap();
+        let YamlValue::Sequence(elements) = first_doc(index.root(yaml)) else {
+            panic!("expected sequence");
+        };
+        let values: Vec<_> = elements.collect();
+        assert!(matches!(values[0], YamlValue::Null));
+        assert!(matches!(values[1], YamlValue::Null));
+        assert!(matches!(values[2], YamlValue::Bool(true)));
+        assert!(matches!(values[3], YamlValue::Bool(false)));
+        assert!(matches!(values[4], YamlValue::Int(42)));
+        assert!(matches!(values[5], YamlValue::Int(42)));
+        assert!(matches!(values[6], YamlValue::Int(-7)));
+        assert!(matches!(values[7], YamlValue::Float(f) if f == 1.5));
+        assert!(matches!(values[8], YamlValue::Float(f) if f == f64::INFINITY));
+        // Not numbers under the core schema, or quoted/block scalars
+        for value in &values[9..] {
+            assert!(matches!(value, YamlValue::String(_)), "{value:?}");
+        }
+
+        // Keys stay strings
+        let yaml = b"1: one\ntrue: yes\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let YamlValue::Mapping(fields) = first_doc(index.root(yaml)) else {
+            panic!("expected mapping");
+        };
+        for field in fields {
+            assert!(matches!(field.key(), YamlValue::String(_)));
+        }
+        assert!(matches!(fields.find("1"), Some(YamlValue::String(_))));
+
+        // Disabled, plain scalars come back as text
+        let yaml = b"n: 42\nb: true\nz: null\n";
+        let index = YamlIndex::build(yaml).unwrap().with_type_inference(false);
+        assert!(!index.type_inference());
+        let YamlValue::Mapping(fields) = first_doc(index.root(yaml)) else {
+            panic!("expected mapping");
+        };
+        for field in fields {
+            assert!(matches!(field.value(), YamlValue::String(_)));
+        }
+        // JSON output resolves scalars either way
+        assert_eq!(
+            index.root(yaml).to_json_document(),
+            r#"{"n":42,"b":true,"z":null}"#
+        );
+
+        // YAML 1.1 booleans only on request, and never when quoted
+        let yaml = b"[yes, No, ON, off, y, 'yes', 1]";
+        let index = YamlIndex::build(yaml).unwrap().with_yaml11_booleans(true);
+        let YamlValue::Sequence(elements) = first_doc(index.root(yaml)) else {
+            panic!("expected sequence");
+        };
+        let values: Vec<_> = elements.collect();
+        assert!(matches!(values[0], YamlValue::Bool(true)));
+        assert!(matches!(values[1], YamlValue::Bool(false)));
+        assert!(matches!(values[2], YamlValue::Bool(true)));
+        assert!(matches!(values[3], YamlValue::Bool(false)));
+        assert!(matches!(values[4], YamlValue::String(_)));
+        assert!(matches!(values[5], YamlValue::String(_)));
+        assert!(matches!(values[6], YamlValue::Int(1)));
+        let index = index.with_type_inference(false);
+        let YamlValue::Sequence(mut elements) = first_doc(index.root(yaml)) else {
+            panic!("expected sequence");
+        };
+        assert!(matches!(elements.next(), Some(YamlValue::String(_))));
+    }
+
     #[test]
     fn test_flow_mapping_navigation() {
         let yaml = b"person: {name: Alice, age: 30}";
@@ -5780,11 +6042,10 @@ mod tests {
                 }
 
                 // Check age
-                if let Some(YamlValue::String(s)) = person_fields.find("age") {
-                    assert_eq!(&*s.as_str().unwrap(), "30");
-                } else {
-                    panic!("expected age field");
-                }
+                assert!(matches!(
+                    person_fields.find("age"),
+                    Some(YamlValue::Int(30))
+                ));
             } else {
                 panic!("expected mapping for person");
             }
@@ -5912,6 +6173,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_block_scalar_chomping_and_indent_decoding() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"a: |\n  x\n  y\n\n", "x\ny\n"),
+            (b"a: |-\n  x\n  y\n\n", "x\ny"),
+            (b"a: |+\n  x\n  y\n\n", "x\ny\n\n"),
+            (b"a: |2\n   x\n  y\n", " x\ny\n"),
+            (b"a: |2-\n   x\n", " x"),
+            (b"a: >\n  x\n  y\n\n  z\n", "x y\nz\n"),
+            (b"a: >-\n  x\n  y\n", "x y"),
+            (b"a: >+\n  x\n\n", "x\n\n"),
+        ];
+        for &(yaml, expected) in cases {
+            let index = YamlIndex::build(yaml).unwrap();
+            let YamlValue::Mapping(fields) = first_doc(index.root(yaml)) else {
+                panic!("expected mapping");
+            };
+            let Some(YamlValue::String(s)) = fields.find("a") else {
+                panic!("expected string for {:?}", String::from_utf8_lossy(yaml));
+            };
+            assert!(matches!(
+                s,
+                YamlString::BlockLiteral { .. } | YamlString::BlockFolded { .. }
+            ));
+            assert_eq!(
+                s.as_str().unwrap(),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(yaml)
+            );
+        }
+    }
+
     #[test]
     fn test_block_scalar_in_sequence() {
         // First test a simple sequence without block scalars to establish baseline
@@ -6308,11 +6602,10 @@ mod tests {
                 }
 
                 // Check age
-                if let Some(YamlValue::String(s)) = person_fields.find("age") {
-                    assert_eq!(&*s.as_str().unwrap(), "30");
-                } else {
-                    panic!("expected age field");
-                }
+                assert!(matches!(
+                    person_fields.find("age"),
+                    Some(YamlValue::Int(30))
+                ));
             } else {
                 panic!("expected mapping for person");
             }
@@ -7544,6 +7837,49 @@ mod tests {
         panic!("Could not find alias");
     }
 
+    #[test]
+    fn test_alias_value_matches_anchored_node() {
+        let cases: &[&[u8]] = &[
+            b"a: &x plain\nb: *x",
+            b"a: &x \"quoted\"\nb: *x",
+            b"a: &x\n  - 1\n  - two\nb: *x",
+            b"a: &x\n  host: localhost\n  ports: [80, 443]\nb: *x",
+            b"a: &x {k: [v, {n: 1}]}\nb: *x",
+            b"a:\n  nested: &x [1, 2]\nb: *x",
+        ];
+        for &yaml in cases {
+            let index = YamlIndex::build(yaml).unwrap();
+            let root = index.root(yaml);
+            let anchor_bp = index.get_anchor_bp_pos("x").expect("anchor recorded");
+            let anchored = index.cursor_at(anchor_bp, yaml);
+
+            let YamlValue::Mapping(fields) = first_doc(root) else {
+                panic!("expected mapping");
+            };
+            let alias = fields
+                .into_iter()
+                .last()
+                .expect("alias field")
+                .value_cursor();
+            assert!(alias.is_alias());
+            let YamlValue::Alias {
+                anchor_name,
+                target,
+            } = alias.value()
+            else {
+                panic!(
+                    "expected alias value for {:?}",
+                    String::from_utf8_lossy(yaml)
+                );
+            };
+            assert_eq!(anchor_name, "x");
+            let target = target.expect("alias resolves");
+            assert_eq!(target.bp_position(), anchor_bp);
+            assert_eq!(target.to_json(), anchored.to_json());
+            assert_eq!(alias.to_json(), anchored.to_json());
+        }
+    }
+
     // =========================================================================
     // Line and Column Tests
     // =========================================================================
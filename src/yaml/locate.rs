@@ -1,14 +1,18 @@
 //! YAML path location utilities.
 //!
 //! This module provides functionality to find the jq-like expression that
-//! navigates to a specific byte offset in a YAML document.
+//! navigates to a specific byte offset in a YAML document, and the reverse:
+//! following such an expression with [`YamlCursor::navigate`].
 
 #[cfg(not(test))]
 use alloc::{
+    borrow::Cow,
     format,
     string::{String, ToString},
     vec::Vec,
 };
+#[cfg(test)]
+use std::borrow::Cow;
 
 use super::index::YamlIndex;
 use super::light::{YamlCursor, YamlValue};
@@ -303,6 +307,9 @@ pub fn locate_offset_detailed<W: AsRef<[u64]>>(
 
     let value_type = match cursor.value() {
         YamlValue::Null => "null",
+        YamlValue::Bool(_) => "bool",
+        YamlValue::Int(_) => "int",
+        YamlValue::Float(_) => "float",
         YamlValue::Mapping(_) => "mapping",
         YamlValue::Sequence(_) => "sequence",
         YamlValue::String(_) => "string",
@@ -317,6 +324,130 @@ pub fn locate_offset_detailed<W: AsRef<[u64]>>(
     })
 }
 
+// ============================================================================
+// Path navigation
+// ============================================================================
+
+/// A step of a path expression parsed by [`parse_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathStep<'p> {
+    /// Mapping lookup: `.foo` or `["foo-bar"]`
+    Key(Cow<'p, str>),
+    /// Sequence index: `[0]`
+    Index(usize),
+}
+
+/// Parse a path in the syntax [`path_to_bp`] produces.
+///
+/// A path is `.` (identity) or a series of steps: `.key`, `[n]` and
+/// `["key"]`, where the first step starts with a `.` (`.[0]`, `.["a b"]`).
+fn parse_path(path: &str) -> Option<Vec<PathStep<'_>>> {
+    let rest = path.strip_prefix('.')?;
+    let mut steps = Vec::new();
+    if rest.is_empty() {
+        return Some(steps);
+    }
+
+    let mut rest = match rest.strip_prefix('[') {
+        Some(_) => rest,
+        None => {
+            let (key, rest) = split_dot_key(rest)?;
+            steps.push(PathStep::Key(Cow::Borrowed(key)));
+            rest
+        }
+    };
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let (key, after_key) = split_dot_key(after_dot)?;
+            steps.push(PathStep::Key(Cow::Borrowed(key)));
+            rest = after_key;
+        } else {
+            let (step, after_bracket) = split_bracket(rest.strip_prefix('[')?)?;
+            steps.push(step);
+            rest = after_bracket;
+        }
+    }
+    Some(steps)
+}
+
+/// Split a dot-notation key off the front of `s`.
+fn split_dot_key(s: &str) -> Option<(&str, &str)> {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    let (key, rest) = s.split_at(end);
+    can_use_dot_notation(key).then_some((key, rest))
+}
+
+/// Parse the inside of a bracket step, `s` starting just after the `[`.
+fn split_bracket(s: &str) -> Option<(PathStep<'_>, &str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut key = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    let rest = quoted[i + 1..].strip_prefix(']')?;
+                    return Some((PathStep::Key(Cow::Owned(key)), rest));
+                }
+                '\\' => key.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    c @ ('"' | '\\') => c,
+                    _ => return None,
+                }),
+                c => key.push(c),
+            }
+        }
+        None
+    } else {
+        let (digits, rest) = s.split_once(']')?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((PathStep::Index(digits.parse().ok()?), rest))
+    }
+}
+
+/// Follow `path` from `cursor`, as [`YamlCursor::navigate`] documents.
+pub(crate) fn navigate<'a, W: AsRef<[u64]>>(
+    cursor: YamlCursor<'a, W>,
+    path: &str,
+) -> Option<YamlCursor<'a, W>> {
+    let mut cursor = resolve_alias(cursor);
+    for step in parse_path(path)? {
+        cursor = match (step, cursor.value()) {
+            (PathStep::Key(name), YamlValue::Mapping(mut fields)) => {
+                Iterator::find(&mut fields, |field| match field.key() {
+                    YamlValue::String(key) => key.as_str().is_ok_and(|key| key == name),
+                    _ => false,
+                })?
+                .value_cursor()
+            }
+            (PathStep::Index(n), YamlValue::Sequence(mut elements)) => {
+                for _ in 0..n {
+                    elements = elements.uncons_cursor()?.1;
+                }
+                elements.uncons_cursor()?.0
+            }
+            _ => return None,
+        };
+        cursor = resolve_alias(cursor);
+    }
+    Some(cursor)
+}
+
+/// The anchored node an alias refers to, or `cursor` itself if it is not
+/// an alias.
+fn resolve_alias<W: AsRef<[u64]>>(cursor: YamlCursor<'_, W>) -> YamlCursor<'_, W> {
+    match cursor.value() {
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => target,
+        _ => cursor,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,4 +480,98 @@ mod tests {
         assert_eq!(escape_jq_string("hello\"world"), "hello\\\"world");
         assert_eq!(escape_jq_string("line1\nline2"), "line1\\nline2");
     }
+
+    #[test]
+    fn test_parse_path() {
+        use PathStep::{Index, Key};
+        assert_eq!(parse_path("."), Some(vec![]));
+        assert_eq!(
+            parse_path(".a.b_2[3]"),
+            Some(vec![Key("a".into()), Key("b_2".into()), Index(3)])
+        );
+        assert_eq!(
+            parse_path(r#".[0]["x-y"].z["a\"b"]"#),
+            Some(vec![
+                Index(0),
+                Key("x-y".into()),
+                Key("z".into()),
+                Key("a\"b".into())
+            ])
+        );
+        for invalid in [
+            "", "a", "..", ".a.", ".[", ".[x]", ".[-1]", ".a-b", r#".["a"#, ".a]",
+        ] {
+            assert_eq!(parse_path(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_navigate_manifest() {
+        let yaml = b"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  labels: &labels
+    app.kubernetes.io/name: web
+spec:
+  replicas: 3
+  template:
+    metadata:
+      labels: *labels
+    spec:
+      containers:
+        - name: nginx
+          image: nginx:1.25
+          ports:
+            - containerPort: 80
+        - name: sidecar
+          image: busybox
+";
+        let index = YamlIndex::build(yaml).unwrap();
+        let doc = index.root(yaml).first_child().unwrap();
+        let json = |path: &str| doc.navigate(path).map(|cursor| cursor.to_json());
+
+        assert_eq!(json(".metadata.name").as_deref(), Some(r#""web""#));
+        assert_eq!(json(".spec.replicas").as_deref(), Some("3"));
+        assert_eq!(
+            json(".spec.template.spec.containers[1].image").as_deref(),
+            Some(r#""busybox""#)
+        );
+        assert_eq!(
+            json(".spec.template.spec.containers[0].ports[0].containerPort").as_deref(),
+            Some("80")
+        );
+        // Through an alias, with a key that needs brackets
+        assert_eq!(
+            json(r#".spec.template.metadata.labels["app.kubernetes.io/name"]"#).as_deref(),
+            Some(r#""web""#)
+        );
+        assert_eq!(json(".").as_deref(), Some(doc.to_json().as_str()));
+
+        assert!(doc.navigate(".metadata.namespace").is_none());
+        assert!(doc.navigate(".spec.template.spec.containers[2]").is_none());
+        assert!(doc.navigate(".kind[0]").is_none());
+        assert!(doc.navigate(".metadata[0]").is_none());
+        assert!(doc.navigate("metadata").is_none());
+    }
+
+    #[test]
+    fn test_navigate_inverts_locate() {
+        let yaml = b"a:\n  - x: 1\n    \"y z\": [2, 3]\n  - plain\nb: {c: d}\n---\n- e\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let root = index.root(yaml);
+        let mut paths = 0;
+        for offset in 0..yaml.len() {
+            let Some(path) = locate_offset(&index, yaml, offset) else {
+                continue;
+            };
+            // Paths start at the stream root, `.[0]` being the first document
+            let cursor = root
+                .navigate(&path)
+                .unwrap_or_else(|| panic!("{path} at {offset}"));
+            assert_eq!(path_to_bp(&index, yaml, cursor.bp_position()), Some(path));
+            paths += 1;
+        }
+        assert!(paths > 0);
+    }
 }
@@ -0,0 +1,202 @@
+//! Merge keys (`<<`).
+//!
+//! A mapping entry `<<: *base` (or `<<: [*a, *b]`) stands for the fields of
+//! the anchored mappings. [`merge_table`] flattens every mapping that has
+//! such an entry into the field list [`YamlFields`](super::YamlFields)
+//! iterates: merged fields take the place of the `<<` entry, a key the
+//! mapping defines itself overrides a merged one, and an earlier merged
+//! mapping overrides a later one.
+//!
+//! The table is built through [`YamlCursor::unmerged_value`], never through
+//! the merge-aware accessors, since those look the table up.
+
+#[cfg(not(test))]
+use alloc::{borrow::Cow, collections::BTreeMap, collections::BTreeSet, vec, vec::Vec};
+#[cfg(test)]
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use super::index::YamlIndex;
+use super::light::{YamlCursor, YamlString, YamlValue};
+
+/// Flattened fields, as key and value BP positions, of every mapping with a
+/// merge key, keyed by the BP position of the mapping's first key.
+pub(crate) fn merge_table<W: AsRef<[u64]>>(
+    index: &YamlIndex<W>,
+    text: &[u8],
+) -> BTreeMap<usize, Vec<(usize, usize)>> {
+    let mut table = BTreeMap::new();
+    if !text.windows(2).any(|pair| pair == b"<<") {
+        return table;
+    }
+
+    let mut stack = vec![index.root(text)];
+    while let Some(cursor) = stack.pop() {
+        stack.extend(cursor.children());
+        let Some(first_key) = cursor.first_child() else {
+            continue;
+        };
+        if table.contains_key(&first_key.bp_position()) {
+            // A sequence item and the mapping inside it share their fields
+            continue;
+        }
+        if let YamlValue::Mapping(fields) = cursor.unmerged_value() {
+            if fields
+                .into_iter()
+                .any(|field| is_merge_key(field.key_cursor()))
+            {
+                table.insert(first_key.bp_position(), flatten(cursor));
+            }
+        }
+    }
+    table
+}
+
+/// The fields of `mapping` with its merge keys applied.
+fn flatten<W: AsRef<[u64]>>(mapping: YamlCursor<'_, W>) -> Vec<(usize, usize)> {
+    let YamlValue::Mapping(fields) = mapping.unmerged_value() else {
+        return Vec::new();
+    };
+    let own_keys: BTreeSet<Cow<'_, str>> = fields
+        .into_iter()
+        .map(|field| field.key_cursor())
+        .filter(|key| !is_merge_key(*key))
+        .map(key_text)
+        .collect();
+
+    let mut merged_keys = BTreeSet::new();
+    let mut flat = Vec::new();
+    for field in fields {
+        let (key, value) = (field.key_cursor(), field.value_cursor());
+        if !is_merge_key(key) {
+            flat.push((key.bp_position(), value.bp_position()));
+            continue;
+        }
+        for source in merge_sources(value) {
+            for (key_bp, value_bp) in flatten(source) {
+                let key = key_text(mapping.index().cursor_at(key_bp, mapping.text()));
+                if !own_keys.contains(&key) && merged_keys.insert(key) {
+                    flat.push((key_bp, value_bp));
+                }
+            }
+        }
+    }
+    flat
+}
+
+/// The mappings a `<<` value merges, in priority order.
+fn merge_sources<W: AsRef<[u64]>>(value: YamlCursor<'_, W>) -> Vec<YamlCursor<'_, W>> {
+    match value.unmerged_value() {
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => vec![target],
+        YamlValue::Sequence(mut elements) => {
+            let mut sources = Vec::new();
+            while let Some((element, rest)) = elements.uncons_cursor() {
+                if let YamlValue::Alias {
+                    target: Some(target),
+                    ..
+                } = element.unmerged_value()
+                {
+                    sources.push(target);
+                }
+                elements = rest;
+            }
+            sources
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Whether the key at `key` is a plain `<<`.
+fn is_merge_key<W: AsRef<[u64]>>(key: YamlCursor<'_, W>) -> bool {
+    matches!(
+        key.unmerged_value(),
+        YamlValue::String(s @ YamlString::Unquoted { .. }) if s.as_str().is_ok_and(|k| k == "<<")
+    )
+}
+
+/// A key's text, as [`YamlValue::key_string`] gives it.
+fn key_text<W: AsRef<[u64]>>(key: YamlCursor<'_, W>) -> Cow<'_, str> {
+    let value = match key.unmerged_value() {
+        YamlValue::Alias {
+            target: Some(target),
+            ..
+        } => target.unmerged_value(),
+        value => value,
+    };
+    match value {
+        YamlValue::String(s) => s.as_str().unwrap_or(Cow::Borrowed("")),
+        _ => Cow::Borrowed(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::yaml::{YamlIndex, YamlValue};
+
+    fn json(yaml: &[u8]) -> String {
+        let index = YamlIndex::build(yaml).unwrap();
+        index.root(yaml).to_json_document()
+    }
+
+    #[test]
+    fn test_merge_key() {
+        let yaml = b"base: &b\n  x: 1\n  y: 2\nitem:\n  <<: *b\n  y: 3\n  z: 4\n";
+        assert_eq!(
+            json(yaml),
+            r#"{"base":{"x":1,"y":2},"item":{"x":1,"y":3,"z":4}}"#
+        );
+
+        let index = YamlIndex::build(yaml).unwrap();
+        let root = index.root(yaml).first_child().unwrap();
+        let YamlValue::Mapping(fields) = root.value() else {
+            panic!("expected mapping");
+        };
+        let Some(YamlValue::Mapping(item)) = fields.find("item") else {
+            panic!("expected item mapping");
+        };
+        assert!(matches!(item.find("x"), Some(YamlValue::Int(1))));
+        assert!(matches!(item.find("y"), Some(YamlValue::Int(3))));
+        assert!(item.find("<<").is_none());
+        assert_eq!(item.into_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_merge_sequence_and_nesting() {
+        // Earlier sources win over later ones
+        let yaml = b"a: &a {k: a}\nb: &b {k: b, m: 1}\nc: {<<: [*a, *b], n: 2}\n";
+        assert_eq!(
+            json(yaml),
+            r#"{"a":{"k":"a"},"b":{"k":"b","m":1},"c":{"k":"a","m":1,"n":2}}"#
+        );
+
+        // A merged mapping's own merge keys apply too
+        let yaml = b"a: &a {x: 1}\nb: &b\n  <<: *a\n  y: 2\nc:\n  <<: *b\n  x: 0\n";
+        assert_eq!(
+            json(yaml),
+            r#"{"a":{"x":1},"b":{"x":1,"y":2},"c":{"y":2,"x":0}}"#
+        );
+
+        // In sequence items
+        let yaml = b"d: &d {x: 1}\nlist:\n  - <<: *d\n    y: 2\n  - {<<: *d}\n";
+        assert_eq!(
+            json(yaml),
+            r#"{"d":{"x":1},"list":[{"x":1,"y":2},{"x":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_not_a_merge() {
+        // Quoted `<<` is an ordinary key, as is `<<` without aliases
+        assert_eq!(
+            json(b"a: &a {x: 1}\nb: {\"<<\": *a}\n"),
+            r#"{"a":{"x":1},"b":{"<<":{"x":1}}}"#
+        );
+        assert_eq!(json(b"<<: 1\n"), r#"{"<<":1}"#);
+        assert_eq!(json(b"a: &a 1\nb: {<<: *a}\n"), r#"{"a":1,"b":{}}"#);
+    }
+}
@@ -13,7 +13,10 @@
 //! - Chomping modifiers: strip (`-`), keep (`+`), clip (default)
 //! - Anchors (`&name`) and aliases (`*name`)
 //! - Explicit keys (`?` / `:`)
-//! - Multi-document streams (`---` / `...`), wrapped in an implicit root sequence
+//! - Merge keys (`<<: *anchor`, `<<: [*a, *b]`), applied when iterating and
+//!   searching mapping fields
+//! - Multi-document streams (`---` / `...`), wrapped in an implicit root sequence, or
+//!   indexed one document at a time with `YamlIndex::build_multi` / `build_streaming`
 //! - Comments (ignored in block context)
 //!
 //! # Not supported
@@ -21,7 +24,6 @@
 //! - Tags (`!!str`, `!custom`, verbatim `!<...>`) — rejected in block context, absorbed
 //!   as scalar text in flow context
 //! - `%YAML` / `%TAG` directives — parsed as plain scalars
-//! - Merge keys (`<<`) — parsed as an ordinary key
 //!
 //! # Validation
 //!
@@ -49,6 +51,11 @@
 //! let index_anchor = YamlIndex::build(yaml_anchor)?;
 //! ```
 //!
+//! # serde
+//!
+//! With the `serde` feature, [`serde`](crate::yaml::serde) deserializes typed values from a
+//! cursor, typing plain scalars by the YAML 1.2 core schema.
+//!
 //! # Architecture
 //!
 //! YAML parsing uses an oracle + index model:
@@ -63,20 +70,27 @@
 //! (bracket-based like JSON), anchors, aliases, and block scalars uniformly.
 
 mod advance_positions;
+mod diff;
+mod documents;
 mod end_positions;
 mod error;
 mod index;
+mod json;
 mod light;
 mod locate;
+mod merge;
 mod parser;
 mod scalar;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod simd;
 
-pub use error::YamlError;
-pub use index::YamlIndex;
+pub use diff::YamlPatchOp;
+pub use error::{ConversionError, YamlError};
+pub use index::{YamlIndex, YamlNodeType, YamlStats};
 pub use light::{
     ChompingIndicator, YamlCursor, YamlElements, YamlField, YamlFields, YamlNumber, YamlString,
     YamlValue,
 };
 pub use locate::{locate_offset, locate_offset_detailed, LocateResult};
-pub use scalar::{resolve_plain, ResolvedScalar};
+pub use scalar::{resolve_plain, resolve_yaml11_bool, ResolvedScalar};
@@ -1372,16 +1372,17 @@ impl<'a> Parser<'a> {
                 }
                 // Otherwise, value is a nested structure - main loop will handle it
             }
-        } else {
-            // Inline value
-            self.check_unsupported()?;
-
-            // Open value node
+        } else if self.peek() == Some(b'-') && matches!(self.peek_at(1), Some(b' ' | b'\t')) {
+            // `- key: - x` is not a nested sequence; keep it as text
             self.set_ib();
             self.write_bp_open();
             let end_pos = self.parse_inline_value(indent)?;
             self.set_bp_text_end(end_pos);
             self.write_bp_close();
+        } else {
+            // Inline value: anchors, aliases, flow collections and block
+            // scalars as for any other mapping value
+            self.parse_value(indent)?;
         }
 
         // Don't close the mapping here - leave it open so subsequent lines
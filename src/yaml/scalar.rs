@@ -202,6 +202,33 @@ fn parse_float(s: &str) -> ResolvedScalar {
     }
 }
 
+/// Resolves the YAML 1.1 boolean spellings the 1.2 core schema dropped:
+/// `yes`/`no` and `on`/`off`, each lowercase, capitalized or uppercase.
+///
+/// Opt-in only (see [`YamlIndex::with_yaml11_booleans`]); [`resolve_plain`]
+/// keeps these strings. The one-letter `y`/`n` forms also stay strings, as
+/// in most YAML 1.1 loaders, since they are common as plain data.
+///
+/// [`YamlIndex::with_yaml11_booleans`]: super::YamlIndex::with_yaml11_booleans
+///
+/// # Examples
+///
+/// ```
+/// use succinctly::yaml::resolve_yaml11_bool;
+///
+/// assert_eq!(resolve_yaml11_bool("Yes"), Some(true));
+/// assert_eq!(resolve_yaml11_bool("OFF"), Some(false));
+/// assert_eq!(resolve_yaml11_bool("y"), None);
+/// ```
+#[must_use]
+pub fn resolve_yaml11_bool(s: &str) -> Option<bool> {
+    match s {
+        "yes" | "Yes" | "YES" | "on" | "On" | "ON" => Some(true),
+        "no" | "No" | "NO" | "off" | "Off" | "OFF" => Some(false),
+        _ => None,
+    }
+}
+
 /// Returns true if a plain scalar could resolve to null or bool at all.
 ///
 /// A cheap pre-filter for callers that only need the null/bool answer
@@ -249,6 +276,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn yaml11_bool_spellings() {
+        for s in ["yes", "Yes", "YES", "on", "On", "ON"] {
+            assert_eq!(resolve_yaml11_bool(s), Some(true), "{s}");
+        }
+        for s in ["no", "No", "NO", "off", "Off", "OFF"] {
+            assert_eq!(resolve_yaml11_bool(s), Some(false), "{s}");
+        }
+        for s in ["y", "n", "yEs", "oN", "true", "nope", ""] {
+            assert_eq!(resolve_yaml11_bool(s), None, "{s}");
+        }
+    }
+
     #[test]
     fn decimal_ints() {
         assert_resolves("0", Int(0));
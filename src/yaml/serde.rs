@@ -0,0 +1,547 @@
+//! serde deserialization straight from a semi-indexed YAML document (`serde` feature).
+//!
+//! [`CursorDeserializer`] is the YAML counterpart of
+//! [`json::serde::CursorDeserializer`](crate::json::serde::CursorDeserializer):
+//! it drives any [`Deserialize`] type from a [`YamlCursor`] without building a
+//! DOM. Mappings feed structs and maps, sequences feed sequences and tuples,
+//! and aliases are followed to their anchors. Plain scalars are typed with the
+//! YAML 1.2 core schema (see [`resolve_plain`]), so `port: 8080` fills a `u16`
+//! and `debug: true` a `bool`; quoted and block scalars are always strings.
+//! Scalars that need no decoding are borrowed from the input, so `&str`
+//! fields work for typical config files.
+//!
+//! The root cursor of a [`YamlIndex`](super::YamlIndex) is the document
+//! stream, so deserialize a single document from its first child.
+//!
+//! # Example
+//!
+//! ```
+//! use serde::Deserialize;
+//! use succinctly::yaml::{serde::from_cursor, YamlIndex};
+//!
+//! #[derive(Deserialize)]
+//! struct Config<'a> {
+//!     name: &'a str,
+//!     port: u16,
+//!     features: Vec<String>,
+//! }
+//!
+//! let yaml = b"name: api\nport: 8080\nfeatures: [auth, 'cache']\nextra: {a: 1}\n";
+//! let index = YamlIndex::build(yaml).unwrap();
+//! let document = index.root(yaml).first_child().unwrap();
+//! let config: Config = from_cursor(document).unwrap();
+//! assert_eq!((config.name, config.port, config.features.len()), ("api", 8080, 2));
+//! ```
+
+use alloc::borrow::Cow;
+#[cfg(not(test))]
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use ::serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use ::serde::{forward_to_deserialize_any, Deserialize};
+
+use super::light::{YamlCursor, YamlElements, YamlFields, YamlStringError, YamlValue};
+use super::scalar::{resolve_plain, ResolvedScalar};
+
+/// Deserialize a `T` from the value at `cursor`.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the value doesn't have the shape `T` expects, if
+/// a string in it can't be decoded, or if it refers to an unknown anchor.
+pub fn from_cursor<'de, T, W>(cursor: YamlCursor<'de, W>) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+    W: AsRef<[u64]>,
+{
+    T::deserialize(CursorDeserializer::new(cursor))
+}
+
+/// An error from deserializing a [`YamlCursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl de::StdError for Error {}
+
+impl Error {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::new(msg.to_string())
+    }
+}
+
+impl From<YamlStringError> for Error {
+    fn from(e: YamlStringError) -> Self {
+        Self::new(e.to_string())
+    }
+}
+
+/// A scalar's decoded text and, for plain scalars, its core-schema type.
+struct Scalar<'de> {
+    text: Cow<'de, str>,
+    resolved: ResolvedScalar,
+}
+
+impl<'de> Scalar<'de> {
+    fn visit_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.text {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
+    }
+}
+
+/// A serde [`Deserializer`] over the value at a [`YamlCursor`].
+///
+/// Plain scalars resolve to null, bool, `i64`, `f64` or string by the YAML
+/// 1.2 core schema; quoted and block scalars are strings. A type that asks
+/// for a string (`String`, `&str`, `char`, map keys) gets the scalar's text
+/// whatever it resolves to, so `version: 1.10` can fill a `String` field.
+/// Null is `None` for options and `()` for units.
+pub struct CursorDeserializer<'de, W = alloc::vec::Vec<u64>> {
+    value: YamlValue<'de, W>,
+}
+
+impl<'de, W: AsRef<[u64]>> CursorDeserializer<'de, W> {
+    /// Create a deserializer for the value at `cursor`.
+    ///
+    /// Scalars are read as text whatever the index's
+    /// [`type_inference`](crate::yaml::YamlIndex::type_inference) setting,
+    /// since the target type decides how they resolve.
+    pub fn new(cursor: YamlCursor<'de, W>) -> Self {
+        Self {
+            value: cursor.raw_value(),
+        }
+    }
+
+    fn from_value(value: YamlValue<'de, W>) -> Self {
+        Self { value }
+    }
+
+    /// Follow aliases until a concrete value is reached.
+    fn resolve(self) -> Result<YamlValue<'de, W>, Error> {
+        let mut value = self.value;
+        while let YamlValue::Alias {
+            anchor_name,
+            target,
+        } = value
+        {
+            value = target
+                .ok_or_else(|| Error::new(alloc::format!("unknown anchor '{anchor_name}'")))?
+                .raw_value();
+        }
+        Ok(value)
+    }
+
+    /// Decode a scalar, or hand a non-scalar back to the caller.
+    fn scalar(value: YamlValue<'de, W>) -> Result<Result<Scalar<'de>, YamlValue<'de, W>>, Error> {
+        match value {
+            YamlValue::Null => Ok(Ok(Scalar {
+                text: Cow::Borrowed(""),
+                resolved: ResolvedScalar::Null,
+            })),
+            YamlValue::String(s) => {
+                let text = s.as_str()?;
+                let resolved = if s.is_unquoted() {
+                    resolve_plain(&text)
+                } else {
+                    ResolvedScalar::Str
+                };
+                Ok(Ok(Scalar { text, resolved }))
+            }
+            YamlValue::Error(message) => Err(Error::new(message)),
+            other => Ok(Err(other)),
+        }
+    }
+
+    fn is_null(value: &YamlValue<'de, W>) -> bool {
+        match value {
+            YamlValue::Null => true,
+            YamlValue::String(s) if s.is_unquoted() => s
+                .as_str()
+                .is_ok_and(|text| resolve_plain(&text) == ResolvedScalar::Null),
+            _ => false,
+        }
+    }
+
+    fn deserialize_string_like<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match Self::scalar(self.resolve()?)? {
+            Ok(scalar) if scalar.resolved != ResolvedScalar::Null => scalar.visit_str(visitor),
+            Ok(_) => visitor.visit_unit(),
+            Err(value) => Self::from_value(value).deserialize_any(visitor),
+        }
+    }
+}
+
+impl<'de, W: AsRef<[u64]>> Deserializer<'de> for CursorDeserializer<'de, W> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match Self::scalar(self.resolve()?)? {
+            Ok(scalar) => match scalar.resolved {
+                ResolvedScalar::Null => visitor.visit_unit(),
+                ResolvedScalar::Bool(b) => visitor.visit_bool(b),
+                ResolvedScalar::Int(n) => visitor.visit_i64(n),
+                ResolvedScalar::Float(f) => visitor.visit_f64(f),
+                ResolvedScalar::Str => scalar.visit_str(visitor),
+            },
+            Err(YamlValue::Sequence(elements)) => visitor.visit_seq(Elements { elements }),
+            Err(YamlValue::Mapping(fields)) => visitor.visit_map(Fields {
+                fields,
+                value: None,
+            }),
+            Err(_) => Err(Error::new("unexpected YAML value")),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string_like(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string_like(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string_like(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string_like(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.resolve()?;
+        if Self::is_null(&value) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(Self::from_value(value))
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enums are externally tagged: a unit variant is a scalar, any other
+    /// variant a mapping with a single key.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.resolve()? {
+            tag @ YamlValue::String(_) => visitor.visit_enum(Variant { tag, content: None }),
+            YamlValue::Mapping(mut fields) => match (fields.next(), fields.next()) {
+                (Some(field), None) => visitor.visit_enum(Variant {
+                    tag: field.key(),
+                    content: Some(field.value_cursor().raw_value()),
+                }),
+                _ => Err(Error::new(
+                    "expected a mapping with a single key for an enum variant",
+                )),
+            },
+            _ => Err(Error::new("expected a scalar or a mapping for an enum")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // The index already knows where this value ends; nothing to walk
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+    }
+}
+
+/// Sequence elements as a serde sequence.
+struct Elements<'de, W> {
+    elements: YamlElements<'de, W>,
+}
+
+impl<'de, W: AsRef<[u64]>> SeqAccess<'de> for Elements<'de, W> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some((cursor, rest)) = self.elements.uncons_cursor() else {
+            return Ok(None);
+        };
+        self.elements = rest;
+        seed.deserialize(CursorDeserializer::new(cursor)).map(Some)
+    }
+}
+
+/// Mapping entries as a serde map.
+struct Fields<'de, W> {
+    fields: YamlFields<'de, W>,
+    /// Value of the key most recently returned by `next_key_seed`
+    value: Option<YamlValue<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> MapAccess<'de> for Fields<'de, W> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some(field) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.value = Some(field.value_cursor().raw_value());
+        seed.deserialize(CursorDeserializer::from_value(field.key()))
+            .map(Some)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::new("map value requested before its key"))?;
+        seed.deserialize(CursorDeserializer::from_value(value))
+    }
+}
+
+/// An enum variant: its tag (a scalar) and, for non-unit variants, the value
+/// under that tag.
+struct Variant<'de, W> {
+    tag: YamlValue<'de, W>,
+    content: Option<YamlValue<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> EnumAccess<'de> for Variant<'de, W> {
+    type Error = Error;
+    type Variant = VariantContent<'de, W>;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<(T::Value, Self::Variant), Error> {
+        let tag = seed.deserialize(CursorDeserializer::from_value(self.tag))?;
+        Ok((
+            tag,
+            VariantContent {
+                content: self.content,
+            },
+        ))
+    }
+}
+
+/// The value under an enum variant's tag, `None` for a bare scalar tag.
+struct VariantContent<'de, W> {
+    content: Option<YamlValue<'de, W>>,
+}
+
+impl<'de, W: AsRef<[u64]>> VariantAccess<'de> for VariantContent<'de, W> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.content {
+            None => Ok(()),
+            Some(value) if CursorDeserializer::is_null(&value) => Ok(()),
+            Some(_) => Err(Error::new("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.content {
+            Some(value) => seed.deserialize(CursorDeserializer::from_value(value)),
+            None => seed.deserialize(().into_deserializer()),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.content_deserializer()?.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.content_deserializer()?.deserialize_map(visitor)
+    }
+}
+
+impl<'de, W: AsRef<[u64]>> VariantContent<'de, W> {
+    fn content_deserializer(self) -> Result<CursorDeserializer<'de, W>, Error> {
+        self.content
+            .map(CursorDeserializer::from_value)
+            .ok_or_else(|| Error::new("expected a tuple or struct variant, found a scalar"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::YamlIndex;
+    use std::collections::BTreeMap;
+
+    /// Deserialize the first document of `yaml`.
+    fn parse<'a, T: Deserialize<'a>>(index: &'a YamlIndex, yaml: &'a [u8]) -> Result<T, Error> {
+        from_cursor(index.root(yaml).first_child().expect("a document"))
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        tls: bool,
+        ratio: f64,
+        backup: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Off,
+        Fixed(u32),
+        Range { low: u32, high: u32 },
+    }
+
+    #[test]
+    fn test_derived_struct_with_core_schema_scalars() {
+        let yaml = b"host: localhost\nport: 0x1F90\ntls: True\nratio: .5\nbackup: ~\nignored:\n  - deep: [1, 2]\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let server: Server = parse(&index, yaml).unwrap();
+        assert_eq!(
+            server,
+            Server {
+                host: "localhost".to_string(),
+                port: 8080,
+                tls: true,
+                ratio: 0.5,
+                backup: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_quoted_scalars_stay_strings() {
+        let yaml = b"port: '8080'\n";
+        let index = YamlIndex::build(yaml).unwrap();
+
+        assert!(parse::<BTreeMap<String, u16>>(&index, yaml).is_err());
+
+        // A string field takes a plain scalar's text whatever it resolves to
+        let yaml = b"a: 1.10\nb: \"true\"\nc: null-ish\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let map: BTreeMap<String, String> = parse(&index, yaml).unwrap();
+        assert_eq!(map["a"], "1.10");
+        assert_eq!(map["b"], "true");
+        assert_eq!(map["c"], "null-ish");
+    }
+
+    #[test]
+    fn test_borrowed_and_decoded_strings() {
+        #[derive(Deserialize)]
+        struct Names<'a> {
+            plain: &'a str,
+            quoted: &'a str,
+            escaped: Cow<'a, str>,
+        }
+
+        let yaml = b"plain: zero copy\nquoted: 'single'\nescaped: \"tab\\there\"\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let names: Names<'_> = parse(&index, yaml).unwrap();
+        assert_eq!(names.plain, "zero copy");
+        assert_eq!(names.quoted, "single");
+        assert_eq!(names.escaped, "tab\there");
+
+        // A string with escapes can't be borrowed
+        let yaml = b"plain: a\nquoted: \"b\\nc\"\nescaped: d\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        assert!(parse::<Names<'_>>(&index, yaml).is_err());
+    }
+
+    #[test]
+    fn test_sequences_tuples_and_maps() {
+        let yaml = b"list:\n  - 1\n  - 2\npair: [a, false]\nmap: {k: [~, 3]}\nblock: |\n  line\n";
+        let index = YamlIndex::build(yaml).unwrap();
+
+        #[derive(Deserialize)]
+        struct All {
+            list: Vec<u8>,
+            pair: (String, bool),
+            map: BTreeMap<String, Vec<Option<u64>>>,
+            block: String,
+        }
+        let all: All = parse(&index, yaml).unwrap();
+        assert_eq!(all.list, [1, 2]);
+        assert_eq!(all.pair, ("a".to_string(), false));
+        assert_eq!(all.map["k"], [None, Some(3)]);
+        assert_eq!(all.block, "line\n");
+    }
+
+    #[test]
+    fn test_aliases() {
+        let yaml =
+            b"base: &base {host: db, port: 5432, tls: false, ratio: 1, backup: ~}\ncopy: *base\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let servers: BTreeMap<&str, Server> = parse(&index, yaml).unwrap();
+        assert_eq!(servers["base"], servers["copy"]);
+        assert_eq!(servers["copy"].port, 5432);
+    }
+
+    #[test]
+    fn test_enums() {
+        let yaml = b"- Off\n- Fixed: 3\n- Range:\n    low: 1\n    high: 9\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let modes: Vec<Mode> = parse(&index, yaml).unwrap();
+        assert_eq!(
+            modes,
+            [Mode::Off, Mode::Fixed(3), Mode::Range { low: 1, high: 9 }]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let yaml = b"host: h\nport: big\ntls: false\nratio: 1\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let err = parse::<Server>(&index, yaml).unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{err}");
+
+        let yaml = b"host: h\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let err = parse::<Server>(&index, yaml).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `port`");
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        // The root cursor is the stream, a sequence of documents
+        let yaml = b"---\nn: 1\n---\nn: 2\n";
+        let index = YamlIndex::build(yaml).unwrap();
+        let documents: Vec<BTreeMap<String, i64>> = from_cursor(index.root(yaml)).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1]["n"], 2);
+    }
+}
@@ -18,6 +18,8 @@
 
 use core::arch::aarch64::*;
 
+use crate::util::simd::scan;
+
 /// Extract a bitmask from the high bit of each byte in a NEON vector.
 /// Returns a u16 where bit i is set if byte i has its high bit set.
 ///
@@ -45,46 +47,7 @@ unsafe fn neon_movemask(v: uint8x16_t) -> u16 {
 /// Returns offset from `start` to the found character, or `None` if not found.
 #[inline]
 pub fn find_quote_or_escape_neon(input: &[u8], start: usize, end: usize) -> Option<usize> {
-    // SAFETY: We check bounds and target_arch = aarch64 guarantees NEON
-    unsafe { find_quote_or_escape_neon_impl(input, start, end) }
-}
-
-#[target_feature(enable = "neon")]
-unsafe fn find_quote_or_escape_neon_impl(input: &[u8], start: usize, end: usize) -> Option<usize> {
-    let len = end - start;
-    let data = &input[start..end];
-    let mut offset = 0;
-
-    // Process 16-byte chunks
-    let quote_vec = vdupq_n_u8(b'"');
-    let backslash_vec = vdupq_n_u8(b'\\');
-
-    while offset + 16 <= len {
-        let chunk = vld1q_u8(data.as_ptr().add(offset));
-
-        // Compare against both targets
-        let quotes = vceqq_u8(chunk, quote_vec);
-        let backslashes = vceqq_u8(chunk, backslash_vec);
-
-        // OR the results
-        let matches = vorrq_u8(quotes, backslashes);
-
-        // Extract bitmask
-        let mask = neon_movemask(matches);
-
-        if mask != 0 {
-            // Found a match - return position of first match
-            return Some(offset + mask.trailing_zeros() as usize);
-        }
-
-        offset += 16;
-    }
-
-    // Handle remaining bytes with iterator
-    data[offset..]
-        .iter()
-        .position(|&b| b == b'"' || b == b'\\')
-        .map(|pos| offset + pos)
+    scan::find_first_of_2(&input[start..end], b'"', b'\\')
 }
 
 /// Find the next single-quote using NEON.
@@ -92,41 +55,7 @@ unsafe fn find_quote_or_escape_neon_impl(input: &[u8], start: usize, end: usize)
 /// Returns offset from `start` to the found character, or `None` if not found.
 #[inline]
 pub fn find_single_quote_neon(input: &[u8], start: usize, end: usize) -> Option<usize> {
-    // SAFETY: We check bounds and target_arch = aarch64 guarantees NEON
-    unsafe { find_single_quote_neon_impl(input, start, end) }
-}
-
-#[target_feature(enable = "neon")]
-unsafe fn find_single_quote_neon_impl(input: &[u8], start: usize, end: usize) -> Option<usize> {
-    let len = end - start;
-    let data = &input[start..end];
-    let mut offset = 0;
-
-    // Process 16-byte chunks
-    let quote_vec = vdupq_n_u8(b'\'');
-
-    while offset + 16 <= len {
-        let chunk = vld1q_u8(data.as_ptr().add(offset));
-
-        // Compare against single quote
-        let matches = vceqq_u8(chunk, quote_vec);
-
-        // Extract bitmask
-        let mask = neon_movemask(matches);
-
-        if mask != 0 {
-            // Found a match - return position of first match
-            return Some(offset + mask.trailing_zeros() as usize);
-        }
-
-        offset += 16;
-    }
-
-    // Handle remaining bytes with iterator
-    data[offset..]
-        .iter()
-        .position(|&b| b == b'\'')
-        .map(|pos| offset + pos)
+    scan::find_first_of_2(&input[start..end], b'\'', b'\'')
 }
 
 /// Count leading spaces (indentation) using NEON.
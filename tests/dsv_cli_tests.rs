@@ -1,4 +1,5 @@
-//! Integration tests for the `succinctly dsv generate` CLI command.
+//! Integration tests for the `succinctly dsv generate` and `dsv inspect` CLI
+//! commands.
 //!
 //! Regression coverage for issue #180: `--header` was inferred as a bare
 //! `SetTrue` flag with `default_value = "true"`, making it a no-op with no way
@@ -8,6 +9,7 @@
 //!
 //! Run with: cargo test --test dsv_cli_tests
 
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::OnceLock;
@@ -127,3 +129,99 @@ fn header_then_no_header_last_wins() -> Result<()> {
     );
     Ok(())
 }
+
+/// Run `dsv inspect` on `input` from stdin and capture stdout, stderr, and exit code.
+fn run_inspect(input: &str, extra_args: &[&str]) -> Result<(String, String, i32)> {
+    let mut child = Command::new(succinctly_bin())
+        .args(["dsv", "inspect"])
+        .args(extra_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    let exit_code = output.status.code().unwrap_or(-1);
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    Ok((stdout, stderr, exit_code))
+}
+
+#[test]
+fn inspect_detects_delimiter_and_summarizes_columns() -> Result<()> {
+    let tsv = "name\tscore\tnote\n\"Smith, J\"\t10\ta,b\nAdams\t2.5\t\n";
+    let (stdout, stderr, exit_code) = run_inspect(tsv, &[])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert!(
+        stdout.starts_with("delimiter: \\t (detected)\nrows: 2\ncolumns: 3\n"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "\n[2] score\n  cells: 2, empty: 0\n  types: int 1, float 1, bool 0, string 0\n"
+        ),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("  samples: \"Smith, J\", \"Adams\"\n"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("\n[3] note\n  cells: 2, empty: 1\n"),
+        "stdout: {stdout}"
+    );
+    Ok(())
+}
+
+#[test]
+fn inspect_json_output() -> Result<()> {
+    let csv = "id;ok\n1;yes\n2;no\n3;\n";
+    let (stdout, stderr, exit_code) = run_inspect(csv, &["--json-output", "--samples", "1"])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    let summary: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(summary["delimiter"], ";");
+    assert_eq!(summary["delimiter_detected"], true);
+    assert_eq!(summary["delimiter_source"], "detected");
+    assert_eq!(summary["rows"], 3);
+    assert_eq!(summary["columns"], 2);
+    let id = &summary["column_stats"][0];
+    assert_eq!(id["name"], "id");
+    assert_eq!(id["types"]["int"], 3);
+    assert_eq!(id["numeric"]["mean"], 2.0);
+    assert_eq!(id["samples"], serde_json::json!(["1"]));
+    let ok = &summary["column_stats"][1];
+    assert_eq!((&ok["types"]["bool"], &ok["empty"]), (&2.into(), &1.into()));
+    assert!(ok["numeric"].is_null());
+    Ok(())
+}
+
+#[test]
+fn inspect_explicit_delimiter_without_header() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_inspect("a|b,c\nd|e,f\n", &["-d", ",", "--no-header"])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert!(
+        stdout.starts_with("delimiter: ,\nrows: 2\ncolumns: 2\n\n[1] column 1\n"),
+        "stdout: {stdout}"
+    );
+    Ok(())
+}
+
+#[test]
+fn inspect_empty_input_reports_default_delimiter() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_inspect("", &[])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert_eq!(
+        stdout,
+        "delimiter: , (default; could not detect)\nrows: 0\ncolumns: 0\n"
+    );
+
+    let (stdout, stderr, exit_code) = run_inspect("", &["--json-output"])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    let summary: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(summary["delimiter_detected"], false);
+    assert_eq!(summary["delimiter_source"], "default");
+    Ok(())
+}
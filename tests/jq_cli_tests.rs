@@ -463,6 +463,66 @@ fn test_custom_indent() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_validate_rejects_invalid_input() -> Result<()> {
+    let mut cmd = Command::new("cargo")
+        .args([
+            "run",
+            "--features",
+            "cli",
+            "--bin",
+            "succinctly",
+            "--",
+            "jq",
+            "--validate",
+            ".",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = cmd.stdin.take() {
+        stdin.write_all(br#"{"a": [1,}"#)?;
+    }
+    let output = cmd.wait_with_output()?;
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("validation error"), "stderr: {stderr}");
+    // Position is reported as file:line:column
+    assert!(stderr.contains("<stdin>:1:10"), "stderr: {stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_invalid_input_exit_status() -> Result<()> {
+    // Values before the invalid input are still processed, as in jq
+    let (output, code) = run_jq_stdin(".a", r#"{"a":1}}"#, &[])?;
+    assert_eq!(code, 2);
+    assert_eq!(output, "1\n");
+    let (output, code) = run_jq_stdin(".a", "nul", &[])?;
+    assert_eq!(code, 2);
+    assert_eq!(output, "");
+    let (output, code) = run_jq_stdin(".", "1 2 [3", &["-c"])?;
+    assert_eq!(code, 2);
+    assert_eq!(output, "1\n2\n");
+
+    // --lenient skips what does not parse
+    let (output, code) = run_jq_stdin(".a", r#"{"a":1}}"#, &["--lenient"])?;
+    assert_eq!(code, 0);
+    assert_eq!(output, "1\n");
+    Ok(())
+}
+
+#[test]
+fn test_validate_accepts_valid_input() -> Result<()> {
+    let (output, code) = run_jq_stdin(".a", r#"{"a": [1, 2]}"#, &["--validate", "-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(output, "[1,2]\n");
+    Ok(())
+}
+
 // =============================================================================
 // Variable Tests
 // =============================================================================
@@ -850,8 +910,6 @@ fn test_builtin_first_last_on_empty_array_output_null() -> Result<()> {
 #[test]
 fn test_builtin_first_on_non_array_errors() -> Result<()> {
     // jq: 5 | first => error. The error goes to stderr and nothing to stdout.
-    // (Runtime eval errors do not yet set jq's exit code 5 -- see
-    // exit_codes in jq_runner.rs -- so the exit status is not asserted.)
     let mut cmd = Command::new("cargo")
         .args([
             "run",
@@ -873,6 +931,7 @@ fn test_builtin_first_on_non_array_errors() -> Result<()> {
     }
     let output = cmd.wait_with_output()?;
 
+    assert_eq!(output.status.code(), Some(5));
     let stdout = String::from_utf8(output.stdout)?;
     let stderr = String::from_utf8(output.stderr)?;
     assert_eq!(stdout.trim(), "");
@@ -883,6 +942,22 @@ fn test_builtin_first_on_non_array_errors() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_runtime_error_exit_status() -> Result<()> {
+    // Like jq 1.7, later inputs are still processed and the exit status is 5
+    let (output, code) = run_jq_stdin(". + 1", r#"1 "a" 2"#, &[])?;
+    assert_eq!(code, 5);
+    assert_eq!(output, "2\n3\n");
+    let (output, code) = run_jq_stdin(". + 1", r#""a""#, &["-S"])?;
+    assert_eq!(code, 5);
+    assert_eq!(output, "");
+
+    let (output, code) = run_jq_stdin(". + 1", r#"1 "a" 2"#, &["--lenient"])?;
+    assert_eq!(code, 0);
+    assert_eq!(output, "2\n3\n");
+    Ok(())
+}
+
 #[test]
 fn test_builtin_keys() -> Result<()> {
     let (output, code) = run_jq_stdin("keys", r#"{"z":1,"a":2}"#, &["-c"])?;
@@ -975,6 +1050,14 @@ fn test_try_catch() -> Result<()> {
     assert_eq!(code, 0);
     assert_eq!(output.trim(), "error");
 
+    // Outputs before the error are kept
+    let (output, code) = run_jq_stdin(r#"try (1, error("x"), 3) catch ."#, "null", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(output, "1\n\"x\"\n");
+    let (output, code) = run_jq_stdin(r#"[.[] | (., error("x"))?]"#, "[1,2]", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(output.trim(), "[1,2]");
+
     Ok(())
 }
 
@@ -1817,6 +1900,41 @@ fn test_import_with_namespace() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_import_data() -> Result<()> {
+    // A data file holding two JSON values, imported as an array of both
+    let temp_dir = tempfile::tempdir()?;
+    std::fs::write(
+        temp_dir.path().join("limits.json"),
+        r#"{"max": 3} {"max": 5}"#,
+    )?;
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--features",
+            "cli",
+            "--bin",
+            "succinctly",
+            "--",
+            "jq",
+            "-n",
+            "-c",
+            "-L",
+        ])
+        .arg(temp_dir.path())
+        .arg(r#"import "limits" as $limits; [$limits[].max, $limits::limits[1].max]"#)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(output.status.success(), "data import failed: {stderr}");
+    assert_eq!(stdout.trim(), "[3,5,5]");
+    Ok(())
+}
+
 // =============================================================================
 // JSON Sequence Format (RFC 7464) Tests
 // =============================================================================
@@ -2079,3 +2197,55 @@ fn test_collect_iterator_pipe_issue_295() -> Result<()> {
 
     Ok(())
 }
+
+// `limit` and `first` must stop an infinite `recurse` even when an earlier
+// pipe stage feeds it. Expected outputs match jq-1.7.1.
+#[test]
+fn test_recurse_behind_pipe_stage_terminates() -> Result<()> {
+    let (out, code) = run_jq_null("first(1 | recurse(. + 1))", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out.trim(), "1");
+
+    let (out, code) = run_jq_null("[limit(3; 1 | recurse(. + 1))]", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out.trim(), "[1,2,3]");
+
+    let (out, code) = run_jq_stdin("[limit(3; .a | recurse(. + 1))]", r#"{"a":5}"#, &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out.trim(), "[5,6,7]");
+    Ok(())
+}
+
+// `select` emits its input once per truthy output of the condition, as jq-1.7.1
+// does, so falsy outputs drop nothing and several truthy ones repeat it.
+#[test]
+fn test_select_multiple_condition_outputs() -> Result<()> {
+    let (out, code) = run_jq_stdin(".[] | select(. > 1, . > 2)", "[3,1,2]", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out, "3\n3\n2\n");
+
+    let (out, code) = run_jq_stdin("[.[] | select(false, null)]", "[3,1,2]", &["-c"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out.trim(), "[]");
+    Ok(())
+}
+
+// jq writes the infinities as the largest finite double and NaN as null.
+// Expected outputs match jq-1.7.1.
+#[test]
+fn test_non_finite_output() -> Result<()> {
+    let (out, code) = run_jq_null(
+        "infinite, -infinite, [infinite, nan], (infinite | tostring)",
+        &["-c"],
+    )?;
+    assert_eq!(code, 0);
+    assert_eq!(
+        out,
+        "1.7976931348623157e+308\n-1.7976931348623157e+308\n[1.7976931348623157e+308,null]\n\"1.7976931348623157e+308\"\n"
+    );
+
+    let (out, code) = run_jq_null("[infinite]", &["-S"])?;
+    assert_eq!(code, 0);
+    assert_eq!(out, "[\n  1.7976931348623157e+308\n]\n");
+    Ok(())
+}
@@ -1240,6 +1240,354 @@ fn test_split_consecutive_delimiters() {
     );
 }
 
+#[test]
+fn test_split_join_round_trip() {
+    // jq: "a,b,,c" | split(",") => ["a","b","","c"]
+    query!(br#""a,b,,c""#, r#"split(",")"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"["a","b","","c"]"#)
+    );
+    // jq: "" | split(",") => []
+    query!(br#""""#, r#"split(",")"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[]")
+    );
+    // Leading, trailing and multi-character separators survive the round trip
+    for input in [",a,,b,", "a::b::::c", "::", "abc"] {
+        for sep in [",", "::"] {
+            let json = format!("\"{input}\"");
+            query!(json.as_bytes(), &format!(r#"split("{sep}") | join("{sep}")"#),
+                QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, input)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_split_errors() {
+    // jq: 1 | split(",") => error
+    query!(b"1", r#"split(",")"#, QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), "split input and separator must be strings");
+    });
+    query!(br#""a""#, "split(1)", QueryResult::Error(_) => {});
+}
+
+#[test]
+fn test_join_element_types() {
+    // jq: ["a","b","c"] | join("-") => "a-b-c"
+    query!(br#"["a", "b", "c"]"#, r#"join("-")"#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "a-b-c")
+    );
+    // jq: [1,null,"a",true,1.5] | join("-") => "1--a-true-1.5"
+    query!(br#"[1, null, "a", true, 1.5]"#, r#"join("-")"#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "1--a-true-1.5")
+    );
+    // jq: [] | join("-") => ""
+    query!(b"[]", r#"join("-")"#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert!(s.is_empty())
+    );
+    // jq: [[1]] | join(",") => error
+    query!(b"[[1]]", r#"join(",")"#, QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), "Cannot join with array");
+    });
+    query!(br#"{"a": "b"}"#, r#"join(",")"#, QueryResult::Error(_) => {});
+}
+
+// =============================================================================
+// Compatibility tests - indices/index/rindex
+// =============================================================================
+
+#[test]
+fn test_indices_string_byte_offsets() {
+    // jq 1.7: offsets are in bytes, and occurrences may overlap
+    query!(br#""a,b, cd, efg""#, r#"indices(", ")"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[3,7]")
+    );
+    query!(br#""aaa""#, r#"indices("aa")"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[0,1]")
+    );
+    query!("\"\u{e9}x\u{e9}x\"".as_bytes(), r#"[indices("x"), index("x"), rindex("x")]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[[2,5],2,5]")
+    );
+    query!(br#""abc""#, r#"[indices(""), index(""), index("z"), rindex("z")]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[[],null,null,null]")
+    );
+}
+
+#[test]
+fn test_indices_arrays() {
+    // jq: [0,1,2,1,3,1,2] | indices(1) => [1,3,5]
+    query!(b"[0,1,2,1,3,1,2]", "indices(1)",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,3,5]")
+    );
+    // jq: [0,1,2,1,3,1,2] | indices([1,2]) => [1,5] (subarray positions)
+    query!(b"[0,1,2,1,3,1,2]", "indices([1,2])",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,5]")
+    );
+    query!(b"[0,1,2,1,3,1,2]", "[index([1,2]), rindex([1,2]), indices([])]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,5,[]]")
+    );
+}
+
+#[test]
+fn test_indices_null_and_errors() {
+    // jq: null | indices("a") => null
+    query!(b"null", r#"[indices("a"), index("a"), rindex("a")]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[null,null,null]")
+    );
+    query!(br#""abc""#, "indices(1)", QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), "Cannot index string with number");
+    });
+}
+
+// =============================================================================
+// Compatibility tests - any/all with generators and conditions
+// =============================================================================
+
+#[test]
+fn test_any_all_with_condition() {
+    // jq: [1,2] | any(. > 1), all(. > 1) => true, false
+    query!(b"[1, 2]", "[any(. > 1), all(. > 1)]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+    // jq: {"a":true,"b":false} | any, all => true, false (objects iterate values)
+    query!(br#"{"a": true, "b": false}"#, "[any, all]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+    // jq: [] | any(.), all(.) => false, true
+    query!(b"[]", "[any(.), all(.)]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[false,true]")
+    );
+}
+
+#[test]
+fn test_any_all_with_generator() {
+    // jq: any(.[]; . == 4), all(.[]; . > 3) over [3,4] => true, false
+    query!(b"[3, 4]", "[any(.[]; . == 4), all(.[]; . > 3), any(empty; .), all(empty; .)]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false,false,true]")
+    );
+    // A generating condition counts if any of its outputs decides
+    query!(b"null", "[any(1, 2; false, . == 2), all(1, 2; true, . == 2)]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+}
+
+#[test]
+fn test_any_all_short_circuit() {
+    // jq: any(range(1000000); . == 5) => true, without running the other iterations
+    query!(b"null", "any(range(1000000); . == 5)",
+        QueryResult::Owned(OwnedValue::Bool(true)) => {}
+    );
+    query!(
+        b"null",
+        r#"any(range(1000000); if . > 5 then error("evaluated past the match") else . == 5 end)"#,
+        QueryResult::Owned(OwnedValue::Bool(true)) => {}
+    );
+    query!(
+        b"null",
+        r#"all(range(1000000); if . > 5 then error("evaluated past the match") else . < 5 end)"#,
+        QueryResult::Owned(OwnedValue::Bool(false)) => {}
+    );
+    // jq: any(1, error("x"); . == 1) => true
+    query!(b"[1, 2]", r#"[any(1, error("x"); . == 1), any(.[], error("x"); . == 1)]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,true]")
+    );
+    // Errors reached before the answer is known still propagate
+    query!(b"null", r#"any(error("x"), 1; . == 1)"#, QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), "x");
+    });
+}
+
+// =============================================================================
+// Compatibility tests - tojson/fromjson
+// =============================================================================
+
+#[test]
+fn test_tojson_fromjson_round_trip() {
+    // jq: {"a":1} | tojson | fromjson == {"a":1} => true
+    query!(br#"{"a": 1}"#, r#"tojson | fromjson == {"a": 1}"#,
+        QueryResult::Owned(OwnedValue::Bool(true)) => {}
+    );
+    // jq: [1,"a",null,{"b":[true]}] | tojson => "[1,\"a\",null,{\"b\":[true]}]"
+    query!(br#"[1, "a", null, {"b": [true]}]"#, "tojson",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, r#"[1,"a",null,{"b":[true]}]"#)
+    );
+    // Serialized JSON embedded in a field
+    query!(br#"{"payload": "{\"id\": 7}"}"#, ".payload | fromjson | .id",
+        QueryResult::Owned(OwnedValue::Int(7)) => {}
+    );
+}
+
+#[test]
+fn test_fromjson_errors() {
+    // jq: 1 | fromjson => error (only strings parse)
+    query!(b"1", "fromjson", QueryResult::Error(_) => {});
+    // jq: "{" | fromjson => error naming the text being parsed
+    query!(br#""{""#, "fromjson", QueryResult::Error(e) => {
+        assert!(e.to_string().contains("(while parsing '{')"), "{e}");
+    });
+}
+
+// =============================================================================
+// Compatibility tests - contains/inside
+// =============================================================================
+
+#[test]
+fn test_contains_by_type() {
+    let cases: &[(&[u8], &str, bool)] = &[
+        // Scalars compare by equality
+        (b"null", "contains(null)", true),
+        (b"true", "contains(true)", true),
+        (b"1", "contains(1)", true),
+        (b"1", "contains(2)", false),
+        // Strings by substring
+        (br#""foobar""#, r#"contains("bar")"#, true),
+        (br#""foobar""#, r#"contains("")"#, true),
+        (br#""foobar""#, r#"contains("baz")"#, false),
+        // jq: ["foobar","foobaz","blarp"] | contains(["baz","bar"]) => true
+        (
+            br#"["foobar", "foobaz", "blarp"]"#,
+            r#"contains(["baz", "bar"])"#,
+            true,
+        ),
+        (
+            br#"["foobar", "foobaz", "blarp"]"#,
+            r#"contains(["bazzzzz", "bar"])"#,
+            false,
+        ),
+        (b"[1, 2]", "contains([])", true),
+        (b"[]", "contains([1])", false),
+        (b"[[1, 2], [3]]", "contains([[1], [3]])", true),
+        // jq manual: nested objects match key by key
+        (
+            br#"{"foo": 12, "bar": [1, 2, {"barp": 12, "blip": 13}]}"#,
+            "contains({foo: 12, bar: [{barp: 12}]})",
+            true,
+        ),
+        (
+            br#"{"foo": 12, "bar": [1, 2, {"barp": 12, "blip": 13}]}"#,
+            "contains({foo: 12, bar: [{barp: 15}]})",
+            false,
+        ),
+        (br#"{"a": 1}"#, "contains({})", true),
+        (br#"{"a": 1}"#, r#"contains({"b": 1})"#, false),
+        // Mismatched types below the top level are simply not contained
+        (br#"{"a": 1}"#, r#"contains({"a": "1"})"#, false),
+        (b"[1]", r#"contains(["1"])"#, false),
+        (br#"{"a": true}"#, r#"contains({"a": false})"#, false),
+    ];
+    for &(json, filter, expected) in cases {
+        query!(json, filter, QueryResult::Owned(OwnedValue::Bool(b)) => {
+            assert_eq!(b, expected, "{} | {filter}", String::from_utf8_lossy(json));
+        });
+    }
+}
+
+#[test]
+fn test_contains_differs_from_equality() {
+    // Containment is looser than ==: partial strings, arrays and objects match
+    query!(br#""foobar""#, r#"[contains("foo"), . == "foo"]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+    query!(br#"{"a": [1, 2], "b": 0}"#, r#"[contains({"a": [1]}), . == {"a": [1]}]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+    // Duplicates and order in the needle don't matter
+    query!(b"[1, 2]", "[contains([2, 1, 1]), . == [2, 1, 1]]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false]")
+    );
+}
+
+#[test]
+fn test_contains_type_errors() {
+    // jq: 1 | contains("a") => error
+    query!(b"1", r#"contains("a")"#, QueryResult::Error(e) => {
+        assert_eq!(
+            e.to_string(),
+            r#"number (1) and string ("a") cannot have their containment checked"#
+        );
+    });
+    // jq treats true and false as different kinds here
+    query!(b"true", "contains(false)", QueryResult::Error(e) => {
+        assert_eq!(
+            e.to_string(),
+            "boolean (true) and boolean (false) cannot have their containment checked"
+        );
+    });
+    query!(b"[1]", "contains({})", QueryResult::Error(_) => {});
+    query!(b"1", r#"contains("a")?"#, QueryResult::None => {});
+}
+
+#[test]
+fn test_inside() {
+    // jq: "bar" | inside("foobar") => true
+    query!(br#""bar""#, r#"inside("foobar")"#, QueryResult::Owned(OwnedValue::Bool(true)) => {});
+    query!(br#"{"foo": 12}"#, r#"inside({"foo": 12, "bar": 1})"#,
+        QueryResult::Owned(OwnedValue::Bool(true)) => {}
+    );
+    query!(b"[1, 3]", "inside([1, 2])", QueryResult::Owned(OwnedValue::Bool(false)) => {});
+    // inside(b) is b | contains(.), so b comes first in the error
+    query!(br#"{"a": 1}"#, r#"inside("x")"#, QueryResult::Error(e) => {
+        assert_eq!(
+            e.to_string(),
+            r#"string ("x") and object ({"a":1}) cannot have their containment checked"#
+        );
+    });
+}
+
+#[test]
+fn test_contains_generator_argument() {
+    // jq: [1, 2] | [contains([1], [3])] => [true, false]
+    query!(b"[1, 2]", "[contains([1], [3]), inside([1, 2, 3], [2])]",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[true,false,true,false]")
+    );
+}
+
+// =============================================================================
+// Compatibility tests - getpath/setpath/delpaths
+// =============================================================================
+
+#[test]
+fn test_getpath_negative_and_missing() {
+    // jq: {"a":{"b":[1,2,3]}} | [getpath(["a","b",-1]), getpath(["a","x","y"])] => [3,null]
+    query!(br#"{"a": {"b": [1, 2, 3]}}"#, r#"[getpath(["a","b",-1]), getpath(["a","x","y"]), getpath(["a","b",-9])]"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[3,null,null]")
+    );
+    // jq: {"a":1} | getpath(["a","b"]) => error
+    query!(br#"{"a": 1}"#, r#"getpath(["a","b"])"#, QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), r#"Cannot index number with "b""#);
+    });
+}
+
+#[test]
+fn test_setpath_negative_and_errors() {
+    // jq: [1,2,3] | setpath([-1]; 9) => [1,2,9]
+    query!(b"[1, 2, 3]", "setpath([-1]; 9)",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,2,9]")
+    );
+    // jq: null | setpath(["a",2]; 1) => {"a":[null,null,1]}
+    query!(b"null", r#"setpath(["a", 2]; 1)"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":[null,null,1]}"#)
+    );
+    // jq: null | setpath([-1]; 1) => error
+    query!(b"null", "setpath([-1]; 1)", QueryResult::Error(e) => {
+        assert_eq!(e.to_string(), "Out of bounds negative array index");
+    });
+}
+
+#[test]
+fn test_delpaths_against_original_indices() {
+    // jq: [1,2,3] | delpaths([[1],[1]]) => [1,3]
+    query!(b"[1, 2, 3]", "delpaths([[1], [1]])",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[1,3]")
+    );
+    // jq: [1,2,3] | delpaths([[-1],[0]]) => [2]
+    query!(b"[1, 2, 3]", "delpaths([[-1], [0]])",
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), "[2]")
+    );
+    query!(br#"{"a": [1, {"b": 2}]}"#, r#"delpaths([["a", 1, "b"], ["a", 0]])"#,
+        QueryResult::Owned(v) => assert_eq!(v.to_json(), r#"{"a":[{}]}"#)
+    );
+}
+
 // =============================================================================
 // Compatibility tests - @csv quoting behavior
 // =============================================================================
@@ -1276,6 +1624,93 @@ fn test_csv_numbers_not_quoted() {
     );
 }
 
+#[test]
+fn test_csv_mixed_row() {
+    // jq: [1,"a\"b",null,true,1.5] | @csv => "1,\"a\"\"b\",,true,1.5"
+    query!(br#"[1, "a\"b", null, true, 1.5]"#, "@csv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, r#"1,"a""b",,true,1.5"#)
+    );
+}
+
+#[test]
+fn test_csv_embedded_newline_kept() {
+    // jq: ["a\nb"] | @csv => "\"a\nb\"" (the newline stays inside the quotes)
+    query!(br#"["a\nb", "c"]"#, "@csv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "\"a\nb\",\"c\"")
+    );
+}
+
+#[test]
+fn test_csv_tsv_empty_and_null_rows() {
+    for format in ["@csv", "@tsv"] {
+        query!(b"[]", format,
+            QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "")
+        );
+    }
+    query!(b"[null, null, null]", "@csv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, ",,")
+    );
+    query!(b"[null, 1, null]", "@tsv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "\t1\t")
+    );
+}
+
+#[test]
+fn test_csv_tsv_long_row() {
+    // More fields than fit in a single 64-bit word of anything
+    let fields: Vec<String> = (0..100).map(|i| format!("\"f{i}\"")).collect();
+    let json = format!("[{}]", fields.join(","));
+
+    let expected_csv = fields.join(",");
+    query!(json.as_bytes(), "@csv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, expected_csv)
+    );
+
+    let expected_tsv: Vec<String> = (0..100).map(|i| format!("f{i}")).collect();
+    query!(json.as_bytes(), "@tsv",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, expected_tsv.join("\t"))
+    );
+}
+
+#[test]
+fn test_tsv_escapes() {
+    // jq: ["a\tb","c\nd","e\\f","g\rh"] | @tsv => "a\\tb\tc\\nd\te\\\\f\tg\\rh"
+    query!(br#"["a\tb", "c\nd", "e\\f", "g\rh", true, 2]"#, "@tsv",
+        QueryResult::Owned(OwnedValue::String(s)) => {
+            assert_eq!(s, [r"a\tb", r"c\nd", r"e\\f", r"g\rh", "true", "2"].join("\t"));
+        }
+    );
+}
+
+#[test]
+fn test_csv_tsv_reject_nested_values() {
+    // jq: [[1]] | @csv => error: array ([1]) is not valid in a csv row
+    query!(b"[[1]]", "@csv",
+        QueryResult::Error(e) => assert_eq!(e.message, "array ([1]) is not valid in a csv row")
+    );
+    query!(br#"[{"a": 1}]"#, "@tsv",
+        QueryResult::Error(e) => {
+            assert_eq!(e.message, r#"object ({"a":1}) is not valid in a tsv row"#);
+        }
+    );
+    query!(br#""x""#, "@csv",
+        QueryResult::Error(e) => {
+            assert_eq!(e.message, r#"string ("x") cannot be csv-formatted, only an array can be"#);
+        }
+    );
+}
+
+#[test]
+fn test_csv_tsv_format_strings() {
+    // jq: @csv "row: \(.)" and interpolation of @tsv
+    query!(br#"["a", 1]"#, r#"@csv "row: \(.)""#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, r#"row: "a",1"#)
+    );
+    query!(br#"["a", 1]"#, r#""[\(@tsv)]""#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "[a\t1]")
+    );
+}
+
 // =============================================================================
 // Compatibility tests - @base64d edge cases
 // =============================================================================
@@ -1300,6 +1735,71 @@ fn test_base64_encode() {
     );
 }
 
+#[test]
+fn test_base64_rfc4648_vectors() {
+    // jq: "Man" | @base64 => "TWFu", and the padded shorter inputs
+    for (plain, encoded) in [
+        ("", ""),
+        ("M", "TQ=="),
+        ("Ma", "TWE="),
+        ("Man", "TWFu"),
+        ("foobar", "Zm9vYmFy"),
+    ] {
+        let json = format!("\"{plain}\"");
+        query!(json.as_bytes(), "@base64",
+            QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, encoded)
+        );
+        let json = format!("\"{encoded}\"");
+        query!(json.as_bytes(), "@base64d",
+            QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, plain)
+        );
+    }
+}
+
+#[test]
+fn test_base64d_padding_optional() {
+    // jq: "TWE" | @base64d => "Ma"
+    query!(br#""TWE""#, "@base64d",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "Ma")
+    );
+    query!(br#""TQ""#, "@base64d",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "M")
+    );
+}
+
+#[test]
+fn test_base64d_invalid() {
+    // jq: "!!" | @base64d => error
+    query!(br#""!!""#, "@base64d",
+        QueryResult::Error(e) => {
+            assert_eq!(e.message, r#"string ("!!") is not valid base64 data"#);
+        }
+    );
+    // Padding in the middle
+    query!(br#""TW=Fu""#, "@base64d",
+        QueryResult::Error(_) => {}
+    );
+}
+
+#[test]
+fn test_base64_non_string() {
+    // jq: [1,"a"] | @base64 => "WzEsImEiXQ==" (the JSON text is encoded)
+    query!(br#"[1,"a"]"#, "@base64",
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "WzEsImEiXQ==")
+    );
+}
+
+#[test]
+fn test_base64_format_string() {
+    // jq: "hi" | @base64 "v=\(.)" => "v=aGk="
+    query!(br#""hi""#, r#"@base64 "v=\(.), w=\(. + "!")""#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "v=aGk=, w=aGkh")
+    );
+    query!(br#""hi""#, r#""<\(@base64)>""#,
+        QueryResult::Owned(OwnedValue::String(s)) => assert_eq!(s, "<aGk=>")
+    );
+}
+
 // =============================================================================
 // Compatibility tests - Comparison edge cases
 // =============================================================================
@@ -0,0 +1,126 @@
+//! Integration tests for the succinctly json stats CLI command
+//!
+//! Run with: cargo test --test json_stats_cli_tests
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use tempfile::NamedTempFile;
+
+/// Resolve the path to the pre-built `succinctly` CLI binary, building it once.
+///
+/// See `json_validate_tests.rs` for why the binary is built explicitly rather
+/// than located via `CARGO_BIN_EXE_succinctly`.
+fn succinctly_bin() -> &'static Path {
+    static BIN: OnceLock<PathBuf> = OnceLock::new();
+    BIN.get_or_init(|| {
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let output = Command::new(cargo)
+            .args(["build", "--features", "cli", "--bin", "succinctly"])
+            .output()
+            .expect("failed to spawn `cargo build`");
+        assert!(
+            output.status.success(),
+            "`cargo build --features cli --bin succinctly` failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let mut path = std::env::current_exe().expect("resolve current_exe");
+        path.pop();
+        if path.file_name().and_then(|s| s.to_str()) == Some("deps") {
+            path.pop();
+        }
+        path.push(format!("succinctly{}", std::env::consts::EXE_SUFFIX));
+        assert!(
+            path.is_file(),
+            "built `succinctly` binary not found at {}",
+            path.display()
+        );
+        path
+    })
+}
+
+/// Helper to run `json stats` with input from stdin.
+fn run_stats_stdin(input: &str) -> Result<(String, String, i32)> {
+    let mut cmd = Command::new(succinctly_bin())
+        .args(["json", "stats"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = cmd.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let output = cmd.wait_with_output()?;
+    let exit_code = output.status.code().unwrap_or(-1);
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+        exit_code,
+    ))
+}
+
+#[test]
+fn test_stats_stdin() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_stats_stdin(
+        r#"{"users": [{"name": "Alice", "age": 30, "admin": true, "tag": null}]}"#,
+    )?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert_eq!(
+        stdout,
+        "objects: 2\narrays: 1\nstrings: 1\nnumbers: 1\nbooleans: 1\nnulls: 1\nmax_depth: 3\ntotal_nodes: 7\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_stats_file() -> Result<()> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(b"[1, 2, 3]")?;
+
+    let output = Command::new(succinctly_bin())
+        .args(["json", "stats"])
+        .arg(file.path())
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("arrays: 1\n"), "stdout: {stdout}");
+    assert!(stdout.contains("numbers: 3\n"), "stdout: {stdout}");
+    assert!(stdout.contains("max_depth: 1\n"), "stdout: {stdout}");
+    Ok(())
+}
+
+#[test]
+fn test_stats_memory() -> Result<()> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(br#"{"a": [1, 2, 3]}"#)?;
+
+    let output = Command::new(succinctly_bin())
+        .args(["json", "stats", "--memory"])
+        .arg(file.path())
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("objects: 1\n"), "stdout: {stdout}");
+    assert!(stdout.contains("bp_bits: 12\n"), "stdout: {stdout}");
+    assert!(stdout.contains("ib_bits: 16\n"), "stdout: {stdout}");
+    assert!(stdout.contains("\ntotal_bytes: "), "stdout: {stdout}");
+
+    let (stdout, _, _) = run_stats_stdin("[1]")?;
+    assert!(!stdout.contains("total_bytes"), "stdout: {stdout}");
+    Ok(())
+}
+
+#[test]
+fn test_stats_missing_file_fails() -> Result<()> {
+    let output = Command::new(succinctly_bin())
+        .args(["json", "stats", "/nonexistent/input.json"])
+        .output()?;
+    assert!(!output.status.success());
+    Ok(())
+}
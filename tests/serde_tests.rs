@@ -86,9 +86,7 @@ mod bitvec_serde {
     #[test]
     fn test_bitvec_with_config() {
         let words = vec![0xAAAA_AAAA_AAAA_AAAAu64; 16];
-        let config = Config {
-            select_sample_rate: 64,
-        };
+        let config = Config::builder().select_sample_rate(64).build();
         let bv = BitVec::with_config(words, 1024, config);
 
         let json = serde_json::to_string(&bv).unwrap();
@@ -258,14 +256,27 @@ mod config_serde {
 
     #[test]
     fn test_custom_config() {
-        let config = Config {
-            select_sample_rate: 64,
-        };
+        let config = Config::builder()
+            .select_sample_rate(64)
+            .bp_select_sample_rate(16)
+            .build();
 
         let json = serde_json::to_string(&config).unwrap();
         let restored: Config = serde_json::from_str(&json).unwrap();
 
         assert_eq!(restored.select_sample_rate, 64);
+        assert_eq!(restored.bp_select_sample_rate, 16);
+    }
+
+    #[test]
+    fn test_config_missing_fields_default() {
+        // Configs serialized before bp_select_sample_rate existed
+        let restored: Config = serde_json::from_str(r#"{"select_sample_rate": 64}"#).unwrap();
+        assert_eq!(restored.select_sample_rate, 64);
+        assert_eq!(
+            restored.bp_select_sample_rate,
+            Config::default().bp_select_sample_rate
+        );
     }
 }
 
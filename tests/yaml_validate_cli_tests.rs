@@ -0,0 +1,159 @@
+//! Integration tests for the succinctly yaml validate CLI command
+//!
+//! Run with: cargo test --test yaml_validate_cli_tests
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use tempfile::NamedTempFile;
+
+/// Resolve the path to the pre-built `succinctly` CLI binary, building it once.
+///
+/// See `json_validate_tests.rs` for why the binary is built explicitly rather
+/// than located via `CARGO_BIN_EXE_succinctly`.
+fn succinctly_bin() -> &'static Path {
+    static BIN: OnceLock<PathBuf> = OnceLock::new();
+    BIN.get_or_init(|| {
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let output = Command::new(cargo)
+            .args(["build", "--features", "cli", "--bin", "succinctly"])
+            .output()
+            .expect("failed to spawn `cargo build`");
+        assert!(
+            output.status.success(),
+            "`cargo build --features cli --bin succinctly` failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let mut path = std::env::current_exe().expect("resolve current_exe");
+        path.pop();
+        if path.file_name().and_then(|s| s.to_str()) == Some("deps") {
+            path.pop();
+        }
+        path.push(format!("succinctly{}", std::env::consts::EXE_SUFFIX));
+        assert!(
+            path.is_file(),
+            "built `succinctly` binary not found at {}",
+            path.display()
+        );
+        path
+    })
+}
+
+/// Helper to run `yaml validate` with input from stdin.
+fn run_validate_stdin(input: &str, args: &[&str]) -> Result<(String, String, i32)> {
+    let mut cmd = Command::new(succinctly_bin())
+        .args(["yaml", "validate"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = cmd.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let output = cmd.wait_with_output()?;
+    let exit_code = output.status.code().unwrap_or(-1);
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+        exit_code,
+    ))
+}
+
+#[test]
+fn test_validate_valid_is_silent() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_validate_stdin("a: 1\nb: [x, y]\n", &[])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert!(stdout.is_empty());
+    assert!(stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_validate_invalid_reports_location() -> Result<()> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(b"a: 1\nb: \"unclosed\n")?;
+
+    let output = Command::new(succinctly_bin())
+        .args(["yaml", "validate"])
+        .arg(file.path())
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("unclosed double quote"), "stderr: {stderr}");
+    let location = format!("{}:2:4", file.path().display());
+    assert!(stderr.contains(&location), "stderr: {stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_validate_unexpected_eof_points_at_end() -> Result<()> {
+    let (_, stderr, exit_code) = run_validate_stdin("a: [1,\n  2", &[])?;
+    assert_eq!(exit_code, 1);
+    assert_eq!(
+        stderr,
+        "error: unexpected end of input: flow sequence\n  --> <stdin>:2:4\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_validate_quiet() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_validate_stdin("a:\n\tb: 1\n", &["-q"])?;
+    assert_eq!(exit_code, 1);
+    assert!(stdout.is_empty());
+    assert!(stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_validate_missing_file_is_io_error() -> Result<()> {
+    let output = Command::new(succinctly_bin())
+        .args(["yaml", "validate", "/nonexistent/input.yaml"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_validate_strict_decodes_scalars() -> Result<()> {
+    // The index accepts an unknown escape; decoding it does not
+    let (_, _, exit_code) = run_validate_stdin("a: \"\\q\"\n", &[])?;
+    assert_eq!(exit_code, 0);
+    let (_, stderr, exit_code) = run_validate_stdin("a: \"\\q\"\n", &["--strict"])?;
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("<stdin>:1:4"), "stderr: {stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_validate_convert_json() -> Result<()> {
+    let (stdout, stderr, exit_code) = run_validate_stdin(
+        "base: &b {x: 1}\nitems:\n  - *b\n  - \"t\\u00e9\"\n  - .inf\n",
+        &["--convert-json"],
+    )?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert_eq!(
+        stdout,
+        "{\"base\":{\"x\":1},\"items\":[{\"x\":1},\"t\u{e9}\",null]}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_validate_stats() -> Result<()> {
+    let (stdout, stderr, exit_code) =
+        run_validate_stdin("a: &x [1, {b: 2}]\nc: *x\n", &["--stats"])?;
+    assert_eq!(exit_code, 0, "stderr: {stderr}");
+    assert_eq!(
+        stdout,
+        "nodes: 6\ncontainers: 3\nmax_depth: 3\nanchors: 1\naliases: 1\n"
+    );
+    Ok(())
+}